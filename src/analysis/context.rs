@@ -0,0 +1,143 @@
+//! Context-window pressure
+//!
+//! The prompt of each model call (input plus cache reads and writes) is the whole
+//! conversation sent so far, so its size against the model's context window shows
+//! how close a session ran to the limit. A prompt that shrinks sharply after running
+//! near the limit means the history was summarized or truncated. Windows are keyed
+//! by a model name fragment like prices, longest match first; `chronicle.yaml`
+//! overrides them key by key.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::TokenCounts;
+
+/// Window assumed for models no key matches
+pub const DEFAULT_WINDOW: i64 = 128_000;
+
+/// Share of the window from which a session counts as under pressure
+pub const PRESSURE_RATIO: f64 = 0.8;
+
+/// A prompt shrinking below this share of the previous one, after the previous
+/// one reached [`PRESSURE_RATIO`], is taken as a compaction
+const DROP_RATIO: f64 = 0.5;
+
+/// Built-in context windows (tokens) for common models
+pub fn default_windows() -> BTreeMap<String, i64> {
+    [
+        ("claude", 200_000),
+        ("gpt-4o", 128_000),
+        ("gpt-4.1", 1_047_576),
+        ("gpt-5", 400_000),
+        ("o3", 200_000),
+        ("gemini", 1_048_576),
+    ]
+    .into_iter()
+    .map(|(model, window)| (model.to_string(), window))
+    .collect()
+}
+
+/// Context window lookup combining the built-in table with configured overrides
+#[derive(Debug, Clone)]
+pub struct ContextWindows {
+    /// Longest key first, so the most specific fragment wins
+    windows: Vec<(String, i64)>,
+}
+
+impl ContextWindows {
+    pub fn new(overrides: &BTreeMap<String, i64>) -> Self {
+        let mut merged = default_windows();
+        merged.extend(overrides.iter().map(|(k, v)| (k.to_lowercase(), *v)));
+        let mut windows: Vec<_> = merged.into_iter().collect();
+        windows.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Self { windows }
+    }
+
+    /// Context window of a model, falling back to [`DEFAULT_WINDOW`]
+    pub fn window(&self, model: Option<&str>) -> i64 {
+        let model = model.unwrap_or_default().to_lowercase();
+        self.windows
+            .iter()
+            .find(|(key, _)| model.contains(key.as_str()))
+            .map(|(_, window)| *window)
+            .unwrap_or(DEFAULT_WINDOW)
+    }
+}
+
+/// How full a session's context got
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ContextPressure {
+    /// Largest prompt sent (tokens)
+    pub peak_tokens: i64,
+    /// Context window of the model that received the largest prompt
+    pub window: i64,
+    /// `peak_tokens / window`
+    pub peak_ratio: f64,
+    /// Model calls with token usage
+    pub requests: usize,
+    /// Sharp prompt shrinks after running near the window
+    pub drops: usize,
+}
+
+impl ContextPressure {
+    pub fn under_pressure(&self) -> bool {
+        self.peak_ratio >= PRESSURE_RATIO
+    }
+}
+
+/// Prompt size of a model call: everything the model read
+pub fn prompt_tokens(tokens: &TokenCounts) -> i64 {
+    tokens.input + tokens.cache_read + tokens.cache_creation
+}
+
+/// Pressure over a session's model calls, in order: (model, prompt tokens)
+pub fn pressure(calls: &[(Option<String>, i64)], windows: &ContextWindows) -> ContextPressure {
+    let mut result = ContextPressure::default();
+    let mut previous: Option<(i64, i64)> = None;
+    for (model, prompt) in calls.iter().filter(|(_, prompt)| *prompt > 0) {
+        let window = windows.window(model.as_deref());
+        result.requests += 1;
+        if *prompt > result.peak_tokens {
+            result.peak_tokens = *prompt;
+            result.window = window;
+        }
+        if let Some((last, last_window)) = previous {
+            if last as f64 >= last_window as f64 * PRESSURE_RATIO
+                && (*prompt as f64) < last as f64 * DROP_RATIO
+            {
+                result.drops += 1;
+            }
+        }
+        previous = Some((*prompt, window));
+    }
+    if result.window > 0 {
+        result.peak_ratio = result.peak_tokens as f64 / result.window as f64;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_detects_compaction() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("my-model".to_string(), 10_000);
+        let windows = ContextWindows::new(&overrides);
+        assert_eq!(windows.window(Some("claude-sonnet-4-5")), 200_000);
+        assert_eq!(windows.window(Some("My-Model:7b")), 10_000);
+        assert_eq!(windows.window(None), DEFAULT_WINDOW);
+
+        let model = Some("my-model".to_string());
+        let calls: Vec<_> = [2_000, 6_000, 9_000, 0, 1_500, 4_000]
+            .into_iter()
+            .map(|tokens| (model.clone(), tokens))
+            .collect();
+        let p = pressure(&calls, &windows);
+        assert_eq!(p.peak_tokens, 9_000);
+        assert_eq!(p.requests, 5);
+        assert_eq!(p.drops, 1);
+        assert!(p.under_pressure());
+    }
+}
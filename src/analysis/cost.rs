@@ -0,0 +1,155 @@
+//! Token cost estimation
+//!
+//! Prices are USD per million tokens, keyed by a model name fragment. A model
+//! is priced by the longest key it contains, so `claude-sonnet-4-5-20250929`
+//! matches `sonnet` unless a more specific key is configured. Entries from
+//! `chronicle.yaml` override the built-in table key by key.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Price of one model in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    /// Prompt cache reads; defaults to the input price when unset
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+    /// Prompt cache writes; defaults to the input price when unset
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelPrice {
+    const fn new(input: f64, output: f64, cache_read: f64, cache_write: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: Some(cache_read),
+            cache_write: Some(cache_write),
+        }
+    }
+
+    /// Estimated cost of `tokens` in USD
+    pub fn cost(&self, tokens: &TokenCounts) -> f64 {
+        let per_token = |price: f64, count: i64| price * count as f64 / 1_000_000.0;
+        per_token(self.input, tokens.input)
+            + per_token(self.output, tokens.output)
+            + per_token(self.cache_read.unwrap_or(self.input), tokens.cache_read)
+            + per_token(
+                self.cache_write.unwrap_or(self.input),
+                tokens.cache_creation,
+            )
+    }
+}
+
+/// Token totals split by billing category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TokenCounts {
+    pub input: i64,
+    pub output: i64,
+    pub cache_read: i64,
+    pub cache_creation: i64,
+}
+
+impl TokenCounts {
+    pub fn add(&mut self, other: &TokenCounts) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_read += other.cache_read;
+        self.cache_creation += other.cache_creation;
+    }
+
+    pub fn total(&self) -> i64 {
+        self.input + self.output + self.cache_read + self.cache_creation
+    }
+}
+
+/// Built-in list prices for common models
+pub fn default_prices() -> BTreeMap<String, ModelPrice> {
+    [
+        ("opus", ModelPrice::new(15.0, 75.0, 1.5, 18.75)),
+        ("claude-opus-4-5", ModelPrice::new(5.0, 25.0, 0.5, 6.25)),
+        ("sonnet", ModelPrice::new(3.0, 15.0, 0.3, 3.75)),
+        ("haiku", ModelPrice::new(1.0, 5.0, 0.1, 1.25)),
+        ("claude-3-5-haiku", ModelPrice::new(0.8, 4.0, 0.08, 1.0)),
+        ("gpt-4o", ModelPrice::new(2.5, 10.0, 1.25, 2.5)),
+        ("gpt-4o-mini", ModelPrice::new(0.15, 0.6, 0.075, 0.15)),
+        ("gpt-4.1", ModelPrice::new(2.0, 8.0, 0.5, 2.0)),
+        ("gpt-5", ModelPrice::new(1.25, 10.0, 0.125, 1.25)),
+        ("o3", ModelPrice::new(2.0, 8.0, 0.5, 2.0)),
+        ("gemini-2.5-pro", ModelPrice::new(1.25, 10.0, 0.31, 1.25)),
+        ("gemini-2.5-flash", ModelPrice::new(0.3, 2.5, 0.075, 0.3)),
+    ]
+    .into_iter()
+    .map(|(model, price)| (model.to_string(), price))
+    .collect()
+}
+
+/// Price lookup combining the built-in table with configured overrides
+#[derive(Debug, Clone)]
+pub struct Pricing {
+    /// Longest key first, so the most specific fragment wins
+    prices: Vec<(String, ModelPrice)>,
+}
+
+impl Pricing {
+    pub fn new(overrides: &BTreeMap<String, ModelPrice>) -> Self {
+        let mut merged = default_prices();
+        merged.extend(overrides.iter().map(|(k, v)| (k.to_lowercase(), *v)));
+        let mut prices: Vec<_> = merged.into_iter().collect();
+        prices.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Self { prices }
+    }
+
+    /// Price for a model name, if any key matches
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.to_lowercase();
+        self.prices
+            .iter()
+            .find(|(key, _)| model.contains(key.as_str()))
+            .map(|(_, price)| price)
+    }
+
+    /// Estimated cost in USD; `None` when the model is unknown or unpriced
+    pub fn estimate(&self, model: Option<&str>, tokens: &TokenCounts) -> Option<f64> {
+        model.and_then(|m| self.price(m)).map(|p| p.cost(tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_price_wins() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "my-local-model".to_string(),
+            ModelPrice {
+                input: 0.0,
+                output: 0.0,
+                ..Default::default()
+            },
+        );
+        let pricing = Pricing::new(&overrides);
+
+        let tokens = TokenCounts {
+            input: 1_000_000,
+            output: 1_000_000,
+            cache_read: 1_000_000,
+            cache_creation: 0,
+        };
+        let opus = pricing.estimate(Some("claude-opus-4-1-20250805"), &tokens);
+        let opus45 = pricing.estimate(Some("claude-opus-4-5-20251101"), &tokens);
+        assert_eq!(opus, Some(91.5));
+        assert_eq!(opus45, Some(30.5));
+        assert_eq!(
+            pricing.estimate(Some("My-Local-Model:7b"), &tokens),
+            Some(0.0)
+        );
+        assert_eq!(pricing.estimate(Some("mystery"), &tokens), None);
+        assert_eq!(pricing.estimate(None, &tokens), None);
+    }
+}
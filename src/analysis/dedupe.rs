@@ -0,0 +1,213 @@
+//! Cross-source duplicate session detection
+//!
+//! The same conversation can be indexed twice, e.g. when a Claude Code session
+//! is also picked up from a synced copy or an editor integration logs the same
+//! exchange. Sessions from different sources are compared on three signals:
+//!
+//! - **tool_ids**: tool-call IDs are minted by the provider, so sharing any is
+//!   strong evidence of the same conversation
+//! - **content_hash**: identical normalized user prompts
+//! - **timestamp**: message timestamps that coincide to the second
+//!
+//! The strongest signal decides the confidence and the recorded method.
+
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// Comparable summary of one indexed session
+#[derive(Debug, Clone, Default)]
+pub struct SessionSignature {
+    pub session_id: String,
+    pub probe_source_id: String,
+    /// Session start/end as unix seconds
+    pub first: Option<i64>,
+    pub last: Option<i64>,
+    /// Message timestamps as unix seconds
+    pub timestamps: HashSet<i64>,
+    pub tool_ids: HashSet<String>,
+}
+
+/// A likely duplicate pair; `session_a` sorts before `session_b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateMatch {
+    pub session_a: String,
+    pub session_b: String,
+    pub confidence: f64,
+    pub method: &'static str,
+}
+
+/// Slack allowed between session time ranges when pairing candidates (seconds)
+const RANGE_SLACK: i64 = 300;
+
+/// Shorter tool IDs (`t1`, `call_0`) are per-session counters, not provider-minted
+const MIN_TOOL_ID_LEN: usize = 12;
+
+/// Pairs of sessions from different sources worth scoring: overlapping time ranges
+/// or a shared tool-call ID. Returned as index pairs into `sessions`.
+pub fn candidate_pairs(sessions: &[SessionSignature]) -> Vec<(usize, usize)> {
+    let mut pairs = HashSet::new();
+
+    let mut by_start: Vec<usize> = (0..sessions.len())
+        .filter(|&i| sessions[i].first.is_some())
+        .collect();
+    by_start.sort_by_key(|&i| sessions[i].first);
+    for (pos, &i) in by_start.iter().enumerate() {
+        let end = sessions[i].last.or(sessions[i].first).unwrap_or_default() + RANGE_SLACK;
+        for &j in &by_start[pos + 1..] {
+            if sessions[j].first.unwrap_or_default() > end {
+                break;
+            }
+            pairs.insert((i.min(j), i.max(j)));
+        }
+    }
+
+    let mut by_tool: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, session) in sessions.iter().enumerate() {
+        for id in distinctive(&session.tool_ids) {
+            by_tool.entry(id).or_default().push(i);
+        }
+    }
+    for indices in by_tool.values() {
+        for (pos, &i) in indices.iter().enumerate() {
+            for &j in &indices[pos + 1..] {
+                pairs.insert((i.min(j), i.max(j)));
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = pairs
+        .into_iter()
+        .filter(|&(i, j)| sessions[i].probe_source_id != sessions[j].probe_source_id)
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Score a candidate pair; `content_hashes` are the sessions' prompt hashes when known
+pub fn score(
+    a: &SessionSignature,
+    b: &SessionSignature,
+    content_hashes: (Option<&str>, Option<&str>),
+) -> Option<DuplicateMatch> {
+    let mut best: Option<(f64, &'static str)> = None;
+    let mut consider = |confidence: f64, method: &'static str| {
+        if confidence > best.map_or(0.0, |(c, _)| c) {
+            best = Some((confidence, method));
+        }
+    };
+
+    if let Some(overlap) = overlap(&distinctive(&a.tool_ids), &distinctive(&b.tool_ids)) {
+        if overlap > 0.0 {
+            consider(0.7 + 0.3 * overlap, "tool_ids");
+        }
+    }
+    if let (Some(ha), Some(hb)) = content_hashes {
+        if ha == hb {
+            consider(0.95, "content_hash");
+        }
+    }
+    if a.timestamps.len() >= 2 && b.timestamps.len() >= 2 {
+        if let Some(overlap) = overlap(&a.timestamps, &b.timestamps) {
+            consider(0.9 * overlap, "timestamp");
+        }
+    }
+
+    let (confidence, method) = best?;
+    let (session_a, session_b) = if a.session_id <= b.session_id {
+        (a.session_id.clone(), b.session_id.clone())
+    } else {
+        (b.session_id.clone(), a.session_id.clone())
+    };
+    Some(DuplicateMatch {
+        session_a,
+        session_b,
+        confidence,
+        method,
+    })
+}
+
+/// Hash of a session's user prompts with case and whitespace normalized, so the same
+/// conversation stored in different formats hashes alike; `None` without any text
+pub fn prompt_hash<S: AsRef<str>>(prompts: &[S]) -> Option<String> {
+    let normalized: Vec<String> = prompts
+        .iter()
+        .map(|p| {
+            p.as_ref()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        })
+        .filter(|p| !p.is_empty())
+        .collect();
+    if normalized.is_empty() {
+        return None;
+    }
+    let digest = Sha256::digest(normalized.join("\n").as_bytes());
+    Some(hex::encode(&digest[..8]))
+}
+
+/// Tool IDs long enough to be globally unique
+fn distinctive(ids: &HashSet<String>) -> HashSet<&str> {
+    ids.iter()
+        .map(String::as_str)
+        .filter(|id| id.len() >= MIN_TOOL_ID_LEN)
+        .collect()
+}
+
+/// Shared fraction of the smaller set; `None` if either is empty
+fn overlap<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> Option<f64> {
+    let smaller = a.len().min(b.len());
+    (smaller > 0).then(|| a.intersection(b).count() as f64 / smaller as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(id: &str, source: &str, timestamps: &[i64], tools: &[&str]) -> SessionSignature {
+        SessionSignature {
+            session_id: id.to_string(),
+            probe_source_id: source.to_string(),
+            first: timestamps.iter().min().copied(),
+            last: timestamps.iter().max().copied(),
+            timestamps: timestamps.iter().copied().collect(),
+            tool_ids: tools.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_cross_source_duplicates() {
+        let sessions = vec![
+            signature("b", "claude", &[100, 160, 220], &["toolu_01abcdef", "t1"]),
+            signature("a", "mirror", &[100, 160, 220], &[]),
+            signature("c", "claude", &[130, 400], &["t1"]),
+            signature("d", "cursor", &[9_000, 9_100], &["toolu_01abcdef"]),
+        ];
+
+        // Same-source pairs (b, c) are never candidates
+        let pairs = candidate_pairs(&sessions);
+        assert_eq!(pairs, vec![(0, 1), (0, 3), (1, 2)]);
+
+        let same = score(&sessions[0], &sessions[1], (None, None)).unwrap();
+        assert_eq!(
+            (same.session_a.as_str(), same.session_b.as_str()),
+            ("a", "b")
+        );
+        assert_eq!(same.method, "timestamp");
+        assert!((same.confidence - 0.9).abs() < 1e-9);
+
+        let tools = score(&sessions[0], &sessions[3], (None, None)).unwrap();
+        assert_eq!((tools.method, tools.confidence), ("tool_ids", 1.0));
+
+        assert_eq!(
+            prompt_hash(&["Fix  the\nbug", ""]),
+            prompt_hash(&["fix the bug"])
+        );
+        assert_eq!(prompt_hash(&["  "]), None);
+
+        let hashed = score(&sessions[1], &sessions[2], (Some("x"), Some("x"))).unwrap();
+        assert_eq!(hashed.method, "content_hash");
+        assert!(score(&sessions[1], &sessions[2], (Some("x"), Some("y"))).is_none());
+    }
+}
@@ -0,0 +1,121 @@
+//! Prompts and output parsing for LLM-generated session metadata
+//!
+//! Enrichment jobs are queued per session and kind, then worked by `chronicle
+//! enrich` through a user-configured command. This module only builds the
+//! prompt from a transcript and normalizes the model's answer.
+
+use anyhow::{bail, Result};
+
+/// Kinds of metadata an enrichment job can generate
+pub const KINDS: &[&str] = &["summary", "topics"];
+
+/// Output tokens assumed per job when estimating cost up front
+pub const OUTPUT_ALLOWANCE: i64 = 300;
+
+/// Validate a kind name
+pub fn kind(name: &str) -> Result<&'static str> {
+    match KINDS.iter().find(|k| **k == name) {
+        Some(kind) => Ok(kind),
+        None => bail!(
+            "Unknown enrichment kind: {} (expected {})",
+            name,
+            KINDS.join(" or ")
+        ),
+    }
+}
+
+/// Prompt asking for `kind` over a transcript of (role, text) turns. Long transcripts
+/// keep their beginning and end, which carry the task and its outcome.
+pub fn prompt(kind: &str, turns: &[(String, String)], max_chars: usize) -> String {
+    let instruction = match kind {
+        "topics" => {
+            "List 3 to 6 short topics (a few words each) covered by this conversation \
+             between a developer and an AI assistant. Reply with one topic per line and \
+             nothing else."
+        }
+        _ => {
+            "Summarize this conversation between a developer and an AI assistant in 2 to 3 \
+             sentences: what was asked, what was done, and how it ended. Reply with the \
+             summary only."
+        }
+    };
+
+    let mut transcript = String::new();
+    for (role, text) in turns {
+        let text = text.trim();
+        if !text.is_empty() {
+            transcript.push_str(&format!("{}: {}\n\n", role.to_uppercase(), text));
+        }
+    }
+    let chars = transcript.chars().count();
+    if chars > max_chars {
+        let half = max_chars / 2;
+        let head: String = transcript.chars().take(half).collect();
+        let tail: String = transcript.chars().skip(chars - half).collect();
+        transcript = format!(
+            "{}\n[... {} characters omitted ...]\n\n{}",
+            head,
+            chars - max_chars,
+            tail
+        );
+    }
+    format!(
+        "{}\n\n<conversation>\n{}</conversation>\n",
+        instruction, transcript
+    )
+}
+
+/// Normalize the model's answer: a trimmed summary, or comma-separated topics
+pub fn parse_output(kind: &str, output: &str) -> Option<String> {
+    let result = match kind {
+        "topics" => output
+            .lines()
+            .map(|line| {
+                line.trim()
+                    .trim_start_matches(|c: char| {
+                        c == '-' || c == '*' || c == '•' || c.is_ascii_digit() || c == '.'
+                    })
+                    .trim()
+                    .trim_end_matches('.')
+            })
+            .filter(|topic| !topic.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => output.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+    (!result.is_empty()).then_some(result)
+}
+
+/// Rough token count of prompt text (about four characters per token)
+pub fn estimate_tokens(text: &str) -> i64 {
+    (text.chars().count() as i64 + 3) / 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_truncation_and_parsing() {
+        let turns = vec![
+            ("user".to_string(), "a".repeat(300)),
+            ("assistant".to_string(), "b".repeat(300)),
+        ];
+        let prompt = prompt("summary", &turns, 200);
+        assert!(prompt.starts_with("Summarize"));
+        assert!(prompt.contains("USER: aaa"));
+        assert!(prompt.contains("characters omitted"));
+        assert!(prompt.contains("bbb\n\n</conversation>"));
+
+        assert_eq!(
+            parse_output("topics", "1. Rust lifetimes\n- SQLite migrations.\n\n"),
+            Some("Rust lifetimes, SQLite migrations".to_string())
+        );
+        assert_eq!(
+            parse_output("summary", "  Fixed the\nbuild. "),
+            Some("Fixed the build.".to_string())
+        );
+        assert_eq!(parse_output("summary", " \n"), None);
+        assert!(kind("sentiment").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Files touched by tool calls
+//!
+//! Tool inputs name files under a handful of keys, mostly as absolute paths. For
+//! per-project reports each path is made relative to the session's project root (or
+//! its working directory), so the same file edited from different sessions and
+//! checkouts counts once; paths outside every root (temp files, other repositories)
+//! are left out.
+
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Input keys holding the file a tool reads or edits, in order of preference
+const PATH_KEYS: &[&str] = &[
+    "file_path",
+    "filePath",
+    "notebook_path",
+    "target_file",
+    "absolute_path",
+    "path",
+    "filename",
+];
+
+/// Words in the names of tools that change files (`Edit`, `write_file`, `apply_patch`)
+const EDIT_WORDS: &[&str] = &[
+    "edit", "write", "replace", "patch", "create", "apply", "insert",
+];
+
+/// File named in a tool input, if any
+pub fn touched_file(input: &Value) -> Option<String> {
+    let object = match input {
+        Value::Object(object) => object.clone(),
+        // Some sources keep the arguments as a JSON string
+        Value::String(text) => serde_json::from_str(text).ok()?,
+        _ => return None,
+    };
+    PATH_KEYS
+        .iter()
+        .find_map(|key| object.get(*key)?.as_str())
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+}
+
+/// Whether a tool changes the files it names
+pub fn is_edit_tool(tool_name: &str) -> bool {
+    let name = tool_name.to_lowercase();
+    EDIT_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// `path` relative to the nearest of the project `roots`, or else the session's
+/// working directory `cwd` (relative paths are taken as relative to `cwd`); None when
+/// it lies outside all of them
+pub fn workspace_path(path: &str, cwd: Option<&str>, roots: &[String]) -> Option<String> {
+    let absolute = match (Path::new(path).is_absolute(), cwd) {
+        (true, _) => clean(Path::new(path)),
+        (false, Some(cwd)) => clean(&Path::new(cwd).join(path)),
+        (false, None) => {
+            let relative = clean(Path::new(path));
+            return (!relative.starts_with("..")).then(|| display(&relative));
+        }
+    };
+    let mut candidates: Vec<PathBuf> = roots.iter().map(|r| clean(Path::new(r))).collect();
+    // Deepest project root first; the working directory is the last resort
+    candidates.sort_by_key(|r| std::cmp::Reverse(r.components().count()));
+    candidates.extend(cwd.map(|c| clean(Path::new(c))));
+    candidates.iter().find_map(|root| {
+        let relative = absolute.strip_prefix(root).ok()?;
+        (!relative.as_os_str().is_empty()).then(|| display(relative))
+    })
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn clean(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_workspace_relative_paths() {
+        assert_eq!(
+            touched_file(&json!({"file_path": "/w/app/src/main.rs", "old_string": "a"})),
+            Some("/w/app/src/main.rs".to_string())
+        );
+        assert_eq!(
+            touched_file(&json!("{\"path\": \"README.md\"}")),
+            Some("README.md".to_string())
+        );
+        assert_eq!(touched_file(&json!({"command": "ls"})), None);
+        assert!(is_edit_tool("MultiEdit") && is_edit_tool("write_file"));
+        assert!(!is_edit_tool("Read"));
+
+        let roots = vec!["/w/app/".to_string(), "/w/app/packages/ui".to_string()];
+        let cwd = Some("/w/app/packages/ui");
+        assert_eq!(
+            workspace_path("/w/app/src/./main.rs", cwd, &roots[..1]),
+            Some("src/main.rs".to_string())
+        );
+        // Relative to the working directory, then to the deepest project root
+        assert_eq!(
+            workspace_path("src/button.tsx", cwd, &roots),
+            Some("src/button.tsx".to_string())
+        );
+        assert_eq!(
+            workspace_path("../../src/main.rs", cwd, &roots[..1]),
+            Some("src/main.rs".to_string())
+        );
+        assert_eq!(workspace_path("/tmp/scratch.py", cwd, &roots), None);
+        assert_eq!(workspace_path("/w/app", None, &roots), None);
+    }
+}
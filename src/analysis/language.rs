@@ -0,0 +1,208 @@
+//! Natural language detection for sessions
+//!
+//! Probes feed the text of user messages into a [`LanguageSample`]; the session's
+//! language is then guessed from the writing system, and for Latin-script text
+//! from common function words. Codes are ISO 639-1 (`en`, `de`, `zh`, ...).
+//! Short or code-only samples yield no language rather than a guess.
+
+/// Characters of user text kept per session
+const SAMPLE_CHARS: usize = 4000;
+
+/// Share of letters a non-Latin script needs to decide the language
+const SCRIPT_SHARE: f64 = 0.2;
+
+/// Function-word hits needed before a Latin-script language is reported
+const MIN_WORD_HITS: usize = 3;
+
+/// Common function words of Latin-script languages; words shared between
+/// languages count for each of them
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "to", "of", "in", "that", "this", "it", "with", "for",
+            "you", "can", "what", "how", "not", "why", "should", "would", "please",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "mit", "auf", "wie",
+            "warum", "bitte", "kannst", "noch", "auch", "wird", "sind",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "une", "des", "pas", "que", "pour", "dans", "avec",
+            "je", "vous", "comment", "pourquoi", "peux", "ce", "qui",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "y", "es", "una", "que", "por", "para", "con", "no", "cómo",
+            "como", "qué", "puedes", "está", "del", "pero",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "e", "é", "uma", "que", "não", "para", "com", "do", "da", "em",
+            "como", "você", "pode", "está", "mas",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "gli", "e", "è", "una", "che", "non", "per", "con", "del", "della", "come",
+            "perché", "puoi", "sono", "questo",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "niet", "ik", "van", "met", "op", "dat", "voor", "hoe",
+            "waarom", "kun", "zijn", "ook",
+        ],
+    ),
+];
+
+/// Bounded sample of a session's user text
+#[derive(Debug, Default)]
+pub struct LanguageSample {
+    text: String,
+    chars: usize,
+}
+
+impl LanguageSample {
+    /// Add user text until the sample is full
+    pub fn add(&mut self, text: &str) {
+        if self.chars >= SAMPLE_CHARS {
+            return;
+        }
+        for c in text.chars().take(SAMPLE_CHARS - self.chars) {
+            self.text.push(c);
+            self.chars += 1;
+        }
+        self.text.push('\n');
+    }
+
+    /// Most likely language of the sample, if it can be told
+    pub fn detect(&self) -> Option<&'static str> {
+        detect(&self.text)
+    }
+}
+
+/// Most likely language of `text`, if it can be told
+pub fn detect(text: &str) -> Option<&'static str> {
+    let mut scripts: Vec<(&'static str, usize)> = vec![];
+    let mut letters = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        if let Some(script) = script(c) {
+            match scripts.iter_mut().find(|(s, _)| *s == script) {
+                Some((_, count)) => *count += 1,
+                None => scripts.push((script, 1)),
+            }
+        }
+    }
+    if letters == 0 {
+        return None;
+    }
+
+    // Kana marks Japanese even alongside kanji; otherwise the largest script wins
+    let share = |script: &str| {
+        scripts
+            .iter()
+            .find(|(s, _)| *s == script)
+            .map_or(0.0, |(_, n)| *n as f64 / letters as f64)
+    };
+    if share("ja") >= SCRIPT_SHARE / 4.0 {
+        return Some("ja");
+    }
+    if let Some((script, _)) = scripts
+        .iter()
+        .filter(|(s, _)| share(s) >= SCRIPT_SHARE)
+        .max_by_key(|(_, n)| *n)
+    {
+        if *script == "ru" && text.chars().any(|c| matches!(c, 'і' | 'ї' | 'є' | 'ґ')) {
+            return Some("uk");
+        }
+        return Some(script);
+    }
+
+    latin_language(text)
+}
+
+/// Whether text in this language is mostly double-width in a terminal
+pub fn is_wide(language: &str) -> bool {
+    matches!(language, "zh" | "ja" | "ko")
+}
+
+/// Language implied by a letter's writing system (None for Latin and others)
+fn script(c: char) -> Option<&'static str> {
+    Some(match c as u32 {
+        0x3040..=0x30FF => "ja",
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF => "zh",
+        0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+        0x0400..=0x04FF => "ru",
+        0x0370..=0x03FF => "el",
+        0x0590..=0x05FF => "he",
+        0x0600..=0x06FF => "ar",
+        0x0900..=0x097F => "hi",
+        0x0E00..=0x0E7F => "th",
+        _ => return None,
+    })
+}
+
+/// Latin-script language with the most function-word hits
+fn latin_language(text: &str) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut best: Option<(&'static str, usize)> = None;
+    for (language, stopwords) in STOPWORDS {
+        let hits = words.iter().filter(|w| stopwords.contains(w)).count();
+        if hits >= MIN_WORD_HITS && best.is_none_or(|(_, most)| hits > most) {
+            best = Some((language, hits));
+        }
+    }
+    best.map(|(language, _)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_common_languages() {
+        assert_eq!(
+            detect("Can you explain why the build is failing with this error?"),
+            Some("en")
+        );
+        assert_eq!(
+            detect(
+                "Kannst du bitte erklären, warum der Test nicht mehr läuft und wie ich das ändere?"
+            ),
+            Some("de")
+        );
+        assert_eq!(
+            detect("请帮我修复这个 `parse_config` 函数中的错误"),
+            Some("zh")
+        );
+        assert_eq!(
+            detect("このエラーを直してください: cargo build"),
+            Some("ja")
+        );
+        assert_eq!(detect("fn main() { let x = 1; }"), None);
+
+        let mut sample = LanguageSample::default();
+        sample.add("Pourquoi est-ce que le serveur ne démarre pas avec cette configuration ?");
+        sample.add("Je vois une erreur dans les logs.");
+        assert_eq!(sample.detect(), Some("fr"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Runaway tool loop detection
+//!
+//! Agentic tools occasionally get stuck re-running the same tool with the
+//! same (or trivially different) input. Each tool call's input is reduced to
+//! a fingerprint that ignores whitespace, case and digits, and consecutive
+//! calls sharing a tool name and fingerprint are counted as one run.
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::probe::MessageMetadata;
+
+/// A run of near-identical consecutive tool calls
+#[derive(Debug, Clone)]
+pub struct ToolLoop {
+    pub tool_name: String,
+    pub repeats: usize,
+    /// Index of the message containing the first call of the run
+    pub first_message: usize,
+}
+
+/// Fingerprint a tool input so near-identical inputs collide
+pub fn fingerprint(input: &Value) -> String {
+    let raw = match input {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let mut normalized = String::with_capacity(raw.len());
+    let mut last_space = false;
+    for c in raw.chars() {
+        if c.is_whitespace() {
+            if !last_space {
+                normalized.push(' ');
+            }
+            last_space = true;
+            continue;
+        }
+        last_space = false;
+        normalized.push(if c.is_ascii_digit() {
+            '0'
+        } else {
+            c.to_ascii_lowercase()
+        });
+    }
+
+    let digest = Sha256::digest(normalized.trim().as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// Find runs of at least `threshold` consecutive identical tool calls
+pub fn detect_tool_loops(messages: &[MessageMetadata], threshold: usize) -> Vec<ToolLoop> {
+    let mut loops = vec![];
+    let mut current: Option<(String, Option<String>, usize, usize)> = None;
+
+    let calls = messages.iter().enumerate().flat_map(|(idx, msg)| {
+        msg.tool_uses
+            .iter()
+            .map(move |tool| (idx, &tool.tool_name, &tool.input_hash))
+    });
+
+    for (idx, name, hash) in calls {
+        match current {
+            // Calls without an input fingerprint never extend a run
+            Some((ref cur_name, ref cur_hash, ref mut count, _))
+                if cur_name == name && hash.is_some() && cur_hash == hash =>
+            {
+                *count += 1;
+            }
+            _ => {
+                if let Some((name, _, count, first)) = current.take() {
+                    if count >= threshold {
+                        loops.push(ToolLoop {
+                            tool_name: name,
+                            repeats: count,
+                            first_message: first,
+                        });
+                    }
+                }
+                current = Some((name.clone(), hash.clone(), 1, idx));
+            }
+        }
+    }
+
+    if let Some((name, _, count, first)) = current {
+        if count >= threshold {
+            loops.push(ToolLoop {
+                tool_name: name,
+                repeats: count,
+                first_message: first,
+            });
+        }
+    }
+
+    loops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{ContentRef, ToolUseMetadata};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// One message per call: (tool name, input)
+    fn messages(calls: &[(&str, &str)]) -> Vec<MessageMetadata> {
+        calls
+            .iter()
+            .enumerate()
+            .map(|(i, (name, input))| MessageMetadata {
+                uuid: None,
+                role: "assistant".to_string(),
+                provider_id: None,
+                model: None,
+                timestamp: None,
+                content_ref: ContentRef::jsonl(PathBuf::from("/tmp/s.jsonl"), 0, i as u32),
+                has_tool_use: true,
+                has_thinking: false,
+                tool_uses: vec![ToolUseMetadata {
+                    tool_id: None,
+                    tool_name: name.to_string(),
+                    has_result: true,
+                    is_error: false,
+                    permission: None,
+                    input_hash: Some(fingerprint(&json!({ "command": input }))),
+                    file_path: None,
+                }],
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_loops_at_threshold() {
+        let run = |n| vec![("Bash", "cargo test 1"); n];
+
+        // Exactly the threshold counts; one short doesn't
+        let loops = detect_tool_loops(&messages(&run(3)), 3);
+        assert_eq!(loops.len(), 1);
+        assert_eq!((loops[0].repeats, loops[0].first_message), (3, 0));
+        assert!(detect_tool_loops(&messages(&run(2)), 3).is_empty());
+
+        // A different input or tool breaks the run; trivial differences don't
+        let calls = [
+            ("Read", "a.rs"),
+            ("Bash", "cargo test 1"),
+            ("Bash", "cargo  test 2"),
+            ("Bash", "cargo build"),
+            ("Bash", "cargo build"),
+            ("Bash", "cargo build"),
+            ("Grep", "cargo build"),
+        ];
+        let loops = detect_tool_loops(&messages(&calls), 3);
+        assert_eq!(loops.len(), 1);
+        assert_eq!((loops[0].repeats, loops[0].first_message), (3, 3));
+        assert_eq!(detect_tool_loops(&messages(&calls), 2).len(), 2);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_trivial_differences() {
+        let a = fingerprint(&json!({"command": "cargo test  --lib 1"}));
+        let b = fingerprint(&json!({"command": "Cargo test --lib 2"}));
+        let c = fingerprint(&json!({"command": "cargo build"}));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,27 @@
+//! Derived-data analysis over extracted sessions
+//!
+//! Detectors here are pure functions over text and metadata; probes and the
+//! extract pipeline call them, and the store persists their results.
+
+pub mod context;
+pub mod cost;
+pub mod dedupe;
+pub mod enrich;
+pub mod files;
+pub mod language;
+pub mod loops;
+pub mod permissions;
+pub mod references;
+pub mod snapshot;
+pub mod switches;
+pub mod textdiff;
+pub mod tools;
+pub mod usage;
+
+pub use context::{ContextPressure, ContextWindows};
+pub use cost::{ModelPrice, Pricing, TokenCounts};
+pub use loops::ToolLoop;
+pub use references::{IssueReference, ReferenceKind};
+pub use snapshot::{ProjectSnapshot, SnapshotDiff};
+pub use switches::{ModelSwitch, SwitchKind};
+pub use usage::{BudgetStatus, DailyUsage, UsageSpike};
@@ -0,0 +1,141 @@
+//! Tool permission decisions
+//!
+//! Claude Code transcripts record a denied tool call as a rejection result, but not
+//! the approval prompt itself. Whether a call that ran needed approval is inferred
+//! from the session's permission mode, the tools that never prompt, and the
+//! project's allow rules (`Bash(npm test:*)`, `WebFetch`, `mcp__github`, ...) as
+//! they are configured now.
+
+use serde_json::Value;
+
+/// How a tool call got permission to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Ran without asking: read-only tool, allow rule or permissive mode
+    Auto,
+    /// Needed the user's approval and got it
+    Approved,
+    /// The user rejected the call
+    Denied,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Auto => "auto",
+            Permission::Approved => "approved",
+            Permission::Denied => "denied",
+        }
+    }
+}
+
+/// Claude Code tools that never ask for permission
+const READ_ONLY_TOOLS: &[&str] = &[
+    "Read",
+    "Glob",
+    "Grep",
+    "LS",
+    "NotebookRead",
+    "TodoRead",
+    "TodoWrite",
+    "Task",
+    "Agent",
+    "ExitPlanMode",
+];
+
+/// File edit tools, allowed without asking in `acceptEdits` mode
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Opening of the tool result Claude Code sends back for a rejected call
+const REJECTION: &str = "The user doesn't want to proceed with this tool use";
+
+/// Whether a tool result's text reports that the user rejected the call
+pub fn is_rejection(result_text: &str) -> bool {
+    result_text.trim_start().starts_with(REJECTION)
+}
+
+/// Permission of a call that was not rejected, under `mode` and the `allow` rules
+pub fn granted(tool_name: &str, input: Option<&Value>, mode: &str, allow: &[String]) -> Permission {
+    let auto = mode == "bypassPermissions"
+        || READ_ONLY_TOOLS.contains(&tool_name)
+        || (mode == "acceptEdits" && EDIT_TOOLS.contains(&tool_name))
+        || allow
+            .iter()
+            .any(|rule| rule_matches(rule, tool_name, input));
+    match auto {
+        true => Permission::Auto,
+        false => Permission::Approved,
+    }
+}
+
+/// Whether an allow rule covers a call. Bash rules match the command exactly or, with
+/// a trailing `:*`, by prefix; other rule specifiers are taken to cover the whole tool.
+fn rule_matches(rule: &str, tool_name: &str, input: Option<&Value>) -> bool {
+    let (tool, spec) = match rule.split_once('(') {
+        Some((tool, spec)) => (tool, spec.strip_suffix(')')),
+        None => (rule, None),
+    };
+    // `mcp__server` allows every tool of the server
+    if tool.starts_with("mcp__") && tool.matches("__").count() == 1 {
+        return tool_name
+            .strip_prefix(tool)
+            .is_some_and(|rest| rest.starts_with("__"));
+    }
+    if tool != tool_name {
+        return false;
+    }
+    match (tool, spec) {
+        ("Bash", Some(spec)) => {
+            let command = input
+                .and_then(|i| i.get("command"))
+                .and_then(|c| c.as_str())
+                .unwrap_or_default();
+            match spec.strip_suffix(":*") {
+                Some(prefix) => command.starts_with(prefix),
+                None => command == spec,
+            }
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_permission_inference() {
+        let allow = vec![
+            "Bash(npm test:*)".to_string(),
+            "mcp__github".to_string(),
+            "WebFetch(domain:docs.rs)".to_string(),
+        ];
+        let bash = |command: &str| json!({ "command": command });
+        let granted_in =
+            |tool: &str, input: Option<&Value>, mode: &str| granted(tool, input, mode, &allow);
+
+        assert_eq!(granted_in("Read", None, "default"), Permission::Auto);
+        assert_eq!(
+            granted_in("Bash", Some(&bash("npm test -- x")), "default"),
+            Permission::Auto
+        );
+        assert_eq!(
+            granted_in("Bash", Some(&bash("rm -rf target")), "default"),
+            Permission::Approved
+        );
+        assert_eq!(
+            granted_in("mcp__github__create_issue", None, "default"),
+            Permission::Auto
+        );
+        assert_eq!(
+            granted_in("mcp__linear__list", None, "default"),
+            Permission::Approved
+        );
+        assert_eq!(granted_in("Edit", None, "acceptEdits"), Permission::Auto);
+        assert_eq!(granted_in("Edit", None, "default"), Permission::Approved);
+        assert!(is_rejection(
+            "The user doesn't want to proceed with this tool use. The tool use was rejected"
+        ));
+    }
+}
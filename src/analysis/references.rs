@@ -0,0 +1,238 @@
+//! Issue tracker reference detection
+//!
+//! Recognizes references that link a conversation to tickets:
+//! - GitHub URLs: `https://github.com/owner/repo/issues/12` and `/pull/34`
+//! - Qualified shorthand: `owner/repo#12`
+//! - Bare shorthand: `#12`
+//! - JIRA-style keys: `PROJ-42`
+
+use std::collections::BTreeSet;
+
+/// Kind of issue reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReferenceKind {
+    /// GitHub issue URL
+    GithubIssue,
+    /// GitHub pull request URL
+    GithubPull,
+    /// `owner/repo#N` or bare `#N`
+    Issue,
+    /// JIRA-style `KEY-N`
+    Jira,
+}
+
+impl ReferenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceKind::GithubIssue => "github_issue",
+            ReferenceKind::GithubPull => "github_pr",
+            ReferenceKind::Issue => "issue",
+            ReferenceKind::Jira => "jira",
+        }
+    }
+}
+
+/// A detected issue/PR reference
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IssueReference {
+    pub kind: ReferenceKind,
+    /// Normalized key: `owner/repo#12`, `#12` or `PROJ-42`
+    pub key: String,
+    pub url: Option<String>,
+}
+
+/// Uppercase prefixes that look like JIRA keys but are standards or model names
+const JIRA_DENYLIST: &[&str] = &[
+    "AES", "ARM", "CP", "ECMA", "ES", "GPT", "HTTP", "ISO", "MD", "PEP", "RFC", "RSA", "SHA",
+    "TLS", "UTF", "WCAG", "X",
+];
+
+/// Detect all references in a block of text, deduplicated and sorted
+pub fn detect(text: &str) -> Vec<IssueReference> {
+    let mut found = BTreeSet::new();
+    detect_github_urls(text, &mut found);
+    detect_hash_refs(text, &mut found);
+    detect_jira_keys(text, &mut found);
+    found.into_iter().collect()
+}
+
+/// Normalize a user-supplied lookup key (URL, `#12`, `12`, `proj-42`) to a stored key
+pub fn normalize_key(query: &str) -> String {
+    let query = query.trim();
+    if let Some(reference) = detect(query).into_iter().next() {
+        return reference.key;
+    }
+    if !query.is_empty() && query.chars().all(|c| c.is_ascii_digit()) {
+        return format!("#{}", query);
+    }
+    query.to_uppercase()
+}
+
+fn detect_github_urls(text: &str, found: &mut BTreeSet<IssueReference>) {
+    const HOST: &str = "github.com/";
+    let mut search_from = 0;
+
+    while let Some(pos) = text[search_from..].find(HOST) {
+        let start = search_from + pos + HOST.len();
+        search_from = start;
+
+        let tail: String = text[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'))
+            .collect();
+        let parts: Vec<&str> = tail.split('/').collect();
+        if parts.len() < 4 {
+            continue;
+        }
+
+        let kind = match parts[2] {
+            "issues" => ReferenceKind::GithubIssue,
+            "pull" => ReferenceKind::GithubPull,
+            _ => continue,
+        };
+        let number = parts[3];
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        found.insert(IssueReference {
+            kind,
+            key: format!("{}/{}#{}", parts[0], parts[1], number),
+            url: Some(format!(
+                "https://github.com/{}/{}/{}/{}",
+                parts[0], parts[1], parts[2], number
+            )),
+        });
+    }
+}
+
+fn detect_hash_refs(text: &str, found: &mut BTreeSet<IssueReference>) {
+    let bytes = text.as_bytes();
+
+    for (idx, _) in text.match_indices('#') {
+        let digits: String = text[idx + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if digits.is_empty() || digits.len() > 6 {
+            continue;
+        }
+        let after = idx + 1 + digits.len();
+        if bytes
+            .get(after)
+            .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        {
+            continue;
+        }
+
+        // Walk back over an optional `owner/repo` slug
+        let slug_start = text[..idx]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'))
+            .last()
+            .map(|(i, _)| i)
+            .unwrap_or(idx);
+        let slug = &text[slug_start..idx];
+
+        // Skip HTML entities (`&#123;`) and things like `C#1`
+        if slug.is_empty() {
+            if idx > 0 && matches!(bytes[idx - 1], b'&') {
+                continue;
+            }
+            found.insert(IssueReference {
+                kind: ReferenceKind::Issue,
+                key: format!("#{}", digits),
+                url: None,
+            });
+        } else if slug.matches('/').count() == 1
+            && !slug.starts_with('/')
+            && !slug.ends_with('/')
+            && !slug.contains("github.com")
+        {
+            found.insert(IssueReference {
+                kind: ReferenceKind::Issue,
+                key: format!("{}#{}", slug, digits),
+                url: Some(format!("https://github.com/{}/issues/{}", slug, digits)),
+            });
+        }
+    }
+}
+
+fn detect_jira_keys(text: &str, found: &mut BTreeSet<IssueReference>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let boundary = i == 0 || !(chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+        if !boundary || !chars[i].is_ascii_uppercase() {
+            i += 1;
+            continue;
+        }
+
+        let prefix_end = (i..chars.len())
+            .find(|&j| !(chars[j].is_ascii_uppercase() || chars[j].is_ascii_digit()))
+            .unwrap_or(chars.len());
+        let prefix_len = prefix_end - i;
+
+        if (2..=10).contains(&prefix_len) && chars.get(prefix_end) == Some(&'-') {
+            let num_end = (prefix_end + 1..chars.len())
+                .find(|&j| !chars[j].is_ascii_digit())
+                .unwrap_or(chars.len());
+            let num_len = num_end - prefix_end - 1;
+            let trailing_ok = chars
+                .get(num_end)
+                .is_none_or(|c| !(c.is_alphanumeric() || *c == '_' || *c == '-'));
+
+            if (1..=7).contains(&num_len) && chars[prefix_end + 1] != '0' && trailing_ok {
+                let prefix: String = chars[i..prefix_end].iter().collect();
+                if !JIRA_DENYLIST.contains(&prefix.as_str()) {
+                    let number: String = chars[prefix_end + 1..num_end].iter().collect();
+                    found.insert(IssueReference {
+                        kind: ReferenceKind::Jira,
+                        key: format!("{}-{}", prefix, number),
+                        url: None,
+                    });
+                }
+            }
+            i = num_end;
+            continue;
+        }
+
+        i = prefix_end.max(i + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<String> {
+        detect(text).into_iter().map(|r| r.key).collect()
+    }
+
+    #[test]
+    fn test_detects_each_reference_style() {
+        let text = "Fix PROJ-42 per https://github.com/acme/app/pull/7 and acme/lib#12, see #3.";
+        assert_eq!(
+            keys(text),
+            vec!["acme/app#7", "#3", "acme/lib#12", "PROJ-42"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_lookalikes() {
+        let text = "UTF-8 text, GPT-4 output, &#123; entity, heading ## 1, color #fff, ABC-0";
+        assert!(keys(text).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(normalize_key("proj-42"), "PROJ-42");
+        assert_eq!(normalize_key("12"), "#12");
+        assert_eq!(
+            normalize_key("https://github.com/acme/app/issues/9"),
+            "acme/app#9"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Point-in-time project snapshots and their differences
+//!
+//! A snapshot freezes a project's aggregates (sessions, messages, tokens, sources
+//! and models in use, referenced issues) together with the titles and open
+//! questions of its most recent sessions, so two snapshots can be compared in a
+//! retrospective.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Sessions whose titles and questions are captured
+pub const RECENT_SESSIONS: usize = 10;
+
+/// Questions kept per snapshot
+pub const MAX_QUESTIONS: usize = 10;
+
+/// Aggregates of one project at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub session_count: i64,
+    pub message_count: i64,
+    pub total_tokens: i64,
+    /// Sessions per source
+    pub sources: BTreeMap<String, i64>,
+    /// Sessions per primary model
+    pub models: BTreeMap<String, i64>,
+    /// Issue/PR references mentioned in the project's sessions
+    pub references: BTreeSet<String>,
+    /// `short_hash: title` of the most recent sessions, newest first
+    pub recent_sessions: Vec<String>,
+    /// Questions the user asked last in recent sessions, newest first
+    pub open_questions: Vec<String>,
+}
+
+/// Change of a counted value between two snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountChange {
+    pub name: String,
+    pub before: i64,
+    pub after: i64,
+}
+
+/// What changed from one snapshot to a later one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    /// Sessions, messages and tokens
+    pub totals: Vec<CountChange>,
+    pub sources: Vec<CountChange>,
+    pub models: Vec<CountChange>,
+    pub new_references: Vec<String>,
+    pub new_sessions: Vec<String>,
+    pub new_questions: Vec<String>,
+    /// Open before, no longer among the open questions after
+    pub settled_questions: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn between(before: &ProjectSnapshot, after: &ProjectSnapshot) -> Self {
+        let totals = [
+            ("Sessions", before.session_count, after.session_count),
+            ("Messages", before.message_count, after.message_count),
+            ("Tokens", before.total_tokens, after.total_tokens),
+        ]
+        .into_iter()
+        .map(|(name, before, after)| CountChange {
+            name: name.to_string(),
+            before,
+            after,
+        })
+        .collect();
+
+        let only_in = |a: &[String], b: &[String]| -> Vec<String> {
+            a.iter().filter(|x| !b.contains(x)).cloned().collect()
+        };
+        Self {
+            totals,
+            sources: count_changes(&before.sources, &after.sources),
+            models: count_changes(&before.models, &after.models),
+            new_references: after
+                .references
+                .difference(&before.references)
+                .cloned()
+                .collect(),
+            new_sessions: only_in(&after.recent_sessions, &before.recent_sessions),
+            new_questions: only_in(&after.open_questions, &before.open_questions),
+            settled_questions: only_in(&before.open_questions, &after.open_questions),
+        }
+    }
+}
+
+/// A prompt that reads as a question: its last non-empty line ends with `?`
+pub fn is_question(prompt: &str) -> bool {
+    prompt
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.ends_with('?'))
+}
+
+/// Changed entries of two counters, keyed by name
+fn count_changes(
+    before: &BTreeMap<String, i64>,
+    after: &BTreeMap<String, i64>,
+) -> Vec<CountChange> {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .map(|name| CountChange {
+            name: name.clone(),
+            before: before.get(name).copied().unwrap_or(0),
+            after: after.get(name).copied().unwrap_or(0),
+        })
+        .filter(|change| change.before != change.after)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_between_snapshots() {
+        let before = ProjectSnapshot {
+            session_count: 2,
+            sources: BTreeMap::from([("ClaudeCode".to_string(), 2)]),
+            references: BTreeSet::from(["#1".to_string()]),
+            recent_sessions: vec!["aaaa: first".to_string()],
+            open_questions: vec!["Why is CI red?".to_string()],
+            ..Default::default()
+        };
+        let after = ProjectSnapshot {
+            session_count: 4,
+            sources: BTreeMap::from([("ClaudeCode".to_string(), 2), ("Cursor".to_string(), 2)]),
+            references: BTreeSet::from(["#1".to_string(), "#7".to_string()]),
+            recent_sessions: vec!["bbbb: second".to_string(), "aaaa: first".to_string()],
+            open_questions: vec!["Should we cache?".to_string()],
+            ..Default::default()
+        };
+
+        let diff = SnapshotDiff::between(&before, &after);
+        assert_eq!((diff.totals[0].before, diff.totals[0].after), (2, 4));
+        assert_eq!(
+            diff.sources,
+            vec![CountChange {
+                name: "Cursor".to_string(),
+                before: 0,
+                after: 2
+            }]
+        );
+        assert_eq!(diff.new_references, vec!["#7"]);
+        assert_eq!(diff.new_sessions, vec!["bbbb: second"]);
+        assert_eq!(diff.new_questions, vec!["Should we cache?"]);
+        assert_eq!(diff.settled_questions, vec!["Why is CI red?"]);
+
+        assert!(is_question("Some context\n\nwhat now? \n"));
+        assert!(!is_question("Fix it. Why? Because."));
+    }
+}
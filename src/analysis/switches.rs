@@ -0,0 +1,100 @@
+//! Model switch detection
+//!
+//! Tools can change the model mid-session, by user choice or silently (rate-limit
+//! fallbacks to a cheaper model). Each assistant message records the model that
+//! answered; a switch is any change between consecutive answers. Switches are classed
+//! as downgrades or upgrades by output token price, since that is what quality and
+//! cost attribution follow.
+
+use serde::Serialize;
+
+use super::Pricing;
+
+/// Direction of a switch by output price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchKind {
+    /// To a cheaper model
+    Downgrade,
+    /// To a pricier model
+    Upgrade,
+    /// Same price, or a model without a known price
+    Switch,
+}
+
+impl SwitchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwitchKind::Downgrade => "downgrade",
+            SwitchKind::Upgrade => "upgrade",
+            SwitchKind::Switch => "switch",
+        }
+    }
+}
+
+/// The answering model changed between two assistant messages
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSwitch {
+    pub from: String,
+    pub to: String,
+    /// 1-based message number (as `read` numbers them) of the first answer by `to`
+    pub message: usize,
+    pub timestamp: Option<String>,
+    pub kind: SwitchKind,
+}
+
+/// Switches in a session's answers, given as (message number, model, timestamp) in order
+pub fn detect_model_switches(
+    answers: &[(usize, String, Option<String>)],
+    pricing: &Pricing,
+) -> Vec<ModelSwitch> {
+    answers
+        .windows(2)
+        .filter(|pair| pair[0].1 != pair[1].1)
+        .map(|pair| {
+            let (from, (message, to, timestamp)) = (&pair[0].1, &pair[1]);
+            let price = |model: &str| pricing.price(model).map(|p| p.output);
+            let kind = match (price(from), price(to)) {
+                (Some(a), Some(b)) if b < a => SwitchKind::Downgrade,
+                (Some(a), Some(b)) if b > a => SwitchKind::Upgrade,
+                _ => SwitchKind::Switch,
+            };
+            ModelSwitch {
+                from: from.clone(),
+                to: to.clone(),
+                message: *message,
+                timestamp: timestamp.clone(),
+                kind,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_detect_model_switches() {
+        let answer = |n: usize, model: &str| (n, model.to_string(), None);
+        let answers = vec![
+            answer(2, "claude-opus-4-1"),
+            answer(4, "claude-opus-4-1"),
+            answer(6, "claude-sonnet-4-5"),
+            answer(8, "claude-opus-4-1"),
+            answer(10, "local-model"),
+        ];
+        let switches = detect_model_switches(&answers, &Pricing::new(&BTreeMap::new()));
+        let kinds: Vec<_> = switches.iter().map(|s| (s.message, s.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (6, SwitchKind::Downgrade),
+                (8, SwitchKind::Upgrade),
+                (10, SwitchKind::Switch)
+            ]
+        );
+        assert_eq!(switches[0].from, "claude-opus-4-1");
+    }
+}
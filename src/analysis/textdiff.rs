@@ -0,0 +1,79 @@
+//! Line diff for comparing texts such as system prompts
+//!
+//! A plain longest-common-subsequence diff after trimming the shared head and tail,
+//! which keeps the quadratic table small for prompts that changed in a few places.
+
+/// One line of a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line-by-line diff turning `before` into `after`
+pub fn diff_lines<'a>(before: &'a str, after: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    let head = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let tail = a[head..]
+        .iter()
+        .rev()
+        .zip(b[head..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[head..a.len() - tail], &b[head..b.len() - tail]);
+
+    // lcs[i][j]: common subsequence length of a_mid[i..] and b_mid[j..]
+    let mut lcs = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<DiffLine> = a[..head].iter().map(|l| DiffLine::Same(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() || j < b_mid.len() {
+        if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+            lines.push(DiffLine::Same(a_mid[i]));
+            i += 1;
+            j += 1;
+        } else if i < a_mid.len() && (j == b_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(DiffLine::Removed(a_mid[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b_mid[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[a.len() - tail..].iter().map(|l| DiffLine::Same(l)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let before = "You are an agent.\nBe concise.\nNever guess.\nUse tools.";
+        let after = "You are an agent.\nBe thorough.\nNever guess.\nUse tools.\nAsk first.";
+        assert_eq!(
+            diff_lines(before, after),
+            vec![
+                DiffLine::Same("You are an agent."),
+                DiffLine::Removed("Be concise."),
+                DiffLine::Added("Be thorough."),
+                DiffLine::Same("Never guess."),
+                DiffLine::Same("Use tools."),
+                DiffLine::Added("Ask first."),
+            ]
+        );
+    }
+}
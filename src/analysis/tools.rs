@@ -0,0 +1,41 @@
+//! Tool-use classification
+//!
+//! Tools provided by MCP servers reach the model under namespaced names; Claude
+//! Code (and Codex) call them `mcp__<server>__<tool>`. Anything else is one of the
+//! source's built-in tools.
+
+/// `tool_uses.origin` of built-in tools
+pub const BUILTIN: &str = "builtin";
+/// `tool_uses.origin` of MCP server tools
+pub const MCP: &str = "mcp";
+
+/// MCP server a tool belongs to, if its name is namespaced as one
+pub fn mcp_server(tool_name: &str) -> Option<&str> {
+    let (server, tool) = tool_name.strip_prefix("mcp__")?.split_once("__")?;
+    (!server.is_empty() && !tool.is_empty()).then_some(server)
+}
+
+/// Where a tool comes from: [`MCP`] or [`BUILTIN`]
+pub fn origin(tool_name: &str) -> &'static str {
+    match mcp_server(tool_name) {
+        Some(_) => MCP,
+        None => BUILTIN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_mcp_tools() {
+        assert_eq!(mcp_server("mcp__github__create_issue"), Some("github"));
+        assert_eq!(
+            mcp_server("mcp__claude-in-chrome__navigate"),
+            Some("claude-in-chrome")
+        );
+        assert_eq!(mcp_server("mcp__broken"), None);
+        assert_eq!(origin("Bash"), BUILTIN);
+        assert_eq!(origin("mcp__linear__list_issues"), MCP);
+    }
+}
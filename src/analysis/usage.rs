@@ -0,0 +1,193 @@
+//! Usage spike detection and spend budgets
+//!
+//! Compares today's token volume against the average of the preceding
+//! baseline days, idle days included. A spike is reported when today exceeds
+//! the baseline by a configurable multiple and an absolute floor, so quiet
+//! weeks don't trigger alerts on modest activity. Budgets compare a day's
+//! estimated spend, in total and per project, against fixed limits.
+
+use chrono::{Days, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Token totals for one local calendar day (`YYYY-MM-DD`)
+#[derive(Debug, Clone)]
+pub struct DailyUsage {
+    pub day: String,
+    pub tokens: i64,
+}
+
+/// A detected spike in daily usage
+#[derive(Debug, Clone)]
+pub struct UsageSpike {
+    pub day: String,
+    pub tokens: i64,
+    pub baseline: f64,
+    pub ratio: f64,
+}
+
+/// Detect a spike on `today` against the `baseline_days` before it. `days` lists active
+/// days only; days missing from it count as no usage.
+pub fn detect_spike(
+    days: &[DailyUsage],
+    today: NaiveDate,
+    baseline_days: u32,
+    multiplier: f64,
+    min_tokens: i64,
+) -> Option<UsageSpike> {
+    let start = today.checked_sub_days(Days::new(baseline_days.into()))?;
+    let day = today.format("%Y-%m-%d").to_string();
+    let tokens = days.iter().find(|d| d.day == day).map_or(0, |d| d.tokens);
+    if baseline_days == 0 || tokens < min_tokens {
+        return None;
+    }
+
+    let history: Vec<i64> = days
+        .iter()
+        .filter_map(|d| {
+            let date = NaiveDate::parse_from_str(&d.day, "%Y-%m-%d").ok()?;
+            (date >= start && date < today).then_some(d.tokens)
+        })
+        .collect();
+    // Without any activity in the window there's nothing to compare against
+    if history.is_empty() {
+        return None;
+    }
+
+    let baseline = history.iter().sum::<i64>() as f64 / baseline_days as f64;
+    let ratio = if baseline > 0.0 {
+        tokens as f64 / baseline
+    } else {
+        f64::INFINITY
+    };
+
+    (ratio >= multiplier).then_some(UsageSpike {
+        day,
+        tokens,
+        baseline,
+        ratio,
+    })
+}
+
+/// A day's estimated spend against one configured limit
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetStatus {
+    /// Project the limit applies to; `None` for the overall daily budget
+    pub project: Option<String>,
+    pub spent: f64,
+    pub limit: f64,
+}
+
+impl BudgetStatus {
+    pub fn exceeded(&self) -> bool {
+        self.spent > self.limit
+    }
+
+    /// Alert kind, distinct per project so each fires once a day
+    pub fn alert_kind(&self) -> String {
+        match &self.project {
+            Some(project) => format!("budget:{}", project),
+            None => "budget".to_string(),
+        }
+    }
+}
+
+/// Spend against each configured limit; projects without spend count as zero
+pub fn budget_status(
+    total: f64,
+    by_project: &BTreeMap<String, f64>,
+    daily_limit: Option<f64>,
+    project_limits: &BTreeMap<String, f64>,
+) -> Vec<BudgetStatus> {
+    let overall = daily_limit.map(|limit| BudgetStatus {
+        project: None,
+        spent: total,
+        limit,
+    });
+    let projects = project_limits.iter().map(|(project, limit)| BudgetStatus {
+        spent: by_project.get(project).copied().unwrap_or(0.0),
+        project: Some(project.clone()),
+        limit: *limit,
+    });
+    overall.into_iter().chain(projects).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    /// Usage on consecutive days from 2025-01-01
+    fn days(tokens: &[i64]) -> Vec<DailyUsage> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(i, t)| DailyUsage {
+                day: format!("2025-01-{:02}", i + 1),
+                tokens: *t,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_spike_over_baseline() {
+        let spike = detect_spike(&days(&[1000, 1200, 800, 5000]), date(4), 3, 3.0, 100).unwrap();
+        assert_eq!(spike.day, "2025-01-04");
+        assert_eq!(spike.baseline, 1000.0);
+        assert_eq!(spike.ratio, 5.0);
+    }
+
+    #[test]
+    fn test_respects_multiplier_and_floor() {
+        assert!(detect_spike(&days(&[1000, 1000, 2500]), date(3), 2, 3.0, 100).is_none());
+        assert!(detect_spike(&days(&[10, 10, 90]), date(3), 2, 3.0, 100).is_none());
+        assert!(detect_spike(&days(&[5000]), date(1), 7, 3.0, 100).is_none());
+    }
+
+    #[test]
+    fn test_idle_days_count_toward_baseline() {
+        // Two active days in a 7-day window: the baseline is 4000 / 7, not 2000
+        let usage = vec![
+            DailyUsage {
+                day: "2025-01-02".to_string(),
+                tokens: 2000,
+            },
+            DailyUsage {
+                day: "2025-01-05".to_string(),
+                tokens: 2000,
+            },
+            DailyUsage {
+                day: "2025-01-08".to_string(),
+                tokens: 2000,
+            },
+        ];
+        let spike = detect_spike(&usage, date(8), 7, 3.0, 100).unwrap();
+        assert!((spike.baseline - 4000.0 / 7.0).abs() < 1e-9);
+        assert!((spike.ratio - 3.5).abs() < 1e-9);
+
+        // The last active day is not today: today has no usage, so no spike
+        assert!(detect_spike(&usage, date(10), 7, 3.0, 100).is_none());
+        // Days before the window don't count
+        assert!(detect_spike(&usage, date(20), 7, 3.0, 0).is_none());
+    }
+
+    #[test]
+    fn test_budget_status() {
+        let by_project = BTreeMap::from([("web".to_string(), 6.0), ("cli".to_string(), 1.0)]);
+        let limits = BTreeMap::from([("web".to_string(), 5.0), ("docs".to_string(), 2.0)]);
+        let status = budget_status(7.0, &by_project, Some(10.0), &limits);
+        let exceeded: Vec<String> = status
+            .iter()
+            .filter(|s| s.exceeded())
+            .map(|s| s.alert_kind())
+            .collect();
+        assert_eq!(status.len(), 3);
+        assert_eq!(exceeded, vec!["budget:web"]);
+        assert_eq!(status[1].project.as_deref(), Some("docs"));
+        assert_eq!(status[1].spent, 0.0);
+
+        assert!(budget_status(7.0, &by_project, None, &BTreeMap::new()).is_empty());
+    }
+}
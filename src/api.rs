@@ -0,0 +1,251 @@
+//! Library entry point
+//!
+//! [`Chronicle`] owns the configuration, the metadata store and the probe registry, and
+//! answers queries with typed values, so another program can embed the index without
+//! going through the CLI:
+//!
+//! ```no_run
+//! use chronicle::store::SessionQuery;
+//! use chronicle::Chronicle;
+//!
+//! let chronicle = Chronicle::open("~/.config/chronicle/chronicle.yaml")?;
+//! for session in chronicle.sessions(&SessionQuery::default())? {
+//!     println!("{} {:?} {:?}", session.short_hash, session.last_active_at, session.title);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::content::ContentLoader;
+use crate::extract::{self, ExtractOptions, ExtractSummary};
+use crate::probe::ProbeRegistry;
+use crate::store::{
+    check_backend, parse_stored_time, MessageRow, MetadataStore, ProjectRow, SessionQuery,
+    SessionRow, Visibility,
+};
+
+/// Config, store and probes of one Chronicle installation
+pub struct Chronicle {
+    config: Config,
+    store: MetadataStore,
+    registry: ProbeRegistry,
+}
+
+impl Chronicle {
+    /// Load the config (searched like the CLI's `--config`) and open its database
+    pub fn open(config_path: &str) -> Result<Self> {
+        Self::from_config(Config::load(config_path)?)
+    }
+
+    pub fn from_config(config: Config) -> Result<Self> {
+        check_backend(&config.database)?;
+        let store = MetadataStore::open_with(&config.database_path(), &config.database)?
+            .with_path_normalization(config.linking.normalize_paths);
+        let registry = ProbeRegistry::new(&config);
+        Ok(Self {
+            config,
+            store,
+            registry,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The underlying store, for queries the facade doesn't cover
+    pub fn store(&self) -> &MetadataStore {
+        &self.store
+    }
+
+    pub fn registry(&self) -> &ProbeRegistry {
+        &self.registry
+    }
+
+    /// Index new and changed sessions from all available probes
+    pub fn extract(&self, options: &ExtractOptions) -> Result<ExtractSummary> {
+        extract::run(
+            &self.store,
+            &self.registry,
+            &self.config,
+            options,
+            &mut |_| {},
+        )
+    }
+
+    /// Sessions matching `query`, most recent first
+    pub fn sessions(&self, query: &SessionQuery) -> Result<Vec<Session>> {
+        Ok(self
+            .store
+            .query_sessions(query)?
+            .into_iter()
+            .map(Session::from)
+            .collect())
+    }
+
+    /// A session by short hash, ID or external ID (or a prefix of one)
+    pub fn session(&self, query: &str) -> Result<Option<Session>> {
+        Ok(self.store.get_session(query)?.map(Session::from))
+    }
+
+    /// Messages of a session in order
+    pub fn messages(&self, session: &Session) -> Result<Vec<Message>> {
+        Ok(self
+            .store
+            .get_messages(&session.id)?
+            .into_iter()
+            .map(Message::from)
+            .collect())
+    }
+
+    pub fn projects(&self) -> Result<Vec<Project>> {
+        Ok(self
+            .store
+            .list_projects()?
+            .into_iter()
+            .map(Project::from)
+            .collect())
+    }
+
+    /// Text of each message of a session, read from the source (or the content cache
+    /// when the source is gone); messages whose content can't be read are left out
+    pub fn transcript(&self, session: &Session) -> Result<Vec<(Message, String)>> {
+        let loader = ContentLoader::new(&self.registry).with_archive(&self.store);
+        Ok(self
+            .store
+            .get_messages(&session.id)?
+            .into_iter()
+            .filter_map(|row| {
+                let text = loader.load_text(&session.row, &row).ok()?;
+                Some((Message::from(row), text))
+            })
+            .collect())
+    }
+}
+
+/// An indexed session
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub id: String,
+    pub short_hash: String,
+    /// Probe that indexed the session, e.g. `claude-code:ClaudeCode`
+    pub probe: String,
+    pub provider: String,
+    pub source: String,
+    pub title: Option<String>,
+    pub model: Option<String>,
+    pub project_id: Option<String>,
+    pub project: Option<String>,
+    /// Working directory
+    pub path: Option<String>,
+    pub message_count: usize,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_active_at: Option<DateTime<Utc>>,
+    pub archived: bool,
+    #[serde(skip)]
+    row: SessionRow,
+}
+
+impl From<SessionRow> for Session {
+    fn from(row: SessionRow) -> Self {
+        Self {
+            id: row.id.clone(),
+            short_hash: row.short_hash.clone(),
+            probe: row.probe_source_id.clone(),
+            provider: row.provider_name.clone(),
+            source: row.source_name.clone(),
+            title: row.title.clone(),
+            model: row.primary_model.clone(),
+            project_id: row.project_id.clone(),
+            project: row.project_name.clone(),
+            path: row.project_path.clone(),
+            message_count: row.message_count.max(0) as usize,
+            started_at: row.first_at(),
+            last_active_at: row.last_at(),
+            archived: row.archived,
+            row,
+        }
+    }
+}
+
+/// A message of a session; its content is read on demand with [`Chronicle::transcript`]
+#[derive(Debug, Serialize)]
+pub struct Message {
+    pub id: i64,
+    pub role: String,
+    pub model: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub has_tool_use: bool,
+    pub has_thinking: bool,
+}
+
+impl From<MessageRow> for Message {
+    fn from(row: MessageRow) -> Self {
+        Self {
+            id: row.id,
+            sent_at: row.at(),
+            role: row.role,
+            model: row.model,
+            has_tool_use: row.has_tool_use,
+            has_thinking: row.has_thinking,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub project_type: String,
+    pub path: Option<String>,
+    pub session_count: usize,
+    pub message_count: usize,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_session_at: Option<DateTime<Utc>>,
+    pub visibility: Visibility,
+}
+
+impl From<ProjectRow> for Project {
+    fn from(row: ProjectRow) -> Self {
+        let time = |t: Option<String>| t.as_deref().and_then(parse_stored_time);
+        Self {
+            id: row.id,
+            name: row.name,
+            project_type: row.project_type,
+            path: row.primary_path,
+            session_count: row.session_count.max(0) as usize,
+            message_count: row.message_count.max(0) as usize,
+            created_at: time(row.created_at),
+            last_session_at: time(row.last_session_at),
+            visibility: row.visibility,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_timestamps() {
+        let session = Session::from(SessionRow {
+            id: "claude-code:ClaudeCode:abc".to_string(),
+            first_timestamp: Some("2026-03-01T09:30:00+01:00".to_string()),
+            last_timestamp: Some("garbled".to_string()),
+            message_count: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            session.started_at.map(|t| t.to_rfc3339()).as_deref(),
+            Some("2026-03-01T08:30:00+00:00")
+        );
+        assert_eq!((session.last_active_at, session.message_count), (None, 4));
+
+        let sqlite_default = parse_stored_time("2026-03-01 08:30:00");
+        assert_eq!(sqlite_default, session.started_at);
+    }
+}
@@ -0,0 +1,195 @@
+//! Usage alerting
+//!
+//! Checks run at the end of every extraction (and therefore on every watch
+//! cycle); each alert fires at most once per day.
+
+use anyhow::Result;
+use chrono::{Days, Local};
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use crate::analysis::usage::{budget_status, detect_spike, BudgetStatus, UsageSpike};
+use crate::config::Config;
+use crate::store::{MetadataStore, StorageBackend};
+
+/// Check for a usage spike, notifying once per day; returns a newly fired spike
+pub fn check_usage_spike(
+    store: &dyn StorageBackend,
+    config: &Config,
+) -> Result<Option<UsageSpike>> {
+    let alerts = &config.alerts;
+    if !alerts.enabled {
+        return Ok(None);
+    }
+
+    let today = Local::now().date_naive();
+    let since = today - Days::new(alerts.baseline_days.into());
+    let days = store.daily_token_usage(&since.format("%Y-%m-%d").to_string())?;
+    let Some(spike) = detect_spike(
+        &days,
+        today,
+        alerts.baseline_days,
+        alerts.spike_multiplier,
+        alerts.min_tokens,
+    ) else {
+        return Ok(None);
+    };
+
+    if !store.record_alert(
+        "token_spike",
+        &spike.day,
+        spike.tokens as f64,
+        Some(spike.baseline),
+    )? {
+        return Ok(None);
+    }
+
+    eprintln!(
+        "⚠️  Usage spike on {}: {} tokens ({:.1}x the {}-day average of {:.0})",
+        spike.day, spike.tokens, spike.ratio, alerts.baseline_days, spike.baseline
+    );
+
+    if let Some(ref command) = alerts.command {
+        notify(
+            command,
+            &[
+                ("CHRONICLE_ALERT_KIND", "token_spike".to_string()),
+                ("CHRONICLE_ALERT_DAY", spike.day.clone()),
+                ("CHRONICLE_ALERT_VALUE", spike.tokens.to_string()),
+                ("CHRONICLE_ALERT_BASELINE", format!("{:.0}", spike.baseline)),
+                ("CHRONICLE_ALERT_RATIO", format!("{:.2}", spike.ratio)),
+            ],
+        );
+    }
+
+    Ok(Some(spike))
+}
+
+/// Today's estimated spend against each configured budget
+pub fn budgets_today(store: &dyn StorageBackend, config: &Config) -> Result<Vec<BudgetStatus>> {
+    let budgets = &config.budgets;
+    if budgets.daily_usd.is_none() && budgets.per_project.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let midnight = super::timeparse::parse("today")?;
+    let pricing = config.pricing();
+    let (mut total, mut by_project) = (0.0, BTreeMap::new());
+    for row in store.token_usage(Some(&midnight.to_rfc3339()))? {
+        let Some(cost) = pricing.estimate(row.model.as_deref(), &row.tokens) else {
+            continue;
+        };
+        total += cost;
+        if let Some(project) = row.project_name {
+            *by_project.entry(project).or_insert(0.0) += cost;
+        }
+    }
+    Ok(budget_status(
+        total,
+        &by_project,
+        budgets.daily_usd,
+        &budgets.per_project,
+    ))
+}
+
+/// Warn about budgets crossed today, notifying once per budget per day; returns the
+/// newly crossed ones
+pub fn check_budgets(store: &dyn StorageBackend, config: &Config) -> Result<Vec<BudgetStatus>> {
+    let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut fired = vec![];
+    for budget in budgets_today(store, config)? {
+        if !budget.exceeded()
+            || !store.record_alert(&budget.alert_kind(), &day, budget.spent, Some(budget.limit))?
+        {
+            continue;
+        }
+
+        eprintln!(
+            "⚠️  {} over budget: {} spent today, limit {}",
+            budget.project.as_deref().unwrap_or("Daily spend"),
+            super::costs::format_cost(budget.spent),
+            super::costs::format_cost(budget.limit)
+        );
+        let command = config
+            .budgets
+            .command
+            .as_ref()
+            .or(config.alerts.command.as_ref());
+        if let Some(command) = command {
+            notify(
+                command,
+                &[
+                    ("CHRONICLE_ALERT_KIND", budget.alert_kind()),
+                    ("CHRONICLE_ALERT_DAY", day.clone()),
+                    ("CHRONICLE_ALERT_VALUE", format!("{:.2}", budget.spent)),
+                    ("CHRONICLE_ALERT_BASELINE", format!("{:.2}", budget.limit)),
+                    (
+                        "CHRONICLE_ALERT_PROJECT",
+                        budget.project.clone().unwrap_or_default(),
+                    ),
+                ],
+            );
+        }
+        fired.push(budget);
+    }
+    Ok(fired)
+}
+
+/// Run a notification command with the alert details in its environment; failures are
+/// reported but not fatal
+fn notify(command: &str, env: &[(&str, String)]) {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .status();
+
+    match status {
+        Ok(s) if s.success() => {}
+        Ok(s) => eprintln!("   Alert command exited with {}", s),
+        Err(e) => eprintln!("   Failed to run alert command: {}", e),
+    }
+}
+
+/// `chronicle alerts`: run checks now and show recent alerts
+pub fn run(store: &MetadataStore, config: &Config) -> Result<()> {
+    check_usage_spike(store, config)?;
+    check_budgets(store, config)?;
+
+    let alerts = store.list_alerts(20)?;
+    if alerts.is_empty() {
+        println!("No alerts recorded.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:<14} {:>14} {:>14} {:>8}",
+        "Day", "Kind", "Value", "Baseline", "Ratio"
+    );
+    println!("{}", "-".repeat(66));
+    for alert in alerts {
+        let ratio = alert
+            .baseline
+            .filter(|b| *b > 0.0)
+            .map(|b| format!("{:.1}x", alert.value / b))
+            .unwrap_or_else(|| "-".to_string());
+        // Budget alerts record spend in USD, spikes record tokens
+        let format = |value: f64| match alert.kind.starts_with("budget") {
+            true => super::costs::format_cost(value),
+            false => format!("{:.0}", value),
+        };
+        println!(
+            "{:<12} {:<14} {:>14} {:>14} {:>8}",
+            alert.day,
+            alert.kind,
+            format(alert.value),
+            alert
+                .baseline
+                .map(format)
+                .unwrap_or_else(|| "-".to_string()),
+            ratio
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,270 @@
+//! `chronicle backfill` - recompute one kind of derived data for indexed sessions
+//!
+//! Re-reads each session through its probe and rewrites only the rows of the chosen
+//! feature, so data added by a newer extractor reaches old sessions without a full
+//! `extract --full`. Text-only features fall back to cached content when the source
+//! is gone.
+
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{loops, references};
+use crate::config::Config;
+use crate::content::ContentLoader;
+use crate::extract::session_references;
+use crate::probe::{ProbeRegistry, SessionMetadata, SessionRef};
+use crate::store::{MetadataStore, SessionSourceRow};
+
+/// Features that can be backfilled, with what they rewrite
+pub const FEATURES: &[(&str, &str)] = &[
+    (
+        "tools",
+        "tool calls with their results, errors and permissions",
+    ),
+    ("loops", "runaway tool loop flags"),
+    ("references", "issue and PR references"),
+    ("commits", "commits made during sessions"),
+    ("language", "language of the user's messages"),
+];
+
+/// How a session was backfilled
+enum Outcome {
+    Source,
+    Cached,
+    Skipped(String),
+}
+
+pub fn run(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    config: &Config,
+    feature: &str,
+    probe: Option<&str>,
+) -> Result<()> {
+    if !FEATURES.iter().any(|(name, _)| *name == feature) {
+        let features: Vec<String> = FEATURES
+            .iter()
+            .map(|(name, rewrites)| format!("  {:<12} {}", name, rewrites))
+            .collect();
+        bail!(
+            "Unknown feature: {}. Expected one of:\n{}",
+            feature,
+            features.join("\n")
+        );
+    }
+    let sessions = store.session_sources(probe)?;
+    if sessions.is_empty() {
+        println!("No sessions to backfill. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    let loader = ContentLoader::new(registry).with_archive(store);
+    let (mut from_source, mut from_cache) = (0, 0);
+    let mut skipped: Vec<(String, String)> = vec![];
+    for session in &sessions {
+        let outcome = backfill_session(store, registry, &loader, config, feature, session)?;
+        match outcome {
+            Outcome::Source => from_source += 1,
+            Outcome::Cached => from_cache += 1,
+            Outcome::Skipped(reason) => skipped.push((session.session_id.clone(), reason)),
+        }
+    }
+
+    println!(
+        "✅ Backfilled {} for {} session(s){}",
+        feature,
+        from_source + from_cache,
+        match from_cache {
+            0 => String::new(),
+            n => format!(" ({} from cached content)", n),
+        }
+    );
+    if !skipped.is_empty() {
+        println!("⚠️  Skipped {} session(s):", skipped.len());
+        for (session_id, reason) in skipped.iter().take(10) {
+            println!("   {}: {}", session_id, reason);
+        }
+        if skipped.len() > 10 {
+            println!("   ... and {} more", skipped.len() - 10);
+        }
+    }
+    Ok(())
+}
+
+fn backfill_session(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    loader: &ContentLoader,
+    config: &Config,
+    feature: &str,
+    session: &SessionSourceRow,
+) -> Result<Outcome> {
+    let Some(probe) = registry.get_probe(&session.probe_source_id) else {
+        return Ok(Outcome::Skipped("probe not enabled".to_string()));
+    };
+    // Stored IDs are "<probe id>:<source session id>"
+    let id = session
+        .session_id
+        .strip_prefix(&format!("{}:", session.probe_source_id))
+        .unwrap_or(&session.session_id);
+    let reference = SessionRef {
+        id: id.to_string(),
+        source_path: PathBuf::from(&session.source_path),
+    };
+
+    let metadata = match probe.extract_metadata(&reference) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            return match feature {
+                "references" | "language" => from_cache(store, loader, feature, session, e),
+                _ => Ok(Outcome::Skipped(format!("{:#}", e))),
+            }
+        }
+    };
+    store.transaction(|| apply(store, config, feature, &session.session_id, &metadata))?;
+    Ok(Outcome::Source)
+}
+
+/// Rewrite one feature's rows from freshly extracted metadata
+fn apply(
+    store: &MetadataStore,
+    config: &Config,
+    feature: &str,
+    session_id: &str,
+    metadata: &SessionMetadata,
+) -> Result<()> {
+    match feature {
+        // Message IDs survive re-indexing, so only their tool rows change
+        "tools" => {
+            store.insert_messages(session_id, &metadata.messages)?;
+            store.refresh_session_metrics(session_id)?;
+        }
+        "loops" => {
+            let tool_loops =
+                loops::detect_tool_loops(&metadata.messages, config.anomalies.tool_loop_threshold);
+            store.replace_tool_loops(session_id, &tool_loops)?;
+        }
+        "references" => {
+            store.replace_session_references(session_id, &session_references(metadata))?;
+        }
+        "commits" => store.replace_session_commits(session_id, &metadata.commits)?,
+        "language" => store.set_session_language(session_id, metadata.language.as_deref())?,
+        _ => unreachable!("feature names are checked up front"),
+    }
+    Ok(())
+}
+
+/// Recompute a text-only feature from content the loader can still reach
+fn from_cache(
+    store: &MetadataStore,
+    loader: &ContentLoader,
+    feature: &str,
+    session: &SessionSourceRow,
+    source_error: anyhow::Error,
+) -> Result<Outcome> {
+    let Some(row) = store.get_session(&session.session_id)? else {
+        return Ok(Outcome::Skipped("session no longer indexed".to_string()));
+    };
+    let mut found = vec![];
+    let mut language = LanguageSample::default();
+    let mut loaded = 0;
+    for message in store.get_messages(&row.id)? {
+        let Ok(text) = loader.load_text(&row, &message) else {
+            continue;
+        };
+        loaded += 1;
+        found.extend(references::detect(&text));
+        if message.role == "user" {
+            language.add(&text);
+        }
+    }
+    if loaded == 0 {
+        return Ok(Outcome::Skipped(format!("{:#}", source_error)));
+    }
+
+    if feature == "references" {
+        if let Some(ref title) = row.title {
+            found.extend(references::detect(title));
+        }
+        found.sort();
+        found.dedup();
+        store.replace_session_references(&row.id, &found)?;
+    } else {
+        store.set_session_language(&row.id, language.detect())?;
+    }
+    Ok(Outcome::Cached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{ContentRef, MessageMetadata, SkipCounts, SourceType};
+
+    #[test]
+    fn test_references_backfilled_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source(
+                "claude:ClaudeCode",
+                None,
+                "ClaudeCode",
+                SourceType::Multi,
+                None,
+                "active",
+            )
+            .unwrap();
+        let source = dir.path().join("gone.jsonl");
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: source.clone(),
+        };
+        let metadata = SessionMetadata {
+            external_id: session.id.clone(),
+            title: None,
+            project_path: None,
+            git_remote: None,
+            source_group: None,
+            primary_provider: None,
+            primary_model: None,
+            first_timestamp: None,
+            last_timestamp: None,
+            messages: vec![MessageMetadata {
+                uuid: Some("a".to_string()),
+                role: "user".to_string(),
+                provider_id: None,
+                model: None,
+                timestamp: None,
+                content_ref: ContentRef::jsonl(source, 0, 0),
+                has_tool_use: false,
+                has_thinking: false,
+                tool_uses: vec![],
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            }],
+            references: vec![],
+            language: None,
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        };
+        let session_id = store
+            .upsert_session("claude:ClaudeCode", &session, &metadata)
+            .unwrap();
+        let ids = store
+            .insert_messages(&session_id, &metadata.messages)
+            .unwrap();
+        store
+            .cache_content(&[(ids[0], r#"{"content":"Please fix #42"}"#.to_string())])
+            .unwrap();
+
+        // The source file never existed, so only the cached copy can be read
+        let config = Config::default();
+        let registry = ProbeRegistry::new(&config);
+        run(&store, &registry, &config, "references", None).unwrap();
+        assert_eq!(store.get_session_references(&session_id).unwrap(), ["#42"]);
+        assert!(run(&store, &registry, &config, "everything", None).is_err());
+    }
+}
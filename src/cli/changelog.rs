@@ -0,0 +1,370 @@
+//! Changelog command implementation
+//!
+//! Drafts a markdown worklog from sessions in a time window, correlated with git commits:
+//! commits recorded by the session itself (Aider) or committed while the session was active.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::store::{Audience, MetadataStore, SessionRow};
+
+/// Commits made this long after a session's last message still count as its work
+const COMMIT_GRACE_MINUTES: i64 = 30;
+
+/// A session with the details shown in the changelog
+struct Entry {
+    session: SessionRow,
+    recorded_commits: Vec<String>,
+    references: Vec<String>,
+}
+
+/// A commit from the project's git history
+struct GitCommit {
+    hash: String,
+    short_hash: String,
+    time: DateTime<Utc>,
+    subject: String,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    config: &Config,
+    project: Option<String>,
+    since: &str,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let since_time = super::timeparse::parse(since)?;
+
+    // Real projects filter by assignment; virtual projects by their config filter
+    let mut virtual_project = None;
+    let project = match project {
+        Some(query) => match store.find_project(&query)? {
+            Some(project) => {
+                super::ensure_visible(&project, Audience::Public)?;
+                Some(project)
+            }
+            None => {
+                let vp = config
+                    .virtual_project(&query)
+                    .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?;
+                virtual_project = Some((query, vp));
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut sessions = store.sessions_since(
+        project.as_ref().map(|p| p.id.as_str()),
+        &since_time.to_rfc3339(),
+    )?;
+    if let Some((_, vp)) = virtual_project {
+        sessions.retain(|s| vp.matches(s));
+    }
+    let withheld = super::withhold(store, &mut sessions, Audience::Public)?;
+    if withheld > 0 {
+        eprintln!(
+            "Left out {} session(s) from private or internal projects",
+            withheld
+        );
+    }
+    let mut entries = vec![];
+    for session in sessions {
+        entries.push(Entry {
+            recorded_commits: store
+                .get_session_commits(&session.id)?
+                .into_iter()
+                .map(|c| c.hash)
+                .collect(),
+            references: store.get_session_references(&session.id)?,
+            session,
+        });
+    }
+
+    // Git history only makes sense for a single project checkout
+    let commits = match project {
+        Some(ref p) => store
+            .get_project_paths(&p.id)?
+            .iter()
+            .map(Path::new)
+            .find(|path| path.join(".git").exists())
+            .map(|path| git_log(path, since_time))
+            .transpose()?
+            .unwrap_or_default(),
+        None => vec![],
+    };
+
+    let title = project
+        .as_ref()
+        .map(|p| p.name.clone())
+        .or(virtual_project.map(|(name, _)| name))
+        .unwrap_or_else(|| "all projects".to_string());
+    let markdown = render(&title, since_time, &entries, &commits)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, markdown)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote changelog to {}", path.display());
+        }
+        None => print!("{}", markdown),
+    }
+
+    Ok(())
+}
+
+fn git_log(repo: &Path, since: DateTime<Utc>) -> Result<Vec<GitCommit>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args([
+            "log",
+            "--no-merges",
+            "--pretty=format:%H%x09%h%x09%aI%x09%s",
+        ])
+        .arg(format!("--since={}", since.to_rfc3339()))
+        .output()
+        .context("Failed to run git log")?;
+
+    if !output.status.success() {
+        return Ok(vec![]);
+    }
+
+    let commits = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+            let hash = parts.next()?.to_string();
+            let short_hash = parts.next()?.to_string();
+            let time = DateTime::parse_from_rfc3339(parts.next()?)
+                .ok()?
+                .with_timezone(&Utc);
+            let subject = parts.next().unwrap_or("").to_string();
+            Some(GitCommit {
+                hash,
+                short_hash,
+                time,
+                subject,
+            })
+        })
+        .collect();
+    Ok(commits)
+}
+
+fn parse_time(ts: Option<&str>) -> Option<DateTime<Utc>> {
+    ts.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Index of the session a commit belongs to: recorded by the session, else made while it ran
+fn owning_session(entries: &[Entry], commit: &GitCommit) -> Option<usize> {
+    let recorded = entries.iter().position(|e| {
+        e.recorded_commits
+            .iter()
+            .any(|h| commit.hash.starts_with(h.as_str()))
+    });
+    recorded.or_else(|| {
+        entries.iter().position(|e| {
+            let start = parse_time(e.session.first_timestamp.as_deref());
+            let end = parse_time(e.session.last_timestamp.as_deref()).or(start);
+            match (start, end) {
+                (Some(start), Some(end)) => {
+                    commit.time >= start
+                        && commit.time <= end + Duration::minutes(COMMIT_GRACE_MINUTES)
+                }
+                _ => false,
+            }
+        })
+    })
+}
+
+fn render(
+    title: &str,
+    since: DateTime<Utc>,
+    entries: &[Entry],
+    commits: &[GitCommit],
+) -> Result<String> {
+    let mut session_commits: Vec<Vec<&GitCommit>> = vec![vec![]; entries.len()];
+    let mut other_commits: BTreeMap<String, Vec<&GitCommit>> = BTreeMap::new();
+    for commit in commits {
+        match owning_session(entries, commit) {
+            Some(idx) => session_commits[idx].push(commit),
+            None => other_commits
+                .entry(commit.time.format("%Y-%m-%d").to_string())
+                .or_default()
+                .push(commit),
+        }
+    }
+
+    // Group sessions by the day they started
+    let mut days: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        let day = entry
+            .session
+            .first_timestamp
+            .as_deref()
+            .and_then(|ts| ts.get(..10))
+            .unwrap_or("undated")
+            .to_string();
+        days.entry(day).or_default().push(idx);
+    }
+    for day in other_commits.keys() {
+        days.entry(day.clone()).or_default();
+    }
+
+    let mut out = String::new();
+    writeln!(out, "# Worklog: {}\n", title)?;
+    writeln!(
+        out,
+        "_{} → {} · {} AI session(s) · {} commit(s)_",
+        since.format("%Y-%m-%d"),
+        Utc::now().format("%Y-%m-%d"),
+        entries.len(),
+        commits.len()
+    )?;
+
+    if days.is_empty() {
+        writeln!(out, "\nNo sessions or commits in this period.")?;
+        return Ok(out);
+    }
+
+    for (day, indexes) in &days {
+        writeln!(out, "\n## {}\n", day)?;
+
+        for &idx in indexes {
+            let entry = &entries[idx];
+            let session = &entry.session;
+            let title = session
+                .title
+                .as_deref()
+                .and_then(|t| t.lines().next())
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or("Untitled session");
+            let model = session
+                .primary_model
+                .as_deref()
+                .map(|m| format!(", {}", m))
+                .unwrap_or_default();
+            write!(
+                out,
+                "- **{}** (`{}`, {}{}, {} msgs)",
+                title.trim(),
+                session.short_hash,
+                session.source_name,
+                model,
+                session.message_count
+            )?;
+            if !entry.references.is_empty() {
+                write!(out, " — refs: {}", entry.references.join(", "))?;
+            }
+            writeln!(out)?;
+
+            for commit in &session_commits[idx] {
+                writeln!(out, "  - `{}` {}", commit.short_hash, commit.subject)?;
+            }
+            // Commits recorded by the tool but not found in the checked-out history
+            for hash in &entry.recorded_commits {
+                if !session_commits[idx]
+                    .iter()
+                    .any(|c| c.hash.starts_with(hash.as_str()))
+                {
+                    writeln!(out, "  - `{}`", hash)?;
+                }
+            }
+        }
+
+        if let Some(commits) = other_commits.get(day) {
+            writeln!(out, "\nOther commits:\n")?;
+            for commit in commits {
+                writeln!(out, "- `{}` {}", commit.short_hash, commit.subject)?;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(hash: &str, first: &str, last: &str) -> SessionRow {
+        SessionRow {
+            id: hash.to_string(),
+            probe_source_id: "aider:Aider".to_string(),
+            external_id: hash.to_string(),
+            short_hash: hash.to_string(),
+            project_id: None,
+            project_assignment: "auto".to_string(),
+            title: Some(format!("Work in {}", hash)),
+            primary_provider: None,
+            primary_model: Some("gpt-4o".to_string()),
+            message_count: 4,
+            first_timestamp: Some(first.to_string()),
+            last_timestamp: Some(last.to_string()),
+            project_path: None,
+            source_name: "Aider".to_string(),
+            provider_name: "multi".to_string(),
+            project_name: None,
+            source_group: None,
+            project_type: None,
+            language: None,
+            archived: false,
+            metrics: Default::default(),
+        }
+    }
+
+    fn commit(hash: &str, time: &str) -> GitCommit {
+        GitCommit {
+            hash: hash.to_string(),
+            short_hash: hash[..7].to_string(),
+            time: parse_time(Some(time)).unwrap(),
+            subject: format!("commit {}", hash),
+        }
+    }
+
+    #[test]
+    fn test_commits_correlate_by_record_then_time() {
+        let entries = vec![
+            Entry {
+                session: session(
+                    "aaaa",
+                    "2025-10-10T09:00:00+00:00",
+                    "2025-10-10T10:00:00+00:00",
+                ),
+                recorded_commits: vec!["3a4b5c6".to_string()],
+                references: vec!["#7".to_string()],
+            },
+            Entry {
+                session: session(
+                    "bbbb",
+                    "2025-10-11T09:00:00+00:00",
+                    "2025-10-11T10:00:00+00:00",
+                ),
+                recorded_commits: vec![],
+                references: vec![],
+            },
+        ];
+        let commits = vec![
+            commit("3a4b5c6d", "2025-10-12T08:00:00+00:00"),
+            commit("1111111a", "2025-10-11T10:20:00+00:00"),
+            commit("2222222b", "2025-10-11T15:00:00+00:00"),
+        ];
+
+        assert_eq!(owning_session(&entries, &commits[0]), Some(0));
+        assert_eq!(owning_session(&entries, &commits[1]), Some(1));
+        assert_eq!(owning_session(&entries, &commits[2]), None);
+
+        let since = parse_time(Some("2025-10-09T00:00:00+00:00")).unwrap();
+        let markdown = render("app", since, &entries, &commits).unwrap();
+        assert!(markdown.contains("- **Work in aaaa** (`aaaa`, Aider, gpt-4o, 4 msgs) — refs: #7"));
+        assert!(markdown.contains("  - `3a4b5c6` commit 3a4b5c6d"));
+        assert!(markdown.contains("Other commits:\n\n- `2222222` commit 2222222b"));
+    }
+}
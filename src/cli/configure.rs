@@ -0,0 +1,382 @@
+//! `chronicle config probes` - interactive probe settings editor
+//!
+//! Lists the built-in probes with their enablement, data path and whether data was
+//! found there, then reads commands a line at a time from stdin:
+//! - `<n>` toggle probe n on or off
+//! - `p <n> <path>` set its base path, `p <n>` go back to the default location
+//! - `t <n>` test it: check the path and count the sessions it would index
+//! - `w` write the changes to the config file, `q` quit without writing
+//!
+//! The config file is edited in place, line by line, so comments and the layout of
+//! everything outside the touched `probes:` entries are kept.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use super::theme;
+use crate::config::Config;
+use crate::probe::{builtin_probe, BUILTIN_PROBES};
+
+/// Pending settings of one probe
+#[derive(Debug, Clone, PartialEq)]
+struct Setting {
+    enabled: bool,
+    /// Configured base path as written in the file (`~` unexpanded); None = default
+    path: Option<String>,
+}
+
+/// What the loop does after a command
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Write,
+    Quit,
+}
+
+struct ProbeEditor {
+    original: BTreeMap<&'static str, Setting>,
+    settings: BTreeMap<&'static str, Setting>,
+    /// Frozen or deprecated probes stay off whatever `enabled` says
+    statuses: BTreeMap<&'static str, String>,
+}
+
+pub fn probes(config_path: &str) -> Result<()> {
+    let file = Config::find_file(config_path)
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde(config_path).to_string()));
+    let config = Config::load(config_path)?;
+    let mut editor = ProbeEditor::new(&config);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    println!("Probe settings ({})", file.display());
+    print!("{}", editor.table());
+    loop {
+        write!(
+            stdout,
+            "\n<n> toggle · p <n> [path] set path · t <n> test · w write · q quit\n> "
+        )?;
+        stdout.flush()?;
+        let mut input = String::new();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            println!();
+            break;
+        }
+        match editor.handle(input.trim()) {
+            Ok(Step::Continue) => {}
+            Ok(Step::Write) => {
+                let changes = editor.changes();
+                if changes == 0 {
+                    println!("No changes to write.");
+                } else {
+                    editor.save(&file)?;
+                    println!("Wrote {} change(s) to {}", changes, file.display());
+                }
+                return Ok(());
+            }
+            Ok(Step::Quit) => break,
+            Err(e) => println!("{}", e),
+        }
+    }
+    if editor.changes() > 0 {
+        println!("Discarded {} unsaved change(s).", editor.changes());
+    }
+    Ok(())
+}
+
+impl ProbeEditor {
+    fn new(config: &Config) -> Self {
+        let mut settings = BTreeMap::new();
+        let mut statuses = BTreeMap::new();
+        for id in BUILTIN_PROBES {
+            let configured = config.probes.get(*id);
+            settings.insert(
+                *id,
+                Setting {
+                    enabled: configured.is_none_or(|p| p.enabled),
+                    path: configured.and_then(|p| p.base_path.clone()),
+                },
+            );
+            if let Some(status) = config.probe_status(id) {
+                statuses.insert(*id, status.to_string());
+            }
+        }
+        Self {
+            original: settings.clone(),
+            settings,
+            statuses,
+        }
+    }
+
+    fn handle(&mut self, command: &str) -> Result<Step> {
+        let mut words = command.splitn(3, ' ');
+        match (words.next().unwrap_or(""), words.next(), words.next()) {
+            ("", _, _) => print!("{}", self.table()),
+            ("w", None, _) => return Ok(Step::Write),
+            ("q", None, _) => return Ok(Step::Quit),
+            ("p", Some(n), path) => {
+                let id = self.probe(n)?;
+                let setting = self.settings.get_mut(id).unwrap();
+                setting.path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+                print!("{}", self.table());
+            }
+            ("t", Some(n), None) => {
+                let id = self.probe(n)?;
+                println!("{}", self.test(id));
+            }
+            (n, None, _) => {
+                let id = self.probe(n)?;
+                let setting = self.settings.get_mut(id).unwrap();
+                setting.enabled = !setting.enabled;
+                print!("{}", self.table());
+            }
+            _ => anyhow::bail!("Unknown command: {}", command),
+        }
+        Ok(Step::Continue)
+    }
+
+    /// Probe ID for a 1-based row number
+    fn probe(&self, n: &str) -> Result<&'static str> {
+        n.parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| BUILTIN_PROBES.get(i))
+            .copied()
+            .with_context(|| format!("No probe numbered {}", n))
+    }
+
+    fn expanded(&self, id: &str) -> Option<PathBuf> {
+        self.settings[id]
+            .path
+            .as_ref()
+            .map(|p| PathBuf::from(shellexpand::tilde(p).to_string()))
+    }
+
+    fn table(&self) -> String {
+        let theme = theme::current();
+        let mut out = format!(
+            "{:>3}  {:<19} {:<48} {:<8} {:<6} Path\n",
+            "#", "Probe", "Tool", "Enabled", "Found"
+        );
+        for (i, id) in BUILTIN_PROBES.iter().enumerate() {
+            let Some(probe) = builtin_probe(id, self.expanded(id)) else {
+                continue;
+            };
+            let setting = &self.settings[id];
+            let enabled = match self.statuses.get(id).map(String::as_str) {
+                Some(status @ ("frozen" | "deprecated")) => status.to_string(),
+                _ if setting.enabled => "yes".to_string(),
+                _ => "no".to_string(),
+            };
+            let changed = if *setting != self.original[id] {
+                "*"
+            } else {
+                " "
+            };
+            out.push_str(&format!(
+                "{:>3}{} {:<19} {:<48} {:<8} {:<6} {}{}\n",
+                i + 1,
+                changed,
+                theme.source(probe.id(), 19),
+                probe.description(),
+                enabled,
+                match probe.is_available() {
+                    true => theme.icon("✓", "yes"),
+                    false => theme.icon("✗", "no"),
+                },
+                probe.base_path().display(),
+                if setting.path.is_none() {
+                    " (default)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        out
+    }
+
+    /// Availability and session count at the pending path
+    fn test(&self, id: &str) -> String {
+        let Some(probe) = builtin_probe(id, self.expanded(id)) else {
+            return format!("{}: unknown probe", id);
+        };
+        let path = probe.base_path().display().to_string();
+        if !probe.is_available() {
+            return format!("{}: no data at {}", id, path);
+        }
+        match probe.discover() {
+            Ok(sessions) => format!("{}: {} session(s) found at {}", id, sessions.len(), path),
+            Err(e) => format!("{}: data at {} but discovery failed: {:#}", id, path, e),
+        }
+    }
+
+    fn changes(&self) -> usize {
+        self.settings
+            .iter()
+            .filter(|(id, setting)| self.original[*id] != **setting)
+            .count()
+    }
+
+    fn save(&self, file: &Path) -> Result<()> {
+        let mut yaml = match file.exists() {
+            true => std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?,
+            false => String::new(),
+        };
+        for (id, setting) in &self.settings {
+            let original = &self.original[id];
+            if setting.enabled != original.enabled {
+                let enabled = setting.enabled.into();
+                yaml = set_probe_option(&yaml, id, "enabled", Some(enabled))?;
+            }
+            if setting.path != original.path {
+                let path = setting.path.clone().map(Into::into);
+                yaml = set_probe_option(&yaml, id, "base_path", path)?;
+            }
+        }
+        std::fs::write(file, yaml).with_context(|| format!("Failed to write {}", file.display()))
+    }
+}
+
+/// Set (or with `None`, remove) `probes.<id>.<key>` in config text, keeping comments and
+/// everything else as written
+fn set_probe_option(
+    yaml: &str,
+    id: &str,
+    key: &str,
+    value: Option<serde_yaml::Value>,
+) -> Result<String> {
+    let mut lines: Vec<String> = yaml.lines().map(String::from).collect();
+    let is_top_level =
+        |line: &str| !line.is_empty() && !line.starts_with([' ', '#']) && !line.trim().is_empty();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let content = |line: &str| !line.trim().is_empty() && !line.trim_start().starts_with('#');
+    let rendered = value
+        .map(|v| {
+            serde_yaml::to_string(&v)
+                .map(|s| s.trim_end().to_string())
+                .context("Failed to quote value")
+        })
+        .transpose()?;
+
+    let section = match lines.iter().position(|l| l.trim_end() == "probes:") {
+        Some(i) => i,
+        None => {
+            lines.push("probes:".to_string());
+            lines.len() - 1
+        }
+    };
+    let section_end = (section + 1..lines.len())
+        .find(|&i| is_top_level(&lines[i]))
+        .unwrap_or(lines.len());
+    let header = format!("{}:", id);
+    let entry = (section + 1..section_end)
+        .find(|&i| indent(&lines[i]) == 2 && lines[i].trim_start().starts_with(&header));
+
+    let Some(entry) = entry else {
+        if let Some(value) = rendered {
+            // Append after the section's last entry line, before trailing blank lines
+            let at = (section + 1..section_end)
+                .rev()
+                .find(|&i| content(&lines[i]))
+                .map_or(section + 1, |i| i + 1);
+            lines.insert(at, format!("  {}", header));
+            lines.insert(at + 1, format!("    {}: {}", key, value));
+        }
+        return Ok(join(lines));
+    };
+    let entry_end = (entry + 1..section_end)
+        .find(|&i| content(&lines[i]) && indent(&lines[i]) <= 2)
+        .unwrap_or(section_end);
+    let key_prefix = format!("{}:", key);
+    let existing = (entry + 1..entry_end)
+        .find(|&i| indent(&lines[i]) == 4 && lines[i].trim_start().starts_with(&key_prefix));
+
+    match (existing, rendered) {
+        (Some(i), Some(value)) => {
+            // Keep a trailing comment on the replaced line
+            let comment = lines[i]
+                .find(" #")
+                .map(|at| lines[i][lines[i][..at].trim_end().len()..].to_string())
+                .unwrap_or_default();
+            lines[i] = format!("    {}: {}{}", key, value, comment);
+        }
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(value)) => lines.insert(entry + 1, format!("    {}: {}", key, value)),
+        (None, None) => {}
+    }
+    Ok(join(lines))
+}
+
+fn join(lines: Vec<String>) -> String {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_options_edited_in_place() {
+        let yaml = "\
+database:
+  path: ~/.chronicle/db
+probes:
+  # Claude Code
+  claude:ClaudeCode:
+    enabled: true  # on by default
+    base_path: ~/.claude/projects
+
+  zed:Zed:
+    enabled: true
+display:
+  color: auto
+";
+        let edited =
+            set_probe_option(yaml, "claude:ClaudeCode", "enabled", Some(false.into())).unwrap();
+        let edited = set_probe_option(
+            &edited,
+            "zed:Zed",
+            "base_path",
+            Some("~/zed: threads".into()),
+        )
+        .unwrap();
+        let edited = set_probe_option(&edited, "claude:ClaudeCode", "base_path", None).unwrap();
+        let edited =
+            set_probe_option(&edited, "aider:Aider", "enabled", Some(false.into())).unwrap();
+        assert_eq!(
+            edited,
+            "\
+database:
+  path: ~/.chronicle/db
+probes:
+  # Claude Code
+  claude:ClaudeCode:
+    enabled: false  # on by default
+
+  zed:Zed:
+    base_path: '~/zed: threads'
+    enabled: true
+  aider:Aider:
+    enabled: false
+display:
+  color: auto
+"
+        );
+
+        let config: Config = serde_yaml::from_str(&edited).unwrap();
+        assert!(!config.is_probe_enabled("claude:ClaudeCode"));
+        assert!(!config.is_probe_enabled("aider:Aider"));
+        assert_eq!(
+            config.probes["zed:Zed"].base_path.as_deref(),
+            Some("~/zed: threads")
+        );
+        let fresh = set_probe_option("", "zed:Zed", "enabled", Some(false.into())).unwrap();
+        assert_eq!(fresh, "probes:\n  zed:Zed:\n    enabled: false\n");
+    }
+}
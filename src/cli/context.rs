@@ -0,0 +1,261 @@
+//! `chronicle context` - how close sessions ran to their model's context window
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{short_time, theme, timeparse, truncate, Page};
+use crate::analysis::context::{self, ContextPressure, PRESSURE_RATIO};
+use crate::config::Config;
+use crate::store::{CompactionRow, MetadataStore};
+
+/// Context pressure of one session
+#[derive(Debug, Serialize)]
+struct SessionContext {
+    session_id: String,
+    short_hash: String,
+    source: String,
+    project: Option<String>,
+    title: Option<String>,
+    last_timestamp: Option<String>,
+    #[serde(flatten)]
+    pressure: ContextPressure,
+    /// Compactions the source recorded
+    compactions: i64,
+    /// Recorded compactions, or token drops that look like one
+    likely_compacted: bool,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    config: &Config,
+    since: Option<String>,
+    all: bool,
+    page: Page,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let windows = config.context_windows();
+    let sessions: Vec<SessionContext> = store
+        .context_usage(since.as_deref())?
+        .into_iter()
+        .map(|row| {
+            let pressure = context::pressure(&row.calls, &windows);
+            SessionContext {
+                likely_compacted: row.compactions > 0 || pressure.drops > 0,
+                session_id: row.session_id,
+                short_hash: row.short_hash,
+                source: row.probe_source_id,
+                project: row.project_name,
+                title: row.title,
+                last_timestamp: row.last_timestamp,
+                pressure,
+                compactions: row.compactions,
+            }
+        })
+        .collect();
+
+    let analysed = sessions.len();
+    let pressured = sessions
+        .iter()
+        .filter(|s| s.pressure.under_pressure())
+        .count();
+    let recorded = sessions.iter().filter(|s| s.compactions > 0).count();
+    let detected = sessions.iter().filter(|s| s.pressure.drops > 0).count();
+    let both = sessions
+        .iter()
+        .filter(|s| s.compactions > 0 && s.pressure.drops > 0)
+        .count();
+
+    let shown: Vec<SessionContext> = sessions
+        .into_iter()
+        .filter(|s| all || s.pressure.under_pressure() || s.likely_compacted)
+        .collect();
+    let (rows, total) = page.apply(shown);
+    if json {
+        super::print_json(&rows)?;
+        page.print_footer(rows.len(), total, true);
+        return Ok(());
+    }
+    let untracked = super::sources_without(store, |c| c.supports_token_usage)?;
+    if analysed == 0 {
+        println!("No token usage recorded. Run 'chronicle extract' first.");
+        if !untracked.is_empty() {
+            println!(
+                "These sources don't record token usage: {}",
+                untracked.join(", ")
+            );
+        }
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    if rows.is_empty() {
+        println!(
+            "No session reached {:.0}% of its context window.",
+            PRESSURE_RATIO * 100.0
+        );
+    } else {
+        println!(
+            "{:<10} {:<20} {:<16} {:>9} {:>9} {:>6} {:>8} {:>6}  {:<16}",
+            "Session", "Source", "Project", "Peak", "Window", "Peak%", "Compacts", "Drops", "Last"
+        );
+        println!("{}", theme.rule(110));
+        for s in &rows {
+            let percent = format!("{:.0}%", s.pressure.peak_ratio * 100.0);
+            let percent = match s.pressure.peak_ratio {
+                r if r >= 1.0 => theme.paint("red", &format!("{:>6}", percent)),
+                r if r >= PRESSURE_RATIO => theme.paint("yellow", &format!("{:>6}", percent)),
+                _ => format!("{:>6}", percent),
+            };
+            println!(
+                "{:<10} {} {:<16} {:>9} {:>9} {} {:>8} {:>6}  {:<16}",
+                s.short_hash,
+                theme.source(&s.source, 20),
+                truncate(s.project.as_deref().unwrap_or("-"), 16),
+                s.pressure.peak_tokens,
+                s.pressure.window,
+                percent,
+                s.compactions,
+                s.pressure.drops,
+                short_time(&s.last_timestamp)
+            );
+        }
+        page.print_footer(rows.len(), total, false);
+    }
+
+    println!(
+        "\n{} sessions analysed: {} reached {:.0}% of the context window",
+        analysed,
+        pressured,
+        PRESSURE_RATIO * 100.0
+    );
+    println!(
+        "Compaction: {} sessions recorded by the source, {} detected from token drops ({} both)",
+        recorded, detected, both
+    );
+    if !untracked.is_empty() {
+        println!(
+            "Not analysed: {} (no token usage recorded by the source)",
+            untracked.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// How often the sessions of a project or model were compacted
+#[derive(Debug, Default, Serialize)]
+struct GroupCompactions {
+    group: String,
+    sessions: usize,
+    /// Sessions with at least `--min-messages` messages
+    long_sessions: usize,
+    /// Sessions compacted at least once
+    compacted: usize,
+    /// Long sessions compacted at least once
+    long_compacted: usize,
+    compactions: i64,
+    /// Share of long sessions that were compacted (%)
+    long_compacted_rate: f64,
+    /// Messages per compaction over the compacted sessions
+    messages_per_compaction: Option<f64>,
+}
+
+/// Compaction frequency per project or model
+pub fn by_group(
+    store: &MetadataStore,
+    since: Option<String>,
+    by: &str,
+    min_messages: i64,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let key = |row: &CompactionRow| -> Result<String> {
+        Ok(match by {
+            "project" => row
+                .project_name
+                .clone()
+                .unwrap_or_else(|| "(unassigned)".to_string()),
+            "model" => row.model.clone().unwrap_or_else(|| "(unknown)".to_string()),
+            other => bail!("Unknown grouping: {} (expected project or model)", other),
+        })
+    };
+
+    let mut groups: BTreeMap<String, (GroupCompactions, i64)> = BTreeMap::new();
+    for row in store.compaction_stats(since.as_deref())? {
+        let (group, compacted_messages) = groups.entry(key(&row)?).or_default();
+        group.sessions += 1;
+        if row.message_count >= min_messages {
+            group.long_sessions += 1;
+        }
+        if row.compactions > 0 {
+            group.compacted += 1;
+            if row.message_count >= min_messages {
+                group.long_compacted += 1;
+            }
+            group.compactions += row.compactions;
+            *compacted_messages += row.message_count;
+        }
+    }
+    let mut groups: Vec<GroupCompactions> = groups
+        .into_iter()
+        .map(|(name, (mut group, compacted_messages))| {
+            group.group = name;
+            group.long_compacted_rate = match group.long_sessions {
+                0 => 0.0,
+                long => group.long_compacted as f64 * 100.0 / long as f64,
+            };
+            group.messages_per_compaction = (group.compactions > 0)
+                .then(|| compacted_messages as f64 / group.compactions as f64);
+            group
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse((g.compactions, g.sessions)));
+
+    if json {
+        return super::print_json(&groups);
+    }
+    if groups.is_empty() {
+        println!("No sessions recorded. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<32} {:>8} {:>6} {:>9} {:>11} {:>8} {:>12}",
+        if by == "model" { "Model" } else { "Project" },
+        "Sessions",
+        "Long",
+        "Compacted",
+        "Compactions",
+        "Long %",
+        "Msgs/compact"
+    );
+    println!("{}", theme.rule(92));
+    for g in &groups {
+        println!(
+            "{:<32} {:>8} {:>6} {:>9} {:>11} {:>7.1}% {:>12}",
+            truncate(&g.group, 32),
+            g.sessions,
+            g.long_sessions,
+            g.compacted,
+            g.compactions,
+            g.long_compacted_rate,
+            g.messages_per_compaction
+                .map(|m| format!("{:.0}", m))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    println!(
+        "
+Long sessions have at least {} messages; Long % is the share of them compacted.",
+        min_messages
+    );
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! Costs command implementation
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{truncate, Page};
+use crate::analysis::{Pricing, TokenCounts};
+use crate::config::Config;
+use crate::error::Error;
+use crate::store::{MetadataStore, UsageRow};
+
+/// Estimated cost per session ID (USD); sessions with only unpriced models are omitted
+pub fn session_costs(store: &MetadataStore, pricing: &Pricing) -> Result<HashMap<String, f64>> {
+    let mut costs = HashMap::new();
+    for row in store.token_usage(None)? {
+        if let Some(cost) = pricing.estimate(row.model.as_deref(), &row.tokens) {
+            *costs.entry(row.session_id).or_insert(0.0) += cost;
+        }
+    }
+    Ok(costs)
+}
+
+/// Format a USD estimate for tables
+pub fn format_cost(cost: f64) -> String {
+    if cost > 0.0 && cost < 0.01 {
+        "<$0.01".to_string()
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+/// Estimated costs grouped by model, session, project, probe or period
+pub fn run(
+    store: &MetadataStore,
+    config: &Config,
+    by: &str,
+    since: Option<String>,
+    page: Page,
+) -> Result<()> {
+    let pricing = config.pricing();
+    let since = since
+        .map(|s| super::timeparse::parse(&s))
+        .transpose()?
+        .map(|start| start.to_rfc3339());
+    let rows = store.token_usage(since.as_deref())?;
+    if rows.is_empty() {
+        match since {
+            Some(_) => println!("No token usage recorded in this period."),
+            None => println!("No token usage recorded. Run 'chronicle extract' first."),
+        }
+        let untracked = super::sources_without(store, |c| c.supports_token_usage)?;
+        if !untracked.is_empty() {
+            println!(
+                "These sources don't record token usage: {}",
+                untracked.join(", ")
+            );
+        }
+        return Ok(());
+    }
+
+    let mut groups: BTreeMap<String, (TokenCounts, f64)> = BTreeMap::new();
+    let mut unpriced: BTreeMap<String, i64> = BTreeMap::new();
+    for row in &rows {
+        let entry = groups.entry(group_key(row, by)?).or_default();
+        entry.0.add(&row.tokens);
+        match pricing.estimate(row.model.as_deref(), &row.tokens) {
+            Some(cost) => entry.1 += cost,
+            None => {
+                let model = row.model.clone().unwrap_or_else(|| "(unknown)".to_string());
+                *unpriced.entry(model).or_insert(0) += row.tokens.total();
+            }
+        }
+    }
+
+    // Periods read chronologically; everything else most expensive first
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    if !matches!(by, "day" | "week" | "month") {
+        groups.sort_by(|a, b| b.1 .1.total_cmp(&a.1 .1));
+    }
+
+    println!(
+        "{:<28} {:>12} {:>12} {:>12} {:>12} {:>10}",
+        by_label(by),
+        "Input",
+        "Output",
+        "Cache read",
+        "Cache write",
+        "Est. cost"
+    );
+    println!("{}", "-".repeat(91));
+    // Totals cover every group, not just the page shown
+    let (mut total_tokens, mut total_cost) = (TokenCounts::default(), 0.0);
+    for (tokens, cost) in groups.iter().map(|(_, v)| v) {
+        total_tokens.add(tokens);
+        total_cost += cost;
+    }
+    let (groups, total_groups) = page.apply(groups);
+    for (key, (tokens, cost)) in &groups {
+        println!(
+            "{:<28} {:>12} {:>12} {:>12} {:>12} {:>10}",
+            truncate(key, 28),
+            tokens.input,
+            tokens.output,
+            tokens.cache_read,
+            tokens.cache_creation,
+            format_cost(*cost)
+        );
+    }
+    println!("{}", "-".repeat(91));
+    println!(
+        "{:<28} {:>12} {:>12} {:>12} {:>12} {:>10}",
+        "Total",
+        total_tokens.input,
+        total_tokens.output,
+        total_tokens.cache_read,
+        total_tokens.cache_creation,
+        format_cost(total_cost)
+    );
+    page.print_footer(groups.len(), total_groups, false);
+
+    let untracked = super::sources_without(store, |c| c.supports_token_usage)?;
+    if !untracked.is_empty() {
+        println!(
+            "\nNot included: {} (no token usage recorded by the source)",
+            untracked.join(", ")
+        );
+    }
+    if !unpriced.is_empty() {
+        let models: Vec<String> = unpriced
+            .iter()
+            .map(|(model, tokens)| format!("{} ({} tokens)", model, tokens))
+            .collect();
+        println!(
+            "\n⚠️  No price for: {} (add them under `pricing` in chronicle.yaml)",
+            models.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Per-message cost breakdown of one session
+pub fn session(store: &MetadataStore, config: &Config, query: &str) -> Result<()> {
+    let session = store
+        .get_session(query)?
+        .ok_or_else(|| Error::SessionNotFound(query.to_string()))?;
+    let pricing = config.pricing();
+    let usage = store.message_token_usage(&session.id)?;
+    if usage.is_empty() {
+        let untracked = super::sources_without(store, |c| c.supports_token_usage)?;
+        match untracked.contains(&session.probe_source_id) {
+            true => println!(
+                "Token usage unavailable: {} doesn't record it.",
+                session.probe_source_id
+            ),
+            false => println!(
+                "No token usage recorded for session {}.",
+                session.short_hash
+            ),
+        }
+        return Ok(());
+    }
+
+    println!(
+        "Session {} - {}\n",
+        session.short_hash,
+        session.title.as_deref().unwrap_or("-")
+    );
+    println!(
+        "{:>6} {:<10} {:<28} {:>10} {:>10} {:>10}",
+        "Msg", "Role", "Model", "Input", "Output", "Est. cost"
+    );
+    println!("{}", "-".repeat(79));
+    let mut total = 0.0;
+    for (index, (message, tokens)) in usage.iter().enumerate() {
+        let cost = pricing.estimate(message.model.as_deref(), tokens);
+        total += cost.unwrap_or(0.0);
+        println!(
+            "{:>6} {:<10} {:<28} {:>10} {:>10} {:>10}",
+            index + 1,
+            message.role,
+            truncate(message.model.as_deref().unwrap_or("-"), 28),
+            tokens.input + tokens.cache_read + tokens.cache_creation,
+            tokens.output,
+            cost.map(format_cost).unwrap_or_else(|| "-".to_string())
+        );
+    }
+    println!("\nEstimated total: {}", format_cost(total));
+    Ok(())
+}
+
+fn group_key(row: &UsageRow, by: &str) -> Result<String> {
+    let day = row.day.as_deref();
+    Ok(match by {
+        "model" => row.model.clone().unwrap_or_else(|| "(unknown)".to_string()),
+        "session" => row.short_hash.clone(),
+        "project" => row
+            .project_name
+            .clone()
+            .unwrap_or_else(|| "(unassigned)".to_string()),
+        "probe" => row.probe_source_id.clone(),
+        "day" => day.unwrap_or("(undated)").to_string(),
+        "week" => day
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .map(|d| d.format("%G-W%V").to_string())
+            .unwrap_or_else(|| "(undated)".to_string()),
+        "month" => day
+            .map(|d| d[..7.min(d.len())].to_string())
+            .unwrap_or_else(|| "(undated)".to_string()),
+        other => anyhow::bail!(
+            "Unknown grouping: {} (expected model, session, project, probe, day, week or month)",
+            other
+        ),
+    })
+}
+
+fn by_label(by: &str) -> String {
+    let mut label = by.to_string();
+    if let Some(first) = label.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    label
+}
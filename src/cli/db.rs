@@ -0,0 +1,186 @@
+//! `chronicle db` - maintenance of the metadata database itself
+
+use crate::store::{ConflictPolicy, MetadataStore};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Write the index as a portable archive to a file, or stdout
+pub fn export(store: &MetadataStore, output: Option<PathBuf>) -> Result<()> {
+    let stats = match &output {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut out = BufWriter::new(file);
+            let stats = store.export_archive(&mut out)?;
+            out.flush()?;
+            stats
+        }
+        None => {
+            let stdout = std::io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            let stats = store.export_archive(&mut out)?;
+            out.flush()?;
+            stats
+        }
+    };
+
+    // Keep stdout clean for the archive itself
+    eprintln!(
+        "Exported {} sessions ({} messages) and {} projects{}",
+        stats.sessions,
+        stats.messages,
+        stats.projects,
+        output
+            .map(|p| format!(" to {}", p.display()))
+            .unwrap_or_default()
+    );
+    if stats.redacted > 0 {
+        eprintln!(
+            "Redacted {} session(s) of private projects (usage only: no titles, paths or references)",
+            stats.redacted
+        );
+    }
+    Ok(())
+}
+
+/// Merge an archive written by `db export` into this store
+pub fn import(store: &MetadataStore, path: &Path, on_conflict: &str, json: bool) -> Result<()> {
+    let policy = ConflictPolicy::parse(on_conflict)?;
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let stats = store.import_archive(BufReader::new(file), policy)?;
+
+    if json {
+        return super::print_json(&stats);
+    }
+    println!(
+        "Imported {} sessions ({} messages) from {}",
+        stats.sessions,
+        stats.messages,
+        path.display()
+    );
+    if stats.skipped + stats.replaced + stats.renamed > 0 {
+        println!(
+            "Conflicting session IDs: {} skipped, {} replaced, {} imported under a new ID",
+            stats.skipped, stats.replaced, stats.renamed
+        );
+    }
+    println!(
+        "Projects: {} created, {} matched to existing projects",
+        stats.projects_created, stats.projects_matched
+    );
+    Ok(())
+}
+
+/// Rebuild the search index, then rewrite the file without free pages
+pub fn vacuum(store: &MetadataStore, json: bool) -> Result<()> {
+    let before = store.file_size()?;
+    store.rebuild_search_index()?;
+    store.vacuum()?;
+    let after = store.file_size()?;
+    let reclaimed = before.bytes.saturating_sub(after.bytes);
+
+    if json {
+        return super::print_json(&serde_json::json!({
+            "before": before,
+            "after": after,
+            "reclaimed_bytes": reclaimed,
+        }));
+    }
+    println!("🔎 Rebuilt the search index");
+    println!(
+        "🧹 Vacuumed: {} → {} ({} reclaimed)",
+        human_bytes(before.bytes),
+        human_bytes(after.bytes),
+        human_bytes(reclaimed)
+    );
+    Ok(())
+}
+
+/// Check the database's integrity and report space used per table
+pub fn check(store: &MetadataStore, json: bool) -> Result<()> {
+    let report = store.integrity_check()?;
+    let size = store.file_size()?;
+    let tables = store.table_sizes()?;
+
+    if json {
+        super::print_json(&serde_json::json!({
+            "ok": report.is_ok(),
+            "integrity": report,
+            "size": size,
+            "tables": tables,
+        }))?;
+    } else {
+        match report.problems.is_empty() {
+            true => println!("✅ Integrity check passed"),
+            false => {
+                println!(
+                    "❌ Integrity check found {} problem(s):",
+                    report.problems.len()
+                );
+                for problem in &report.problems {
+                    println!("   {}", problem);
+                }
+            }
+        }
+        match report.search_index_drift {
+            0 => println!("✅ Search index is consistent"),
+            drift => println!(
+                "⚠️  Search index has {} session(s) out of sync; run `chronicle db vacuum` to rebuild it",
+                drift
+            ),
+        }
+
+        println!(
+            "\n{} total, {} in free pages",
+            human_bytes(size.bytes),
+            human_bytes(size.free_bytes)
+        );
+        let names: Vec<String> = tables
+            .iter()
+            .map(|t| match t.name == t.table {
+                true => t.name.clone(),
+                false => format!("  {}", t.name),
+            })
+            .collect();
+        let width = names.iter().map(|n| n.len()).max().unwrap_or(0).max(13);
+        println!(
+            "{:<width$} {:>10} {:>10}",
+            "Table / index",
+            "Size",
+            "Rows",
+            width = width
+        );
+        println!("{}", "-".repeat(width + 22));
+        for (table, name) in tables.iter().zip(names) {
+            println!(
+                "{:<width$} {:>10} {:>10}",
+                name,
+                human_bytes(table.bytes),
+                table.rows.map(|r| r.to_string()).unwrap_or_default(),
+                width = width
+            );
+        }
+    }
+
+    if !report.is_ok() {
+        anyhow::bail!("Database check failed");
+    }
+    Ok(())
+}
+
+/// `1536` -> `1.5 KB`
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
@@ -0,0 +1,172 @@
+//! Dedupe command implementation
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use super::Page;
+use crate::analysis::dedupe::{self, SessionSignature};
+use crate::config::Config;
+use crate::content::ContentLoader;
+use crate::probe::ProbeRegistry;
+use crate::store::{DuplicateRow, MetadataStore};
+
+/// User prompts hashed per session for the content signal
+const PROMPTS_HASHED: usize = 20;
+
+/// Detect cross-source duplicate sessions, record them, and list unresolved pairs
+pub fn run(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    config: &Config,
+    page: Page,
+) -> Result<()> {
+    if !config.deduplication.enabled {
+        println!("Deduplication is disabled (deduplication.enabled in chronicle.yaml).");
+        return Ok(());
+    }
+
+    let loader = ContentLoader::new(registry).with_archive(store);
+    let signatures = store.session_signatures()?;
+    let pairs = dedupe::candidate_pairs(&signatures);
+    let mut hashes: HashMap<usize, Option<String>> = HashMap::new();
+    let mut new = 0;
+    for (i, j) in pairs {
+        for idx in [i, j] {
+            hashes
+                .entry(idx)
+                .or_insert_with(|| prompt_hash(store, &loader, &signatures[idx]));
+        }
+        let content = (hashes[&i].as_deref(), hashes[&j].as_deref());
+        if let Some(duplicate) = dedupe::score(&signatures[i], &signatures[j], content) {
+            if duplicate.confidence >= config.deduplication.confidence_threshold
+                && store.record_duplicate(&duplicate)?
+            {
+                new += 1;
+            }
+        }
+    }
+
+    let (duplicates, total) = page.apply(store.list_duplicates(false)?);
+    if total == 0 {
+        println!("No duplicate sessions found.");
+        return Ok(());
+    }
+    print_duplicates(&duplicates);
+    page.print_footer(duplicates.len(), total, false);
+    println!(
+        "\n{} unresolved ({} new). Resolve with: chronicle dedupe --resolve merge|keep-both [ID...]",
+        total, new
+    );
+    Ok(())
+}
+
+/// Resolve unresolved duplicate pairs (all of them when `ids` is empty). Merging keeps
+/// the session with more messages and hides the other.
+pub fn resolve(store: &MetadataStore, resolution: &str, ids: &[i64]) -> Result<()> {
+    let resolution = match resolution {
+        "merge" => "merged",
+        "keep-both" => "kept_both",
+        other => anyhow::bail!(
+            "Unknown resolution: {} (expected merge or keep-both)",
+            other
+        ),
+    };
+
+    let pending: Vec<DuplicateRow> = store
+        .list_duplicates(false)?
+        .into_iter()
+        .filter(|d| ids.is_empty() || ids.contains(&d.id))
+        .collect();
+    for id in ids {
+        if !pending.iter().any(|d| d.id == *id) {
+            anyhow::bail!("No unresolved duplicate with ID {}", id);
+        }
+    }
+    if pending.is_empty() {
+        println!("No unresolved duplicates. Run 'chronicle dedupe' to detect them.");
+        return Ok(());
+    }
+
+    // Sessions merged away earlier in this run or before
+    let mut merged: HashSet<String> = pending
+        .iter()
+        .flat_map(|d| [&d.a, &d.b])
+        .filter(|s| s.merged_into.is_some())
+        .map(|s| s.session_id.clone())
+        .collect();
+    for duplicate in &pending {
+        store.transaction(|| {
+            if resolution == "merged"
+                && (merged.contains(&duplicate.a.session_id)
+                    || merged.contains(&duplicate.b.session_id))
+            {
+                println!(
+                    "Skipping {} / {}: already merged",
+                    duplicate.a.short_hash, duplicate.b.short_hash
+                );
+            } else if resolution == "merged" {
+                let (keep, drop) = if duplicate.b.message_count > duplicate.a.message_count {
+                    (&duplicate.b, &duplicate.a)
+                } else {
+                    (&duplicate.a, &duplicate.b)
+                };
+                store.merge_session(&keep.session_id, &drop.session_id)?;
+                merged.insert(drop.session_id.clone());
+                println!(
+                    "Merged {} ({}) into {} ({})",
+                    drop.short_hash, drop.source_name, keep.short_hash, keep.source_name
+                );
+            } else {
+                println!(
+                    "Keeping both {} and {}",
+                    duplicate.a.short_hash, duplicate.b.short_hash
+                );
+            }
+            store.resolve_duplicate(duplicate.id, resolution)
+        })?;
+    }
+    Ok(())
+}
+
+fn print_duplicates(duplicates: &[DuplicateRow]) {
+    println!(
+        "{:>4} {:>5} {:<13} {:<24} {:<24} Title",
+        "ID", "Conf", "Method", "Session A", "Session B"
+    );
+    println!("{}", "-".repeat(100));
+    for d in duplicates {
+        let side = |s: &crate::store::DuplicateSide| {
+            format!("{} {} ({})", s.short_hash, s.source_name, s.message_count)
+        };
+        println!(
+            "{:>4} {:>4.0}% {:<13} {:<24} {:<24} {}",
+            d.id,
+            d.confidence * 100.0,
+            d.method,
+            side(&d.a),
+            side(&d.b),
+            d.a.title
+                .as_deref()
+                .or(d.b.title.as_deref())
+                .map(|t| t.lines().next().unwrap_or(t))
+                .unwrap_or("-")
+        );
+    }
+}
+
+/// Hash of the first user prompts, loaded through the session's probe
+fn prompt_hash(
+    store: &MetadataStore,
+    loader: &ContentLoader,
+    signature: &SessionSignature,
+) -> Option<String> {
+    let session = store.get_session(&signature.session_id).ok()??;
+    let messages = store.get_messages(&signature.session_id).ok()?;
+    let prompts: Vec<String> = messages
+        .iter()
+        .filter(|m| m.role == "user")
+        .take(PROMPTS_HASHED)
+        .filter_map(|m| loader.load_text(&session, m).ok())
+        .collect();
+    dedupe::prompt_hash(&prompts)
+}
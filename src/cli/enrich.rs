@@ -0,0 +1,200 @@
+//! Enrich command implementation
+//!
+//! Works the enrichment queue through `enrichment.command`, one session per
+//! request, pacing requests to `requests_per_minute` and stopping before the
+//! run's estimated cost passes `max_cost`. Progress lives in the queue table,
+//! so an interrupted run resumes where it stopped.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::truncate;
+use crate::analysis::enrich::{self, OUTPUT_ALLOWANCE};
+use crate::analysis::TokenCounts;
+use crate::config::Config;
+use crate::content::ContentLoader;
+use crate::probe::ProbeRegistry;
+use crate::store::{EnrichmentJob, MetadataStore};
+
+/// Queue sessions for `kind` and work up to `budget` jobs
+pub fn run(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    config: &Config,
+    kind: &str,
+    budget: usize,
+) -> Result<()> {
+    let kind = enrich::kind(kind)?;
+    let settings = &config.enrichment;
+    let Some(command) = settings.command.as_deref() else {
+        anyhow::bail!(
+            "No enrichment command configured. Set enrichment.command in chronicle.yaml \
+             (a command reading the prompt on stdin, e.g. `llm -m claude-3-5-haiku`)."
+        );
+    };
+
+    let requeued = store.requeue_running_enrichment()?;
+    let queued = store.enqueue_enrichment(kind)?;
+    if queued + requeued > 0 {
+        println!(
+            "Queued {} new {} job(s){}",
+            queued,
+            kind,
+            match requeued {
+                0 => String::new(),
+                n => format!(", resumed {} interrupted", n),
+            }
+        );
+    }
+
+    let jobs = store.next_enrichment_jobs(kind, settings.max_attempts, Some(budget))?;
+    if jobs.is_empty() {
+        println!("Nothing to enrich.");
+        return Ok(());
+    }
+
+    let pricing = config.pricing();
+    let model = settings.model.as_deref();
+    let interval = Duration::from_secs(60) / settings.requests_per_minute.max(1);
+    let loader = ContentLoader::new(registry).with_archive(store);
+    let (mut done, mut failed, mut spent) = (0, 0, 0.0);
+    let mut last_request: Option<Instant> = None;
+
+    for job in &jobs {
+        let (short, prompt) = match job_prompt(store, &loader, job, settings.max_prompt_chars) {
+            Ok(found) => found,
+            Err(e) => {
+                store.finish_enrichment(job.id, Err(&format!("{:#}", e)), None)?;
+                failed += 1;
+                continue;
+            }
+        };
+
+        let input = enrich::estimate_tokens(&prompt);
+        let expected = pricing.estimate(model, &tokens(input, OUTPUT_ALLOWANCE));
+        if let (Some(cap), Some(expected)) = (settings.max_cost, expected) {
+            if spent + expected > cap {
+                println!("Stopping: the next job would exceed max_cost (${:.2})", cap);
+                break;
+            }
+        }
+
+        if let Some(last) = last_request {
+            std::thread::sleep(interval.saturating_sub(last.elapsed()));
+        }
+        last_request = Some(Instant::now());
+
+        store.start_enrichment(job.id)?;
+        match complete(command, &prompt) {
+            Ok(output) => match enrich::parse_output(kind, &output) {
+                Some(result) => {
+                    let output_tokens = enrich::estimate_tokens(&output);
+                    let cost = pricing.estimate(model, &tokens(input, output_tokens));
+                    spent += cost.unwrap_or(0.0);
+                    store.finish_enrichment(job.id, Ok(&result), cost)?;
+                    println!("   → {} {}", short, truncate(&result, 70));
+                    done += 1;
+                }
+                None => {
+                    store.finish_enrichment(job.id, Err("empty response"), None)?;
+                    println!("   ✗ {} empty response", short);
+                    failed += 1;
+                }
+            },
+            Err(e) => {
+                store.finish_enrichment(job.id, Err(&format!("{:#}", e)), None)?;
+                println!("   ✗ {} {:#}", short, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let remaining = store
+        .next_enrichment_jobs(kind, settings.max_attempts, None)?
+        .len();
+    println!(
+        "\n{} done, {} failed, {} remaining | est. cost {}",
+        done,
+        failed,
+        remaining,
+        super::costs::format_cost(spent)
+    );
+    Ok(())
+}
+
+/// Job counts per kind and status
+pub fn status(store: &MetadataStore) -> Result<()> {
+    let rows = store.enrichment_status()?;
+    if rows.is_empty() {
+        println!("The enrichment queue is empty. Run 'chronicle enrich' to fill it.");
+        return Ok(());
+    }
+    println!("{:<10} {:<10} {:>8}", "Kind", "Status", "Jobs");
+    println!("{}", "-".repeat(30));
+    for (kind, status, count) in rows {
+        println!("{:<10} {:<10} {:>8}", kind, status, count);
+    }
+    Ok(())
+}
+
+/// Short hash and prompt for a job from its session's user and assistant text
+fn job_prompt(
+    store: &MetadataStore,
+    loader: &ContentLoader,
+    job: &EnrichmentJob,
+    max_chars: usize,
+) -> Result<(String, String)> {
+    let session = store
+        .get_session(&job.session_id)?
+        .ok_or_else(|| anyhow::anyhow!("session no longer indexed"))?;
+    let mut turns = vec![];
+    for msg in store.get_messages(&session.id)? {
+        if msg.role == "user" || msg.role == "assistant" {
+            if let Ok(text) = loader.load_text(&session, &msg) {
+                turns.push((msg.role.clone(), text));
+            }
+        }
+    }
+    if turns.is_empty() {
+        anyhow::bail!("no readable content");
+    }
+    Ok((
+        session.short_hash,
+        enrich::prompt(&job.kind, &turns, max_chars),
+    ))
+}
+
+/// Run the completion command with the prompt on stdin
+fn complete(command: &str, prompt: &str) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run enrichment command: {}", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(prompt.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "command exited with {}: {}",
+            output.status,
+            stderr.lines().next().unwrap_or("").trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn tokens(input: i64, output: i64) -> TokenCounts {
+    TokenCounts {
+        input,
+        output,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,203 @@
+//! Export command implementation
+//!
+//! Without a session, exports Anki flashcards or a decision log across sessions; with
+//! one, renders it
+//! through any registered [`Exporter`](crate::export::Exporter) (content lazy-loaded
+//! via the probe).
+//!
+//! Exports can be published anywhere, so only shareable projects are included:
+//! sessions of private and internal projects are left out or refused.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::content::ContentLoader;
+use crate::error::Error;
+use crate::export::{self, ExporterRegistry};
+use crate::probe::ProbeRegistry;
+use crate::store::{Audience, MetadataStore, SessionRow};
+
+pub fn run(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    config: &Config,
+    format: &str,
+    filter: Option<String>,
+    project: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut sessions = store.list_sessions(None, None)?;
+    if let Some(ref query) = project {
+        match store.find_project(query)? {
+            Some(project) => {
+                super::ensure_visible(&project, Audience::Public)?;
+                sessions.retain(|s| s.project_id.as_deref() == Some(project.id.as_str()))
+            }
+            None => {
+                let vp = config
+                    .virtual_project(query)
+                    .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?;
+                sessions.retain(|s| vp.matches(s));
+            }
+        }
+    }
+
+    if format != "anki" && format != "decisions" {
+        match ExporterRegistry::new().get(format) {
+            Some(_) => anyhow::bail!(
+                "The {} format exports one session: pass a session ID",
+                format
+            ),
+            None => anyhow::bail!(
+                "Unsupported export format: {} (expected: anki or decisions)",
+                format
+            ),
+        }
+    }
+
+    let withheld = super::withhold(store, &mut sessions, Audience::Public)?;
+    if withheld > 0 {
+        eprintln!(
+            "Left out {} session(s) from private or internal projects",
+            withheld
+        );
+    }
+
+    let content = ContentLoader::new(registry).with_archive(store);
+    let filter = filter.map(|f| f.to_lowercase());
+    if format == "decisions" {
+        return decisions(store, &content, sessions, project, filter, output);
+    }
+    let mut cards = vec![];
+    for session in &sessions {
+        let messages = store.get_messages(&session.id)?;
+        cards.extend(export::session_cards(
+            session,
+            &messages,
+            &content,
+            filter.as_deref(),
+        ));
+    }
+    let text = export::anki_csv(&cards);
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Exported {} card(s) to {}", cards.len(), path.display());
+        }
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
+
+/// Decision log across sessions, grouped by project; `filter` (lowercase) keeps only
+/// decisions mentioning it
+fn decisions(
+    store: &MetadataStore,
+    content: &ContentLoader,
+    mut sessions: Vec<SessionRow>,
+    project: Option<String>,
+    filter: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    sessions.sort_by(|a, b| {
+        (
+            a.project_name.is_none(),
+            &a.project_name,
+            &a.first_timestamp,
+        )
+            .cmp(&(
+                b.project_name.is_none(),
+                &b.project_name,
+                &b.first_timestamp,
+            ))
+    });
+
+    let mut log = vec![];
+    for session in &sessions {
+        let messages = store.get_messages(&session.id)?;
+        let mut decisions = export::session_decisions(session, &messages, content);
+        if let Some(ref filter) = filter {
+            decisions.retain(|d| {
+                d.text.to_lowercase().contains(filter)
+                    || d.confirmation
+                        .as_ref()
+                        .is_some_and(|c| c.to_lowercase().contains(filter))
+            });
+        }
+        if !decisions.is_empty() {
+            log.push((session, decisions));
+        }
+    }
+    let title = match project {
+        Some(project) => format!("Decision log: {}", project),
+        None => "Decision log".to_string(),
+    };
+    let text = export::decision_log(&title, &log)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Exported {} decision(s) from {} session(s) to {}",
+                log.iter().map(|(_, d)| d.len()).sum::<usize>(),
+                log.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Export a single session with the named exporter
+pub fn session(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    exporters: &ExporterRegistry,
+    query: &str,
+    format: &str,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let session = store
+        .get_session(query)?
+        .ok_or_else(|| Error::SessionNotFound(query.to_string()))?;
+    if let Some(ref project_id) = session.project_id {
+        if let Some(project) = store.find_project(project_id)? {
+            super::ensure_visible(&project, Audience::Public)?;
+        }
+    }
+    let exporter = exporters.get(format).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unsupported export format: {} (expected: {})",
+            format,
+            exporters.names().join(", ")
+        )
+    })?;
+
+    let messages = store.get_messages(&session.id)?;
+    let text = exporter.render(
+        &session,
+        &messages,
+        &ContentLoader::new(registry).with_archive(store),
+    )?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Exported session {} to {}",
+                session.short_hash,
+                path.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
@@ -1,77 +1,306 @@
 //! Extract command implementation
 
 use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::time::Instant;
 
+use crate::config::Config;
+use crate::extract::{self, ExtractEvent};
 use crate::probe::ProbeRegistry;
-use crate::store::MetadataStore;
+use crate::store::StorageBackend;
 
-pub fn run(store: &MetadataStore, registry: &ProbeRegistry) -> Result<()> {
-    println!("Discovering available probes...\n");
+pub use crate::extract::{has_changes, ExtractOptions};
 
-    let available = registry.available_probes();
+/// How much `extract` prints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Warnings and errors only
+    Quiet,
+    /// Probe summaries, with a progress line while sessions are extracted
+    #[default]
+    Normal,
+    /// A line for every extracted session
+    Verbose,
+}
+
+/// Extract sessions from all available probes, printing progress, then check for a usage
+/// spike
+pub fn run(
+    store: &dyn StorageBackend,
+    registry: &ProbeRegistry,
+    config: &Config,
+    options: &ExtractOptions,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let mut printer = Printer::new(verbosity, options.strict);
+    printer.say(format_args!("Discovering available probes...\n"));
+    let result = extract::run(store, registry, config, options, &mut |event| {
+        printer.event(event)
+    });
+    printer.finish_probe();
+    let summary = result?;
+    super::alerts::check_usage_spike(store, config)?;
+    super::alerts::check_budgets(store, config)?;
+
+    if !summary.failures.is_empty() {
+        eprintln!(
+            "\n⚠️  {} session(s) could not be extracted and will be retried next run:",
+            summary.failures.len()
+        );
+        for failure in &summary.failures {
+            eprintln!(
+                "   {} {} ({})\n      {}",
+                failure.probe,
+                failure.session_id,
+                failure.source_path.display(),
+                failure.error
+            );
+        }
+        eprintln!("   Use --strict to stop at the first failure.");
+    }
+    Ok(())
+}
+
+/// Narrow `options` to one session for `--session`: an indexed session (short hash or
+/// ID, as elsewhere) selects its probe and source ID; anything else is taken as the ID in
+/// the source, looked up in `--probe` or every probe
+pub fn select_session(
+    store: &dyn StorageBackend,
+    query: &str,
+    options: &mut ExtractOptions,
+) -> Result<()> {
+    match store.session_origin(query)? {
+        Some((probe_source_id, external_id)) => {
+            if let Some(ref probe) = options.probe {
+                if *probe != probe_source_id {
+                    anyhow::bail!(
+                        "Session {} was indexed by {}, not {}",
+                        query,
+                        probe_source_id,
+                        probe
+                    );
+                }
+            }
+            options.probe = Some(probe_source_id);
+            options.session = Some(external_id);
+        }
+        None => options.session = Some(query.to_string()),
+    }
+    Ok(())
+}
 
-    if available.is_empty() {
-        println!("No probes available. Check your configuration.");
-        return Ok(());
+/// Sessions of the current probe extracted so far
+struct Progress {
+    bar: ProgressBar,
+    messages: usize,
+    started: Instant,
+}
+
+impl Progress {
+    /// `   [██████░░░░] 120/3000 sessions · 350 msgs/s`
+    const TEMPLATE: &'static str = "   [{bar:24}] {pos}/{len} sessions · {msg}";
+
+    fn new(total: usize, live: bool) -> Self {
+        let bar = if live {
+            ProgressBar::new(total as u64).with_style(
+                ProgressStyle::with_template(Self::TEMPLATE)
+                    .expect("valid progress template")
+                    .progress_chars("█░"),
+            )
+        } else {
+            ProgressBar::hidden()
+        };
+        bar.set_length(total as u64);
+        Self {
+            bar,
+            messages: 0,
+            started: Instant::now(),
+        }
     }
 
-    for probe in available {
-        println!("📡 {} ({})", probe.id(), probe.description());
+    fn rate(&self) -> f64 {
+        let secs = self.started.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            self.messages as f64 / secs
+        } else {
+            0.0
+        }
+    }
 
-        // Ensure provider exists (for multi-provider sources, we'll store specific ones at message level)
-        if probe.source_type() == crate::probe::SourceType::Single {
-            store.ensure_provider(probe.provider(), probe.provider(), None)?;
+    /// Count a finished session and its messages
+    fn advance(&mut self, messages: usize) {
+        self.messages += messages;
+        self.bar.set_message(format!("{:.0} msgs/s", self.rate()));
+        self.bar.inc(1);
+    }
+}
+
+/// Renders extraction events at the chosen verbosity. The progress bar goes to stderr
+/// and only when it's a terminal, so redirected output stays line-based.
+struct Printer {
+    verbosity: Verbosity,
+    strict: bool,
+    live: bool,
+    progress: Option<Progress>,
+}
+
+impl Printer {
+    fn new(verbosity: Verbosity, strict: bool) -> Self {
+        Self {
+            verbosity,
+            strict,
+            live: verbosity == Verbosity::Normal && std::io::stderr().is_terminal(),
+            progress: None,
         }
+    }
 
-        // Ensure probe source exists
-        store.ensure_probe_source(
-            probe.id(),
-            if probe.source_type() == crate::probe::SourceType::Single {
-                Some(probe.provider())
-            } else {
-                None
-            },
-            probe.source(),
-            probe.source_type(),
-            None, // base_path not tracked in DB yet
-            "active",
-        )?;
-
-        // Discover sessions
-        let sessions = probe.discover()?;
-        println!("   Found {} sessions", sessions.len());
-
-        for session in &sessions {
-            print!("   → {} ", &session.id[..8.min(session.id.len())]);
-
-            // Extract metadata
-            let metadata = probe.extract_metadata(session)?;
-
-            // Store session
-            let session_id = store.upsert_session(probe.id(), session, &metadata)?;
-
-            // Store messages
-            if !metadata.messages.is_empty() {
-                store.insert_messages(&session_id, &metadata.messages)?;
-                print!("({} msgs) ", metadata.messages.len());
+    /// Run `f` with the progress bar cleared, redrawing it afterwards
+    fn suspend(&self, f: impl FnOnce()) {
+        match self.progress {
+            Some(ref progress) => progress.bar.suspend(f),
+            None => f(),
+        }
+    }
+
+    /// Print a line unless quiet
+    fn say(&mut self, line: std::fmt::Arguments) {
+        if self.verbosity != Verbosity::Quiet {
+            self.suspend(|| println!("{}", line));
+        }
+    }
+
+    /// Print a warning at every verbosity
+    fn warn(&mut self, line: std::fmt::Arguments) {
+        self.suspend(|| eprintln!("{}", line));
+    }
+
+    /// Replace the probe's progress bar with its totals
+    fn finish_probe(&mut self) {
+        if let Some(progress) = self.progress.take() {
+            progress.bar.finish_and_clear();
+            if progress.bar.position() > 0 && self.verbosity != Verbosity::Quiet {
+                println!(
+                    "   Extracted {} sessions ({} msgs) in {:.1}s, {:.0} msgs/s",
+                    progress.bar.position(),
+                    progress.messages,
+                    progress.started.elapsed().as_secs_f64(),
+                    progress.rate()
+                );
             }
+        }
+    }
 
-            if let Some(ref title) = metadata.title {
-                let display_title = if title.len() > 30 {
-                    format!("{}...", &title[..27])
-                } else {
-                    title.clone()
+    fn event(&mut self, event: ExtractEvent) {
+        match event {
+            ExtractEvent::NoProbes => self.say(format_args!(
+                "No probes available. Check your configuration."
+            )),
+            ExtractEvent::GeneralProjectCreated { name } => {
+                self.say(format_args!("📥 Created general project '{}'\n", name))
+            }
+            ExtractEvent::ProbeStarted { probe } => {
+                self.say(format_args!("📡 {} ({})", probe.id(), probe.description()))
+            }
+            ExtractEvent::SessionsFound { count, changed } => {
+                self.say(format_args!("   Found {} sessions", count));
+                self.progress = Some(Progress::new(changed, self.live));
+            }
+            ExtractEvent::SessionStored(stored) => {
+                if let Some(mapped) = stored.missing_mapping {
+                    let id = &stored.session.id;
+                    self.warn(format_args!(
+                        "   ⚠️  {}: mapped project '{}' not found",
+                        &id[..8.min(id.len())],
+                        mapped
+                    ));
+                }
+                if self.verbosity == Verbosity::Verbose {
+                    print_session(&stored);
+                }
+                if let Some(progress) = self.progress.as_mut() {
+                    progress.advance(stored.metadata.messages.len());
+                }
+            }
+            ExtractEvent::SessionFailed { session, error } => {
+                let id = &session.id;
+                self.say(format_args!(
+                    "   ✗ {} failed: {}",
+                    &id[..8.min(id.len())],
+                    error
+                ));
+                if let Some(progress) = self.progress.as_mut() {
+                    progress.advance(0);
+                }
+            }
+            ExtractEvent::SessionsUnchanged { count } => {
+                self.finish_probe();
+                self.say(format_args!("   Skipped {} unchanged sessions", count));
+            }
+            ExtractEvent::EntriesDropped { skipped } => {
+                self.finish_probe();
+                if self.strict {
+                    for record in skipped.records() {
+                        self.say(format_args!("   ✗ {}: {}", record.location, record.reason));
+                    }
+                }
+                let reasons: Vec<String> = skipped
+                    .iter()
+                    .map(|(reason, count)| format!("{} × {}", count, reason))
+                    .collect();
+                self.say(format_args!(
+                    "   ⚠️  Dropped {} unparseable entries: {}",
+                    skipped.total(),
+                    reasons.join(", ")
+                ));
+            }
+            ExtractEvent::ProbeFinished => {
+                self.finish_probe();
+                self.say(format_args!(""));
+            }
+            ExtractEvent::ProjectSettingsIndexed { count } => self.say(format_args!(
+                "🔐 Indexed tool settings for {} project(s)\n",
+                count
+            )),
+            ExtractEvent::Complete => self.say(format_args!("✅ Extraction complete!")),
+            ExtractEvent::StrictSummary { unparsed, records } => {
+                let rate = match records {
+                    0 => 0.0,
+                    records => unparsed as f64 / records as f64,
                 };
-                print!("- {}", display_title);
+                self.say(format_args!(
+                    "\n🔎 Strict: {} of {} records unparsed ({:.2}%), details in parse_errors",
+                    unparsed,
+                    records,
+                    rate * 100.0
+                ));
             }
-
-            println!();
+            ExtractEvent::HookFailed { message } => self.warn(format_args!("   ⚠️  {}", message)),
         }
-
-        store.update_probe_indexed(probe.id())?;
-        println!();
     }
+}
 
-    println!("✅ Extraction complete!");
-    Ok(())
+/// `   → abcdef12 [new project 'app'] (12 msgs) - Fix the parser`
+fn print_session(stored: &extract::StoredSession) {
+    let id = &stored.session.id;
+    print!("   → {} ", &id[..8.min(id.len())]);
+    if let Some(name) = stored.created_project {
+        print!("[new project '{}'] ", name);
+    }
+    if stored.tool_loops > 0 {
+        print!("⚠️ ");
+    }
+    let metadata = stored.metadata;
+    if !metadata.messages.is_empty() {
+        print!("({} msgs) ", metadata.messages.len());
+    }
+    if let Some(ref title) = metadata.title {
+        let display_title = if title.chars().count() > 30 {
+            format!("{}...", title.chars().take(27).collect::<String>())
+        } else {
+            title.clone()
+        };
+        print!("- {}", display_title);
+    }
+    println!();
 }
@@ -0,0 +1,126 @@
+//! `chronicle files` - the files AI tools edit most, per project
+//!
+//! Paths from tool calls are made relative to the project root (see
+//! [`crate::analysis::files`]), so a file counts once however many sessions,
+//! worktrees or machines touched it; paths outside the project are skipped.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+use super::{short_time, theme, Page};
+use crate::analysis::files::{is_edit_tool, workspace_path};
+use crate::error::Error;
+use crate::store::MetadataStore;
+
+/// Calls touching one file of a project
+#[derive(Debug, Default, Serialize)]
+struct FileUsage {
+    project: Option<String>,
+    path: String,
+    edits: usize,
+    reads: usize,
+    sessions: usize,
+    last_touched: Option<String>,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    project: Option<String>,
+    all: bool,
+    page: Page,
+    json: bool,
+) -> Result<()> {
+    let project_id = match project.as_deref() {
+        Some(query) => Some(
+            store
+                .find_project(query)?
+                .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?
+                .id,
+        ),
+        None => None,
+    };
+
+    let mut roots: HashMap<String, Vec<String>> = HashMap::new();
+    let mut usage: HashMap<(Option<String>, String), (FileUsage, BTreeSet<String>)> =
+        HashMap::new();
+    for touch in store.file_touches(project_id.as_deref())? {
+        let project_roots = match &touch.project_id {
+            Some(id) => match roots.get(id) {
+                Some(paths) => paths,
+                None => roots
+                    .entry(id.clone())
+                    .or_insert(store.get_project_paths(id)?),
+            },
+            None => &vec![],
+        };
+        let Some(path) = workspace_path(&touch.file_path, touch.cwd.as_deref(), project_roots)
+        else {
+            continue;
+        };
+        let (file, sessions) = usage
+            .entry((touch.project_name.clone(), path.clone()))
+            .or_insert_with(|| {
+                let file = FileUsage {
+                    project: touch.project_name.clone(),
+                    path,
+                    ..Default::default()
+                };
+                (file, BTreeSet::new())
+            });
+        match is_edit_tool(&touch.tool_name) {
+            true => file.edits += 1,
+            false => file.reads += 1,
+        }
+        sessions.insert(touch.session_id);
+        file.last_touched = file.last_touched.take().max(touch.timestamp);
+    }
+
+    let mut files: Vec<FileUsage> = usage
+        .into_values()
+        .map(|(mut file, sessions)| {
+            file.sessions = sessions.len();
+            file
+        })
+        .filter(|f| all || f.edits > 0)
+        .collect();
+    files.sort_by(|a, b| {
+        (b.edits, b.sessions, b.reads)
+            .cmp(&(a.edits, a.sessions, a.reads))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let (files, total) = page.apply(files);
+    if json {
+        super::print_json(&files)?;
+        page.print_footer(files.len(), total, true);
+        return Ok(());
+    }
+    if total == 0 {
+        println!(
+            "No edited files recorded. File paths are captured during extraction; run \
+             'chronicle extract --full' to record them for older sessions."
+        );
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<16} {:<48} {:>6} {:>6} {:>9}  {:<16}",
+        "Project", "File", "Edits", "Reads", "Sessions", "Last touched"
+    );
+    println!("{}", theme.rule(108));
+    for f in &files {
+        println!(
+            "{:<16} {:<48} {:>6} {:>6} {:>9}  {:<16}",
+            f.project.as_deref().unwrap_or("-"),
+            f.path,
+            f.edits,
+            f.reads,
+            f.sessions,
+            short_time(&f.last_touched)
+        );
+    }
+    page.print_footer(files.len(), total, false);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+//! Issue reference lookup command implementation
+
+use anyhow::Result;
+
+use super::list::print_sessions;
+use super::Page;
+use crate::analysis::references;
+use crate::store::MetadataStore;
+
+pub fn sessions_for_issue(store: &MetadataStore, query: &str, page: Page) -> Result<()> {
+    let key = references::normalize_key(query);
+    let (sessions, total) = page.apply(store.find_sessions_by_reference(&key)?);
+
+    if total == 0 {
+        println!("No sessions reference '{}'.", key);
+        return Ok(());
+    }
+
+    println!("Sessions referencing {}:\n", key);
+    print_sessions(&sessions);
+    page.print_footer(sessions.len(), total, false);
+    Ok(())
+}
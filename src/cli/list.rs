@@ -1,22 +1,181 @@
 //! List command implementation
 
 use anyhow::Result;
+use std::collections::{HashMap, HashSet};
 
-use crate::store::MetadataStore;
+use super::{theme, Page};
+use crate::analysis::{language, Pricing};
+use crate::error::Error;
+use crate::store::{MetadataStore, SessionOrder, SessionQuery, SessionRow};
 
-pub fn run(store: &MetadataStore, provider: Option<String>, source: Option<String>) -> Result<()> {
-    let sessions = store.list_sessions(provider.as_deref(), source.as_deref())?;
+/// Which sessions `list` shows
+#[derive(Debug, Default)]
+pub struct ListFilter {
+    pub provider: Option<String>,
+    pub source: Option<String>,
+    /// `code` or `general`
+    pub kind: Option<String>,
+    /// ISO 639-1 language code
+    pub lang: Option<String>,
+    /// Time expressions (see [`super::timeparse`]) bounding the last activity
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Project ID or name
+    pub project: Option<String>,
+    /// Only sessions without a project
+    pub unassigned: bool,
+    /// Include archived sessions
+    pub all: bool,
+    pub sort: SessionOrder,
+}
+
+/// Sessions matching `filter`, with how many archived ones it hid
+fn filtered_sessions(
+    store: &MetadataStore,
+    filter: &ListFilter,
+) -> Result<(Vec<SessionRow>, usize)> {
+    let bound = |expr: &Option<String>| -> Result<Option<String>> {
+        expr.as_deref()
+            .map(|e| super::timeparse::parse(e).map(|t| t.to_rfc3339()))
+            .transpose()
+    };
+    let project_id = match filter.project.as_deref() {
+        Some(query) => Some(
+            store
+                .find_project(query)?
+                .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?
+                .id,
+        ),
+        None => None,
+    };
+    let query = SessionQuery {
+        provider: filter.provider.clone(),
+        source: filter.source.clone(),
+        since: bound(&filter.since)?,
+        until: bound(&filter.until)?,
+        project_id,
+        unassigned: filter.unassigned,
+        // Counted below so the hint can say how many were hidden
+        archived: true,
+        order: filter.sort,
+        ..Default::default()
+    };
+    let mut sessions = store.query_sessions(&query)?;
+    match filter.kind.as_deref() {
+        Some("general") => sessions.retain(|s| s.is_general()),
+        Some("code") => sessions.retain(|s| !s.is_general()),
+        Some(other) => anyhow::bail!("Unknown session kind: {} (expected code or general)", other),
+        None => {}
+    }
+    if let Some(lang) = filter.lang.as_deref() {
+        sessions.retain(|s| s.language.as_deref() == Some(lang));
+    }
 
-    if sessions.is_empty() {
-        println!("No sessions found. Run 'chronicle extract' first.");
+    let archived = match filter.all {
+        true => 0,
+        false => {
+            let before = sessions.len();
+            sessions.retain(|s| !s.archived);
+            before - sessions.len()
+        }
+    };
+    Ok((sessions, archived))
+}
+
+pub fn run(
+    store: &MetadataStore,
+    filter: ListFilter,
+    pricing: Option<&Pricing>,
+    json: bool,
+    page: Page,
+) -> Result<()> {
+    let (sessions, archived) = filtered_sessions(store, &filter)?;
+    let general = sessions.iter().filter(|s| s.is_general()).count();
+    let (sessions, total) = page.apply(sessions);
+    if json {
+        print_json(store, &sessions, pricing)?;
+        page.print_footer(sessions.len(), total, true);
         return Ok(());
     }
 
+    if total == 0 {
+        match archived {
+            0 => println!("No sessions found. Run 'chronicle extract' first."),
+            n => println!("No sessions found ({} archived; --all to include).", n),
+        }
+        return Ok(());
+    }
+
+    // Sorting by time shows the times sorted by
+    let times = matches!(filter.sort, SessionOrder::Duration | SessionOrder::Active);
+    match pricing {
+        Some(pricing) => {
+            let costs = super::costs::session_costs(store, pricing)?;
+            print_session_table(&sessions, Some(&costs), times);
+            let total: f64 = sessions.iter().filter_map(|s| costs.get(&s.id)).sum();
+            println!("\nEstimated total: {}", super::costs::format_cost(total));
+        }
+        None => print_session_table(&sessions, None, times),
+    }
+    page.print_footer(sessions.len(), total, false);
+    if filter.unassigned {
+        println!("\nAssign with: chronicle session assign <session> <project>");
+    }
+    if filter.kind.is_none() {
+        println!(
+            "\n{} coding · {} general (filter with --kind code|general)",
+            total - general,
+            general
+        );
+    }
+    if archived > 0 {
+        println!("{} archived session(s) hidden (--all to include)", archived);
+    }
+    Ok(())
+}
+
+/// Sessions as a JSON array, each with `estimated_cost` when pricing is given
+fn print_json(
+    store: &MetadataStore,
+    sessions: &[SessionRow],
+    pricing: Option<&Pricing>,
+) -> Result<()> {
+    let Some(pricing) = pricing else {
+        return super::print_json(sessions);
+    };
+    let costs = super::costs::session_costs(store, pricing)?;
+    let mut rows = vec![];
+    for session in sessions {
+        let mut row = serde_json::to_value(session)?;
+        row["estimated_cost"] = serde_json::json!(costs.get(&session.id));
+        rows.push(row);
+    }
+    super::print_json(&rows)
+}
+
+/// Print sessions as the standard list table
+pub fn print_sessions(sessions: &[SessionRow]) {
+    print_session_table(sessions, None, false);
+}
+
+/// List table, with an estimated cost column when `costs` (by session ID) is given and
+/// duration and active time columns with `times`
+fn print_session_table(sessions: &[SessionRow], costs: Option<&HashMap<String, f64>>, times: bool) {
+    let cost_header = if costs.is_some() {
+        format!("{:>9} ", "Cost")
+    } else {
+        String::new()
+    };
+    let times_header = match times {
+        true => format!("{:>8} {:>8} ", "Duration", "Active"),
+        false => String::new(),
+    };
     println!(
-        "{:<12} {:<10} {:<12} {:<12} {:<15} Title",
-        "Timestamp", "ID", "Project", "Provider", "Source"
+        "{:<12} {:<10} {:<12} {:<12} {:<15} {}{}Title",
+        "Timestamp", "ID", "Project", "Provider", "Source", times_header, cost_header
     );
-    println!("{}", "-".repeat(100));
+    let theme = theme::current();
+    println!("{}", theme.rule(100));
 
     for session in sessions {
         // Format timestamp
@@ -35,30 +194,113 @@ pub fn run(store: &MetadataStore, provider: Option<String>, source: Option<Strin
         // Project name
         let project = session.project_name.as_deref().unwrap_or("-");
 
-        // Truncate title
+        // Truncate title; CJK titles take two columns per character
+        let width = match session.language.as_deref().is_some_and(language::is_wide) {
+            true => 17,
+            false => 35,
+        };
         let title = session
             .title
             .as_ref()
             .map(|t| {
                 let t = t.lines().next().unwrap_or(t);
-                if t.len() > 35 {
-                    format!("{}...", &t[..32])
+                if t.chars().count() > width {
+                    format!("{}...", t.chars().take(width - 3).collect::<String>())
                 } else {
                     t.to_string()
                 }
             })
             .unwrap_or_else(|| "-".to_string());
 
+        let cost = match costs {
+            Some(costs) => format!(
+                "{:>9} ",
+                costs
+                    .get(&session.id)
+                    .map(|c| super::costs::format_cost(*c))
+                    .unwrap_or_else(|| "-".to_string())
+            ),
+            None => String::new(),
+        };
+        let times = match times {
+            true => {
+                let time = |secs: Option<i64>| secs.map_or_else(|| "-".to_string(), super::elapsed);
+                format!(
+                    "{:>8} {:>8} ",
+                    time(session.metrics.duration_secs),
+                    time(session.metrics.active_secs)
+                )
+            }
+            false => String::new(),
+        };
+
         println!(
-            "{:<12} {:<10} {:<12} {:<12} {:<15} {}",
+            "{:<12} {:<10} {:<12} {} {} {}{}{}",
             timestamp,
             session.short_hash,
             project,
-            session.provider_name,
-            session.source_name,
+            theme.provider(&session.provider_name, 12),
+            theme.source(&session.source_name, 15),
+            times,
+            cost,
+            title,
+        );
+    }
+}
+
+/// List anomalies (e.g. runaway tool loops) of the sessions matching `filter`
+pub fn anomalies(store: &MetadataStore, filter: &ListFilter, json: bool, page: Page) -> Result<()> {
+    let (sessions, _) = filtered_sessions(store, filter)?;
+    let matching: HashSet<&str> = sessions.iter().map(|s| s.short_hash.as_str()).collect();
+    let mut anomalies = store.list_anomalies()?;
+    anomalies.retain(|a| matching.contains(a.short_hash.as_str()));
+    let (anomalies, total) = page.apply(anomalies);
+    if json {
+        super::print_json(&anomalies)?;
+        page.print_footer(anomalies.len(), total, true);
+        return Ok(());
+    }
+
+    if total == 0 {
+        println!("No anomalies detected.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<10} {:<10} {:<16} {:>7} {:>5}  Title",
+        "ID", "Kind", "Tool", "Repeats", "Msg"
+    );
+    println!("{}", theme::current().rule(100));
+
+    let shown = anomalies.len();
+    for anomaly in anomalies {
+        let title = anomaly
+            .title
+            .as_ref()
+            .map(|t| {
+                let t = t.lines().next().unwrap_or(t);
+                if t.chars().count() > 40 {
+                    format!("{}...", t.chars().take(37).collect::<String>())
+                } else {
+                    t.to_string()
+                }
+            })
+            .unwrap_or_else(|| "-".to_string());
+
+        println!(
+            "{:<10} {:<10} {:<16} {:>7} {:>5}  {}",
+            anomaly.short_hash,
+            anomaly.kind,
+            anomaly.tool_name.as_deref().unwrap_or("-"),
+            anomaly.repeat_count.unwrap_or(0),
+            anomaly
+                .message_index
+                .map(|i| (i + 1).to_string())
+                .unwrap_or_else(|| "-".to_string()),
             title,
         );
     }
+    page.print_footer(shown, total, false);
 
     Ok(())
 }
@@ -0,0 +1,130 @@
+//! `chronicle mcp` - which MCP servers are used, and how often their tools fail
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{short_time, theme, timeparse};
+use crate::store::{McpUsageRow, MetadataStore};
+
+/// Calls of one MCP server's tools
+#[derive(Debug, Default, Serialize)]
+struct ServerUsage {
+    server: String,
+    tools: usize,
+    calls: i64,
+    errors: i64,
+    unanswered: i64,
+    last_used: Option<String>,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    since: Option<String>,
+    server: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let mut rows = store.mcp_usage(since.as_deref())?;
+
+    if let Some(server) = server.as_deref() {
+        rows.retain(|r| r.server == server);
+        if json {
+            return super::print_json(&rows);
+        }
+        if rows.is_empty() {
+            println!("No calls to MCP server '{}'.", server);
+            return Ok(());
+        }
+        print_tools(&rows);
+        return Ok(());
+    }
+
+    let servers = by_server(&rows);
+    if json {
+        return super::print_json(&servers);
+    }
+    if servers.is_empty() {
+        println!("No MCP tool calls recorded. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<24} {:>6} {:>8} {:>8} {:>8} {:>10}  {:<16}",
+        "Server", "Tools", "Calls", "Errors", "Error %", "No result", "Last used"
+    );
+    println!("{}", theme.rule(88));
+    for s in &servers {
+        println!(
+            "{:<24} {:>6} {:>8} {:>8} {:>7.1}% {:>10}  {:<16}",
+            s.server,
+            s.tools,
+            s.calls,
+            s.errors,
+            rate(s.errors, s.calls),
+            s.unanswered,
+            short_time(&s.last_used)
+        );
+    }
+    println!("\nPer-tool breakdown: chronicle mcp --server <name>");
+    Ok(())
+}
+
+fn print_tools(rows: &[McpUsageRow]) {
+    let theme = theme::current();
+    println!(
+        "{:<40} {:>8} {:>8} {:>8} {:>10} {:>9}  {:<16}",
+        "Tool", "Calls", "Errors", "Error %", "No result", "Sessions", "Last used"
+    );
+    println!("{}", theme.rule(106));
+    for row in rows {
+        println!(
+            "{:<40} {:>8} {:>8} {:>7.1}% {:>10} {:>9}  {:<16}",
+            row.tool,
+            row.calls,
+            row.errors,
+            rate(row.errors, row.calls),
+            row.unanswered,
+            row.sessions,
+            short_time(&row.last_used)
+        );
+    }
+}
+
+/// Per-tool rows rolled up per server, most-called server first
+fn by_server(rows: &[McpUsageRow]) -> Vec<ServerUsage> {
+    let mut servers: Vec<ServerUsage> = vec![];
+    for row in rows {
+        let index = match servers.iter().position(|s| s.server == row.server) {
+            Some(index) => index,
+            None => {
+                servers.push(ServerUsage {
+                    server: row.server.clone(),
+                    ..Default::default()
+                });
+                servers.len() - 1
+            }
+        };
+        let server = &mut servers[index];
+        server.tools += 1;
+        server.calls += row.calls;
+        server.errors += row.errors;
+        server.unanswered += row.unanswered;
+        if row.last_used > server.last_used {
+            server.last_used = row.last_used.clone();
+        }
+    }
+    servers.sort_by_key(|s| std::cmp::Reverse(s.calls));
+    servers
+}
+
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
@@ -1,7 +1,246 @@
 //! CLI command modules
 
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::probe::ProbeCapabilities;
+use crate::store::{Audience, MetadataStore, ProjectRow, SessionRow};
+
+pub mod alerts;
+pub mod backfill;
+pub mod changelog;
+pub mod configure;
+pub mod context;
+pub mod costs;
+pub mod db;
+pub mod dedupe;
+pub mod enrich;
+pub mod export;
 pub mod extract;
+pub mod files;
+pub mod issues;
 pub mod list;
+pub mod mcp;
+pub mod models;
+pub mod pager;
+pub mod permissions;
 pub mod project;
+pub mod prune;
 pub mod read;
+pub mod render;
+pub mod report;
+pub mod search;
+pub mod serve;
 pub mod session;
+pub mod stats;
+pub mod statusline;
+pub mod switches;
+pub mod sysprompt;
+pub mod theme;
+pub mod timeline;
+pub mod timeparse;
+pub mod tools;
+pub mod watch;
+
+/// Print a value as pretty JSON (the `--json` output of read-only commands)
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// `YYYY-MM-DD HH:MM` of a stored timestamp, or `-`
+pub fn short_time(timestamp: &Option<String>) -> String {
+    match timestamp {
+        Some(t) => t.chars().take(16).collect::<String>().replace('T', " "),
+        None => "-".to_string(),
+    }
+}
+
+/// Cut `s` to at most `max` characters for a table column, ending in `...` when shortened
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() > max {
+        let cut: String = s.chars().take(max.saturating_sub(3)).collect();
+        format!("{}...", cut)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Indexed sources lacking a capability, e.g. `|c| c.supports_token_usage`; aggregates
+/// over them are incomplete rather than zero
+pub fn sources_without(
+    store: &MetadataStore,
+    supports: impl Fn(&ProbeCapabilities) -> bool,
+) -> Result<Vec<String>> {
+    Ok(store
+        .probe_stats()?
+        .into_iter()
+        .filter(|p| p.sessions > 0 && !supports(&p.capabilities))
+        .map(|p| p.probe_source_id)
+        .collect())
+}
+
+/// Drop sessions of projects whose visibility keeps them from `audience`; returns how
+/// many were dropped
+pub fn withhold(
+    store: &MetadataStore,
+    sessions: &mut Vec<SessionRow>,
+    audience: Audience,
+) -> Result<usize> {
+    let withheld = store.withheld_projects(audience)?;
+    let before = sessions.len();
+    sessions.retain(|s| {
+        s.project_id
+            .as_ref()
+            .is_none_or(|p| !withheld.contains_key(p))
+    });
+    Ok(before - sessions.len())
+}
+
+/// Refuse to send a project's history to `audience` when its visibility forbids it
+pub fn ensure_visible(project: &ProjectRow, audience: Audience) -> Result<()> {
+    if project.visibility.allows(audience) {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Project '{}' is {}: its sessions can't be {}. Change it with: chronicle project visibility '{}' {}",
+        project.name,
+        project.visibility.as_str(),
+        match audience {
+            Audience::Team => "shared",
+            Audience::Public => "exported",
+        },
+        project.name,
+        match audience {
+            Audience::Team => "internal",
+            Audience::Public => "shareable",
+        }
+    )
+}
+
+/// `--limit` / `--page` window over a command's rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// Rows per page; 0 shows everything
+    pub limit: usize,
+    /// 1-based page number
+    pub page: usize,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self {
+            limit: Self::DEFAULT_LIMIT,
+            page: 1,
+        }
+    }
+}
+
+impl Page {
+    pub const DEFAULT_LIMIT: usize = 50;
+
+    /// Everything on one page
+    pub const ALL: Page = Page { limit: 0, page: 1 };
+
+    /// Keep this page's rows; returns them with the total row count
+    pub fn apply<T>(&self, rows: Vec<T>) -> (Vec<T>, usize) {
+        let total = rows.len();
+        if self.limit == 0 {
+            return (rows, total);
+        }
+        let start = self.page.saturating_sub(1).saturating_mul(self.limit);
+        let rows = rows.into_iter().skip(start).take(self.limit).collect();
+        (rows, total)
+    }
+
+    /// Footer hint when rows were left out, e.g. "showing 51-100 of 3,214"
+    pub fn footer(&self, shown: usize, total: usize) -> Option<String> {
+        if shown >= total {
+            return None;
+        }
+        let start = self.page.saturating_sub(1) * self.limit;
+        let range = match shown {
+            0 => "none".to_string(),
+            _ if start == 0 => group_thousands(shown),
+            _ => format!(
+                "{}-{}",
+                group_thousands(start + 1),
+                group_thousands(start + shown)
+            ),
+        };
+        Some(format!(
+            "showing {} of {} — use --limit/--page (--limit 0 for all)",
+            range,
+            group_thousands(total)
+        ))
+    }
+
+    /// Print the footer hint if rows were left out. JSON output sends it to stderr so
+    /// stdout stays parseable.
+    pub fn print_footer(&self, shown: usize, total: usize, json: bool) {
+        if let Some(footer) = self.footer(shown, total) {
+            if json {
+                eprintln!("{}", footer);
+            } else {
+                println!("\n{}", footer);
+            }
+        }
+    }
+}
+
+/// 45s, 12m, 3h05m, 2d04h
+pub fn elapsed(secs: i64) -> String {
+    let secs = secs.max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d{:02}h", secs / 86_400, secs % 86_400 / 3600),
+    }
+}
+
+/// `3214` -> `3,214`
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("claude-sonnet-4", 20), "claude-sonnet-4");
+        assert_eq!(truncate("claude-sonnet-4", 10), "claude-...");
+        assert_eq!(truncate("größenwahn", 8), "größe...");
+        // Columns narrower than the ellipsis don't underflow
+        assert_eq!(truncate("abcdef", 2), "...");
+    }
+
+    #[test]
+    fn test_page_window_and_footer() {
+        let page = Page { limit: 50, page: 2 };
+        let (rows, total) = page.apply((0..3214).collect::<Vec<_>>());
+        assert_eq!((rows.first(), rows.len(), total), (Some(&50), 50, 3214));
+        assert_eq!(
+            page.footer(rows.len(), total).as_deref(),
+            Some("showing 51-100 of 3,214 — use --limit/--page (--limit 0 for all)")
+        );
+        assert_eq!(
+            Page::default().footer(50, 3214).as_deref(),
+            Some("showing 50 of 3,214 — use --limit/--page (--limit 0 for all)")
+        );
+
+        let (rows, total) = Page::ALL.apply(vec![1, 2, 3]);
+        assert_eq!(Page::ALL.footer(rows.len(), total), None);
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
+}
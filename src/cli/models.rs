@@ -0,0 +1,222 @@
+//! Models command implementation
+//!
+//! Aggregates indexed messages by model across every source: volume, tokens, how much
+//! of the prompt came from the cache, how often the model calls tools, and what it cost.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::costs::format_cost;
+use super::{theme, timeparse, truncate};
+use crate::config::Config;
+use crate::store::{MetadataStore, ModelUsageRow};
+
+/// A model with its derived ratios, as printed and serialized
+#[derive(Debug, Serialize)]
+struct ModelReport {
+    #[serde(flatten)]
+    usage: ModelUsageRow,
+    cache_hit_ratio: Option<f64>,
+    tool_calls_per_message: Option<f64>,
+    /// `None` when the model has no configured price
+    estimated_cost: Option<f64>,
+    /// Share of all messages in the period
+    message_share: f64,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    config: &Config,
+    since: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let reports = reports(store, config, since.as_deref())?;
+    let total_messages: i64 = reports.iter().map(|r| r.usage.messages).sum();
+
+    if json {
+        return super::print_json(&reports);
+    }
+    if reports.is_empty() {
+        match since {
+            Some(_) => println!("No model messages recorded in this period."),
+            None => println!("No model messages recorded. Run 'chronicle extract' first."),
+        }
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<28} {:>8} {:>6} {:>8} {:>13} {:>9} {:>9} {:>10}  Sources",
+        "Model", "Messages", "Share", "Sessions", "Tokens", "Cache hit", "Tools/msg", "Est. cost"
+    );
+    println!("{}", theme.rule(110));
+    let ratio = |value: Option<f64>, format: fn(f64) -> String| {
+        value.map_or_else(|| "-".to_string(), format)
+    };
+    for report in &reports {
+        let usage = &report.usage;
+        println!(
+            "{:<28} {:>8} {:>5.1}% {:>8} {:>13} {:>9} {:>9} {:>10}  {}",
+            truncate(&usage.model, 28),
+            usage.messages,
+            report.message_share,
+            usage.sessions,
+            super::group_thousands(usage.tokens.total().max(0) as usize),
+            ratio(report.cache_hit_ratio, |r| format!("{:.1}%", r * 100.0)),
+            ratio(report.tool_calls_per_message, |r| format!("{:.2}", r)),
+            report
+                .estimated_cost
+                .map_or_else(|| "-".to_string(), format_cost),
+            usage.sources.join(", ")
+        );
+    }
+
+    let total_cost: f64 = reports.iter().filter_map(|r| r.estimated_cost).sum();
+    println!("{}", theme.rule(110));
+    println!(
+        "{} model(s) · {} message(s) · estimated {}",
+        reports.len(),
+        total_messages,
+        format_cost(total_cost)
+    );
+    let unpriced: Vec<&str> = reports
+        .iter()
+        .filter(|r| r.estimated_cost.is_none())
+        .map(|r| r.usage.model.as_str())
+        .collect();
+    if !unpriced.is_empty() {
+        println!(
+            "\n⚠️  No price for: {} (add them under `pricing` in chronicle.yaml)",
+            unpriced.join(", ")
+        );
+    }
+    let untracked = super::sources_without(store, |c| c.supports_token_usage)?;
+    if !untracked.is_empty() {
+        println!(
+            "Tokens not recorded by: {} (their messages count, their tokens don't)",
+            untracked.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Models used since `since` (RFC 3339), most messages first
+fn reports(
+    store: &MetadataStore,
+    config: &Config,
+    since: Option<&str>,
+) -> Result<Vec<ModelReport>> {
+    let pricing = config.pricing();
+    let rows = store.model_usage(since)?;
+    let total_messages: i64 = rows.iter().map(|r| r.messages).sum();
+    Ok(rows
+        .into_iter()
+        .map(|usage| ModelReport {
+            cache_hit_ratio: usage.cache_hit_ratio(),
+            tool_calls_per_message: usage.tool_calls_per_message(),
+            estimated_cost: pricing.estimate(Some(&usage.model), &usage.tokens),
+            message_share: percent(usage.messages, total_messages),
+            usage,
+        })
+        .collect())
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => part as f64 * 100.0 / total as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{TokenUsage, ToolUseMetadata};
+    use crate::store::fixtures::{message, seed, session};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_model_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        let reply = |uuid: &str, model: &str, day: u32| {
+            let mut msg = message(
+                uuid,
+                "assistant",
+                Some(Utc.with_ymd_and_hms(2025, 3, day, 12, 0, 0).unwrap()),
+            );
+            msg.model = Some(model.to_string());
+            msg.token_usage = Some(TokenUsage {
+                input_tokens: Some(250_000),
+                output_tokens: Some(100_000),
+                cache_read_tokens: Some(750_000),
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            });
+            msg
+        };
+        let mut tool_turn = reply("a2", "acme-large", 2);
+        tool_turn.has_tool_use = true;
+        tool_turn.tool_uses = ["Bash", "Read"]
+            .iter()
+            .map(|tool| ToolUseMetadata {
+                tool_id: None,
+                tool_name: tool.to_string(),
+                has_result: true,
+                is_error: false,
+                permission: None,
+                input_hash: None,
+                file_path: None,
+            })
+            .collect();
+        let large = session(
+            "s1",
+            None,
+            vec![
+                reply("a1", "acme-large", 1),
+                tool_turn,
+                reply("a3", "acme-large", 9),
+            ],
+        );
+        seed(&store, "claude:ClaudeCode", &large);
+        seed(
+            &store,
+            "cursor:Cursor",
+            &session("s2", None, vec![reply("b1", "acme-small", 9)]),
+        );
+
+        let config: Config = serde_yaml::from_str(
+            "pricing:\n  acme-small: { input: 1.0, output: 10.0, cache_read: 0.1 }\n",
+        )
+        .unwrap();
+        let all = reports(&store, &config, None).unwrap();
+        let large = &all[0].usage;
+        assert_eq!(
+            (large.model.as_str(), large.messages, large.sessions),
+            ("acme-large", 3, 1)
+        );
+        assert_eq!(large.tool_calls, 2);
+        assert_eq!(all[0].message_share, 75.0);
+        assert_eq!(all[0].cache_hit_ratio, Some(0.75));
+        assert_eq!(all[0].estimated_cost, None);
+
+        // 0.25 input + 1.0 output + 0.075 cache reads, per million tokens
+        let small = &all[1];
+        assert_eq!(small.usage.sources, ["Cursor"]);
+        assert!((small.estimated_cost.unwrap() - 1.325).abs() < 1e-9);
+
+        // Only messages since the cutoff count
+        let since = Utc
+            .with_ymd_and_hms(2025, 3, 5, 0, 0, 0)
+            .unwrap()
+            .to_rfc3339();
+        let recent = reports(&store, &config, Some(&since)).unwrap();
+        let counts: Vec<_> = recent.iter().map(|r| r.usage.messages).collect();
+        assert_eq!(counts, [1, 1]);
+        assert_eq!(recent[0].message_share, 50.0);
+    }
+}
@@ -0,0 +1,255 @@
+//! Built-in pager for long `read --full` transcripts
+//!
+//! Commands are read a line at a time from stdin (no raw terminal mode):
+//! - `<enter>` / `f` next page, `b` previous page, `g` / `G` top / bottom
+//! - `/pattern` search forward, `?pattern` search backward
+//! - `n` / `N` repeat the last search in the same / opposite direction
+//! - `q` quit
+//!
+//! Searches are case-insensitive unless the pattern contains uppercase.
+
+use anyhow::Result;
+use std::io::{self, BufRead, Write};
+
+use super::render::strip_ansi;
+
+const REVERSE: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+/// Page `text` interactively on the terminal
+pub fn page(text: &str) -> Result<()> {
+    let mut pager = Pager::new(text, page_height());
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    loop {
+        write!(stdout, "{}", pager.screen())?;
+        write!(stdout, "{}", pager.prompt())?;
+        stdout.flush()?;
+
+        let mut input = String::new();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            break;
+        }
+        if !pager.handle(input.trim_end_matches(['\n', '\r'])) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn page_height() -> usize {
+    std::env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse::<usize>().ok())
+        .filter(|l| *l > 2)
+        .map(|l| l - 1)
+        .unwrap_or(23)
+}
+
+struct Pager {
+    lines: Vec<String>,
+    plain: Vec<String>,
+    height: usize,
+    top: usize,
+    pattern: Option<String>,
+    forward: bool,
+    status: Option<String>,
+}
+
+impl Pager {
+    fn new(text: &str, height: usize) -> Self {
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        let plain = lines.iter().map(|l| strip_ansi(l)).collect();
+        Self {
+            lines,
+            plain,
+            height,
+            top: 0,
+            pattern: None,
+            forward: true,
+            status: None,
+        }
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    /// Apply one command; returns false when the pager should exit
+    fn handle(&mut self, cmd: &str) -> bool {
+        self.status = None;
+        match cmd {
+            "q" | "Q" => return false,
+            "" | "f" | " " => {
+                if self.top >= self.max_top() {
+                    return false;
+                }
+                self.top = (self.top + self.height).min(self.max_top());
+            }
+            "b" => self.top = self.top.saturating_sub(self.height),
+            "g" => self.top = 0,
+            "G" => self.top = self.max_top(),
+            "n" => self.repeat_search(self.forward),
+            "N" => self.repeat_search(!self.forward),
+            _ => {
+                if let Some(pattern) = cmd.strip_prefix('/') {
+                    self.search(pattern, true);
+                } else if let Some(pattern) = cmd.strip_prefix('?') {
+                    self.search(pattern, false);
+                } else {
+                    self.status = Some(format!("Unknown command: {}", cmd));
+                }
+            }
+        }
+        true
+    }
+
+    fn search(&mut self, pattern: &str, forward: bool) {
+        if !pattern.is_empty() {
+            self.pattern = Some(pattern.to_string());
+        }
+        self.forward = forward;
+        self.repeat_search(forward);
+    }
+
+    fn repeat_search(&mut self, forward: bool) {
+        let Some(pattern) = self.pattern.clone() else {
+            self.status = Some("No previous search".to_string());
+            return;
+        };
+
+        let found = if forward {
+            (self.top + 1..self.lines.len()).find(|&i| self.line_matches(i, &pattern))
+        } else {
+            (0..self.top)
+                .rev()
+                .find(|&i| self.line_matches(i, &pattern))
+        };
+
+        match found {
+            Some(i) => self.top = i,
+            None => self.status = Some(format!("Pattern not found: {}", pattern)),
+        }
+    }
+
+    fn line_matches(&self, idx: usize, pattern: &str) -> bool {
+        find_match(&self.plain[idx], pattern).is_some()
+    }
+
+    fn screen(&self) -> String {
+        let mut out = String::new();
+        let end = (self.top + self.height).min(self.lines.len());
+        for idx in self.top..end {
+            out.push_str(&self.decorate(idx));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Highlight search hits; lines carrying their own escapes get a gutter marker instead
+    fn decorate(&self, idx: usize) -> String {
+        let line = &self.lines[idx];
+        let Some(pattern) = self.pattern.as_deref() else {
+            return line.clone();
+        };
+
+        if line.contains('\x1b') {
+            return if find_match(&self.plain[idx], pattern).is_some() {
+                format!("{}»{} {}", REVERSE, RESET, line)
+            } else {
+                line.clone()
+            };
+        }
+
+        let mut out = String::new();
+        let mut rest = line.as_str();
+        while let Some((start, len)) = find_match(rest, pattern) {
+            out.push_str(&rest[..start]);
+            out.push_str(REVERSE);
+            out.push_str(&rest[start..start + len]);
+            out.push_str(RESET);
+            rest = &rest[start + len..];
+            if len == 0 {
+                break;
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn prompt(&self) -> String {
+        if let Some(ref status) = self.status {
+            return format!("{}{}{} ", REVERSE, status, RESET);
+        }
+        let end = (self.top + self.height).min(self.lines.len());
+        let position = if end >= self.lines.len() {
+            "(END)".to_string()
+        } else {
+            format!("lines {}-{} of {}", self.top + 1, end, self.lines.len())
+        };
+        format!(
+            "{}{} — enter: next, b: back, /pat ?pat n N: search, q: quit{} ",
+            REVERSE, position, RESET
+        )
+    }
+}
+
+/// Find `pattern` in `haystack`, returning byte offset and length of the hit
+fn find_match(haystack: &str, pattern: &str) -> Option<(usize, usize)> {
+    if pattern.chars().any(|c| c.is_uppercase()) {
+        return haystack.find(pattern).map(|i| (i, pattern.len()));
+    }
+    // Lowercasing can change byte lengths for some scripts, so only fold ASCII
+    let lowered = haystack.to_ascii_lowercase();
+    lowered
+        .find(&pattern.to_ascii_lowercase())
+        .map(|i| (i, pattern.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> String {
+        (0..50)
+            .map(|i| {
+                if i % 10 == 5 {
+                    format!("[ASSISTANT] Needle {}", i)
+                } else {
+                    format!("line {}", i)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_search_and_repeat() {
+        let mut pager = Pager::new(&transcript(), 10);
+        pager.handle("/needle");
+        assert_eq!(pager.top, 5);
+        pager.handle("n");
+        assert_eq!(pager.top, 15);
+        pager.handle("N");
+        assert_eq!(pager.top, 5);
+    }
+
+    #[test]
+    fn test_smart_case_and_missing_pattern() {
+        let mut pager = Pager::new(&transcript(), 10);
+        pager.handle("/NEEDLE");
+        assert_eq!(pager.top, 0);
+        assert!(pager.status.is_some());
+    }
+
+    #[test]
+    fn test_paging_stops_at_end() {
+        let mut pager = Pager::new(&transcript(), 10);
+        pager.handle("G");
+        assert_eq!(pager.top, 40);
+        assert!(!pager.handle(""));
+        assert!(!pager.handle("q"));
+    }
+}
@@ -0,0 +1,94 @@
+//! `chronicle permissions` - which tool calls needed approval, and how often they were denied
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{theme, timeparse};
+use crate::store::{MetadataStore, PermissionRow};
+
+/// Permission decisions of one tool (or all tools), with rates
+#[derive(Debug, Default, Serialize)]
+struct ToolPermissions {
+    tool: String,
+    calls: i64,
+    auto: i64,
+    approved: i64,
+    denied: i64,
+    /// Share of calls that asked the user (approved + denied)
+    prompt_rate: f64,
+    /// Share of prompted calls the user denied
+    denial_rate: f64,
+}
+
+impl ToolPermissions {
+    fn new(tool: &str, auto: i64, approved: i64, denied: i64) -> Self {
+        let calls = auto + approved + denied;
+        let prompted = approved + denied;
+        ToolPermissions {
+            tool: tool.to_string(),
+            calls,
+            auto,
+            approved,
+            denied,
+            prompt_rate: rate(prompted, calls),
+            denial_rate: rate(denied, prompted),
+        }
+    }
+}
+
+pub fn run(store: &MetadataStore, since: Option<String>, json: bool) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let rows = store.permission_stats(since.as_deref())?;
+    let tools: Vec<ToolPermissions> = rows
+        .iter()
+        .map(|r| ToolPermissions::new(&r.tool_name, r.auto, r.approved, r.denied))
+        .collect();
+    let total = totals(&rows);
+
+    if json {
+        return super::print_json(&serde_json::json!({ "tools": tools, "total": total }));
+    }
+    if tools.is_empty() {
+        println!("No permission decisions recorded. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<40} {:>8} {:>8} {:>9} {:>8} {:>9} {:>9}",
+        "Tool", "Calls", "Auto", "Approved", "Denied", "Prompt %", "Denied %"
+    );
+    println!("{}", theme.rule(97));
+    for t in &tools {
+        print_row(t);
+    }
+    println!("{}", theme.rule(97));
+    print_row(&total);
+    println!("\nDenied % is the share of approval prompts the user rejected.");
+    Ok(())
+}
+
+fn print_row(t: &ToolPermissions) {
+    println!(
+        "{:<40} {:>8} {:>8} {:>9} {:>8} {:>8.1}% {:>8.1}%",
+        t.tool, t.calls, t.auto, t.approved, t.denied, t.prompt_rate, t.denial_rate
+    );
+}
+
+fn totals(rows: &[PermissionRow]) -> ToolPermissions {
+    let (auto, approved, denied) = rows.iter().fold((0, 0, 0), |(a, p, d), r| {
+        (a + r.auto, p + r.approved, d + r.denied)
+    });
+    ToolPermissions::new("Total", auto, approved, denied)
+}
+
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
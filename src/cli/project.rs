@@ -1,5 +1,11 @@
-use crate::store::MetadataStore;
+use crate::analysis::snapshot::{self, CountChange, ProjectSnapshot, SnapshotDiff};
+use crate::config::{Config, VirtualProjectConfig};
+use crate::content::ContentLoader;
+use crate::error::Error;
+use crate::probe::ProbeRegistry;
+use crate::store::{MetadataStore, ProjectRow, SessionRow, Visibility};
 use anyhow::Result;
+use serde_json::Value;
 use uuid::Uuid;
 
 pub fn create(
@@ -7,62 +13,570 @@ pub fn create(
     name: String,
     project_type: String,
     path: Option<String>,
+    visibility: &str,
 ) -> Result<()> {
+    let visibility = Visibility::parse(visibility)?;
     let id = Uuid::new_v4().to_string();
-    store.create_project(&id, &name, &project_type, path.as_deref(), None)?;
+    store.transaction(|| {
+        store.create_project(&id, &name, &project_type, path.as_deref(), None)?;
+        store.set_project_visibility(&id, visibility)
+    })?;
     println!("Project '{}' created with ID: {}", name, id);
+    if path.is_some() {
+        print_relinked(store.relink_sessions()?);
+    }
+    Ok(())
+}
+
+/// Show a project's visibility, or change it
+pub fn visibility(
+    store: &MetadataStore,
+    project_id_query: String,
+    level: Option<String>,
+) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    let Some(level) = level else {
+        println!("{}: {}", project.name, project.visibility.as_str());
+        return Ok(());
+    };
+    let visibility = Visibility::parse(&level)?;
+    store.set_project_visibility(&project.id, visibility)?;
+    println!(
+        "Project '{}' is now {} (was {})",
+        project.name,
+        visibility.as_str(),
+        project.visibility.as_str()
+    );
+    Ok(())
+}
+
+pub fn rename(store: &MetadataStore, project_id_query: String, name: String) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    if let Some(other) = store.find_project(&name)? {
+        if other.id != project.id {
+            anyhow::bail!(
+                "A project named '{}' already exists ({})",
+                name,
+                &other.id[..8]
+            );
+        }
+    }
+    store.rename_project(&project.id, &name)?;
+    println!("Renamed project '{}' to '{}'", project.name, name);
     Ok(())
 }
 
-pub fn list(store: &MetadataStore) -> Result<()> {
+/// Change a project's type, primary path or metadata keys
+pub fn set(
+    store: &MetadataStore,
+    project_id_query: String,
+    project_type: Option<String>,
+    path: Option<String>,
+    metadata: Vec<String>,
+) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    if project_type.is_none() && path.is_none() && metadata.is_empty() {
+        anyhow::bail!("Nothing to set: pass --type, --path or --metadata KEY=VALUE");
+    }
+    // Parse everything before writing anything
+    let metadata = metadata
+        .iter()
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected KEY=VALUE, got '{}'", pair))?;
+            // JSON values (numbers, lists, ...) are kept typed, anything else is a string
+            let value = (!value.is_empty()).then(|| {
+                serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+            });
+            Ok((key.to_string(), value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    store.transaction(|| {
+        if let Some(ref project_type) = project_type {
+            store.set_project_type(&project.id, project_type)?;
+            println!("type: {} -> {}", project.project_type, project_type);
+        }
+        if let Some(ref path) = path {
+            store.set_project_primary_path(&project.id, path)?;
+            println!(
+                "primary path: {} -> {}",
+                project.primary_path.as_deref().unwrap_or("-"),
+                path
+            );
+        }
+        for (key, value) in metadata {
+            match value {
+                Some(value) => {
+                    println!("metadata.{} = {}", key, value);
+                    store.merge_project_metadata(&project.id, &key, value)?;
+                }
+                None if store.remove_project_metadata(&project.id, &key)? => {
+                    println!("metadata.{} removed", key)
+                }
+                None => println!("metadata.{} was not set", key),
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Delete a project, moving its sessions to `reassign` or leaving them unassigned
+pub fn delete(
+    store: &MetadataStore,
+    project_id_query: String,
+    reassign: Option<String>,
+) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    let target = reassign.map(|q| find(store, &q)).transpose()?;
+    if target.as_ref().is_some_and(|t| t.id == project.id) {
+        anyhow::bail!("Cannot reassign sessions to the project being deleted");
+    }
+    let sessions = store.delete_project(&project.id, target.as_ref().map(|t| t.id.as_str()))?;
+    println!("Deleted project '{}'", project.name);
+    match target {
+        Some(target) => println!("  {} session(s) moved to '{}'", sessions, target.name),
+        None => println!(
+            "  {} session(s) unassigned (they may auto-link to another project on the next extract)",
+            sessions
+        ),
+    }
+    Ok(())
+}
+
+/// Fold one project into another
+pub fn merge(store: &MetadataStore, source_query: String, target_query: String) -> Result<()> {
+    let source = find(store, &source_query)?;
+    let target = find(store, &target_query)?;
+    if source.id == target.id {
+        anyhow::bail!("Cannot merge a project into itself");
+    }
+    let moved = store.merge_projects(&source, &target)?;
+    println!("Merged '{}' into '{}'", source.name, target.name);
+    println!(
+        "  {} session(s), {} path(s), {} identifier(s) moved",
+        moved.sessions, moved.paths, moved.identifiers
+    );
+    if source.visibility < target.visibility {
+        println!(
+            "  Visibility lowered to {} (from '{}')",
+            source.visibility.as_str(),
+            source.name
+        );
+    }
+    Ok(())
+}
+
+pub fn list(store: &MetadataStore, config: &Config, recount: bool, json: bool) -> Result<()> {
+    if recount {
+        let drifted = store.recount_projects()?;
+        if !json {
+            println!("Recounted project counters ({} corrected)\n", drifted);
+        }
+    }
+    if json {
+        return super::print_json(&list_json(store, config)?);
+    }
     let projects = store.list_projects()?;
-    if projects.is_empty() {
+    if projects.is_empty() && config.virtual_projects.is_empty() {
         println!("No projects found.");
         return Ok(());
     }
 
     println!(
-        "{:<12} {:<20} {:<10} {:<8} {:<30}",
-        "ID", "Name", "Type", "Sessions", "Path"
+        "{:<12} {:<20} {:<10} {:<10} {:<8} {:<8} {:<30}",
+        "ID", "Name", "Type", "Visibility", "Sessions", "Messages", "Path"
     );
-    println!("{}", "-".repeat(85));
+    println!("{}", "-".repeat(105));
     for p in projects {
         println!(
-            "{:<12} {:<20} {:<10} {:<8} {:<30}",
+            "{:<12} {:<20} {:<10} {:<10} {:<8} {:<8} {:<30}",
             &p.id[..8],
             p.name,
             p.project_type,
+            p.visibility.as_str(),
             p.session_count,
+            p.message_count,
             p.primary_path.unwrap_or_default()
         );
     }
+
+    // Virtual projects are config-defined filters; they own no sessions
+    for (name, vp) in &config.virtual_projects {
+        let sessions = virtual_sessions(store, vp)?;
+        println!(
+            "{:<12} {:<20} {:<10} {:<10} {:<8} {:<8} {:<30}",
+            "-",
+            name,
+            "virtual",
+            "-",
+            sessions.len(),
+            sessions.iter().map(|s| s.message_count).sum::<i64>(),
+            vp.description.as_deref().unwrap_or_default()
+        );
+    }
     Ok(())
 }
 
+/// Projects and virtual projects as JSON (`project list --json` and the API)
+pub fn list_json(store: &MetadataStore, config: &Config) -> Result<Value> {
+    let mut virtual_projects = vec![];
+    for (name, vp) in &config.virtual_projects {
+        let sessions = virtual_sessions(store, vp)?;
+        virtual_projects.push(serde_json::json!({
+            "name": name,
+            "description": vp.description,
+            "session_count": sessions.len(),
+            "message_count": sessions.iter().map(|s| s.message_count).sum::<i64>(),
+        }));
+    }
+    Ok(serde_json::json!({
+        "projects": store.list_projects()?,
+        "virtual_projects": virtual_projects,
+    }))
+}
+
+/// Sessions matching a virtual project's filter, most recent first
+pub fn virtual_sessions(
+    store: &MetadataStore,
+    vp: &VirtualProjectConfig,
+) -> Result<Vec<SessionRow>> {
+    Ok(store
+        .list_sessions(None, None)?
+        .into_iter()
+        .filter(|s| vp.matches(s))
+        .collect())
+}
+
 pub fn add_path(store: &MetadataStore, project_id_query: String, path: String) -> Result<()> {
     // Find project by id or name
-    let projects = store.list_projects()?;
-    let project = projects
-        .iter()
-        .find(|p| p.id.starts_with(&project_id_query) || p.name == project_id_query)
-        .ok_or_else(|| anyhow::anyhow!("Project not found: {}", project_id_query))?;
+    let project = store
+        .find_project(&project_id_query)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id_query.to_string()))?;
 
     store.add_project_path(&project.id, &path, false)?;
     println!("Added path '{}' to project '{}'", path, project.name);
+    print_relinked(store.relink_sessions()?);
     Ok(())
 }
 
 pub fn add_git(store: &MetadataStore, project_id_query: String, remote: String) -> Result<()> {
-    let projects = store.list_projects()?;
-    let project = projects
-        .iter()
-        .find(|p| p.id.starts_with(&project_id_query) || p.name == project_id_query)
-        .ok_or_else(|| anyhow::anyhow!("Project not found: {}", project_id_query))?;
+    let project = store
+        .find_project(&project_id_query)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id_query.to_string()))?;
 
     store.add_project_identifier(&project.id, "git_remote", &remote)?;
     println!(
         "Added git remote '{}' to project '{}'",
         remote, project.name
     );
+    print_relinked(store.relink_sessions()?);
+    Ok(())
+}
+
+/// Link sessions extracted before their project's paths or remotes were known
+pub fn relink(store: &MetadataStore) -> Result<()> {
+    let linked = store.relink_sessions()?;
+    println!("Linked {} session(s) to projects", linked);
+    Ok(())
+}
+
+fn print_relinked(linked: usize) {
+    if linked > 0 {
+        println!("Linked {} existing session(s)", linked);
+    }
+}
+
+pub fn show(store: &MetadataStore, config: &Config, project_id_query: String) -> Result<()> {
+    let Some(project) = store.find_project(&project_id_query)? else {
+        let vp = config
+            .virtual_project(&project_id_query)
+            .ok_or_else(|| Error::ProjectNotFound(project_id_query.to_string()))?;
+        return show_virtual(store, &project_id_query, vp);
+    };
+
+    println!("\n{}", "=".repeat(80));
+    println!("Project: {} ({})", project.name, project.id);
+    println!(
+        "Type: {} | Visibility: {} | Sessions: {} | Messages: {}",
+        project.project_type,
+        project.visibility.as_str(),
+        project.session_count,
+        project.message_count
+    );
+    if let Some(last) = project
+        .last_session_at
+        .as_ref()
+        .or(project.last_activity.as_ref())
+    {
+        println!("Last Activity: {}", last);
+    }
+    println!("{}", "=".repeat(80));
+
+    let paths = store.get_project_paths(&project.id)?;
+    if !paths.is_empty() {
+        println!("\nPaths:");
+        for path in paths {
+            println!("  {}", path);
+        }
+    }
+
+    let identifiers = store.get_project_identifiers(&project.id)?;
+    if !identifiers.is_empty() {
+        println!("\nIdentifiers:");
+        for (kind, value) in identifiers {
+            println!("  {}: {}", kind, value);
+        }
+    }
+
+    let snapshots = store.list_project_snapshots(&project.id)?;
+    if !snapshots.is_empty() {
+        println!("\nSnapshots:");
+        for row in snapshots {
+            println!(
+                "  #{} {} {} ({} sessions)",
+                row.id,
+                row.taken_at,
+                row.label.as_deref().unwrap_or("-"),
+                row.snapshot.session_count
+            );
+        }
+    }
+
+    let metadata = project
+        .metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<Value>(m).ok());
+    if let Some(Value::Object(map)) = metadata {
+        for (key, value) in map {
+            println!("\n{}:", key);
+            print_metadata_value(&value, 1);
+        }
+    }
+
     Ok(())
 }
+
+/// Capture the project's current aggregates as a snapshot
+pub fn snapshot(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    project_id_query: String,
+    label: Option<String>,
+) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    let current = current_snapshot(store, registry, &project)?;
+    let id = store.save_project_snapshot(&project.id, label.as_deref(), &current)?;
+    println!(
+        "Saved snapshot #{} of '{}': {} sessions, {} messages, {} open question(s)",
+        id,
+        project.name,
+        current.session_count,
+        current.message_count,
+        current.open_questions.len()
+    );
+    Ok(())
+}
+
+/// Compare two snapshots (ID or label); `from` defaults to the latest snapshot and `to`
+/// to the project's current state
+pub fn diff_snapshots(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    project_id_query: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<()> {
+    let project = find(store, &project_id_query)?;
+    let snapshots = store.list_project_snapshots(&project.id)?;
+    if snapshots.is_empty() {
+        anyhow::bail!(
+            "Project '{}' has no snapshots yet. Take one with: chronicle project snapshot {}",
+            project.name,
+            project.name
+        );
+    }
+    let lookup = |query: &str| {
+        snapshots
+            .iter()
+            .rev()
+            .find(|row| row.id.to_string() == query || row.label.as_deref() == Some(query))
+            .ok_or_else(|| anyhow::anyhow!("Snapshot not found: {}", query))
+    };
+
+    let before = match from.as_deref() {
+        Some(query) => lookup(query)?,
+        None => snapshots.last().expect("snapshots is not empty"),
+    };
+    let (after, after_label) = match to.as_deref() {
+        Some("now") | None => (
+            current_snapshot(store, registry, &project)?,
+            "now".to_string(),
+        ),
+        Some(query) => {
+            let row = lookup(query)?;
+            (
+                row.snapshot.clone(),
+                describe(row.id, &row.label, &row.taken_at),
+            )
+        }
+    };
+
+    println!(
+        "Project: {} | {} -> {}\n",
+        project.name,
+        describe(before.id, &before.label, &before.taken_at),
+        after_label
+    );
+    print_diff(&SnapshotDiff::between(&before.snapshot, &after));
+    Ok(())
+}
+
+fn find(store: &MetadataStore, query: &str) -> Result<ProjectRow> {
+    let project = store
+        .find_project(query)?
+        .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?;
+    Ok(project)
+}
+
+/// Aggregates plus the questions left open at the end of recent sessions
+fn current_snapshot(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    project: &ProjectRow,
+) -> Result<ProjectSnapshot> {
+    let mut current = store.project_snapshot(&project.id)?;
+    let loader = ContentLoader::new(registry).with_archive(store);
+    let recent = store
+        .list_sessions(None, None)?
+        .into_iter()
+        .filter(|s| s.project_id.as_deref() == Some(project.id.as_str()))
+        .take(snapshot::RECENT_SESSIONS);
+    for session in recent {
+        let messages = store.get_messages(&session.id)?;
+        let Some(last) = messages.iter().rev().find(|m| m.role == "user") else {
+            continue;
+        };
+        if let Ok(text) = loader.load_text(&session, last) {
+            if snapshot::is_question(&text) {
+                current.open_questions.push(one_line(&text, 200));
+            }
+        }
+        if current.open_questions.len() >= snapshot::MAX_QUESTIONS {
+            break;
+        }
+    }
+    Ok(current)
+}
+
+fn describe(id: i64, label: &Option<String>, taken_at: &str) -> String {
+    match label {
+        Some(label) => format!("#{} {} ({})", id, label, taken_at),
+        None => format!("#{} ({})", id, taken_at),
+    }
+}
+
+fn print_diff(diff: &SnapshotDiff) {
+    let change = |c: &CountChange| {
+        println!(
+            "  {:<28} {:>8} -> {:<8} ({:+})",
+            c.name,
+            c.before,
+            c.after,
+            c.after - c.before
+        )
+    };
+    diff.totals.iter().for_each(change);
+    for (title, changes) in [("Sources", &diff.sources), ("Models", &diff.models)] {
+        if !changes.is_empty() {
+            println!("\n{}:", title);
+            changes.iter().for_each(change);
+        }
+    }
+    for (title, items) in [
+        ("New references", &diff.new_references),
+        ("New sessions", &diff.new_sessions),
+        ("New open questions", &diff.new_questions),
+        ("No longer open", &diff.settled_questions),
+    ] {
+        if !items.is_empty() {
+            println!("\n{}:", title);
+            for item in items {
+                println!("  {}", item);
+            }
+        }
+    }
+}
+
+/// First line of `text`, cut to `max` characters
+fn one_line(text: &str, max: usize) -> String {
+    let line = text.trim().lines().next().unwrap_or("").trim();
+    if line.chars().count() > max {
+        format!("{}...", line.chars().take(max - 3).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn show_virtual(store: &MetadataStore, name: &str, vp: &VirtualProjectConfig) -> Result<()> {
+    let sessions = virtual_sessions(store, vp)?;
+
+    println!("\n{}", "=".repeat(80));
+    println!("Project: {} (virtual)", name);
+    println!("Type: virtual | Sessions: {}", sessions.len());
+    if let Some(description) = &vp.description {
+        println!("Description: {}", description);
+    }
+    println!("{}", "=".repeat(80));
+
+    println!("\nFilter:");
+    for (label, values) in [
+        ("title", &vp.title),
+        ("sources", &vp.sources),
+        ("providers", &vp.providers),
+        ("models", &vp.models),
+        ("paths", &vp.paths),
+    ] {
+        if !values.is_empty() {
+            println!("  {}: {}", label, values.join(", "));
+        }
+    }
+
+    if !sessions.is_empty() {
+        println!();
+        super::list::print_sessions(&sessions);
+    }
+    Ok(())
+}
+
+/// Print nested metadata as indented `key: value` lines
+fn print_metadata_value(value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                match v {
+                    Value::Null => {}
+                    Value::Array(items) if items.is_empty() => {}
+                    Value::Object(_) | Value::Array(_) => {
+                        println!("{}{}:", indent, key);
+                        print_metadata_value(v, depth + 1);
+                    }
+                    Value::String(s) => println!("{}{}: {}", indent, key, s),
+                    _ => println!("{}{}: {}", indent, key, v),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::String(s) => println!("{}- {}", indent, s),
+                    _ => println!("{}- {}", indent, item),
+                }
+            }
+        }
+        Value::String(s) => println!("{}{}", indent, s),
+        _ => println!("{}{}", indent, value),
+    }
+}
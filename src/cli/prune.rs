@@ -0,0 +1,96 @@
+//! Prune command implementation
+//!
+//! Finds sessions whose source file is gone (e.g. rotated away by the tool) and marks
+//! them orphaned or deletes them. Orphaned sessions stay listed and readable from the
+//! content cache; the mark clears once the source is back.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::store::MetadataStore;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PruneOptions {
+    /// Delete sessions instead of marking them orphaned
+    pub delete: bool,
+    /// With `delete`, only mark sessions whose content is cached
+    pub keep_cached: bool,
+    /// Only report what would change
+    pub dry_run: bool,
+}
+
+pub fn run(store: &MetadataStore, probe: Option<&str>, options: PruneOptions) -> Result<()> {
+    let sources = store.session_sources(probe)?;
+    let (missing, present): (Vec<_>, Vec<_>) = sources
+        .into_iter()
+        .partition(|s| !Path::new(&s.source_path).exists());
+
+    // Sources that came back clear their mark (unchanged ones aren't re-extracted)
+    let restored: Vec<String> = present
+        .into_iter()
+        .filter(|s| s.orphaned)
+        .map(|s| s.session_id)
+        .collect();
+    if !restored.is_empty() && !options.dry_run {
+        store.set_sessions_orphaned(&restored, false)?;
+    }
+    if !restored.is_empty() {
+        println!(
+            "♻️  {} orphaned session(s) have their source back",
+            restored.len()
+        );
+    }
+
+    if missing.is_empty() {
+        println!("No sessions with missing sources.");
+        return Ok(());
+    }
+
+    let mut by_probe: BTreeMap<&str, usize> = BTreeMap::new();
+    for source in &missing {
+        *by_probe.entry(&source.probe_source_id).or_default() += 1;
+    }
+    println!("🧹 {} session(s) with missing sources:", missing.len());
+    for (probe, count) in &by_probe {
+        println!("   {:<28} {}", probe, count);
+    }
+
+    let cached = match options.delete && options.keep_cached {
+        true => store.sessions_with_cached_content()?,
+        false => Default::default(),
+    };
+    let (to_mark, to_delete): (Vec<String>, Vec<String>) = missing
+        .into_iter()
+        .map(|s| s.session_id)
+        .partition(|id| !options.delete || cached.contains(id));
+
+    if options.dry_run {
+        println!(
+            "\nDry run: would delete {} and mark {} orphaned",
+            to_delete.len(),
+            to_mark.len()
+        );
+        return Ok(());
+    }
+
+    let marked = store.set_sessions_orphaned(&to_mark, true)?;
+    let deleted = store.delete_sessions(&to_delete)?;
+    println!();
+    if deleted > 0 {
+        println!("🗑️  Deleted {} session(s)", deleted);
+    }
+    if !to_mark.is_empty() {
+        let kept = match options.delete {
+            true => " (content cached)",
+            false => "",
+        };
+        println!(
+            "🏷️  {} session(s) orphaned{}, {} newly marked",
+            to_mark.len(),
+            kept,
+            marked
+        );
+    }
+    Ok(())
+}
@@ -1,55 +1,230 @@
 //! Read command implementation
 
 use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
 use serde_json::Value;
+use std::fmt::Write;
+use std::io::IsTerminal;
 
-use crate::probe::{ContentRef, ProbeRegistry};
-use crate::store::MetadataStore;
+use super::render::render_markdown;
+use super::{pager, theme, timeparse};
+use crate::content::ContentLoader;
+use crate::error::Error;
+use crate::probe::ProbeRegistry;
+use crate::store::{MessageRow, MetadataStore, SessionRow};
+
+/// How the session to read is identified
+pub enum SessionLookup {
+    /// Short hash, ID or external ID prefix (or a source path)
+    Query(String),
+    /// Path of the session's source file
+    Path(String),
+    /// Exact source-native session ID
+    ExternalId(String),
+}
+
+impl SessionLookup {
+    fn resolve(&self, store: &MetadataStore) -> Result<Option<SessionRow>> {
+        match self {
+            SessionLookup::Query(query) => store.get_session(query),
+            SessionLookup::Path(path) => store.get_session_by_path(path),
+            SessionLookup::ExternalId(id) => store.get_session_by_external_id(id),
+        }
+    }
+}
+
+impl std::fmt::Display for SessionLookup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionLookup::Query(query) => write!(f, "{}", query),
+            SessionLookup::Path(path) => write!(f, "path {}", path),
+            SessionLookup::ExternalId(id) => write!(f, "external ID {}", id),
+        }
+    }
+}
+
+/// Options for reading a session
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Load and show message content
+    pub full: bool,
+    /// Mark messages that used tools
+    pub tools: bool,
+    /// Render assistant markdown for the terminal
+    pub render: bool,
+    /// Print long transcripts directly instead of paging them
+    pub no_pager: bool,
+    /// Emit the session and its messages as JSON
+    pub json: bool,
+    /// Which of the session's messages to show
+    pub selection: MessageSelection,
+}
+
+/// Part of a session to read. Positions are 1-based message numbers in the session;
+/// the role filter applies within them, then `tail` keeps the last matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageSelection {
+    /// Inclusive position range; a single message is `(n, n)`
+    pub range: Option<(usize, usize)>,
+    pub tail: Option<usize>,
+    pub role: Option<String>,
+}
+
+impl MessageSelection {
+    /// Parse `a..b`, `a..` or `..b` (inclusive, 1-based)
+    pub fn parse_range(value: &str) -> Result<(usize, usize)> {
+        let (start, end) = value
+            .split_once("..")
+            .ok_or_else(|| anyhow::anyhow!("Invalid range '{}' (expected a..b)", value))?;
+        let bound = |s: &str, default: usize| -> Result<usize> {
+            match s.trim() {
+                "" => Ok(default),
+                n => n
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid message number '{}' in range", n)),
+            }
+        };
+        let (start, end) = (bound(start, 1)?, bound(end, usize::MAX)?);
+        if start > end {
+            anyhow::bail!("Range '{}' ends before it starts", value);
+        }
+        Ok((start, end))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Selected messages with their 1-based position in `messages`
+    pub fn apply(&self, messages: Vec<MessageRow>) -> Vec<(usize, MessageRow)> {
+        let mut selected: Vec<(usize, MessageRow)> = messages
+            .into_iter()
+            .enumerate()
+            .map(|(i, msg)| (i + 1, msg))
+            .filter(|(n, _)| self.range.is_none_or(|(a, b)| (a..=b).contains(n)))
+            .filter(|(_, msg)| self.role.as_ref().is_none_or(|r| msg.role == *r))
+            .collect();
+        if let Some(tail) = self.tail {
+            selected.drain(..selected.len().saturating_sub(tail));
+        }
+        selected
+    }
+}
 
 pub fn run(
     store: &MetadataStore,
     registry: &ProbeRegistry,
-    session_id: &str,
-    full: bool,
-    tools: bool,
+    lookup: &SessionLookup,
+    options: &ReadOptions,
 ) -> Result<()> {
-    let session = store.get_session(session_id)?;
-
-    let session = match session {
-        Some(s) => s,
-        None => {
-            println!("Session '{}' not found.", session_id);
-            return Ok(());
-        }
-    };
+    let ReadOptions { full, json, .. } = *options;
+    let session = lookup
+        .resolve(store)?
+        .ok_or_else(|| Error::SessionNotFound(lookup.to_string()))?;
+    if json {
+        return print_json(store, registry, &session, full, &options.selection);
+    }
 
-    println!("\n{}", "=".repeat(80));
-    println!("Session: {} ({})", session.short_hash, session.external_id);
-    println!(
+    let theme = theme::current();
+    let mut out = String::new();
+    writeln!(out, "\n{}", theme.heavy_rule(80))?;
+    writeln!(
+        out,
+        "Session: {} ({})",
+        session.short_hash, session.external_id
+    )?;
+    writeln!(
+        out,
         "Provider: {} | Source: {}",
-        session.provider_name, session.source_name
-    );
+        theme.provider(&session.provider_name, 0),
+        theme.source(&session.source_name, 0)
+    )?;
     if let Some(model) = &session.primary_model {
-        println!("Primary Model: {}", model);
+        writeln!(out, "Primary Model: {}", model)?;
+    }
+    // Sessions that switched models get the split
+    let models = store.session_models(&session.id)?;
+    if models.len() > 1 {
+        let parts: Vec<String> = models
+            .iter()
+            .map(|m| {
+                format!(
+                    "{} ({} msgs, {} tokens)",
+                    m.model,
+                    m.messages,
+                    super::group_thousands(m.tokens.total().max(0) as usize)
+                )
+            })
+            .collect();
+        writeln!(out, "Models: {}", parts.join(", "))?;
+    }
+    if let Some(group) = &session.source_group {
+        writeln!(out, "Source Group: {}", group)?;
     }
     if let Some(project) = &session.project_name {
-        println!("Project: {}", project);
+        writeln!(out, "Project: {}", project)?;
     } else if let Some(path) = &session.project_path {
-        println!("Raw Path: {}", path);
+        writeln!(out, "Raw Path: {}", path)?;
     }
-    println!("{}", "=".repeat(80));
+    if let Some(language) = &session.language {
+        writeln!(out, "Language: {}", language)?;
+    }
+    for params in store.session_request_params(&session.id)? {
+        let mut parts = vec![];
+        if let (Some(hash), Some(chars)) = (&params.system_prompt_hash, params.system_prompt_chars)
+        {
+            parts.push(format!("system prompt {} ({} chars)", &hash[..12], chars));
+        }
+        if let Some(temperature) = params.temperature {
+            parts.push(format!("temperature {}", temperature));
+        }
+        if let Some(max_tokens) = params.max_tokens {
+            parts.push(format!("max tokens {}", max_tokens));
+        }
+        writeln!(
+            out,
+            "Request: {} [{} request(s)]",
+            parts.join(", "),
+            params.requests
+        )?;
+    }
+    for (kind, result) in store.session_enrichments(&session.id)? {
+        let mut label = kind;
+        if let Some(first) = label.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        writeln!(out, "{}: {}", label, result)?;
+    }
+    writeln!(out, "{}", theme.heavy_rule(80))?;
 
     // Show messages
     let messages = store.get_messages(&session.id)?;
 
     if messages.is_empty() {
-        println!("\nNo messages found (this may be an empty session).");
+        writeln!(out, "\nNo messages found (this may be an empty session).")?;
+        print!("{}", out);
+        return Ok(());
+    }
+    let total = messages.len();
+    let selected = options.selection.apply(messages);
+    if selected.is_empty() {
+        writeln!(
+            out,
+            "\nNo messages match the selection (the session has {}).",
+            total
+        )?;
+        print!("{}", out);
         return Ok(());
     }
+    if !options.selection.is_empty() {
+        writeln!(out, "Showing {} of {} messages", selected.len(), total)?;
+    }
 
-    let probe = registry.get_probe(&session.probe_source_id);
+    let loader = ContentLoader::new(registry).with_archive(store);
 
-    for msg in messages {
+    for (number, msg) in selected {
         let provider_info = if let Some(p) = &msg.provider_id {
             format!(" | {}", p)
         } else {
@@ -61,80 +236,508 @@ pub fn run(
             String::new()
         };
 
-        println!(
-            "\n[{}{}{}] ({})",
-            msg.role.to_uppercase(),
+        writeln!(
+            out,
+            "\n#{} [{}{}{}] ({})",
+            number,
+            role_label(&msg),
             provider_info,
             model_info,
             msg.timestamp.as_deref().unwrap_or("?")
-        );
+        )?;
 
-        if full {
-            if let Some(probe) = probe {
-                let content_ref = ContentRef {
-                    source_path: msg.source_path.into(),
-                    byte_offset: msg.byte_offset.map(|o| o as u64),
-                    line_number: msg.line_number.map(|n| n as u32),
-                    content_path: msg.content_ref.map(Into::into),
+        write_body(&mut out, &loader, &session, &msg, options)?;
+    }
+
+    show(&out, options)
+}
+
+/// Messages from all of a project's sessions on one local day, interleaved by time
+pub fn merged(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    project: &str,
+    date: &str,
+    options: &ReadOptions,
+) -> Result<()> {
+    let project = store
+        .find_project(project)?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))?;
+    let day = timeparse::parse(date)?.with_timezone(&Local).date_naive();
+
+    let (sessions, entries) =
+        day_entries(store, &project.id, day, options.selection.role.as_deref())?;
+    let mut involved: Vec<usize> = entries.iter().map(|(_, index, _)| *index).collect();
+    involved.sort_unstable();
+    involved.dedup();
+
+    let loader = ContentLoader::new(registry).with_archive(store);
+    if options.json {
+        let mut messages = vec![];
+        for (_, index, msg) in &entries {
+            let session = &sessions[*index];
+            let mut row = serde_json::to_value(msg)?;
+            row["session"] = serde_json::json!(session.short_hash);
+            row["source"] = serde_json::json!(session.source_name);
+            if options.full {
+                row["content"] = match loader.load(session, msg) {
+                    Ok(raw) => Value::Array(crate::export::content_json(&raw)),
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
                 };
+            }
+            messages.push(row);
+        }
+        return super::print_json(&serde_json::json!({
+            "project": project,
+            "date": day.to_string(),
+            "sessions": involved.iter().map(|i| &sessions[*i]).collect::<Vec<_>>(),
+            "messages": messages,
+        }));
+    }
+
+    let theme = theme::current();
+    let mut out = String::new();
+    let mut sources: Vec<&str> = involved
+        .iter()
+        .map(|index| sessions[*index].source_name.as_str())
+        .collect();
+    sources.sort_unstable();
+    sources.dedup();
+
+    writeln!(out, "\n{}", theme.heavy_rule(80))?;
+    writeln!(out, "Project: {} | {}", project.name, day)?;
+    writeln!(
+        out,
+        "{} messages from {} sessions ({})",
+        entries.len(),
+        involved.len(),
+        sources.join(", ")
+    )?;
+    writeln!(out, "{}", theme.heavy_rule(80))?;
+    if entries.is_empty() {
+        writeln!(out, "\nNo messages on {}.", day)?;
+    }
+
+    let mut current = None;
+    for (time, index, msg) in &entries {
+        let session = &sessions[*index];
+        if current != Some(*index) {
+            current = Some(*index);
+            writeln!(
+                out,
+                "\n>> {} | {} | {}",
+                session.short_hash,
+                theme.source(&session.source_name, 0),
+                session.title.as_deref().unwrap_or("-")
+            )?;
+        }
+        writeln!(
+            out,
+            "\n[{}{}] ({})",
+            role_label(msg),
+            msg.model
+                .as_deref()
+                .map(|m| format!(" | {}", m))
+                .unwrap_or_default(),
+            time.format("%H:%M:%S")
+        )?;
+        write_body(&mut out, &loader, session, msg, options)?;
+    }
 
-                match probe.get_content(&content_ref) {
-                    Ok(raw) => {
-                        // For JSONL sources, we might need to parse and extract content
-                        // For OpenCode, get_content already returns the extracted text
-                        if raw.trim().starts_with('{') {
-                            if let Ok(json) = serde_json::from_str::<Value>(&raw) {
-                                if let Some(content) =
-                                    json.get("message").and_then(|m| m.get("content"))
-                                {
-                                    print_content(content);
-                                } else if let Some(content) = json.get("content") {
-                                    print_content(content);
-                                } else {
-                                    println!("{}", raw);
-                                }
-                            } else {
-                                println!("{}", raw);
-                            }
+    show(&out, options)
+}
+
+/// A message of a merged transcript: its local time and the index of its session
+type Entry = (DateTime<Local>, usize, MessageRow);
+
+/// A project's sessions, and their messages on `day` in time order. Messages without a
+/// timestamp take the one before them in their session.
+fn day_entries(
+    store: &MetadataStore,
+    project_id: &str,
+    day: NaiveDate,
+    role: Option<&str>,
+) -> Result<(Vec<SessionRow>, Vec<Entry>)> {
+    let mut sessions = store.list_sessions(None, None)?;
+    sessions.retain(|s| s.project_id.as_deref() == Some(project_id));
+    sessions.sort_by(|a, b| a.first_timestamp.cmp(&b.first_timestamp));
+
+    let mut entries = vec![];
+    for (index, session) in sessions.iter().enumerate() {
+        let mut last = session.first_timestamp.as_deref().and_then(parse_time);
+        for msg in store.get_messages(&session.id)? {
+            let time = msg.timestamp.as_deref().and_then(parse_time).or(last);
+            last = time;
+            if role.is_some_and(|r| msg.role != r) {
+                continue;
+            }
+            if let Some(time) = time.filter(|t| t.date_naive() == day) {
+                entries.push((time, index, msg));
+            }
+        }
+    }
+    entries.sort_by_key(|(time, _, _)| *time);
+    Ok((sessions, entries))
+}
+
+/// `USER`, `ASSISTANT`, or e.g. `SYSTEM/COMPACT` for events within a role
+fn role_label(msg: &MessageRow) -> String {
+    match &msg.subtype {
+        Some(subtype) => format!("{}/{}", msg.role, subtype).to_uppercase(),
+        None => msg.role.to_uppercase(),
+    }
+}
+
+/// A message's content (or a hint to use --full), tool marker and separator
+fn write_body(
+    out: &mut String,
+    loader: &ContentLoader,
+    session: &SessionRow,
+    msg: &MessageRow,
+    options: &ReadOptions,
+) -> Result<()> {
+    let theme = theme::current();
+    if options.full {
+        // Only assistant output is rendered; user prompts are shown verbatim
+        let render = options.render && msg.role == "assistant";
+
+        match loader.load(session, msg) {
+            Ok(raw) => {
+                // For JSONL sources, we might need to parse and extract content
+                // For OpenCode, get_content already returns the extracted text
+                if raw.trim().starts_with('{') {
+                    if let Ok(json) = serde_json::from_str::<Value>(&raw) {
+                        if let Some(content) = json.get("message").and_then(|m| m.get("content")) {
+                            print_content(out, content, render)?;
+                        } else if let Some(content) = json.get("content") {
+                            print_content(out, content, render)?;
                         } else {
-                            println!("{}", raw);
+                            writeln!(out, "{}", raw)?;
                         }
+                    } else {
+                        writeln!(out, "{}", raw)?;
                     }
-                    Err(e) => println!("[Error loading content: {}]", e),
+                } else {
+                    print_text(out, &raw, render)?;
                 }
             }
-        } else {
-            println!("[Use --full to see content]");
-        }
-
-        if tools && msg.has_tool_use {
-            println!("  🔧 Has tool use");
+            Err(e) => writeln!(out, "[Error loading content: {}]", e)?,
         }
+    } else {
+        writeln!(out, "[Use --full to see content]")?;
+    }
 
-        println!("{}", "-".repeat(40));
+    if options.tools && msg.has_tool_use {
+        writeln!(out, "  {} Has tool use", theme.icon("🔧", "*"))?;
     }
 
+    writeln!(out, "{}", theme.rule(40))?;
+    Ok(())
+}
+
+/// Page long transcripts interactively; pipes and redirects get plain output
+fn show(out: &str, options: &ReadOptions) -> Result<()> {
+    if options.full && !options.no_pager && std::io::stdout().is_terminal() {
+        pager::page(out)?;
+    } else {
+        print!("{}", out);
+    }
     Ok(())
 }
 
-fn print_content(content: &Value) {
+fn parse_time(timestamp: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+fn print_json(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    session: &SessionRow,
+    full: bool,
+    selection: &MessageSelection,
+) -> Result<()> {
+    super::print_json(&session_json(store, registry, session, full, selection)?)
+}
+
+/// The session and its selected messages as JSON; `full` adds each message's content
+/// blocks
+pub fn session_json(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    session: &SessionRow,
+    full: bool,
+    selection: &MessageSelection,
+) -> Result<Value> {
+    let loader = ContentLoader::new(registry).with_archive(store);
+    let mut messages = vec![];
+    for (number, msg) in selection.apply(store.get_messages(&session.id)?) {
+        let mut row = serde_json::to_value(&msg)?;
+        row["number"] = serde_json::json!(number);
+        if full {
+            row["content"] = match loader.load(session, &msg) {
+                Ok(raw) => Value::Array(crate::export::content_json(&raw)),
+                Err(e) => serde_json::json!({ "error": e.to_string() }),
+            };
+        }
+        messages.push(row);
+    }
+    Ok(serde_json::json!({
+        "session": session,
+        "models": store.session_models(&session.id)?,
+        "request_params": store.session_request_params(&session.id)?,
+        "messages": messages,
+    }))
+}
+
+fn print_content(out: &mut String, content: &Value, render: bool) -> Result<()> {
     match content {
-        Value::String(s) => println!("{}", s),
+        Value::String(s) => print_text(out, s, render)?,
         Value::Array(arr) => {
             for item in arr {
                 if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                    println!("{}", text);
+                    print_text(out, text, render)?;
                 } else if item.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
                     if let Some(name) = item.get("name").and_then(|n| n.as_str()) {
-                        println!("  🔧 [Tool: {}]", name);
+                        writeln!(
+                            out,
+                            "  {} [Tool: {}]",
+                            theme::current().icon("🔧", "*"),
+                            name
+                        )?;
                     }
                 } else if item.get("type").and_then(|t| t.as_str()) == Some("thinking") {
                     if let Some(thinking) = item.get("thinking").and_then(|t| t.as_str()) {
-                        println!("  💭 [Thinking]\n{}", thinking);
+                        writeln!(out, "  💭 [Thinking]\n{}", thinking)?;
                     }
                 }
             }
         }
-        _ => println!("{}", content),
+        _ => writeln!(out, "{}", content)?,
+    }
+    Ok(())
+}
+
+fn print_text(out: &mut String, text: &str, render: bool) -> Result<()> {
+    if render {
+        out.push_str(&render_markdown(text));
+    } else {
+        writeln!(out, "{}", text)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures::{message, seed, seed_from, session};
+    use chrono::{TimeZone, Utc};
+    use std::path::Path;
+
+    #[test]
+    fn test_merged_day_across_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .create_project("p1", "app", "git", Some("/src/app"), None)
+            .unwrap();
+        let at = |d: u32, h: u32, m: u32| {
+            let local = Local.with_ymd_and_hms(2025, 3, d, h, m, 0).unwrap();
+            Some(local.with_timezone(&Utc))
+        };
+
+        // Claude Code runs through local midnight; its untimed reply follows 09:10
+        let claude = session(
+            "c1",
+            Some("/src/app"),
+            vec![
+                message("c-late", "user", at(9, 23, 59)),
+                message("c-midnight", "assistant", at(10, 0, 0)),
+                message("c-0900", "user", at(10, 9, 0)),
+                message("c-0910", "user", at(10, 9, 10)),
+                message("c-untimed", "assistant", None),
+            ],
+        );
+        let cursor = session(
+            "u1",
+            Some("/src/app"),
+            vec![
+                message("u-0905", "user", at(10, 9, 5)),
+                message("u-next", "assistant", at(11, 0, 0)),
+            ],
+        );
+        seed(&store, "claude:ClaudeCode", &claude);
+        seed(&store, "cursor:Cursor", &cursor);
+
+        let day = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let (sessions, entries) = day_entries(&store, "p1", day, None).unwrap();
+        let merged: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|(_, index, msg)| {
+                (
+                    sessions[*index].source_name.as_str(),
+                    msg.uuid.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            merged,
+            [
+                ("ClaudeCode", "c-midnight"),
+                ("ClaudeCode", "c-0900"),
+                ("Cursor", "u-0905"),
+                ("ClaudeCode", "c-0910"),
+                ("ClaudeCode", "c-untimed"),
+            ]
+        );
+
+        let (_, users) = day_entries(&store, "p1", day, Some("user")).unwrap();
+        assert_eq!(users.len(), 3);
+    }
+
+    #[test]
+    fn test_session_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        let folder = dir.path().join("my project");
+        std::fs::create_dir(&folder).unwrap();
+        let source = folder.join("abc.jsonl");
+        std::fs::write(&source, "{}\n").unwrap();
+        let messages = || vec![message("m", "user", None)];
+        let by_file = seed_from(
+            &store,
+            "claude:ClaudeCode",
+            &session("abc", None, messages()),
+            &source,
+        );
+        let by_id = seed(
+            &store,
+            "opencode:OpenCode",
+            &session("ses_xyz", None, messages()),
+        );
+        let found = |lookup: SessionLookup| lookup.resolve(&store).unwrap().map(|s| s.id);
+
+        // Paths resolve however they are spelled
+        let path = |p: &Path| SessionLookup::Path(p.to_string_lossy().to_string());
+        assert_eq!(found(path(&source)), Some(by_file.clone()));
+        let roundabout = folder.join("..").join("my project").join("abc.jsonl");
+        assert_eq!(found(path(&roundabout)), Some(by_file.clone()));
+        assert_eq!(found(path(&folder.join("other.jsonl"))), None);
+        // A query that looks like a path falls back to path lookup
+        let query = SessionLookup::Query(source.to_string_lossy().to_string());
+        assert_eq!(found(query), Some(by_file));
+
+        // External IDs match exactly; prefixes are for queries
+        let external = |id: &str| SessionLookup::ExternalId(id.to_string());
+        assert_eq!(found(external("ses_xyz")), Some(by_id.clone()));
+        assert_eq!(found(external("ses_x")), None);
+        assert_eq!(
+            found(SessionLookup::Query("ses_x".to_string())),
+            Some(by_id)
+        );
+    }
+
+    #[test]
+    fn test_session_json_shape() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        let mut reply = message("a1", "assistant", None);
+        reply.model = Some("claude-sonnet-4".to_string());
+        let metadata = session("abc", None, vec![message("u1", "user", None), reply]);
+        let session_id = seed(&store, "claude:ClaudeCode", &metadata);
+        let ids: Vec<i64> = store
+            .get_messages(&session_id)
+            .unwrap()
+            .iter()
+            .map(|m| m.id)
+            .collect();
+        // The source file doesn't exist: only the first message's cached copy can load
+        store
+            .cache_content(&[(ids[0], r#"{"content":"Fix the parser"}"#.to_string())])
+            .unwrap();
+        let session_row = store.get_session(&session_id).unwrap().unwrap();
+        let registry = ProbeRegistry::new(&crate::config::Config::default());
+
+        let json = session_json(
+            &store,
+            &registry,
+            &session_row,
+            true,
+            &MessageSelection::default(),
+        )
+        .unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["messages", "models", "request_params", "session"]);
+        assert_eq!(json["session"]["id"], session_id.as_str());
+        assert_eq!(json["session"]["external_id"], "abc");
+        assert_eq!(json["session"]["source_name"], "ClaudeCode");
+        assert_eq!(json["models"][0]["model"], "claude-sonnet-4");
+
+        let messages = json["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["number"], 1);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(messages[0]["uuid"], "u1");
+        assert_eq!(messages[0]["content"][0]["type"], "text");
+        assert_eq!(messages[0]["content"][0]["text"], "Fix the parser");
+        assert!(messages[1]["content"]["error"].is_string());
+
+        // Without --full there is no content, and numbers follow the selection
+        let selection = MessageSelection {
+            role: Some("assistant".to_string()),
+            ..Default::default()
+        };
+        let json = session_json(&store, &registry, &session_row, false, &selection).unwrap();
+        assert_eq!(json["messages"][0]["number"], 2);
+        assert!(json["messages"][0].get("content").is_none());
+    }
+
+    #[test]
+    fn test_message_selection() {
+        assert_eq!(MessageSelection::parse_range("3..5").unwrap(), (3, 5));
+        assert_eq!(MessageSelection::parse_range("..2").unwrap(), (1, 2));
+        assert_eq!(MessageSelection::parse_range("4..").unwrap().0, 4);
+        assert!(MessageSelection::parse_range("5..3").is_err());
+        assert!(MessageSelection::parse_range("0..3").is_err());
+
+        let messages = || {
+            ["user", "assistant", "user", "assistant", "assistant"]
+                .iter()
+                .map(|role| MessageRow {
+                    role: role.to_string(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        let numbers = |selection: MessageSelection| -> Vec<usize> {
+            selection
+                .apply(messages())
+                .iter()
+                .map(|(n, _)| *n)
+                .collect()
+        };
+        assert_eq!(
+            numbers(MessageSelection {
+                range: Some((2, 4)),
+                ..Default::default()
+            }),
+            [2, 3, 4]
+        );
+        // Role filter first, then the tail of what matched; numbers stay positional
+        assert_eq!(
+            numbers(MessageSelection {
+                role: Some("assistant".to_string()),
+                tail: Some(2),
+                ..Default::default()
+            }),
+            [4, 5]
+        );
     }
 }
@@ -0,0 +1,442 @@
+//! Terminal markdown rendering for `read --render`
+//!
+//! A small line-oriented renderer covering what assistants actually emit:
+//! headings, fenced code blocks (with keyword highlighting), lists, block
+//! quotes, rules and inline emphasis. Output uses plain ANSI escape codes.
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+/// Render markdown source into ANSI-decorated terminal text
+pub fn render_markdown(text: &str) -> String {
+    let mut out = String::new();
+    let mut code_lang: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        // Fenced code blocks
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            match code_lang {
+                Some(_) => {
+                    code_lang = None;
+                    out.push_str(&format!("{}└─{}\n", DIM, RESET));
+                }
+                None => {
+                    let lang = trimmed[3..].trim().to_lowercase();
+                    out.push_str(&format!("{}┌─ {}{}\n", DIM, lang, RESET));
+                    code_lang = Some(lang);
+                }
+            }
+            continue;
+        }
+
+        if let Some(ref lang) = code_lang {
+            out.push_str(&format!(
+                "{}│{} {}\n",
+                DIM,
+                RESET,
+                highlight_code(line, lang)
+            ));
+            continue;
+        }
+
+        // Headings
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            let heading = render_inline(trimmed[hashes..].trim());
+            let style = if hashes == 1 {
+                format!("{}{}{}", BOLD, UNDERLINE, BLUE)
+            } else {
+                format!("{}{}", BOLD, BLUE)
+            };
+            out.push_str(&format!("{}{}{}\n", style, heading, RESET));
+            continue;
+        }
+
+        // Horizontal rules
+        if is_rule(trimmed) {
+            out.push_str(&format!("{}{}{}\n", DIM, "─".repeat(40), RESET));
+            continue;
+        }
+
+        // Block quotes
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            out.push_str(&format!(
+                "{}▎{} {}{}{}\n",
+                DIM,
+                RESET,
+                ITALIC,
+                render_inline(quote.trim_start()),
+                RESET
+            ));
+            continue;
+        }
+
+        // Lists
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .or_else(|| trimmed.strip_prefix("+ "))
+        {
+            let item = render_task_marker(item);
+            out.push_str(&format!(
+                "{}{}•{} {}\n",
+                indent,
+                YELLOW,
+                RESET,
+                render_inline(&item)
+            ));
+            continue;
+        }
+        if let Some((number, item)) = split_ordered_item(trimmed) {
+            out.push_str(&format!(
+                "{}{}{}.{} {}\n",
+                indent,
+                YELLOW,
+                number,
+                RESET,
+                render_inline(item)
+            ));
+            continue;
+        }
+
+        out.push_str(&render_inline(line));
+        out.push('\n');
+    }
+
+    // Close an unterminated code block so the frame stays balanced
+    if code_lang.is_some() {
+        out.push_str(&format!("{}└─{}\n", DIM, RESET));
+    }
+
+    out
+}
+
+/// Remove ANSI escape sequences, e.g. for searching or measuring rendered text
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_escape = false;
+    for c in s.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            if c == 'm' {
+                in_escape = false;
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && (compact.chars().all(|c| c == '-')
+            || compact.chars().all(|c| c == '*')
+            || compact.chars().all(|c| c == '_'))
+}
+
+fn render_task_marker(item: &str) -> String {
+    if let Some(rest) = item.strip_prefix("[ ] ") {
+        format!("☐ {}", rest)
+    } else if let Some(rest) = item
+        .strip_prefix("[x] ")
+        .or_else(|| item.strip_prefix("[X] "))
+    {
+        format!("☑ {}", rest)
+    } else {
+        item.to_string()
+    }
+}
+
+fn split_ordered_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let rest = &line[digits..];
+    let item = rest
+        .strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))?;
+    Some((&line[..digits], item))
+}
+
+/// Render inline markup: `code`, **bold**, *italic*/_italic_ and [links](url)
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '`' {
+            if let Some(end) = find_char(&chars, i + 1, '`') {
+                let code: String = chars[i + 1..end].iter().collect();
+                out.push_str(&format!("{}{}{}", CYAN, code, RESET));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if (c == '*' || c == '_') && chars.get(i + 1) == Some(&c) {
+            if let Some(end) = find_pair(&chars, i + 2, c) {
+                let inner: String = chars[i + 2..end].iter().collect();
+                out.push_str(&format!("{}{}{}", BOLD, render_inline(&inner), RESET));
+                i = end + 2;
+                continue;
+            }
+        }
+
+        if (c == '*' || c == '_') && chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) {
+            // Avoid treating snake_case identifiers as emphasis
+            let word_before = i > 0 && chars[i - 1].is_alphanumeric();
+            if !(c == '_' && word_before) {
+                if let Some(end) = find_char(&chars, i + 1, c) {
+                    let inner: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&format!("{}{}{}", ITALIC, inner, RESET));
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+
+        if c == '[' {
+            if let Some(close) = find_char(&chars, i + 1, ']') {
+                if chars.get(close + 1) == Some(&'(') {
+                    if let Some(paren) = find_char(&chars, close + 2, ')') {
+                        let label: String = chars[i + 1..close].iter().collect();
+                        let url: String = chars[close + 2..paren].iter().collect();
+                        out.push_str(&format!(
+                            "{}{}{} {}({}){}",
+                            UNDERLINE, label, RESET, DIM, url, RESET
+                        ));
+                        i = paren + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        out.push(c);
+        i += 1;
+    }
+
+    out
+}
+
+fn find_char(chars: &[char], start: usize, target: char) -> Option<usize> {
+    (start..chars.len()).find(|&j| chars[j] == target)
+}
+
+fn find_pair(chars: &[char], start: usize, target: char) -> Option<usize> {
+    (start..chars.len().saturating_sub(1)).find(|&j| chars[j] == target && chars[j + 1] == target)
+}
+
+/// Keyword-level syntax highlighting for a single line of code
+fn highlight_code(line: &str, lang: &str) -> String {
+    let keywords = keywords_for(lang);
+    let comment_prefix = match lang {
+        "python" | "py" | "sh" | "bash" | "zsh" | "shell" | "yaml" | "yml" | "toml" | "ruby"
+        | "rb" => "#",
+        "sql" => "--",
+        _ => "//",
+    };
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest: String = chars[i..].iter().collect();
+
+        if rest.starts_with(comment_prefix) {
+            out.push_str(&format!("{}{}{}", DIM, rest, RESET));
+            break;
+        }
+
+        let c = chars[i];
+
+        if c == '"' || c == '\'' || c == '`' {
+            let end = (i + 1..chars.len())
+                .find(|&j| chars[j] == c && chars[j - 1] != '\\')
+                .unwrap_or(chars.len() - 1);
+            let literal: String = chars[i..=end].iter().collect();
+            out.push_str(&format!("{}{}{}", GREEN, literal, RESET));
+            i = end + 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() && (i == 0 || !is_ident_char(chars[i - 1])) {
+            let end = (i..chars.len())
+                .find(|&j| {
+                    !(chars[j].is_ascii_alphanumeric() || chars[j] == '.' || chars[j] == '_')
+                })
+                .unwrap_or(chars.len());
+            let number: String = chars[i..end].iter().collect();
+            out.push_str(&format!("{}{}{}", CYAN, number, RESET));
+            i = end;
+            continue;
+        }
+
+        if is_ident_char(c) {
+            let end = (i..chars.len())
+                .find(|&j| !is_ident_char(chars[j]))
+                .unwrap_or(chars.len());
+            let word: String = chars[i..end].iter().collect();
+            if keywords.contains(&word.as_str()) {
+                out.push_str(&format!("{}{}{}", MAGENTA, word, RESET));
+            } else if word.chars().next().is_some_and(|f| f.is_uppercase()) {
+                out.push_str(&format!("{}{}{}", YELLOW, word, RESET));
+            } else {
+                out.push_str(&word);
+            }
+            i = end;
+            continue;
+        }
+
+        if matches!(c, '{' | '}' | '(' | ')' | '[' | ']') {
+            out.push_str(&format!("{}{}{}", RED, c, RESET));
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+
+    out
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn keywords_for(lang: &str) -> &'static [&'static str] {
+    match lang {
+        "rust" | "rs" => &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+            "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+            "type", "unsafe", "use", "where", "while",
+        ],
+        "python" | "py" => &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in",
+            "is", "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while",
+            "with", "yield",
+        ],
+        "javascript" | "js" | "typescript" | "ts" | "tsx" | "jsx" => &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "while",
+            "yield",
+        ],
+        "go" => &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "false",
+            "for",
+            "func",
+            "go",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        "sh" | "bash" | "zsh" | "shell" => &[
+            "case", "do", "done", "echo", "elif", "else", "esac", "export", "fi", "for",
+            "function", "if", "in", "local", "return", "then", "while",
+        ],
+        "sql" => &[
+            "AND", "AS", "BY", "CREATE", "DELETE", "FROM", "GROUP", "INDEX", "INSERT", "INTO",
+            "JOIN", "LEFT", "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER", "SELECT", "SET", "TABLE",
+            "UPDATE", "VALUES", "WHERE",
+        ],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headings_and_lists() {
+        let rendered = strip_ansi(&render_markdown("# Title\n- one\n2. two"));
+        assert_eq!(rendered, "Title\n• one\n2. two\n");
+    }
+
+    #[test]
+    fn test_code_block_is_framed_and_unterminated_block_closed() {
+        let rendered = strip_ansi(&render_markdown("```rust\nlet x = 1;"));
+        assert_eq!(rendered, "┌─ rust\n│ let x = 1;\n└─\n");
+    }
+
+    #[test]
+    fn test_inline_markup_and_snake_case() {
+        let rendered = strip_ansi(&render_inline("use `foo_bar` and **bold** in my_var"));
+        assert_eq!(rendered, "use foo_bar and bold in my_var");
+    }
+}
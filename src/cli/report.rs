@@ -0,0 +1,306 @@
+//! Report command implementation
+//!
+//! Summarizes the current day, week or month per project: sessions, messages, token
+//! spend, busiest tools and models. Printed as a table; `--output` also writes Markdown
+//! for a standup note or journal.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use super::costs::format_cost;
+use super::truncate;
+use crate::analysis::{Pricing, TokenCounts};
+use crate::config::Config;
+use crate::store::{ActivityRow, MetadataStore, ToolUsageRow, UsageRow};
+
+/// Tools and models listed per project
+const TOP_ITEMS: usize = 3;
+
+/// Activity of one project in the period
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectActivity {
+    pub project: String,
+    pub sessions: usize,
+    pub messages: i64,
+    pub tokens: i64,
+    pub estimated_cost: f64,
+    /// Most-called tools, busiest first
+    pub tools: Vec<(String, i64)>,
+    /// Models by messages sent, busiest first
+    pub models: Vec<(String, i64)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub period: String,
+    pub since: DateTime<Utc>,
+    pub projects: Vec<ProjectActivity>,
+}
+
+impl Report {
+    fn total<T: std::iter::Sum<T>>(&self, field: impl Fn(&ProjectActivity) -> T) -> T {
+        self.projects.iter().map(field).sum()
+    }
+}
+
+pub fn run(
+    store: &MetadataStore,
+    config: &Config,
+    period: &str,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let since = super::timeparse::parse(match period {
+        "day" => "today",
+        "week" => "this week",
+        "month" => "this month",
+        other => bail!("Unknown period: {} (expected day, week or month)", other),
+    })?;
+    let start = since.to_rfc3339();
+    let report = Report {
+        period: period.to_string(),
+        since,
+        projects: summarize(
+            &store.message_activity(&start)?,
+            &store.token_usage(Some(&start))?,
+            &store.tool_usage(Some(&start))?,
+            &config.pricing(),
+        ),
+    };
+
+    if let Some(path) = output {
+        std::fs::write(&path, markdown(&report)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("Wrote report to {}", path.display());
+    }
+    if json {
+        return super::print_json(&report);
+    }
+    print_table(&report);
+    Ok(())
+}
+
+/// Per-project activity, most messages first
+pub fn summarize(
+    activity: &[ActivityRow],
+    usage: &[UsageRow],
+    tools: &[ToolUsageRow],
+    pricing: &Pricing,
+) -> Vec<ProjectActivity> {
+    let name = |project: &Option<String>| {
+        project
+            .clone()
+            .unwrap_or_else(|| "(unassigned)".to_string())
+    };
+
+    #[derive(Default)]
+    struct Totals {
+        sessions: BTreeSet<String>,
+        messages: i64,
+        tokens: TokenCounts,
+        cost: f64,
+        tools: BTreeMap<String, i64>,
+        models: BTreeMap<String, i64>,
+    }
+    let mut projects: BTreeMap<String, Totals> = BTreeMap::new();
+    for row in activity {
+        let totals = projects.entry(name(&row.project_name)).or_default();
+        totals.sessions.insert(row.session_id.clone());
+        totals.messages += row.messages;
+        if let Some(ref model) = row.model {
+            *totals.models.entry(model.clone()).or_default() += row.messages;
+        }
+    }
+    for row in usage {
+        let totals = projects.entry(name(&row.project_name)).or_default();
+        totals.tokens.add(&row.tokens);
+        totals.cost += pricing
+            .estimate(row.model.as_deref(), &row.tokens)
+            .unwrap_or(0.0);
+    }
+    for row in tools {
+        let totals = projects.entry(name(&row.project_name)).or_default();
+        *totals.tools.entry(row.tool_name.clone()).or_default() += row.calls;
+    }
+
+    let top = |counts: BTreeMap<String, i64>| {
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(TOP_ITEMS);
+        counts
+    };
+    let mut summary: Vec<ProjectActivity> = projects
+        .into_iter()
+        .map(|(project, totals)| ProjectActivity {
+            project,
+            sessions: totals.sessions.len(),
+            messages: totals.messages,
+            tokens: totals.tokens.total(),
+            estimated_cost: totals.cost,
+            tools: top(totals.tools),
+            models: top(totals.models),
+        })
+        .collect();
+    summary.sort_by(|a, b| {
+        b.messages
+            .cmp(&a.messages)
+            .then_with(|| a.project.cmp(&b.project))
+    });
+    summary
+}
+
+fn heading(report: &Report) -> String {
+    let since = report.since.with_timezone(&Local).format("%Y-%m-%d");
+    match report.period.as_str() {
+        "day" => format!("Activity on {}", since),
+        period => format!("Activity for the {} of {}", period, since),
+    }
+}
+
+fn names(items: &[(String, i64)]) -> String {
+    match items.is_empty() {
+        true => "-".to_string(),
+        false => items
+            .iter()
+            .map(|(name, count)| format!("{} ({})", name, count))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn print_table(report: &Report) {
+    println!("{}\n", heading(report));
+    if report.projects.is_empty() {
+        println!("No activity in this period.");
+        return;
+    }
+
+    println!(
+        "{:<24} {:>8} {:>9} {:>12} {:>10}  Top tools / models",
+        "Project", "Sessions", "Messages", "Tokens", "Est. cost"
+    );
+    let theme = super::theme::current();
+    println!("{}", theme.rule(100));
+    for project in &report.projects {
+        println!(
+            "{:<24} {:>8} {:>9} {:>12} {:>10}  {}",
+            truncate(&project.project, 24),
+            project.sessions,
+            project.messages,
+            super::group_thousands(project.tokens.max(0) as usize),
+            format_cost(project.estimated_cost),
+            names(&project.tools)
+        );
+        println!("{:>69}{}", "", names(&project.models));
+    }
+    println!("{}", theme.rule(100));
+    println!(
+        "{:<24} {:>8} {:>9} {:>12} {:>10}",
+        "Total",
+        report.total(|p| p.sessions),
+        report.total(|p| p.messages),
+        super::group_thousands(report.total(|p| p.tokens).max(0) as usize),
+        format_cost(report.total(|p| p.estimated_cost))
+    );
+}
+
+/// The report as a Markdown document
+pub fn markdown(report: &Report) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "# {}\n", heading(report))?;
+    if report.projects.is_empty() {
+        writeln!(out, "No activity in this period.")?;
+        return Ok(out);
+    }
+
+    writeln!(
+        out,
+        "_{} session(s) · {} message(s) · {} tokens · {}_\n",
+        report.total(|p| p.sessions),
+        report.total(|p| p.messages),
+        super::group_thousands(report.total(|p| p.tokens).max(0) as usize),
+        format_cost(report.total(|p| p.estimated_cost))
+    )?;
+    writeln!(
+        out,
+        "| Project | Sessions | Messages | Tokens | Est. cost | Top tools | Top models |"
+    )?;
+    writeln!(out, "|---|---:|---:|---:|---:|---|---|")?;
+    for project in &report.projects {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            project.project.replace('|', "\\|"),
+            project.sessions,
+            project.messages,
+            super::group_thousands(project.tokens.max(0) as usize),
+            format_cost(project.estimated_cost),
+            names(&project.tools),
+            names(&project.models)
+        )?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_per_project() {
+        let activity = |session: &str, project: Option<&str>, model: &str, messages| ActivityRow {
+            session_id: session.to_string(),
+            project_name: project.map(str::to_string),
+            model: Some(model.to_string()),
+            messages,
+        };
+        let rows = [
+            activity("a", Some("web"), "claude-sonnet-4-5", 10),
+            activity("a", Some("web"), "claude-opus-4-1", 4),
+            activity("b", Some("web"), "claude-sonnet-4-5", 6),
+            activity("c", None, "gpt-5", 30),
+        ];
+        let usage = [UsageRow {
+            session_id: "a".to_string(),
+            short_hash: "a".to_string(),
+            probe_source_id: "claude-code:ClaudeCode".to_string(),
+            project_name: Some("web".to_string()),
+            model: Some("claude-sonnet-4-5".to_string()),
+            day: None,
+            tokens: TokenCounts {
+                input: 1_000_000,
+                ..Default::default()
+            },
+        }];
+        let tools = [ToolUsageRow {
+            tool_name: "Bash".to_string(),
+            project_name: Some("web".to_string()),
+            provider: None,
+            day: None,
+            calls: 7,
+            unanswered: 0,
+            errors: 0,
+            last_used: None,
+        }];
+
+        let summary = summarize(&rows, &usage, &tools, &Pricing::new(&BTreeMap::new()));
+        assert_eq!(summary[0].project, "(unassigned)");
+        let web = &summary[1];
+        assert_eq!((web.sessions, web.messages), (2, 20));
+        assert_eq!((web.tokens, web.estimated_cost), (1_000_000, 3.0));
+        assert_eq!(web.tools, vec![("Bash".to_string(), 7)]);
+        assert_eq!(web.models[0], ("claude-sonnet-4-5".to_string(), 16));
+
+        let report = Report {
+            period: "week".to_string(),
+            since: Utc::now(),
+            projects: summary,
+        };
+        let markdown = markdown(&report).unwrap();
+        assert!(markdown.contains("| web | 2 | 20 | 1,000,000 | $3.00 | Bash (7) |"));
+    }
+}
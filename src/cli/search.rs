@@ -0,0 +1,57 @@
+//! `chronicle search` - find sessions by title, summary, project or path
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::Page;
+use crate::error::Error;
+use crate::store::{MetadataStore, SearchRank};
+
+pub fn run(
+    store: &MetadataStore,
+    text: &str,
+    rank: &str,
+    project: Option<&str>,
+    json: bool,
+    page: Page,
+) -> Result<()> {
+    let rank = SearchRank::parse(rank)?;
+    // Boost the named project, else the one the current directory belongs to
+    let project_id = match project {
+        Some(query) => Some(
+            store
+                .find_project(query)?
+                .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?
+                .id,
+        ),
+        None => match std::env::current_dir() {
+            Ok(dir) => project_for_dir(store, &dir)?,
+            Err(_) => None,
+        },
+    };
+
+    let sessions = store.search_sessions(text, rank, project_id.as_deref())?;
+    let (sessions, total) = page.apply(sessions);
+    if json {
+        super::print_json(&sessions)?;
+        page.print_footer(sessions.len(), total, true);
+        return Ok(());
+    }
+    if total == 0 {
+        println!("No sessions match '{}'.", text);
+        return Ok(());
+    }
+    super::list::print_sessions(&sessions);
+    page.print_footer(sessions.len(), total, false);
+    Ok(())
+}
+
+/// Project registered for `dir` or its nearest registered ancestor
+fn project_for_dir(store: &MetadataStore, dir: &Path) -> Result<Option<String>> {
+    for ancestor in dir.ancestors() {
+        if let Some(project_id) = store.find_project_by_path(&ancestor.to_string_lossy())? {
+            return Ok(Some(project_id));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,378 @@
+//! Serve command implementation
+//!
+//! A small read-only JSON API over the index, for a web UI or queries from other
+//! machines. Each connection is handled on its own thread, reading through a pool of
+//! read-only connections so slow queries don't hold up other requests.
+//!
+//! - `GET /api/sessions?provider=&source=&limit=&page=`
+//! - `GET /api/sessions/{id}` (`?full=true` adds content blocks)
+//! - `GET /api/projects`, `GET /api/projects/{id}`
+//! - `GET /api/search?q=&rank=relevance|recent&project=`
+//! - `GET /api/stats`
+//!
+//! Private projects and their sessions are hidden; only stats aggregate over them, and
+//! their budgets are left out.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use super::Page;
+use crate::config::Config;
+use crate::probe::ProbeRegistry;
+use crate::store::{Audience, MetadataStore, SearchRank, SessionRow};
+
+/// Largest request head accepted
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Read connections kept open between requests
+const MAX_IDLE_READERS: usize = 4;
+
+/// Serve the API on `host:port` until interrupted
+pub fn run(
+    store: &MetadataStore,
+    registry: &ProbeRegistry,
+    config: &Config,
+    host: &str,
+    port: u16,
+) -> Result<()> {
+    let listener = TcpListener::bind((host, port))
+        .with_context(|| format!("Failed to listen on {}:{}", host, port))?;
+    println!(
+        "Serving the Chronicle API on http://{}",
+        listener.local_addr()?
+    );
+    if !matches!(host, "127.0.0.1" | "localhost" | "::1") {
+        println!(
+            "Note: the API has no authentication; anyone who can reach this address \
+             can read your history."
+        );
+    }
+
+    let pool = store.read_pool(MAX_IDLE_READERS)?;
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Connection failed: {}", e);
+                    continue;
+                }
+            };
+            let pool = &pool;
+            scope.spawn(move || {
+                let result = pool.get().and_then(|store| {
+                    let api = Api {
+                        store: &store,
+                        registry,
+                        config,
+                    };
+                    api.handle(stream)
+                });
+                if let Err(e) = result {
+                    eprintln!("Request failed: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+struct Api<'a> {
+    store: &'a MetadataStore,
+    registry: &'a ProbeRegistry,
+    config: &'a Config,
+}
+
+impl Api<'_> {
+    fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        // Headers are read and ignored; the API takes no request bodies
+        let mut head = request_line.len();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            head += line.len();
+            if head > MAX_HEAD_BYTES {
+                return respond(&mut stream, 431, &error("request header too large"));
+            }
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+        let (path, query) = parse_target(target);
+        let (status, body) = match method {
+            "GET" => self
+                .route(&path, &query)
+                .unwrap_or_else(|e| (500, error(&format!("{:#}", e)))),
+            _ => (405, error("only GET is supported")),
+        };
+        respond(&mut stream, status, &body)
+    }
+
+    fn route(&self, path: &str, query: &HashMap<String, String>) -> Result<(u16, Value)> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let page = match page(query) {
+            Ok(page) => page,
+            Err(e) => return Ok((400, error(&e.to_string()))),
+        };
+        Ok(match segments.as_slice() {
+            ["api", "sessions"] => {
+                let sessions = self.store.list_sessions(
+                    query.get("provider").map(String::as_str),
+                    query.get("source").map(String::as_str),
+                )?;
+                (200, paged("sessions", self.visible(sessions)?, page)?)
+            }
+            ["api", "sessions", id] => match self.store.get_session(id)? {
+                Some(session) if !self.hidden(&session)? => {
+                    let full = query.get("full").is_some_and(|v| v == "true" || v == "1");
+                    let mut body = super::read::session_json(
+                        self.store,
+                        self.registry,
+                        &session,
+                        full,
+                        &Default::default(),
+                    )?;
+                    body["enrichments"] = json!(self
+                        .store
+                        .session_enrichments(&session.id)?
+                        .into_iter()
+                        .collect::<HashMap<_, _>>());
+                    (200, body)
+                }
+                _ => (404, error(&format!("session '{}' not found", id))),
+            },
+            ["api", "projects"] => {
+                let mut body = super::project::list_json(self.store, self.config)?;
+                let withheld = self.store.withheld_projects(Audience::Team)?;
+                if let Some(projects) = body["projects"].as_array_mut() {
+                    projects.retain(|p| {
+                        p["id"]
+                            .as_str()
+                            .is_some_and(|id| !withheld.contains_key(id))
+                    });
+                }
+                (200, body)
+            }
+            ["api", "projects", id] => match self.store.find_project(id)? {
+                Some(project) if project.visibility.allows(Audience::Team) => {
+                    let paths = self.store.get_project_paths(&project.id)?;
+                    let mut sessions = self.store.list_sessions(None, None)?;
+                    sessions.retain(|s| s.project_id.as_deref() == Some(project.id.as_str()));
+                    let mut body = paged("sessions", sessions, page)?;
+                    body["project"] = json!(project);
+                    body["paths"] = json!(paths);
+                    (200, body)
+                }
+                _ => (404, error(&format!("project '{}' not found", id))),
+            },
+            ["api", "search"] => match query.get("q").map(|q| q.trim()) {
+                Some(q) if !q.is_empty() => {
+                    let rank = match SearchRank::parse(query.get("rank").map_or("relevance", |r| r))
+                    {
+                        Ok(rank) => rank,
+                        Err(e) => return Ok((400, error(&e.to_string()))),
+                    };
+                    let project = match query.get("project") {
+                        Some(p) => self.store.find_project(p)?.map(|p| p.id),
+                        None => None,
+                    };
+                    let sessions = self.store.search_sessions(q, rank, project.as_deref())?;
+                    (200, paged("sessions", self.visible(sessions)?, page)?)
+                }
+                _ => (400, error("missing query parameter 'q'")),
+            },
+            ["api", "stats"] => {
+                let mut body = super::stats::report(self.store, self.config)?;
+                // Budgets name their projects, so private ones are left out
+                let withheld = self.store.withheld_projects(Audience::Team)?;
+                let hidden: Vec<String> = self
+                    .store
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| withheld.contains_key(&p.id))
+                    .map(|p| p.name)
+                    .collect();
+                if let Some(budgets) = body["budgets"].as_array_mut() {
+                    budgets.retain(|b| {
+                        b["project"]
+                            .as_str()
+                            .is_none_or(|name| !hidden.iter().any(|h| h == name))
+                    });
+                }
+                (200, body)
+            }
+            _ => (404, error(&format!("no route for {}", path))),
+        })
+    }
+}
+
+impl Api<'_> {
+    /// Sessions the API may show: those outside private projects
+    fn visible(&self, mut sessions: Vec<SessionRow>) -> Result<Vec<SessionRow>> {
+        super::withhold(self.store, &mut sessions, Audience::Team)?;
+        Ok(sessions)
+    }
+
+    fn hidden(&self, session: &SessionRow) -> Result<bool> {
+        Ok(match session.project_id {
+            Some(ref id) => self
+                .store
+                .withheld_projects(Audience::Team)?
+                .contains_key(id),
+            None => false,
+        })
+    }
+}
+
+/// `limit` and `page` query parameters, defaulting like the CLI
+fn page(query: &HashMap<String, String>) -> Result<Page> {
+    let number = |name: &str, default: usize| -> Result<usize> {
+        match query.get(name) {
+            Some(value) => value
+                .parse()
+                .with_context(|| format!("invalid {}: {}", name, value)),
+            None => Ok(default),
+        }
+    };
+    Ok(Page {
+        limit: number("limit", Page::DEFAULT_LIMIT)?,
+        page: number("page", 1)?.max(1),
+    })
+}
+
+/// One page of rows under `key`, with the total row count
+fn paged<T: serde::Serialize>(key: &str, rows: Vec<T>, page: Page) -> Result<Value> {
+    let (rows, total) = page.apply(rows);
+    let mut body = json!({ "total": total, "page": page.page, "limit": page.limit });
+    body[key] = serde_json::to_value(rows)?;
+    Ok(body)
+}
+
+fn error(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Split a request target into its decoded path and query parameters
+fn parse_target(target: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    (decode(path), params)
+}
+
+/// Percent-decode a URL component (`+` is a space)
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target_decodes_query() {
+        let (path, query) = parse_target("/api/search?q=tool+loop%3F&limit=5&full");
+        assert_eq!(path, "/api/search");
+        assert_eq!(query["q"], "tool loop?");
+        assert_eq!(query["limit"], "5");
+        assert_eq!(query["full"], "");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(page(&query).unwrap(), Page { limit: 5, page: 1 });
+    }
+
+    #[test]
+    fn test_stats_leave_out_private_budgets() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .create_project("p1", "app", "git", Some("/src/app"), None)
+            .unwrap();
+        store
+            .create_project("p2", "secret", "git", Some("/src/secret"), None)
+            .unwrap();
+        store
+            .set_project_visibility("p2", crate::store::Visibility::Private)
+            .unwrap();
+        let config: Config = serde_yaml::from_str(
+            "budgets:\n  daily_usd: 20\n  per_project:\n    app: 5\n    secret: 5\n",
+        )
+        .unwrap();
+        let registry = ProbeRegistry::new(&config);
+        let api = Api {
+            store: &store,
+            registry: &registry,
+            config: &config,
+        };
+
+        let (status, body) = api.route("/api/stats", &HashMap::new()).unwrap();
+        assert_eq!(status, 200);
+        let projects: Vec<&Value> = body["budgets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| &b["project"])
+            .collect();
+        assert_eq!(projects, [&Value::Null, &json!("app")]);
+    }
+}
@@ -1,18 +1,21 @@
-use crate::store::MetadataStore;
-use anyhow::Result;
+use crate::config::Config;
+use crate::error::Error;
+use crate::store::{MetadataStore, SessionQuery};
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
 
 pub fn assign(store: &MetadataStore, session_query: String, project_query: String) -> Result<()> {
     // Find session
     let session = store
         .get_session(&session_query)?
-        .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_query))?;
+        .ok_or_else(|| Error::SessionNotFound(session_query.to_string()))?;
 
     // Find project
-    let projects = store.list_projects()?;
-    let project = projects
-        .iter()
-        .find(|p| p.id.starts_with(&project_query) || p.name == project_query)
-        .ok_or_else(|| anyhow::anyhow!("Project not found: {}", project_query))?;
+    let project = store
+        .find_project(&project_query)?
+        .ok_or_else(|| Error::ProjectNotFound(project_query.to_string()))?;
 
     store.assign_session_to_project(&session.id, Some(&project.id))?;
     println!(
@@ -22,12 +25,336 @@ pub fn assign(store: &MetadataStore, session_query: String, project_query: Strin
     Ok(())
 }
 
+/// Which sessions `session assign-bulk` assigns
+#[derive(Debug, Default)]
+pub struct BulkFilter {
+    /// Directory prefix, or a glob over the whole working directory
+    pub path_prefix: Option<String>,
+    pub provider: Option<String>,
+    /// Time expressions (see [`super::timeparse`]) bounding the last activity
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+pub fn assign_bulk(
+    store: &MetadataStore,
+    filter: BulkFilter,
+    project_query: String,
+    dry_run: bool,
+) -> Result<()> {
+    if filter.path_prefix.is_none()
+        && filter.provider.is_none()
+        && filter.before.is_none()
+        && filter.after.is_none()
+    {
+        anyhow::bail!("Give at least one of --path-prefix, --provider, --before or --after");
+    }
+    let project = store
+        .find_project(&project_query)?
+        .ok_or_else(|| Error::ProjectNotFound(project_query.to_string()))?;
+    let bound = |expr: &Option<String>| -> Result<Option<String>> {
+        expr.as_deref()
+            .map(|e| super::timeparse::parse(e).map(|t| t.to_rfc3339()))
+            .transpose()
+    };
+    let (path_prefix, path_glob) = match filter.path_prefix {
+        Some(path) if path.contains(['*', '?', '[']) => (None, Some(path)),
+        Some(path) => {
+            let expanded = shellexpand::tilde(&path).to_string();
+            let trimmed = expanded.trim_end_matches('/');
+            // Keep "/" itself
+            let prefix = if trimmed.is_empty() { "/" } else { trimmed };
+            (Some(prefix.to_string()), None)
+        }
+        None => (None, None),
+    };
+    let query = SessionQuery {
+        provider: filter.provider,
+        since: bound(&filter.after)?,
+        until: bound(&filter.before)?,
+        archived: true,
+        path_prefix,
+        path_glob,
+        ..Default::default()
+    };
+
+    if dry_run {
+        let sessions = store.query_sessions(&query)?;
+        let moving = sessions
+            .iter()
+            .filter(|s| s.project_id.as_deref() != Some(&project.id))
+            .count();
+        println!(
+            "{} session(s) match; {} would be assigned to project '{}'",
+            sessions.len(),
+            moving,
+            project.name
+        );
+        return Ok(());
+    }
+    let assigned = store.assign_sessions(&query, &project.id)?;
+    println!(
+        "Assigned {} session(s) to project '{}'",
+        assigned, project.name
+    );
+    Ok(())
+}
+
 pub fn unassign(store: &MetadataStore, session_query: String) -> Result<()> {
     let session = store
         .get_session(&session_query)?
-        .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_query))?;
+        .ok_or_else(|| Error::SessionNotFound(session_query.to_string()))?;
 
     store.unassign_session(&session.id)?;
     println!("Unassigned session '{}'", session.short_hash);
     Ok(())
 }
+
+/// Archive the given sessions, plus every session with at most `max_messages` messages
+pub fn archive(
+    store: &MetadataStore,
+    session_queries: Vec<String>,
+    max_messages: Option<i64>,
+) -> Result<()> {
+    if session_queries.is_empty() && max_messages.is_none() {
+        anyhow::bail!("Name sessions to archive, or use --max-messages");
+    }
+    let mut ids = vec![];
+    for query in &session_queries {
+        let session = store
+            .get_session(query)?
+            .ok_or_else(|| Error::SessionNotFound(query.to_string()))?;
+        ids.push(session.id);
+    }
+    if let Some(max) = max_messages {
+        ids.extend(
+            store
+                .list_sessions(None, None)?
+                .into_iter()
+                .filter(|s| s.message_count <= max)
+                .map(|s| s.id),
+        );
+    }
+    ids.sort();
+    ids.dedup();
+
+    let mut archived = 0;
+    store.transaction(|| {
+        for id in &ids {
+            archived += store.set_session_archived(id, true)? as usize;
+        }
+        Ok(())
+    })?;
+    println!(
+        "Archived {} session(s){}",
+        archived,
+        match ids.len() - archived {
+            0 => String::new(),
+            n => format!(" ({} already archived)", n),
+        }
+    );
+    Ok(())
+}
+
+pub fn unarchive(store: &MetadataStore, session_queries: Vec<String>) -> Result<()> {
+    for query in &session_queries {
+        let session = store
+            .get_session(query)?
+            .ok_or_else(|| Error::SessionNotFound(query.to_string()))?;
+        match store.set_session_archived(&session.id, false)? {
+            true => println!("Unarchived session '{}'", session.short_hash),
+            false => println!("Session '{}' was not archived", session.short_hash),
+        }
+    }
+    Ok(())
+}
+
+pub fn split(store: &MetadataStore, session_query: String, at: usize) -> Result<()> {
+    let session = store
+        .get_session(&session_query)?
+        .ok_or_else(|| Error::SessionNotFound(session_query.to_string()))?;
+
+    let derived = store.split_session(&session, at)?;
+    println!(
+        "Split session '{}' at message {} into new session '{}'",
+        session.short_hash, at, derived
+    );
+    Ok(())
+}
+
+/// Print the raw source files behind a session and each message's location in them;
+/// optionally open the session source in `$EDITOR` or reveal it in the file manager
+pub fn open_source(
+    store: &MetadataStore,
+    session_query: String,
+    edit: bool,
+    reveal: bool,
+) -> Result<()> {
+    let session = store
+        .get_session(&session_query)?
+        .ok_or_else(|| Error::SessionNotFound(session_query.to_string()))?;
+    let source = store
+        .get_session_source_path(&session.id)?
+        .unwrap_or_default();
+
+    println!(
+        "Session {} ({}, {})",
+        session.short_hash, session.source_name, session.external_id
+    );
+    println!("Source: {}", source);
+
+    let mut files = BTreeSet::new();
+    files.insert(source.clone());
+
+    let messages = store.get_messages(&session.id)?;
+    if !messages.is_empty() {
+        println!("\nMessages:");
+    }
+    for (idx, msg) in messages.iter().enumerate() {
+        // Line-based sources point into the file; others at a content file or key
+        let location = match (msg.line_number, msg.byte_offset, &msg.content_ref) {
+            (Some(line), Some(offset), _) => {
+                format!("{}:{} (byte {})", msg.source_path, line, offset)
+            }
+            (_, _, Some(content)) if Path::new(content).is_file() => {
+                files.insert(content.clone());
+                content.clone()
+            }
+            (_, _, Some(key)) => format!("{} [{}]", msg.source_path, key),
+            (Some(index), None, None) => format!("{} [#{}]", msg.source_path, index),
+            _ => msg.source_path.clone(),
+        };
+        files.insert(msg.source_path.clone());
+        println!("  {:>4}  {:<10} {}", idx + 1, msg.role, location);
+    }
+
+    if files.len() > 1 {
+        println!("\nFiles ({}):", files.len());
+        for file in &files {
+            println!("  {}", file);
+        }
+    }
+
+    if edit {
+        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+        let status = Command::new(&editor)
+            .arg(&source)
+            .status()
+            .with_context(|| format!("Failed to run editor '{}'", editor))?;
+        if !status.success() {
+            anyhow::bail!("Editor exited with {}", status);
+        }
+    }
+    if reveal {
+        reveal_in_file_manager(Path::new(&source))?;
+    }
+
+    Ok(())
+}
+
+/// Reopen a session in the tool it came from, using the probe's resume command
+pub fn resume(
+    store: &MetadataStore,
+    config: &Config,
+    session_query: String,
+    print: bool,
+) -> Result<()> {
+    let session = store
+        .get_session(&session_query)?
+        .ok_or_else(|| Error::SessionNotFound(session_query.to_string()))?;
+    let template = config
+        .resume_command(&session.probe_source_id)
+        .with_context(|| {
+            format!(
+                "{} sessions can't be resumed; set probes.{}.resume in the config",
+                session.source_name, session.probe_source_id
+            )
+        })?;
+    let dir = session.project_path.as_deref().unwrap_or_default();
+    let args = resume_args(template, &session.external_id, dir);
+    let Some((program, rest)) = args.split_first() else {
+        anyhow::bail!("Empty resume command for {}", session.probe_source_id);
+    };
+
+    if print {
+        let words: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+        println!("{}", words.join(" "));
+        return Ok(());
+    }
+    let mut command = Command::new(program);
+    command.args(rest);
+    // Tools look sessions up by the directory they ran in
+    if Path::new(dir).is_dir() {
+        command.current_dir(dir);
+    }
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run '{}'", program))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Resume command arguments with `{id}` and `{path}` filled in. Substitution is per
+/// word, so IDs and paths with spaces stay one argument.
+fn resume_args(template: &str, id: &str, dir: &str) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|word| word.replace("{id}", id).replace("{path}", dir))
+        .collect()
+}
+
+/// Quote a word for POSIX shells, so a printed command can be pasted as-is
+fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        // xdg-open can't select a file, so open its folder
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let mut command = Command::new("xdg-open");
+        command.arg(dir);
+        command
+    };
+    command
+        .status()
+        .context("Failed to open the file manager")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_args() {
+        assert_eq!(
+            resume_args("claude --resume {id}", "abc-123", "/src/app"),
+            ["claude", "--resume", "abc-123"]
+        );
+        // A path with spaces stays one argument, and is quoted when printed
+        let args = resume_args("tool --cwd={path} {id}", "it's", "/My Projects/app");
+        assert_eq!(args, ["tool", "--cwd=/My Projects/app", "it's"]);
+        let printed: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+        assert_eq!(
+            printed.join(" "),
+            r"tool '--cwd=/My Projects/app' 'it'\''s'"
+        );
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,375 @@
+//! Stats command implementation
+
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::analysis::TokenCounts;
+use crate::config::Config;
+use crate::store::MetadataStore;
+
+/// Models listed in the stats breakdown
+const TOP_MODELS: usize = 10;
+
+/// Per-probe index statistics, including source entries dropped while parsing
+pub fn run(store: &MetadataStore, config: &Config, json: bool) -> Result<()> {
+    if json {
+        return super::print_json(&report(store, config)?);
+    }
+    let probes = store.probe_stats()?;
+    if probes.is_empty() {
+        println!("No sessions found. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+    let skips = store.skip_stats()?;
+    let costs = probe_costs(store, config)?;
+
+    println!(
+        "{:<22} {:>8} {:>9} {:>8} {:>10}  Last indexed",
+        "Probe", "Sessions", "Messages", "Skipped", "Est. cost"
+    );
+    let theme = super::theme::current();
+    println!("{}", theme.rule(81));
+    for probe in &probes {
+        let skipped: i64 = skips
+            .iter()
+            .filter(|s| s.probe_source_id == probe.probe_source_id)
+            .map(|s| s.count)
+            .sum();
+        let cost = match probe.capabilities.supports_token_usage {
+            true => {
+                super::costs::format_cost(costs.get(&probe.probe_source_id).copied().unwrap_or(0.0))
+            }
+            false => "n/a".to_string(),
+        };
+        println!(
+            "{} {:>8} {:>9} {:>8} {:>10}  {}",
+            theme.source(&probe.probe_source_id, 22),
+            probe.sessions,
+            probe.messages,
+            skipped,
+            cost,
+            probe.last_indexed.as_deref().unwrap_or("-")
+        );
+    }
+    println!(
+        "\nEstimated total cost: {} (see 'chronicle costs')",
+        super::costs::format_cost(costs.values().sum())
+    );
+    if probes.iter().any(|p| !p.capabilities.supports_token_usage) {
+        println!("n/a: the source doesn't record token usage, so its cost is unknown");
+    }
+
+    let activity = store.activity_stats()?;
+    if !activity.is_empty() {
+        println!(
+            "\n{:<22} {:>10} {:>10} {:>11} {:>10}",
+            "Activity", "Avg length", "Avg active", "User/asst.", "Tools/msg"
+        );
+        println!("{}", theme.rule(67));
+        let time = |secs: Option<i64>| secs.map_or_else(|| "-".to_string(), super::elapsed);
+        let ratio =
+            |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| format!("{:.2}", v));
+        for row in &activity {
+            println!(
+                "{} {:>10} {:>10} {:>11} {:>10}",
+                theme.source(&row.probe_source_id, 22),
+                time(row.totals.duration_secs),
+                time(row.totals.active_secs),
+                ratio(row.totals.user_assistant_ratio()),
+                ratio(row.totals.tool_calls_per_message())
+            );
+        }
+    }
+
+    let budgets = super::alerts::budgets_today(store, config)?;
+    if !budgets.is_empty() {
+        println!("\nBudgets today:");
+        for budget in &budgets {
+            let marker = match budget.exceeded() {
+                true => theme.icon("⚠️ ", "!"),
+                false => " ",
+            };
+            println!(
+                "{} {:<22} {:>10} of {}",
+                marker,
+                budget.project.as_deref().unwrap_or("(all projects)"),
+                super::costs::format_cost(budget.spent),
+                super::costs::format_cost(budget.limit)
+            );
+        }
+    }
+
+    let models = store.model_breakdown()?;
+    if !models.is_empty() {
+        println!(
+            "\n{:<32} {:>8} {:>9} {:>14}",
+            "Model", "Sessions", "Messages", "Tokens"
+        );
+        println!("{}", theme.rule(66));
+        for row in models.iter().take(TOP_MODELS) {
+            println!(
+                "{:<32} {:>8} {:>9} {:>14}",
+                row.model,
+                row.sessions,
+                row.messages,
+                super::group_thousands(row.tokens.total().max(0) as usize)
+            );
+        }
+        if models.len() > TOP_MODELS {
+            println!("... and {} more model(s)", models.len() - TOP_MODELS);
+        }
+        let switched = store.count_multi_model_sessions()?;
+        if switched > 0 {
+            println!("{} session(s) used more than one model", switched);
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n{} Skipped entries (not indexed):",
+            theme.icon("⚠️ ", "!")
+        );
+        for skip in &skips {
+            println!(
+                "  {:<22} {:>6} × {} ({} session(s))",
+                skip.probe_source_id, skip.count, skip.reason, skip.sessions
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Today's usage from the daily rollups, cheap enough for a shell prompt or status bar.
+/// Reflects the last extraction (`refreshed_at`), not sessions still being written.
+pub fn today(store: &MetadataStore, config: &Config, json: bool) -> Result<()> {
+    let report = today_report(store, config)?;
+    if json {
+        // One line, so prompts can read it without a JSON pretty-printer in the way
+        println!("{}", report);
+        return Ok(());
+    }
+    println!(
+        "Today ({}): {} session(s) · {} message(s) · {} tokens · {}",
+        report["day"].as_str().unwrap_or_default(),
+        report["sessions"],
+        report["messages"],
+        super::group_thousands(report["tokens"]["total"].as_u64().unwrap_or(0) as usize),
+        super::costs::format_cost(report["estimated_cost"].as_f64().unwrap_or(0.0))
+    );
+    Ok(())
+}
+
+/// Today's sessions, messages, tokens and estimated cost, with a per-source breakdown
+pub fn today_report(store: &MetadataStore, config: &Config) -> Result<Value> {
+    // A read-only database reports whatever rollups it was copied with
+    if store.rollups_missing()? && !store.is_read_only() {
+        store.refresh_usage_rollups(None)?;
+    }
+    let now = chrono::Local::now();
+    let day = now.format("%Y-%m-%d").to_string();
+    let midnight = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map_or_else(|| now.to_utc(), |t| t.to_utc());
+
+    let pricing = config.pricing();
+    let mut tokens = TokenCounts::default();
+    let (mut messages, mut cost) = (0, 0.0);
+    let mut refreshed_at: Option<String> = None;
+    let mut sources: BTreeMap<String, (i64, TokenCounts, f64)> = BTreeMap::new();
+    for row in store.usage_rollups(&day)? {
+        let row_cost = pricing
+            .estimate(row.model.as_deref(), &row.tokens)
+            .unwrap_or(0.0);
+        let source = sources.entry(row.probe_source_id).or_default();
+        source.0 += row.messages;
+        source.1.add(&row.tokens);
+        source.2 += row_cost;
+        messages += row.messages;
+        tokens.add(&row.tokens);
+        cost += row_cost;
+        refreshed_at = refreshed_at.max(row.refreshed_at);
+    }
+
+    Ok(serde_json::json!({
+        "day": day,
+        "sessions": store.count_sessions_active_since(&midnight.to_rfc3339())?,
+        "messages": messages,
+        "tokens": token_json(&tokens),
+        "estimated_cost": cost,
+        "sources": sources
+            .into_iter()
+            .map(|(id, (messages, tokens, cost))| serde_json::json!({
+                "probe_source_id": id,
+                "messages": messages,
+                "tokens": tokens.total(),
+                "estimated_cost": cost,
+            }))
+            .collect::<Vec<_>>(),
+        "refreshed_at": refreshed_at,
+    }))
+}
+
+/// How much of the output, and of the estimated spend, went to reasoning
+pub fn thinking(store: &MetadataStore, config: &Config, json: bool) -> Result<()> {
+    let report = thinking_report(store, config)?;
+    if json {
+        return super::print_json(&report);
+    }
+    let rows = report["models"].as_array().cloned().unwrap_or_default();
+    if rows.is_empty() {
+        println!("No thinking recorded. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<22} {:<28} {:>13} {:>12} {:>8} {:>10}",
+        "Source", "Model", "Thinking msgs", "Reasoning", "% output", "Est. cost"
+    );
+    let theme = super::theme::current();
+    println!("{}", theme.rule(98));
+    for row in &rows {
+        let counted = row["counted_messages"].as_i64().unwrap_or(0) > 0;
+        let (tokens, share) = match counted {
+            true => (
+                super::group_thousands(row["reasoning_tokens"].as_u64().unwrap_or(0) as usize),
+                row["reasoning_share"]
+                    .as_f64()
+                    .map_or_else(|| "-".to_string(), |s| format!("{:.1}%", s * 100.0)),
+            ),
+            false => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{} {:<28} {:>13} {:>12} {:>8} {:>10}",
+            theme.source(row["probe_source_id"].as_str().unwrap_or_default(), 22),
+            row["model"].as_str().unwrap_or("-"),
+            format!("{}/{}", row["thinking_messages"], row["messages"]),
+            tokens,
+            share,
+            row["estimated_cost"]
+                .as_f64()
+                .map_or_else(|| "-".to_string(), super::costs::format_cost)
+        );
+    }
+    println!("{}", theme.rule(98));
+
+    let reasoning_cost = report["estimated_cost"].as_f64().unwrap_or(0.0);
+    let total_cost = report["total_estimated_cost"].as_f64().unwrap_or(0.0);
+    println!(
+        "Reasoning: {} tokens · estimated {} of {} spent{}",
+        super::group_thousands(report["reasoning_tokens"].as_u64().unwrap_or(0) as usize),
+        super::costs::format_cost(reasoning_cost),
+        super::costs::format_cost(total_cost),
+        match total_cost > 0.0 {
+            true => format!(" ({:.1}%)", reasoning_cost * 100.0 / total_cost),
+            false => String::new(),
+        }
+    );
+    println!(
+        "Counts are estimated from the thinking text where a source doesn't report them; \
+         -: thinking without a recorded count (re-extract with 'chronicle extract --full')"
+    );
+    Ok(())
+}
+
+/// Reasoning tokens per source and model, priced as output, against the total spend
+pub fn thinking_report(store: &MetadataStore, config: &Config) -> Result<Value> {
+    let pricing = config.pricing();
+    let rows = store.thinking_usage()?;
+    let mut reasoning_cost = 0.0;
+    let models: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let reasoning = TokenCounts {
+                output: row.reasoning_tokens,
+                ..Default::default()
+            };
+            let cost = pricing.estimate(row.model.as_deref(), &reasoning);
+            reasoning_cost += cost.unwrap_or(0.0);
+            serde_json::json!({
+                "probe_source_id": row.probe_source_id,
+                "model": row.model,
+                "messages": row.messages,
+                "thinking_messages": row.thinking_messages,
+                "counted_messages": row.counted_messages,
+                "output_tokens": row.output_tokens,
+                "reasoning_tokens": row.reasoning_tokens,
+                "reasoning_share": row.reasoning_share(),
+                "estimated_cost": cost,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "models": models,
+        "reasoning_tokens": rows.iter().map(|r| r.reasoning_tokens).sum::<i64>(),
+        "estimated_cost": reasoning_cost,
+        "total_estimated_cost": probe_costs(store, config)?.values().sum::<f64>(),
+    }))
+}
+
+fn token_json(tokens: &TokenCounts) -> Value {
+    serde_json::json!({
+        "input": tokens.input,
+        "output": tokens.output,
+        "cache_read": tokens.cache_read,
+        "cache_creation": tokens.cache_creation,
+        "total": tokens.total(),
+    })
+}
+
+/// Per-probe statistics as JSON (`stats --json` and the API)
+pub fn report(store: &MetadataStore, config: &Config) -> Result<Value> {
+    let probes = store.probe_stats()?;
+    let skips = store.skip_stats()?;
+    let costs = probe_costs(store, config)?;
+    Ok(serde_json::json!({
+            "probes": probes
+                .iter()
+                .map(|probe| serde_json::json!({
+                    "probe_source_id": probe.probe_source_id,
+                    "sessions": probe.sessions,
+                    "messages": probe.messages,
+                    "skipped": skips
+                        .iter()
+                        .filter(|s| s.probe_source_id == probe.probe_source_id)
+                        .map(|s| s.count)
+                        .sum::<i64>(),
+                    // Unknown rather than free when the source has no token counts
+                    "estimated_cost": probe.capabilities.supports_token_usage.then(|| {
+                        costs.get(&probe.probe_source_id).copied().unwrap_or(0.0)
+                    }),
+                    "last_indexed": probe.last_indexed,
+                    "capabilities": probe.capabilities,
+                }))
+                .collect::<Vec<_>>(),
+            "skips": skips,
+            "activity": store.activity_stats()?,
+            "models": store.model_breakdown()?,
+            "multi_model_sessions": store.count_multi_model_sessions()?,
+            "estimated_cost": costs.values().sum::<f64>(),
+            "budgets": super::alerts::budgets_today(store, config)?
+                .iter()
+                .map(|budget| serde_json::json!({
+                    "project": budget.project,
+                    "spent": budget.spent,
+                    "limit": budget.limit,
+                    "exceeded": budget.exceeded(),
+                }))
+                .collect::<Vec<_>>(),
+    }))
+}
+
+/// Estimated cost per probe source
+fn probe_costs(store: &MetadataStore, config: &Config) -> Result<HashMap<String, f64>> {
+    let pricing = config.pricing();
+    let mut costs: HashMap<String, f64> = HashMap::new();
+    for row in store.token_usage(None)? {
+        if let Some(cost) = pricing.estimate(row.model.as_deref(), &row.tokens) {
+            *costs.entry(row.probe_source_id).or_insert(0.0) += cost;
+        }
+    }
+    Ok(costs)
+}
@@ -0,0 +1,102 @@
+//! `chronicle statusline` - one compact line for tmux, zellij or starship status bars
+//!
+//! Status bars re-run their commands every few seconds, so today's totals are cached
+//! in a file next to the database and only recomputed (opening the database) once the
+//! cache is older than the TTL or from another day. Whether `watch` is running comes
+//! from the heartbeat file it touches on every poll, which costs one `stat`.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::{stats, theme, watch};
+use crate::config::Config;
+use crate::store::MetadataStore;
+
+/// Default line; placeholders: {watch} {sessions} {messages} {tokens} {cost}
+pub const DEFAULT_FORMAT: &str = "{watch} {sessions} sess · {tokens} tok · {cost}";
+
+pub fn run(config: &Config, format: &str, ttl_secs: u64) -> Result<()> {
+    let report = cached_report(config, Duration::from_secs(ttl_secs))?;
+    let watching = watch::is_running(config);
+    println!("{}", render(format, &report, watching));
+    Ok(())
+}
+
+fn cache_path(config: &Config) -> PathBuf {
+    config.database_path().with_extension("statusline")
+}
+
+/// `stats --today` report, from the cache while it is fresh and from today
+fn cached_report(config: &Config, ttl: Duration) -> Result<Value> {
+    let cache = cache_path(config);
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if let Some(report) = read_fresh(&cache, ttl).filter(|r| r["day"] == today.as_str()) {
+        return Ok(report);
+    }
+    let store = MetadataStore::open_with(&config.database_path(), &config.database)?;
+    let report = stats::today_report(&store, config)?;
+    // A failed write only costs the next render a recompute
+    let _ = std::fs::write(&cache, report.to_string());
+    Ok(report)
+}
+
+fn read_fresh(path: &Path, ttl: Duration) -> Option<Value> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > ttl {
+        return None;
+    }
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+fn render(format: &str, report: &Value, watching: bool) -> String {
+    let theme = theme::current();
+    let watch = match watching {
+        true => theme.icon("●", "on"),
+        false => theme.icon("○", "off"),
+    };
+    format
+        .replace("{watch}", watch)
+        .replace("{sessions}", &report["sessions"].to_string())
+        .replace("{messages}", &report["messages"].to_string())
+        .replace(
+            "{tokens}",
+            &compact(report["tokens"]["total"].as_u64().unwrap_or(0)),
+        )
+        .replace(
+            "{cost}",
+            &super::costs::format_cost(report["estimated_cost"].as_f64().unwrap_or(0.0)),
+        )
+}
+
+/// 950, 12.3k, 4.5M
+fn compact(n: u64) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=999_999 => format!("{:.1}k", n as f64 / 1e3),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statusline_render() {
+        let report = serde_json::json!({
+            "day": "2025-06-16",
+            "sessions": 3,
+            "messages": 41,
+            "tokens": {"total": 12_345},
+            "estimated_cost": 0.4219,
+        });
+        let line = render("{sessions}/{messages} {tokens} {cost}", &report, true);
+        assert_eq!(line, "3/41 12.3k $0.42");
+        assert_eq!(compact(4_500_000), "4.5M");
+    }
+}
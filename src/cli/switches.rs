@@ -0,0 +1,129 @@
+//! `chronicle switches` - where the answering model changed mid-session
+
+use anyhow::Result;
+use chrono::DateTime;
+use serde::Serialize;
+
+use super::{elapsed, short_time, theme, timeparse, Page};
+use crate::analysis::switches::detect_model_switches;
+use crate::analysis::{ModelSwitch, Pricing, SwitchKind};
+use crate::error::Error;
+use crate::store::{AnswerModelRow, MetadataStore};
+
+/// A switch with the session it happened in
+#[derive(Debug, Serialize)]
+struct SessionSwitch {
+    session_id: String,
+    short_hash: String,
+    title: Option<String>,
+    /// Seconds from the session's first message to the switch
+    after_secs: Option<i64>,
+    #[serde(flatten)]
+    switch: ModelSwitch,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    pricing: &Pricing,
+    session: Option<String>,
+    since: Option<String>,
+    page: Page,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let session_id = match session.as_deref() {
+        Some(query) => Some(
+            store
+                .get_session(query)?
+                .ok_or_else(|| Error::SessionNotFound(query.to_string()))?
+                .id,
+        ),
+        None => None,
+    };
+    let rows = store.answer_models(session_id.as_deref(), since.as_deref())?;
+    let switches = session_switches(&rows, pricing);
+    let sessions = {
+        let mut ids: Vec<&str> = switches.iter().map(|s| s.session_id.as_str()).collect();
+        ids.dedup();
+        ids.len()
+    };
+    let downgrades = switches
+        .iter()
+        .filter(|s| s.switch.kind == SwitchKind::Downgrade)
+        .count();
+
+    let (shown, total) = page.apply(switches);
+    if json {
+        super::print_json(&shown)?;
+        page.print_footer(shown.len(), total, true);
+        return Ok(());
+    }
+    if total == 0 {
+        println!("No model switches found.");
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<16} {:<10} {:>5} {:>8}  {:<10} {:<28} {:<28}",
+        "Time", "Session", "Msg", "After", "Kind", "From", "To"
+    );
+    println!("{}", theme.rule(112));
+    for s in &shown {
+        // Pad before coloring; escape codes would otherwise count toward the width
+        let kind = format!("{:<10}", s.switch.kind.as_str());
+        let kind = match s.switch.kind {
+            SwitchKind::Downgrade => theme.paint("yellow", &kind),
+            _ => kind,
+        };
+        println!(
+            "{:<16} {:<10} {:>5} {:>8}  {} {:<28} {:<28}",
+            short_time(&s.switch.timestamp),
+            s.short_hash,
+            s.switch.message,
+            s.after_secs.map_or_else(|| "-".to_string(), elapsed),
+            kind,
+            s.switch.from,
+            s.switch.to
+        );
+    }
+    page.print_footer(shown.len(), total, false);
+    println!(
+        "\n{} switch(es) in {} session(s), {} to a cheaper model",
+        total, sessions, downgrades
+    );
+    Ok(())
+}
+
+/// Switches per session, keeping the store's session order
+fn session_switches(rows: &[AnswerModelRow], pricing: &Pricing) -> Vec<SessionSwitch> {
+    let mut switches = vec![];
+    for session in rows.chunk_by(|a, b| a.session_id == b.session_id) {
+        let answers: Vec<_> = session
+            .iter()
+            .map(|r| (r.message, r.model.clone(), r.timestamp.clone()))
+            .collect();
+        let first = &session[0];
+        let start = first
+            .session_start
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        for switch in detect_model_switches(&answers, pricing) {
+            let at = switch
+                .timestamp
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+            switches.push(SessionSwitch {
+                session_id: first.session_id.clone(),
+                short_hash: first.short_hash.clone(),
+                title: first.title.clone(),
+                after_secs: start.zip(at).map(|(start, at)| (at - start).num_seconds()),
+                switch,
+            });
+        }
+    }
+    switches
+}
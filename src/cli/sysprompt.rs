@@ -0,0 +1,220 @@
+//! `chronicle sysprompt` - the system prompts tools sent, and how they changed
+
+use crate::analysis::textdiff::{diff_lines, DiffLine};
+use crate::cli::{short_time, theme};
+use crate::store::{MetadataStore, SystemPromptRow};
+use anyhow::{bail, Result};
+use serde_json::json;
+
+/// Unchanged lines shown around each change
+const CONTEXT: usize = 3;
+
+/// Distinct system prompts per source, oldest first
+pub fn list(store: &MetadataStore, source: Option<String>, json: bool) -> Result<()> {
+    let rows = store.system_prompt_registry(source.as_deref())?;
+    if json {
+        return super::print_json(&rows);
+    }
+    if rows.is_empty() {
+        println!("No system prompts recorded. Run 'chronicle extract' first.");
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    println!(
+        "{:<20} {:<12} {:>7} {:>8} {:>8} {:<16} {:<16} {:>8} {:>9}",
+        "Source",
+        "Prompt",
+        "Chars",
+        "Sessions",
+        "Requests",
+        "First seen",
+        "Last seen",
+        "Avg msgs",
+        "Avg tools"
+    );
+    println!("{}", theme.rule(112));
+    for row in &rows {
+        println!(
+            "{} {:<12} {:>7} {:>8} {:>8} {:<16} {:<16} {:>8.1} {:>9.1}",
+            theme.source(&row.probe_source_id, 20),
+            &row.hash[..12.min(row.hash.len())],
+            row.chars,
+            row.sessions,
+            row.requests,
+            short_time(&row.first_seen),
+            short_time(&row.last_seen),
+            row.avg_messages,
+            row.avg_tool_uses
+        );
+    }
+
+    let mut changed: Vec<&str> = rows
+        .iter()
+        .map(|r| r.probe_source_id.as_str())
+        .filter(|s| rows.iter().filter(|r| r.probe_source_id == *s).count() > 1)
+        .collect();
+    changed.dedup();
+    if !changed.is_empty() {
+        println!(
+            "\nSystem prompt changed over time in: {} (compare with 'chronicle sysprompt diff')",
+            changed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Line diff between the system prompts of two sessions (or two prompt hashes)
+pub fn diff(store: &MetadataStore, a: &str, b: &str, json: bool) -> Result<()> {
+    let (label_a, hash_a, before) = resolve(store, a)?;
+    let (label_b, hash_b, after) = resolve(store, b)?;
+    let registry = store.system_prompt_registry(None)?;
+    let lines = diff_lines(&before, &after);
+
+    if json {
+        let side = |label: &str, hash: &str, content: &str| {
+            json!({
+                "label": label,
+                "hash": hash,
+                "chars": content.chars().count(),
+                "usage": registry.iter().filter(|r| r.hash == hash).collect::<Vec<_>>(),
+            })
+        };
+        let lines: Vec<_> = lines
+            .iter()
+            .map(|line| match line {
+                DiffLine::Same(text) => json!({ "op": "same", "text": text }),
+                DiffLine::Removed(text) => json!({ "op": "removed", "text": text }),
+                DiffLine::Added(text) => json!({ "op": "added", "text": text }),
+            })
+            .collect();
+        return super::print_json(&json!({
+            "a": side(&label_a, &hash_a, &before),
+            "b": side(&label_b, &hash_b, &after),
+            "identical": hash_a == hash_b,
+            "lines": lines,
+        }));
+    }
+
+    let theme = theme::current();
+    for (sign, label, hash, content) in [
+        ("---", &label_a, &hash_a, &before),
+        ("+++", &label_b, &hash_b, &after),
+    ] {
+        println!(
+            "{} {}  prompt {} ({} chars)",
+            theme.bold(sign),
+            label,
+            &hash[..12.min(hash.len())],
+            content.chars().count()
+        );
+        for row in registry.iter().filter(|r| &r.hash == hash) {
+            println!("    {}", usage(row));
+        }
+    }
+    if hash_a == hash_b {
+        println!("\nSame system prompt.");
+        return Ok(());
+    }
+
+    println!();
+    let changed: Vec<bool> = lines
+        .iter()
+        .map(|l| !matches!(l, DiffLine::Same(_)))
+        .collect();
+    let mut hidden = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let near =
+            (i.saturating_sub(CONTEXT)..=(i + CONTEXT).min(lines.len() - 1)).any(|j| changed[j]);
+        match line {
+            DiffLine::Same(_) if !near => {
+                hidden += 1;
+                continue;
+            }
+            _ if hidden > 0 => {
+                println!(
+                    "{}",
+                    theme.paint("gray", &format!("  ... {} unchanged lines", hidden))
+                );
+                hidden = 0;
+            }
+            _ => {}
+        }
+        match line {
+            DiffLine::Same(text) => println!("  {}", text),
+            DiffLine::Removed(text) => println!("{}", theme.paint("red", &format!("- {}", text))),
+            DiffLine::Added(text) => println!("{}", theme.paint("green", &format!("+ {}", text))),
+        }
+    }
+    if hidden > 0 {
+        println!(
+            "{}",
+            theme.paint("gray", &format!("  ... {} unchanged lines", hidden))
+        );
+    }
+
+    let removed = lines
+        .iter()
+        .filter(|l| matches!(l, DiffLine::Removed(_)))
+        .count();
+    let added = lines
+        .iter()
+        .filter(|l| matches!(l, DiffLine::Added(_)))
+        .count();
+    println!("\n{} lines removed, {} added", removed, added);
+    Ok(())
+}
+
+/// A session's most-sent system prompt, or a prompt by hash prefix:
+/// (label, hash, content)
+fn resolve(store: &MetadataStore, query: &str) -> Result<(String, String, String)> {
+    if let Some(session) = store.get_session(query)? {
+        let params = store.session_request_params(&session.id)?;
+        let mut prompts: Vec<(&str, i64)> = vec![];
+        for row in &params {
+            if let Some(hash) = row.system_prompt_hash.as_deref() {
+                match prompts.iter_mut().find(|(h, _)| *h == hash) {
+                    Some((_, requests)) => *requests += row.requests,
+                    None => prompts.push((hash, row.requests)),
+                }
+            }
+        }
+        let Some((hash, _)) = prompts.iter().max_by_key(|(_, requests)| *requests) else {
+            bail!(
+                "No system prompt recorded for session {}",
+                session.short_hash
+            );
+        };
+        let Some((hash, content)) = store.get_system_prompt(hash)? else {
+            bail!("System prompt {} is missing from the database", hash);
+        };
+        let mut label = format!(
+            "{} ({}, {})",
+            session.short_hash,
+            session.source_name,
+            short_time(&session.first_timestamp)
+        );
+        if prompts.len() > 1 {
+            label.push_str(&format!(", most used of {} prompts", prompts.len()));
+        }
+        return Ok((label, hash, content));
+    }
+
+    match store.get_system_prompt(query)? {
+        Some((hash, content)) => Ok((format!("prompt {}", query), hash, content)),
+        None => bail!("No session or system prompt matches '{}'", query),
+    }
+}
+
+fn usage(row: &SystemPromptRow) -> String {
+    format!(
+        "{}: {} sessions, {} requests, {} to {}, avg {:.1} messages / {:.1} tool uses",
+        row.probe_source_id,
+        row.sessions,
+        row.requests,
+        short_time(&row.first_seen),
+        short_time(&row.last_seen),
+        row.avg_messages,
+        row.avg_tool_uses
+    )
+}
@@ -0,0 +1,236 @@
+//! Output theme: provider/source badges, table rules and emoji markers
+//!
+//! Configured under `display.theme` and installed once at startup; commands
+//! read it through [`current`] so tables look the same everywhere.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use crate::config::ThemeConfig;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+
+/// Built-in badge colors, overridable per name in the config
+const DEFAULT_PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "magenta"),
+    ("claude", "magenta"),
+    ("openai", "green"),
+    ("gemini", "blue"),
+    ("google", "blue"),
+    ("multi", "cyan"),
+    ("local", "white"),
+];
+const DEFAULT_SOURCES: &[(&str, &str)] = &[
+    ("ClaudeCode", "magenta"),
+    ("OpenCode", "yellow"),
+    ("Cursor", "cyan"),
+    ("Aider", "green"),
+    ("GeminiCLI", "blue"),
+    ("Zed", "bright_blue"),
+    ("Continue", "bright_magenta"),
+    ("Copilot", "bright_cyan"),
+    ("ChatGPT", "bright_green"),
+    ("ClaudeAI", "bright_yellow"),
+    ("Windsurf", "bright_red"),
+    ("Amp", "red"),
+    ("JetBrains", "bright_white"),
+    ("LMStudio", "white"),
+    ("Ollama", "white"),
+];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Resolved theme
+#[derive(Debug, Clone)]
+pub struct Theme {
+    color: bool,
+    emoji: bool,
+    unicode: bool,
+    /// Lowercased provider name -> SGR code
+    providers: HashMap<String, &'static str>,
+    /// Lowercased source name -> SGR code
+    sources: HashMap<String, &'static str>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::build(&ThemeConfig::default(), false).expect("default theme is valid")
+    }
+}
+
+impl Theme {
+    /// Resolve a configured theme; `terminal` decides `color: auto`
+    pub fn build(config: &ThemeConfig, terminal: bool) -> Result<Self> {
+        let color = match config.color.as_str() {
+            "auto" => terminal && std::env::var_os("NO_COLOR").is_none(),
+            "always" => true,
+            "never" => false,
+            other => anyhow::bail!(
+                "Unknown display.theme.color: {} (expected auto, always or never)",
+                other
+            ),
+        };
+        let unicode = match config.table_style.as_str() {
+            "ascii" => false,
+            "unicode" => true,
+            other => anyhow::bail!(
+                "Unknown display.theme.table_style: {} (expected ascii or unicode)",
+                other
+            ),
+        };
+        Ok(Self {
+            color,
+            emoji: config.emoji,
+            unicode,
+            providers: palette(DEFAULT_PROVIDERS, &config.providers)?,
+            sources: palette(DEFAULT_SOURCES, &config.sources)?,
+        })
+    }
+
+    /// Provider name padded to `width`, in its badge color
+    pub fn provider(&self, name: &str, width: usize) -> String {
+        self.badge(self.providers.get(&name.to_lowercase()), name, width)
+    }
+
+    /// Source name (or `probe:Source` ID) padded to `width`, in its badge color
+    pub fn source(&self, name: &str, width: usize) -> String {
+        let source = name.rsplit(':').next().unwrap_or(name);
+        self.badge(self.sources.get(&source.to_lowercase()), name, width)
+    }
+
+    /// Bold text when colors are on
+    pub fn bold(&self, text: &str) -> String {
+        match self.color {
+            true => format!("{}{}{}", BOLD, text, RESET),
+            false => text.to_string(),
+        }
+    }
+
+    /// Text in a named color (see the theme color names) when colors are on
+    pub fn paint(&self, color: &str, text: &str) -> String {
+        match (self.color, sgr(color)) {
+            (true, Ok(code)) => format!("{}{}{}", code, text, RESET),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Light table rule (under headers)
+    pub fn rule(&self, width: usize) -> String {
+        (if self.unicode { "─" } else { "-" }).repeat(width)
+    }
+
+    /// Heavy table rule (around titles)
+    pub fn heavy_rule(&self, width: usize) -> String {
+        (if self.unicode { "═" } else { "=" }).repeat(width)
+    }
+
+    /// `emoji` when enabled, otherwise the ASCII `fallback`
+    pub fn icon<'a>(&self, emoji: &'a str, fallback: &'a str) -> &'a str {
+        if self.emoji {
+            emoji
+        } else {
+            fallback
+        }
+    }
+
+    fn badge(&self, code: Option<&&'static str>, name: &str, width: usize) -> String {
+        // Pad before coloring; escape codes would otherwise count toward the width
+        let padded = format!("{:<width$}", name, width = width);
+        match (self.color, code) {
+            (true, Some(code)) => {
+                let trimmed = padded.trim_end();
+                format!("{}{}{}{}", code, trimmed, RESET, &padded[trimmed.len()..])
+            }
+            _ => padded,
+        }
+    }
+}
+
+/// Install the theme for this process; later calls are ignored
+pub fn init(config: &ThemeConfig) -> Result<()> {
+    let theme = Theme::build(config, std::io::stdout().is_terminal())?;
+    let _ = THEME.set(theme);
+    Ok(())
+}
+
+/// The installed theme, or the default (uncolored) one
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+fn palette(
+    defaults: &[(&str, &str)],
+    configured: &std::collections::BTreeMap<String, String>,
+) -> Result<HashMap<String, &'static str>> {
+    let mut colors = HashMap::new();
+    let entries = defaults
+        .iter()
+        .map(|(k, v)| (*k, *v))
+        .chain(configured.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    for (name, color) in entries {
+        colors.insert(name.to_lowercase(), sgr(color)?);
+    }
+    Ok(colors)
+}
+
+/// ANSI code for a color name
+fn sgr(color: &str) -> Result<&'static str> {
+    let code = match color.to_lowercase().as_str() {
+        "black" => "\x1b[30m",
+        "red" => "\x1b[31m",
+        "green" => "\x1b[32m",
+        "yellow" => "\x1b[33m",
+        "blue" => "\x1b[34m",
+        "magenta" => "\x1b[35m",
+        "cyan" => "\x1b[36m",
+        "white" => "\x1b[37m",
+        "gray" | "grey" => "\x1b[90m",
+        "bright_red" => "\x1b[91m",
+        "bright_green" => "\x1b[92m",
+        "bright_yellow" => "\x1b[93m",
+        "bright_blue" => "\x1b[94m",
+        "bright_magenta" => "\x1b[95m",
+        "bright_cyan" => "\x1b[96m",
+        "bright_white" => "\x1b[97m",
+        other => anyhow::bail!("Unknown theme color: {}", other),
+    };
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badges_keep_column_width() {
+        let mut config = ThemeConfig {
+            color: "always".to_string(),
+            table_style: "unicode".to_string(),
+            ..Default::default()
+        };
+        config
+            .providers
+            .insert("Claude".to_string(), "bright_red".to_string());
+        let theme = Theme::build(&config, false).unwrap();
+
+        assert_eq!(theme.provider("claude", 8), "\x1b[91mclaude\x1b[0m  ");
+        assert_eq!(theme.source("Unknown", 9), "Unknown  ");
+        assert_eq!(
+            theme.source("aider:Aider", 11),
+            "\x1b[32maider:Aider\x1b[0m"
+        );
+        assert_eq!(theme.rule(3), "───");
+
+        config.color = "auto".to_string();
+        config.emoji = false;
+        let plain = Theme::build(&config, false).unwrap();
+        assert_eq!(plain.provider("claude", 8), "claude  ");
+        assert_eq!(plain.icon("🔧", "*"), "*");
+
+        config.table_style = "fancy".to_string();
+        assert!(Theme::build(&config, true).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Timeline command implementation
+//!
+//! Interleaves one day's sessions from every probe in start order, so a day spent
+//! across several tools reads as one sequence. Times are local.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use serde::Serialize;
+
+use super::{elapsed, theme, truncate};
+use crate::error::Error;
+use crate::store::{MetadataStore, SessionQuery, SessionRow};
+
+/// A session as placed on the timeline
+#[derive(Debug, Serialize)]
+pub struct TimelineEntry {
+    pub short_hash: String,
+    pub source: String,
+    pub project: Option<String>,
+    pub title: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_secs: i64,
+    /// Started while an earlier session was still going
+    pub overlaps: bool,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    project: Option<String>,
+    day: Option<String>,
+    json: bool,
+) -> Result<()> {
+    // Any time expression works; only its local date counts
+    let date = match day {
+        Some(expr) => super::timeparse::parse(&expr)?
+            .with_timezone(&Local)
+            .date_naive(),
+        None => Local::now().date_naive(),
+    };
+    let (start, end) = day_bounds(date);
+    let project_id = match project.as_deref() {
+        Some(query) => Some(
+            store
+                .find_project(query)?
+                .ok_or_else(|| Error::ProjectNotFound(query.to_string()))?
+                .id,
+        ),
+        None => None,
+    };
+    let sessions = store.query_sessions(&SessionQuery {
+        since: Some(start.to_rfc3339()),
+        project_id,
+        archived: true,
+        ..Default::default()
+    })?;
+    let entries = timeline(sessions, end);
+    if json {
+        return super::print_json(&entries);
+    }
+
+    let theme = theme::current();
+    let active = active_secs(&entries, start, end);
+    println!(
+        "{} Timeline for {} · {} session(s) · {} active\n",
+        theme.icon("🕒", "#"),
+        date.format("%a %Y-%m-%d"),
+        entries.len(),
+        elapsed(active)
+    );
+    if entries.is_empty() {
+        println!("No sessions on this day.");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        let time = |t: DateTime<Utc>| {
+            let local = t.with_timezone(&Local);
+            match local.date_naive() {
+                d if d == date => local.format("%H:%M").to_string(),
+                d if d.year() == date.year() => local.format("%m-%d %H:%M").to_string(),
+                _ => local.format("%Y-%m-%d").to_string(),
+            }
+        };
+        let title = entry
+            .title
+            .as_deref()
+            .and_then(|t| t.lines().next())
+            .unwrap_or("Untitled session");
+        println!(
+            "{:>11} - {:<11} {:>7} {} {} {:<16} {} {}",
+            time(entry.start),
+            time(entry.end),
+            elapsed(entry.duration_secs),
+            if entry.overlaps { "*" } else { " " },
+            theme.source(&entry.source, 14),
+            truncate(entry.project.as_deref().unwrap_or("-"), 16),
+            theme.paint("gray", &entry.short_hash),
+            title
+        );
+    }
+    if entries.iter().any(|e| e.overlaps) {
+        println!("\n* started while an earlier session was still active");
+    }
+    Ok(())
+}
+
+/// Local midnight of `date` and of the day after, in UTC
+fn day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let midnight = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map_or_else(
+                || date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+                |t| t.to_utc(),
+            )
+    };
+    (midnight(date), midnight(date + Duration::days(1)))
+}
+
+/// Sessions active on the day (already filtered to end after its start) in start order
+fn timeline(sessions: Vec<SessionRow>, end: DateTime<Utc>) -> Vec<TimelineEntry> {
+    let mut entries: Vec<TimelineEntry> = sessions
+        .into_iter()
+        .filter_map(|s| {
+            let last = s.last_at().or(s.first_at())?;
+            let first = s.first_at().unwrap_or(last);
+            (first < end).then(|| TimelineEntry {
+                duration_secs: (last - first).num_seconds(),
+                short_hash: s.short_hash,
+                source: s.source_name,
+                project: s.project_name,
+                title: s.title,
+                start: first,
+                end: last,
+                overlaps: false,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| e.start);
+
+    let mut latest_end: Option<DateTime<Utc>> = None;
+    for entry in &mut entries {
+        entry.overlaps = latest_end.is_some_and(|t| entry.start < t);
+        latest_end = latest_end.max(Some(entry.end));
+    }
+    entries
+}
+
+/// Seconds within the day covered by at least one session
+fn active_secs(entries: &[TimelineEntry], start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
+    let mut total = 0;
+    let mut covered_to = start;
+    for entry in entries {
+        let from = entry.start.max(covered_to);
+        let to = entry.end.min(end);
+        if to > from {
+            total += (to - from).num_seconds();
+            covered_to = to;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(hash: &str, first: &str, last: &str) -> SessionRow {
+        SessionRow {
+            short_hash: hash.to_string(),
+            first_timestamp: Some(first.to_string()),
+            last_timestamp: Some(last.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_interleaves_and_counts_overlap_once() {
+        let time = |t: &str| DateTime::parse_from_rfc3339(t).unwrap().to_utc();
+        let (start, end) = (time("2026-03-02T00:00:00Z"), time("2026-03-03T00:00:00Z"));
+        let entries = timeline(
+            vec![
+                session("zed", "2026-03-02T10:30:00Z", "2026-03-02T11:30:00Z"),
+                session("claude", "2026-03-02T10:00:00Z", "2026-03-02T11:00:00Z"),
+                session("late", "2026-03-03T09:00:00Z", "2026-03-03T09:30:00Z"),
+                session("night", "2026-03-01T23:30:00Z", "2026-03-02T00:30:00Z"),
+            ],
+            end,
+        );
+
+        let order: Vec<&str> = entries.iter().map(|e| e.short_hash.as_str()).collect();
+        assert_eq!(order, vec!["night", "claude", "zed"]);
+        assert_eq!(entries[2].duration_secs, 3600);
+        assert!(entries[2].overlaps && !entries[1].overlaps);
+        // 30 minutes after midnight plus 10:00-11:30
+        assert_eq!(active_secs(&entries, start, end), 30 * 60 + 90 * 60);
+    }
+}
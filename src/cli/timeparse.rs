@@ -0,0 +1,181 @@
+//! Time expressions for `--since` / `--until` flags
+//!
+//! Accepted forms, all resolved against the local time zone:
+//!
+//! - durations back from now: `90m`, `12h`, `3d`, `2w`, `6mo`, `1y`, optionally
+//!   spelled out (`3 days`, `2 weeks ago`)
+//! - keywords: `now`, `today`, `yesterday`
+//! - weekdays and periods: `last monday`, `monday` (most recent, before today),
+//!   `last week`, `last month`, `this week`, `this month`
+//! - absolute: `YYYY-MM-DD`, `YYYY/MM/DD`, `DD.MM.YYYY`, `YYYY-MM-DD HH:MM[:SS]`
+//!   and RFC 3339
+//!
+//! Dates without a time mean local midnight.
+
+use anyhow::{bail, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, TimeZone,
+    Utc, Weekday,
+};
+
+/// Parse a time expression relative to the current local time
+pub fn parse(expr: &str) -> Result<DateTime<Utc>> {
+    parse_at(expr, Local::now().fixed_offset())
+}
+
+/// Parse a time expression relative to `now`, whose offset interprets naive dates
+pub fn parse_at(expr: &str, now: DateTime<FixedOffset>) -> Result<DateTime<Utc>> {
+    let input = expr.trim().to_lowercase();
+    if let Some(time) = absolute(&input, now.offset()) {
+        return Ok(time);
+    }
+
+    let today = now.date_naive();
+    let midnight = |date: NaiveDate| local_midnight(date, now.offset());
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let time = match words.as_slice() {
+        ["now"] => now.with_timezone(&Utc),
+        ["today"] => midnight(today),
+        ["yesterday"] => midnight(today - Duration::days(1)),
+        ["this", "week"] => midnight(week_start(today)),
+        ["last", "week"] => midnight(week_start(today) - Duration::weeks(1)),
+        ["this", "month"] => midnight(today.with_day(1).unwrap_or(today)),
+        ["last", "month"] => midnight(
+            today
+                .with_day(1)
+                .and_then(|d| d.checked_sub_months(Months::new(1)))
+                .unwrap_or(today),
+        ),
+        ["last", day] | [day] if weekday(day).is_some() => {
+            let target = weekday(day).unwrap_or(Weekday::Mon);
+            // Most recent such day strictly before today
+            let mut date = today - Duration::days(1);
+            while date.weekday() != target {
+                date -= Duration::days(1);
+            }
+            midnight(date)
+        }
+        _ => match relative(&words, now) {
+            Some(time) => time,
+            None => bail!(
+                "Invalid time: {} (use 7d, 2w, 12h, yesterday, last monday, YYYY-MM-DD or RFC 3339)",
+                expr.trim()
+            ),
+        },
+    };
+    Ok(time)
+}
+
+/// Absolute timestamps and dates
+fn absolute(input: &str, offset: &FixedOffset) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dt%H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(input, format) {
+            return offset
+                .from_local_datetime(&time)
+                .single()
+                .map(|t| t.with_timezone(&Utc));
+        }
+    }
+    ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        .map(|date| local_midnight(date, offset))
+}
+
+/// `3d`, `3 days`, `3 days ago`, `6mo`
+fn relative(words: &[&str], now: DateTime<FixedOffset>) -> Option<DateTime<Utc>> {
+    let words = match words {
+        [rest @ .., "ago"] => rest,
+        _ => words,
+    };
+    let (amount, unit) = match words {
+        [single] => {
+            let split = single.find(|c: char| !c.is_ascii_digit())?;
+            (&single[..split], &single[split..])
+        }
+        [amount, unit] => (*amount, *unit),
+        _ => return None,
+    };
+    let amount: u32 = amount.parse().ok()?;
+    let n = i64::from(amount);
+
+    let time = match unit.trim_end_matches('s') {
+        "m" | "min" | "minute" => now - Duration::minutes(n),
+        "h" | "hr" | "hour" => now - Duration::hours(n),
+        "d" | "day" => now - Duration::days(n),
+        "w" | "wk" | "week" => now - Duration::weeks(n),
+        "mo" | "month" => now.checked_sub_months(Months::new(amount))?,
+        "y" | "yr" | "year" => now.checked_sub_months(Months::new(amount.checked_mul(12)?))?,
+        _ => return None,
+    };
+    Some(time.with_timezone(&Utc))
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    let day = match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+/// Monday of the week containing `date`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+fn local_midnight(date: NaiveDate, offset: &FixedOffset) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    offset
+        .from_local_datetime(&midnight)
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2025-06-18 15:30 at UTC+2
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2025-06-18T15:30:00+02:00").unwrap()
+    }
+
+    fn at(expr: &str) -> String {
+        parse_at(expr, now()).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_relative_and_keywords() {
+        assert_eq!(at("3d"), "2025-06-15T13:30:00+00:00");
+        assert_eq!(at("2 weeks ago"), "2025-06-04T13:30:00+00:00");
+        assert_eq!(at("90m"), "2025-06-18T12:00:00+00:00");
+        assert_eq!(at("1mo"), "2025-05-18T13:30:00+00:00");
+        assert_eq!(at("today"), "2025-06-17T22:00:00+00:00");
+        assert_eq!(at("Yesterday"), "2025-06-16T22:00:00+00:00");
+        assert_eq!(at("last monday"), "2025-06-15T22:00:00+00:00");
+        assert_eq!(at("wed"), "2025-06-10T22:00:00+00:00");
+        assert_eq!(at("this week"), "2025-06-15T22:00:00+00:00");
+        assert_eq!(at("last month"), "2025-04-30T22:00:00+00:00");
+    }
+
+    #[test]
+    fn test_absolute() {
+        assert_eq!(at("2025-01-02"), "2025-01-01T22:00:00+00:00");
+        assert_eq!(at("02.01.2025"), "2025-01-01T22:00:00+00:00");
+        assert_eq!(at("2025-01-02 08:15"), "2025-01-02T06:15:00+00:00");
+        assert_eq!(at("2025-01-02T08:15:00Z"), "2025-01-02T08:15:00+00:00");
+        assert!(parse_at("soon", now()).is_err());
+        assert!(parse_at("3 fortnights", now()).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! `chronicle tools` - which tools get called, where, and how often they fail
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{short_time, theme, timeparse, truncate, Page};
+use crate::store::{MetadataStore, ToolUsageRow};
+
+/// Tools listed per group in `--by project|provider`
+const TOP_TOOLS: usize = 3;
+
+/// Calls of a tool, group or period
+#[derive(Debug, Default, Clone, Serialize)]
+struct ToolStats {
+    calls: i64,
+    /// Calls without any recorded result
+    unanswered: i64,
+    /// Calls that came back with an error result
+    errors: i64,
+    /// Share of calls without a result (%)
+    failure_rate: f64,
+    last_used: Option<String>,
+}
+
+impl ToolStats {
+    fn add(&mut self, row: &ToolUsageRow) {
+        self.calls += row.calls;
+        self.unanswered += row.unanswered;
+        self.errors += row.errors;
+        self.failure_rate = rate(self.unanswered, self.calls);
+        if row.last_used > self.last_used {
+            self.last_used = row.last_used.clone();
+        }
+    }
+}
+
+/// A group (tool, project, provider or period) with its totals and busiest tools
+#[derive(Debug, Serialize)]
+struct Group {
+    key: String,
+    #[serde(flatten)]
+    stats: ToolStats,
+    /// Most-called tools in the group
+    top_tools: Vec<ToolCalls>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolCalls {
+    tool: String,
+    calls: i64,
+}
+
+pub fn run(
+    store: &MetadataStore,
+    by: &str,
+    trend: Option<String>,
+    tool: Option<String>,
+    since: Option<String>,
+    page: Page,
+    json: bool,
+) -> Result<()> {
+    let since = since
+        .as_deref()
+        .map(|expr| timeparse::parse(expr).map(|t| t.to_rfc3339()))
+        .transpose()?;
+    let mut rows = store.tool_usage(since.as_deref())?;
+    if let Some(tool) = tool.as_deref() {
+        rows.retain(|r| r.tool_name == tool);
+    }
+
+    let groups = grouped(&rows, by, trend.as_deref())?;
+    let (groups, total) = page.apply(groups);
+    if json {
+        super::print_json(&groups)?;
+        page.print_footer(groups.len(), total, true);
+        return Ok(());
+    }
+    if groups.is_empty() {
+        match tool {
+            Some(tool) => println!("No calls to '{}' recorded.", tool),
+            None => println!("No tool calls recorded. Run 'chronicle extract' first."),
+        }
+        return Ok(());
+    }
+
+    let theme = theme::current();
+    let label = match (&trend, by) {
+        (Some(unit), _) => by_label(unit),
+        (None, by) => by_label(by),
+    };
+    let per_tool = trend.is_none() && by == "tool";
+    println!(
+        "{:<36} {:>8} {:>10} {:>7} {:>8}  {}",
+        label,
+        "Calls",
+        "No result",
+        "Fail %",
+        "Errors",
+        if per_tool { "Last used" } else { "Top tools" }
+    );
+    println!("{}", theme.rule(110));
+    for g in &groups {
+        let detail = if per_tool {
+            short_time(&g.stats.last_used)
+        } else {
+            g.top_tools
+                .iter()
+                .map(|t| format!("{} ({})", t.tool, t.calls))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!(
+            "{:<36} {:>8} {:>10} {:>6.1}% {:>8}  {}",
+            truncate(&g.key, 36),
+            g.stats.calls,
+            g.stats.unanswered,
+            g.stats.failure_rate,
+            g.stats.errors,
+            detail
+        );
+    }
+    page.print_footer(groups.len(), total, false);
+    let untracked = super::sources_without(store, |c| c.supports_tool_results)?;
+    if !untracked.is_empty() {
+        println!(
+            "\nResults aren't tracked for {}: their calls count as answered",
+            untracked.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Rows rolled up by `by` (tool, project or provider), or into chronological periods
+/// for a trend
+fn grouped(rows: &[ToolUsageRow], by: &str, trend: Option<&str>) -> Result<Vec<Group>> {
+    Ok(match trend {
+        Some(unit) => {
+            // Periods read chronologically
+            let mut groups = group(rows, |row| period(row.day.as_deref(), unit))?;
+            groups.sort_by(|a, b| a.key.cmp(&b.key));
+            groups
+        }
+        None => group(rows, |row| {
+            Ok(match by {
+                "tool" => row.tool_name.clone(),
+                "project" => row
+                    .project_name
+                    .clone()
+                    .unwrap_or_else(|| "(unassigned)".to_string()),
+                "provider" => row
+                    .provider
+                    .clone()
+                    .unwrap_or_else(|| "(unknown)".to_string()),
+                other => bail!(
+                    "Unknown grouping: {} (expected tool, project or provider)",
+                    other
+                ),
+            })
+        })?,
+    })
+}
+
+/// Roll rows up by key; most-called group first
+fn group(
+    rows: &[ToolUsageRow],
+    key: impl Fn(&ToolUsageRow) -> Result<String>,
+) -> Result<Vec<Group>> {
+    let mut groups: BTreeMap<String, (ToolStats, BTreeMap<&str, i64>)> = BTreeMap::new();
+    for row in rows {
+        let (stats, tools) = groups.entry(key(row)?).or_default();
+        stats.add(row);
+        *tools.entry(row.tool_name.as_str()).or_default() += row.calls;
+    }
+    let mut groups: Vec<Group> = groups
+        .into_iter()
+        .map(|(key, (stats, tools))| {
+            let mut top_tools: Vec<ToolCalls> = tools
+                .into_iter()
+                .map(|(name, calls)| ToolCalls {
+                    tool: name.to_string(),
+                    calls,
+                })
+                .collect();
+            top_tools.sort_by_key(|t| std::cmp::Reverse(t.calls));
+            top_tools.truncate(TOP_TOOLS);
+            Group {
+                key,
+                stats,
+                top_tools,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.stats.calls));
+    Ok(groups)
+}
+
+/// Day, ISO week or month of a `YYYY-MM-DD` date
+fn period(day: Option<&str>, unit: &str) -> Result<String> {
+    Ok(match unit {
+        "day" => day.unwrap_or("(undated)").to_string(),
+        "week" => day
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .map(|d| d.format("%G-W%V").to_string())
+            .unwrap_or_else(|| "(undated)".to_string()),
+        "month" => day
+            .map(|d| d[..7.min(d.len())].to_string())
+            .unwrap_or_else(|| "(undated)".to_string()),
+        other => bail!("Unknown period: {} (expected day, week or month)", other),
+    })
+}
+
+fn by_label(by: &str) -> String {
+    let mut label = by.to_string();
+    if let Some(first) = label.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    label
+}
+
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::ToolUseMetadata;
+    use crate::store::fixtures::{message, seed, session};
+    use chrono::{TimeZone, Utc};
+
+    fn call(tool: &str, has_result: bool, is_error: bool) -> ToolUseMetadata {
+        ToolUseMetadata {
+            tool_id: None,
+            tool_name: tool.to_string(),
+            has_result,
+            is_error,
+            permission: None,
+            input_hash: None,
+            file_path: None,
+        }
+    }
+
+    #[test]
+    fn test_failure_rates_and_trend() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .create_project("p1", "app", "git", Some("/src/app"), None)
+            .unwrap();
+        let turn = |uuid: &str, day: u32, calls: Vec<ToolUseMetadata>| {
+            let mut msg = message(
+                uuid,
+                "assistant",
+                Some(Utc.with_ymd_and_hms(2025, 3, day, 12, 0, 0).unwrap()),
+            );
+            msg.has_tool_use = true;
+            msg.tool_uses = calls;
+            msg
+        };
+        // Monday of ISO week 10, then a week later
+        let metadata = session(
+            "s1",
+            Some("/src/app"),
+            vec![
+                turn(
+                    "a",
+                    3,
+                    vec![
+                        call("Bash", true, false),
+                        call("Bash", false, false),
+                        call("Read", true, true),
+                    ],
+                ),
+                turn("b", 10, vec![call("Bash", true, false)]),
+            ],
+        );
+        seed(&store, "claude:ClaudeCode", &metadata);
+        let rows = store.tool_usage(None).unwrap();
+
+        // Only unanswered calls count as failures; error results are reported apart
+        let tools = grouped(&rows, "tool", None).unwrap();
+        let stats: Vec<_> = tools
+            .iter()
+            .map(|g| {
+                (
+                    g.key.as_str(),
+                    g.stats.calls,
+                    g.stats.unanswered,
+                    g.stats.errors,
+                )
+            })
+            .collect();
+        assert_eq!(stats, [("Bash", 3, 1, 0), ("Read", 1, 0, 1)]);
+        assert!((tools[0].stats.failure_rate - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(tools[1].stats.failure_rate, 0.0);
+        assert_eq!(
+            tools[0].stats.last_used.as_deref().map(|t| &t[..10]),
+            Some("2025-03-10")
+        );
+
+        let projects = grouped(&rows, "project", None).unwrap();
+        assert_eq!(projects[0].key, "app");
+        assert_eq!(projects[0].top_tools[0].tool, "Bash");
+
+        // Trend periods read chronologically, not by volume
+        let weeks = grouped(&rows, "tool", Some("week")).unwrap();
+        let weeks: Vec<_> = weeks
+            .iter()
+            .map(|g| (g.key.as_str(), g.stats.calls))
+            .collect();
+        assert_eq!(weeks, [("2025-W10", 3), ("2025-W11", 1)]);
+        let months = grouped(&rows, "tool", Some("month")).unwrap();
+        assert_eq!(
+            (months[0].key.as_str(), months[0].stats.calls),
+            ("2025-03", 4)
+        );
+
+        assert!(grouped(&rows, "model", None).is_err());
+        assert!(grouped(&rows, "tool", Some("year")).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Watch command implementation
+//!
+//! Watches probe base paths for filesystem notifications and runs an incremental extraction
+//! of the probes whose files changed, keeping the database fresh without cron. Probes whose
+//! base path can't be watched (not created yet, or no notification support) are polled
+//! instead: their sessions are re-discovered and compared with the store every interval.
+
+use anyhow::Result;
+use chrono::Local;
+use notify::RecursiveMode;
+use notify_debouncer_mini::new_debouncer;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::extract::{self, ExtractOptions, Verbosity};
+use crate::config::Config;
+use crate::probe::{IngestionProbe, ProbeRegistry};
+use crate::store::StorageBackend;
+
+/// Quiet period before a burst of writes (e.g. a streaming reply) triggers extraction
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// A probe whose base path has a notification watch
+struct Watched<'a> {
+    probe: &'a dyn IngestionProbe,
+    /// Base path as the watcher reports it (symlinks resolved)
+    canonical: PathBuf,
+}
+
+impl Watched<'_> {
+    fn covers(&self, path: &Path) -> bool {
+        path.starts_with(&self.canonical) || path.starts_with(self.probe.base_path())
+    }
+}
+
+pub fn run(
+    store: &dyn StorageBackend,
+    registry: &ProbeRegistry,
+    config: &Config,
+    interval_secs: u64,
+) -> Result<()> {
+    extract::run(
+        store,
+        registry,
+        config,
+        &ExtractOptions::default(),
+        Verbosity::Normal,
+    )?;
+
+    let interval = Duration::from_secs(interval_secs.max(1));
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = match new_debouncer(DEBOUNCE, tx) {
+        Ok(debouncer) => Some(debouncer),
+        Err(e) => {
+            eprintln!(
+                "⚠️  Filesystem notifications unavailable ({}), polling instead",
+                e
+            );
+            None
+        }
+    };
+
+    let mut watched: Vec<Watched> = vec![];
+    let mut polled: Vec<&dyn IngestionProbe> = vec![];
+    // Every enabled probe: one whose data appears later is picked up then
+    for probe in registry.all_probes() {
+        match debouncer.as_mut().and_then(|d| watch(d.watcher(), probe)) {
+            Some(entry) => watched.push(entry),
+            None => polled.push(probe),
+        }
+    }
+
+    println!(
+        "\n👀 Watching {} probe(s) for changes, polling {} every {}s (Ctrl-C to stop)",
+        watched.len(),
+        polled.len(),
+        interval.as_secs()
+    );
+
+    // Chronicle's own writes (database, journal, heartbeat) sit next to the database and
+    // must not look like source changes; import directories below it are still watched
+    let database = config.database_path();
+    let own_dirs: Vec<PathBuf> = database
+        .parent()
+        .map(|dir| vec![dir.to_path_buf(), dir.canonicalize().unwrap_or_default()])
+        .unwrap_or_default();
+
+    let mut last_poll = Instant::now();
+    loop {
+        beat(config, interval_secs);
+
+        let mut changed: Vec<&dyn IngestionProbe> = vec![];
+        match rx.recv_timeout(interval) {
+            Ok(Ok(events)) => {
+                let events: Vec<_> = events
+                    .iter()
+                    .filter(|event| {
+                        !event
+                            .path
+                            .parent()
+                            .is_some_and(|dir| own_dirs.iter().any(|own| own == dir))
+                    })
+                    .collect();
+                for entry in &watched {
+                    if events.iter().any(|event| entry.covers(&event.path)) {
+                        changed.push(entry.probe);
+                    }
+                }
+            }
+            Ok(Err(e)) => eprintln!("⚠️  {}: {}", Local::now().format("%H:%M:%S"), e),
+            // No watcher: the sender is gone, so pace the polling loop ourselves
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(interval),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        if last_poll.elapsed() >= interval {
+            last_poll = Instant::now();
+            // A base path that appeared since the last poll gets a watch from now on
+            if let Some(debouncer) = debouncer.as_mut() {
+                let mut still_polled = vec![];
+                for probe in polled {
+                    match watch(debouncer.watcher(), probe) {
+                        Some(entry) => {
+                            watched.push(entry);
+                            changed.push(probe);
+                        }
+                        None => still_polled.push(probe),
+                    }
+                }
+                polled = still_polled;
+            }
+            for &probe in polled.iter().filter(|p| p.is_available()) {
+                // Probe failures (e.g. a file mid-write) are reported and retried next poll
+                match extract::has_changes(store, &[probe]) {
+                    Ok(true) => changed.push(probe),
+                    Ok(false) => {}
+                    Err(e) => eprintln!("⚠️  {}: {:#}", Local::now().format("%H:%M:%S"), e),
+                }
+            }
+        }
+
+        for probe in changed {
+            println!(
+                "\n🔄 {} changes in {}",
+                Local::now().format("%H:%M:%S"),
+                probe.id()
+            );
+            let options = ExtractOptions {
+                probe: Some(probe.id().to_string()),
+                ..ExtractOptions::default()
+            };
+            // Few sessions change at a time: name each one
+            if let Err(e) = extract::run(store, registry, config, &options, Verbosity::Verbose) {
+                eprintln!("⚠️  {}: {:#}", Local::now().format("%H:%M:%S"), e);
+            }
+        }
+    }
+}
+
+/// Watch a probe's base path, if it exists and the platform watcher accepts it
+fn watch<'a>(
+    watcher: &mut dyn notify::Watcher,
+    probe: &'a dyn IngestionProbe,
+) -> Option<Watched<'a>> {
+    let base = probe.base_path();
+    watcher.watch(base, RecursiveMode::Recursive).ok()?;
+    Some(Watched {
+        probe,
+        canonical: base.canonicalize().unwrap_or_else(|_| base.to_path_buf()),
+    })
+}
+
+/// File touched on every wakeup; `statusline` treats a recent one as a running watch
+fn heartbeat_path(config: &Config) -> PathBuf {
+    config.database_path().with_extension("watch")
+}
+
+fn beat(config: &Config, interval_secs: u64) {
+    // Holds the interval so readers know how stale "recent" may get
+    let _ = std::fs::write(heartbeat_path(config), interval_secs.max(1).to_string());
+}
+
+/// Whether a `watch` has polled within a few of its intervals
+pub fn is_running(config: &Config) -> bool {
+    let path = heartbeat_path(config);
+    let Some(modified) = std::fs::metadata(&path).and_then(|m| m.modified()).ok() else {
+        return false;
+    };
+    let interval: u64 = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(10);
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    // Extraction runs between beats, so allow for a slow one
+    age <= Duration::from_secs(interval * 3 + 60)
+}
@@ -0,0 +1,144 @@
+//! Message content loading
+//!
+//! The store only indexes where each message lives; its content is read back
+//! from the original source through the probe that indexed it. Every consumer
+//! (read, export, dedupe) goes through [`ContentLoader`], which rebuilds the
+//! probe's [`ContentRef`], caches what it has loaded and falls back to a
+//! [`ContentArchive`] when the source can no longer be read.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::probe::{ContentRef, ProbeRegistry};
+use crate::store::{MessageRow, SessionRow};
+
+/// Stored copies of message content, consulted when a source is unreadable
+pub trait ContentArchive {
+    /// Archived raw content of a message, if any
+    fn get(&self, message: &MessageRow) -> Result<Option<String>>;
+}
+
+/// Loads raw message content through the session's probe. Loaded content is cached
+/// for the loader's lifetime, so create one per command or request.
+pub struct ContentLoader<'a> {
+    registry: &'a ProbeRegistry,
+    archive: Option<&'a dyn ContentArchive>,
+    /// Message ID -> raw content
+    cache: RefCell<HashMap<i64, String>>,
+}
+
+impl<'a> ContentLoader<'a> {
+    pub fn new(registry: &'a ProbeRegistry) -> Self {
+        Self {
+            registry,
+            archive: None,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Fall back to `archive` when a message's source can't be read
+    pub fn with_archive(mut self, archive: &'a dyn ContentArchive) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Raw content of a message in the probe's source format
+    pub fn load(&self, session: &SessionRow, message: &MessageRow) -> Result<String> {
+        if let Some(raw) = self.cache.borrow().get(&message.id) {
+            return Ok(raw.clone());
+        }
+
+        let raw = match self.read_source(session, message) {
+            Ok(raw) => raw,
+            Err(e) => match self.archive.map(|a| a.get(message)).transpose()?.flatten() {
+                Some(raw) => raw,
+                None => return Err(e),
+            },
+        };
+        self.cache.borrow_mut().insert(message.id, raw.clone());
+        Ok(raw)
+    }
+
+    /// Only the text parts of a message (no tool or thinking blocks)
+    pub fn load_text(&self, session: &SessionRow, message: &MessageRow) -> Result<String> {
+        self.load(session, message).map(|raw| plain_text(&raw))
+    }
+
+    fn read_source(&self, session: &SessionRow, message: &MessageRow) -> Result<String> {
+        let probe = self
+            .registry
+            .get_probe(&session.probe_source_id)
+            .ok_or_else(|| Error::ProbeUnavailable(session.probe_source_id.clone()))?;
+        probe.get_content(&content_ref(message))
+    }
+}
+
+/// Rebuild the probe content reference for an indexed message
+pub fn content_ref(msg: &MessageRow) -> ContentRef {
+    ContentRef {
+        source_path: msg.source_path.clone().into(),
+        byte_offset: msg.byte_offset.map(|o| o as u64),
+        line_number: msg.line_number.map(|n| n as u32),
+        content_path: msg.content_ref.clone().map(Into::into),
+    }
+}
+
+/// Extract only the text parts of raw probe content (no tool or thinking blocks)
+pub fn plain_text(raw: &str) -> String {
+    let Ok(json) = serde_json::from_str::<Value>(raw.trim()) else {
+        return raw.to_string();
+    };
+    let content = json
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .or_else(|| json.get("content"));
+
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => raw.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    struct Archive;
+
+    impl ContentArchive for Archive {
+        fn get(&self, message: &MessageRow) -> Result<Option<String>> {
+            Ok((message.id == 1).then(|| r#"{"content":"archived"}"#.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_archive_fallback_for_missing_source() {
+        let registry = ProbeRegistry::new(&Config::default());
+        let session = SessionRow {
+            probe_source_id: "gone:Probe".to_string(),
+            ..Default::default()
+        };
+        let message = |id| MessageRow {
+            id,
+            source_path: "/nonexistent/session.jsonl".to_string(),
+            ..Default::default()
+        };
+
+        let plain = ContentLoader::new(&registry);
+        assert!(plain.load(&session, &message(1)).is_err());
+
+        let archive = Archive;
+        let loader = ContentLoader::new(&registry).with_archive(&archive);
+        assert_eq!(loader.load_text(&session, &message(1)).unwrap(), "archived");
+        assert!(loader.load(&session, &message(2)).is_err());
+    }
+}
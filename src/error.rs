@@ -0,0 +1,95 @@
+//! Failures callers may want to tell apart
+//!
+//! Functions return `anyhow::Result`; the failures below are raised as [`Error`] values
+//! inside it, so library users can `downcast_ref::<chronicle::Error>()` and the CLI can
+//! pick an exit code. Anything else is a plain message.
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The probe a session was indexed by is disabled or its source is gone
+    #[error("Source probe not available: {0}")]
+    ProbeUnavailable(String),
+
+    #[error("Session not found: {0}")]
+    SessionNotFound(String),
+
+    #[error("Project not found: {0}")]
+    ProjectNotFound(String),
+
+    #[error("Database not found: {}", .0.display())]
+    DatabaseNotFound(PathBuf),
+
+    /// The database was written by a newer chronicle
+    #[error("{} has schema version {found}, newer than this chronicle supports ({supported}); upgrade chronicle", .path.display())]
+    SchemaMismatch {
+        path: PathBuf,
+        found: u32,
+        supported: u32,
+    },
+
+    /// A source record that can't be read; `line` is 1-based when known
+    #[error("{}{}: {reason}", .path.display(), .line.map(|l| format!(":{}", l)).unwrap_or_default())]
+    ParseError {
+        path: PathBuf,
+        line: Option<usize>,
+        reason: String,
+    },
+
+    /// A strict extraction left more records unparsed than allowed
+    #[error("Parse failure rate {:.2}% exceeds the {:.2}% threshold", .rate * 100.0, .max * 100.0)]
+    FailureRateExceeded { rate: f64, max: f64 },
+}
+
+impl Error {
+    /// A [`Error::ParseError`] for JSON that is malformed or not of the expected shape,
+    /// at the line serde reports when it knows one
+    pub fn invalid_json(path: &Path, what: &str, error: &serde_json::Error) -> Self {
+        Error::ParseError {
+            path: path.to_path_buf(),
+            line: Some(error.line()).filter(|&line| line > 0),
+            reason: format!("Invalid {}: {}", what, error),
+        }
+    }
+
+    /// Process exit code for this failure; 1 is any other error, 2 a usage error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::SessionNotFound(_) | Error::ProjectNotFound(_) => 3,
+            Error::DatabaseNotFound(_) | Error::SchemaMismatch { .. } => 4,
+            Error::ProbeUnavailable(_) => 5,
+            Error::ParseError { .. } | Error::FailureRateExceeded { .. } => 6,
+        }
+    }
+}
+
+/// Exit code of a failed command: that of the first [`Error`] in its cause chain
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .map_or(1, Error::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_from_cause_chain() {
+        let error = Err::<(), _>(Error::SessionNotFound("abc".to_string()))
+            .context("Export failed")
+            .unwrap_err();
+        assert_eq!(exit_code(&error), 3);
+        assert_eq!(exit_code(&anyhow::anyhow!("other")), 1);
+
+        let parse = Error::ParseError {
+            path: PathBuf::from("/tmp/a.jsonl"),
+            line: Some(7),
+            reason: "Invalid JSON line".to_string(),
+        };
+        assert_eq!(parse.to_string(), "/tmp/a.jsonl:7: Invalid JSON line");
+    }
+}
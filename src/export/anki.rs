@@ -0,0 +1,121 @@
+//! Anki flashcard exporter
+
+use anyhow::Result;
+
+use super::Exporter;
+use crate::content::ContentLoader;
+use crate::store::{MessageRow, SessionRow};
+
+/// A question/answer pair taken from a session
+pub struct Card {
+    pub question: String,
+    pub answer: String,
+    pub tags: Vec<String>,
+}
+
+pub struct AnkiExporter;
+
+impl Exporter for AnkiExporter {
+    fn name(&self) -> &str {
+        "anki"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String> {
+        Ok(anki_csv(&session_cards(session, messages, content, None)))
+    }
+}
+
+/// Pair each user message with the assistant text that follows it; `filter` (lowercase)
+/// keeps only questions containing it
+pub fn session_cards(
+    session: &SessionRow,
+    messages: &[MessageRow],
+    content: &ContentLoader,
+    filter: Option<&str>,
+) -> Vec<Card> {
+    let mut tags = vec!["chronicle".to_string(), session.short_hash.clone()];
+    if let Some(ref project) = session.project_name {
+        tags.push(project.replace(char::is_whitespace, "_"));
+    }
+
+    let mut cards = vec![];
+    let mut current: Option<Card> = None;
+
+    for msg in messages {
+        if msg.role != "user" && msg.role != "assistant" {
+            continue;
+        }
+        // Unreadable sources simply yield no cards
+        let Ok(text) = content.load_text(session, msg) else {
+            continue;
+        };
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        if msg.role == "user" {
+            cards.extend(current.take().filter(|c| !c.answer.is_empty()));
+            let selected = filter.is_none_or(|f| text.to_lowercase().contains(f));
+            current = selected.then(|| Card {
+                question: text,
+                answer: String::new(),
+                tags: tags.clone(),
+            });
+        } else if let Some(ref mut card) = current {
+            if !card.answer.is_empty() {
+                card.answer.push_str("\n\n");
+            }
+            card.answer.push_str(&text);
+        }
+    }
+    cards.extend(current.filter(|c| !c.answer.is_empty()));
+
+    cards
+}
+
+/// Render cards as CSV with Anki import headers (front, back, tags)
+pub fn anki_csv(cards: &[Card]) -> String {
+    let mut out = String::from("#separator:Comma\n#html:false\n#tags column:3\n");
+    for card in cards {
+        out.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&card.question),
+            csv_field(&card.answer),
+            csv_field(&card.tags.join(" "))
+        ));
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anki_csv_quotes_fields() {
+        let cards = vec![Card {
+            question: "What does \"move\" do?".to_string(),
+            answer: "Transfers\nownership".to_string(),
+            tags: vec!["chronicle".to_string(), "abc12345".to_string()],
+        }];
+        let csv = anki_csv(&cards);
+        assert!(csv.starts_with("#separator:Comma\n"));
+        assert!(csv.ends_with(
+            "\"What does \"\"move\"\" do?\",\"Transfers\nownership\",\"chronicle abc12345\"\n"
+        ));
+    }
+}
@@ -0,0 +1,334 @@
+//! Decision log exporter
+//!
+//! Keeps only the exchanges worth re-reading: plans the assistant committed to
+//! ("Plan:", "I will ..."), proposals the user confirmed ("yes, go ahead"), and the
+//! closing summary of each session. Everything else in the transcript is dropped.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write;
+
+use super::{session_title, Exporter};
+use crate::content::ContentLoader;
+use crate::store::{MessageRow, SessionRow};
+
+/// Longest excerpt kept per decision
+const MAX_EXCERPT: usize = 600;
+
+/// Shortest closing message kept as a summary (shorter ones are acknowledgements)
+const MIN_SUMMARY: usize = 40;
+
+/// Longest user message still read as a confirmation rather than new instructions
+const MAX_CONFIRMATION: usize = 120;
+
+/// Lines that open a plan or a commitment (lowercase)
+const PLAN_MARKERS: &[&str] = &[
+    "plan:",
+    "# plan",
+    "## plan",
+    "decision:",
+    "i will ",
+    "i'll ",
+    "i'm going to ",
+    "i am going to ",
+    "let's go with ",
+    "we'll go with ",
+    "i decided ",
+    "i've decided ",
+];
+
+/// First words of a confirming reply (lowercase)
+const CONFIRM_WORDS: &[&str] = &[
+    "yes",
+    "yep",
+    "yeah",
+    "ok",
+    "okay",
+    "sure",
+    "agreed",
+    "approved",
+    "proceed",
+    "lgtm",
+    "perfect",
+    "confirmed",
+];
+
+/// Openings of a confirming reply (lowercase)
+const CONFIRM_PHRASES: &[&str] = &[
+    "go ahead",
+    "do it",
+    "sounds good",
+    "looks good",
+    "let's do",
+    "lets do",
+    "go with",
+    "option ",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecisionKind {
+    /// The assistant stated what it would do
+    Plan,
+    /// The user confirmed the assistant's proposal
+    Agreed,
+    /// The session's closing summary
+    Summary,
+}
+
+impl DecisionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DecisionKind::Plan => "Plan",
+            DecisionKind::Agreed => "Agreed",
+            DecisionKind::Summary => "Summary",
+        }
+    }
+}
+
+/// A decision-bearing exchange taken from a session
+#[derive(Debug, Clone, Serialize)]
+pub struct Decision {
+    pub kind: DecisionKind,
+    pub timestamp: Option<String>,
+    /// Excerpt of the plan, proposal or summary
+    pub text: String,
+    /// The user's reply, for agreed proposals
+    pub confirmation: Option<String>,
+}
+
+pub struct DecisionsExporter;
+
+impl Exporter for DecisionsExporter {
+    fn name(&self) -> &str {
+        "decisions"
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String> {
+        let decisions = session_decisions(session, messages, content);
+        decision_log(
+            &format!("Decision log: {}", session_title(session)),
+            &[(session, decisions)],
+        )
+    }
+}
+
+/// Decisions of one session, in order
+pub fn session_decisions(
+    session: &SessionRow,
+    messages: &[MessageRow],
+    content: &ContentLoader,
+) -> Vec<Decision> {
+    let mut decisions: Vec<Decision> = vec![];
+    // Last assistant text, and whether it produced the last decision
+    let mut proposal: Option<(&MessageRow, String, bool)> = None;
+
+    for msg in messages {
+        if msg.role != "user" && msg.role != "assistant" {
+            continue;
+        }
+        // Unreadable sources simply yield no decisions
+        let Ok(text) = content.load_text(session, msg) else {
+            continue;
+        };
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        if msg.role == "assistant" {
+            let plan = plan_excerpt(&text);
+            if let Some(ref plan) = plan {
+                decisions.push(Decision {
+                    kind: DecisionKind::Plan,
+                    timestamp: msg.timestamp.clone(),
+                    text: plan.clone(),
+                    confirmation: None,
+                });
+            }
+            proposal = Some((msg, text, plan.is_some()));
+            continue;
+        }
+
+        if !is_confirmation(&text) {
+            proposal = None;
+            continue;
+        }
+        let Some((assistant, proposed, planned)) = proposal.take() else {
+            continue;
+        };
+        match decisions.last_mut() {
+            // The confirmed message already yielded a plan: that plan was agreed
+            Some(last) if planned => {
+                last.kind = DecisionKind::Agreed;
+                last.confirmation = Some(text);
+            }
+            _ => decisions.push(Decision {
+                kind: DecisionKind::Agreed,
+                timestamp: assistant.timestamp.clone(),
+                text: tail_excerpt(&proposed),
+                confirmation: Some(text),
+            }),
+        }
+    }
+
+    // The closing message, unless it was already kept as a plan
+    if let Some((last, text, false)) = proposal {
+        if text.chars().count() < MIN_SUMMARY {
+            return decisions;
+        }
+        decisions.push(Decision {
+            kind: DecisionKind::Summary,
+            timestamp: last.timestamp.clone(),
+            text: head_excerpt(&text),
+            confirmation: None,
+        });
+    }
+    decisions
+}
+
+/// Markdown decision log over sessions; sessions without decisions are left out
+pub fn decision_log(title: &str, sessions: &[(&SessionRow, Vec<Decision>)]) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "# {}\n", title)?;
+    let mut project: Option<&str> = None;
+    let grouped = sessions.len() > 1;
+    for (session, decisions) in sessions.iter().filter(|(_, d)| !d.is_empty()) {
+        let name = session.project_name.as_deref().unwrap_or("Unassigned");
+        if grouped && project != Some(name) {
+            writeln!(out, "## {}\n", name)?;
+            project = Some(name);
+        }
+        let day = session
+            .first_timestamp
+            .as_deref()
+            .map(|t| &t[..10.min(t.len())])
+            .unwrap_or("undated");
+        writeln!(
+            out,
+            "{} {} · {} · {}\n",
+            if grouped { "###" } else { "##" },
+            day,
+            session.short_hash,
+            session_title(session)
+        )?;
+        for decision in decisions {
+            let time = decision
+                .timestamp
+                .as_deref()
+                .and_then(|t| t.get(11..16))
+                .map(|t| format!(" ({})", t))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "- **{}**{}: {}",
+                decision.kind.label(),
+                time,
+                indent(&decision.text)
+            )?;
+            if let Some(ref reply) = decision.confirmation {
+                for line in reply.lines() {
+                    writeln!(out, "  > {}", line)?;
+                }
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(out)
+}
+
+/// The paragraph opened by the first plan marker, if any line has one
+fn plan_excerpt(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.iter().position(|line| {
+        let line = line.trim_start_matches(['-', '*', '>', ' ']).to_lowercase();
+        PLAN_MARKERS.iter().any(|m| line.starts_with(m))
+    })?;
+    // A heading or "Plan:" line introduces the list below it
+    let mut end = start + 1;
+    while end < lines.len() && !(lines[end].trim().is_empty() && end > start + 1) {
+        end += 1;
+    }
+    Some(clip(lines[start..end].join("\n").trim()))
+}
+
+/// A short reply that accepts what was proposed
+fn is_confirmation(text: &str) -> bool {
+    if text.chars().count() > MAX_CONFIRMATION {
+        return false;
+    }
+    let lower = text.to_lowercase();
+    let first_word: String = lower.chars().take_while(|c| c.is_alphanumeric()).collect();
+    CONFIRM_WORDS.contains(&first_word.as_str())
+        || CONFIRM_PHRASES.iter().any(|p| lower.starts_with(p))
+}
+
+/// Last paragraph of a proposal: where the question to the user usually is
+fn tail_excerpt(text: &str) -> String {
+    let paragraphs: Vec<&str> = text
+        .split("\n\n")
+        .filter(|p| !p.trim().is_empty())
+        .collect();
+    let start = paragraphs.len().saturating_sub(2);
+    clip(paragraphs[start..].join("\n\n").trim())
+}
+
+/// First paragraphs of a summary, up to the excerpt limit
+fn head_excerpt(text: &str) -> String {
+    clip(text.trim())
+}
+
+fn clip(text: &str) -> String {
+    if text.chars().count() <= MAX_EXCERPT {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_EXCERPT).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Continuation lines of a list item
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| match line.trim().is_empty() {
+            true => String::new(),
+            false => format!("  {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_start()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decision_heuristics() {
+        let plan = "Looked at the parser.\n\nPlan:\n1. Split the lexer\n2. Add tests\n\nDone.";
+        assert_eq!(
+            plan_excerpt(plan).as_deref(),
+            Some("Plan:\n1. Split the lexer\n2. Add tests")
+        );
+        assert_eq!(
+            plan_excerpt("Both work.\n- I'll switch to rusqlite.\n- It is bundled.").as_deref(),
+            Some("- I'll switch to rusqlite.\n- It is bundled.")
+        );
+        assert!(plan_excerpt("Here is the output you asked for.").is_none());
+
+        assert!(is_confirmation("Yes, go ahead"));
+        assert!(is_confirmation("ok!"));
+        assert!(is_confirmation("Sounds good, do it"));
+        assert!(!is_confirmation("yesterday the build broke"));
+        assert!(!is_confirmation(&format!("yes but {}", "also ".repeat(40))));
+    }
+}
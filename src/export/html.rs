@@ -0,0 +1,116 @@
+//! Standalone HTML transcript exporter
+
+use anyhow::Result;
+use std::fmt::Write;
+
+use super::{message_heading, session_details, session_title, transcript, Block, Exporter};
+use crate::content::ContentLoader;
+use crate::store::{MessageRow, SessionRow};
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:52rem;margin:2rem auto;\
+padding:0 1rem;color:#222;line-height:1.5}dl{display:grid;grid-template-columns:max-content auto;\
+gap:.2rem 1rem;color:#555}dt{font-weight:600}.msg{border-left:4px solid #ccc;padding:.2rem 1rem;\
+margin:1.5rem 0}.user{border-color:#3b82f6}.assistant{border-color:#10b981}.meta{color:#777;\
+font-size:.85rem}.text{white-space:pre-wrap}pre{background:#f5f5f5;padding:.6rem;overflow-x:auto}\
+details{color:#555;margin:.5rem 0}";
+
+pub struct HtmlExporter;
+
+impl Exporter for HtmlExporter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String> {
+        let title = html_escape(&session_title(session));
+        let mut out = String::new();
+        writeln!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+        )?;
+        writeln!(
+            out,
+            "<title>{}</title>\n<style>{}</style>\n</head>\n<body>",
+            title, HTML_STYLE
+        )?;
+        writeln!(out, "<h1>{}</h1>\n<dl>", title)?;
+        for (label, value) in session_details(session) {
+            writeln!(out, "<dt>{}</dt><dd>{}</dd>", label, html_escape(&value))?;
+        }
+        writeln!(out, "</dl>")?;
+
+        for msg in transcript(session, messages, content) {
+            let (role, meta) = message_heading(&msg);
+            writeln!(out, "<section class=\"msg {}\">", html_escape(&msg.role))?;
+            writeln!(out, "<h2>{}</h2>", html_escape(&role))?;
+            if !meta.is_empty() {
+                writeln!(out, "<div class=\"meta\">{}</div>", html_escape(&meta))?;
+            }
+            for block in &msg.blocks {
+                match block {
+                    Block::Text(text) => writeln!(
+                        out,
+                        "<div class=\"text\">{}</div>",
+                        html_escape(text.trim_end())
+                    )?,
+                    Block::Thinking(text) => writeln!(
+                        out,
+                        "<details><summary>Thinking</summary><div class=\"text\">{}</div></details>",
+                        html_escape(text.trim_end())
+                    )?,
+                    Block::ToolUse { name, input } => {
+                        writeln!(
+                            out,
+                            "<p><strong>🔧 Tool: {}</strong></p>",
+                            html_escape(name)
+                        )?;
+                        if let Some(input) = input {
+                            writeln!(
+                                out,
+                                "<pre>{}</pre>",
+                                html_escape(&serde_json::to_string_pretty(input)?)
+                            )?;
+                        }
+                    }
+                    Block::ToolResult(text) => writeln!(
+                        out,
+                        "<details><summary>Tool result</summary><pre>{}</pre></details>",
+                        html_escape(text.trim_end())
+                    )?,
+                }
+            }
+            writeln!(out, "</section>")?;
+        }
+        writeln!(out, "</body>\n</html>")?;
+        Ok(out)
+    }
+}
+
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_escape() {
+        assert_eq!(
+            html_escape("Use <Vec<T>> & \"co\""),
+            "Use &lt;Vec&lt;T&gt;&gt; &amp; &quot;co&quot;"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! JSON transcript exporter
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::{transcript, Block, Exporter};
+use crate::content::ContentLoader;
+use crate::store::{MessageRow, SessionRow};
+
+pub struct JsonExporter;
+
+impl Exporter for JsonExporter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String> {
+        let messages: Vec<Value> = transcript(session, messages, content)
+            .iter()
+            .map(|msg| {
+                let content: Vec<Value> = msg.blocks.iter().map(block_json).collect();
+                json!({
+                    "role": msg.role,
+                    "timestamp": msg.timestamp,
+                    "model": msg.model,
+                    "content": content,
+                })
+            })
+            .collect();
+
+        let document = json!({
+            "session": {
+                "id": session.id,
+                "short_hash": session.short_hash,
+                "external_id": session.external_id,
+                "title": session.title,
+                "source": session.source_name,
+                "provider": session.provider_name,
+                "model": session.primary_model,
+                "project": session.project_name,
+                "project_path": session.project_path,
+                "first_timestamp": session.first_timestamp,
+                "last_timestamp": session.last_timestamp,
+            },
+            "messages": messages,
+        });
+        Ok(serde_json::to_string_pretty(&document)? + "\n")
+    }
+}
+
+pub(super) fn block_json(block: &Block) -> Value {
+    match block {
+        Block::Text(text) => json!({ "type": "text", "text": text }),
+        Block::Thinking(text) => json!({ "type": "thinking", "thinking": text }),
+        Block::ToolUse { name, input } => {
+            json!({ "type": "tool_use", "name": name, "input": input })
+        }
+        Block::ToolResult(text) => json!({ "type": "tool_result", "content": text }),
+    }
+}
@@ -0,0 +1,72 @@
+//! Markdown transcript exporter
+
+use anyhow::Result;
+use std::fmt::Write;
+
+use super::{message_heading, session_details, session_title, transcript, Block, Exporter};
+use crate::content::ContentLoader;
+use crate::store::{MessageRow, SessionRow};
+
+pub struct MarkdownExporter;
+
+impl Exporter for MarkdownExporter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["md"]
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "# {}\n", session_title(session))?;
+        for (label, value) in session_details(session) {
+            writeln!(out, "- **{}:** {}", label, value)?;
+        }
+        writeln!(out)?;
+
+        for msg in transcript(session, messages, content) {
+            let (role, meta) = message_heading(&msg);
+            writeln!(out, "## {}\n", role)?;
+            if !meta.is_empty() {
+                writeln!(out, "_{}_\n", meta)?;
+            }
+            for block in &msg.blocks {
+                match block {
+                    Block::Text(text) => writeln!(out, "{}\n", text.trim_end())?,
+                    Block::Thinking(text) => writeln!(
+                        out,
+                        "<details><summary>Thinking</summary>\n\n{}\n\n</details>\n",
+                        text.trim_end()
+                    )?,
+                    Block::ToolUse { name, input } => {
+                        writeln!(out, "**🔧 Tool: {}**\n", name)?;
+                        if let Some(input) = input {
+                            writeln!(
+                                out,
+                                "```json\n{}\n```\n",
+                                serde_json::to_string_pretty(input)?
+                            )?;
+                        }
+                    }
+                    Block::ToolResult(text) => writeln!(
+                        out,
+                        "<details><summary>Tool result</summary>\n\n```\n{}\n```\n\n</details>\n",
+                        text.trim_end()
+                    )?,
+                }
+            }
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,280 @@
+//! Session exporters and registry
+//!
+//! An [`Exporter`] renders one session into a file format. Built-ins:
+//! - `markdown` (`md`), `json`, `html`: the full transcript
+//! - `anki`: Q&A flashcards (user question → assistant answer) as an Anki-importable CSV
+//! - `decisions`: a markdown log of plans, agreed proposals and closing summaries
+//!
+//! Crates embedding Chronicle can add their own formats with
+//! [`ExporterRegistry::register`]; a registered exporter replaces a built-in of the
+//! same name.
+
+mod anki;
+mod decisions;
+mod html;
+mod json;
+mod markdown;
+
+pub use anki::{anki_csv, session_cards, AnkiExporter, Card};
+pub use decisions::{decision_log, session_decisions, Decision, DecisionKind, DecisionsExporter};
+pub use html::HtmlExporter;
+pub use json::JsonExporter;
+pub use markdown::MarkdownExporter;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::content::{plain_text, ContentLoader};
+use crate::store::{MessageRow, SessionRow};
+
+/// Renders a session into one export format
+pub trait Exporter: Send + Sync {
+    /// Format name selected with `--format`
+    fn name(&self) -> &str;
+
+    /// Other names accepted for this format
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// File extension for exported files, without the dot
+    fn extension(&self) -> &str;
+
+    /// Render a session; message content is read through `content`
+    fn render(
+        &self,
+        session: &SessionRow,
+        messages: &[MessageRow],
+        content: &ContentLoader,
+    ) -> Result<String>;
+}
+
+/// Registry of available exporters
+pub struct ExporterRegistry {
+    exporters: Vec<Box<dyn Exporter>>,
+}
+
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExporterRegistry {
+    /// Registry with the built-in formats
+    pub fn new() -> Self {
+        let mut registry = Self { exporters: vec![] };
+        registry.register(Box::new(MarkdownExporter));
+        registry.register(Box::new(JsonExporter));
+        registry.register(Box::new(HtmlExporter));
+        registry.register(Box::new(AnkiExporter));
+        registry.register(Box::new(DecisionsExporter));
+        registry
+    }
+
+    /// Add an exporter, replacing any existing one with the same name
+    pub fn register(&mut self, exporter: Box<dyn Exporter>) {
+        self.exporters.retain(|e| e.name() != exporter.name());
+        self.exporters.push(exporter);
+    }
+
+    /// Exporter by name or alias (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        let name = name.to_lowercase();
+        self.exporters
+            .iter()
+            .find(|e| e.name() == name || e.aliases().contains(&name.as_str()))
+            .map(|e| e.as_ref())
+    }
+
+    /// Names of all registered formats
+    pub fn names(&self) -> Vec<&str> {
+        self.exporters.iter().map(|e| e.name()).collect()
+    }
+}
+
+/// A message with its content normalized from the probe's raw format
+pub struct TranscriptMessage {
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub model: Option<String>,
+    pub blocks: Vec<Block>,
+}
+
+/// One piece of message content
+pub enum Block {
+    Text(String),
+    Thinking(String),
+    ToolUse { name: String, input: Option<Value> },
+    ToolResult(String),
+}
+
+/// Load the content of every message; unreadable content becomes an error note
+pub fn transcript(
+    session: &SessionRow,
+    messages: &[MessageRow],
+    content: &ContentLoader,
+) -> Vec<TranscriptMessage> {
+    messages
+        .iter()
+        .map(|msg| TranscriptMessage {
+            role: msg.role.clone(),
+            timestamp: msg.timestamp.clone(),
+            model: msg.model.clone(),
+            blocks: match content.load(session, msg) {
+                Ok(raw) => blocks_from_raw(&raw),
+                Err(e) => vec![Block::Text(format!("[Error loading content: {}]", e))],
+            },
+        })
+        .collect()
+}
+
+/// Split raw probe content into text, thinking and tool blocks
+pub fn blocks_from_raw(raw: &str) -> Vec<Block> {
+    let Ok(json) = serde_json::from_str::<Value>(raw.trim()) else {
+        return vec![Block::Text(raw.to_string())];
+    };
+    let content = json
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .or_else(|| json.get("content"));
+
+    match content {
+        Some(Value::String(s)) => vec![Block::Text(s.clone())],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| {
+                let text = |key: &str| item.get(key).and_then(|t| t.as_str()).map(String::from);
+                match item.get("type").and_then(|t| t.as_str()) {
+                    Some("thinking") => text("thinking").map(Block::Thinking),
+                    Some("tool_use") => Some(Block::ToolUse {
+                        name: text("name").unwrap_or_else(|| "unknown".to_string()),
+                        input: item.get("input").cloned(),
+                    }),
+                    Some("tool_result") => Some(Block::ToolResult(match item.get("content") {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(content) => plain_text(&json!({ "content": content }).to_string()),
+                        None => String::new(),
+                    })),
+                    _ => text("text").map(Block::Text),
+                }
+            })
+            .collect(),
+        _ => vec![Block::Text(raw.to_string())],
+    }
+}
+
+/// Raw probe content as typed JSON blocks (the `content` of JSON exports)
+pub fn content_json(raw: &str) -> Vec<Value> {
+    blocks_from_raw(raw).iter().map(json::block_json).collect()
+}
+
+fn session_title(session: &SessionRow) -> String {
+    session
+        .title
+        .as_deref()
+        .and_then(|t| t.lines().next())
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or("Untitled session")
+        .trim()
+        .to_string()
+}
+
+/// Session details shown at the top of every transcript
+fn session_details(session: &SessionRow) -> Vec<(&'static str, String)> {
+    let mut details = vec![
+        (
+            "Session",
+            format!("{} ({})", session.short_hash, session.external_id),
+        ),
+        (
+            "Source",
+            format!("{} ({})", session.source_name, session.provider_name),
+        ),
+    ];
+    if let Some(ref model) = session.primary_model {
+        details.push(("Model", model.clone()));
+    }
+    if let Some(ref project) = session.project_name {
+        details.push(("Project", project.clone()));
+    } else if let Some(ref path) = session.project_path {
+        details.push(("Path", path.clone()));
+    }
+    if let Some(ref start) = session.first_timestamp {
+        let end = session.last_timestamp.as_deref().unwrap_or(start);
+        details.push(("Time", format!("{} → {}", start, end)));
+    }
+    details
+}
+
+fn message_heading(msg: &TranscriptMessage) -> (String, String) {
+    let mut role = msg.role.clone();
+    if let Some(first) = role.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    let meta = [msg.timestamp.as_deref(), msg.model.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ");
+    (role, meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Plain;
+
+    impl Exporter for Plain {
+        fn name(&self) -> &str {
+            "markdown"
+        }
+        fn extension(&self) -> &str {
+            "txt"
+        }
+        fn render(
+            &self,
+            _: &SessionRow,
+            messages: &[MessageRow],
+            _: &ContentLoader,
+        ) -> Result<String> {
+            Ok(format!("{} messages", messages.len()))
+        }
+    }
+
+    #[test]
+    fn test_registry_lookup_and_override() {
+        let mut registry = ExporterRegistry::new();
+        assert_eq!(
+            registry.names(),
+            vec!["markdown", "json", "html", "anki", "decisions"]
+        );
+        assert_eq!(registry.get("MD").map(|e| e.extension()), Some("md"));
+        assert!(registry.get("pdf").is_none());
+
+        registry.register(Box::new(Plain));
+        assert_eq!(
+            registry.names(),
+            vec!["json", "html", "anki", "decisions", "markdown"]
+        );
+        assert_eq!(registry.get("markdown").map(|e| e.extension()), Some("txt"));
+        assert!(registry.get("md").is_none());
+    }
+
+    #[test]
+    fn test_blocks_from_raw() {
+        let raw = r#"{"message":{"content":[
+            {"type":"thinking","thinking":"hmm"},
+            {"type":"text","text":"Use <Vec<T>> & co"},
+            {"type":"tool_use","name":"Bash","input":{"command":"ls"}},
+            {"type":"tool_result","content":[{"type":"text","text":"src"}]}
+        ]}}"#;
+        let blocks = blocks_from_raw(raw);
+        assert_eq!(blocks.len(), 4);
+        assert!(matches!(&blocks[0], Block::Thinking(t) if t == "hmm"));
+        assert!(matches!(&blocks[2], Block::ToolUse { name, input: Some(_) } if name == "Bash"));
+        assert!(matches!(&blocks[3], Block::ToolResult(t) if t == "src"));
+        assert!(matches!(&blocks_from_raw("plain")[0], Block::Text(t) if t == "plain"));
+    }
+}
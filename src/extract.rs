@@ -0,0 +1,1067 @@
+//! Session extraction: discover sessions through the probes and index them
+//!
+//! The run reports its progress as [`ExtractEvent`]s and never prints; `chronicle
+//! extract` renders them, embedders can ignore them.
+
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use uuid::Uuid;
+
+use crate::analysis::{loops, references, IssueReference};
+use crate::config::Config;
+use crate::error::Error;
+use crate::hooks::{self, HookEvent, ProbeRun};
+use crate::probe::{
+    IngestionProbe, ProbeRegistry, SessionMetadata, SessionRef, SkipCounts, SourceFingerprint,
+};
+use crate::store::StorageBackend;
+
+/// Options for an extraction run
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Re-extract sessions even if their source is unchanged
+    pub full: bool,
+    /// Parsing threads; overrides `extraction.workers`
+    pub jobs: Option<usize>,
+    /// Record every unparsed record in `parse_errors`, fail above the configured rate
+    /// and stop at the first session that can't be extracted
+    pub strict: bool,
+    /// Only this probe (e.g. `claude:ClaudeCode`)
+    pub probe: Option<String>,
+    /// Only the session with this ID in its source; it is re-extracted even if unchanged
+    pub session: Option<String>,
+}
+
+/// Progress of an extraction run, in the order things happen
+pub enum ExtractEvent<'a> {
+    /// No probe is enabled with an existing source
+    NoProbes,
+    /// The configured general project didn't exist and was created
+    GeneralProjectCreated {
+        name: &'a str,
+    },
+    ProbeStarted {
+        probe: &'a dyn IngestionProbe,
+    },
+    /// Sessions discovered, and how many of them will be extracted
+    SessionsFound {
+        count: usize,
+        changed: usize,
+    },
+    SessionStored(StoredSession<'a>),
+    /// A session couldn't be extracted and was left for the next run
+    SessionFailed {
+        session: &'a SessionRef,
+        error: &'a anyhow::Error,
+    },
+    /// Sessions skipped because their source is unchanged
+    SessionsUnchanged {
+        count: usize,
+    },
+    /// Source entries of the probe that could not be parsed
+    EntriesDropped {
+        skipped: &'a SkipCounts,
+    },
+    ProbeFinished,
+    /// Tool settings were merged into this many projects' metadata
+    ProjectSettingsIndexed {
+        count: usize,
+    },
+    Complete,
+    /// Strict runs only: unparsed records over all records
+    StrictSummary {
+        unparsed: usize,
+        records: usize,
+    },
+    /// A hook command failed or timed out; extraction goes on
+    HookFailed {
+        message: String,
+    },
+}
+
+/// A session written during a run
+#[derive(Debug)]
+pub struct StoredSession<'a> {
+    pub session: &'a SessionRef,
+    pub session_id: &'a str,
+    pub metadata: &'a SessionMetadata,
+    /// Project mapped to the session's source group that doesn't exist
+    pub missing_mapping: Option<&'a str>,
+    /// Project created for the session's working directory
+    pub created_project: Option<&'a str>,
+    pub tool_loops: usize,
+}
+
+/// Totals of a run
+#[derive(Debug, Default)]
+pub struct ExtractSummary {
+    pub probes: Vec<ProbeRun>,
+    /// Sessions that couldn't be extracted
+    pub failures: Vec<SessionFailure>,
+    /// Messages plus dropped entries
+    pub records: usize,
+    pub unparsed: usize,
+}
+
+impl ExtractSummary {
+    /// Sessions written across probes
+    pub fn extracted(&self) -> usize {
+        self.probes.iter().map(|r| r.extracted).sum()
+    }
+
+    /// Fraction of records that could not be parsed
+    pub fn failure_rate(&self) -> f64 {
+        match self.records {
+            0 => 0.0,
+            records => self.unparsed as f64 / records as f64,
+        }
+    }
+}
+
+/// A session that couldn't be extracted
+#[derive(Debug)]
+pub struct SessionFailure {
+    pub probe: String,
+    pub session_id: String,
+    pub source_path: std::path::PathBuf,
+    /// The error with its causes
+    pub error: String,
+}
+
+/// A parsed session, with raw message content when caching is on
+struct ParsedSession {
+    metadata: SessionMetadata,
+    /// Raw content per message; empty unless `extraction.cache_content` is set
+    contents: Vec<Option<String>>,
+}
+
+/// Extract sessions from all available probes. Sessions whose source is unchanged since the
+/// last run are skipped unless `full` (or `strict`, which must see every record) is set.
+pub fn run(
+    store: &dyn StorageBackend,
+    registry: &ProbeRegistry,
+    config: &Config,
+    options: &ExtractOptions,
+    report: &mut dyn FnMut(ExtractEvent),
+) -> Result<ExtractSummary> {
+    let workers = options
+        .jobs
+        .unwrap_or_else(|| config.extraction.worker_count())
+        .max(1);
+    let full = options.full || options.strict || options.session.is_some();
+    let cache_content = config.extraction.cache_content;
+    let mut summary = ExtractSummary::default();
+
+    let available = match options.probe.as_deref() {
+        Some(id) => vec![registry
+            .get_probe(id)
+            .filter(|p| p.is_available())
+            .ok_or_else(|| Error::ProbeUnavailable(id.to_string()))?],
+        None => registry.available_probes(),
+    };
+
+    if available.is_empty() {
+        report(ExtractEvent::NoProbes);
+        return Ok(summary);
+    }
+
+    fire(config, HookEvent::PreExtract, report, || {
+        json!({
+            "probes": available.iter().map(|p| p.id()).collect::<Vec<_>>(),
+            "full": full,
+        })
+    });
+    let started_at = chrono::Utc::now();
+    // Earliest activity among re-indexed sessions: rollups are refreshed from that day
+    let mut earliest: Option<chrono::DateTime<chrono::Utc>> = None;
+
+    let general_project = match config.linking.general_project {
+        Some(ref name) => Some(ensure_general_project(store, name, report)?),
+        None => None,
+    };
+
+    for &probe in &available {
+        report(ExtractEvent::ProbeStarted { probe });
+
+        // Ensure provider exists (for multi-provider sources, we'll store specific ones at message level)
+        if probe.source_type() == crate::probe::SourceType::Single {
+            store.ensure_provider(probe.provider(), probe.provider(), None)?;
+        }
+
+        // Ensure probe source exists
+        store.ensure_probe_source(
+            probe.id(),
+            if probe.source_type() == crate::probe::SourceType::Single {
+                Some(probe.provider())
+            } else {
+                None
+            },
+            probe.source(),
+            probe.source_type(),
+            None, // base_path not tracked in DB yet
+            "active",
+        )?;
+        store.set_probe_capabilities(probe.id(), &probe.capabilities())?;
+
+        // Discover sessions, skipping those whose source hasn't changed since the last run
+        let mut sessions = probe.discover()?;
+        if let Some(ref id) = options.session {
+            sessions.retain(|s| &s.id == id);
+        }
+        let pending = changed_sessions(store, probe, &sessions, full)?;
+        let unchanged = sessions.len() - pending.len();
+        report(ExtractEvent::SessionsFound {
+            count: sessions.len(),
+            changed: pending.len(),
+        });
+
+        // Parse sessions on worker threads; writes stay on this thread as results arrive
+        let workers = workers.min(pending.len()).max(1);
+        let next = AtomicUsize::new(0);
+        let mut skipped = SkipCounts::default();
+        let mut failed = 0;
+        thread::scope(|scope| -> Result<()> {
+            let (tx, rx) = mpsc::sync_channel(workers * 2);
+            for _ in 0..workers {
+                let tx = tx.clone();
+                let (next, pending) = (&next, &pending);
+                scope.spawn(move || loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((session, _)) = pending.get(idx) else {
+                        break;
+                    };
+                    // Content to cache is read here too, while the source is known to exist
+                    let parsed = probe.extract_metadata(session).map(|metadata| {
+                        let contents = match cache_content {
+                            true => metadata
+                                .messages
+                                .iter()
+                                .map(|m| probe.get_content(&m.content_ref).ok())
+                                .collect(),
+                            false => vec![],
+                        };
+                        ParsedSession { metadata, contents }
+                    });
+                    // Stop early once the writer has bailed out
+                    if tx.send((idx, parsed)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            for (idx, parsed) in rx {
+                let (session, fingerprint) = &pending[idx];
+                // A malformed source fails its own session, not the run; its fingerprint
+                // isn't recorded, so the next run tries it again
+                let parsed = match parsed {
+                    Ok(parsed) => parsed,
+                    Err(e) if options.strict => {
+                        return Err(e.context(format!(
+                            "Failed to extract {} session {}",
+                            probe.id(),
+                            session.id
+                        )))
+                    }
+                    Err(e) => {
+                        report(ExtractEvent::SessionFailed { session, error: &e });
+                        failed += 1;
+                        summary.failures.push(SessionFailure {
+                            probe: probe.id().to_string(),
+                            session_id: session.id.clone(),
+                            source_path: session.source_path.clone(),
+                            error: format!("{:#}", e),
+                        });
+                        continue;
+                    }
+                };
+                let metadata = &parsed.metadata;
+                skipped.merge(&metadata.skipped);
+                summary.records += metadata.messages.len() + metadata.skipped.total();
+                // One transaction per session instead of autocommitting every row
+                let mut stored = None;
+                store.transaction(&mut || {
+                    let outcome = store_session(
+                        store,
+                        config,
+                        probe,
+                        session,
+                        &parsed,
+                        fingerprint.as_ref(),
+                        general_project.as_deref(),
+                    )?;
+                    if options.strict {
+                        store.replace_parse_errors(&outcome.session_id, &metadata.skipped)?;
+                    }
+                    stored = Some(outcome);
+                    Ok(())
+                })?;
+                let first = metadata
+                    .messages
+                    .iter()
+                    .filter_map(|m| m.timestamp)
+                    .chain(metadata.first_timestamp)
+                    .min();
+                earliest = earliest.into_iter().chain(first).min();
+                if let Some(ref outcome) = stored {
+                    report(ExtractEvent::SessionStored(StoredSession {
+                        session,
+                        session_id: &outcome.session_id,
+                        metadata,
+                        missing_mapping: outcome.missing_mapping.as_deref(),
+                        created_project: outcome.created_project.as_deref(),
+                        tool_loops: outcome.tool_loops,
+                    }));
+                    fire(config, HookEvent::PostSession, report, || {
+                        hooks::session_payload(probe.id(), &outcome.session_id, metadata)
+                    });
+                }
+            }
+            Ok(())
+        })?;
+
+        if unchanged > 0 {
+            report(ExtractEvent::SessionsUnchanged { count: unchanged });
+        }
+        summary.unparsed += skipped.total();
+        if !skipped.is_empty() {
+            report(ExtractEvent::EntriesDropped { skipped: &skipped });
+        }
+        store.update_probe_indexed(probe.id())?;
+        summary.probes.push(ProbeRun {
+            probe: probe.id().to_string(),
+            found: sessions.len(),
+            extracted: pending.len() - failed,
+            unchanged,
+            dropped: skipped.total(),
+            failed,
+        });
+        report(ExtractEvent::ProbeFinished);
+    }
+
+    if let Some(ref id) = options.session {
+        if summary.probes.iter().all(|r| r.found == 0) {
+            return Err(Error::SessionNotFound(id.clone()).into());
+        }
+    }
+
+    let indexed = index_project_metadata(store, &available)?;
+    if indexed > 0 {
+        report(ExtractEvent::ProjectSettingsIndexed { count: indexed });
+    }
+    if let Some(earliest) = earliest {
+        let day = earliest.with_timezone(&chrono::Local).format("%Y-%m-%d");
+        store.refresh_usage_rollups(Some(&day.to_string()))?;
+    }
+
+    report(ExtractEvent::Complete);
+    fire(config, HookEvent::PostRun, report, || {
+        json!({
+            "started_at": started_at.to_rfc3339(),
+            "finished_at": chrono::Utc::now().to_rfc3339(),
+            "extracted": summary.extracted(),
+            "probes": summary.probes,
+        })
+    });
+
+    if options.strict {
+        report(ExtractEvent::StrictSummary {
+            unparsed: summary.unparsed,
+            records: summary.records,
+        });
+        let rate = summary.failure_rate();
+        if rate > config.extraction.max_failure_rate {
+            return Err(Error::FailureRateExceeded {
+                rate,
+                max: config.extraction.max_failure_rate,
+            }
+            .into());
+        }
+    }
+    Ok(summary)
+}
+
+/// Run an event's hooks, reporting failures
+fn fire(
+    config: &Config,
+    event: HookEvent,
+    report: &mut dyn FnMut(ExtractEvent),
+    payload: impl FnOnce() -> serde_json::Value,
+) {
+    for message in hooks::fire(&config.hooks, event, payload) {
+        report(ExtractEvent::HookFailed { message });
+    }
+}
+
+/// Sessions needing extraction, with their current source fingerprints
+fn changed_sessions<'a>(
+    store: &dyn StorageBackend,
+    probe: &dyn IngestionProbe,
+    sessions: &'a [SessionRef],
+    full: bool,
+) -> Result<Vec<(&'a SessionRef, Option<SourceFingerprint>)>> {
+    let mut pending = vec![];
+    for session in sessions {
+        let fingerprint = probe.fingerprint(session);
+        if !full && fingerprint.is_some() {
+            let stored_id = format!("{}:{}", probe.id(), session.id);
+            if store.get_session_fingerprint(&stored_id)? == fingerprint {
+                continue;
+            }
+        }
+        pending.push((session, fingerprint));
+    }
+    Ok(pending)
+}
+
+/// Whether any of `probes` has new or modified sessions since the last extraction.
+/// Sessions without a fingerprint count as changed only when they were never stored.
+pub fn has_changes(store: &dyn StorageBackend, probes: &[&dyn IngestionProbe]) -> Result<bool> {
+    for probe in probes {
+        for (session, fingerprint) in changed_sessions(store, *probe, &probe.discover()?, false)? {
+            let stored_id = format!("{}:{}", probe.id(), session.id);
+            if fingerprint.is_some() || !store.has_session(&stored_id)? {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// What storing a session did
+struct StoreOutcome {
+    session_id: String,
+    missing_mapping: Option<String>,
+    created_project: Option<String>,
+    tool_loops: usize,
+}
+
+/// Write one extracted session with its links, references, loops and messages
+fn store_session(
+    store: &dyn StorageBackend,
+    config: &Config,
+    probe: &dyn IngestionProbe,
+    session: &SessionRef,
+    parsed: &ParsedSession,
+    fingerprint: Option<&SourceFingerprint>,
+    general_project: Option<&str>,
+) -> Result<StoreOutcome> {
+    let metadata = &parsed.metadata;
+
+    // Store session
+    let session_id = store.upsert_session(probe.id(), session, metadata)?;
+
+    // Apply configured source-group -> project mappings
+    let mapped = metadata
+        .source_group
+        .as_deref()
+        .and_then(|group| config.mapped_project(probe.id(), group));
+    let mut missing_mapping = None;
+    if let Some(mapped) = mapped {
+        match store.find_project(mapped)? {
+            Some(project) => {
+                store.link_session_auto(&session_id, &project.id)?;
+            }
+            None => missing_mapping = Some(mapped.to_string()),
+        }
+    }
+
+    // Unmatched working directories get a project of their own
+    let mut created_project = None;
+    if config.linking.auto_create_projects && mapped.is_none() {
+        if let Some((project_id, name)) = auto_create_project(store, metadata)? {
+            store.link_session_auto(&session_id, &project_id)?;
+            created_project = Some(name);
+        }
+    }
+
+    // Sessions without a working directory or repository go to the general project
+    if let Some(general_id) = general_project {
+        if mapped.is_none() && metadata.project_path.is_none() && metadata.git_remote.is_none() {
+            store.link_session_auto(&session_id, general_id)?;
+        }
+    }
+
+    // Store issue/PR references from content and title
+    store.replace_session_references(&session_id, &session_references(metadata))?;
+    store.replace_session_commits(&session_id, &metadata.commits)?;
+    store.replace_session_skips(&session_id, &metadata.skipped)?;
+
+    // Flag runaway tool loops
+    let tool_loops =
+        loops::detect_tool_loops(&metadata.messages, config.anomalies.tool_loop_threshold);
+    store.replace_tool_loops(&session_id, &tool_loops)?;
+
+    // Store messages
+    if !metadata.messages.is_empty() {
+        let message_ids = store.insert_messages(&session_id, &metadata.messages)?;
+
+        // Contents line up with the messages when caching is on, and are empty otherwise
+        let cached: Vec<(i64, String)> = message_ids
+            .into_iter()
+            .zip(&parsed.contents)
+            .filter_map(|(id, content)| Some((id, content.clone()?)))
+            .collect();
+        store.cache_content(&cached)?;
+    }
+    store.refresh_session_metrics(&session_id)?;
+
+    if let Some(fingerprint) = fingerprint {
+        store.set_session_fingerprint(&session_id, fingerprint)?;
+    }
+
+    Ok(StoreOutcome {
+        session_id,
+        missing_mapping,
+        created_project,
+        tool_loops: tool_loops.len(),
+    })
+}
+
+/// Issue/PR references of a session's content and title
+pub fn session_references(metadata: &SessionMetadata) -> Vec<IssueReference> {
+    let mut session_refs = metadata.references.clone();
+    if let Some(ref title) = metadata.title {
+        session_refs.extend(references::detect(title));
+    }
+    session_refs.sort();
+    session_refs.dedup();
+    session_refs
+}
+
+/// Find or create the project collecting general (non-code) sessions; returns its ID
+fn ensure_general_project(
+    store: &dyn StorageBackend,
+    name: &str,
+    report: &mut dyn FnMut(ExtractEvent),
+) -> Result<String> {
+    if let Some(project) = store.find_project(name)? {
+        return Ok(project.id);
+    }
+    let id = Uuid::new_v4().to_string();
+    store.create_project(&id, name, "general", None, None)?;
+    report(ExtractEvent::GeneralProjectCreated { name });
+    Ok(id)
+}
+
+/// Create a project for a session's working directory when no project matches its path or
+/// git remote; returns the new project's ID and name
+fn auto_create_project(
+    store: &dyn StorageBackend,
+    metadata: &SessionMetadata,
+) -> Result<Option<(String, String)>> {
+    let Some(ref path) = metadata.project_path else {
+        return Ok(None);
+    };
+    if store.project_for_path(path)?.is_some() {
+        return Ok(None);
+    }
+    if let Some(ref remote) = metadata.git_remote {
+        if store.find_project_by_git_remote(remote)?.is_some() {
+            return Ok(None);
+        }
+    }
+    let Some(base) = project_name(path, metadata.git_remote.as_deref()) else {
+        return Ok(None);
+    };
+
+    // Same-named repositories in different places get numbered names
+    let taken: HashSet<String> = store.list_projects()?.into_iter().map(|p| p.name).collect();
+    let name = (1..)
+        .map(|n| match n {
+            1 => base.clone(),
+            n => format!("{}-{}", base, n),
+        })
+        .find(|name| !taken.contains(name))
+        .unwrap_or(base);
+
+    let id = Uuid::new_v4().to_string();
+    store.create_project(&id, &name, "code", Some(path), None)?;
+    if let Some(ref remote) = metadata.git_remote {
+        store.add_project_identifier(&id, "git_remote", remote)?;
+    }
+    Ok(Some((id, name)))
+}
+
+/// Repository name from the git remote, else the directory name
+fn project_name(path: &str, git_remote: Option<&str>) -> Option<String> {
+    let from_remote = git_remote.and_then(|remote| {
+        remote
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+    });
+    from_remote
+        .or_else(|| Path::new(path).file_name().and_then(|n| n.to_str()))
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// Merge tool-specific project settings (e.g. Claude Code permissions) into project
+/// metadata for the probes that ran; returns how many project/tool pairs were merged
+fn index_project_metadata(
+    store: &dyn StorageBackend,
+    probes: &[&dyn IngestionProbe],
+) -> Result<usize> {
+    let mut indexed = 0;
+
+    for project in store.list_projects()? {
+        // Primary path first, so it wins when several paths carry settings
+        let paths = store.get_project_paths(&project.id)?;
+        for probe in probes {
+            if let Some(settings) = paths.iter().find_map(|p| probe.project_metadata(p)) {
+                store.merge_project_metadata(&project.id, probe.source(), settings)?;
+                indexed += 1;
+            }
+        }
+    }
+
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::SourceType;
+    use crate::store::fixtures::{message, session};
+
+    /// Config with the exec probe `acme:Acme` running `script` as the only probe;
+    /// `probe_yaml` goes under the probe and `yaml` at the top level
+    fn exec_config(dir: &Path, script: &Path, probe_yaml: &str, yaml: &str) -> Config {
+        let mut config = format!(
+            "database:\n  path: {}\nprobes:\n  acme:Acme:\n    base_path: {}\n    command: sh {}\n{}",
+            dir.join("db").display(),
+            dir.display(),
+            script.display(),
+            probe_yaml
+        );
+        for id in crate::probe::BUILTIN_PROBES {
+            config.push_str(&format!("  {}:\n    enabled: false\n", id));
+        }
+        config.push_str(yaml);
+        serde_yaml::from_str(&config).unwrap()
+    }
+
+    #[test]
+    fn test_source_group_mapped_to_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = exec_config(
+            dir.path(),
+            &dir.path().join("probe.sh"),
+            "    project_map:\n      hash1: app\n      hash2: gone\n",
+            "",
+        );
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+        let probe = registry.get_probe("acme:Acme").unwrap();
+        store
+            .ensure_probe_source("acme:Acme", None, "Acme", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project("p1", "app", "git", Some("/src/app"), None)
+            .unwrap();
+
+        // Started outside the repo, so only the mapping can link it
+        let stored = |group: &str| {
+            let mut metadata = session(group, Some("/tmp"), vec![message("m", "user", None)]);
+            metadata.source_group = Some(group.to_string());
+            let session = SessionRef {
+                id: group.to_string(),
+                source_path: dir.path().join(group),
+            };
+            let parsed = ParsedSession {
+                metadata,
+                contents: vec![],
+            };
+            store_session(&store, &config, probe, &session, &parsed, None, None).unwrap()
+        };
+        let mapped = stored("hash1");
+        let session = store.get_session(&mapped.session_id).unwrap().unwrap();
+        assert_eq!(session.project_id.as_deref(), Some("p1"));
+        assert_eq!(session.source_group.as_deref(), Some("hash1"));
+
+        // A mapping to a project that doesn't exist is reported, not created
+        let missing = stored("hash2");
+        assert_eq!(missing.missing_mapping.as_deref(), Some("gone"));
+        let session = store.get_session(&missing.session_id).unwrap().unwrap();
+        assert_eq!(session.project_id, None);
+        assert!(stored("hash3").missing_mapping.is_none());
+    }
+
+    #[test]
+    fn test_project_name_prefers_repository() {
+        assert_eq!(
+            project_name("/work/checkout", Some("git@github.com:acme/widgets.git")).as_deref(),
+            Some("widgets")
+        );
+        assert_eq!(
+            project_name("/work/app", Some("https://github.com/acme/api/")).as_deref(),
+            Some("api")
+        );
+        assert_eq!(project_name("/work/app", None).as_deref(), Some("app"));
+        assert_eq!(project_name("/", None), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_failed_session_does_not_stop_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        std::fs::write(
+            &script,
+            "case \"$1\" in\n\
+             discover) echo '[{\"id\":\"good\"},{\"id\":\"bad\"}]' ;;\n\
+             extract) [ \"$2\" = bad ] && { echo corrupt >&2; exit 1; }\n\
+             echo '{\"messages\":[{\"role\":\"user\",\"text\":\"hi\"}]}' ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        let config = exec_config(dir.path(), &script, "", "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+
+        let mut options = ExtractOptions::default();
+        let summary = run(&store, &registry, &config, &options, &mut |_| {}).unwrap();
+        assert_eq!(summary.extracted(), 1);
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].session_id, "bad");
+        assert!(summary.failures[0].error.contains("corrupt"));
+
+        options.strict = true;
+        assert!(run(&store, &registry, &config, &options, &mut |_| {}).is_err());
+
+        // Selecting one session re-extracts it alone, even though it's unchanged
+        let options = ExtractOptions {
+            probe: Some("acme:Acme".to_string()),
+            session: Some("good".to_string()),
+            ..Default::default()
+        };
+        let summary = run(&store, &registry, &config, &options, &mut |_| {}).unwrap();
+        assert_eq!((summary.extracted(), summary.failures.len()), (1, 0));
+        let missing = ExtractOptions {
+            session: Some("gone".to_string()),
+            ..options
+        };
+        assert!(run(&store, &registry, &config, &missing, &mut |_| {}).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_has_changes_counts_new_sessions_without_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        // The session's path doesn't exist, so it has no fingerprint
+        std::fs::write(
+            &script,
+            format!(
+                "case \"$1\" in\n\
+                 discover) echo '[{{\"id\":\"a\",\"path\":\"{}\"}}]' ;;\n\
+                 extract) echo '{{\"messages\":[{{\"role\":\"user\",\"text\":\"hi\"}}]}}' ;;\n\
+                 esac\n",
+                dir.path().join("missing").display()
+            ),
+        )
+        .unwrap();
+        let config = exec_config(dir.path(), &script, "", "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+        let probes = registry.available_probes();
+
+        assert!(has_changes(&store, &probes).unwrap());
+        run(
+            &store,
+            &registry,
+            &config,
+            &ExtractOptions::default(),
+            &mut |_| {},
+        )
+        .unwrap();
+        assert!(!has_changes(&store, &probes).unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_second_extract_skips_unchanged_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("a.jsonl");
+        std::fs::write(&source, "hi\n").unwrap();
+        let script = dir.path().join("probe.sh");
+        std::fs::write(
+            &script,
+            format!(
+                "case \"$1\" in\n\
+                 discover) echo '[{{\"id\":\"a\",\"path\":\"{}\"}}]' ;;\n\
+                 extract) echo '{{\"messages\":[{{\"role\":\"user\",\"text\":\"hi\"}}]}}' ;;\n\
+                 esac\n",
+                source.display()
+            ),
+        )
+        .unwrap();
+        let config = exec_config(dir.path(), &script, "", "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+        let options = ExtractOptions::default();
+
+        let summary = run(&store, &registry, &config, &options, &mut |_| {}).unwrap();
+        assert_eq!((summary.extracted(), summary.probes[0].unchanged), (1, 0));
+        assert!(store
+            .get_session_fingerprint("acme:Acme:a")
+            .unwrap()
+            .is_some());
+
+        let summary = run(&store, &registry, &config, &options, &mut |_| {}).unwrap();
+        assert_eq!((summary.extracted(), summary.probes[0].unchanged), (0, 1));
+
+        // Appending changes the size, so the fingerprint no longer matches
+        std::fs::write(&source, "hi\nagain\n").unwrap();
+        let summary = run(&store, &registry, &config, &options, &mut |_| {}).unwrap();
+        assert_eq!((summary.extracted(), summary.probes[0].unchanged), (1, 0));
+
+        let full = ExtractOptions {
+            full: true,
+            ..ExtractOptions::default()
+        };
+        let summary = run(&store, &registry, &config, &full, &mut |_| {}).unwrap();
+        assert_eq!(summary.extracted(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_worker_pool_stores_every_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        let discovered: Vec<String> = (1..=12).map(|n| format!("{{\"id\":\"s{}\"}}", n)).collect();
+        // The message text names the session it was extracted from
+        std::fs::write(
+            &script,
+            format!(
+                "case \"$1\" in\n\
+                 discover) echo '[{}]' ;;\n\
+                 extract) [ \"$2\" = s7 ] && {{ echo corrupt >&2; exit 1; }}\n\
+                 echo '{{\"messages\":[{{\"role\":\"user\",\"text\":\"session '\"$2\"'\"}}]}}' ;;\n\
+                 esac\n",
+                discovered.join(",")
+            ),
+        )
+        .unwrap();
+        let config = exec_config(dir.path(), &script, "", "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+
+        let options = ExtractOptions {
+            jobs: Some(4),
+            ..Default::default()
+        };
+        let mut stored = 0;
+        let summary = run(&store, &registry, &config, &options, &mut |event| {
+            if let ExtractEvent::SessionStored(_) = event {
+                stored += 1;
+            }
+        })
+        .unwrap();
+        assert_eq!((summary.extracted(), stored), (11, 11));
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].session_id, "s7");
+
+        // Each worker's result lands on its own session
+        let sessions = store.list_sessions(None, None).unwrap();
+        assert_eq!(sessions.len(), 11);
+        for session in &sessions {
+            let expected = format!("session {}", session.external_id);
+            assert_eq!(session.title.as_deref(), Some(expected.as_str()));
+            assert_eq!(session.message_count, 1);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_general_project_collects_sessions_without_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        std::fs::write(
+            &script,
+            "case \"$1\" in\n\
+             discover) echo '[{\"id\":\"code\"},{\"id\":\"chat\"}]' ;;\n\
+             extract) [ \"$2\" = code ] && dir=',\"project_path\":\"/src/app\"'\n\
+             echo '{\"messages\":[{\"role\":\"user\",\"text\":\"hi\"}]'\"$dir\"'}' ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        let config = exec_config(
+            dir.path(),
+            &script,
+            "",
+            "linking:\n  general_project: Inbox\n  auto_create_projects: true\n",
+        );
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+
+        let mut created = vec![];
+        run(
+            &store,
+            &registry,
+            &config,
+            &ExtractOptions::default(),
+            &mut |event| {
+                if let ExtractEvent::GeneralProjectCreated { name } = event {
+                    created.push(name.to_string());
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(created, ["Inbox"]);
+
+        let inbox = store.find_project("Inbox").unwrap().unwrap();
+        let session = |id: &str| store.get_session(id).unwrap().unwrap();
+        let chat = session("acme:Acme:chat");
+        assert_eq!(chat.project_id.as_deref(), Some(inbox.id.as_str()));
+        assert!(chat.is_general());
+        let code = session("acme:Acme:code");
+        assert_ne!(code.project_id.as_deref(), Some(inbox.id.as_str()));
+        assert!(!code.is_general());
+
+        // Later runs reuse the project
+        created.clear();
+        run(
+            &store,
+            &registry,
+            &config,
+            &ExtractOptions::default(),
+            &mut |event| {
+                if let ExtractEvent::GeneralProjectCreated { name } = event {
+                    created.push(name.to_string());
+                }
+            },
+        )
+        .unwrap();
+        assert!(created.is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_strict_records_unparsed_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        std::fs::write(
+            &script,
+            "case \"$1\" in\n\
+             discover) echo '[{\"id\":\"s1\"}]' ;;\n\
+             extract) echo '{\"messages\":[{\"role\":\"user\",\"text\":\"hi\"},{\"role\":\"bogus\"}]}' ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        let parse_errors = |config: &Config| -> Vec<(String, String)> {
+            let conn = rusqlite::Connection::open(config.database_path()).unwrap();
+            let mut stmt = conn
+                .prepare("SELECT reason, location FROM parse_errors ORDER BY id")
+                .unwrap();
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)));
+            rows.unwrap().map(Result::unwrap).collect()
+        };
+        let strict = ExtractOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        // Without --strict the skip is only counted
+        let config = exec_config(dir.path(), &script, "", "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+        run(
+            &store,
+            &registry,
+            &config,
+            &ExtractOptions::default(),
+            &mut |_| {},
+        )
+        .unwrap();
+        assert!(parse_errors(&config).is_empty());
+
+        // One of two records unparsed is far above the default 1%
+        let error = run(&store, &registry, &config, &strict, &mut |_| {}).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::FailureRateExceeded { .. })
+        ));
+        let expected = [(
+            "unknown message role 'bogus'".to_string(),
+            "acme:Acme s1 message 1".to_string(),
+        )];
+        assert_eq!(parse_errors(&config), expected);
+
+        // Re-running replaces the session's rows rather than adding to them
+        let config = exec_config(
+            dir.path(),
+            &script,
+            "",
+            "extraction:\n  max_failure_rate: 0.6\n",
+        );
+        let summary = run(&store, &registry, &config, &strict, &mut |_| {}).unwrap();
+        assert_eq!((summary.unparsed, summary.records), (1, 2));
+        assert_eq!(parse_errors(&config), expected);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_selective_extraction() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        std::fs::write(
+            &script,
+            "case \"$1\" in\n\
+             discover) echo '[{\"id\":\"s1\"},{\"id\":\"s2\"}]' ;;\n\
+             extract) echo '{\"messages\":[{\"role\":\"user\",\"text\":\"hi\"}]}' ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        // A second exec probe, listed after acme's settings
+        let beta = format!(
+            "  beta:Beta:\n    base_path: {}\n    command: sh {}\n",
+            dir.path().display(),
+            script.display()
+        );
+        let config = exec_config(dir.path(), &script, &beta, "");
+        let store = crate::store::MetadataStore::open(&config.database_path()).unwrap();
+        let registry = ProbeRegistry::new(&config);
+        let extract = |probe: Option<&str>, session: Option<&str>| {
+            let options = ExtractOptions {
+                probe: probe.map(String::from),
+                session: session.map(String::from),
+                ..Default::default()
+            };
+            run(&store, &registry, &config, &options, &mut |_| {})
+        };
+
+        let summary = extract(Some("beta:Beta"), None).unwrap();
+        let ran: Vec<_> = summary.probes.iter().map(|p| p.probe.as_str()).collect();
+        assert_eq!(ran, ["beta:Beta"]);
+        assert_eq!(summary.extracted(), 2);
+        assert!(store.get_session("acme:Acme:s1").unwrap().is_none());
+
+        // One session, from whichever probe has it; only that session is re-read
+        let summary = extract(None, Some("s2")).unwrap();
+        let extracted: Vec<_> = summary
+            .probes
+            .iter()
+            .map(|p| (p.probe.as_str(), p.extracted))
+            .collect();
+        assert_eq!(extracted, [("acme:Acme", 1), ("beta:Beta", 1)]);
+        assert!(store.get_session("acme:Acme:s2").unwrap().is_some());
+        assert!(store.get_session("acme:Acme:s1").unwrap().is_none());
+
+        // Unknown and disabled probes are refused rather than skipped
+        for id in ["nope:Nope", "claude:ClaudeCode"] {
+            let error = extract(Some(id), None).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<Error>(),
+                Some(Error::ProbeUnavailable(p)) if p == id
+            ));
+        }
+    }
+}
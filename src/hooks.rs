@@ -0,0 +1,160 @@
+//! Extraction lifecycle hooks
+//!
+//! Configured shell commands run before extraction, after each stored session and
+//! after the run. Each gets a JSON payload on stdin and the event name in
+//! CHRONICLE_HOOK; failures and timeouts are reported but never stop extraction.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::HooksConfig;
+use crate::probe::SessionMetadata;
+
+/// Interval between checks on a running hook
+const POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreExtract,
+    PostSession,
+    PostRun,
+}
+
+impl HookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PreExtract => "pre_extract",
+            HookEvent::PostSession => "post_session",
+            HookEvent::PostRun => "post_run",
+        }
+    }
+
+    fn commands<'a>(&self, hooks: &'a HooksConfig) -> &'a [String] {
+        match self {
+            HookEvent::PreExtract => &hooks.pre_extract,
+            HookEvent::PostSession => &hooks.post_session,
+            HookEvent::PostRun => &hooks.post_run,
+        }
+    }
+}
+
+/// Totals of one probe in a run, for the post_run payload
+#[derive(Debug, Default, Serialize)]
+pub struct ProbeRun {
+    pub probe: String,
+    pub found: usize,
+    pub extracted: usize,
+    pub unchanged: usize,
+    /// Source entries dropped as unparseable
+    pub dropped: usize,
+    /// Sessions that couldn't be extracted
+    pub failed: usize,
+}
+
+/// Run an event's commands in order; the payload is only built when one is configured.
+/// Returns a message per failed command.
+pub fn fire(hooks: &HooksConfig, event: HookEvent, payload: impl FnOnce() -> Value) -> Vec<String> {
+    let commands = event.commands(hooks);
+    if commands.is_empty() {
+        return vec![];
+    }
+    let mut payload = payload();
+    payload["event"] = json!(event.name());
+    let input = format!("{}\n", payload);
+    let timeout = (hooks.timeout_secs > 0).then(|| Duration::from_secs(hooks.timeout_secs));
+    let mut failures = vec![];
+    for command in commands {
+        if let Err(e) = run_hook(command, event, &input, timeout) {
+            failures.push(format!("{} hook '{}' failed: {}", event.name(), command, e));
+        }
+    }
+    failures
+}
+
+/// Payload describing a stored session
+pub fn session_payload(probe_id: &str, session_id: &str, metadata: &SessionMetadata) -> Value {
+    json!({
+        "probe": probe_id,
+        "session_id": session_id,
+        "external_id": metadata.external_id,
+        "title": metadata.title,
+        "project_path": metadata.project_path,
+        "git_remote": metadata.git_remote,
+        "primary_provider": metadata.primary_provider,
+        "primary_model": metadata.primary_model,
+        "first_timestamp": metadata.first_timestamp.map(|t| t.to_rfc3339()),
+        "last_timestamp": metadata.last_timestamp.map(|t| t.to_rfc3339()),
+        "messages": metadata.messages.len(),
+        "tool_calls": metadata.messages.iter().map(|m| m.tool_uses.len()).sum::<usize>(),
+    })
+}
+
+fn run_hook(
+    command: &str,
+    event: HookEvent,
+    input: &str,
+    timeout: Option<Duration>,
+) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("CHRONICLE_HOOK", event.name())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks that ignore the payload may exit before reading it
+        let _ = stdin.write_all(input.as_bytes());
+    }
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(format!("exited with {}", status)),
+            None if timeout.is_some_and(|t| started.elapsed() >= t) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("killed after {}s", timeout.unwrap().as_secs()));
+            }
+            None => thread::sleep(POLL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_receive_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("payload.json");
+        let hooks = HooksConfig {
+            post_run: vec![
+                "exit 3".to_string(),
+                format!(
+                    "cat > '{}'; echo $CHRONICLE_HOOK >> '{}'",
+                    out.display(),
+                    out.display()
+                ),
+            ],
+            ..Default::default()
+        };
+
+        fire(&hooks, HookEvent::PreExtract, || unreachable!());
+        fire(&hooks, HookEvent::PostRun, || json!({ "extracted": 2 }));
+
+        // The failing hook doesn't stop the ones after it
+        let written = std::fs::read_to_string(&out).unwrap();
+        let (payload, event) = written.split_once('\n').unwrap();
+        let payload: Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(payload["event"], "post_run");
+        assert_eq!(payload["extracted"], 2);
+        assert_eq!(event.trim(), "post_run");
+    }
+}
@@ -1,8 +1,17 @@
+pub mod analysis;
+pub mod api;
 pub mod cli;
 pub mod config;
+pub mod content;
+pub mod error;
+pub mod export;
+pub mod extract;
+pub mod hooks;
 pub mod probe;
 pub mod store;
 
+pub use api::Chronicle;
 pub use config::Config;
+pub use error::Error;
 pub use probe::{IngestionProbe, ProbeRegistry};
 pub use store::MetadataStore;
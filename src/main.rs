@@ -1,10 +1,19 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-use chronicle::cli::{extract, list, project, read, session};
+use chronicle::cli::read::{MessageSelection, ReadOptions, SessionLookup};
+use chronicle::cli::Page;
+use chronicle::cli::{
+    alerts, backfill, changelog, configure, context, costs, db, dedupe, enrich, export, extract,
+    files, issues, list, mcp, models, permissions, project, prune, read, report, search, serve,
+    session, stats, statusline, switches, sysprompt, theme, timeline, tools, watch,
+};
 use chronicle::config::Config;
+use chronicle::export::ExporterRegistry;
 use chronicle::probe::ProbeRegistry;
-use chronicle::store::MetadataStore;
+use chronicle::store::{check_backend, open_backend, MetadataStore};
 
 #[derive(Parser)]
 #[command(name = "chronicle")]
@@ -16,12 +25,67 @@ struct Cli {
     /// Config file path
     #[arg(short, long, default_value = "chronicle.yaml")]
     config: String,
+
+    /// Machine-readable JSON output (list, read, project list, stats, sysprompt)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Explore another Chronicle database (e.g. copied from another machine) read-only,
+    /// instead of the configured one; probes are only needed for source-file content
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Extract metadata from all available probes
-    Extract,
+    Extract {
+        /// Re-extract every session, even if its source is unchanged
+        #[arg(long)]
+        full: bool,
+
+        /// Number of threads parsing sessions (default: extraction.workers)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Record every unparsed record; fail on any session that can't be extracted or
+        /// above extraction.max_failure_rate
+        #[arg(long)]
+        strict: bool,
+
+        /// Only extract sessions of this probe (e.g. claude:ClaudeCode)
+        #[arg(long)]
+        probe: Option<String>,
+
+        /// Re-extract just this session (short hash, ID, or the ID in its source)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only print warnings and errors
+        #[arg(short, long, conflicts_with = "verbose")]
+        quiet: bool,
+
+        /// Print a line for every extracted session
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Recompute one kind of derived data for indexed sessions, without a full re-extract
+    Backfill {
+        /// tools, loops, references, commits or language
+        feature: String,
+
+        /// Only sessions from this probe (e.g. claude:ClaudeCode)
+        #[arg(long)]
+        probe: Option<String>,
+    },
+
+    /// Keep the database fresh: watch probe sources and index new or modified sessions
+    Watch {
+        /// Seconds between polls of probes whose files can't be watched for changes
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+    },
 
     /// List sessions
     List {
@@ -32,12 +96,76 @@ enum Commands {
         /// Filter by probe source
         #[arg(short, long)]
         source: Option<String>,
+
+        /// Show the anomalies (e.g. runaway tool loops) of the matching sessions
+        #[arg(long, conflicts_with_all = ["costs", "sort"])]
+        anomalies: bool,
+
+        /// Show only coding sessions (code) or general chat without a repo (general)
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Show only sessions whose user messages are in this language (ISO 639-1, e.g. en, de, zh)
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// Only sessions last active since this time (7d, yesterday, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only sessions last active before this time
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only sessions active in this recent span (e.g. 24h, 7d, 2w); same as --since
+        #[arg(long, conflicts_with = "since")]
+        last: Option<String>,
+
+        /// Only sessions of this project (ID or name)
+        #[arg(long, conflicts_with = "unassigned")]
+        project: Option<String>,
+
+        /// Only sessions not assigned to a project yet
+        #[arg(long)]
+        unassigned: bool,
+
+        /// Include archived sessions
+        #[arg(long)]
+        all: bool,
+
+        /// Show the estimated cost of each session
+        #[arg(long)]
+        costs: bool,
+
+        /// Order by last activity, wall-clock duration, active time or message count
+        #[arg(long, default_value = "recent", value_parser = ["recent", "duration", "active", "messages"])]
+        sort: String,
+
+        #[command(flatten)]
+        page: PageArgs,
     },
 
     /// Read a session
     Read {
-        /// Session ID (short hash or full ID)
-        session_id: String,
+        /// Session ID (short hash, full ID, external ID or source file path)
+        #[arg(required_unless_present_any = ["path", "external_id", "project"])]
+        session_id: Option<String>,
+
+        /// Read the session stored from this source file
+        #[arg(long, conflicts_with_all = ["session_id", "external_id"])]
+        path: Option<String>,
+
+        /// Read the session with this exact source-native ID (e.g. ses_xyz)
+        #[arg(long, conflicts_with = "session_id")]
+        external_id: Option<String>,
+
+        /// Interleave the messages of all this project's sessions on one day
+        #[arg(long, requires = "date", conflicts_with_all = ["session_id", "path", "external_id"])]
+        project: Option<String>,
+
+        /// Day to merge with --project (YYYY-MM-DD, today, yesterday, ...)
+        #[arg(long, requires = "project")]
+        date: Option<String>,
 
         /// Show full content (lazy load from source)
         #[arg(long)]
@@ -46,6 +174,91 @@ enum Commands {
         /// Show tool uses
         #[arg(long)]
         tools: bool,
+
+        /// Render assistant markdown (headings, code blocks, lists) for the terminal
+        #[arg(long, requires = "full")]
+        render: bool,
+
+        /// Show only message N (1-based, as numbered in the output)
+        #[arg(long, conflicts_with_all = ["range", "project"])]
+        message: Option<usize>,
+
+        /// Show messages a..b (inclusive; a.. and ..b leave an end open)
+        #[arg(long, conflicts_with = "project")]
+        range: Option<String>,
+
+        /// Show only the last N (matching) messages
+        #[arg(long, conflicts_with = "project")]
+        tail: Option<usize>,
+
+        /// Show only messages with this role (user, assistant, system, tool)
+        #[arg(long)]
+        role: Option<String>,
+
+        /// Print straight to stdout instead of the built-in pager
+        #[arg(long)]
+        no_pager: bool,
+    },
+
+    /// Export sessions (anki: Q&A flashcard deck CSV)
+    Export {
+        /// Session to export (short hash or ID); omit to export across sessions
+        session: Option<String>,
+
+        /// Export format: markdown, json, html or decisions for a session; anki (default)
+        /// or decisions without one
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Only include questions (anki) or decisions containing this text (case-insensitive)
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Only include sessions assigned to this project (ID or name)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Summarize per-project sessions, messages, spend, tools and models for the
+    /// current day, week or month
+    Report {
+        /// Period to cover: day, week or month (calendar, up to now)
+        #[arg(long, default_value = "week", value_parser = ["day", "week", "month"])]
+        period: String,
+
+        /// Also write the report as Markdown to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// One day's sessions from all sources in chronological order, with durations
+    Timeline {
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Day to show: YYYY-MM-DD, yesterday, last monday ... (default: today)
+        #[arg(long)]
+        day: Option<String>,
+    },
+
+    /// Draft a markdown worklog of AI-assisted work and related commits
+    Changelog {
+        /// Project ID or name (enables git commit correlation)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Start of the window: 7d, 2w, yesterday, last monday, YYYY-MM-DD or RFC 3339
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
     /// Project management
@@ -60,8 +273,301 @@ enum Commands {
         command: SessionCommands,
     },
 
-    /// Show statistics
-    Stats,
+    /// MCP servers used by tool calls, with call counts and failure rates
+    Mcp {
+        /// Only count calls since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Break down one server's calls per tool
+        #[arg(long)]
+        server: Option<String>,
+    },
+
+    /// Files AI tools edited most, relative to each project's root
+    Files {
+        /// Only this project (ID or name)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Include files that were only read
+        #[arg(long)]
+        all: bool,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Sessions where the answering model changed (fallbacks to cheaper models and
+    /// manual switches), with when in the session it happened
+    Switches {
+        /// Only this session (short hash, ID or external ID)
+        session: Option<String>,
+
+        /// Only sessions active since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Tool calls per tool, project or provider, with failure rates and trends
+    Tools {
+        /// Group by tool, project or provider
+        #[arg(long, default_value = "tool")]
+        by: String,
+
+        /// Calls per day, week or month instead of per group
+        #[arg(long, conflicts_with = "by")]
+        trend: Option<String>,
+
+        /// Only count calls of this tool
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Only count calls since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Compare models: messages, tokens, cache hits, tool use and estimated cost
+    Models {
+        /// Only count messages since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Tool calls that needed approval, with approval and denial rates per tool
+    Permissions {
+        /// Only count calls since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// System prompts sent by each source, and how they changed
+    Sysprompt {
+        #[command(subcommand)]
+        command: SyspromptCommands,
+    },
+
+    /// Edit chronicle.yaml settings interactively
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Metadata database maintenance (portable export/import)
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
+    /// Show the raw source files backing a session and where each message lives in them
+    OpenSource {
+        /// Session ID (short hash, ID or external ID)
+        session: String,
+
+        /// Open the session's source file in $EDITOR
+        #[arg(long)]
+        edit: bool,
+
+        /// Reveal the source file in Finder / the file manager
+        #[arg(long)]
+        reveal: bool,
+    },
+
+    /// Find sessions that reference an issue or PR (e.g. PROJ-42, #12, owner/repo#12)
+    SessionsForIssue {
+        /// Issue key, number or URL
+        issue: String,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Continue a session in the tool it came from (e.g. `claude --resume <id>`)
+    Resume {
+        /// Session ID (short hash, ID or external ID)
+        session: String,
+
+        /// Print the command instead of running it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Find sessions by title, summary, project or working directory
+    Search {
+        /// Words to look for (each matches as a prefix)
+        query: String,
+
+        /// Result order: relevance (best match, favouring recent sessions and the
+        /// current project) or recent (last active first)
+        #[arg(long, default_value = "relevance")]
+        rank: String,
+
+        /// Project to favour in relevance ranking (default: the current directory's)
+        #[arg(long)]
+        project: Option<String>,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Check usage alerts and show recent ones
+    Alerts,
+
+    /// Show per-probe index statistics and skipped source entries
+    Stats {
+        /// Only today's sessions, tokens and cost, from cached rollups (fast enough
+        /// for a shell prompt)
+        #[arg(long)]
+        today: bool,
+
+        /// How much output and estimated spend went to reasoning/thinking, per source
+        /// and model
+        #[arg(long, conflicts_with = "today")]
+        thinking: bool,
+
+        /// Output format: text or json (same as --json)
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        output: String,
+    },
+
+    /// One compact line (watch status, today's sessions, tokens and cost) for tmux,
+    /// zellij or starship status bars; cached so renders rarely touch the database
+    Statusline {
+        /// Line template; placeholders: {watch} {sessions} {messages} {tokens} {cost}
+        #[arg(long, default_value = statusline::DEFAULT_FORMAT)]
+        format: String,
+
+        /// Seconds to reuse the cached totals
+        #[arg(long, default_value_t = 30)]
+        ttl: u64,
+    },
+
+    /// Serve a read-only JSON API (sessions, projects, search, stats)
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 7878)]
+        port: u16,
+    },
+
+    /// Generate session summaries or topics with an LLM through a rate-limited queue
+    Enrich {
+        /// What to generate: summary or topics
+        #[arg(long, default_value = "summary")]
+        kind: String,
+
+        /// Jobs to work in this run
+        #[arg(long, default_value_t = 10)]
+        budget: usize,
+
+        /// Show queue counts instead of working jobs
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Detect the same conversation indexed from several sources
+    Dedupe {
+        /// Resolve unresolved pairs: merge (keep the longer session) or keep-both
+        #[arg(long)]
+        resolve: Option<String>,
+
+        /// Duplicate pair IDs to resolve (default: all unresolved)
+        #[arg(requires = "resolve")]
+        ids: Vec<i64>,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Mark or delete sessions whose source file no longer exists
+    Prune {
+        /// Only sessions from this probe (e.g. claude:ClaudeCode)
+        #[arg(long)]
+        probe: Option<String>,
+
+        /// Delete the sessions instead of marking them orphaned
+        #[arg(long)]
+        delete: bool,
+
+        /// With --delete, keep sessions whose content is cached (they stay readable)
+        #[arg(long, requires = "delete")]
+        keep_cached: bool,
+
+        /// Show what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Estimate token costs from the configured price table
+    Costs {
+        /// Group by model, session, project, probe, day, week or month
+        #[arg(long, default_value = "model")]
+        by: String,
+
+        /// Only count usage since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Break down a single session's cost per message
+        #[arg(long, conflicts_with_all = ["by", "since"])]
+        session: Option<String>,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+
+    /// Sessions that ran close to their model's context window or got compacted
+    Context {
+        /// Only sessions active since this time (7d, 2w, last monday, YYYY-MM-DD ...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Show every session with token usage, not just the pressured ones
+        #[arg(long)]
+        all: bool,
+
+        /// Report how often sessions get compacted per project or model instead
+        #[arg(long, conflicts_with = "all")]
+        by: Option<String>,
+
+        /// Messages from which a session counts as long in --by reports
+        #[arg(long, default_value_t = 100, requires = "by")]
+        min_messages: i64,
+
+        #[command(flatten)]
+        page: PageArgs,
+    },
+}
+
+/// Row window for commands that can print long tables
+#[derive(Args)]
+struct PageArgs {
+    /// Rows per page (0 = all)
+    #[arg(long, default_value_t = Page::DEFAULT_LIMIT)]
+    limit: usize,
+
+    /// Page to show, starting at 1
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    page: u64,
+}
+
+impl From<PageArgs> for Page {
+    fn from(args: PageArgs) -> Self {
+        Page {
+            limit: args.limit,
+            page: args.page as usize,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -76,9 +582,21 @@ enum ProjectCommands {
         /// Primary directory path
         #[arg(short, long)]
         path: Option<String>,
+        /// Who its history may be shown to: private, internal or shareable
+        #[arg(long, default_value = "shareable")]
+        visibility: String,
     },
     /// List all projects
-    List,
+    List {
+        /// Rebuild cached session/message counters from the sessions table first
+        #[arg(long)]
+        recount: bool,
+    },
+    /// Show project details, paths and indexed tool settings
+    Show {
+        /// Project ID or Name
+        project: String,
+    },
     /// Add an additional path to a project
     AddPath {
         /// Project ID or Name
@@ -93,6 +611,72 @@ enum ProjectCommands {
         /// Git remote URL
         remote: String,
     },
+    /// Link sessions without a project whose path or git remote now matches one
+    /// (done automatically by create, add-path and add-git)
+    Relink,
+    /// Rename a project
+    Rename {
+        /// Project ID or Name
+        project: String,
+        /// New name
+        name: String,
+    },
+    /// Change a project's type, primary path or metadata
+    Set {
+        /// Project ID or Name
+        project: String,
+        /// Project type (code, research, general)
+        #[arg(long = "type")]
+        project_type: Option<String>,
+        /// New primary directory path (the old one stays registered)
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Metadata key to set, as KEY=VALUE (VALUE may be JSON; empty removes the key)
+        #[arg(short, long, value_name = "KEY=VALUE")]
+        metadata: Vec<String>,
+    },
+    /// Delete a project; its sessions become unassigned unless --reassign is given
+    Delete {
+        /// Project ID or Name
+        project: String,
+        /// Move its sessions to this project instead (ID or Name)
+        #[arg(long)]
+        reassign: Option<String>,
+    },
+    /// Merge one project into another: sessions, paths and identifiers move to the
+    /// second, which is kept
+    Merge {
+        /// Project to merge away (ID or Name)
+        source: String,
+        /// Project to keep (ID or Name)
+        target: String,
+    },
+    /// Show or set who a project's history may be shown to. private: never leaves this
+    /// machine (redacted from `db export`, hidden from `serve`); internal: team channels
+    /// only (`db export`, `serve`); shareable: also `export` and `changelog`
+    Visibility {
+        /// Project ID or Name
+        project: String,
+        /// New level: private, internal or shareable
+        level: Option<String>,
+    },
+    /// Save the project's current aggregates and open questions for later comparison
+    Snapshot {
+        /// Project ID or Name
+        project: String,
+        /// Label to refer to the snapshot by (e.g. sprint-12)
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// Compare two snapshots of a project (default: latest snapshot vs now)
+    DiffSnapshots {
+        /// Project ID or Name
+        project: String,
+        /// Earlier snapshot (ID or label; default: the latest)
+        from: Option<String>,
+        /// Later snapshot (ID, label or "now"; default: now)
+        to: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -104,49 +688,320 @@ enum SessionCommands {
         /// Project ID or Name
         project: String,
     },
+    /// Assign every session matching the filters to a project
+    AssignBulk {
+        /// Working directory, matching it and everything below it; a glob (`*`, `?`,
+        /// `[...]`) is matched against the whole path instead
+        #[arg(long)]
+        path_prefix: Option<String>,
+        /// Project ID or Name
+        #[arg(long)]
+        project: String,
+        /// Only sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only sessions last active before this time (e.g. 2024-06-01, 30d)
+        #[arg(long)]
+        before: Option<String>,
+        /// Only sessions last active at or after this time
+        #[arg(long)]
+        after: Option<String>,
+        /// Show how many sessions match without assigning them
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Mark a session as explicitly unassigned
     Unassign {
         /// Session ID (short hash)
         session: String,
     },
+    /// Hide sessions from `list` (still searchable, readable and counted in stats)
+    Archive {
+        /// Session IDs (short hash)
+        sessions: Vec<String>,
+        /// Also archive every session with at most this many messages
+        #[arg(long)]
+        max_messages: Option<i64>,
+    },
+    /// Show archived sessions in `list` again
+    Unarchive {
+        /// Session IDs (short hash)
+        #[arg(required = true)]
+        sessions: Vec<String>,
+    },
+    /// Split off the tail of a session into a derived session (sources untouched)
+    Split {
+        /// Session ID (short hash)
+        session: String,
+        /// 1-based message number where the derived session starts
+        #[arg(long)]
+        at: usize,
+    },
 }
 
-fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum SyspromptCommands {
+    /// Distinct system prompts seen per source
+    List {
+        /// Only this source (e.g. OpenCode)
+        #[arg(long)]
+        source: Option<String>,
+    },
+    /// Line diff between the system prompts of two sessions
+    Diff {
+        /// First session (or system prompt hash prefix)
+        a: String,
+        /// Second session (or system prompt hash prefix)
+        b: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Toggle probes, set their data paths and test them, then write back
+    Probes,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Write the index as a portable archive (JSONL, paths relative to home)
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Merge an archive from `db export` into this database
+    Import {
+        /// Archive file
+        path: PathBuf,
+        /// For session IDs that already exist: skip, replace or keep-both
+        #[arg(long, default_value = "skip")]
+        on_conflict: String,
+    },
+    /// Rebuild the search index and compact the database file
+    Vacuum,
+    /// Run integrity checks and show the space used per table
+    Check,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(chronicle::error::exit_code(&e))
+        }
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
 
     // Load config
     let config = Config::load(&cli.config).unwrap_or_default();
+    theme::init(&config.display.theme)?;
 
-    // Initialize store
-    let store = MetadataStore::open(&config.database_path())?;
+    if cli.db.is_some()
+        && matches!(
+            cli.command,
+            Commands::Extract { .. }
+                | Commands::Watch { .. }
+                | Commands::Statusline { .. }
+                | Commands::Prune { .. }
+                | Commands::Db {
+                    command: DbCommands::Import { .. } | DbCommands::Vacuum | DbCommands::Check
+                }
+        )
+    {
+        anyhow::bail!("--db opens a database read-only; this command works on the configured one");
+    }
+
+    // Status bars render often; answer from the cache before opening the database
+    if let Commands::Statusline { format, ttl } = &cli.command {
+        return statusline::run(&config, format, *ttl);
+    }
 
     // Initialize probe registry
     let registry = ProbeRegistry::new(&config);
+    for warning in registry.warnings() {
+        eprintln!("⚠️  {}", warning);
+    }
+
+    // Ingestion goes through the configured storage backend, which may not be SQLite
+    match cli.command {
+        Commands::Extract {
+            full,
+            jobs,
+            strict,
+            probe,
+            session,
+            quiet,
+            verbose,
+        } => {
+            let backend = open_backend(&config)?;
+            let mut options = extract::ExtractOptions {
+                full,
+                jobs,
+                strict,
+                probe,
+                session: None,
+            };
+            if let Some(ref query) = session {
+                extract::select_session(backend.as_ref(), query, &mut options)?;
+            }
+            let verbosity = match (quiet, verbose) {
+                (true, _) => extract::Verbosity::Quiet,
+                (_, true) => extract::Verbosity::Verbose,
+                _ => extract::Verbosity::Normal,
+            };
+            return extract::run(backend.as_ref(), &registry, &config, &options, verbosity);
+        }
+        Commands::Watch { interval } => {
+            let backend = open_backend(&config)?;
+            return watch::run(backend.as_ref(), &registry, &config, interval);
+        }
+        _ => {}
+    }
+
+    // Initialize store
+    let store = match &cli.db {
+        Some(path) => MetadataStore::open_read_only(path)?,
+        None => {
+            check_backend(&config.database)?;
+            MetadataStore::open_with(&config.database_path(), &config.database)?
+        }
+    }
+    .with_path_normalization(config.linking.normalize_paths);
 
     match cli.command {
-        Commands::Extract => {
-            extract::run(&store, &registry)?;
+        Commands::Extract { .. } | Commands::Watch { .. } => {
+            unreachable!("handled before the store is opened")
+        }
+        Commands::Backfill { feature, probe } => {
+            backfill::run(&store, &registry, &config, &feature, probe.as_deref())?;
         }
-        Commands::List { provider, source } => {
-            list::run(&store, provider, source)?;
+        Commands::List {
+            provider,
+            source,
+            anomalies,
+            kind,
+            lang,
+            since,
+            until,
+            last,
+            project,
+            unassigned,
+            all,
+            costs,
+            sort,
+            page,
+        } => {
+            let filter = list::ListFilter {
+                provider,
+                source,
+                kind,
+                lang,
+                since: since.or(last),
+                until,
+                project,
+                unassigned,
+                all,
+                sort: sort.parse()?,
+            };
+            if anomalies {
+                list::anomalies(&store, &filter, cli.json, page.into())?;
+            } else {
+                let costs = costs.then(|| config.pricing());
+                list::run(&store, filter, costs.as_ref(), cli.json, page.into())?;
+            }
         }
         Commands::Read {
             session_id,
+            path,
+            external_id,
+            project,
+            date,
             full,
             tools,
+            render,
+            message,
+            range,
+            tail,
+            role,
+            no_pager,
         } => {
-            read::run(&store, &registry, &session_id, full, tools)?;
+            let range = match (message, range) {
+                (Some(0), _) => anyhow::bail!("Message numbers start at 1"),
+                (Some(n), _) => Some((n, n)),
+                (None, Some(range)) => Some(MessageSelection::parse_range(&range)?),
+                (None, None) => None,
+            };
+            let options = ReadOptions {
+                full,
+                tools,
+                render,
+                no_pager,
+                json: cli.json,
+                selection: MessageSelection { range, tail, role },
+            };
+            match (project, date) {
+                (Some(project), Some(date)) => {
+                    read::merged(&store, &registry, &project, &date, &options)?
+                }
+                _ => {
+                    let lookup = match (session_id, path, external_id) {
+                        (_, Some(path), _) => SessionLookup::Path(path),
+                        (_, _, Some(id)) => SessionLookup::ExternalId(id),
+                        (query, _, _) => SessionLookup::Query(query.unwrap_or_default()),
+                    };
+                    read::run(&store, &registry, &lookup, &options)?;
+                }
+            }
+        }
+        Commands::Export {
+            session,
+            format,
+            filter,
+            project,
+            output,
+        } => match session {
+            Some(session) => {
+                let format = format.unwrap_or_else(|| "markdown".to_string());
+                let exporters = ExporterRegistry::new();
+                export::session(&store, &registry, &exporters, &session, &format, output)?;
+            }
+            None => {
+                let format = format.unwrap_or_else(|| "anki".to_string());
+                export::run(&store, &registry, &config, &format, filter, project, output)?;
+            }
+        },
+        Commands::Report { period, output } => {
+            report::run(&store, &config, &period, output, cli.json)?;
+        }
+        Commands::Timeline { project, day } => {
+            timeline::run(&store, project, day, cli.json)?;
+        }
+        Commands::Changelog {
+            project,
+            since,
+            output,
+        } => {
+            changelog::run(&store, &config, project, &since, output)?;
         }
         Commands::Project { command } => match command {
             ProjectCommands::Create {
                 name,
                 project_type,
                 path,
+                visibility,
             } => {
-                project::create(&store, name, project_type, path)?;
+                project::create(&store, name, project_type, path, &visibility)?;
+            }
+            ProjectCommands::List { recount } => {
+                project::list(&store, &config, recount, cli.json)?;
             }
-            ProjectCommands::List => {
-                project::list(&store)?;
+            ProjectCommands::Show { project } => {
+                project::show(&store, &config, project)?;
             }
             ProjectCommands::AddPath { project, path } => {
                 project::add_path(&store, project, path)?;
@@ -154,18 +1009,213 @@ fn main() -> Result<()> {
             ProjectCommands::AddGit { project, remote } => {
                 project::add_git(&store, project, remote)?;
             }
+            ProjectCommands::Relink => {
+                project::relink(&store)?;
+            }
+            ProjectCommands::Rename { project, name } => {
+                project::rename(&store, project, name)?;
+            }
+            ProjectCommands::Set {
+                project,
+                project_type,
+                path,
+                metadata,
+            } => {
+                project::set(&store, project, project_type, path, metadata)?;
+            }
+            ProjectCommands::Delete { project, reassign } => {
+                project::delete(&store, project, reassign)?;
+            }
+            ProjectCommands::Merge { source, target } => {
+                project::merge(&store, source, target)?;
+            }
+            ProjectCommands::Visibility { project, level } => {
+                project::visibility(&store, project, level)?;
+            }
+            ProjectCommands::Snapshot { project, label } => {
+                project::snapshot(&store, &registry, project, label)?;
+            }
+            ProjectCommands::DiffSnapshots { project, from, to } => {
+                project::diff_snapshots(&store, &registry, project, from, to)?;
+            }
         },
         Commands::Session { command } => match command {
             SessionCommands::Assign { session, project } => {
                 session::assign(&store, session, project)?;
             }
+            SessionCommands::AssignBulk {
+                path_prefix,
+                project,
+                provider,
+                before,
+                after,
+                dry_run,
+            } => {
+                let filter = session::BulkFilter {
+                    path_prefix,
+                    provider,
+                    before,
+                    after,
+                };
+                session::assign_bulk(&store, filter, project, dry_run)?;
+            }
             SessionCommands::Unassign { session } => {
                 session::unassign(&store, session)?;
             }
+            SessionCommands::Archive {
+                sessions,
+                max_messages,
+            } => {
+                session::archive(&store, sessions, max_messages)?;
+            }
+            SessionCommands::Unarchive { sessions } => {
+                session::unarchive(&store, sessions)?;
+            }
+            SessionCommands::Split { session, at } => {
+                session::split(&store, session, at)?;
+            }
         },
-        Commands::Stats => {
-            println!("Stats not yet implemented");
+        Commands::Mcp { since, server } => {
+            mcp::run(&store, since, server, cli.json)?;
+        }
+        Commands::Files { project, all, page } => {
+            files::run(&store, project, all, page.into(), cli.json)?;
         }
+        Commands::Switches {
+            session,
+            since,
+            page,
+        } => {
+            let pricing = config.pricing();
+            switches::run(&store, &pricing, session, since, page.into(), cli.json)?;
+        }
+        Commands::Tools {
+            by,
+            trend,
+            tool,
+            since,
+            page,
+        } => {
+            tools::run(&store, &by, trend, tool, since, page.into(), cli.json)?;
+        }
+        Commands::Models { since } => {
+            models::run(&store, &config, since, cli.json)?;
+        }
+        Commands::Permissions { since } => {
+            permissions::run(&store, since, cli.json)?;
+        }
+        Commands::Sysprompt { command } => match command {
+            SyspromptCommands::List { source } => {
+                sysprompt::list(&store, source, cli.json)?;
+            }
+            SyspromptCommands::Diff { a, b } => {
+                sysprompt::diff(&store, &a, &b, cli.json)?;
+            }
+        },
+        Commands::Config { command } => match command {
+            ConfigCommands::Probes => configure::probes(&cli.config)?,
+        },
+        Commands::Db { command } => match command {
+            DbCommands::Export { output } => {
+                db::export(&store, output)?;
+            }
+            DbCommands::Import { path, on_conflict } => {
+                db::import(&store, &path, &on_conflict, cli.json)?;
+            }
+            DbCommands::Vacuum => db::vacuum(&store, cli.json)?,
+            DbCommands::Check => db::check(&store, cli.json)?,
+        },
+        Commands::Resume { session, print } => {
+            session::resume(&store, &config, session, print)?;
+        }
+        Commands::OpenSource {
+            session: query,
+            edit,
+            reveal,
+        } => {
+            session::open_source(&store, query, edit, reveal)?;
+        }
+        Commands::SessionsForIssue { issue, page } => {
+            issues::sessions_for_issue(&store, &issue, page.into())?;
+        }
+        Commands::Alerts => {
+            alerts::run(&store, &config)?;
+        }
+        Commands::Statusline { .. } => unreachable!("handled before the store is opened"),
+        Commands::Stats {
+            today,
+            thinking,
+            output,
+        } => {
+            let json = cli.json || output == "json";
+            match (today, thinking) {
+                (true, _) => stats::today(&store, &config, json)?,
+                (_, true) => stats::thinking(&store, &config, json)?,
+                _ => stats::run(&store, &config, json)?,
+            }
+        }
+        Commands::Search {
+            query,
+            rank,
+            project,
+            page,
+        } => {
+            search::run(
+                &store,
+                &query,
+                &rank,
+                project.as_deref(),
+                cli.json,
+                page.into(),
+            )?;
+        }
+        Commands::Serve { host, port } => {
+            serve::run(&store, &registry, &config, &host, port)?;
+        }
+        Commands::Enrich {
+            kind,
+            budget,
+            status,
+        } => match status {
+            true => enrich::status(&store)?,
+            false => enrich::run(&store, &registry, &config, &kind, budget)?,
+        },
+        Commands::Dedupe { resolve, ids, page } => match resolve {
+            Some(resolution) => dedupe::resolve(&store, &resolution, &ids)?,
+            None => dedupe::run(&store, &registry, &config, page.into())?,
+        },
+        Commands::Prune {
+            probe,
+            delete,
+            keep_cached,
+            dry_run,
+        } => {
+            let options = prune::PruneOptions {
+                delete,
+                keep_cached,
+                dry_run,
+            };
+            prune::run(&store, probe.as_deref(), options)?;
+        }
+        Commands::Costs {
+            by,
+            since,
+            session,
+            page,
+        } => match session {
+            Some(query) => costs::session(&store, &config, &query)?,
+            None => costs::run(&store, &config, &by, since, page.into())?,
+        },
+        Commands::Context {
+            since,
+            all,
+            by,
+            min_messages,
+            page,
+        } => match by {
+            Some(by) => context::by_group(&store, since, &by, min_messages, cli.json)?,
+            None => context::run(&store, &config, since, all, page.into(), cli.json)?,
+        },
     }
 
     Ok(())
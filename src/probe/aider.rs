@@ -0,0 +1,607 @@
+//! Aider probe implementation
+//!
+//! Extracts conversation history from Aider's per-repository markdown transcripts.
+//! Data format: `.aider.chat.history.md` in project directories (searched under base_path)
+//!   - `# aider chat started at YYYY-MM-DD HH:MM:SS` starts a new session
+//!   - `#### ` lines are user input
+//!   - `> ` lines are Aider output (model banner, token counts, applied edits, commits)
+//!   - everything else is assistant output
+//!
+//! One file holds many sessions; each is identified by a hash of the file path and the
+//! byte offset of its header, which stays stable because Aider only appends.
+//!
+//! Aider is a multi-provider source (model names are LiteLLM-style, e.g. `anthropic/...`).
+//!
+//! Transcripts live inside the projects themselves, so there is no default location: the
+//! probe stays unavailable until `base_path` names the directory holding them.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{loops, references};
+
+use super::{
+    infer_provider, read_source, CommitRef, ContentRef, IngestionProbe, MessageMetadata,
+    ProbeCapabilities, SessionMetadata, SessionRef, SkipCounts, SourceType, TokenUsage,
+    ToolUseMetadata,
+};
+
+const HISTORY_FILE: &str = ".aider.chat.history.md";
+const SESSION_HEADER: &str = "# aider chat started at ";
+
+/// Directories never worth descending into when looking for transcripts
+const SKIP_DIRS: &[&str] = &["node_modules", "target", "venv", ".venv", "__pycache__"];
+
+pub struct AiderProbe {
+    base_path: PathBuf,
+    max_depth: usize,
+}
+
+/// One `# aider chat started at` section of a history file
+#[derive(Debug, Default)]
+struct ChatSession {
+    byte_offset: u64,
+    started_at: Option<DateTime<Utc>>,
+    messages: Vec<ChatMessage>,
+    commits: Vec<CommitRef>,
+}
+
+#[derive(Debug)]
+struct ChatMessage {
+    role: &'static str,
+    byte_offset: u64,
+    line_number: u32,
+    text: String,
+    model: Option<String>,
+    edited_files: Vec<String>,
+    token_usage: Option<TokenUsage>,
+}
+
+impl AiderProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        Self {
+            base_path: custom_path.unwrap_or_default(),
+            max_depth: 4,
+        }
+    }
+
+    /// Find history files under the base path (hidden directories are skipped)
+    fn history_files(&self) -> Vec<PathBuf> {
+        WalkDir::new(&self.base_path)
+            .max_depth(self.max_depth)
+            .into_iter()
+            .filter_entry(|e| {
+                let name = e.file_name().to_string_lossy();
+                e.depth() == 0
+                    || !e.file_type().is_dir()
+                    || !(name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()))
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.file_name() == HISTORY_FILE)
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// Stable session ID from the history file and the session header offset
+    fn session_id(path: &Path, byte_offset: u64) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(b":");
+        hasher.update(byte_offset.to_string().as_bytes());
+        hex::encode(&hasher.finalize()[..8])
+    }
+
+    fn read_sessions(path: &Path) -> Result<Vec<ChatSession>> {
+        Ok(parse_history(&read_source(path)?))
+    }
+
+    /// Extract git remote from project directory if available
+    fn extract_git_remote(project_path: &Path) -> Option<String> {
+        let git_config = project_path.join(".git/config");
+        if git_config.exists() {
+            if let Ok(content) = std::fs::read_to_string(&git_config) {
+                // Simple parsing: find [remote "origin"] section and url line
+                let mut in_origin = false;
+                for line in content.lines() {
+                    if line.contains("[remote \"origin\"]") {
+                        in_origin = true;
+                    } else if in_origin && line.trim().starts_with("url = ") {
+                        return Some(line.trim().strip_prefix("url = ")?.to_string());
+                    } else if line.starts_with('[') {
+                        in_origin = false;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Split a history file into sessions and messages
+fn parse_history(content: &str) -> Vec<ChatSession> {
+    let mut parser = HistoryParser::new(0);
+    for line in content.split_inclusive('\n') {
+        parser.push(line);
+    }
+    parser.finish()
+}
+
+/// Text of the message at `offset`: only that message is parsed, from a slice of the file
+fn message_at(content: &str, offset: u64) -> Option<String> {
+    let start = usize::try_from(offset).ok()?;
+    if start > 0 && content.as_bytes().get(start - 1) != Some(&b'\n') {
+        return None;
+    }
+    let rest = content.get(start..)?;
+    let mut parser = HistoryParser::new(offset);
+    // The slice starts inside a session
+    parser.sessions.push(ChatSession::default());
+    for line in rest.split_inclusive('\n') {
+        parser.push(line);
+        if parser.sessions.len() > 1 || parser.sessions[0].messages.len() > 1 {
+            break;
+        }
+    }
+    let message = parser
+        .finish()
+        .into_iter()
+        .next()?
+        .messages
+        .into_iter()
+        .next()?;
+    (message.byte_offset == offset).then_some(message.text)
+}
+
+/// Line-by-line state of history parsing
+struct HistoryParser {
+    sessions: Vec<ChatSession>,
+    model: Option<String>,
+    /// Role of the block the previous line belonged to ("output" for `>` lines)
+    block: &'static str,
+    offset: u64,
+    line_idx: u32,
+}
+
+impl HistoryParser {
+    fn new(offset: u64) -> Self {
+        Self {
+            sessions: vec![],
+            model: None,
+            block: "header",
+            offset,
+            line_idx: 0,
+        }
+    }
+
+    fn push(&mut self, raw_line: &str) {
+        let line_offset = self.offset;
+        let line_idx = self.line_idx;
+        self.offset += raw_line.len() as u64;
+        self.line_idx += 1;
+        let line = raw_line.trim_end_matches(['\n', '\r']);
+
+        if let Some(started) = line.strip_prefix(SESSION_HEADER) {
+            self.sessions.push(ChatSession {
+                byte_offset: line_offset,
+                started_at: parse_local_time(started.trim()),
+                ..Default::default()
+            });
+            self.model = None;
+            self.block = "header";
+            return;
+        }
+        let Some(session) = self.sessions.last_mut() else {
+            return;
+        };
+
+        if line == "####" || line.starts_with("#### ") {
+            let text = line.strip_prefix("####").unwrap_or("").trim_start();
+            match session.messages.last_mut() {
+                Some(msg) if self.block == "user" => {
+                    msg.text.push('\n');
+                    msg.text.push_str(text);
+                }
+                _ => session.messages.push(ChatMessage::new(
+                    "user",
+                    line_offset,
+                    line_idx,
+                    text,
+                    None,
+                )),
+            }
+            self.block = "user";
+        } else if line == ">" || line.starts_with("> ") {
+            let output = line.strip_prefix('>').unwrap_or("").trim();
+            parse_output_line(output, session, &mut self.model);
+            self.block = "output";
+        } else if self.block == "assistant" {
+            if let Some(msg) = session.messages.last_mut() {
+                msg.text.push('\n');
+                msg.text.push_str(line);
+            }
+        } else if !line.trim().is_empty() {
+            session.messages.push(ChatMessage::new(
+                "assistant",
+                line_offset,
+                line_idx,
+                line,
+                self.model.clone(),
+            ));
+            self.block = "assistant";
+        }
+    }
+
+    fn finish(mut self) -> Vec<ChatSession> {
+        for session in &mut self.sessions {
+            for msg in &mut session.messages {
+                msg.text = msg.text.trim_end().to_string();
+            }
+        }
+        self.sessions
+    }
+}
+
+/// Interpret one line of Aider's `>` output
+fn parse_output_line(output: &str, session: &mut ChatSession, model: &mut Option<String>) {
+    // `Model: X with diff edit format`, `Models: X with ..., weak model Y`, `Main model: X ...`
+    for prefix in ["Main model: ", "Models: ", "Model: "] {
+        if let Some(rest) = output.strip_prefix(prefix) {
+            let name = rest.split([' ', ',']).next().unwrap_or("").trim();
+            if !name.is_empty() {
+                *model = Some(name.to_string());
+            }
+            return;
+        }
+    }
+
+    if let Some(rest) = output.strip_prefix("Commit ") {
+        let mut parts = rest.splitn(2, ' ');
+        let hash = parts.next().unwrap_or("");
+        if hash.len() >= 7 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            session.commits.push(CommitRef {
+                hash: hash.to_string(),
+                message: parts.next().map(|m| m.trim().to_string()),
+            });
+        }
+        return;
+    }
+
+    let Some(last) = session
+        .messages
+        .iter_mut()
+        .rev()
+        .find(|m| m.role == "assistant")
+    else {
+        return;
+    };
+
+    if let Some(file) = output.strip_prefix("Applied edit to ") {
+        last.edited_files.push(file.trim().to_string());
+    } else if let Some(rest) = output.strip_prefix("Tokens: ") {
+        // `Tokens: 2.1k sent, 300 received. Cost: ...`
+        let mut counts = rest.split(',').map(|part| {
+            part.split_whitespace()
+                .next()
+                .and_then(parse_token_count)
+                .unwrap_or(0)
+        });
+        let input = counts.next().unwrap_or(0);
+        let output_tokens = counts.next().unwrap_or(0);
+        if input > 0 || output_tokens > 0 {
+            last.token_usage = Some(TokenUsage {
+                input_tokens: Some(input),
+                output_tokens: Some(output_tokens),
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            });
+        }
+    }
+}
+
+/// Parse token counts such as `300`, `1,234`, `2.1k` or `1.5M`
+fn parse_token_count(s: &str) -> Option<i64> {
+    let s = s.replace(',', "");
+    let (number, scale) = match s.chars().last()? {
+        'k' | 'K' => (&s[..s.len() - 1], 1_000.0),
+        'm' | 'M' => (&s[..s.len() - 1], 1_000_000.0),
+        _ => (s.as_str(), 1.0),
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|n| (n * scale).round() as i64)
+}
+
+/// Session headers are written in local time without an offset
+fn parse_local_time(s: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+impl ChatMessage {
+    fn new(
+        role: &'static str,
+        byte_offset: u64,
+        line_number: u32,
+        text: &str,
+        model: Option<String>,
+    ) -> Self {
+        Self {
+            role,
+            byte_offset,
+            line_number,
+            text: text.to_string(),
+            model,
+            edited_files: vec![],
+            token_usage: None,
+        }
+    }
+}
+
+impl IngestionProbe for AiderProbe {
+    fn id(&self) -> &str {
+        "aider:Aider"
+    }
+
+    fn provider(&self) -> &str {
+        "aider"
+    }
+
+    fn source(&self) -> &str {
+        "Aider"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "Aider chat histories (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        // Never walk the working directory (or home) by default
+        !self.base_path.as_os_str().is_empty() && self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Aider edits are applied inline; there are no tool results to track
+        ProbeCapabilities {
+            supports_token_usage: true,
+            supports_tool_results: false,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+        if !self.is_available() {
+            return Ok(sessions);
+        }
+
+        for path in self.history_files() {
+            let parsed = match Self::read_sessions(&path) {
+                Ok(parsed) => parsed,
+                Err(_) => continue,
+            };
+            for session in parsed.iter().filter(|s| !s.messages.is_empty()) {
+                sessions.push(SessionRef {
+                    id: Self::session_id(&path, session.byte_offset),
+                    source_path: path.clone(),
+                });
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let parsed = Self::read_sessions(&session.source_path)?;
+        let count = parsed.len();
+        let (index, chat) = parsed
+            .into_iter()
+            .enumerate()
+            .find(|(_, s)| Self::session_id(&session.source_path, s.byte_offset) == session.id)
+            .context("Aider session not found in history file")?;
+
+        let project_dir = session.source_path.parent().map(Path::to_path_buf);
+        let project_path = project_dir
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string());
+        let git_remote = project_dir.as_deref().and_then(Self::extract_git_remote);
+
+        // Only the newest session can still be growing; the file mtime bounds it
+        let last_timestamp = if index + 1 == count {
+            std::fs::metadata(&session.source_path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from)
+                .or(chat.started_at)
+        } else {
+            chat.started_at
+        };
+
+        let title = chat
+            .messages
+            .iter()
+            .find(|m| m.role == "user")
+            .and_then(|m| m.text.lines().find(|l| !l.trim().is_empty()))
+            .map(|l| l.trim().to_string());
+
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut messages = vec![];
+        for (idx, msg) in chat.messages.iter().enumerate() {
+            session_refs.extend(references::detect(&msg.text));
+            if msg.role == "user" {
+                language.add(&msg.text);
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .edited_files
+                .iter()
+                .map(|file| ToolUseMetadata {
+                    tool_id: None,
+                    tool_name: "apply_edit".to_string(),
+                    has_result: true,
+                    is_error: false,
+                    permission: None,
+                    input_hash: Some(loops::fingerprint(&serde_json::json!({ "path": file }))),
+                    file_path: Some(file.clone()),
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: msg.role.to_string(),
+                provider_id: msg.model.as_deref().and_then(infer_provider),
+                model: msg.model.clone(),
+                timestamp: if idx == 0 { chat.started_at } else { None },
+                content_ref: ContentRef::jsonl(
+                    session.source_path.clone(),
+                    msg.byte_offset,
+                    msg.line_number,
+                ),
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: false,
+                tool_uses,
+                token_usage: msg.token_usage.clone(),
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let primary_model = chat.messages.iter().rev().find_map(|m| m.model.clone());
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title,
+            project_path,
+            git_remote,
+            source_group: None,
+            primary_provider: primary_model.as_deref().and_then(infer_provider),
+            primary_model,
+            first_timestamp: chat.started_at,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: chat.commits,
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let offset = reference
+            .byte_offset
+            .context("Missing byte offset for Aider message")?;
+
+        message_at(&read_source(&reference.source_path)?, offset).context("Aider message not found")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HISTORY: &str = "\
+# aider chat started at 2024-06-10 14:22:31
+
+> Aider v0.50.0
+> Main model: anthropic/claude-3-5-sonnet-20240620 with diff edit format
+> Git repo: .git with 42 files
+
+#### add a hello function
+####
+#### keep it short
+
+Here is the change:
+
+```python
+def hello(): pass
+```
+
+> Tokens: 2.1k sent, 150 received. Cost: $0.01 message, $0.01 session.
+> Applied edit to hello.py
+> Commit 3a4b5c6 feat: Add hello function
+
+# aider chat started at 2024-06-11 09:00:00
+
+#### thanks
+";
+
+    #[test]
+    fn test_unavailable_without_base_path() {
+        let probe = AiderProbe::new(None);
+        assert!(!probe.is_available());
+        assert!(probe.discover().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let sessions = parse_history(HISTORY);
+        assert_eq!(sessions.len(), 2);
+
+        let first = &sessions[0];
+        assert_eq!(first.messages.len(), 2);
+        assert_eq!(
+            first.messages[0].text,
+            "add a hello function\n\nkeep it short"
+        );
+        let reply = &first.messages[1];
+        assert_eq!(reply.role, "assistant");
+        assert!(reply.text.starts_with("Here is the change:"));
+        assert!(reply.text.ends_with("```"));
+        assert_eq!(
+            reply.model.as_deref(),
+            Some("anthropic/claude-3-5-sonnet-20240620")
+        );
+        assert_eq!(reply.edited_files, vec!["hello.py"]);
+        assert_eq!(reply.token_usage.as_ref().unwrap().input_tokens, Some(2100));
+        assert_eq!(first.commits[0].hash, "3a4b5c6");
+
+        assert_eq!(sessions[1].messages[0].text, "thanks");
+    }
+
+    #[test]
+    fn test_message_at_matches_full_parse() {
+        for msg in parse_history(HISTORY).iter().flat_map(|s| &s.messages) {
+            assert_eq!(
+                message_at(HISTORY, msg.byte_offset).as_deref(),
+                Some(msg.text.as_str())
+            );
+        }
+        // Offsets must point at the start of a line
+        assert_eq!(message_at(HISTORY, 1), None);
+    }
+
+    #[test]
+    fn test_non_utf8_history_is_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        std::fs::write(&path, b"# aider chat started at \xff\xfe\n").unwrap();
+
+        let error = AiderProbe::read_sessions(&path).unwrap_err();
+        match error.downcast_ref::<crate::error::Error>() {
+            Some(crate::error::Error::ParseError { path: p, line, .. }) => {
+                assert_eq!((p, *line), (&path, None));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Amp probe implementation
+//!
+//! Extracts threads from Sourcegraph's Amp CLI and editor extensions.
+//! Data format: ~/.local/share/amp/threads/
+//!   - T-<uuid>.json: one thread
+//!     {id, created (ms), title, messages: [{role, messageId, content: [...],
+//!     meta: {sentAt}, usage: {model, inputTokens, outputTokens, ...}}],
+//!     env: {initial: {trees: [{uri, repository: {url}}]}}}
+//!
+//! Content blocks follow the Anthropic shape (`text`, `thinking`, `tool_use`), except
+//! that results come back in user messages as `tool_result` blocks keyed by `toolUseID`
+//! with a `run.status` of `done`, `error`, `cancelled` or `rejected-by-user`.
+//!
+//! Amp is a multi-provider source: each assistant message's usage names its model.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::permissions::Permission;
+use crate::analysis::{files, loops, references};
+
+use super::cursor::file_uri_to_path;
+use super::{
+    infer_provider, thinking_tokens, ContentRef, IngestionProbe, MessageMetadata, SessionMetadata,
+    SessionRef, SkipCounts, SourceType, TokenUsage, ToolUseMetadata,
+};
+
+pub struct AmpProbe {
+    base_path: PathBuf,
+}
+
+// Amp data structures (threads/T-<uuid>.json)
+#[derive(Debug, Deserialize)]
+struct Thread {
+    id: Option<String>,
+    /// Milliseconds since the epoch
+    created: Option<i64>,
+    title: Option<String>,
+    #[serde(default)]
+    messages: Vec<Message>,
+    env: Option<Env>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+    role: String,
+    message_id: Option<i64>,
+    #[serde(default)]
+    content: Vec<Value>,
+    meta: Option<MessageMeta>,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageMeta {
+    /// Milliseconds since the epoch
+    sent_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Usage {
+    model: Option<String>,
+    input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+    cache_read_input_tokens: Option<i64>,
+    cache_creation_input_tokens: Option<i64>,
+    /// ISO 8601 time the answer finished
+    timestamp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Env {
+    initial: Option<EnvSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnvSnapshot {
+    #[serde(default)]
+    trees: Vec<Tree>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tree {
+    uri: Option<String>,
+    repository: Option<TreeRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeRepository {
+    url: Option<String>,
+}
+
+impl AmpProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".local/share/amp/threads")
+        });
+        Self { base_path }
+    }
+
+    fn read_thread(path: &Path) -> Result<Thread> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse Amp thread JSON")
+    }
+}
+
+fn block_type(block: &Value) -> Option<&str> {
+    block.get("type").and_then(|t| t.as_str())
+}
+
+fn millis_to_datetime(ms: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+impl IngestionProbe for AmpProbe {
+    fn id(&self) -> &str {
+        "amp:Amp"
+    }
+
+    fn provider(&self) -> &str {
+        "amp"
+    }
+
+    fn source(&self) -> &str {
+        "Amp"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "Sourcegraph Amp coding agent (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.exists() {
+            return Ok(sessions);
+        }
+
+        for entry in std::fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            sessions.push(SessionRef {
+                id,
+                source_path: path,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let thread = Self::read_thread(&session.source_path)?;
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = thread.title.clone().filter(|t| !t.trim().is_empty());
+        // toolUseID -> run status of the results sent back in user messages
+        let mut results: HashMap<String, String> = HashMap::new();
+
+        for (idx, msg) in thread.messages.iter().enumerate() {
+            if !matches!(msg.role.as_str(), "user" | "assistant") {
+                skipped.add(
+                    format!("unknown message role '{}'", msg.role),
+                    format!("{} message {}", session.source_path.display(), idx),
+                );
+                continue;
+            }
+
+            let text = msg
+                .content
+                .iter()
+                .filter(|b| block_type(b) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            session_refs.extend(references::detect(&text));
+            if msg.role == "user" {
+                language.add(&text);
+                if title.is_none() {
+                    title = text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            for block in msg.content.iter() {
+                if block_type(block) != Some("tool_result") {
+                    continue;
+                }
+                let id = block.get("toolUseID").and_then(|v| v.as_str());
+                let status = block.pointer("/run/status").and_then(|v| v.as_str());
+                if let Some(id) = id {
+                    results.insert(id.to_string(), status.unwrap_or("done").to_string());
+                }
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .content
+                .iter()
+                .filter(|b| block_type(b) == Some("tool_use"))
+                .map(|b| ToolUseMetadata {
+                    tool_id: b.get("id").and_then(|v| v.as_str()).map(String::from),
+                    tool_name: b
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown")
+                        .to_string(),
+                    has_result: false,
+                    is_error: false,
+                    permission: None,
+                    input_hash: b.get("input").map(loops::fingerprint),
+                    file_path: b.get("input").and_then(files::touched_file),
+                })
+                .collect();
+
+            let usage = msg.usage.as_ref();
+            let model = usage.and_then(|u| u.model.clone());
+            let provider_id = model.as_deref().and_then(infer_provider);
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+            let timestamp = msg
+                .meta
+                .as_ref()
+                .and_then(|m| m.sent_at)
+                .and_then(millis_to_datetime)
+                .or_else(|| {
+                    usage
+                        .and_then(|u| u.timestamp.as_deref())
+                        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                        .map(|t| t.with_timezone(&Utc))
+                });
+
+            messages.push(MessageMetadata {
+                uuid: msg.message_id.map(|id| id.to_string()),
+                role: msg.role.clone(),
+                provider_id,
+                model,
+                timestamp,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: msg
+                    .content
+                    .iter()
+                    .any(|b| block_type(b) == Some("thinking")),
+                tool_uses,
+                token_usage: usage.map(|u| TokenUsage {
+                    input_tokens: u.input_tokens,
+                    output_tokens: u.output_tokens,
+                    cache_read_tokens: u.cache_read_input_tokens,
+                    cache_creation_tokens: u.cache_creation_input_tokens,
+                    reasoning_tokens: thinking_tokens(&msg.content, u.output_tokens),
+                }),
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        // Results arrive in later user messages; attach them to their calls
+        for tool in messages.iter_mut().flat_map(|m| m.tool_uses.iter_mut()) {
+            let Some(status) = tool.tool_id.as_ref().and_then(|id| results.get(id)) else {
+                continue;
+            };
+            tool.has_result = true;
+            tool.is_error = status != "done";
+            if status == "rejected-by-user" {
+                tool.permission = Some(Permission::Denied);
+            }
+        }
+
+        let first_timestamp = thread
+            .created
+            .and_then(millis_to_datetime)
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = messages
+            .iter()
+            .rev()
+            .find_map(|m| m.timestamp)
+            .or(first_timestamp);
+        let tree = thread
+            .env
+            .and_then(|e| e.initial)
+            .and_then(|i| i.trees.into_iter().next());
+
+        Ok(SessionMetadata {
+            external_id: thread.id.unwrap_or(session.id.clone()),
+            title,
+            project_path: tree
+                .as_ref()
+                .and_then(|t| t.uri.as_deref())
+                .map(file_uri_to_path),
+            git_remote: tree.and_then(|t| t.repository).and_then(|r| r.url),
+            source_group: None,
+            primary_provider: provider_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(provider, _)| provider),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let thread = Self::read_thread(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let message = thread
+            .messages
+            .get(index)
+            .context("Amp message index out of range")?;
+        Ok(json!({ "content": message.content }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_thread_tools_and_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let thread = json!({
+            "v": 42,
+            "id": "T-0b5e",
+            "created": 1760000000000i64,
+            "messages": [
+                {
+                    "role": "user",
+                    "messageId": 0,
+                    "content": [{ "type": "text", "text": "Tidy up src/lib.rs" }],
+                    "meta": { "sentAt": 1760000001000i64 }
+                },
+                {
+                    "role": "assistant",
+                    "messageId": 1,
+                    "content": [
+                        { "type": "thinking", "thinking": "look first" },
+                        { "type": "tool_use", "id": "toolu_1", "name": "read_file", "input": { "path": "/w/app/src/lib.rs" } },
+                        { "type": "tool_use", "id": "toolu_2", "name": "Bash", "input": { "cmd": "rm -rf target" } }
+                    ],
+                    "usage": {
+                        "model": "claude-sonnet-4-5",
+                        "inputTokens": 12,
+                        "outputTokens": 80,
+                        "cacheReadInputTokens": 3000,
+                        "timestamp": "2025-10-09T09:33:30Z"
+                    }
+                },
+                {
+                    "role": "user",
+                    "messageId": 2,
+                    "content": [
+                        { "type": "tool_result", "toolUseID": "toolu_1", "run": { "status": "done" } },
+                        { "type": "tool_result", "toolUseID": "toolu_2", "run": { "status": "rejected-by-user" } }
+                    ]
+                }
+            ],
+            "env": { "initial": { "trees": [{ "uri": "file:///w/app", "repository": { "url": "https://github.com/me/app" } }] } }
+        });
+        let path = dir.path().join("T-0b5e.json");
+        std::fs::write(&path, thread.to_string()).unwrap();
+
+        let probe = AmpProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.external_id, "T-0b5e");
+        assert_eq!(metadata.title.as_deref(), Some("Tidy up src/lib.rs"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/w/app"));
+        assert_eq!(
+            metadata.git_remote.as_deref(),
+            Some("https://github.com/me/app")
+        );
+        assert_eq!(metadata.primary_model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("anthropic"));
+
+        let answer = &metadata.messages[1];
+        assert!(answer.has_thinking);
+        assert_eq!(
+            answer.token_usage.as_ref().unwrap().cache_read_tokens,
+            Some(3000)
+        );
+        let (read, bash) = (&answer.tool_uses[0], &answer.tool_uses[1]);
+        assert!(read.has_result && !read.is_error);
+        assert_eq!(read.file_path.as_deref(), Some("/w/app/src/lib.rs"));
+        assert!(bash.is_error);
+        assert_eq!(bash.permission, Some(Permission::Denied));
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("read_file"));
+    }
+}
@@ -3,7 +3,7 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::{
     ArtifactMetadata, ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef,
@@ -68,6 +68,10 @@ impl IngestionProbe for AntigravityProbe {
     fn is_available(&self) -> bool {
         self.base_path.exists()
     }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
     
     fn discover(&self) -> Result<Vec<SessionRef>> {
         let mut sessions = vec![];
@@ -0,0 +1,498 @@
+//! ChatGPT data export probe implementation
+//!
+//! Imports web ChatGPT history from the official data export (Settings → Data
+//! controls → Export). There is no live local source, so exports are unzipped into
+//! the base path (default ~/.local/share/chronicle/imports/chatgpt); every
+//! `conversations.json` found there is read.
+//!
+//! Data format: conversations.json is an array of conversations, each a message tree
+//! (`mapping` of node id -> {message, parent, children}) with `current_node` at the tip
+//! of the branch last shown. Only that branch is indexed; regenerated answers and
+//! edited prompts on other branches are left out.
+//!
+//! The same conversation appears in every later export; the newest export wins.
+//! ChatGPT Projects and custom GPTs carry a `gizmo_id`, used as the source group so
+//! `project_map` can route them to Chronicle projects.
+//!
+//! ChatGPT is a single-provider source (OpenAI).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::references;
+
+use super::imports::ExportCache;
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, ToolUseMetadata,
+};
+
+const EXPORT_FILE: &str = "conversations.json";
+
+pub struct ChatGptProbe {
+    base_path: PathBuf,
+    exports: ExportCache<Conversation>,
+}
+
+// ChatGPT export data structures (conversations.json)
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    id: Option<String>,
+    conversation_id: Option<String>,
+    title: Option<String>,
+    /// Seconds since the epoch (fractional)
+    create_time: Option<f64>,
+    update_time: Option<f64>,
+    #[serde(default)]
+    mapping: HashMap<String, Node>,
+    current_node: Option<String>,
+    default_model_slug: Option<String>,
+    gizmo_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    message: Option<Message>,
+    parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    id: Option<String>,
+    author: Author,
+    create_time: Option<f64>,
+    #[serde(default)]
+    content: Value,
+    #[serde(default)]
+    metadata: Value,
+    /// `all` for messages shown to the user; a tool name for tool calls
+    recipient: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    role: String,
+    /// Tool name on `tool` messages
+    name: Option<String>,
+}
+
+impl Conversation {
+    fn conversation_id(&self) -> Option<&str> {
+        self.conversation_id.as_deref().or(self.id.as_deref())
+    }
+
+    /// Messages on the branch ending at `current_node`, oldest first, without the
+    /// hidden scaffolding (empty system prompts, custom instructions)
+    fn branch(&self) -> Vec<&Message> {
+        let mut messages = vec![];
+        let mut seen = HashSet::new();
+        let mut cursor = self.current_node.as_deref();
+        while let Some(id) = cursor {
+            // Guard against malformed exports with parent cycles
+            if !seen.insert(id) {
+                break;
+            }
+            let Some(node) = self.mapping.get(id) else {
+                break;
+            };
+            if let Some(ref message) = node.message {
+                if !is_hidden(message) {
+                    messages.push(message);
+                }
+            }
+            cursor = node.parent.as_deref();
+        }
+        messages.reverse();
+        messages
+    }
+}
+
+impl ChatGptProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".local/share/chronicle/imports/chatgpt")
+        });
+        Self {
+            base_path,
+            exports: ExportCache::new(EXPORT_FILE),
+        }
+    }
+
+    fn conversation(&self, path: &Path, id: &str) -> Result<(Arc<Vec<Conversation>>, usize)> {
+        let conversations = self.exports.load(path)?;
+        let index = conversations
+            .iter()
+            .position(|c| c.conversation_id() == Some(id))
+            .with_context(|| format!("Conversation {} not found in {}", id, path.display()))?;
+        Ok((conversations, index))
+    }
+
+    /// Normalize a message into the content-array shape `read` understands
+    fn message_content(message: &Message) -> Value {
+        let mut items = vec![];
+        let content_type = message.content["content_type"].as_str().unwrap_or_default();
+        let text = content_text(&message.content);
+        if content_type == "thoughts" {
+            items.push(json!({ "type": "thinking", "thinking": text }));
+        } else if let Some(tool) = tool_call(message) {
+            items.push(json!({ "type": "tool_use", "name": tool, "input": text }));
+        } else if !text.is_empty() {
+            items.push(json!({ "type": "text", "text": text }));
+        }
+        json!({ "content": items })
+    }
+}
+
+/// Scaffolding the ChatGPT UI doesn't show
+fn is_hidden(message: &Message) -> bool {
+    let metadata = &message.metadata;
+    if metadata["is_visually_hidden_from_conversation"] == true {
+        return true;
+    }
+    match message.content["content_type"].as_str() {
+        Some("user_editable_context") | Some("model_editable_context") => true,
+        _ => message.author.role == "system" && content_text(&message.content).trim().is_empty(),
+    }
+}
+
+/// Tool an assistant message is addressed to, if it is a tool call
+fn tool_call(message: &Message) -> Option<&str> {
+    let recipient = message.recipient.as_deref()?;
+    (message.author.role == "assistant" && recipient != "all").then_some(recipient)
+}
+
+/// Plain text of a message, across the export's content types
+fn content_text(content: &Value) -> String {
+    match content["content_type"].as_str() {
+        // Code sent to a tool, and tool output
+        Some("code") | Some("execution_output") | Some("tether_quote") => {
+            content["text"].as_str().unwrap_or_default().to_string()
+        }
+        Some("thoughts") => content["thoughts"]
+            .as_array()
+            .map(|thoughts| {
+                thoughts
+                    .iter()
+                    .filter_map(|t| t["content"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            })
+            .unwrap_or_default(),
+        Some("reasoning_recap") => content["content"].as_str().unwrap_or_default().to_string(),
+        // text, multimodal_text: strings, with uploaded images as asset objects
+        _ => content["parts"]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| match p {
+                        Value::String(s) => Some(s.clone()),
+                        other => other["content_type"]
+                            .as_str()
+                            .map(|kind| format!("[{}]", kind)),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn seconds_to_datetime(seconds: f64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt((seconds * 1000.0) as i64).single()
+}
+
+impl IngestionProbe for ChatGptProbe {
+    fn id(&self) -> &str {
+        "openai:ChatGPT"
+    }
+
+    fn provider(&self) -> &str {
+        "openai"
+    }
+
+    fn source(&self) -> &str {
+        "ChatGPT"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "ChatGPT data export (OpenAI)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Exports carry no token usage, and every conversation shares one file
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: false,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+        let mut seen = HashSet::new();
+
+        for path in self.exports.export_files(&self.base_path) {
+            for conversation in self.exports.load(&path)?.iter() {
+                let Some(id) = conversation.conversation_id() else {
+                    continue;
+                };
+                // Exports are cumulative; the newest copy of a conversation wins
+                if seen.insert(id.to_string()) {
+                    sessions.push(SessionRef {
+                        id: id.to_string(),
+                        source_path: path.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let (conversations, index) = self.conversation(&session.source_path, &session.id)?;
+        let conversation = &conversations[index];
+
+        let mut messages = vec![];
+        let skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+
+        let branch = conversation.branch();
+
+        for (idx, message) in branch.iter().enumerate() {
+            let role = match message.author.role.as_str() {
+                "user" | "assistant" | "system" | "tool" => message.author.role.as_str(),
+                _ => "system",
+            };
+            let text = content_text(&message.content);
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+            }
+
+            let model = (role == "assistant")
+                .then(|| {
+                    message.metadata["model_slug"]
+                        .as_str()
+                        .map(String::from)
+                        .or_else(|| conversation.default_model_slug.clone())
+                })
+                .flatten();
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = tool_call(message)
+                .map(|tool| {
+                    // The reply, if any, is one of the following tool messages
+                    let replied = branch[idx + 1..]
+                        .iter()
+                        .take_while(|m| m.author.role == "tool")
+                        .find(|m| {
+                            m.author.name.as_deref().is_some_and(|name| {
+                                name == tool || tool.starts_with(&format!("{}.", name))
+                            })
+                        });
+                    ToolUseMetadata {
+                        tool_id: message.id.clone(),
+                        tool_name: tool.to_string(),
+                        has_result: replied.is_some(),
+                        is_error: replied.is_some_and(|m| {
+                            m.content["content_type"] == "system_error"
+                                || m.metadata["is_error"] == true
+                        }),
+                        permission: None,
+                        input_hash: None,
+                        file_path: None,
+                    }
+                })
+                .into_iter()
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: message.id.clone(),
+                role: role.to_string(),
+                provider_id: (role == "assistant").then(|| "openai".to_string()),
+                model,
+                timestamp: message.create_time.and_then(seconds_to_datetime),
+                content_ref: ContentRef::db_record(
+                    session.source_path.clone(),
+                    &session.id,
+                    idx as u32,
+                ),
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: message.content["content_type"] == "thoughts",
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = conversation
+            .create_time
+            .and_then(seconds_to_datetime)
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = conversation
+            .update_time
+            .and_then(seconds_to_datetime)
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp))
+            .or(first_timestamp);
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title: conversation.title.clone().filter(|t| !t.trim().is_empty()),
+            project_path: None,
+            git_remote: None,
+            source_group: conversation.gizmo_id.clone(),
+            primary_provider: Some("openai".to_string()),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let id = reference
+            .content_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .context("ChatGPT content reference has no conversation id")?;
+        let (conversations, index) = self.conversation(&reference.source_path, id)?;
+        let branch = conversations[index].branch();
+        let message = branch
+            .get(reference.line_number.unwrap_or(0) as usize)
+            .context("ChatGPT message index out of range")?;
+        Ok(Self::message_content(message).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_active_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = |id: &str, parent: Option<&str>, message: Value| {
+            (
+                id.to_string(),
+                json!({ "id": id, "parent": parent, "message": message }),
+            )
+        };
+        let text = |role: &str, body: &str, time: f64| {
+            json!({
+                "id": format!("m-{}", body.len()),
+                "author": { "role": role },
+                "create_time": time,
+                "content": { "content_type": "text", "parts": [body] },
+                "metadata": { "model_slug": "gpt-4o" },
+                "recipient": "all"
+            })
+        };
+        let mapping: serde_json::Map<String, Value> = [
+            node("root", None, Value::Null),
+            node("sys", Some("root"), text("system", "", 1.0)),
+            node(
+                "u1",
+                Some("sys"),
+                text("user", "Plot issue #7", 1760000000.0),
+            ),
+            node(
+                "a1",
+                Some("u1"),
+                json!({
+                    "author": { "role": "assistant" },
+                    "content": { "content_type": "code", "text": "plot()" },
+                    "metadata": { "model_slug": "gpt-4o" },
+                    "recipient": "python"
+                }),
+            ),
+            node(
+                "t1",
+                Some("a1"),
+                json!({
+                    "author": { "role": "tool", "name": "python" },
+                    "content": { "content_type": "execution_output", "text": "ok" },
+                    "recipient": "all"
+                }),
+            ),
+            node(
+                "a2",
+                Some("t1"),
+                text("assistant", "Here is the plot", 1760000060.0),
+            ),
+            // A regenerated answer on another branch
+            node(
+                "a2b",
+                Some("t1"),
+                text("assistant", "Discarded", 1760000050.0),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let export = json!([{
+            "id": "conv-1",
+            "title": "Plotting",
+            "create_time": 1760000000.0,
+            "update_time": 1760000060.5,
+            "mapping": mapping,
+            "current_node": "a2",
+            "gizmo_id": "g-p-123"
+        }]);
+        std::fs::create_dir_all(dir.path().join("2025-10")).unwrap();
+        let path = dir.path().join("2025-10").join(EXPORT_FILE);
+        std::fs::write(&path, export.to_string()).unwrap();
+
+        let probe = ChatGptProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Plotting"));
+        assert_eq!(metadata.source_group.as_deref(), Some("g-p-123"));
+        assert_eq!(metadata.primary_model.as_deref(), Some("gpt-4o"));
+        let roles: Vec<&str> = metadata.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "tool", "assistant"]);
+        assert_eq!(metadata.messages[1].tool_uses[0].tool_name, "python");
+        assert!(metadata.messages[1].tool_uses[0].has_result);
+        assert_eq!(metadata.references.len(), 1);
+
+        let content = probe
+            .get_content(&metadata.messages[3].content_ref)
+            .unwrap();
+        assert!(content.contains("Here is the plot"));
+    }
+}
@@ -0,0 +1,420 @@
+//! Claude.ai data export probe implementation
+//!
+//! Imports browser and desktop Claude conversations from the official data export
+//! (Settings → Privacy → Export data). Exports are unzipped into the base path
+//! (default ~/.local/share/chronicle/imports/claude); every `conversations.json`
+//! found there is read, and the newest export wins for conversations present in
+//! several.
+//!
+//! Data format: conversations.json is an array of conversations
+//! ({uuid, name, created_at, updated_at, chat_messages}). Each chat message has a
+//! `sender` (human/assistant), a flat `text` and, in newer exports, a typed `content`
+//! array (text, thinking, tool_use, tool_result) plus attachment and file names.
+//! Tool results come back inside the assistant message that made the call.
+//!
+//! Conversations that were also run through Claude Code share the provider-minted
+//! `toolu_` tool IDs with the CLI transcript, so `chronicle dedupe` pairs them by
+//! tool ID (or by identical prompts) like any other cross-source duplicate.
+//!
+//! Claude.ai is a single-provider source (Anthropic). Exports carry no model or token
+//! usage per message.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::references;
+
+use super::imports::ExportCache;
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, ToolUseMetadata,
+};
+
+const EXPORT_FILE: &str = "conversations.json";
+
+pub struct ClaudeAiProbe {
+    base_path: PathBuf,
+    exports: ExportCache<Conversation>,
+}
+
+// Claude.ai export data structures (conversations.json)
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    uuid: String,
+    name: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    /// Claude Project the conversation belongs to, when the export records it
+    project_uuid: Option<String>,
+    model: Option<String>,
+    #[serde(default)]
+    chat_messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    uuid: Option<String>,
+    sender: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    content: Vec<Value>,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+    #[serde(default)]
+    files: Vec<Attachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Attachment {
+    file_name: Option<String>,
+}
+
+impl ChatMessage {
+    fn role(&self) -> &str {
+        match self.sender.as_str() {
+            "human" => "user",
+            "assistant" => "assistant",
+            _ => "system",
+        }
+    }
+
+    /// Content items, falling back to the flat text of older exports
+    fn items(&self) -> Vec<Value> {
+        let mut items = if self.content.is_empty() {
+            vec![json!({ "type": "text", "text": self.text })]
+        } else {
+            self.content.clone()
+        };
+        for attachment in self.attachments.iter().chain(&self.files) {
+            if let Some(ref name) = attachment.file_name {
+                items.push(json!({ "type": "text", "text": format!("[attachment: {}]", name) }));
+            }
+        }
+        items
+    }
+
+    /// Text the user or assistant wrote (no tool traffic or thinking)
+    fn plain_text(&self) -> String {
+        if self.content.is_empty() {
+            return self.text.clone();
+        }
+        self.content
+            .iter()
+            .filter(|item| item["type"] == "text")
+            .filter_map(|item| item["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl ClaudeAiProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".local/share/chronicle/imports/claude")
+        });
+        Self {
+            base_path,
+            exports: ExportCache::new(EXPORT_FILE),
+        }
+    }
+
+    fn conversation(&self, path: &Path, id: &str) -> Result<(Arc<Vec<Conversation>>, usize)> {
+        let conversations = self.exports.load(path)?;
+        let index = conversations
+            .iter()
+            .position(|c| c.uuid == id)
+            .with_context(|| format!("Conversation {} not found in {}", id, path.display()))?;
+        Ok((conversations, index))
+    }
+
+    /// Normalize a message into the content-array shape `read` understands
+    fn message_content(message: &ChatMessage) -> Value {
+        let items: Vec<Value> = message
+            .items()
+            .into_iter()
+            .filter_map(|item| match item["type"].as_str() {
+                Some("text") if item["text"].as_str().is_some_and(|t| !t.is_empty()) => Some(item),
+                Some("thinking") => Some(item),
+                Some("tool_use") => Some(json!({
+                    "type": "tool_use",
+                    "name": item["name"],
+                    "input": item["input"],
+                })),
+                // Server-side tool output is rendered as text
+                Some("tool_result") => Some(json!({
+                    "type": "text",
+                    "text": format!("[{} result]\n{}", item["name"].as_str().unwrap_or("tool"), result_text(&item)),
+                })),
+                _ => None,
+            })
+            .collect();
+        json!({ "content": items })
+    }
+}
+
+/// Text of a tool_result item: a string or an array of text blocks
+fn result_text(item: &Value) -> String {
+    match &item["content"] {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Tool calls of an assistant message, matched to the results that follow them
+fn tool_uses(message: &ChatMessage) -> Vec<ToolUseMetadata> {
+    let results: Vec<&Value> = message
+        .content
+        .iter()
+        .filter(|item| item["type"] == "tool_result")
+        .collect();
+    let mut used = HashSet::new();
+    message
+        .content
+        .iter()
+        .filter(|item| item["type"] == "tool_use")
+        .map(|item| {
+            let id = item["id"].as_str();
+            let name = item["name"].as_str().unwrap_or("unknown");
+            // Pair by ID when the export has one, otherwise the next unused result by name
+            let result = results.iter().enumerate().find(|(i, r)| {
+                !used.contains(i)
+                    && match (id, r["tool_use_id"].as_str()) {
+                        (Some(id), Some(result_id)) => id == result_id,
+                        _ => r["name"].as_str() == Some(name),
+                    }
+            });
+            if let Some((i, _)) = result {
+                used.insert(i);
+            }
+            ToolUseMetadata {
+                tool_id: id.map(String::from),
+                tool_name: name.to_string(),
+                has_result: result.is_some(),
+                is_error: result.is_some_and(|(_, r)| r["is_error"] == true),
+                permission: None,
+                input_hash: None,
+                file_path: None,
+            }
+        })
+        .collect()
+}
+
+impl IngestionProbe for ClaudeAiProbe {
+    fn id(&self) -> &str {
+        "claude:ClaudeAI"
+    }
+
+    fn provider(&self) -> &str {
+        "claude"
+    }
+
+    fn source(&self) -> &str {
+        "ClaudeAI"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "Claude.ai data export (Anthropic)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Exports carry no token usage, and every conversation shares one file
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: false,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+        let mut seen = HashSet::new();
+
+        for path in self.exports.export_files(&self.base_path) {
+            for conversation in self.exports.load(&path)?.iter() {
+                // Exports are cumulative; the newest copy of a conversation wins
+                if !conversation.chat_messages.is_empty() && seen.insert(conversation.uuid.clone())
+                {
+                    sessions.push(SessionRef {
+                        id: conversation.uuid.clone(),
+                        source_path: path.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let (conversations, index) = self.conversation(&session.source_path, &session.id)?;
+        let conversation = &conversations[index];
+
+        let mut messages = vec![];
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+
+        for (idx, message) in conversation.chat_messages.iter().enumerate() {
+            let role = message.role();
+            let text = message.plain_text();
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+            }
+
+            let tool_uses = tool_uses(message);
+            messages.push(MessageMetadata {
+                uuid: message.uuid.clone(),
+                role: role.to_string(),
+                provider_id: (role == "assistant").then(|| "anthropic".to_string()),
+                model: (role == "assistant")
+                    .then(|| conversation.model.clone())
+                    .flatten(),
+                timestamp: message.created_at,
+                content_ref: ContentRef::db_record(
+                    session.source_path.clone(),
+                    &session.id,
+                    idx as u32,
+                ),
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: message
+                    .content
+                    .iter()
+                    .any(|item| item["type"] == "thinking"),
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = conversation
+            .created_at
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = conversation
+            .updated_at
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp))
+            .or(first_timestamp);
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title: conversation.name.clone().filter(|t| !t.trim().is_empty()),
+            project_path: None,
+            git_remote: None,
+            source_group: conversation.project_uuid.clone(),
+            primary_provider: Some("anthropic".to_string()),
+            primary_model: conversation.model.clone(),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let id = reference
+            .content_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .context("Claude.ai content reference has no conversation id")?;
+        let (conversations, index) = self.conversation(&reference.source_path, id)?;
+        let message = conversations[index]
+            .chat_messages
+            .get(reference.line_number.unwrap_or(0) as usize)
+            .context("Claude.ai message index out of range")?;
+        Ok(Self::message_content(message).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_conversations() {
+        let dir = tempfile::tempdir().unwrap();
+        let export = json!([
+            {
+                "uuid": "conv-1",
+                "name": "Parser bug",
+                "created_at": "2025-10-01T09:00:00Z",
+                "updated_at": "2025-10-01T09:05:00Z",
+                "chat_messages": [
+                    {
+                        "uuid": "m1",
+                        "sender": "human",
+                        "text": "Why does issue #12 crash?",
+                        "created_at": "2025-10-01T09:00:00Z",
+                        "attachments": [{ "file_name": "trace.txt" }]
+                    },
+                    {
+                        "uuid": "m2",
+                        "sender": "assistant",
+                        "text": "",
+                        "created_at": "2025-10-01T09:01:00Z",
+                        "content": [
+                            { "type": "thinking", "thinking": "Look at the trace" },
+                            { "type": "tool_use", "name": "web_search", "input": { "query": "crash" } },
+                            { "type": "tool_result", "name": "web_search", "is_error": true, "content": [] },
+                            { "type": "text", "text": "The lexer overflows." }
+                        ]
+                    }
+                ]
+            },
+            { "uuid": "empty", "name": "", "chat_messages": [] }
+        ]);
+        let path = dir.path().join(EXPORT_FILE);
+        std::fs::write(&path, export.to_string()).unwrap();
+
+        let probe = ClaudeAiProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Parser bug"));
+        assert_eq!(metadata.references.len(), 1);
+        let roles: Vec<&str> = metadata.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+        let reply = &metadata.messages[1];
+        assert!(reply.has_thinking);
+        assert_eq!(reply.tool_uses[0].tool_name, "web_search");
+        assert!(reply.tool_uses[0].has_result && reply.tool_uses[0].is_error);
+
+        let prompt = probe
+            .get_content(&metadata.messages[0].content_ref)
+            .unwrap();
+        assert!(prompt.contains("[attachment: trace.txt]"));
+        let content = probe.get_content(&reply.content_ref).unwrap();
+        assert!(content.contains("The lexer overflows."));
+    }
+}
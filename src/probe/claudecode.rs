@@ -5,19 +5,29 @@
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use serde_json::Value;
-use std::collections::HashMap;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::permissions::{self, Permission};
+use crate::analysis::{files, loops, references};
+use crate::error::Error;
 
 use super::{
-    ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef, SourceType,
-    TokenUsage, ToolUseMetadata,
+    thinking_tokens, ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef,
+    SkipCounts, SourceFingerprint, SourceType, TokenUsage, ToolUseMetadata,
 };
 
 pub struct ClaudeCodeProbe {
     base_path: PathBuf,
+    /// Global state file (~/.claude.json) holding per-project trust and allowed tools
+    state_file: PathBuf,
+    /// Parsed state file; it can run to megabytes, so it is reparsed only when it changes
+    state: Mutex<Option<(Option<SourceFingerprint>, Arc<Value>)>>,
 }
 
 impl ClaudeCodeProbe {
@@ -26,7 +36,88 @@ impl ClaudeCodeProbe {
             let home = dirs::home_dir().unwrap_or_default();
             home.join(".claude/projects")
         });
-        Self { base_path }
+        // ~/.claude/projects -> ~/.claude.json
+        let state_file = base_path
+            .parent()
+            .and_then(Path::parent)
+            .map(|home| home.join(".claude.json"))
+            .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".claude.json"));
+        Self {
+            base_path,
+            state_file,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Read a settings file's `permissions` block (allow/deny/ask lists)
+    fn read_permissions(path: &Path) -> Option<Value> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let json: Value = serde_json::from_str(&content).ok()?;
+        json.get("permissions").cloned()
+    }
+
+    /// Collect string entries from `permissions.<key>` across settings files
+    fn permission_list(permissions: &[Value], key: &str) -> Vec<String> {
+        let mut entries: Vec<String> = permissions
+            .iter()
+            .filter_map(|p| p.get(key).and_then(|v| v.as_array()))
+            .flatten()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect();
+        entries.sort();
+        entries.dedup();
+        entries
+    }
+
+    /// Project settings files (shared and local) and their `permissions` blocks
+    fn project_permissions(project_path: &str) -> Vec<(PathBuf, Value)> {
+        ["settings.json", "settings.local.json"]
+            .iter()
+            .map(|name| Path::new(project_path).join(".claude").join(name))
+            .filter_map(|path| Self::read_permissions(&path).map(|p| (path, p)))
+            .collect()
+    }
+
+    /// The global state file, parsed once per process while unchanged
+    fn global_state(&self) -> Arc<Value> {
+        let fingerprint = SourceFingerprint::of(&self.state_file);
+        let mut cached = self.state.lock().unwrap();
+        if let Some((at, ref state)) = *cached {
+            if at == fingerprint {
+                return state.clone();
+            }
+        }
+        let state = Arc::new(
+            std::fs::read_to_string(&self.state_file)
+                .ok()
+                .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+                .unwrap_or(Value::Null),
+        );
+        *cached = Some((fingerprint, state.clone()));
+        state
+    }
+
+    /// The project's entry in the global state file
+    fn project_state(&self, project_path: &str) -> Option<Value> {
+        self.global_state()
+            .get("projects")?
+            .get(project_path)
+            .cloned()
+    }
+
+    /// Allow rules of a project: settings `permissions.allow` plus tools approved
+    /// with "don't ask again" (`allowedTools` in the state file)
+    fn allow_rules(&self, permissions: &[Value], state: Option<&Value>) -> Vec<String> {
+        let mut allowed = Self::permission_list(permissions, "allow");
+        if let Some(tools) = state
+            .and_then(|s| s.get("allowedTools"))
+            .and_then(|v| v.as_array())
+        {
+            allowed.extend(tools.iter().filter_map(|v| v.as_str().map(String::from)));
+            allowed.sort();
+            allowed.dedup();
+        }
+        allowed
     }
 
     /// Extract git remote from project directory if available
@@ -77,6 +168,10 @@ impl IngestionProbe for ClaudeCodeProbe {
         self.base_path.exists()
     }
 
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
     fn discover(&self) -> Result<Vec<SessionRef>> {
         let mut sessions = vec![];
 
@@ -114,22 +209,40 @@ impl IngestionProbe for ClaudeCodeProbe {
         let file = File::open(&session.source_path).context("Failed to open session file")?;
         let reader = BufReader::new(file);
 
-        let mut messages = vec![];
+        let mut messages: Vec<MessageMetadata> = vec![];
+        let mut skipped = SkipCounts::default();
         let mut first_ts: Option<DateTime<Utc>> = None;
         let mut last_ts: Option<DateTime<Utc>> = None;
         let mut project_path: Option<String> = None;
         let mut title: Option<String> = None;
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
 
         // Track provider/model usage for determining primary
         let mut provider_counts: HashMap<String, usize> = HashMap::new();
         let mut model_counts: HashMap<String, usize> = HashMap::new();
+        // tool_use_id -> (is_error, rejected) of the tool_result blocks sent back in user turns
+        let mut tool_results: HashMap<String, (bool, bool)> = HashMap::new();
+        // Permission mode in effect and the project's allow rules (read once cwd is known)
+        let mut permission_mode = "default".to_string();
+        let mut allow_rules: Option<Vec<String>> = None;
+        // Compact row still waiting for the summary that follows its boundary
+        let mut pending_compact: Option<usize> = None;
 
         let mut byte_offset: u64 = 0;
         let mut line_number: u32 = 0;
 
         for line in reader.lines() {
-            let line = line?;
             line_number += 1;
+            let line = line.map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidData => Error::ParseError {
+                    path: session.source_path.clone(),
+                    line: Some(line_number as usize),
+                    reason: "Not valid UTF-8".to_string(),
+                }
+                .into(),
+                _ => anyhow::Error::new(e),
+            })?;
 
             if line.trim().is_empty() {
                 byte_offset += line.len() as u64 + 1;
@@ -141,7 +254,13 @@ impl IngestionProbe for ClaudeCodeProbe {
 
             let json: Value = match serde_json::from_str(&line) {
                 Ok(v) => v,
-                Err(_) => continue,
+                Err(_) => {
+                    skipped.add(
+                        "invalid JSON line",
+                        format!("{}:{}", session.source_path.display(), line_number),
+                    );
+                    continue;
+                }
             };
 
             // Skip queue operations
@@ -153,6 +272,9 @@ impl IngestionProbe for ClaudeCodeProbe {
             if project_path.is_none() {
                 project_path = json.get("cwd").and_then(|v| v.as_str()).map(String::from);
             }
+            if let Some(mode) = json.get("permissionMode").and_then(|v| v.as_str()) {
+                permission_mode = mode.to_string();
+            }
 
             // Parse timestamp
             let timestamp = json
@@ -168,6 +290,40 @@ impl IngestionProbe for ClaudeCodeProbe {
                 last_ts = Some(ts);
             }
 
+            // Compaction: a boundary marker followed by the summary that opens the new
+            // context (older versions write only the summary). Both become one system row
+            // whose content is the summary.
+            let line_ref =
+                ContentRef::jsonl(session.source_path.clone(), current_offset, line_number);
+            let is_boundary =
+                json.get("subtype").and_then(|v| v.as_str()) == Some("compact_boundary");
+            let is_summary = json.get("isCompactSummary").and_then(|v| v.as_bool()) == Some(true);
+            if is_summary {
+                if let Some(index) = pending_compact.take() {
+                    messages[index].content_ref = line_ref;
+                    continue;
+                }
+            }
+            if is_boundary || is_summary {
+                messages.push(MessageMetadata {
+                    uuid: json.get("uuid").and_then(|v| v.as_str()).map(String::from),
+                    role: "system".to_string(),
+                    provider_id: None,
+                    model: None,
+                    timestamp,
+                    content_ref: line_ref,
+                    has_tool_use: false,
+                    has_thinking: false,
+                    tool_uses: vec![],
+                    token_usage: None,
+                    request_params: None,
+                    subtype: Some("compact".to_string()),
+                });
+                pending_compact = is_boundary.then(|| messages.len() - 1);
+                continue;
+            }
+            pending_compact = None;
+
             // Extract role
             let role = json
                 .get("message")
@@ -210,6 +366,22 @@ impl IngestionProbe for ClaudeCodeProbe {
 
             // Check for tool use
             let content = json.get("message").and_then(|m| m.get("content"));
+
+            // Scan message text for issue/PR references and the user's language
+            let texts: Vec<&str> = match content {
+                Some(Value::String(text)) => vec![text],
+                Some(Value::Array(arr)) => arr
+                    .iter()
+                    .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+                    .collect(),
+                _ => vec![],
+            };
+            for text in texts {
+                session_refs.extend(references::detect(text));
+                if role == "user" {
+                    language.add(text);
+                }
+            }
             let has_tool_use = content
                 .and_then(|c| c.as_array())
                 .map(|arr| {
@@ -219,23 +391,47 @@ impl IngestionProbe for ClaudeCodeProbe {
                 .unwrap_or(false);
 
             // Extract tool uses
+            if allow_rules.is_none() && has_tool_use {
+                allow_rules = Some(match project_path.as_deref() {
+                    Some(path) => {
+                        let permissions: Vec<Value> = Self::project_permissions(path)
+                            .into_iter()
+                            .map(|(_, p)| p)
+                            .collect();
+                        self.allow_rules(&permissions, self.project_state(path).as_ref())
+                    }
+                    None => vec![],
+                });
+            }
+            let allow = allow_rules.as_deref().unwrap_or_default();
             let tool_uses = content
                 .and_then(|c| c.as_array())
                 .map(|arr| {
                     arr.iter()
                         .filter_map(|item| {
                             if item.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
+                                let tool_name = item
+                                    .get("name")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or("unknown")
+                                    .to_string();
+                                let permission = permissions::granted(
+                                    &tool_name,
+                                    item.get("input"),
+                                    &permission_mode,
+                                    allow,
+                                );
                                 Some(ToolUseMetadata {
                                     tool_id: item
                                         .get("id")
                                         .and_then(|v| v.as_str())
                                         .map(String::from),
-                                    tool_name: item
-                                        .get("name")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("unknown")
-                                        .to_string(),
+                                    tool_name,
                                     has_result: false,
+                                    is_error: false,
+                                    permission: Some(permission),
+                                    input_hash: item.get("input").map(loops::fingerprint),
+                                    file_path: item.get("input").and_then(files::touched_file),
                                 })
                             } else {
                                 None
@@ -245,6 +441,23 @@ impl IngestionProbe for ClaudeCodeProbe {
                 })
                 .unwrap_or_default();
 
+            for item in content.and_then(|c| c.as_array()).into_iter().flatten() {
+                if item.get("type").and_then(|t| t.as_str()) == Some("tool_result") {
+                    if let Some(id) = item.get("tool_use_id").and_then(|v| v.as_str()) {
+                        let is_error = item.get("is_error").and_then(|v| v.as_bool());
+                        let rejected = match item.get("content") {
+                            Some(Value::String(text)) => permissions::is_rejection(text),
+                            Some(Value::Array(parts)) => parts
+                                .iter()
+                                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                                .any(permissions::is_rejection),
+                            _ => false,
+                        };
+                        tool_results.insert(id.to_string(), (is_error.unwrap_or(false), rejected));
+                    }
+                }
+            }
+
             // Check for thinking
             let has_thinking = content
                 .and_then(|c| c.as_array())
@@ -258,15 +471,22 @@ impl IngestionProbe for ClaudeCodeProbe {
             let token_usage = json
                 .get("message")
                 .and_then(|m| m.get("usage"))
-                .map(|usage| TokenUsage {
-                    input_tokens: usage.get("input_tokens").and_then(|v| v.as_i64()),
-                    output_tokens: usage.get("output_tokens").and_then(|v| v.as_i64()),
-                    cache_read_tokens: usage
-                        .get("cache_read_input_tokens")
-                        .and_then(|v| v.as_i64()),
-                    cache_creation_tokens: usage
-                        .get("cache_creation_input_tokens")
-                        .and_then(|v| v.as_i64()),
+                .map(|usage| {
+                    let output_tokens = usage.get("output_tokens").and_then(|v| v.as_i64());
+                    TokenUsage {
+                        input_tokens: usage.get("input_tokens").and_then(|v| v.as_i64()),
+                        output_tokens,
+                        cache_read_tokens: usage
+                            .get("cache_read_input_tokens")
+                            .and_then(|v| v.as_i64()),
+                        cache_creation_tokens: usage
+                            .get("cache_creation_input_tokens")
+                            .and_then(|v| v.as_i64()),
+                        // The log keeps thinking blocks but no count for them
+                        reasoning_tokens: content
+                            .and_then(|c| c.as_array())
+                            .and_then(|blocks| thinking_tokens(blocks, output_tokens)),
+                    }
                 });
 
             messages.push(MessageMetadata {
@@ -275,18 +495,34 @@ impl IngestionProbe for ClaudeCodeProbe {
                 provider_id: Some("anthropic".to_string()),
                 model: msg_model,
                 timestamp,
-                content_ref: ContentRef::jsonl(
-                    session.source_path.clone(),
-                    current_offset,
-                    line_number,
-                ),
+                content_ref: line_ref,
                 has_tool_use,
                 has_thinking,
                 tool_uses,
                 token_usage,
+                request_params: None,
+                subtype: None,
             });
         }
 
+        // Results arrive in later user turns; attach them to their calls
+        for tool in messages.iter_mut().flat_map(|m| m.tool_uses.iter_mut()) {
+            if let Some(&(is_error, rejected)) =
+                tool.tool_id.as_ref().and_then(|id| tool_results.get(id))
+            {
+                tool.has_result = true;
+                tool.is_error = is_error;
+                if rejected {
+                    tool.permission = Some(Permission::Denied);
+                }
+            }
+        }
+
+        let compactions = messages
+            .iter()
+            .filter(|m| m.subtype.as_deref() == Some("compact"))
+            .count();
+
         // Determine primary provider/model
         let primary_provider = provider_counts
             .into_iter()
@@ -298,6 +534,14 @@ impl IngestionProbe for ClaudeCodeProbe {
             .max_by_key(|(_, count)| *count)
             .map(|(model, _)| model);
 
+        // Claude Code groups sessions by an encoded project directory name
+        let source_group = session
+            .source_path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .map(String::from);
+
         // Extract git remote if we have a project path
         let git_remote = project_path
             .as_ref()
@@ -308,14 +552,55 @@ impl IngestionProbe for ClaudeCodeProbe {
             title,
             project_path,
             git_remote,
+            source_group,
             primary_provider,
             primary_model,
             first_timestamp: first_ts,
             last_timestamp: last_ts,
             messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions,
+            skipped,
         })
     }
 
+    fn project_metadata(&self, project_path: &str) -> Option<Value> {
+        // Project settings: shared (checked in) and local (per-user)
+        let (settings_files, permissions): (Vec<String>, Vec<Value>) =
+            Self::project_permissions(project_path)
+                .into_iter()
+                .map(|(path, p)| (path.to_string_lossy().to_string(), p))
+                .unzip();
+
+        // Trust decision and approved tools from the global state file
+        let state = self.project_state(project_path);
+
+        if permissions.is_empty() && state.is_none() {
+            return None;
+        }
+
+        let allowed = self.allow_rules(&permissions, state.as_ref());
+
+        let default_mode = permissions
+            .iter()
+            .rev()
+            .find_map(|p| p.get("defaultMode").and_then(|v| v.as_str()));
+
+        Some(json!({
+            "trusted": state
+                .as_ref()
+                .and_then(|s| s.get("hasTrustDialogAccepted"))
+                .and_then(|v| v.as_bool()),
+            "default_mode": default_mode,
+            "allowed_tools": allowed,
+            "denied_tools": Self::permission_list(&permissions, "deny"),
+            "ask_tools": Self::permission_list(&permissions, "ask"),
+            "settings_files": settings_files,
+        }))
+    }
+
     fn get_content(&self, reference: &ContentRef) -> Result<String> {
         let byte_offset = reference.byte_offset.unwrap_or(0);
         let mut file = File::open(&reference.source_path)?;
@@ -325,6 +610,15 @@ impl IngestionProbe for ClaudeCodeProbe {
         let mut line = String::new();
         reader.read_line(&mut line)?;
 
+        // A stale offset (the file was rewritten) lands mid-line
+        if let Err(e) = serde_json::from_str::<Value>(&line) {
+            return Err(Error::ParseError {
+                path: reference.source_path.clone(),
+                line: reference.line_number.map(|n| n as usize),
+                reason: format!("Invalid JSON line: {}", e),
+            }
+            .into());
+        }
         Ok(line)
     }
 }
@@ -332,9 +626,117 @@ impl IngestionProbe for ClaudeCodeProbe {
 /// Truncate a string to make a reasonable title (first 100 chars, first line)
 fn truncate_title(text: &str) -> String {
     let first_line = text.lines().next().unwrap_or(text);
-    if first_line.len() > 100 {
-        format!("{}...", &first_line[..97])
+    if first_line.chars().count() > 100 {
+        format!("{}...", first_line.chars().take(97).collect::<String>())
     } else {
         first_line.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MetadataStore;
+
+    #[test]
+    fn test_compaction_becomes_system_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+        let lines = [
+            json!({ "type": "user", "uuid": "u1", "timestamp": "2025-03-10T09:00:00Z",
+                    "cwd": "/src/app", "message": { "role": "user", "content": "Fix the parser" } }),
+            json!({ "type": "assistant", "uuid": "a1", "timestamp": "2025-03-10T09:01:00Z",
+                    "message": { "role": "assistant", "model": "claude-sonnet-4",
+                                 "content": [{ "type": "text", "text": "Done." }],
+                                 "usage": { "input_tokens": 900, "output_tokens": 20 } } }),
+            // Current format: a boundary marker, then the summary opening the new context
+            json!({ "type": "system", "subtype": "compact_boundary", "uuid": "b1",
+                    "timestamp": "2025-03-10T09:02:00Z" }),
+            json!({ "type": "user", "uuid": "s1", "isCompactSummary": true,
+                    "timestamp": "2025-03-10T09:02:01Z",
+                    "message": { "role": "user", "content": "Summary: parser fixed" } }),
+            json!({ "type": "user", "uuid": "u2", "timestamp": "2025-03-10T09:03:00Z",
+                    "message": { "role": "user", "content": "Now the tests" } }),
+            // Older versions write only the summary
+            json!({ "type": "user", "uuid": "s2", "isCompactSummary": true,
+                    "timestamp": "2025-03-10T09:04:00Z",
+                    "message": { "role": "user", "content": "Summary: tests added" } }),
+        ];
+        let content: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let probe = ClaudeCodeProbe::new(Some(dir.path().to_path_buf()));
+        let session = SessionRef {
+            id: "s1".to_string(),
+            source_path: path.clone(),
+        };
+        let metadata = probe.extract_metadata(&session).unwrap();
+        assert_eq!(metadata.compactions, 2);
+        let roles: Vec<_> = metadata
+            .messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.subtype.as_deref()))
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                ("user", None),
+                ("assistant", None),
+                ("system", Some("compact")),
+                ("user", None),
+                ("system", Some("compact")),
+            ]
+        );
+        // The boundary's row reads the summary that follows it
+        let summary_offset: usize = content.lines().take(3).map(|l| l.len() + 1).sum();
+        assert_eq!(
+            metadata.messages[2].content_ref.byte_offset,
+            Some(summary_offset as u64)
+        );
+
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        let session_id = crate::store::fixtures::seed(&store, "claude:ClaudeCode", &metadata);
+        let rows = store.get_messages(&session_id).unwrap();
+        assert_eq!(
+            rows.iter()
+                .filter(|m| m.role == "system" && m.subtype.as_deref() == Some("compact"))
+                .count(),
+            2
+        );
+        let usage = store.context_usage(None).unwrap();
+        assert_eq!(usage[0].compactions, 2);
+        assert_eq!(store.compaction_stats(None).unwrap()[0].compactions, 2);
+    }
+
+    #[test]
+    fn test_project_metadata_follows_state_file() {
+        let home = tempfile::tempdir().unwrap();
+        let projects = home.path().join(".claude/projects");
+        std::fs::create_dir_all(&projects).unwrap();
+        let state_file = home.path().join(".claude.json");
+        std::fs::write(
+            &state_file,
+            json!({ "projects": { "/src/app": {
+                "hasTrustDialogAccepted": true, "allowedTools": ["Bash(cargo test)"]
+            } } })
+            .to_string(),
+        )
+        .unwrap();
+
+        let probe = ClaudeCodeProbe::new(Some(projects));
+        let settings = probe.project_metadata("/src/app").unwrap();
+        assert_eq!(settings["trusted"], json!(true));
+        assert_eq!(settings["allowed_tools"], json!(["Bash(cargo test)"]));
+        assert!(probe.project_metadata("/src/other").is_none());
+
+        // Served from the cache while unchanged, reparsed once the file changes
+        std::fs::write(
+            &state_file,
+            json!({ "projects": { "/src/app": { "hasTrustDialogAccepted": false } } }).to_string(),
+        )
+        .unwrap();
+        let settings = probe.project_metadata("/src/app").unwrap();
+        assert_eq!(settings["trusted"], json!(false));
+        assert_eq!(settings["allowed_tools"], json!([]));
+    }
+}
@@ -0,0 +1,481 @@
+//! Continue.dev probe implementation
+//!
+//! Extracts chat history from the Continue IDE extension.
+//! Data format: ~/.continue/sessions/
+//!   - <session-id>.json: one session (`history` of {message, promptLogs, toolCallState})
+//!   - sessions.json: index of {sessionId, title, dateCreated, workspaceDirectory}
+//!
+//! History items carry no timestamps; sessions are dated by their index entry's
+//! creation time and the session file's modification time.
+//!
+//! Continue is a multi-provider source: the model comes from each item's prompt log,
+//! falling back to the session's selected chat model.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::cursor::file_uri_to_path;
+use super::{
+    infer_provider, ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, RequestParams,
+    SessionMetadata, SessionRef, SkipCounts, SourceType, ToolUseMetadata,
+};
+
+/// Session index written next to the session files
+const INDEX_FILE: &str = "sessions.json";
+
+pub struct ContinueProbe {
+    base_path: PathBuf,
+}
+
+// Continue data structures (sessions/<id>.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    session_id: Option<String>,
+    title: Option<String>,
+    workspace_directory: Option<String>,
+    /// Title of the chat model selected when the session was saved
+    chat_model_title: Option<String>,
+    #[serde(default)]
+    history: Vec<HistoryItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryItem {
+    message: ChatMessage,
+    #[serde(default)]
+    prompt_logs: Vec<PromptLog>,
+    tool_call_state: Option<ToolCallState>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: Value,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+    /// Set on `tool` messages: the call this is the result of
+    tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    id: Option<String>,
+    function: Option<ToolFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolFunction {
+    name: Option<String>,
+    /// JSON-encoded arguments
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolCallState {
+    tool_call_id: Option<String>,
+    /// 'generating', 'generated', 'calling', 'done', 'errored' or 'canceled'
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptLog {
+    model_title: Option<String>,
+    completion_options: Option<CompletionOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionOptions {
+    model: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<i64>,
+}
+
+/// Entry of sessions.json
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    session_id: String,
+    /// Milliseconds since the epoch, as a number or a string
+    date_created: Option<Value>,
+    workspace_directory: Option<String>,
+}
+
+impl ContinueProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".continue/sessions")
+        });
+        Self { base_path }
+    }
+
+    fn read_session(path: &Path) -> Result<Session> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse Continue session JSON")
+    }
+
+    /// The session's entry in sessions.json, if the index lists it
+    fn index_entry(&self, session_id: &str) -> Option<IndexEntry> {
+        let content = std::fs::read_to_string(self.base_path.join(INDEX_FILE)).ok()?;
+        let entries: Vec<IndexEntry> = serde_json::from_str(&content).ok()?;
+        entries.into_iter().find(|e| e.session_id == session_id)
+    }
+
+    /// Normalize a history item into the content-array shape `read` understands
+    fn item_content(item: &HistoryItem) -> Value {
+        let mut items = vec![];
+        let text = message_text(&item.message.content);
+        match item.message.role.as_str() {
+            "thinking" => items.push(json!({ "type": "thinking", "thinking": text })),
+            _ if !text.is_empty() => items.push(json!({ "type": "text", "text": text })),
+            _ => {}
+        }
+        for call in &item.message.tool_calls {
+            let name = call.function.as_ref().and_then(|f| f.name.as_deref());
+            items.push(json!({ "type": "tool_use", "name": name }));
+        }
+        json!({ "content": items })
+    }
+}
+
+/// Plain text of a message's content (string or list of parts)
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn millis_to_datetime(value: &Value) -> Option<DateTime<Utc>> {
+    let ms = match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }?;
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+impl IngestionProbe for ContinueProbe {
+    fn id(&self) -> &str {
+        "continue:Continue"
+    }
+
+    fn provider(&self) -> &str {
+        "continue"
+    }
+
+    fn source(&self) -> &str {
+        "Continue"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "Continue.dev IDE extension (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Continue doesn't persist token usage
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.exists() {
+            return Ok(sessions);
+        }
+
+        for entry in std::fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            let is_session = path.extension().is_some_and(|ext| ext == "json")
+                && path.file_name().is_some_and(|n| n != INDEX_FILE);
+            if !is_session {
+                continue;
+            }
+            let id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            sessions.push(SessionRef {
+                id,
+                source_path: path,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let record = Self::read_session(&session.source_path)?;
+        let external_id = record.session_id.clone().unwrap_or(session.id.clone());
+        let index = self.index_entry(&external_id);
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = record.title.clone().filter(|t| !t.trim().is_empty());
+
+        // Results of tool calls: sent back as `tool` messages, and tracked on the
+        // assistant item's call state
+        let answered: BTreeSet<&str> = record
+            .history
+            .iter()
+            .filter_map(|item| item.message.tool_call_id.as_deref())
+            .collect();
+        let states: HashMap<&str, &str> = record
+            .history
+            .iter()
+            .filter_map(|item| item.tool_call_state.as_ref())
+            .filter_map(|s| Some((s.tool_call_id.as_deref()?, s.status.as_deref()?)))
+            .collect();
+
+        for (idx, item) in record.history.iter().enumerate() {
+            let msg = &item.message;
+            let role = match msg.role.as_str() {
+                "user" | "assistant" | "system" | "tool" => msg.role.as_str(),
+                // Reasoning is streamed as its own message ahead of the answer
+                "thinking" => "assistant",
+                other => {
+                    skipped.add(
+                        format!("unknown message role '{}'", other),
+                        format!("{} history item {}", session.source_path.display(), idx),
+                    );
+                    continue;
+                }
+            };
+
+            let text = message_text(&msg.content);
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+                if title.is_none() {
+                    title = text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            let log = item.prompt_logs.last();
+            let options = log.and_then(|l| l.completion_options.as_ref());
+            let model = (role == "assistant")
+                .then(|| {
+                    options
+                        .and_then(|o| o.model.clone())
+                        .or_else(|| log.and_then(|l| l.model_title.clone()))
+                        .or_else(|| record.chat_model_title.clone())
+                })
+                .flatten();
+            let provider_id = model.as_deref().and_then(infer_provider);
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    let id = call.id.as_deref();
+                    let status = id.and_then(|id| states.get(id)).copied();
+                    let arguments = call
+                        .function
+                        .as_ref()
+                        .and_then(|f| f.arguments.as_deref())
+                        .and_then(|a| serde_json::from_str::<Value>(a).ok());
+                    ToolUseMetadata {
+                        tool_id: call.id.clone(),
+                        tool_name: call
+                            .function
+                            .as_ref()
+                            .and_then(|f| f.name.clone())
+                            .unwrap_or_else(|| "unknown".to_string()),
+                        has_result: id.is_some_and(|id| answered.contains(id))
+                            || matches!(status, Some("done" | "errored")),
+                        is_error: status == Some("errored"),
+                        permission: None,
+                        input_hash: arguments.as_ref().map(loops::fingerprint),
+                        file_path: arguments.as_ref().and_then(files::touched_file),
+                    }
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: role.to_string(),
+                provider_id,
+                model,
+                timestamp: None,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: msg.role == "thinking",
+                tool_uses,
+                token_usage: None,
+                request_params: options
+                    .filter(|o| o.temperature.is_some() || o.max_tokens.is_some())
+                    .map(|o| RequestParams {
+                        temperature: o.temperature,
+                        max_tokens: o.max_tokens,
+                        system_prompt: None,
+                    }),
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = index
+            .as_ref()
+            .and_then(|e| e.date_created.as_ref())
+            .and_then(millis_to_datetime);
+        let last_timestamp = std::fs::metadata(&session.source_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from)
+            .or(first_timestamp);
+        let workspace = record
+            .workspace_directory
+            .or_else(|| index.and_then(|e| e.workspace_directory))
+            .filter(|w| !w.is_empty())
+            .map(|w| file_uri_to_path(&w));
+
+        Ok(SessionMetadata {
+            external_id,
+            title,
+            project_path: workspace,
+            git_remote: None,
+            source_group: None,
+            primary_provider: provider_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(provider, _)| provider),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let record = Self::read_session(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let item = record
+            .history
+            .get(index)
+            .context("Continue history index out of range")?;
+        Ok(Self::item_content(item).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_tool_calls_and_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = json!({
+            "sessionId": "c0ffee",
+            "title": "",
+            "workspaceDirectory": "file:///home/me/My%20App",
+            "chatModelTitle": "Claude 3.5 Sonnet",
+            "history": [
+                { "message": { "role": "user", "content": [{ "type": "text", "text": "Fix #12" }] } },
+                {
+                    "message": {
+                        "role": "assistant",
+                        "content": "Reading it",
+                        "toolCalls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "read_file", "arguments": "{\"filepath\":\"a.rs\"}" }
+                        }]
+                    },
+                    "promptLogs": [{ "completionOptions": { "model": "gpt-4o", "temperature": 0.2 } }],
+                    "toolCallState": { "toolCallId": "call_1", "status": "errored" }
+                },
+                { "message": { "role": "tool", "content": "no such file", "toolCallId": "call_1" } }
+            ]
+        });
+        let path = dir.path().join("c0ffee.json");
+        std::fs::write(&path, session.to_string()).unwrap();
+        std::fs::write(
+            dir.path().join(INDEX_FILE),
+            r#"[{"sessionId":"c0ffee","dateCreated":"1760000000000"}]"#,
+        )
+        .unwrap();
+
+        let probe = ContinueProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Fix #12"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/home/me/My App"));
+        assert_eq!(metadata.primary_model.as_deref(), Some("gpt-4o"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("openai"));
+        assert!(metadata.first_timestamp.is_some());
+        assert_eq!(metadata.messages.len(), 3);
+        let tool = &metadata.messages[1].tool_uses[0];
+        assert_eq!(tool.tool_name, "read_file");
+        assert!(tool.has_result && tool.is_error);
+
+        let content = probe
+            .get_content(&metadata.messages[1].content_ref)
+            .unwrap();
+        assert!(content.contains("Reading it") && content.contains("read_file"));
+    }
+}
@@ -0,0 +1,552 @@
+//! GitHub Copilot Chat probe implementation
+//!
+//! Extracts chat sessions from the Copilot Chat extension for VS Code.
+//! Data format (base path ~/Library/Application Support/Code/User):
+//!   - workspaceStorage/<hash>/chatSessions/<session-id>.json: one session
+//!     (`requests` of {message, response, result, modelId, timestamp})
+//!   - workspaceStorage/<hash>/workspace.json: workspace folder URI
+//!   - globalStorage/emptyWindowChatSessions/<session-id>.json: chats opened
+//!     without a folder
+//!
+//! Each request is a user prompt and the streamed response to it; both are indexed
+//! as messages, the prompt at `2 * index` and the response at `2 * index + 1`.
+//!
+//! Copilot is a multi-provider source: `modelId` names the model behind each
+//! request (`copilot/gpt-4o`, `copilot/claude-sonnet-4`, ...).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::permissions::Permission;
+use crate::analysis::{files, loops, references};
+
+use super::cursor::file_uri_to_path;
+use super::{
+    infer_provider, ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities,
+    SessionMetadata, SessionRef, SkipCounts, SourceType, ToolUseMetadata,
+};
+
+/// Sessions of a workspace, under workspaceStorage/<hash>/
+const SESSIONS_DIR: &str = "chatSessions";
+
+/// Sessions started without a folder open, under the base path
+const EMPTY_WINDOW_DIR: &str = "globalStorage/emptyWindowChatSessions";
+
+pub struct CopilotProbe {
+    base_path: PathBuf,
+}
+
+// Copilot Chat data structures (chatSessions/<id>.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatSession {
+    session_id: Option<String>,
+    custom_title: Option<String>,
+    /// Milliseconds since the epoch
+    creation_date: Option<i64>,
+    last_message_date: Option<i64>,
+    #[serde(default)]
+    requests: Vec<ChatRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatRequest {
+    message: Option<RequestMessage>,
+    /// Streamed response parts: markdown, tool invocations, references, edits
+    #[serde(default)]
+    response: Vec<Value>,
+    result: Option<RequestResult>,
+    model_id: Option<String>,
+    /// Milliseconds since the epoch
+    timestamp: Option<i64>,
+    #[serde(default)]
+    is_canceled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestMessage {
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestResult {
+    timings: Option<Timings>,
+    error_details: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Timings {
+    /// Milliseconds from the request to the end of the response
+    total_elapsed: Option<i64>,
+}
+
+/// workspaceStorage/<hash>/workspace.json
+#[derive(Debug, Deserialize)]
+struct WorkspaceFile {
+    folder: Option<String>,
+    /// A .code-workspace file, for multi-root workspaces
+    workspace: Option<String>,
+}
+
+impl CopilotProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join("Library/Application Support/Code/User")
+        });
+        Self { base_path }
+    }
+
+    fn read_session(path: &Path) -> Result<ChatSession> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse Copilot chat session JSON")
+    }
+
+    /// Directories holding session files: one per workspace, plus empty-window chats
+    fn session_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = vec![];
+        let workspaces = self.base_path.join("workspaceStorage");
+        if workspaces.is_dir() {
+            for entry in std::fs::read_dir(&workspaces)? {
+                let dir = entry?.path().join(SESSIONS_DIR);
+                if dir.is_dir() {
+                    dirs.push(dir);
+                }
+            }
+        }
+        let empty_window = self.base_path.join(EMPTY_WINDOW_DIR);
+        if empty_window.is_dir() {
+            dirs.push(empty_window);
+        }
+        Ok(dirs)
+    }
+
+    /// Workspace storage directory (<hash>/) of a session file, if it has one
+    fn workspace_dir(session_path: &Path) -> Option<&Path> {
+        let dir = session_path.parent()?;
+        if dir.file_name()? != SESSIONS_DIR {
+            return None;
+        }
+        dir.parent()
+    }
+
+    /// Folder the workspace was opened on, from its workspace.json
+    fn workspace_folder(workspace_dir: &Path) -> Option<String> {
+        let content = std::fs::read_to_string(workspace_dir.join("workspace.json")).ok()?;
+        let file: WorkspaceFile = serde_json::from_str(&content).ok()?;
+        if let Some(folder) = file.folder {
+            return Some(file_uri_to_path(&folder));
+        }
+        // Multi-root workspaces: the directory holding the .code-workspace file
+        let workspace = PathBuf::from(file_uri_to_path(&file.workspace?));
+        Some(workspace.parent()?.to_string_lossy().to_string())
+    }
+
+    /// Normalize one side of a request into the content-array shape `read` understands
+    fn message_content(request: &ChatRequest, assistant: bool) -> Value {
+        let mut items = vec![];
+        if !assistant {
+            let text = request_text(request);
+            if !text.is_empty() {
+                items.push(json!({ "type": "text", "text": text }));
+            }
+            return json!({ "content": items });
+        }
+        for part in &request.response {
+            match part.get("kind").and_then(|k| k.as_str()) {
+                Some("toolInvocationSerialized") => {
+                    items.push(json!({ "type": "tool_use", "name": tool_name(part) }));
+                }
+                Some("thinking") => {
+                    if let Some(text) = part.get("value").and_then(|v| v.as_str()) {
+                        items.push(json!({ "type": "thinking", "thinking": text }));
+                    }
+                }
+                _ => {
+                    if let Some(text) = markdown(part) {
+                        // Consecutive markdown parts are one streamed answer
+                        match items.last_mut() {
+                            Some(last) if last["type"] == "text" => {
+                                let joined = format!("{}{}", last["text"].as_str().unwrap(), text);
+                                last["text"] = Value::String(joined);
+                            }
+                            _ => items.push(json!({ "type": "text", "text": text })),
+                        }
+                    }
+                }
+            }
+        }
+        json!({ "content": items })
+    }
+}
+
+fn request_text(request: &ChatRequest) -> String {
+    request
+        .message
+        .as_ref()
+        .and_then(|m| m.text.clone())
+        .unwrap_or_default()
+}
+
+/// Markdown text of a response part: a bare `{value}` or a `markdownContent` part
+fn markdown(part: &Value) -> Option<&str> {
+    match part.get("kind").and_then(|k| k.as_str()) {
+        None => part.get("value")?.as_str(),
+        Some("markdownContent") => part.get("content")?.get("value")?.as_str(),
+        Some(_) => None,
+    }
+}
+
+fn tool_name(part: &Value) -> &str {
+    part.get("toolId")
+        .and_then(|t| t.as_str())
+        .unwrap_or("unknown")
+}
+
+/// Model name without the `copilot/` vendor prefix
+fn model_name(model_id: &str) -> String {
+    model_id
+        .rsplit_once('/')
+        .map(|(_, model)| model)
+        .unwrap_or(model_id)
+        .to_string()
+}
+
+fn millis_to_datetime(ms: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+impl IngestionProbe for CopilotProbe {
+    fn id(&self) -> &str {
+        "copilot:Copilot"
+    }
+
+    fn provider(&self) -> &str {
+        "copilot"
+    }
+
+    fn source(&self) -> &str {
+        "Copilot"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "GitHub Copilot Chat in VS Code (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Copilot Chat doesn't persist token usage
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.is_available() {
+            return Ok(sessions);
+        }
+
+        for dir in self.session_dirs()? {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                let id = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                sessions.push(SessionRef {
+                    id,
+                    source_path: path,
+                });
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let record = Self::read_session(&session.source_path)?;
+        let workspace_dir = Self::workspace_dir(&session.source_path);
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = record.custom_title.clone().filter(|t| !t.trim().is_empty());
+
+        for (idx, request) in record.requests.iter().enumerate() {
+            let text = request_text(request);
+            if text.trim().is_empty() && request.response.is_empty() {
+                skipped.add(
+                    "empty request",
+                    format!("{} request {}", session.source_path.display(), idx),
+                );
+                continue;
+            }
+            session_refs.extend(references::detect(&text));
+            language.add(&text);
+            if title.is_none() {
+                title = text
+                    .lines()
+                    .find(|l| !l.trim().is_empty())
+                    .map(|l| l.trim().to_string());
+            }
+
+            let model = request.model_id.as_deref().map(model_name);
+            let provider_id = model.as_deref().and_then(infer_provider);
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+
+            let asked_at = request.timestamp.and_then(millis_to_datetime);
+            let answered_at = request
+                .timestamp
+                .zip(
+                    request
+                        .result
+                        .as_ref()
+                        .and_then(|r| r.timings.as_ref())
+                        .and_then(|t| t.total_elapsed),
+                )
+                .and_then(|(start, elapsed)| millis_to_datetime(start + elapsed))
+                .or(asked_at);
+            let content_ref = |line: usize| ContentRef {
+                source_path: session.source_path.clone(),
+                byte_offset: None,
+                line_number: Some(line as u32),
+                content_path: None,
+            };
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: "user".to_string(),
+                provider_id: None,
+                model: None,
+                timestamp: asked_at,
+                content_ref: content_ref(2 * idx),
+                has_tool_use: false,
+                has_thinking: false,
+                tool_uses: vec![],
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+
+            let failed = request.is_canceled
+                || request
+                    .result
+                    .as_ref()
+                    .is_some_and(|r| r.error_details.is_some());
+            let tool_uses: Vec<ToolUseMetadata> = request
+                .response
+                .iter()
+                .filter(|p| {
+                    p.get("kind").and_then(|k| k.as_str()) == Some("toolInvocationSerialized")
+                })
+                .map(|part| {
+                    let complete = part.get("isComplete").and_then(|c| c.as_bool());
+                    // Calls the user declined in the confirmation prompt
+                    let declined = part.get("isConfirmed").and_then(|c| c.as_bool()) == Some(false);
+                    ToolUseMetadata {
+                        tool_id: part
+                            .get("toolCallId")
+                            .and_then(|t| t.as_str())
+                            .map(String::from),
+                        tool_name: tool_name(part).to_string(),
+                        has_result: complete.unwrap_or(!failed) && !declined,
+                        is_error: false,
+                        permission: declined.then_some(Permission::Denied),
+                        input_hash: part.get("toolSpecificData").map(loops::fingerprint),
+                        file_path: part.get("toolSpecificData").and_then(files::touched_file),
+                    }
+                })
+                .collect();
+            let has_thinking = request
+                .response
+                .iter()
+                .any(|p| p.get("kind").and_then(|k| k.as_str()) == Some("thinking"));
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: "assistant".to_string(),
+                provider_id,
+                model,
+                timestamp: answered_at,
+                content_ref: content_ref(2 * idx + 1),
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking,
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = record
+            .creation_date
+            .and_then(millis_to_datetime)
+            .or_else(|| messages.first().and_then(|m| m.timestamp));
+        let last_timestamp = record
+            .last_message_date
+            .and_then(millis_to_datetime)
+            .or_else(|| messages.last().and_then(|m| m.timestamp))
+            .or(first_timestamp);
+
+        Ok(SessionMetadata {
+            external_id: record.session_id.clone().unwrap_or(session.id.clone()),
+            title,
+            project_path: workspace_dir.and_then(Self::workspace_folder),
+            git_remote: None,
+            source_group: workspace_dir
+                .and_then(|d| d.file_name())
+                .map(|n| n.to_string_lossy().to_string()),
+            primary_provider: provider_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(provider, _)| provider),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let record = Self::read_session(&reference.source_path)?;
+        let line = reference.line_number.unwrap_or(0) as usize;
+        let request = record
+            .requests
+            .get(line / 2)
+            .context("Copilot request index out of range")?;
+        Ok(Self::message_content(request, line % 2 == 1).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_requests_and_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("workspaceStorage/5f1e0a");
+        std::fs::create_dir_all(workspace.join(SESSIONS_DIR)).unwrap();
+        std::fs::write(
+            workspace.join("workspace.json"),
+            r#"{"folder":"file:///home/me/My%20App"}"#,
+        )
+        .unwrap();
+        let session = json!({
+            "version": 3,
+            "sessionId": "7c0b",
+            "creationDate": 1760000000000i64,
+            "lastMessageDate": 1760000090000i64,
+            "requests": [
+                {
+                    "message": { "text": "Why does #42 fail?" },
+                    "response": [
+                        { "value": "Let me look", "supportThemeIcons": false },
+                        {
+                            "kind": "toolInvocationSerialized",
+                            "toolId": "copilot_readFile",
+                            "toolCallId": "t1",
+                            "isComplete": true,
+                            "isConfirmed": true
+                        },
+                        { "kind": "markdownContent", "content": { "value": " — it's the lexer." } }
+                    ],
+                    "result": { "timings": { "totalElapsed": 4000 } },
+                    "modelId": "copilot/claude-sonnet-4",
+                    "timestamp": 1760000000000i64
+                },
+                {
+                    "message": { "text": "Run the tests" },
+                    "response": [{
+                        "kind": "toolInvocationSerialized",
+                        "toolId": "copilot_runInTerminal",
+                        "toolCallId": "t2",
+                        "isComplete": true,
+                        "isConfirmed": false
+                    }],
+                    "modelId": "copilot/gpt-4o",
+                    "timestamp": 1760000060000i64
+                }
+            ]
+        });
+        std::fs::write(
+            workspace.join(SESSIONS_DIR).join("7c0b.json"),
+            session.to_string(),
+        )
+        .unwrap();
+
+        let probe = CopilotProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Why does #42 fail?"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/home/me/My App"));
+        assert_eq!(metadata.source_group.as_deref(), Some("5f1e0a"));
+        assert_eq!(metadata.messages.len(), 4);
+        let answer = &metadata.messages[1];
+        assert_eq!(answer.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(answer.provider_id.as_deref(), Some("anthropic"));
+        assert_eq!(
+            (answer.timestamp.unwrap() - metadata.messages[0].timestamp.unwrap()).num_seconds(),
+            4
+        );
+        assert!(answer.tool_uses[0].has_result);
+        let declined = &metadata.messages[3].tool_uses[0];
+        assert!(!declined.has_result);
+        assert_eq!(declined.permission, Some(Permission::Denied));
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("Let me look") && content.contains("it's the lexer"));
+        assert!(content.contains("copilot_readFile"));
+        let prompt = probe
+            .get_content(&metadata.messages[2].content_ref)
+            .unwrap();
+        assert!(prompt.contains("Run the tests"));
+    }
+}
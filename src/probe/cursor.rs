@@ -0,0 +1,653 @@
+//! Cursor IDE probe implementation
+//!
+//! Extracts Composer/Agent chat history from Cursor's VS Code-style state databases.
+//! Data format (base path ~/Library/Application Support/Cursor/User):
+//!   - globalStorage/state.vscdb: `cursorDiskKV` table with JSON values
+//!     - `composerData:<composerId>` session header (name, timestamps, bubble list)
+//!     - `bubbleId:<composerId>:<bubbleId>` individual messages (newer Cursor versions)
+//!   - workspaceStorage/<hash>/workspace.json: workspace folder URI
+//!   - workspaceStorage/<hash>/state.vscdb: `ItemTable` key `composer.composerData`
+//!     lists the composers opened in that workspace
+//!
+//! Older Cursor versions inline messages in `composerData.conversation` instead of
+//! separate bubble rows; both layouts are supported.
+//!
+//! Cursor is a multi-provider source (Anthropic, OpenAI, Google and its own models).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::{
+    infer_provider, ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities,
+    SessionMetadata, SessionRef, SkipCounts, SourceType, TokenUsage, ToolUseMetadata,
+};
+
+pub struct CursorProbe {
+    base_path: PathBuf,
+    /// Composer ID -> workspace folder, rebuilt by every `discover`
+    folders: Mutex<Option<HashMap<String, String>>>,
+}
+
+// Cursor data structures (from cursorDiskKV JSON values)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ComposerData {
+    name: Option<String>,
+    created_at: Option<i64>,
+    last_updated_at: Option<i64>,
+    #[serde(default)]
+    full_conversation_headers_only: Vec<BubbleHeader>,
+    #[serde(default)]
+    conversation: Vec<Bubble>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BubbleHeader {
+    bubble_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bubble {
+    bubble_id: Option<String>,
+    /// 1 = user, 2 = assistant
+    #[serde(rename = "type")]
+    bubble_type: Option<i64>,
+    text: Option<String>,
+    thinking: Option<Value>,
+    tool_former_data: Option<ToolFormerData>,
+    token_count: Option<TokenCount>,
+    model_info: Option<ModelInfo>,
+    created_at: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolFormerData {
+    tool_call_id: Option<String>,
+    name: Option<String>,
+    raw_args: Option<String>,
+    result: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenCount {
+    input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelInfo {
+    model_name: Option<String>,
+}
+
+impl CursorProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join("Library/Application Support/Cursor/User")
+        });
+        Self {
+            base_path,
+            folders: Mutex::new(None),
+        }
+    }
+
+    fn global_db_path(&self) -> PathBuf {
+        self.base_path.join("globalStorage/state.vscdb")
+    }
+
+    /// Open a state database in read-only mode
+    fn open_db(path: &Path) -> Result<Connection> {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open Cursor database {}", path.display()))
+    }
+
+    /// Fetch and parse a JSON value from the global key-value table
+    fn get_value(conn: &Connection, key: &str) -> Result<Option<Value>> {
+        let raw: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT CAST(value AS BLOB) FROM cursorDiskKV WHERE key = ?",
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match raw {
+            Some(bytes) if !bytes.is_empty() => Ok(Some(serde_json::from_slice(&bytes)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Workspace folder a composer was opened in. Reading every workspace database is
+    /// costly, so the map is built once per run and shared by all composers.
+    fn workspace_folder(&self, composer_id: &str) -> Option<String> {
+        self.folders
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| self.workspace_folders())
+            .get(composer_id)
+            .cloned()
+    }
+
+    /// Map composer IDs to workspace folders using per-workspace state
+    fn workspace_folders(&self) -> HashMap<String, String> {
+        let mut folders = HashMap::new();
+        let Ok(entries) = std::fs::read_dir(self.base_path.join("workspaceStorage")) else {
+            return folders;
+        };
+
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let Some(folder) = std::fs::read_to_string(dir.join("workspace.json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                .and_then(|v| {
+                    v.get("folder")
+                        .and_then(|f| f.as_str())
+                        .map(file_uri_to_path)
+                })
+            else {
+                continue;
+            };
+
+            let Ok(conn) = Self::open_db(&dir.join("state.vscdb")) else {
+                continue;
+            };
+            let data: Option<String> = conn
+                .query_row(
+                    "SELECT value FROM ItemTable WHERE key = 'composer.composerData'",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()
+                .ok()
+                .flatten();
+
+            let composers = data
+                .and_then(|d| serde_json::from_str::<Value>(&d).ok())
+                .and_then(|v| v.get("allComposers").and_then(|a| a.as_array()).cloned())
+                .unwrap_or_default();
+            for composer in composers {
+                if let Some(id) = composer.get("composerId").and_then(|c| c.as_str()) {
+                    folders.insert(id.to_string(), folder.clone());
+                }
+            }
+        }
+
+        folders
+    }
+
+    /// Load the ordered bubbles of a composer with the key each one can be re-read from
+    fn load_bubbles(
+        conn: &Connection,
+        composer_id: &str,
+        composer: ComposerData,
+    ) -> Result<Vec<(String, Bubble)>> {
+        if !composer.conversation.is_empty() {
+            let key = format!("composerData:{}", composer_id);
+            return Ok(composer
+                .conversation
+                .into_iter()
+                .map(|bubble| (key.clone(), bubble))
+                .collect());
+        }
+
+        let mut bubbles = vec![];
+        for header in composer.full_conversation_headers_only {
+            let key = format!("bubbleId:{}:{}", composer_id, header.bubble_id);
+            if let Some(value) = Self::get_value(conn, &key)? {
+                bubbles.push((key, serde_json::from_value(value)?));
+            }
+        }
+        Ok(bubbles)
+    }
+}
+
+/// Convert a `file://` URI to a filesystem path
+pub(super) fn file_uri_to_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    percent_decode(path)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn millis_to_datetime(ms: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+/// Bubble timestamps are ISO strings in newer versions and epoch millis in older ones
+fn parse_bubble_time(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc)),
+        Value::Number(n) => n.as_i64().and_then(millis_to_datetime),
+        _ => None,
+    }
+}
+
+impl IngestionProbe for CursorProbe {
+    fn id(&self) -> &str {
+        "cursor:Cursor"
+    }
+
+    fn provider(&self) -> &str {
+        "cursor"
+    }
+
+    fn source(&self) -> &str {
+        "Cursor"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "Cursor IDE Composer/Agent chats (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.global_db_path().exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Every composer lives in one state database, so any change re-reads them all
+        ProbeCapabilities {
+            supports_token_usage: true,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: false,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.is_available() {
+            return Ok(sessions);
+        }
+
+        // Workspaces opened since the last run may have new composers
+        *self.folders.lock().unwrap() = Some(self.workspace_folders());
+
+        let db_path = self.global_db_path();
+        let conn = Self::open_db(&db_path)?;
+        let mut stmt =
+            conn.prepare("SELECT key FROM cursorDiskKV WHERE key LIKE 'composerData:%'")?;
+
+        let rows = stmt.query_map([], |row| {
+            let key: String = row.get(0)?;
+            Ok(key)
+        })?;
+
+        for row in rows {
+            let key = row?;
+            if let Some(id) = key.strip_prefix("composerData:") {
+                sessions.push(SessionRef {
+                    id: id.to_string(),
+                    source_path: db_path.clone(),
+                });
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let conn = Self::open_db(&session.source_path)?;
+
+        let value = Self::get_value(&conn, &format!("composerData:{}", session.id))?
+            .context("Composer data not found")?;
+        let composer: ComposerData =
+            serde_json::from_value(value).context("Failed to parse composer data")?;
+
+        let title = composer.name.clone().filter(|t| !t.is_empty());
+        let created_at = composer.created_at.and_then(millis_to_datetime);
+        let updated_at = composer.last_updated_at.and_then(millis_to_datetime);
+        let bubbles = Self::load_bubbles(&conn, &session.id, composer)?;
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut first_timestamp: Option<DateTime<Utc>> = None;
+        let mut last_timestamp: Option<DateTime<Utc>> = None;
+
+        if let Some(ref title) = title {
+            session_refs.extend(references::detect(title));
+        }
+
+        for (idx, (key, bubble)) in bubbles.iter().enumerate() {
+            let role = match bubble.bubble_type {
+                Some(1) => "user",
+                Some(2) => "assistant",
+                other => {
+                    let reason = match other {
+                        Some(kind) => format!("unknown bubble type {}", kind),
+                        None => "bubble without type".to_string(),
+                    };
+                    skipped.add(
+                        reason,
+                        format!("{} [{}]", session.source_path.display(), key),
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(ref text) = bubble.text {
+                session_refs.extend(references::detect(text));
+                if role == "user" {
+                    language.add(text);
+                }
+            }
+
+            let timestamp = bubble.created_at.as_ref().and_then(parse_bubble_time);
+            if let Some(ts) = timestamp {
+                first_timestamp = Some(first_timestamp.map_or(ts, |f| f.min(ts)));
+                last_timestamp = Some(last_timestamp.map_or(ts, |l| l.max(ts)));
+            }
+
+            let model = bubble
+                .model_info
+                .as_ref()
+                .and_then(|m| m.model_name.clone())
+                .filter(|m| !m.is_empty() && role == "assistant");
+            let provider_id = model
+                .as_deref()
+                .map(|m| infer_provider(m).unwrap_or_else(|| "cursor".to_string()));
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = bubble
+                .tool_former_data
+                .iter()
+                .map(|tool| ToolUseMetadata {
+                    tool_id: tool.tool_call_id.clone(),
+                    tool_name: tool.name.clone().unwrap_or_else(|| "unknown".to_string()),
+                    has_result: tool.result.as_ref().is_some_and(|r| !r.is_null()),
+                    is_error: false,
+                    permission: None,
+                    input_hash: tool.raw_args.as_ref().map(|args| {
+                        let input = serde_json::from_str(args)
+                            .unwrap_or_else(|_| Value::String(args.clone()));
+                        loops::fingerprint(&input)
+                    }),
+                    file_path: tool
+                        .raw_args
+                        .as_ref()
+                        .and_then(|args| files::touched_file(&Value::String(args.clone()))),
+                })
+                .collect();
+
+            let token_usage = bubble
+                .token_count
+                .as_ref()
+                .filter(|t| t.input_tokens.unwrap_or(0) > 0 || t.output_tokens.unwrap_or(0) > 0)
+                .map(|t| TokenUsage {
+                    input_tokens: t.input_tokens,
+                    output_tokens: t.output_tokens,
+                    cache_read_tokens: None,
+                    cache_creation_tokens: None,
+                    reasoning_tokens: None,
+                });
+
+            messages.push(MessageMetadata {
+                uuid: bubble.bubble_id.clone(),
+                role: role.to_string(),
+                provider_id,
+                model,
+                timestamp,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: Some(PathBuf::from(key)),
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: bubble.thinking.as_ref().is_some_and(|t| !t.is_null()),
+                tool_uses,
+                token_usage,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let primary_provider = provider_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(provider, _)| provider);
+
+        let primary_model = model_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(model, _)| model);
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title,
+            project_path: self.workspace_folder(&session.id),
+            git_remote: None,
+            source_group: None,
+            primary_provider,
+            primary_model,
+            first_timestamp: first_timestamp.or(created_at),
+            last_timestamp: updated_at.or(last_timestamp),
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let conn = Self::open_db(&reference.source_path)?;
+        let key = reference
+            .content_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .context("Missing Cursor content key")?;
+
+        let value = Self::get_value(&conn, key)?.context("Cursor message not found")?;
+
+        // Inline layout: the key points at the composer, line_number at the bubble
+        let bubble = if key.starts_with("composerData:") {
+            let index = reference.line_number.unwrap_or(0) as usize;
+            value
+                .get("conversation")
+                .and_then(|c| c.get(index))
+                .cloned()
+                .context("Cursor message index out of range")?
+        } else {
+            value
+        };
+        let bubble: Bubble = serde_json::from_value(bubble)?;
+
+        let mut parts = vec![];
+        if let Some(text) = bubble.text.filter(|t| !t.is_empty()) {
+            parts.push(text);
+        }
+        if let Some(tool) = bubble.tool_former_data {
+            parts.push(format!(
+                "  🔧 [Tool: {}]",
+                tool.name.as_deref().unwrap_or("unknown")
+            ));
+        }
+        Ok(parts.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn put(conn: &Connection, key: &str, value: Value) {
+        conn.execute(
+            "INSERT INTO cursorDiskKV (key, value) VALUES (?, ?)",
+            rusqlite::params![key, value.to_string()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_extracts_composers_with_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("globalStorage")).unwrap();
+        let global = Connection::open(dir.path().join("globalStorage/state.vscdb")).unwrap();
+        global
+            .execute_batch(
+                "CREATE TABLE cursorDiskKV (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)",
+            )
+            .unwrap();
+
+        // Bubble layout: the composer lists its bubbles, stored under their own keys
+        put(
+            &global,
+            "composerData:c1",
+            json!({
+                "name": "Fix #12",
+                "createdAt": 1760000000000i64,
+                "lastUpdatedAt": 1760000090000i64,
+                "fullConversationHeadersOnly": [{ "bubbleId": "b1" }, { "bubbleId": "b2" }]
+            }),
+        );
+        put(
+            &global,
+            "bubbleId:c1:b1",
+            json!({ "bubbleId": "b1", "type": 1, "text": "Why does the parser fail?",
+                    "createdAt": "2025-10-09T09:00:00Z" }),
+        );
+        put(
+            &global,
+            "bubbleId:c1:b2",
+            json!({
+                "bubbleId": "b2", "type": 2, "text": "Reading it",
+                "createdAt": "2025-10-09T09:00:30Z",
+                "modelInfo": { "modelName": "claude-4-sonnet" },
+                "tokenCount": { "inputTokens": 1200, "outputTokens": 80 },
+                "toolFormerData": { "toolCallId": "t1", "name": "read_file",
+                                    "rawArgs": "{\"target_file\":\"src/parser.rs\"}",
+                                    "result": "fn parse() {}" }
+            }),
+        );
+        // Inline layout of older versions
+        put(
+            &global,
+            "composerData:c2",
+            json!({
+                "name": "",
+                "conversation": [
+                    { "type": 1, "text": "hello" },
+                    { "type": 2, "text": "hi there" },
+                    { "type": 3 }
+                ]
+            }),
+        );
+
+        let workspace = dir.path().join("workspaceStorage/9a8b");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(
+            workspace.join("workspace.json"),
+            r#"{"folder":"file:///home/me/My%20App"}"#,
+        )
+        .unwrap();
+        let state = Connection::open(workspace.join("state.vscdb")).unwrap();
+        state
+            .execute_batch("CREATE TABLE ItemTable (key TEXT UNIQUE, value BLOB)")
+            .unwrap();
+        state
+            .execute(
+                "INSERT INTO ItemTable VALUES ('composer.composerData', ?)",
+                [json!({ "allComposers": [{ "composerId": "c1" }] }).to_string()],
+            )
+            .unwrap();
+
+        let probe = CursorProbe::new(Some(dir.path().to_path_buf()));
+        assert!(probe.is_available());
+        let mut sessions = probe.discover().unwrap();
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(sessions.len(), 2);
+
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Fix #12"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/home/me/My App"));
+        assert_eq!(metadata.references.len(), 1);
+        assert_eq!(metadata.messages.len(), 2);
+        assert_eq!(metadata.primary_model.as_deref(), Some("claude-4-sonnet"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("anthropic"));
+        let answer = &metadata.messages[1];
+        assert_eq!(
+            answer.token_usage.as_ref().unwrap().input_tokens,
+            Some(1200)
+        );
+        assert_eq!(answer.tool_uses[0].tool_name, "read_file");
+        assert!(answer.tool_uses[0].has_result);
+        assert_eq!(
+            answer.tool_uses[0].file_path.as_deref(),
+            Some("src/parser.rs")
+        );
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert_eq!(content, "Reading it\n  🔧 [Tool: read_file]");
+
+        let inline = probe.extract_metadata(&sessions[1]).unwrap();
+        assert_eq!(inline.title, None);
+        assert_eq!(inline.project_path, None);
+        assert_eq!(inline.messages.len(), 2);
+        assert_eq!(inline.skipped.total(), 1);
+        assert_eq!(
+            probe.get_content(&inline.messages[1].content_ref).unwrap(),
+            "hi there"
+        );
+    }
+
+    #[test]
+    fn test_file_uri_to_path() {
+        assert_eq!(
+            file_uri_to_path("file:///Users/me/My%20Project"),
+            "/Users/me/My Project"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Custom JSONL probe
+//!
+//! Reads logs of tools Chronicle has no built-in probe for, as declared in the config:
+//!
+//! ```yaml
+//! probes:
+//!   acme:AcmeAgent:
+//!     base_path: ~/.acme/logs
+//!     custom:
+//!       glob: "**/*.jsonl"
+//!       session: conversation_id
+//!       role: message.role
+//!       content: message.text
+//!       timestamp: ts
+//!       model: meta.model
+//! ```
+//!
+//! Each line of a matching file is one message. Sessions are whole files, or the runs
+//! of lines sharing a `session` value within a file. The probe ID's prefix (`acme`)
+//! is recorded as the provider; a custom probe can't tell which vendor served a model.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::references;
+use crate::config::CustomProbeConfig;
+use crate::error::Error;
+
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType,
+};
+
+pub struct CustomProbe {
+    id: String,
+    provider: String,
+    source: String,
+    base_path: PathBuf,
+    pattern: glob::Pattern,
+    config: CustomProbeConfig,
+}
+
+impl CustomProbe {
+    pub fn new(id: &str, base_path: Option<PathBuf>, config: CustomProbeConfig) -> Result<Self> {
+        let (provider, source) = id
+            .split_once(':')
+            .filter(|(p, s)| !p.is_empty() && !s.is_empty())
+            .with_context(|| format!("Custom probe ID '{}' must be provider:Source", id))?;
+        let base_path = base_path.context("Custom probes need a base_path")?;
+        let pattern = glob::Pattern::new(&config.glob)
+            .with_context(|| format!("Invalid glob '{}'", config.glob))?;
+        Ok(Self {
+            id: id.to_string(),
+            provider: provider.to_string(),
+            source: source.to_string(),
+            base_path,
+            pattern,
+            config,
+        })
+    }
+
+    /// Files matching the glob
+    fn files(&self) -> Vec<PathBuf> {
+        WalkDir::new(&self.base_path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path()
+                    .strip_prefix(&self.base_path)
+                    .is_ok_and(|rel| self.pattern.matches_path(rel))
+            })
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// Session ID of a whole file: its path under base_path without the extension
+    fn file_session_id(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.base_path).unwrap_or(path);
+        relative
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Each parsed line of a file with its byte offset and line number
+    fn lines(path: &Path) -> Result<Vec<(u64, u32, Option<Value>)>> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut lines = vec![];
+        let (mut offset, mut number) = (0u64, 0u32);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            if !line.trim().is_empty() {
+                lines.push((offset, number, serde_json::from_str(&line).ok()));
+            }
+            offset += read as u64;
+            number += 1;
+        }
+        Ok(lines)
+    }
+
+    fn field<'a>(&self, line: &'a Value, path: Option<&str>) -> Option<&'a Value> {
+        lookup(line, path?)
+    }
+
+    fn text_field(&self, line: &Value, path: Option<&str>) -> Option<String> {
+        match self.field(line, path)? {
+            Value::String(s) if !s.is_empty() => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Role of a line mapped to Chronicle's roles
+    fn role(&self, line: &Value) -> Option<String> {
+        let raw = self.text_field(line, Some(&self.config.role))?;
+        let role = self.config.roles.get(&raw).cloned().unwrap_or(raw);
+        matches!(role.as_str(), "user" | "assistant" | "system" | "tool").then_some(role)
+    }
+}
+
+/// Value at a dotted path; numeric segments index arrays
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+/// Plain text of a content value (string or list of parts)
+fn content_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| {
+                p.as_str()
+                    .or_else(|| p.get("text").and_then(|t| t.as_str()))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// RFC 3339 text, or seconds or milliseconds since the epoch
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)),
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            // Anything past 1e11 seconds (year 5138) must be milliseconds
+            let ms = if n > 1e11 { n } else { n * 1000.0 };
+            Utc.timestamp_millis_opt(ms as i64).single()
+        }
+        _ => None,
+    }
+}
+
+impl IngestionProbe for CustomProbe {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "Custom JSONL source (configured)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: false,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+        for path in self.files() {
+            let Some(field) = self.config.session.as_deref() else {
+                sessions.push(SessionRef {
+                    id: self.file_session_id(&path),
+                    source_path: path,
+                });
+                continue;
+            };
+            let mut seen = BTreeSet::new();
+            for (_, _, line) in Self::lines(&path)? {
+                let id = line.as_ref().and_then(|l| self.text_field(l, Some(field)));
+                if let Some(id) = id.filter(|id| seen.insert(id.clone())) {
+                    sessions.push(SessionRef {
+                        id,
+                        source_path: path.clone(),
+                    });
+                }
+            }
+        }
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let config = &self.config;
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = None;
+        let mut cwd = None;
+        let mut first_user = None;
+
+        for (offset, number, line) in Self::lines(&session.source_path)? {
+            let location = || format!("{}:{}", session.source_path.display(), number + 1);
+            let Some(line) = line else {
+                skipped.add("invalid JSON", location());
+                continue;
+            };
+            if let Some(field) = config.session.as_deref() {
+                if self.text_field(&line, Some(field)).as_deref() != Some(session.id.as_str()) {
+                    continue;
+                }
+            }
+            let Some(role) = self.role(&line) else {
+                skipped.add("missing or unknown role", location());
+                continue;
+            };
+
+            let text = content_text(self.field(&line, Some(&config.content)));
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+                if first_user.is_none() {
+                    first_user = text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+            title = title.or_else(|| self.text_field(&line, config.title.as_deref()));
+            cwd = cwd.or_else(|| self.text_field(&line, config.cwd.as_deref()));
+
+            let model = (role == "assistant")
+                .then(|| self.text_field(&line, config.model.as_deref()))
+                .flatten();
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role,
+                provider_id: model.as_ref().map(|_| self.provider.clone()),
+                model,
+                timestamp: self
+                    .field(&line, config.timestamp.as_deref())
+                    .and_then(parse_time),
+                content_ref: ContentRef::jsonl(session.source_path.clone(), offset, number),
+                has_tool_use: false,
+                has_thinking: false,
+                tool_uses: vec![],
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = messages.iter().find_map(|m| m.timestamp);
+        let last_timestamp = messages.iter().rev().find_map(|m| m.timestamp).or_else(|| {
+            std::fs::metadata(&session.source_path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from)
+        });
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title: title.or(first_user),
+            project_path: cwd,
+            git_remote: None,
+            source_group: None,
+            primary_provider: Some(self.provider.clone()),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let mut file = File::open(&reference.source_path)?;
+        file.seek(SeekFrom::Start(reference.byte_offset.unwrap_or(0)))?;
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        let value: Value = serde_json::from_str(&line).map_err(|e| Error::ParseError {
+            path: reference.source_path.clone(),
+            line: reference.line_number.map(|n| n as usize),
+            reason: format!("Invalid JSON line: {}", e),
+        })?;
+        let text = content_text(self.field(&value, Some(&self.config.content)));
+        Ok(json!({ "content": [{ "type": "text", "text": text }] }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_field_mappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("2025");
+        std::fs::create_dir_all(&logs).unwrap();
+        let lines = [
+            r#"{"conv":"a1","ts":1760000000,"msg":{"who":"human","parts":[{"text":"Fix #3"}]},"dir":"/w/app"}"#,
+            r#"{"conv":"b2","ts":1760000100,"msg":{"who":"human","parts":["Hello"]}}"#,
+            r#"not json"#,
+            r#"{"conv":"a1","ts":1760000005000,"msg":{"who":"bot","parts":["Done"]},"meta":{"models":["qwen3"]}}"#,
+        ];
+        std::fs::write(logs.join("chat.jsonl"), lines.join("\n")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let yaml = r#"
+glob: "**/*.jsonl"
+session: conv
+role: msg.who
+content: msg.parts
+timestamp: ts
+model: meta.models.0
+cwd: dir
+roles: { human: user, bot: assistant }
+"#;
+        let config: CustomProbeConfig = serde_yaml::from_str(yaml).unwrap();
+        let probe = CustomProbe::new("acme:Acme", Some(dir.path().to_path_buf()), config).unwrap();
+        let sessions = probe.discover().unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["a1", "b2"]);
+
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Fix #3"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/w/app"));
+        assert_eq!(metadata.primary_model.as_deref(), Some("qwen3"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("acme"));
+        assert_eq!(metadata.messages.len(), 2);
+        assert_eq!(metadata.skipped.total(), 1);
+        let elapsed = metadata.last_timestamp.unwrap() - metadata.first_timestamp.unwrap();
+        assert_eq!(elapsed.num_seconds(), 5);
+
+        let content = probe
+            .get_content(&metadata.messages[1].content_ref)
+            .unwrap();
+        assert!(content.contains("Done"));
+        assert!(CustomProbe::new("nocolon", Some(PathBuf::from("/tmp")), probe.config).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+//! External executable probes
+//!
+//! A probe can be any program speaking a small JSON protocol, configured with
+//! `command:` under its probe ID. The command runs through `sh -c` with a subcommand
+//! and arguments appended, and `CHRONICLE_BASE_PATH` set to the probe's base path:
+//!
+//!   - `discover` prints the sessions: `[{"id": "...", "path": "..."}]` (`path` is what
+//!     Chronicle watches for changes and hands back; the base path when omitted)
+//!   - `extract <id> <path>` prints one session:
+//!     `{"title", "project_path", "git_remote", "provider", "model", "first_timestamp",
+//!     "last_timestamp", "messages": [{"role", "text", "model", "provider", "timestamp",
+//!     "thinking", "ref", "usage": {"input_tokens", ..., "reasoning_tokens"}, "tool_uses": [{"name", "id",
+//!     "input", "has_result", "is_error"}]}]}`; everything but `messages[].role` is
+//!     optional and timestamps are RFC 3339
+//!   - `get-content <path> <index> <ref>` prints the `index`th message's content: JSON
+//!     in the `{"content": [...]}` shape `read` understands, or plain text
+//!
+//! A non-zero exit fails the call with the program's stderr as the error.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::{
+    infer_provider, ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, TokenUsage, ToolUseMetadata,
+};
+
+pub struct ExecProbe {
+    id: String,
+    provider: String,
+    source: String,
+    base_path: PathBuf,
+    command: String,
+}
+
+// Protocol messages
+#[derive(Debug, Deserialize)]
+struct DiscoveredSession {
+    id: String,
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedSession {
+    external_id: Option<String>,
+    title: Option<String>,
+    project_path: Option<String>,
+    git_remote: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    first_timestamp: Option<DateTime<Utc>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    messages: Vec<ExtractedMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedMessage {
+    role: String,
+    model: Option<String>,
+    provider: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+    /// Message text, used for titles, language and issue references
+    text: Option<String>,
+    #[serde(default)]
+    thinking: bool,
+    /// Opaque locator passed back to `get-content`
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+    usage: Option<ExtractedUsage>,
+    #[serde(default)]
+    tool_uses: Vec<ExtractedToolUse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedUsage {
+    input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+    cache_read_tokens: Option<i64>,
+    cache_creation_tokens: Option<i64>,
+    /// Counted within `output_tokens`
+    reasoning_tokens: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedToolUse {
+    name: String,
+    id: Option<String>,
+    input: Option<Value>,
+    #[serde(default)]
+    has_result: bool,
+    #[serde(default)]
+    is_error: bool,
+}
+
+impl ExecProbe {
+    pub fn new(id: &str, base_path: Option<PathBuf>, command: &str) -> Result<Self> {
+        let (provider, source) = id
+            .split_once(':')
+            .filter(|(p, s)| !p.is_empty() && !s.is_empty())
+            .with_context(|| format!("Probe ID '{}' must be provider:Source", id))?;
+        Ok(Self {
+            id: id.to_string(),
+            provider: provider.to_string(),
+            source: source.to_string(),
+            base_path: base_path.unwrap_or_default(),
+            command: command.to_string(),
+        })
+    }
+
+    /// Run a protocol subcommand and return its stdout
+    fn call(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$@\"", self.command))
+            .arg(&self.id)
+            .args(args)
+            .env("CHRONICLE_BASE_PATH", &self.base_path)
+            .output()
+            .with_context(|| format!("Failed to run probe command: {}", self.command))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Probe command '{} {}' failed ({}): {}",
+                self.command,
+                args[0],
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        String::from_utf8(output.stdout).context("Probe command printed invalid UTF-8")
+    }
+
+    fn call_json<T: serde::de::DeserializeOwned>(&self, args: &[&str]) -> Result<T> {
+        let stdout = self.call(args)?;
+        serde_json::from_str(&stdout)
+            .with_context(|| format!("Probe command '{}' printed invalid JSON", args[0]))
+    }
+}
+
+impl IngestionProbe for ExecProbe {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "External probe command (configured)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.as_os_str().is_empty() || self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let found: Vec<DiscoveredSession> = self.call_json(&["discover"])?;
+        Ok(found
+            .into_iter()
+            .map(|s| SessionRef {
+                id: s.id,
+                source_path: s
+                    .path
+                    .map(|p| PathBuf::from(shellexpand::tilde(&p).to_string()))
+                    .unwrap_or_else(|| self.base_path.clone()),
+            })
+            .collect())
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let path = session.source_path.to_string_lossy();
+        let extracted: ExtractedSession = self.call_json(&["extract", &session.id, &path])?;
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = extracted.title.filter(|t| !t.trim().is_empty());
+
+        for (idx, msg) in extracted.messages.into_iter().enumerate() {
+            if !matches!(msg.role.as_str(), "user" | "assistant" | "system" | "tool") {
+                skipped.add(
+                    format!("unknown message role '{}'", msg.role),
+                    format!("{} {} message {}", self.id, session.id, idx),
+                );
+                continue;
+            }
+
+            let text = msg.text.unwrap_or_default();
+            session_refs.extend(references::detect(&text));
+            if msg.role == "user" {
+                language.add(&text);
+                if title.is_none() {
+                    title = text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            let model = (msg.role == "assistant")
+                .then(|| msg.model.or(extracted.model.clone()))
+                .flatten();
+            let provider_id = msg
+                .provider
+                .or_else(|| model.as_deref().and_then(infer_provider));
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .tool_uses
+                .into_iter()
+                .map(|tool| ToolUseMetadata {
+                    tool_id: tool.id,
+                    tool_name: tool.name,
+                    has_result: tool.has_result || tool.is_error,
+                    is_error: tool.is_error,
+                    permission: None,
+                    input_hash: tool.input.as_ref().map(loops::fingerprint),
+                    file_path: tool.input.as_ref().and_then(files::touched_file),
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: msg.role,
+                provider_id,
+                model,
+                timestamp: msg.timestamp,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: msg.reference.map(PathBuf::from),
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: msg.thinking,
+                tool_uses,
+                token_usage: msg.usage.map(|u| TokenUsage {
+                    input_tokens: u.input_tokens,
+                    output_tokens: u.output_tokens,
+                    cache_read_tokens: u.cache_read_tokens,
+                    cache_creation_tokens: u.cache_creation_tokens,
+                    reasoning_tokens: u.reasoning_tokens,
+                }),
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = extracted
+            .first_timestamp
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = extracted
+            .last_timestamp
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp))
+            .or(first_timestamp);
+
+        Ok(SessionMetadata {
+            external_id: extracted.external_id.unwrap_or(session.id.clone()),
+            title,
+            project_path: extracted.project_path,
+            git_remote: extracted.git_remote,
+            source_group: None,
+            primary_provider: extracted.provider.or_else(|| {
+                provider_counts
+                    .into_iter()
+                    .max_by_key(|(_, count)| *count)
+                    .map(|(provider, _)| provider)
+            }),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let path = reference.source_path.to_string_lossy();
+        let index = reference.line_number.unwrap_or(0).to_string();
+        let locator = reference
+            .content_path
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stdout = self.call(&["get-content", &path, &index, &locator])?;
+        match serde_json::from_str::<Value>(&stdout) {
+            Ok(_) => Ok(stdout),
+            Err(_) => Ok(
+                json!({ "content": [{ "type": "text", "text": stdout.trim_end() }] }).to_string(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_exec_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("probe.sh");
+        let session = json!({
+            "title": "",
+            "project_path": "/w/app",
+            "messages": [
+                { "role": "user", "text": "Fix #9", "timestamp": "2025-10-01T09:00:00Z" },
+                {
+                    "role": "assistant",
+                    "model": "gpt-4o",
+                    "ref": "row-2",
+                    "usage": { "input_tokens": 10, "output_tokens": 4 },
+                    "tool_uses": [{ "name": "edit", "input": { "path": "src/a.rs" }, "has_result": true }]
+                },
+                { "role": "narrator" }
+            ]
+        });
+        std::fs::write(
+            &script,
+            format!(
+                "case \"$1\" in\n\
+                 discover) echo '[{{\"id\":\"s1\"}}]' ;;\n\
+                 extract) echo '{}' ;;\n\
+                 get-content) echo \"$2|$3|$4|$CHRONICLE_BASE_PATH\" ;;\n\
+                 *) echo unknown >&2; exit 2 ;;\n\
+                 esac\n",
+                session
+            ),
+        )
+        .unwrap();
+        let command = format!("sh {}", script.display());
+        let probe = ExecProbe::new("acme:Acme", Some(dir.path().to_path_buf()), &command).unwrap();
+
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions[0].id, "s1");
+        assert_eq!(sessions[0].source_path, dir.path());
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Fix #9"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("openai"));
+        assert_eq!(metadata.messages.len(), 2);
+        assert_eq!(metadata.skipped.total(), 1);
+        let answer = &metadata.messages[1];
+        assert_eq!(answer.tool_uses[0].file_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(answer.token_usage.as_ref().unwrap().output_tokens, Some(4));
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        let expected = format!("{}|1|row-2|{}", dir.path().display(), dir.path().display());
+        assert!(content.contains(&expected), "{}", content);
+        let bad = ExecProbe::new("acme:Acme", None, &format!("{} bogus", command)).unwrap();
+        assert!(bad.discover().is_err());
+    }
+}
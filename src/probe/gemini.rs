@@ -0,0 +1,616 @@
+//! Gemini CLI probe implementation
+//!
+//! Extracts conversation history from Google's Gemini CLI.
+//! Data format: ~/.gemini/tmp/<project_hash>/
+//!   - chats/session-*.json: recorded sessions (`messages` with type user/gemini/info/error)
+//!   - checkpoint-<tag>.json: `/chat save` checkpoints (array of {role, parts})
+//!
+//! The project hash is a SHA-256 of the project root, so sessions carry it as their
+//! source group; map it to a project with `project_map` in the probe config.
+//!
+//! Gemini CLI is a single-provider source (Google).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+use crate::error::Error;
+
+use super::{
+    read_source, ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, TokenUsage, ToolUseMetadata,
+};
+
+pub struct GeminiCliProbe {
+    base_path: PathBuf,
+}
+
+// Gemini CLI data structures (chats/session-*.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatRecord {
+    session_id: Option<String>,
+    project_hash: Option<String>,
+    start_time: Option<String>,
+    last_updated: Option<String>,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatMessage {
+    id: Option<String>,
+    timestamp: Option<String>,
+    #[serde(rename = "type")]
+    message_type: String,
+    content: Option<Value>,
+    model: Option<String>,
+    #[serde(default)]
+    thoughts: Vec<Value>,
+    tokens: Option<ChatTokens>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatTokens {
+    input: Option<i64>,
+    output: Option<i64>,
+    cached: Option<i64>,
+    /// Thinking, reported beside the output
+    thoughts: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    id: Option<String>,
+    name: Option<String>,
+    args: Option<Value>,
+    result: Option<Value>,
+    /// 'success', 'error' or 'cancelled'
+    status: Option<String>,
+}
+
+// Checkpoint structures (checkpoint-*.json, Gemini API `Content` objects)
+#[derive(Debug, Deserialize)]
+struct CheckpointContent {
+    role: String,
+    #[serde(default)]
+    parts: Vec<Value>,
+}
+
+impl GeminiCliProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".gemini/tmp")
+        });
+        Self { base_path }
+    }
+
+    fn is_checkpoint(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("checkpoint"))
+    }
+
+    /// Project hash directory a session file belongs to
+    fn project_hash(&self, path: &Path) -> Option<String> {
+        path.strip_prefix(&self.base_path)
+            .ok()?
+            .components()
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+    }
+
+    fn read_json(path: &Path) -> Result<Value> {
+        let content = read_source(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| Error::invalid_json(path, "Gemini CLI session JSON", &e).into())
+    }
+
+    /// Parse a session file's JSON as a checkpoint or chat record
+    fn parse<T: DeserializeOwned>(path: &Path, value: Value, what: &str) -> Result<T> {
+        serde_json::from_value(value).map_err(|e| Error::invalid_json(path, what, &e).into())
+    }
+
+    /// Normalize a chat message into the content-array shape `read` understands
+    fn chat_content(msg: &ChatMessage) -> Value {
+        let mut items = vec![];
+        for thought in &msg.thoughts {
+            let text = match (thought.get("subject"), thought.get("description")) {
+                (Some(s), Some(d)) => {
+                    format!("{}: {}", s.as_str().unwrap_or(""), d.as_str().unwrap_or(""))
+                }
+                _ => thought.to_string(),
+            };
+            items.push(json!({ "type": "thinking", "thinking": text }));
+        }
+        match &msg.content {
+            Some(Value::String(text)) => items.push(json!({ "type": "text", "text": text })),
+            Some(Value::Array(parts)) => items.extend(parts.iter().filter_map(part_content)),
+            _ => {}
+        }
+        for call in &msg.tool_calls {
+            items.push(json!({ "type": "tool_use", "name": call.name }));
+        }
+        json!({ "content": items })
+    }
+
+    fn extract_chat(&self, session: &SessionRef, record: ChatRecord) -> SessionMetadata {
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = None;
+
+        for (idx, msg) in record.messages.iter().enumerate() {
+            let role = match msg.message_type.as_str() {
+                "user" => "user",
+                "gemini" | "model" => "assistant",
+                other => {
+                    skipped.add(
+                        format!("unknown message type '{}'", other),
+                        format!("{} message {}", session.source_path.display(), idx),
+                    );
+                    continue;
+                }
+            };
+
+            let text = message_text(msg.content.as_ref());
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+            }
+            if role == "user" && title.is_none() {
+                title = text
+                    .lines()
+                    .find(|l| !l.trim().is_empty())
+                    .map(|l| l.trim().to_string());
+            }
+
+            if let Some(ref model) = msg.model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .tool_calls
+                .iter()
+                .map(|call| ToolUseMetadata {
+                    tool_id: call.id.clone(),
+                    tool_name: call.name.clone().unwrap_or_else(|| "unknown".to_string()),
+                    has_result: call.result.as_ref().is_some_and(|r| !r.is_null()),
+                    is_error: call.status.as_deref() == Some("error"),
+                    permission: None,
+                    input_hash: call.args.as_ref().map(loops::fingerprint),
+                    file_path: call.args.as_ref().and_then(files::touched_file),
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: msg.id.clone(),
+                role: role.to_string(),
+                provider_id: (role == "assistant").then(|| "google".to_string()),
+                model: msg.model.clone(),
+                timestamp: msg.timestamp.as_deref().and_then(parse_time),
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: !msg.thoughts.is_empty(),
+                tool_uses,
+                token_usage: msg.tokens.as_ref().map(|t| TokenUsage {
+                    input_tokens: t.input,
+                    output_tokens: match t.thoughts {
+                        Some(thoughts) => Some(t.output.unwrap_or(0) + thoughts),
+                        None => t.output,
+                    },
+                    cache_read_tokens: t.cached,
+                    cache_creation_tokens: None,
+                    reasoning_tokens: t.thoughts,
+                }),
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = record
+            .start_time
+            .as_deref()
+            .and_then(parse_time)
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = record
+            .last_updated
+            .as_deref()
+            .and_then(parse_time)
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp));
+
+        SessionMetadata {
+            external_id: record.session_id.unwrap_or_else(|| session.id.clone()),
+            title,
+            project_path: None,
+            git_remote: None,
+            source_group: record
+                .project_hash
+                .or_else(|| self.project_hash(&session.source_path)),
+            primary_provider: Some("google".to_string()),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        }
+    }
+
+    fn extract_checkpoint(
+        &self,
+        session: &SessionRef,
+        contents: Vec<CheckpointContent>,
+    ) -> SessionMetadata {
+        let mut messages = vec![];
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = None;
+
+        // Checkpoints carry no per-message times; the file mtime bounds the session
+        let modified = std::fs::metadata(&session.source_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from);
+
+        for (idx, content) in contents.iter().enumerate() {
+            let role = if content.role == "model" {
+                "assistant"
+            } else {
+                "user"
+            };
+
+            let mut tool_uses = vec![];
+            let mut is_tool_response = false;
+            for part in &content.parts {
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    session_refs.extend(references::detect(text));
+                    if role == "user" {
+                        language.add(text);
+                    }
+                    if role == "user" && title.is_none() {
+                        title = text
+                            .lines()
+                            .find(|l| !l.trim().is_empty())
+                            .map(|l| l.trim().to_string());
+                    }
+                }
+                if let Some(call) = part.get("functionCall") {
+                    tool_uses.push(ToolUseMetadata {
+                        tool_id: call.get("id").and_then(|i| i.as_str()).map(String::from),
+                        tool_name: call
+                            .get("name")
+                            .and_then(|n| n.as_str())
+                            .unwrap_or("unknown")
+                            .to_string(),
+                        has_result: false,
+                        is_error: false,
+                        permission: None,
+                        input_hash: call.get("args").map(loops::fingerprint),
+                        file_path: call.get("args").and_then(files::touched_file),
+                    });
+                }
+                is_tool_response |= part.get("functionResponse").is_some();
+            }
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: if is_tool_response { "tool" } else { role }.to_string(),
+                provider_id: (role == "assistant").then(|| "google".to_string()),
+                model: None,
+                timestamp: None,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: content
+                    .parts
+                    .iter()
+                    .any(|p| p.get("thought").and_then(|t| t.as_bool()) == Some(true)),
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        SessionMetadata {
+            external_id: session.id.clone(),
+            title,
+            project_path: None,
+            git_remote: None,
+            source_group: self.project_hash(&session.source_path),
+            primary_provider: Some("google".to_string()),
+            primary_model: None,
+            first_timestamp: None,
+            last_timestamp: modified,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        }
+    }
+}
+
+/// Convert a Gemini API part into a `read` content item
+fn part_content(part: &Value) -> Option<Value> {
+    if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+        if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+            return Some(json!({ "type": "thinking", "thinking": text }));
+        }
+        return Some(json!({ "type": "text", "text": text }));
+    }
+    if let Some(call) = part.get("functionCall") {
+        return Some(json!({ "type": "tool_use", "name": call.get("name") }));
+    }
+    part.get("functionResponse").map(|response| {
+        json!({
+            "type": "text",
+            "text": format!(
+                "[Tool result: {}]",
+                response.get("name").and_then(|n| n.as_str()).unwrap_or("unknown")
+            )
+        })
+    })
+}
+
+/// Plain text of a message's content (string or list of parts)
+fn message_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+impl IngestionProbe for GeminiCliProbe {
+    fn id(&self) -> &str {
+        "gemini:GeminiCLI"
+    }
+
+    fn provider(&self) -> &str {
+        "gemini"
+    }
+
+    fn source(&self) -> &str {
+        "GeminiCLI"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "Gemini CLI (Google)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.exists() {
+            return Ok(sessions);
+        }
+
+        for project_entry in std::fs::read_dir(&self.base_path)? {
+            let project_dir = project_entry?.path();
+            if !project_dir.is_dir() {
+                continue;
+            }
+
+            let mut files = vec![];
+            if let Ok(entries) = std::fs::read_dir(project_dir.join("chats")) {
+                files.extend(entries.flatten().map(|e| e.path()));
+            }
+            files.extend(
+                std::fs::read_dir(&project_dir)?
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| Self::is_checkpoint(p)),
+            );
+
+            for path in files {
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    let stem = path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    // Checkpoint tags are only unique within a project directory
+                    let id = if Self::is_checkpoint(&path) {
+                        let hash = self.project_hash(&path).unwrap_or_default();
+                        format!("{}-{}", &hash[..8.min(hash.len())], stem)
+                    } else {
+                        stem
+                    };
+                    sessions.push(SessionRef {
+                        id,
+                        source_path: path,
+                    });
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let value = Self::read_json(&session.source_path)?;
+
+        if Self::is_checkpoint(&session.source_path) {
+            let contents: Vec<CheckpointContent> =
+                Self::parse(&session.source_path, value, "Gemini CLI checkpoint")?;
+            return Ok(self.extract_checkpoint(session, contents));
+        }
+
+        let record: ChatRecord = Self::parse(&session.source_path, value, "Gemini CLI chat")?;
+        Ok(self.extract_chat(session, record))
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let value = Self::read_json(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+
+        let content = if Self::is_checkpoint(&reference.source_path) {
+            let contents: Vec<CheckpointContent> =
+                Self::parse(&reference.source_path, value, "Gemini CLI checkpoint")?;
+            let entry = contents
+                .get(index)
+                .context("Gemini CLI message index out of range")?;
+            json!({ "content": entry.parts.iter().filter_map(part_content).collect::<Vec<_>>() })
+        } else {
+            let record: ChatRecord = Self::parse(&reference.source_path, value, "Gemini CLI chat")?;
+            let msg = record
+                .messages
+                .get(index)
+                .context("Gemini CLI message index out of range")?;
+            Self::chat_content(msg)
+        };
+
+        Ok(content.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_chats_and_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("9f86d081884c7d65");
+        std::fs::create_dir_all(project.join("chats")).unwrap();
+        let chat = json!({
+            "sessionId": "1b2c",
+            "projectHash": "9f86d081884c7d65",
+            "startTime": "2025-10-01T09:00:00Z",
+            "lastUpdated": "2025-10-01T09:05:00Z",
+            "messages": [
+                { "id": "m1", "timestamp": "2025-10-01T09:00:00Z", "type": "user",
+                  "content": "Fix issue #7 in the lexer" },
+                { "id": "m2", "timestamp": "2025-10-01T09:00:20Z", "type": "gemini",
+                  "content": "Looking at it",
+                  "model": "gemini-2.5-pro",
+                  "thoughts": [{ "subject": "Plan", "description": "read the lexer" }],
+                  "tokens": { "input": 900, "output": 40, "cached": 300, "thoughts": 60 },
+                  "toolCalls": [{ "id": "c1", "name": "read_file",
+                                  "args": { "absolute_path": "/src/lexer.rs" },
+                                  "result": [{ "text": "fn lex() {}" }],
+                                  "status": "error" }] },
+                { "id": "m3", "timestamp": "2025-10-01T09:01:00Z", "type": "info",
+                  "content": "Request cancelled" }
+            ]
+        });
+        std::fs::write(
+            project.join("chats/session-2025-10-01T09-00-1b2c.json"),
+            chat.to_string(),
+        )
+        .unwrap();
+        let checkpoint = json!([
+            { "role": "user", "parts": [{ "text": "Summarize the design" }] },
+            { "role": "model", "parts": [
+                { "text": "thinking it over", "thought": true },
+                { "functionCall": { "name": "glob", "args": { "pattern": "*.md" } } }
+            ] },
+            { "role": "user", "parts": [{ "functionResponse": { "name": "glob" } }] }
+        ]);
+        std::fs::write(
+            project.join("checkpoint-design.json"),
+            checkpoint.to_string(),
+        )
+        .unwrap();
+
+        let probe = GeminiCliProbe::new(Some(dir.path().to_path_buf()));
+        let mut sessions = probe.discover().unwrap();
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "9f86d081-checkpoint-design",
+                "session-2025-10-01T09-00-1b2c"
+            ]
+        );
+
+        let chat = probe.extract_metadata(&sessions[1]).unwrap();
+        assert_eq!(chat.external_id, "1b2c");
+        assert_eq!(chat.title.as_deref(), Some("Fix issue #7 in the lexer"));
+        assert_eq!(chat.source_group.as_deref(), Some("9f86d081884c7d65"));
+        assert_eq!(chat.primary_model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(chat.references.len(), 1);
+        assert_eq!(chat.messages.len(), 2);
+        assert_eq!(chat.skipped.total(), 1);
+        let answer = &chat.messages[1];
+        assert!(answer.has_thinking);
+        let usage = answer.token_usage.as_ref().unwrap();
+        assert_eq!(
+            (
+                usage.output_tokens,
+                usage.cache_read_tokens,
+                usage.reasoning_tokens
+            ),
+            (Some(100), Some(300), Some(60))
+        );
+        let tool = &answer.tool_uses[0];
+        assert_eq!(tool.tool_name, "read_file");
+        assert!(tool.has_result && tool.is_error);
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("Plan: read the lexer") && content.contains("Looking at it"));
+
+        let saved = probe.extract_metadata(&sessions[0]).unwrap();
+        assert_eq!(saved.title.as_deref(), Some("Summarize the design"));
+        let roles: Vec<&str> = saved.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "tool"]);
+        assert!(saved.messages[1].has_thinking);
+        assert_eq!(saved.messages[1].tool_uses[0].tool_name, "glob");
+        assert!(saved.last_timestamp.is_some());
+        let content = probe.get_content(&saved.messages[1].content_ref).unwrap();
+        assert!(content.contains("\"thinking\":\"thinking it over\""));
+        assert!(content.contains("\"name\":\"glob\""));
+    }
+}
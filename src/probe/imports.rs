@@ -0,0 +1,71 @@
+//! Shared handling of unzipped data exports (ChatGPT, claude.ai)
+//!
+//! Web chat services have no live local source: the user unzips each export under
+//! the probe's base path. Every export holds all conversations in one JSON file, so
+//! files are parsed once per process and reused while unchanged.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+use super::SourceFingerprint;
+
+/// How deep under the base path exports are looked for
+const MAX_DEPTH: usize = 3;
+
+/// A parsed export file with the fingerprint it was read at
+type Parsed<T> = (Option<SourceFingerprint>, Arc<Vec<T>>);
+
+/// Parsed export files, keyed by path
+pub(super) struct ExportCache<T> {
+    file_name: &'static str,
+    files: Mutex<HashMap<PathBuf, Parsed<T>>>,
+}
+
+impl<T: DeserializeOwned> ExportCache<T> {
+    pub fn new(file_name: &'static str) -> Self {
+        Self {
+            file_name,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Export files under the base path (or the base path itself), newest first
+    pub fn export_files(&self, base_path: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<(i64, PathBuf)> = WalkDir::new(base_path)
+            .max_depth(MAX_DEPTH)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.file_name() == self.file_name)
+            .map(|e| {
+                let mtime = SourceFingerprint::of(e.path()).map_or(0, |f| f.mtime);
+                (mtime, e.into_path())
+            })
+            .collect();
+        files.sort_by(|a, b| b.cmp(a));
+        files.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Records of an export file, parsed once per process while unchanged
+    pub fn load(&self, path: &Path) -> Result<Arc<Vec<T>>> {
+        let fingerprint = SourceFingerprint::of(path);
+        if let Some((cached, records)) = self.files.lock().unwrap().get(path) {
+            if *cached == fingerprint {
+                return Ok(records.clone());
+            }
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let records: Vec<T> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let records = Arc::new(records);
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (fingerprint, records.clone()));
+        Ok(records)
+    }
+}
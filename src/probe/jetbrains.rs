@@ -0,0 +1,399 @@
+//! JetBrains AI Assistant probe implementation
+//!
+//! Extracts AI Assistant chats from JetBrains IDEs (IntelliJ IDEA, PyCharm, WebStorm,
+//! GoLand, ...). Every IDE version keeps its own system directory, so all of them are
+//! scanned.
+//! Data format (base path ~/Library/Caches/JetBrains, the IDEs' system root):
+//!   - <Product><Version>/aia/chats/<chat-id>.json: one chat
+//!     {id, title, createdAt, updatedAt (ms), projectPath,
+//!     messages: [{id, role, text, timestamp (ms), llmId, toolCalls: [...]}]}
+//!
+//! `llmId` names the model behind an answer with its vendor as prefix
+//! (`openai-gpt-4o`, `anthropic-claude-3.7-sonnet`, `google-chat-gemini-pro-2.5`).
+//! The IDE directory name (e.g. `PyCharm2025.2`) is kept as the session's source group.
+//!
+//! AI Assistant is a multi-provider source.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, ToolUseMetadata,
+};
+
+/// Chats of an IDE, under its system directory
+const CHATS_DIR: &str = "aia/chats";
+
+/// Vendor prefixes of `llmId`, mapped to Chronicle provider ids
+const LLM_VENDORS: &[(&str, &str)] = &[
+    ("openai-", "openai"),
+    ("anthropic-", "anthropic"),
+    ("google-chat-", "google"),
+    ("google-", "google"),
+    ("mistral-", "mistral"),
+    ("xai-", "xai"),
+    ("ollama-", "ollama"),
+    ("lmstudio-", "lmstudio"),
+];
+
+pub struct JetBrainsProbe {
+    base_path: PathBuf,
+}
+
+// AI Assistant data structures (aia/chats/<id>.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Chat {
+    id: Option<String>,
+    title: Option<String>,
+    /// Milliseconds since the epoch
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    project_path: Option<String>,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatMessage {
+    id: Option<String>,
+    role: String,
+    #[serde(default)]
+    text: String,
+    timestamp: Option<i64>,
+    llm_id: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolCall {
+    id: Option<String>,
+    name: Option<String>,
+    /// JSON-encoded arguments
+    arguments: Option<String>,
+    result: Option<ToolCallResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCallResult {
+    /// 'success', 'error' or 'cancelled'
+    status: Option<String>,
+}
+
+impl JetBrainsProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join("Library/Caches/JetBrains")
+        });
+        Self { base_path }
+    }
+
+    fn read_chat(path: &Path) -> Result<Chat> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse AI Assistant chat JSON")
+    }
+
+    /// Name of the IDE directory a chat file lives in (`<ide>/aia/chats/<id>.json`)
+    fn ide_dir(path: &Path) -> Option<String> {
+        let ide = path.parent()?.parent()?.parent()?;
+        ide.file_name().map(|n| n.to_string_lossy().into_owned())
+    }
+}
+
+/// (provider, model) of an `llmId`: `openai-gpt-4o` -> (openai, gpt-4o)
+fn split_llm_id(llm_id: &str) -> (Option<String>, String) {
+    for (prefix, provider) in LLM_VENDORS {
+        if let Some(model) = llm_id.strip_prefix(prefix) {
+            return (Some(provider.to_string()), model.to_string());
+        }
+    }
+    (super::infer_provider(llm_id), llm_id.to_string())
+}
+
+fn millis_to_datetime(ms: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+impl IngestionProbe for JetBrainsProbe {
+    fn id(&self) -> &str {
+        "jetbrains:JetBrains"
+    }
+
+    fn provider(&self) -> &str {
+        "jetbrains"
+    }
+
+    fn source(&self) -> &str {
+        "JetBrains"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "JetBrains AI Assistant (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // AI Assistant doesn't persist token usage
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.is_dir() {
+            return Ok(sessions);
+        }
+
+        for ide in std::fs::read_dir(&self.base_path)? {
+            let chats = ide?.path().join(CHATS_DIR);
+            if !chats.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&chats)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                let id = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                sessions.push(SessionRef {
+                    id,
+                    source_path: path,
+                });
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let chat = Self::read_chat(&session.source_path)?;
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = chat.title.clone().filter(|t| !t.trim().is_empty());
+
+        for (idx, msg) in chat.messages.iter().enumerate() {
+            if !matches!(msg.role.as_str(), "user" | "assistant" | "system") {
+                skipped.add(
+                    format!("unknown message role '{}'", msg.role),
+                    format!("{} message {}", session.source_path.display(), idx),
+                );
+                continue;
+            }
+
+            session_refs.extend(references::detect(&msg.text));
+            if msg.role == "user" {
+                language.add(&msg.text);
+                if title.is_none() {
+                    title = msg
+                        .text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            let (provider_id, model) = match msg.llm_id.as_deref() {
+                Some(llm_id) if msg.role == "assistant" => {
+                    let (provider, model) = split_llm_id(llm_id);
+                    (provider, Some(model))
+                }
+                _ => (None, None),
+            };
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            if let Some(ref provider) = provider_id {
+                *provider_counts.entry(provider.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = msg
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    let status = call.result.as_ref().and_then(|r| r.status.as_deref());
+                    let arguments = call
+                        .arguments
+                        .as_deref()
+                        .and_then(|a| serde_json::from_str::<Value>(a).ok());
+                    ToolUseMetadata {
+                        tool_id: call.id.clone(),
+                        tool_name: call.name.clone().unwrap_or_else(|| "unknown".to_string()),
+                        has_result: call.result.is_some(),
+                        is_error: matches!(status, Some("error" | "cancelled")),
+                        permission: None,
+                        input_hash: arguments.as_ref().map(loops::fingerprint),
+                        file_path: arguments.as_ref().and_then(files::touched_file),
+                    }
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: msg.id.clone(),
+                role: msg.role.clone(),
+                provider_id,
+                model,
+                timestamp: msg.timestamp.and_then(millis_to_datetime),
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: false,
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = chat
+            .created_at
+            .and_then(millis_to_datetime)
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = chat
+            .updated_at
+            .and_then(millis_to_datetime)
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp))
+            .or(first_timestamp);
+
+        Ok(SessionMetadata {
+            external_id: chat.id.unwrap_or(session.id.clone()),
+            title,
+            project_path: chat.project_path.filter(|p| !p.is_empty()),
+            git_remote: None,
+            source_group: Self::ide_dir(&session.source_path),
+            primary_provider: provider_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(provider, _)| provider),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let chat = Self::read_chat(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let msg = chat
+            .messages
+            .get(index)
+            .context("AI Assistant message index out of range")?;
+        let mut items = vec![];
+        if !msg.text.is_empty() {
+            items.push(json!({ "type": "text", "text": msg.text }));
+        }
+        for call in &msg.tool_calls {
+            items.push(json!({ "type": "tool_use", "name": call.name }));
+        }
+        Ok(json!({ "content": items }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_chats_across_ides() {
+        let dir = tempfile::tempdir().unwrap();
+        let chats = dir.path().join("PyCharm2025.2").join(CHATS_DIR);
+        std::fs::create_dir_all(&chats).unwrap();
+        std::fs::create_dir_all(dir.path().join("IntelliJIdea2025.2/log")).unwrap();
+        let chat = json!({
+            "id": "f00d",
+            "createdAt": 1760000000000i64,
+            "projectPath": "/home/me/api",
+            "messages": [
+                { "id": "m1", "role": "user", "text": "Why does test_login fail?", "timestamp": 1760000001000i64 },
+                {
+                    "id": "m2",
+                    "role": "assistant",
+                    "text": "Let me look.",
+                    "llmId": "anthropic-claude-3.7-sonnet",
+                    "toolCalls": [{
+                        "id": "c1",
+                        "name": "read_file",
+                        "arguments": "{\"path\":\"/home/me/api/tests/test_auth.py\"}",
+                        "result": { "status": "success" }
+                    }]
+                },
+                { "role": "assistant", "text": "Fixed.", "llmId": "openai-gpt-4o" },
+                { "role": "assistant", "text": "Done.", "llmId": "openai-gpt-4o" }
+            ]
+        });
+        std::fs::write(chats.join("f00d.json"), chat.to_string()).unwrap();
+
+        let probe = JetBrainsProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Why does test_login fail?"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/home/me/api"));
+        assert_eq!(metadata.source_group.as_deref(), Some("PyCharm2025.2"));
+        assert_eq!(metadata.primary_model.as_deref(), Some("gpt-4o"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("openai"));
+        let answer = &metadata.messages[1];
+        assert_eq!(answer.model.as_deref(), Some("claude-3.7-sonnet"));
+        assert_eq!(answer.provider_id.as_deref(), Some("anthropic"));
+        let tool = &answer.tool_uses[0];
+        assert!(tool.has_result && !tool.is_error);
+        assert_eq!(
+            tool.file_path.as_deref(),
+            Some("/home/me/api/tests/test_auth.py")
+        );
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("Let me look.") && content.contains("read_file"));
+    }
+}
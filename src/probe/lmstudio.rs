@@ -0,0 +1,433 @@
+//! LM Studio probe implementation
+//!
+//! Extracts chats with locally-run models from the LM Studio desktop app.
+//! Data format: ~/.lmstudio/conversations/ (chats can be sorted into sub-folders)
+//!   - <created-ms>.conversation.json: one chat
+//!     {name, createdAt (ms), systemPrompt, lastUsedModel: {identifier},
+//!     messages: [{currentlySelected, versions: [...]}]}
+//!
+//! Every message keeps all regenerated versions; the one on screen
+//! (`currentlySelected`) is indexed. User versions hold `content` blocks; assistant
+//! versions are `multiStep` with content-block `steps`, each carrying the generating
+//! model and token stats in `genInfo`.
+//!
+//! Models run on the user's machine, so the provider is recorded as `local`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, TokenUsage, ToolUseMetadata,
+};
+
+const CONVERSATION_SUFFIX: &str = ".conversation.json";
+
+pub struct LmStudioProbe {
+    base_path: PathBuf,
+}
+
+// LM Studio data structures (<id>.conversation.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Conversation {
+    name: Option<String>,
+    /// Milliseconds since the epoch
+    created_at: Option<i64>,
+    last_used_model: Option<ModelRef>,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+    #[serde(default)]
+    versions: Vec<Version>,
+    #[serde(default)]
+    currently_selected: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    role: String,
+    /// Blocks of single-step (user, system) versions
+    #[serde(default)]
+    content: Vec<Value>,
+    /// Generation steps of multi-step (assistant) versions
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    #[serde(default)]
+    content: Vec<Value>,
+    gen_info: Option<GenInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenInfo {
+    identifier: Option<String>,
+    stats: Option<GenStats>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenStats {
+    prompt_tokens_count: Option<i64>,
+    predicted_tokens_count: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelRef {
+    identifier: Option<String>,
+}
+
+impl Message {
+    /// The version shown in the app
+    fn selected(&self) -> Option<&Version> {
+        self.versions
+            .get(self.currently_selected)
+            .or(self.versions.last())
+    }
+}
+
+impl Version {
+    /// Content blocks of every step, in order
+    fn blocks(&self) -> impl Iterator<Item = &Value> {
+        self.content
+            .iter()
+            .chain(self.steps.iter().flat_map(|s| s.content.iter()))
+    }
+
+    fn text(&self) -> String {
+        self.blocks()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Requested tool calls: (name, arguments)
+    fn tool_calls(&self) -> impl Iterator<Item = (&str, Option<&Value>)> {
+        self.blocks()
+            .filter_map(|b| b.get("toolCallRequest"))
+            .map(|call| {
+                let name = call.get("name").and_then(|n| n.as_str());
+                (name.unwrap_or("unknown"), call.get("arguments"))
+            })
+    }
+
+    fn has_tool_result(&self) -> bool {
+        self.blocks()
+            .any(|b| b.get("type").and_then(|t| t.as_str()) == Some("toolCallResult"))
+    }
+}
+
+impl LmStudioProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".lmstudio/conversations")
+        });
+        Self { base_path }
+    }
+
+    fn read_conversation(path: &Path) -> Result<Conversation> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse LM Studio conversation JSON")
+    }
+}
+
+impl IngestionProbe for LmStudioProbe {
+    fn id(&self) -> &str {
+        "local:LMStudio"
+    }
+
+    fn provider(&self) -> &str {
+        "local"
+    }
+
+    fn source(&self) -> &str {
+        "LMStudio"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "LM Studio local model chats"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        ProbeCapabilities {
+            supports_token_usage: true,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.exists() {
+            return Ok(sessions);
+        }
+
+        for entry in WalkDir::new(&self.base_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy();
+            let Some(id) = name.strip_suffix(CONVERSATION_SUFFIX) else {
+                continue;
+            };
+            sessions.push(SessionRef {
+                id: id.to_string(),
+                source_path: entry.path().to_path_buf(),
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let conversation = Self::read_conversation(&session.source_path)?;
+
+        let mut messages: Vec<MessageMetadata> = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = conversation.name.clone().filter(|t| !t.trim().is_empty());
+
+        for (idx, message) in conversation.messages.iter().enumerate() {
+            let location = || format!("{} message {}", session.source_path.display(), idx);
+            let Some(version) = message.selected() else {
+                skipped.add("message without versions", location());
+                continue;
+            };
+            let role = version.role.as_str();
+            if !matches!(role, "user" | "assistant" | "system" | "tool") {
+                skipped.add(format!("unknown message role '{}'", role), location());
+                continue;
+            }
+
+            let text = version.text();
+            session_refs.extend(references::detect(&text));
+            if role == "user" {
+                language.add(&text);
+                if title.is_none() {
+                    title = text
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            // The steps of one answer can differ in model only if the user switched
+            // mid-generation; the last step's is the one that finished it
+            let gen_infos: Vec<&GenInfo> = version
+                .steps
+                .iter()
+                .filter_map(|s| s.gen_info.as_ref())
+                .collect();
+            let model = (role == "assistant")
+                .then(|| {
+                    gen_infos
+                        .iter()
+                        .rev()
+                        .find_map(|g| g.identifier.clone())
+                        .or_else(|| {
+                            conversation
+                                .last_used_model
+                                .as_ref()
+                                .and_then(|m| m.identifier.clone())
+                        })
+                })
+                .flatten();
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+            let stats: Vec<&GenStats> = gen_infos.iter().filter_map(|g| g.stats.as_ref()).collect();
+            let token_usage = (!stats.is_empty()).then(|| TokenUsage {
+                input_tokens: stats.iter().filter_map(|s| s.prompt_tokens_count).max(),
+                output_tokens: Some(stats.iter().filter_map(|s| s.predicted_tokens_count).sum()),
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            });
+
+            let tool_uses: Vec<ToolUseMetadata> = version
+                .tool_calls()
+                .map(|(name, arguments)| ToolUseMetadata {
+                    tool_id: None,
+                    tool_name: name.to_string(),
+                    // Results are appended as later blocks of the same answer
+                    has_result: version.has_tool_result(),
+                    is_error: false,
+                    permission: None,
+                    input_hash: arguments.map(loops::fingerprint),
+                    file_path: arguments.and_then(files::touched_file),
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: None,
+                role: role.to_string(),
+                provider_id: model.as_ref().map(|_| "local".to_string()),
+                model,
+                timestamp: None,
+                content_ref: ContentRef {
+                    source_path: session.source_path.clone(),
+                    byte_offset: None,
+                    line_number: Some(idx as u32),
+                    content_path: None,
+                },
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: version.blocks().any(|b| {
+                    b.get("style").and_then(|s| s.get("type")) == Some(&json!("thinking"))
+                }),
+                tool_uses,
+                token_usage,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = conversation
+            .created_at
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single());
+        let last_timestamp = std::fs::metadata(&session.source_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from)
+            .or(first_timestamp);
+        let primary_model = model_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(model, _)| model);
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title,
+            project_path: None,
+            git_remote: None,
+            source_group: None,
+            primary_provider: primary_model.as_ref().map(|_| "local".to_string()),
+            primary_model,
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let conversation = Self::read_conversation(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let version = conversation
+            .messages
+            .get(index)
+            .and_then(Message::selected)
+            .context("LM Studio message index out of range")?;
+        let mut items = vec![];
+        let text = version.text();
+        if !text.is_empty() {
+            items.push(json!({ "type": "text", "text": text }));
+        }
+        for (name, arguments) in version.tool_calls() {
+            items.push(json!({ "type": "tool_use", "name": name, "input": arguments }));
+        }
+        Ok(json!({ "content": items }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_selected_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("work");
+        std::fs::create_dir_all(&folder).unwrap();
+        let conversation = json!({
+            "name": "Regex help",
+            "createdAt": 1760000000000i64,
+            "lastUsedModel": { "identifier": "qwen2.5-7b-instruct" },
+            "messages": [
+                { "currentlySelected": 0, "versions": [
+                    { "type": "singleStep", "role": "user", "content": [{ "type": "text", "text": "Match dates" }] }
+                ]},
+                { "currentlySelected": 1, "versions": [
+                    { "type": "multiStep", "role": "assistant", "steps": [
+                        { "type": "contentBlock", "content": [{ "type": "text", "text": "old answer" }] }
+                    ]},
+                    { "type": "multiStep", "role": "assistant", "steps": [
+                        {
+                            "type": "contentBlock",
+                            "content": [{ "type": "text", "text": "Use \\d{4}" }],
+                            "genInfo": {
+                                "identifier": "llama-3.2-3b-instruct",
+                                "stats": { "promptTokensCount": 40, "predictedTokensCount": 12 }
+                            }
+                        }
+                    ]}
+                ]}
+            ]
+        });
+        std::fs::write(
+            folder.join("1760000000000.conversation.json"),
+            conversation.to_string(),
+        )
+        .unwrap();
+
+        let probe = LmStudioProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Regex help"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("local"));
+        assert_eq!(
+            metadata.primary_model.as_deref(),
+            Some("llama-3.2-3b-instruct")
+        );
+        let answer = &metadata.messages[1];
+        let usage = answer.token_usage.as_ref().unwrap();
+        assert_eq!(
+            (usage.input_tokens, usage.output_tokens),
+            (Some(40), Some(12))
+        );
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("Use \\\\d{4}") && !content.contains("old answer"));
+    }
+}
@@ -6,23 +6,75 @@
 //! - ClaudeCode: Active (single-provider: Anthropic)
 //! - OpenCode: Active (multi-provider)
 //! - Zed: Active (multi-provider)
+//! - Cursor: Active (multi-provider)
+//! - Aider: Active (multi-provider)
+//! - GeminiCLI: Active (single-provider: Google)
+//! - Continue: Active (multi-provider)
+//! - Copilot: Active (multi-provider)
+//! - ChatGPT: Active (single-provider: OpenAI, from data exports)
+//! - ClaudeAI: Active (single-provider: Anthropic, from data exports)
+//! - Windsurf: Active (multi-provider, exported Cascade trajectories)
+//! - Amp: Active (multi-provider)
+//! - JetBrains: Active (multi-provider, AI Assistant)
+//! - LMStudio: Active (single-provider: local models)
+//! - Ollama: Active (single-provider: local models, desktop app)
+//! - Custom: JSONL sources declared in the config (`custom:` field mappings)
+//! - Exec: external programs speaking the probe protocol (`command:`)
 //! - Antigravity: FROZEN (blocked by feasibility, may restart later)
 
+mod aider;
+mod amp;
+mod chatgpt;
+mod claudeai;
 mod claudecode;
+mod continuedev;
+mod copilot;
+mod cursor;
+mod custom;
+mod exec;
+mod gemini;
+mod imports;
+mod jetbrains;
+mod lmstudio;
+mod ollama;
 mod opencode;
+mod windsurf;
 mod zed;
 
 // Antigravity is frozen but kept for reference
 // mod antigravity;
 
+pub use aider::AiderProbe;
+pub use amp::AmpProbe;
+pub use chatgpt::ChatGptProbe;
+pub use claudeai::ClaudeAiProbe;
 pub use claudecode::ClaudeCodeProbe;
+pub use continuedev::ContinueProbe;
+pub use copilot::CopilotProbe;
+pub use cursor::CursorProbe;
+pub use custom::CustomProbe;
+pub use exec::ExecProbe;
+pub use gemini::GeminiCliProbe;
+pub use jetbrains::JetBrainsProbe;
+pub use lmstudio::LmStudioProbe;
+pub use ollama::OllamaProbe;
 pub use opencode::OpenCodeProbe;
+pub use windsurf::WindsurfProbe;
 pub use zed::ZedProbe;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::path::PathBuf;
-
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::analysis::enrich::estimate_tokens;
+use crate::analysis::permissions::Permission;
+use crate::analysis::IssueReference;
+use crate::error::Error;
 use crate::Config;
 
 /// Reference to a session's source location
@@ -32,6 +84,39 @@ pub struct SessionRef {
     pub source_path: PathBuf,
 }
 
+/// Modification time and size of a session's source, used to skip unchanged sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceFingerprint {
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime: i64,
+    pub size: u64,
+}
+
+impl SourceFingerprint {
+    /// Fingerprint of a single file or directory
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mtime = meta
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_millis() as i64;
+        Some(Self {
+            mtime,
+            size: meta.len(),
+        })
+    }
+
+    /// Combine with another source (latest mtime, total size)
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            mtime: self.mtime.max(other.mtime),
+            size: self.size + other.size,
+        }
+    }
+}
+
 /// Reference to content within a source file
 #[derive(Debug, Clone)]
 pub struct ContentRef {
@@ -40,7 +125,8 @@ pub struct ContentRef {
     pub byte_offset: Option<u64>,
     /// Line number for JSONL files
     pub line_number: Option<u32>,
-    /// Path to content file for JSON file sources (OpenCode)
+    /// Path to content file for JSON file sources (OpenCode), or the record holding
+    /// the message in database sources (Zed thread ID, ChatGPT conversation ID)
     pub content_path: Option<PathBuf>,
 }
 
@@ -55,6 +141,16 @@ impl ContentRef {
         }
     }
 
+    /// Create a content reference for the `index`th message of a database record
+    pub fn db_record(source_path: PathBuf, record_id: &str, index: u32) -> Self {
+        Self {
+            source_path,
+            byte_offset: None,
+            line_number: Some(index),
+            content_path: Some(PathBuf::from(record_id)),
+        }
+    }
+
     /// Create a content reference for JSON file-based sources
     pub fn json_file(source_path: PathBuf, content_path: PathBuf) -> Self {
         Self {
@@ -73,11 +169,83 @@ pub struct SessionMetadata {
     pub title: Option<String>,
     pub project_path: Option<String>,
     pub git_remote: Option<String>,
+    /// Source-native grouping key (OpenCode project hash, Claude Code project dir)
+    pub source_group: Option<String>,
     pub primary_provider: Option<String>,
     pub primary_model: Option<String>,
     pub first_timestamp: Option<DateTime<Utc>>,
     pub last_timestamp: Option<DateTime<Utc>>,
     pub messages: Vec<MessageMetadata>,
+    /// Issue/PR references found in message text
+    pub references: Vec<IssueReference>,
+    /// Language of the user's messages (ISO 639-1), when it can be told
+    pub language: Option<String>,
+    /// Git commits the source recorded for this session (e.g. Aider auto-commits)
+    pub commits: Vec<CommitRef>,
+    /// Context compactions the source recorded (Claude Code compact boundaries)
+    pub compactions: usize,
+    /// Source entries dropped while parsing, by reason
+    pub skipped: SkipCounts,
+}
+
+/// Source entries a probe could not index: counts by reason plus each entry's location
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkipCounts {
+    counts: BTreeMap<String, usize>,
+    records: Vec<SkippedRecord>,
+}
+
+/// A single source entry dropped while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRecord {
+    pub reason: String,
+    /// Where the entry lives, e.g. `path/to/file.jsonl:42`
+    pub location: String,
+}
+
+impl SkipCounts {
+    pub fn add(&mut self, reason: impl Into<String>, location: impl Into<String>) {
+        let reason = reason.into();
+        *self.counts.entry(reason.clone()).or_insert(0) += 1;
+        self.records.push(SkippedRecord {
+            reason,
+            location: location.into(),
+        });
+    }
+
+    pub fn merge(&mut self, other: &SkipCounts) {
+        for (reason, count) in &other.counts {
+            *self.counts.entry(reason.clone()).or_insert(0) += count;
+        }
+        self.records.extend(other.records.iter().cloned());
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Counts by reason, in reason order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(reason, count)| (reason.as_str(), *count))
+    }
+
+    pub fn records(&self) -> &[SkippedRecord] {
+        &self.records
+    }
+}
+
+/// Git commit recorded by a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitRef {
+    /// Abbreviated or full commit hash
+    pub hash: String,
+    pub message: Option<String>,
 }
 
 /// Extracted message metadata
@@ -93,6 +261,28 @@ pub struct MessageMetadata {
     pub has_thinking: bool,
     pub tool_uses: Vec<ToolUseMetadata>,
     pub token_usage: Option<TokenUsage>,
+    /// Parameters of the model call, when the source records them
+    pub request_params: Option<RequestParams>,
+    /// Kind of event within the role, e.g. `compact` for a context compaction
+    pub subtype: Option<String>,
+}
+
+/// Request parameters a source recorded for a model call
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestParams {
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<i64>,
+    pub system_prompt: Option<String>,
+}
+
+impl RequestParams {
+    /// SHA-256 (hex) of the system prompt; prompts are stored once per hash
+    pub fn system_prompt_hash(&self) -> Option<String> {
+        use sha2::{Digest, Sha256};
+        self.system_prompt
+            .as_ref()
+            .map(|prompt| hex::encode(Sha256::digest(prompt.as_bytes())))
+    }
 }
 
 /// Tool use metadata
@@ -101,6 +291,14 @@ pub struct ToolUseMetadata {
     pub tool_id: Option<String>,
     pub tool_name: String,
     pub has_result: bool,
+    /// The call came back with an error result
+    pub is_error: bool,
+    /// How the call was permitted, for sources that record approvals
+    pub permission: Option<Permission>,
+    /// Fingerprint of the normalized tool input (for loop detection)
+    pub input_hash: Option<String>,
+    /// File the call read or edited, as given in its input (see [`crate::analysis::files`])
+    pub file_path: Option<String>,
 }
 
 /// Token usage metadata
@@ -110,6 +308,9 @@ pub struct TokenUsage {
     pub output_tokens: Option<i64>,
     pub cache_read_tokens: Option<i64>,
     pub cache_creation_tokens: Option<i64>,
+    /// Spent on reasoning/thinking; already counted in `output_tokens`, which is
+    /// what these tokens are billed as
+    pub reasoning_tokens: Option<i64>,
 }
 
 /// Source type indicator
@@ -130,6 +331,97 @@ impl SourceType {
     }
 }
 
+/// What a source records, so output can say "unavailable" instead of showing zeros
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeCapabilities {
+    /// Messages carry token counts
+    pub supports_token_usage: bool,
+    /// Tool calls record whether (and how) they returned
+    pub supports_tool_results: bool,
+    /// Message content can be read back from the source
+    pub supports_content: bool,
+    /// Unchanged sessions can be skipped on re-extraction
+    pub supports_incremental: bool,
+}
+
+impl ProbeCapabilities {
+    /// Everything supported; the default for probes
+    pub const FULL: Self = Self {
+        supports_token_usage: true,
+        supports_tool_results: true,
+        supports_content: true,
+        supports_incremental: true,
+    };
+}
+
+impl Default for ProbeCapabilities {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// Estimated tokens of the `thinking` blocks in Anthropic-style content, for sources
+/// that keep the thinking but not its count; never more than the reported output
+pub fn thinking_tokens(blocks: &[Value], output_tokens: Option<i64>) -> Option<i64> {
+    let thinking: Vec<&str> = blocks
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("thinking"))
+        .filter_map(|b| b.get("thinking").and_then(|t| t.as_str()))
+        .collect();
+    if thinking.is_empty() {
+        return None;
+    }
+    let estimate: i64 = thinking.into_iter().map(estimate_tokens).sum();
+    Some(output_tokens.map_or(estimate, |output| estimate.min(output)))
+}
+
+/// LiteLLM provider prefixes, mapped to Chronicle provider ids
+const LITELLM_PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "anthropic"),
+    ("openai", "openai"),
+    ("text-completion-openai", "openai"),
+    ("gemini", "google"),
+    ("vertex_ai", "google"),
+    ("vertex_ai_beta", "google"),
+    ("xai", "xai"),
+    ("deepseek", "deepseek"),
+    ("mistral", "mistral"),
+    ("codestral", "mistral"),
+    ("ollama", "ollama"),
+    ("ollama_chat", "ollama"),
+    ("lm_studio", "lmstudio"),
+];
+
+/// Infer a model provider from a model name, for sources that only record the model.
+/// Accepts `provider/model` (LiteLLM style) as well as bare model names; other prefixes
+/// (routers and hosts like `openrouter/` or `bedrock/`) fall back to the rest of the name.
+pub fn infer_provider(model: &str) -> Option<String> {
+    let model = model.trim().to_lowercase();
+    if let Some((prefix, rest)) = model.split_once('/') {
+        return match LITELLM_PROVIDERS.iter().find(|(p, _)| *p == prefix) {
+            Some((_, provider)) => Some(provider.to_string()),
+            None => infer_provider(rest),
+        };
+    }
+
+    let provider = if model.contains("claude") {
+        "anthropic"
+    } else if model.starts_with("gpt")
+        || (model.starts_with('o') && model[1..].starts_with(|c: char| c.is_ascii_digit()))
+    {
+        "openai"
+    } else if model.contains("gemini") {
+        "google"
+    } else if model.contains("grok") {
+        "xai"
+    } else if model.contains("deepseek") {
+        "deepseek"
+    } else {
+        return None;
+    };
+    Some(provider.to_string())
+}
+
 /// Ingestion probe trait
 pub trait IngestionProbe: Send + Sync {
     /// Unique identifier: "{provider}:{source}" or "{source}:{source}" for multi-provider
@@ -150,6 +442,9 @@ pub trait IngestionProbe: Send + Sync {
     /// Check if this probe's data source exists
     fn is_available(&self) -> bool;
 
+    /// Where the probe reads from: its configured `base_path` or default location
+    fn base_path(&self) -> &Path;
+
     /// Discover sessions to index
     fn discover(&self) -> Result<Vec<SessionRef>>;
 
@@ -158,33 +453,139 @@ pub trait IngestionProbe: Send + Sync {
 
     /// Get raw content by reference (lazy load)
     fn get_content(&self, reference: &ContentRef) -> Result<String>;
+
+    /// Fingerprint of a session's source; sessions whose fingerprint is unchanged since the
+    /// last extraction are skipped. Defaults to the session's source file.
+    fn fingerprint(&self, session: &SessionRef) -> Option<SourceFingerprint> {
+        SourceFingerprint::of(&session.source_path)
+    }
+
+    /// Tool-specific settings for a project directory (permissions, trust, etc.),
+    /// merged into the project's metadata under this probe's source name
+    fn project_metadata(&self, _project_path: &str) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// What this source records
+    fn capabilities(&self) -> ProbeCapabilities {
+        ProbeCapabilities::FULL
+    }
+}
+
+/// Read a source file as text; content that isn't UTF-8 is an [`Error::ParseError`]
+pub(crate) fn read_source(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => Error::ParseError {
+            path: path.to_path_buf(),
+            line: None,
+            reason: "Not valid UTF-8".to_string(),
+        }
+        .into(),
+        _ => anyhow::Error::new(e).context(format!("Failed to read {}", path.display())),
+    })
+}
+
+/// Built-in probe IDs, in registration order
+pub const BUILTIN_PROBES: &[&str] = &[
+    "claude:ClaudeCode",
+    "opencode:OpenCode",
+    "zed:Zed",
+    "cursor:Cursor",
+    "aider:Aider",
+    "gemini:GeminiCLI",
+    "continue:Continue",
+    "copilot:Copilot",
+    "openai:ChatGPT",
+    "claude:ClaudeAI",
+    "windsurf:Windsurf",
+    "amp:Amp",
+    "jetbrains:JetBrains",
+    "local:LMStudio",
+    "local:Ollama",
+];
+
+/// Build a built-in probe reading from `path`, or its default location
+pub fn builtin_probe(id: &str, path: Option<PathBuf>) -> Option<Box<dyn IngestionProbe>> {
+    Some(match id {
+        // Single-provider: Anthropic
+        "claude:ClaudeCode" => Box::new(ClaudeCodeProbe::new(path)),
+        // Multi-provider
+        "opencode:OpenCode" => Box::new(OpenCodeProbe::new(path)),
+        "zed:Zed" => Box::new(ZedProbe::new(path)),
+        "cursor:Cursor" => Box::new(CursorProbe::new(path)),
+        "aider:Aider" => Box::new(AiderProbe::new(path)),
+        // Single-provider: Google
+        "gemini:GeminiCLI" => Box::new(GeminiCliProbe::new(path)),
+        // Multi-provider
+        "continue:Continue" => Box::new(ContinueProbe::new(path)),
+        "copilot:Copilot" => Box::new(CopilotProbe::new(path)),
+        // Single-provider data exports: OpenAI, Anthropic
+        "openai:ChatGPT" => Box::new(ChatGptProbe::new(path)),
+        "claude:ClaudeAI" => Box::new(ClaudeAiProbe::new(path)),
+        // Multi-provider
+        "windsurf:Windsurf" => Box::new(WindsurfProbe::new(path)),
+        "amp:Amp" => Box::new(AmpProbe::new(path)),
+        "jetbrains:JetBrains" => Box::new(JetBrainsProbe::new(path)),
+        // Single-provider: models run locally
+        "local:LMStudio" => Box::new(LmStudioProbe::new(path)),
+        "local:Ollama" => Box::new(OllamaProbe::new(path)),
+        _ => return None,
+    })
 }
 
 /// Registry of available probes
 pub struct ProbeRegistry {
     probes: Vec<Box<dyn IngestionProbe>>,
+    /// Configured probes that were ignored or couldn't be set up
+    warnings: Vec<String>,
 }
 
 impl ProbeRegistry {
     pub fn new(config: &Config) -> Self {
-        let mut registry = Self { probes: vec![] };
-
-        // Register Claude Code probe (single-provider: Anthropic)
-        if config.is_probe_enabled("claude:ClaudeCode") {
-            let claudecode = ClaudeCodeProbe::new(config.probe_path("claude:ClaudeCode"));
-            registry.register(Box::new(claudecode));
+        let mut registry = Self {
+            probes: vec![],
+            warnings: vec![],
+        };
+
+        for id in BUILTIN_PROBES {
+            if config.is_probe_enabled(id) {
+                if let Some(probe) = builtin_probe(id, config.probe_path(id)) {
+                    registry.register(probe);
+                }
+            }
         }
 
-        // Register OpenCode probe (multi-provider)
-        if config.is_probe_enabled("opencode:OpenCode") {
-            let opencode = OpenCodeProbe::new(config.probe_path("opencode:OpenCode"));
-            registry.register(Box::new(opencode));
+        // Sources declared in the config with field mappings
+        for (id, path, custom) in config.custom_probes() {
+            if BUILTIN_PROBES.contains(&id) {
+                registry.warnings.push(format!(
+                    "Ignoring custom mappings for built-in probe {}",
+                    id
+                ));
+                continue;
+            }
+            match CustomProbe::new(id, path, custom.clone()) {
+                Ok(probe) => registry.register(Box::new(probe)),
+                Err(e) => registry
+                    .warnings
+                    .push(format!("Skipping custom probe {}: {:#}", id, e)),
+            }
         }
 
-        // Register Zed probe (multi-provider)
-        if config.is_probe_enabled("zed:Zed") {
-            let zed = ZedProbe::new(config.probe_path("zed:Zed"));
-            registry.register(Box::new(zed));
+        // Probes implemented by external programs
+        for (id, path, command) in config.exec_probes() {
+            if BUILTIN_PROBES.contains(&id) {
+                registry
+                    .warnings
+                    .push(format!("Ignoring probe command for built-in probe {}", id));
+                continue;
+            }
+            match ExecProbe::new(id, path, command) {
+                Ok(probe) => registry.register(Box::new(probe)),
+                Err(e) => registry
+                    .warnings
+                    .push(format!("Skipping probe command {}: {:#}", id, e)),
+            }
         }
 
         // Antigravity is FROZEN - not registered
@@ -198,6 +599,11 @@ impl ProbeRegistry {
         self.probes.push(probe);
     }
 
+    /// Problems with the configured probes found while building the registry
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     pub fn available_probes(&self) -> Vec<&dyn IngestionProbe> {
         self.probes
             .iter()
@@ -217,3 +623,62 @@ impl ProbeRegistry {
             .map(|p| p.as_ref())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_provider() {
+        assert_eq!(
+            infer_provider("claude-4-sonnet-thinking").as_deref(),
+            Some("anthropic")
+        );
+        assert_eq!(infer_provider("gpt-5").as_deref(), Some("openai"));
+        assert_eq!(infer_provider("o3").as_deref(), Some("openai"));
+        assert_eq!(
+            infer_provider("gemini/gemini-2.5-pro").as_deref(),
+            Some("google")
+        );
+        assert_eq!(
+            infer_provider("vertex_ai/claude-3-5-sonnet").as_deref(),
+            Some("google")
+        );
+        assert_eq!(
+            infer_provider("openrouter/anthropic/claude-3.5-sonnet").as_deref(),
+            Some("anthropic")
+        );
+        assert_eq!(infer_provider("azure/gpt-4o").as_deref(), Some("openai"));
+        assert_eq!(infer_provider("groq/llama3-70b"), None);
+        assert_eq!(infer_provider("default"), None);
+    }
+
+    #[test]
+    fn test_thinking_tokens_estimate() {
+        let blocks = [
+            serde_json::json!({ "type": "thinking", "thinking": "a".repeat(400) }),
+            serde_json::json!({ "type": "text", "text": "b".repeat(400) }),
+        ];
+        assert_eq!(thinking_tokens(&blocks, None), Some(100));
+        assert_eq!(thinking_tokens(&blocks, Some(40)), Some(40));
+        assert_eq!(thinking_tokens(&blocks[1..], Some(40)), None);
+    }
+
+    #[test]
+    fn test_skip_counts_merge() {
+        let mut probe = SkipCounts::default();
+        let mut session = SkipCounts::default();
+        session.add("invalid JSON line", "a.jsonl:3");
+        session.add("invalid JSON line", "a.jsonl:9");
+        session.add("unknown message type 'info'", "a.json");
+        probe.merge(&session);
+        probe.merge(&session);
+        assert_eq!(probe.total(), 6);
+        assert_eq!(
+            probe.iter().collect::<Vec<_>>(),
+            vec![("invalid JSON line", 4), ("unknown message type 'info'", 2)]
+        );
+        assert_eq!(probe.records().len(), 6);
+        assert_eq!(probe.records()[1].location, "a.jsonl:9");
+    }
+}
@@ -0,0 +1,363 @@
+//! Ollama probe implementation
+//!
+//! Extracts chats from the Ollama desktop app.
+//! Data format: SQLite database at ~/Library/Application Support/Ollama/db.sqlite
+//!   - chats: id, title, created_at
+//!   - messages: id, chat_id, role, content, thinking, model_name, created_at
+//!   - tool_calls: message_id, function_name, function_arguments, function_result
+//!
+//! The CLI's ~/.ollama/history keeps only the prompts typed into `ollama run`, with no
+//! answers or dates, so it is not indexed.
+//!
+//! Models run on the user's machine, so the provider is recorded as `local`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::{
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, ToolUseMetadata,
+};
+
+pub struct OllamaProbe {
+    db_path: PathBuf,
+}
+
+/// A row of the messages table
+struct MessageRow {
+    id: i64,
+    role: String,
+    content: String,
+    thinking: Option<String>,
+    model: Option<String>,
+    created_at: Option<String>,
+}
+
+/// A row of the tool_calls table
+struct ToolCallRow {
+    name: String,
+    arguments: Option<String>,
+    result: Option<String>,
+}
+
+impl OllamaProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let db_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join("Library/Application Support/Ollama/db.sqlite")
+        });
+        Self { db_path }
+    }
+
+    /// Open database in read-only mode
+    fn open_db(&self) -> Result<Connection> {
+        Connection::open_with_flags(&self.db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open Ollama database")
+    }
+
+    fn messages(conn: &Connection, chat_id: &str) -> Result<Vec<MessageRow>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, role, content, thinking, model_name, created_at
+             FROM messages WHERE chat_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map([chat_id], |row| {
+            Ok(MessageRow {
+                id: row.get(0)?,
+                role: row.get(1)?,
+                content: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                thinking: row.get(3)?,
+                model: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn tool_calls(conn: &Connection, message_id: i64) -> Result<Vec<ToolCallRow>> {
+        let mut stmt = conn.prepare(
+            "SELECT function_name, function_arguments, function_result
+             FROM tool_calls WHERE message_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map([message_id], |row| {
+            Ok(ToolCallRow {
+                name: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                arguments: row.get(1)?,
+                result: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// SQLite timestamps come as `2025-08-01 10:00:00.123+00:00` or RFC 3339
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
+
+impl IngestionProbe for OllamaProbe {
+    fn id(&self) -> &str {
+        "local:Ollama"
+    }
+
+    fn provider(&self) -> &str {
+        "local"
+    }
+
+    fn source(&self) -> &str {
+        "Ollama"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Single
+    }
+
+    fn description(&self) -> &str {
+        "Ollama desktop app chats (local models)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.db_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Every chat lives in one database, and Ollama keeps no token counts
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: false,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        if !self.is_available() {
+            return Ok(vec![]);
+        }
+
+        let conn = self.open_db()?;
+        let mut stmt = conn.prepare("SELECT id FROM chats")?;
+        let ids = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut sessions = vec![];
+        for id in ids {
+            sessions.push(SessionRef {
+                id: id?,
+                source_path: self.db_path.clone(),
+            });
+        }
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let conn = self.open_db()?;
+        let (chat_title, created_at): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT title, created_at FROM chats WHERE id = ?1",
+                [&session.id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .context("Failed to query chat")?;
+
+        let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = chat_title.filter(|t| !t.trim().is_empty());
+
+        for (idx, row) in Self::messages(&conn, &session.id)?.into_iter().enumerate() {
+            if !matches!(row.role.as_str(), "user" | "assistant" | "system" | "tool") {
+                skipped.add(
+                    format!("unknown message role '{}'", row.role),
+                    format!(
+                        "{} chat {} message {}",
+                        self.db_path.display(),
+                        session.id,
+                        row.id
+                    ),
+                );
+                continue;
+            }
+
+            session_refs.extend(references::detect(&row.content));
+            if row.role == "user" {
+                language.add(&row.content);
+                if title.is_none() {
+                    title = row
+                        .content
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_string());
+                }
+            }
+
+            let model = (row.role == "assistant")
+                .then_some(row.model)
+                .flatten()
+                .filter(|m| !m.is_empty());
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+            }
+
+            let tool_uses: Vec<ToolUseMetadata> = Self::tool_calls(&conn, row.id)?
+                .into_iter()
+                .map(|call| {
+                    let arguments = call
+                        .arguments
+                        .as_deref()
+                        .and_then(|a| serde_json::from_str::<Value>(a).ok());
+                    ToolUseMetadata {
+                        tool_id: None,
+                        tool_name: call.name,
+                        has_result: call.result.is_some(),
+                        is_error: false,
+                        permission: None,
+                        input_hash: arguments.as_ref().map(loops::fingerprint),
+                        file_path: arguments.as_ref().and_then(files::touched_file),
+                    }
+                })
+                .collect();
+
+            messages.push(MessageMetadata {
+                uuid: Some(row.id.to_string()),
+                role: row.role,
+                provider_id: model.as_ref().map(|_| "local".to_string()),
+                model,
+                timestamp: row.created_at.as_deref().and_then(parse_time),
+                content_ref: ContentRef::db_record(self.db_path.clone(), &session.id, idx as u32),
+                has_tool_use: !tool_uses.is_empty(),
+                has_thinking: row.thinking.is_some_and(|t| !t.is_empty()),
+                tool_uses,
+                token_usage: None,
+                request_params: None,
+                subtype: None,
+            });
+        }
+
+        let first_timestamp = created_at
+            .as_deref()
+            .and_then(parse_time)
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = messages
+            .iter()
+            .rev()
+            .find_map(|m| m.timestamp)
+            .or(first_timestamp);
+        let primary_model = model_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(model, _)| model);
+
+        Ok(SessionMetadata {
+            external_id: session.id.clone(),
+            title,
+            project_path: None,
+            git_remote: None,
+            source_group: None,
+            primary_provider: primary_model.as_ref().map(|_| "local".to_string()),
+            primary_model,
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        // The message is the `line_number`th of the chat named by `content_path`
+        let chat_id = reference
+            .content_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .context("Ollama message reference has no chat ID")?;
+        let conn = self.open_db()?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let row = Self::messages(&conn, chat_id)?
+            .into_iter()
+            .nth(index)
+            .context("Ollama message index out of range")?;
+
+        let mut items = vec![];
+        if let Some(thinking) = row.thinking.filter(|t| !t.is_empty()) {
+            items.push(json!({ "type": "thinking", "thinking": thinking }));
+        }
+        if !row.content.is_empty() {
+            items.push(json!({ "type": "text", "text": row.content }));
+        }
+        for call in Self::tool_calls(&conn, row.id)? {
+            let input = call
+                .arguments
+                .as_deref()
+                .and_then(|a| serde_json::from_str::<Value>(a).ok());
+            items.push(json!({ "type": "tool_use", "name": call.name, "input": input }));
+        }
+        Ok(json!({ "content": items }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_chats_from_app_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("db.sqlite");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE chats (id TEXT PRIMARY KEY, title TEXT, created_at TIMESTAMP);
+             CREATE TABLE messages (id INTEGER PRIMARY KEY, chat_id TEXT, role TEXT,
+                 content TEXT, thinking TEXT, model_name TEXT, created_at TIMESTAMP);
+             CREATE TABLE tool_calls (id INTEGER PRIMARY KEY, message_id INTEGER,
+                 function_name TEXT, function_arguments TEXT, function_result TEXT);
+             INSERT INTO chats VALUES ('c1', '', '2025-10-01 09:00:00.5+00:00');
+             INSERT INTO messages VALUES
+                 (1, 'c1', 'user', 'What is in notes.txt?', NULL, NULL, '2025-10-01 09:00:01'),
+                 (2, 'c1', 'assistant', '', 'need to read it', 'gpt-oss:20b', '2025-10-01 09:00:04'),
+                 (3, 'c1', 'tool', 'hello', NULL, NULL, '2025-10-01 09:00:05');
+             INSERT INTO tool_calls VALUES (1, 2, 'read_file', '{\"path\":\"notes.txt\"}', 'hello');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let probe = OllamaProbe::new(Some(db_path));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("What is in notes.txt?"));
+        assert_eq!(metadata.primary_provider.as_deref(), Some("local"));
+        assert_eq!(metadata.primary_model.as_deref(), Some("gpt-oss:20b"));
+        assert!(metadata.first_timestamp.is_some() && metadata.last_timestamp.is_some());
+        assert_eq!(metadata.messages.len(), 3);
+        let answer = &metadata.messages[1];
+        assert!(answer.has_thinking);
+        assert_eq!(answer.tool_uses[0].file_path.as_deref(), Some("notes.txt"));
+        assert!(answer.tool_uses[0].has_result);
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("need to read it") && content.contains("read_file"));
+    }
+}
@@ -12,13 +12,17 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+use crate::error::Error;
 
 use super::{
-    ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef, SourceType,
-    TokenUsage, ToolUseMetadata,
+    read_source, ContentRef, IngestionProbe, MessageMetadata, RequestParams, SessionMetadata,
+    SessionRef, SkipCounts, SourceFingerprint, SourceType, TokenUsage, ToolUseMetadata,
 };
 
 pub struct OpenCodeProbe {
@@ -28,9 +32,10 @@ pub struct OpenCodeProbe {
 // OpenCode data structures
 #[derive(Debug, Deserialize)]
 struct _OpenCodeSession {
+    #[serde(rename = "id")]
     _id: String,
     #[serde(rename = "projectID")]
-    _project_id: Option<String>,
+    project_id: Option<String>,
     directory: Option<String>,
     title: Option<String>,
     time: Option<SessionTime>,
@@ -54,6 +59,8 @@ struct OpenCodeMessage {
     model_id: Option<String>,
     model: Option<MessageModel>,
     time: Option<MessageTime>,
+    /// System prompt sent with assistant requests (older versions: a list of strings)
+    system: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,6 +78,7 @@ struct MessageTime {
 
 #[derive(Debug, Deserialize)]
 struct OpenCodePart {
+    #[serde(rename = "id")]
     _id: String,
     #[serde(rename = "sessionID")]
     _session_id: String,
@@ -79,7 +87,7 @@ struct OpenCodePart {
     #[serde(rename = "type")]
     part_type: String,
     // For text parts
-    _text: Option<String>,
+    text: Option<String>,
     // For tool parts
     tool: Option<String>,
     #[serde(rename = "callID")]
@@ -92,13 +100,14 @@ struct OpenCodePart {
 #[derive(Debug, Deserialize)]
 struct ToolState {
     status: Option<String>,
+    input: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct TokenInfo {
     input: Option<i64>,
     output: Option<i64>,
-    _reasoning: Option<i64>,
+    reasoning: Option<i64>,
     cache: Option<CacheInfo>,
 }
 
@@ -181,6 +190,10 @@ impl IngestionProbe for OpenCodeProbe {
         self.base_path.exists() && self.session_dir().exists()
     }
 
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
     fn discover(&self) -> Result<Vec<SessionRef>> {
         let mut sessions = vec![];
         let session_dir = self.session_dir();
@@ -224,10 +237,9 @@ impl IngestionProbe for OpenCodeProbe {
 
     fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
         // Read session file
-        let session_content =
-            fs::read_to_string(&session.source_path).context("Failed to read session file")?;
-        let session_data: _OpenCodeSession =
-            serde_json::from_str(&session_content).context("Failed to parse session JSON")?;
+        let session_content = read_source(&session.source_path)?;
+        let session_data: _OpenCodeSession = serde_json::from_str(&session_content)
+            .map_err(|e| Error::invalid_json(&session.source_path, "OpenCode session JSON", &e))?;
 
         // Get timestamps from session
         let first_timestamp = session_data
@@ -243,6 +255,16 @@ impl IngestionProbe for OpenCodeProbe {
 
         // Get project path (directory field, or resolve from project_id)
         let project_path = session_data.directory.clone();
+
+        // OpenCode groups sessions under session/{project_hash}/
+        let source_group = session_data.project_id.clone().or_else(|| {
+            session
+                .source_path
+                .parent()
+                .and_then(|p| p.file_name())
+                .and_then(|n| n.to_str())
+                .map(String::from)
+        });
         let git_remote = project_path
             .as_ref()
             .and_then(|p| Self::extract_git_remote(p));
@@ -250,8 +272,15 @@ impl IngestionProbe for OpenCodeProbe {
         // Read messages for this session
         let message_session_dir = self.message_dir().join(&session.id);
         let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
         let mut provider_counts: HashMap<String, usize> = HashMap::new();
         let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+
+        if let Some(ref title) = session_data.title {
+            session_refs.extend(references::detect(title));
+        }
 
         if message_session_dir.exists() {
             let mut msg_files: Vec<_> = fs::read_dir(&message_session_dir)?
@@ -273,7 +302,10 @@ impl IngestionProbe for OpenCodeProbe {
                 let msg_content = fs::read_to_string(&msg_path)?;
                 let msg_data: OpenCodeMessage = match serde_json::from_str(&msg_content) {
                     Ok(m) => m,
-                    Err(_) => continue,
+                    Err(_) => {
+                        skipped.add("invalid message JSON", msg_path.display().to_string());
+                        continue;
+                    }
                 };
 
                 // Extract provider and model
@@ -291,6 +323,16 @@ impl IngestionProbe for OpenCodeProbe {
                     *model_counts.entry(model.clone()).or_insert(0) += 1;
                 }
 
+                let request_params =
+                    msg_data
+                        .system
+                        .as_ref()
+                        .and_then(system_prompt)
+                        .map(|prompt| RequestParams {
+                            system_prompt: Some(prompt),
+                            ..Default::default()
+                        });
+
                 // Get message timestamp
                 let timestamp = msg_data
                     .time
@@ -330,11 +372,20 @@ impl IngestionProbe for OpenCodeProbe {
                         let part_content = fs::read_to_string(&part_path)?;
                         let part_data: OpenCodePart = match serde_json::from_str(&part_content) {
                             Ok(p) => p,
-                            Err(_) => continue,
+                            Err(_) => {
+                                skipped.add("invalid part JSON", part_path.display().to_string());
+                                continue;
+                            }
                         };
 
                         match part_data.part_type.as_str() {
                             "text" => {
+                                if let Some(ref text) = part_data.text {
+                                    session_refs.extend(references::detect(text));
+                                    if role == "user" {
+                                        language.add(text);
+                                    }
+                                }
                                 if first_text_part_path.is_none() {
                                     first_text_part_path = Some(part_path.clone());
                                 }
@@ -351,13 +402,35 @@ impl IngestionProbe for OpenCodeProbe {
                                         .as_ref()
                                         .map(|s| s.status.as_deref() == Some("completed"))
                                         .unwrap_or(false),
+                                    is_error: part_data
+                                        .state
+                                        .as_ref()
+                                        .is_some_and(|s| s.status.as_deref() == Some("error")),
+                                    permission: None,
+                                    input_hash: part_data
+                                        .state
+                                        .as_ref()
+                                        .and_then(|s| s.input.as_ref())
+                                        .map(loops::fingerprint),
+                                    file_path: part_data
+                                        .state
+                                        .as_ref()
+                                        .and_then(|s| s.input.as_ref())
+                                        .and_then(files::touched_file),
                                 });
                             }
                             "step-finish" => {
                                 if let Some(tokens) = part_data.tokens {
+                                    // Reasoning is reported beside the output but
+                                    // billed as output
                                     token_usage = Some(TokenUsage {
                                         input_tokens: tokens.input,
-                                        output_tokens: tokens.output,
+                                        output_tokens: match tokens.reasoning {
+                                            Some(reasoning) => {
+                                                Some(tokens.output.unwrap_or(0) + reasoning)
+                                            }
+                                            None => tokens.output,
+                                        },
                                         cache_read_tokens: tokens
                                             .cache
                                             .as_ref()
@@ -366,13 +439,17 @@ impl IngestionProbe for OpenCodeProbe {
                                             .cache
                                             .as_ref()
                                             .and_then(|c| c.write),
+                                        reasoning_tokens: tokens.reasoning,
                                     });
                                 }
                             }
-                            "thinking" => {
+                            "thinking" | "reasoning" => {
                                 has_thinking = true;
                             }
-                            _ => {}
+                            other => skipped.add(
+                                format!("unhandled part type '{}'", other),
+                                part_path.display().to_string(),
+                            ),
                         }
                     }
                 }
@@ -395,6 +472,8 @@ impl IngestionProbe for OpenCodeProbe {
                     has_thinking,
                     tool_uses,
                     token_usage,
+                    request_params,
+                    subtype: None,
                 });
             }
         }
@@ -415,14 +494,33 @@ impl IngestionProbe for OpenCodeProbe {
             title: session_data.title,
             project_path,
             git_remote,
+            source_group,
             primary_provider,
             primary_model,
             first_timestamp,
             last_timestamp,
             messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
         })
     }
 
+    fn fingerprint(&self, session: &SessionRef) -> Option<SourceFingerprint> {
+        // Messages live outside the session file, so include every message file
+        let mut fingerprint = SourceFingerprint::of(&session.source_path)?;
+        if let Ok(entries) = fs::read_dir(self.message_dir().join(&session.id)) {
+            for entry in entries.flatten() {
+                if let Some(message) = SourceFingerprint::of(&entry.path()) {
+                    fingerprint = fingerprint.combine(message);
+                }
+            }
+        }
+        Some(fingerprint)
+    }
+
     fn get_content(&self, reference: &ContentRef) -> Result<String> {
         // For OpenCode, content is in separate part files
         if let Some(content_path) = &reference.content_path {
@@ -450,3 +548,17 @@ impl IngestionProbe for OpenCodeProbe {
         fs::read_to_string(&reference.source_path).context("Failed to read content")
     }
 }
+
+/// System prompt text from a message's `system` field (a string or list of strings)
+fn system_prompt(system: &Value) -> Option<String> {
+    let text = match system {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => return None,
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
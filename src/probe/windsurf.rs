@@ -0,0 +1,513 @@
+//! Windsurf probe implementation
+//!
+//! Extracts Cascade (Windsurf's agent) conversations.
+//! Data format: ~/.codeium/windsurf/cascade/
+//!   - <cascade-id>.json: one conversation, as written by "Export trajectory" and by
+//!     Windsurf builds that keep plain-text history
+//!     {cascadeId, summary, createdTime, lastModifiedTime,
+//!     workspaces: [{workspaceFolderAbsoluteUri, repository: {gitOriginUrl}}],
+//!     trajectory: {steps: [{type, metadata: {createdAt, generatorModel}, ...}]}}
+//!   - <cascade-id>.pb: the live store, encrypted by Windsurf; not readable, so only
+//!     conversations exported as JSON are indexed
+//!
+//! Each step is one event: a user prompt (`userInput`), a model answer
+//! (`plannerResponse`, with optional thinking) or a tool action (`viewFile`,
+//! `codeAction`, `runCommand`, ...). Tool actions become tool uses on the answer that
+//! issued them.
+//!
+//! Windsurf is a multi-provider source: each answer records its generating model.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
+
+use super::cursor::file_uri_to_path;
+use super::{
+    infer_provider, ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities,
+    SessionMetadata, SessionRef, SkipCounts, SourceType, ToolUseMetadata,
+};
+
+const STEP_PREFIX: &str = "CORTEX_STEP_TYPE_";
+
+pub struct WindsurfProbe {
+    base_path: PathBuf,
+}
+
+// Windsurf data structures (cascade/<id>.json)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cascade {
+    cascade_id: Option<String>,
+    /// Title Windsurf generates for the conversation
+    summary: Option<String>,
+    created_time: Option<String>,
+    last_modified_time: Option<String>,
+    #[serde(default)]
+    workspaces: Vec<Workspace>,
+    #[serde(default)]
+    trajectory: Trajectory,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Workspace {
+    workspace_folder_absolute_uri: Option<String>,
+    repository: Option<Repository>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    git_origin_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Trajectory {
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    /// e.g. CORTEX_STEP_TYPE_USER_INPUT
+    #[serde(rename = "type")]
+    step_type: String,
+    /// 'CORTEX_STEP_STATUS_DONE', '..._ERROR', '..._CANCELED'
+    status: Option<String>,
+    #[serde(default)]
+    metadata: StepMetadata,
+    /// The step's payload, keyed by its camelCase kind (`userInput`, `viewFile`, ...)
+    #[serde(flatten)]
+    payload: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StepMetadata {
+    created_at: Option<String>,
+    generator_model: Option<String>,
+}
+
+/// What a step contributes to the conversation
+enum StepKind<'a> {
+    User(String),
+    Answer { text: String, thinking: bool },
+    Tool(&'a str),
+    Other,
+}
+
+impl Step {
+    /// Step type without the common prefix, e.g. VIEW_FILE
+    fn kind_name(&self) -> &str {
+        self.step_type
+            .strip_prefix(STEP_PREFIX)
+            .unwrap_or(&self.step_type)
+    }
+
+    /// Payload of the step: the first object value besides the envelope fields
+    fn body(&self) -> Option<&Value> {
+        self.payload.values().find(|v| v.is_object())
+    }
+
+    fn kind(&self) -> StepKind<'_> {
+        let text = |key: &str| {
+            self.body()
+                .and_then(|b| b.get(key))
+                .and_then(|t| t.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        match self.kind_name() {
+            "USER_INPUT" => StepKind::User(text("userResponse")),
+            "PLANNER_RESPONSE" => StepKind::Answer {
+                text: text("response"),
+                thinking: !text("thinking").is_empty(),
+            },
+            "CHECKPOINT" | "MEMORY" | "EPHEMERAL_MESSAGE" | "ERROR_MESSAGE" => StepKind::Other,
+            _ if self.body().is_some() => StepKind::Tool(self.kind_name()),
+            _ => StepKind::Other,
+        }
+    }
+
+    fn status_is(&self, suffix: &str) -> bool {
+        self.status.as_deref().is_some_and(|s| s.ends_with(suffix))
+    }
+}
+
+impl WindsurfProbe {
+    pub fn new(custom_path: Option<PathBuf>) -> Self {
+        let base_path = custom_path.unwrap_or_else(|| {
+            let home = dirs::home_dir().unwrap_or_default();
+            home.join(".codeium/windsurf/cascade")
+        });
+        Self { base_path }
+    }
+
+    fn read_cascade(path: &Path) -> Result<Cascade> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse Windsurf Cascade JSON")
+    }
+
+    /// Normalize a step into the content-array shape `read` understands
+    fn step_content(step: &Step) -> Value {
+        let body = step.body();
+        let field = |key: &str| body.and_then(|b| b.get(key)).and_then(|v| v.as_str());
+        let mut items = vec![];
+        match step.kind() {
+            StepKind::User(text) => items.push(json!({ "type": "text", "text": text })),
+            StepKind::Answer { text, .. } => {
+                if let Some(thinking) = field("thinking").filter(|t| !t.is_empty()) {
+                    items.push(json!({ "type": "thinking", "thinking": thinking }));
+                }
+                items.push(json!({ "type": "text", "text": text }));
+            }
+            StepKind::Tool(name) => {
+                items.push(json!({ "type": "tool_use", "name": tool_name(name), "input": body }))
+            }
+            StepKind::Other => {}
+        }
+        json!({ "content": items })
+    }
+}
+
+/// VIEW_FILE -> view_file
+fn tool_name(kind: &str) -> String {
+    kind.to_lowercase()
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// File a tool step names: `absolutePathUri` (view_file), `filePath`, or a code
+/// action's target under `actionSpec`
+fn step_file(body: &Value) -> Option<String> {
+    let uri = body
+        .get("absolutePathUri")
+        .or_else(|| body.pointer("/actionSpec/createFile/absoluteUri"))
+        .or_else(|| body.pointer("/actionSpec/command/file/absoluteUri"))
+        .and_then(|v| v.as_str());
+    uri.map(file_uri_to_path)
+        .or_else(|| files::touched_file(body))
+}
+
+impl IngestionProbe for WindsurfProbe {
+    fn id(&self) -> &str {
+        "windsurf:Windsurf"
+    }
+
+    fn provider(&self) -> &str {
+        "windsurf"
+    }
+
+    fn source(&self) -> &str {
+        "Windsurf"
+    }
+
+    fn source_type(&self) -> SourceType {
+        SourceType::Multi
+    }
+
+    fn description(&self) -> &str {
+        "Windsurf Cascade agent (multi-provider)"
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_path.exists()
+    }
+
+    fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Trajectories don't record token usage
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: true,
+        }
+    }
+
+    fn discover(&self) -> Result<Vec<SessionRef>> {
+        let mut sessions = vec![];
+
+        if !self.base_path.exists() {
+            return Ok(sessions);
+        }
+
+        for entry in std::fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            sessions.push(SessionRef {
+                id,
+                source_path: path,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn extract_metadata(&self, session: &SessionRef) -> Result<SessionMetadata> {
+        let cascade = Self::read_cascade(&session.source_path)?;
+
+        let mut messages: Vec<MessageMetadata> = vec![];
+        let mut skipped = SkipCounts::default();
+        let mut model_counts: HashMap<String, usize> = HashMap::new();
+        let mut provider_counts: HashMap<String, usize> = HashMap::new();
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
+        let mut title = cascade.summary.clone().filter(|t| !t.trim().is_empty());
+        let mut last_model: Option<String> = None;
+
+        for (idx, step) in cascade.trajectory.steps.iter().enumerate() {
+            let content_ref = ContentRef {
+                source_path: session.source_path.clone(),
+                byte_offset: None,
+                line_number: Some(idx as u32),
+                content_path: None,
+            };
+            let timestamp = parse_time(step.metadata.created_at.as_deref());
+            let model = step.metadata.generator_model.clone().or(last_model.clone());
+
+            let (role, has_thinking) = match step.kind() {
+                StepKind::User(text) => {
+                    session_refs.extend(references::detect(&text));
+                    language.add(&text);
+                    if title.is_none() {
+                        title = text
+                            .lines()
+                            .find(|l| !l.trim().is_empty())
+                            .map(|l| l.trim().to_string());
+                    }
+                    ("user", false)
+                }
+                StepKind::Answer { text, thinking } => {
+                    session_refs.extend(references::detect(&text));
+                    ("assistant", thinking)
+                }
+                StepKind::Tool(kind) => {
+                    let body = step.body().cloned().unwrap_or_default();
+                    let tool = ToolUseMetadata {
+                        tool_id: None,
+                        tool_name: tool_name(kind),
+                        has_result: step.status_is("DONE") || step.status_is("ERROR"),
+                        is_error: step.status_is("ERROR"),
+                        permission: None,
+                        input_hash: Some(loops::fingerprint(&body)),
+                        file_path: step_file(&body),
+                    };
+                    // Actions follow the answer that planned them
+                    if messages.last().is_none_or(|m| m.role != "assistant") {
+                        messages.push(answer_message(model, timestamp, content_ref));
+                    }
+                    let answer = messages.last_mut().expect("answer pushed above");
+                    answer.has_tool_use = true;
+                    answer.tool_uses.push(tool);
+                    continue;
+                }
+                StepKind::Other => {
+                    if step.body().is_none() {
+                        skipped.add(
+                            format!("unknown step type '{}'", step.step_type),
+                            format!("{} step {}", session.source_path.display(), idx),
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            let model = (role == "assistant").then_some(model).flatten();
+            if let Some(ref model) = model {
+                *model_counts.entry(model.clone()).or_insert(0) += 1;
+                if let Some(provider) = infer_provider(model) {
+                    *provider_counts.entry(provider).or_insert(0) += 1;
+                }
+                last_model = Some(model.clone());
+            }
+            let mut message = answer_message(model, timestamp, content_ref);
+            message.role = role.to_string();
+            message.has_thinking = has_thinking;
+            messages.push(message);
+        }
+
+        let first_timestamp = parse_time(cascade.created_time.as_deref())
+            .or_else(|| messages.iter().find_map(|m| m.timestamp));
+        let last_timestamp = parse_time(cascade.last_modified_time.as_deref())
+            .or_else(|| messages.iter().rev().find_map(|m| m.timestamp))
+            .or(first_timestamp);
+        let workspace = cascade.workspaces.first();
+
+        Ok(SessionMetadata {
+            external_id: cascade.cascade_id.unwrap_or(session.id.clone()),
+            title,
+            project_path: workspace
+                .and_then(|w| w.workspace_folder_absolute_uri.as_deref())
+                .map(file_uri_to_path),
+            git_remote: workspace
+                .and_then(|w| w.repository.as_ref())
+                .and_then(|r| r.git_origin_url.clone()),
+            source_group: None,
+            primary_provider: provider_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(provider, _)| provider),
+            primary_model: model_counts
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(model, _)| model),
+            first_timestamp,
+            last_timestamp,
+            messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
+        })
+    }
+
+    fn get_content(&self, reference: &ContentRef) -> Result<String> {
+        let cascade = Self::read_cascade(&reference.source_path)?;
+        let index = reference.line_number.unwrap_or(0) as usize;
+        let steps = &cascade.trajectory.steps;
+        let step = steps
+            .get(index)
+            .context("Windsurf step index out of range")?;
+        let mut content = Self::step_content(step);
+        // An answer's tool actions are the steps that follow it
+        if matches!(step.kind(), StepKind::Answer { .. } | StepKind::Tool(_)) {
+            let items = content["content"].as_array_mut().expect("content array");
+            for next in &steps[index + 1..] {
+                match next.kind() {
+                    StepKind::Tool(_) => items.extend(
+                        Self::step_content(next)["content"]
+                            .as_array()
+                            .cloned()
+                            .unwrap_or_default(),
+                    ),
+                    StepKind::Other => {}
+                    _ => break,
+                }
+            }
+        }
+        Ok(content.to_string())
+    }
+}
+
+fn answer_message(
+    model: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+    content_ref: ContentRef,
+) -> MessageMetadata {
+    MessageMetadata {
+        uuid: None,
+        role: "assistant".to_string(),
+        provider_id: model.as_deref().and_then(infer_provider),
+        model,
+        timestamp,
+        content_ref,
+        has_tool_use: false,
+        has_thinking: false,
+        tool_uses: vec![],
+        token_usage: None,
+        request_params: None,
+        subtype: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_cascade_trajectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let cascade = json!({
+            "cascadeId": "5a1e",
+            "summary": "",
+            "createdTime": "2025-10-01T09:00:00Z",
+            "workspaces": [{
+                "workspaceFolderAbsoluteUri": "file:///home/me/My%20App",
+                "repository": { "gitOriginUrl": "git@github.com:me/app.git" }
+            }],
+            "trajectory": { "steps": [
+                {
+                    "type": "CORTEX_STEP_TYPE_USER_INPUT",
+                    "metadata": { "createdAt": "2025-10-01T09:00:01Z" },
+                    "userInput": { "userResponse": "Fix #7 in main.rs" }
+                },
+                {
+                    "type": "CORTEX_STEP_TYPE_PLANNER_RESPONSE",
+                    "metadata": { "createdAt": "2025-10-01T09:00:05Z", "generatorModel": "claude-sonnet-4-5" },
+                    "plannerResponse": { "response": "Looking at it", "thinking": "hmm" }
+                },
+                {
+                    "type": "CORTEX_STEP_TYPE_VIEW_FILE",
+                    "status": "CORTEX_STEP_STATUS_DONE",
+                    "viewFile": { "absolutePathUri": "file:///home/me/My%20App/src/main.rs" }
+                },
+                {
+                    "type": "CORTEX_STEP_TYPE_RUN_COMMAND",
+                    "status": "CORTEX_STEP_STATUS_ERROR",
+                    "runCommand": { "commandLine": "cargo test" }
+                },
+                { "type": "CORTEX_STEP_TYPE_CHECKPOINT", "checkpoint": {} },
+                { "type": "CORTEX_STEP_TYPE_SOMETHING_NEW" }
+            ]}
+        });
+        let path = dir.path().join("5a1e.json");
+        std::fs::write(&path, cascade.to_string()).unwrap();
+        std::fs::write(dir.path().join("5a1e.pb"), b"\x00encrypted").unwrap();
+
+        let probe = WindsurfProbe::new(Some(dir.path().to_path_buf()));
+        let sessions = probe.discover().unwrap();
+        assert_eq!(sessions.len(), 1);
+        let metadata = probe.extract_metadata(&sessions[0]).unwrap();
+
+        assert_eq!(metadata.external_id, "5a1e");
+        assert_eq!(metadata.title.as_deref(), Some("Fix #7 in main.rs"));
+        assert_eq!(metadata.project_path.as_deref(), Some("/home/me/My App"));
+        assert_eq!(
+            metadata.git_remote.as_deref(),
+            Some("git@github.com:me/app.git")
+        );
+        assert_eq!(metadata.primary_provider.as_deref(), Some("anthropic"));
+        assert_eq!(metadata.messages.len(), 2);
+        assert_eq!(metadata.skipped.total(), 1);
+
+        let answer = &metadata.messages[1];
+        assert!(answer.has_thinking);
+        let tools: Vec<_> = answer
+            .tool_uses
+            .iter()
+            .map(|t| (t.tool_name.as_str(), t.is_error))
+            .collect();
+        assert_eq!(tools, [("view_file", false), ("run_command", true)]);
+        assert_eq!(
+            answer.tool_uses[0].file_path.as_deref(),
+            Some("/home/me/My App/src/main.rs")
+        );
+
+        let content = probe.get_content(&answer.content_ref).unwrap();
+        assert!(content.contains("Looking at it") && content.contains("run_command"));
+    }
+}
@@ -11,13 +11,16 @@ use chrono::{DateTime, Utc};
 use rusqlite::{Connection, OpenFlags};
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::analysis::language::LanguageSample;
+use crate::analysis::{files, loops, references};
 
 use super::{
-    ContentRef, IngestionProbe, MessageMetadata, SessionMetadata, SessionRef, SourceType,
-    ToolUseMetadata,
+    ContentRef, IngestionProbe, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef,
+    SkipCounts, SourceType, ToolUseMetadata,
 };
 
 pub struct ZedProbe {
@@ -78,7 +81,6 @@ enum ContentItem {
         #[serde(rename = "ToolUse")]
         tool_use: ToolUseInfo,
     },
-    #[allow(dead_code)]
     Other(Value),
 }
 
@@ -86,13 +88,14 @@ enum ContentItem {
 struct ToolUseInfo {
     id: Option<String>,
     name: Option<String>,
+    input: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ToolResult {
     _tool_use_id: Option<String>,
     _tool_name: Option<String>,
-    _is_error: Option<bool>,
+    is_error: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -166,6 +169,20 @@ impl IngestionProbe for ZedProbe {
         self.db_path.exists()
     }
 
+    fn base_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    fn capabilities(&self) -> ProbeCapabilities {
+        // Zed keeps only cumulative thread usage, and every thread lives in one database
+        ProbeCapabilities {
+            supports_token_usage: false,
+            supports_tool_results: true,
+            supports_content: true,
+            supports_incremental: false,
+        }
+    }
+
     fn discover(&self) -> Result<Vec<SessionRef>> {
         let mut sessions = vec![];
 
@@ -247,9 +264,12 @@ impl IngestionProbe for ZedProbe {
 
         // Process messages
         let mut messages = vec![];
+        let mut skipped = SkipCounts::default();
         let mut provider_counts: HashMap<String, usize> = HashMap::new();
         let mut model_counts: HashMap<String, usize> = HashMap::new();
         let mut first_timestamp: Option<DateTime<Utc>> = None;
+        let mut session_refs = BTreeSet::new();
+        let mut language = LanguageSample::default();
 
         // Count session-level provider/model
         if let Some(ref provider) = session_provider {
@@ -262,6 +282,25 @@ impl IngestionProbe for ZedProbe {
         for (idx, msg) in thread.messages.iter().enumerate() {
             match msg {
                 ZedMessage::User(user_msg) => {
+                    for item in &user_msg.user.content {
+                        match item {
+                            ContentItem::Text { text } => {
+                                session_refs.extend(references::detect(text));
+                                language.add(text);
+                            }
+                            ContentItem::Other(value) => skipped.add(
+                                format!("unhandled content item '{}'", item_kind(value)),
+                                format!(
+                                    "{} thread {} message {}",
+                                    self.db_path.display(),
+                                    session.id,
+                                    idx
+                                ),
+                            ),
+                            ContentItem::ToolUse { .. } => {}
+                        }
+                    }
+
                     let has_tool_use = false;
                     let tool_uses = vec![];
 
@@ -271,16 +310,17 @@ impl IngestionProbe for ZedProbe {
                         provider_id: None,
                         model: None,
                         timestamp: if idx == 0 { first_timestamp } else { None },
-                        content_ref: ContentRef {
-                            source_path: self.db_path.clone(),
-                            byte_offset: None,
-                            line_number: Some(idx as u32),
-                            content_path: None,
-                        },
+                        content_ref: ContentRef::db_record(
+                            self.db_path.clone(),
+                            &session.id,
+                            idx as u32,
+                        ),
                         has_tool_use,
                         has_thinking: false,
                         tool_uses,
                         token_usage: None,
+                        request_params: None,
+                        subtype: None,
                     });
 
                     // Set first timestamp from first user message
@@ -294,16 +334,26 @@ impl IngestionProbe for ZedProbe {
                     let mut tool_uses = vec![];
 
                     for item in &agent_msg.agent.content {
+                        if let ContentItem::Other(value) = item {
+                            skipped.add(
+                                format!("unhandled content item '{}'", item_kind(value)),
+                                format!(
+                                    "{} thread {} message {}",
+                                    self.db_path.display(),
+                                    session.id,
+                                    idx
+                                ),
+                            );
+                        }
+                        if let ContentItem::Text { text } = item {
+                            session_refs.extend(references::detect(text));
+                        }
                         if let ContentItem::ToolUse { tool_use } = item {
                             has_tool_use = true;
-                            let has_result = agent_msg
-                                .agent
-                                .tool_results
-                                .as_ref()
-                                .and_then(|results| {
+                            let result =
+                                agent_msg.agent.tool_results.as_ref().and_then(|results| {
                                     tool_use.id.as_ref().and_then(|id| results.get(id))
-                                })
-                                .is_some();
+                                });
 
                             tool_uses.push(ToolUseMetadata {
                                 tool_id: tool_use.id.clone(),
@@ -311,7 +361,11 @@ impl IngestionProbe for ZedProbe {
                                     .name
                                     .clone()
                                     .unwrap_or_else(|| "unknown".to_string()),
-                                has_result,
+                                has_result: result.is_some(),
+                                is_error: result.and_then(|r| r.is_error).unwrap_or(false),
+                                permission: None,
+                                input_hash: tool_use.input.as_ref().map(loops::fingerprint),
+                                file_path: tool_use.input.as_ref().and_then(files::touched_file),
                             });
                         }
                     }
@@ -322,16 +376,17 @@ impl IngestionProbe for ZedProbe {
                         provider_id: session_provider.clone(),
                         model: session_model.clone(),
                         timestamp: None,
-                        content_ref: ContentRef {
-                            source_path: self.db_path.clone(),
-                            byte_offset: None,
-                            line_number: Some(idx as u32),
-                            content_path: None,
-                        },
+                        content_ref: ContentRef::db_record(
+                            self.db_path.clone(),
+                            &session.id,
+                            idx as u32,
+                        ),
                         has_tool_use,
                         has_thinking: false,
                         tool_uses,
                         token_usage: None, // Token usage is at thread level in Zed
+                        request_params: None,
+                        subtype: None,
                     });
                 }
                 ZedMessage::Resume => {
@@ -361,33 +416,36 @@ impl IngestionProbe for ZedProbe {
             title,
             project_path,
             git_remote,
+            source_group: None,
             primary_provider,
             primary_model,
             first_timestamp,
             last_timestamp,
             messages,
+            references: session_refs.into_iter().collect(),
+            language: language.detect().map(String::from),
+            commits: vec![],
+            compactions: 0,
+            skipped,
         })
     }
 
     fn get_content(&self, reference: &ContentRef) -> Result<String> {
-        // For Zed, we need to query the database and extract the specific message
-        let conn = self.open_db()?;
-
-        // The source_path contains the thread ID embedded in the session ID
-        // We need to get the message by index (line_number)
-        let _thread_id = reference
-            .source_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
+        // The message is the `line_number`th entry of the thread named by `content_path`
+        let thread_id = reference
+            .content_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .context("Zed message reference has no thread ID; run 'chronicle extract --full'")?;
 
-        // If we have a thread ID in the path, use it; otherwise extract from context
-        // This is a simplified implementation - in practice, we'd need the session ID
+        let conn = self.open_db()?;
         let (data_type, data): (String, Vec<u8>) = conn
-            .query_row("SELECT data_type, data FROM threads LIMIT 1", [], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })
-            .context("Failed to query thread for content")?;
+            .query_row(
+                "SELECT data_type, data FROM threads WHERE id = ?",
+                [thread_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .with_context(|| format!("Zed thread {} not found", thread_id))?;
 
         let json_str = if data_type == "zstd" {
             Self::decompress_zstd(&data)?
@@ -441,3 +499,51 @@ impl IngestionProbe for ZedProbe {
         Ok(String::new())
     }
 }
+
+/// Variant name of an unrecognized content item (`{"Thinking": {...}}` -> `Thinking`)
+fn item_kind(value: &Value) -> String {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.keys().next().cloned().unwrap_or_default(),
+        Value::String(s) => s.clone(),
+        _ => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_content_reads_the_messages_thread() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("threads.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE threads (id TEXT PRIMARY KEY, summary TEXT, updated_at TEXT,
+                 data_type TEXT, data BLOB)",
+        )
+        .unwrap();
+        for (id, text) in [("first", "unrelated"), ("second", "the right one")] {
+            let data = serde_json::json!({
+                "title": id,
+                "messages": [{ "User": { "id": null, "content": [{ "Text": text }] } }],
+            });
+            conn.execute(
+                "INSERT INTO threads VALUES (?1, ?1, '2026-01-01T00:00:00Z', 'json', ?2)",
+                rusqlite::params![id, data.to_string().into_bytes()],
+            )
+            .unwrap();
+        }
+
+        let probe = ZedProbe::new(Some(db_path.clone()));
+        let session = SessionRef {
+            id: "second".to_string(),
+            source_path: db_path,
+        };
+        let metadata = probe.extract_metadata(&session).unwrap();
+        let content = probe
+            .get_content(&metadata.messages[0].content_ref)
+            .unwrap();
+        assert_eq!(content, "the right one");
+    }
+}
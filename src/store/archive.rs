@@ -0,0 +1,762 @@
+//! Portable archive of the metadata DB, for moving an index between machines
+//!
+//! An archive is JSON Lines: a header, then providers, probe sources, projects and
+//! system prompts, then one record per session with its messages and per-session rows
+//! inlined. Row ids are dropped and reassigned on import, and paths under the home
+//! directory are written as `~/...` so they resolve on the importing machine.
+//!
+//! Archives are a team channel: private projects are redacted. Their sessions keep
+//! usage (messages, tokens, tool counts) but lose titles, paths, references, commits
+//! and system prompts, and the project itself is written under a placeholder name.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+use super::{schema, Audience, MetadataStore};
+
+/// `format` field of the archive header
+const ARCHIVE_FORMAT: &str = "chronicle-archive";
+
+/// Archive layout version; bumped when records change shape
+const ARCHIVE_VERSION: u64 = 1;
+
+/// Columns holding filesystem paths, written relative to the home directory
+const PATH_COLUMNS: &[&str] = &[
+    "base_path",
+    "primary_path",
+    "path",
+    "source_path",
+    "raw_project_path",
+    "content_ref",
+];
+
+/// Per-session tables carried along with each session
+const SESSION_TABLES: &[&str] = &[
+    "session_references",
+    "session_commits",
+    "anomalies",
+    "session_skips",
+    "enrichment_jobs",
+];
+
+/// Per-message tables carried along with each message
+const MESSAGE_TABLES: &[&str] = &["tool_uses", "token_usage", "request_params"];
+
+/// Per-message tables kept for sessions of private projects (usage only)
+const REDACTED_MESSAGE_TABLES: &[&str] = &["tool_uses", "token_usage"];
+
+/// Session columns cleared for private projects
+const REDACTED_SESSION_COLUMNS: &[&str] = &[
+    "title",
+    "raw_project_path",
+    "raw_git_remote",
+    "source_group",
+];
+
+/// Stands in for NOT NULL paths of redacted rows
+const REDACTED: &str = "[redacted]";
+
+/// System prompts sent only by sessions of private projects
+const PRIVATE_PROMPTS: &str = r#"SELECT rp.system_prompt_hash FROM request_params rp
+    JOIN messages m ON m.id = rp.message_id
+    JOIN sessions s ON s.id = m.session_id
+    JOIN projects p ON p.id = s.project_id
+    WHERE p.visibility = 'private'
+    EXCEPT
+    SELECT rp.system_prompt_hash FROM request_params rp
+    JOIN messages m ON m.id = rp.message_id
+    JOIN sessions s ON s.id = m.session_id
+    LEFT JOIN projects p ON p.id = s.project_id
+    WHERE p.visibility IS NOT 'private'"#;
+
+/// What to do with an archived session whose ID already exists locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the local session
+    Skip,
+    /// Replace the local session with the archived one
+    Replace,
+    /// Import the archived session under a new ID
+    KeepBoth,
+}
+
+impl ConflictPolicy {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "skip" => Ok(Self::Skip),
+            "replace" => Ok(Self::Replace),
+            "keep-both" => Ok(Self::KeepBoth),
+            other => bail!(
+                "Unknown conflict policy '{}' (use skip, replace or keep-both)",
+                other
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ExportStats {
+    pub projects: usize,
+    pub sessions: usize,
+    pub messages: usize,
+    /// Sessions of private projects written without identifying detail
+    pub redacted: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    pub sessions: usize,
+    pub messages: usize,
+    /// Conflicting sessions left as they were
+    pub skipped: usize,
+    /// Conflicting sessions overwritten by the archive
+    pub replaced: usize,
+    /// Conflicting sessions imported under a new ID
+    pub renamed: usize,
+    pub projects_created: usize,
+    /// Archived projects mapped onto an existing local project
+    pub projects_matched: usize,
+}
+
+impl MetadataStore {
+    // ============================================
+    // PORTABLE ARCHIVE
+    // ============================================
+
+    /// Write the whole index as a portable archive, redacting private projects
+    pub fn export_archive(&self, out: &mut dyn Write) -> Result<ExportStats> {
+        let home = home_prefix();
+        let mut stats = ExportStats::default();
+        let withheld = self.withheld_projects(Audience::Team)?;
+        let mut emit = |record: Value| -> Result<()> {
+            serde_json::to_writer(&mut *out, &record)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        };
+
+        emit(json!({
+            "kind": "header",
+            "format": ARCHIVE_FORMAT,
+            "version": ARCHIVE_VERSION,
+            "schema_version": self.schema_version()?,
+            "exported_at": chrono::Utc::now().to_rfc3339(),
+        }))?;
+
+        for (kind, sql) in [
+            ("provider", "SELECT * FROM providers".to_string()),
+            ("probe_source", "SELECT * FROM probe_sources".to_string()),
+            (
+                "system_prompt",
+                format!(
+                    "SELECT * FROM system_prompts WHERE hash NOT IN ({})",
+                    PRIVATE_PROMPTS
+                ),
+            ),
+        ] {
+            for row in self.archive_rows(&sql, params![], &[], &home)? {
+                emit(json!({ "kind": kind, "row": row }))?;
+            }
+        }
+
+        let projects = self.archive_rows(
+            "SELECT * FROM projects ORDER BY created_at",
+            params![],
+            &["session_count", "message_count", "last_session_at"],
+            &home,
+        )?;
+        for mut project in projects {
+            let id = project.get("id").cloned().unwrap_or(Value::Null);
+            if withheld.contains_key(id.as_str().unwrap_or_default()) {
+                let short: String = id.as_str().unwrap_or_default().chars().take(8).collect();
+                project.insert("name".into(), json!(format!("Private project {}", short)));
+                project.insert("primary_path".into(), Value::Null);
+                project.insert("metadata".into(), Value::Null);
+                emit(json!({
+                    "kind": "project",
+                    "row": project,
+                    "paths": [],
+                    "identifiers": [],
+                }))?;
+                stats.projects += 1;
+                continue;
+            }
+            let paths = self.archive_rows(
+                "SELECT * FROM project_paths WHERE project_id = ?1",
+                params![id.as_str()],
+                &["id", "project_id"],
+                &home,
+            )?;
+            let identifiers = self.archive_rows(
+                "SELECT * FROM project_identifiers WHERE project_id = ?1",
+                params![id.as_str()],
+                &["id", "project_id"],
+                &home,
+            )?;
+            emit(json!({
+                "kind": "project",
+                "row": project,
+                "paths": paths,
+                "identifiers": identifiers,
+            }))?;
+            stats.projects += 1;
+        }
+
+        // Parents first, so split and merged sessions can point at them on import
+        let sessions = self.archive_rows(
+            "SELECT * FROM sessions ORDER BY parent_session_id IS NOT NULL, first_timestamp",
+            params![],
+            &[],
+            &home,
+        )?;
+        for mut session in sessions {
+            let id = session
+                .get("id")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let redact = session
+                .get("project_id")
+                .and_then(Value::as_str)
+                .is_some_and(|p| withheld.contains_key(p));
+            let (message_tables, session_tables) = match redact {
+                true => (REDACTED_MESSAGE_TABLES, &[][..]),
+                false => (MESSAGE_TABLES, SESSION_TABLES),
+            };
+            if redact {
+                for column in REDACTED_SESSION_COLUMNS {
+                    session.insert(column.to_string(), Value::Null);
+                }
+                session.insert("source_path".into(), json!(REDACTED));
+                stats.redacted += 1;
+            }
+
+            let mut messages = vec![];
+            for message in self.archive_rows(
+                "SELECT * FROM messages WHERE session_id = ?1 ORDER BY id",
+                params![id],
+                &["session_id"],
+                &home,
+            )? {
+                let mut message = message;
+                let message_id = message.remove("id").and_then(|v| v.as_i64());
+                if redact {
+                    message.insert("source_path".into(), json!(REDACTED));
+                    message.insert("content_ref".into(), Value::Null);
+                }
+                for table in message_tables {
+                    let sql = format!("SELECT * FROM {} WHERE message_id = ?1", table);
+                    let rows =
+                        self.archive_rows(&sql, params![message_id], &["id", "message_id"], &home)?;
+                    if !rows.is_empty() {
+                        message.insert(table.to_string(), json!(rows));
+                    }
+                }
+                messages.push(message);
+            }
+
+            let mut record = Map::new();
+            record.insert("kind".into(), json!("session"));
+            for table in session_tables {
+                let sql = format!("SELECT * FROM {} WHERE session_id = ?1", table);
+                let rows = self.archive_rows(&sql, params![id], &["id", "session_id"], &home)?;
+                if !rows.is_empty() {
+                    record.insert(table.to_string(), json!(rows));
+                }
+            }
+            stats.messages += messages.len();
+            stats.sessions += 1;
+            record.insert("messages".into(), json!(messages));
+            record.insert("row".into(), Value::Object(session));
+            emit(Value::Object(record))?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Merge a portable archive into this store in a single transaction
+    pub fn import_archive(
+        &self,
+        input: impl BufRead,
+        policy: ConflictPolicy,
+    ) -> Result<ImportStats> {
+        let home = home_prefix();
+        let mut lines = input.lines().enumerate();
+
+        let header: Value = match lines.next() {
+            Some((_, line)) => serde_json::from_str(&line?).context("Invalid archive header")?,
+            None => bail!("Archive is empty"),
+        };
+        if header["format"] != ARCHIVE_FORMAT {
+            bail!("Not a chronicle archive");
+        }
+        if header["version"].as_u64().unwrap_or(0) > ARCHIVE_VERSION {
+            bail!("Archive was written by a newer chronicle; upgrade before importing");
+        }
+        let schema_version = header["schema_version"].as_u64().unwrap_or(0);
+        if schema_version > self.schema_version()? as u64 {
+            bail!(
+                "Archive schema v{} is newer than this database (v{}); upgrade before importing",
+                schema_version,
+                self.schema_version()?
+            );
+        }
+
+        self.transaction(|| {
+            let mut importer = Importer {
+                store: self,
+                home: &home,
+                policy,
+                columns: HashMap::new(),
+                projects: HashMap::new(),
+                renamed: HashMap::new(),
+                stats: ImportStats::default(),
+            };
+            for (index, line) in lines {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record: Value = serde_json::from_str(&line)
+                    .with_context(|| format!("Invalid archive record on line {}", index + 1))?;
+                importer
+                    .record(&record)
+                    .with_context(|| format!("Failed to import line {}", index + 1))?;
+            }
+            // Imported sessions can land on any day
+            self.refresh_usage_rollups(None)?;
+            schema::refresh_session_models(&self.conn, None)?;
+            Ok(importer.stats)
+        })
+    }
+
+    /// Rows of a query as JSON objects, minus `skip` columns, with home paths made relative
+    fn archive_rows(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+        skip: &[&str],
+        home: &str,
+    ) -> Result<Vec<Map<String, Value>>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+        let rows = stmt.query_map(params, |row| {
+            let mut object = Map::new();
+            for (i, name) in names.iter().enumerate() {
+                if skip.contains(&name.as_str()) {
+                    continue;
+                }
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null => Value::Null,
+                    ValueRef::Integer(n) => json!(n),
+                    ValueRef::Real(f) => json!(f),
+                    ValueRef::Text(t) => {
+                        let text = String::from_utf8_lossy(t);
+                        match text.strip_prefix(home) {
+                            Some(rest)
+                                if PATH_COLUMNS.contains(&name.as_str())
+                                    && (rest.is_empty() || rest.starts_with('/')) =>
+                            {
+                                json!(format!("~{}", rest))
+                            }
+                            _ => json!(text),
+                        }
+                    }
+                    ValueRef::Blob(b) => json!(hex::encode(b)),
+                };
+                object.insert(name.clone(), value);
+            }
+            Ok(object)
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Import state: local column sets and the ID mappings built up record by record
+struct Importer<'a> {
+    store: &'a MetadataStore,
+    home: &'a str,
+    policy: ConflictPolicy,
+    columns: HashMap<String, HashSet<String>>,
+    /// Archived project ID -> local project ID
+    projects: HashMap<String, String>,
+    /// Archived session ID -> new local ID, for sessions imported under a new ID
+    renamed: HashMap<String, String>,
+    stats: ImportStats,
+}
+
+impl Importer<'_> {
+    fn record(&mut self, record: &Value) -> Result<()> {
+        let row = record["row"].as_object().cloned().unwrap_or_default();
+        match record["kind"].as_str().unwrap_or_default() {
+            "provider" => self.insert("providers", &row).map(|_| ()),
+            "probe_source" => self.insert("probe_sources", &row).map(|_| ()),
+            "system_prompt" => self.insert("system_prompts", &row).map(|_| ()),
+            "project" => self.project(record, row),
+            "session" => self.session(record, row),
+            other => bail!("Unknown archive record '{}'", other),
+        }
+    }
+
+    /// Map an archived project onto a local one (by ID or name, git remote, or path),
+    /// or create it
+    fn project(&mut self, record: &Value, row: Map<String, Value>) -> Result<()> {
+        let id = row.get("id").and_then(Value::as_str).unwrap_or_default();
+        let identifiers = rows_of(&record["identifiers"]);
+        let paths = rows_of(&record["paths"]);
+
+        let mut local = self.store.find_project(id)?.map(|p| p.id);
+        for identifier in &identifiers {
+            if local.is_some() {
+                break;
+            }
+            if identifier.get("identifier_type") == Some(&json!("git_remote")) {
+                if let Some(remote) = identifier.get("identifier_value").and_then(Value::as_str) {
+                    local = self.store.find_project_by_git_remote(remote)?;
+                }
+            }
+        }
+        for path in &paths {
+            if local.is_some() {
+                break;
+            }
+            if let Some(path) = path.get("path").and_then(Value::as_str) {
+                local = self.store.find_project_by_path(&self.expand(path))?;
+            }
+        }
+
+        match local {
+            Some(local) => {
+                self.stats.projects_matched += 1;
+                self.projects.insert(id.to_string(), local);
+            }
+            None => {
+                self.insert("projects", &row)?;
+                for mut child in paths.into_iter().chain(identifiers) {
+                    child.insert("project_id".into(), json!(id));
+                    let table = if child.contains_key("path") {
+                        "project_paths"
+                    } else {
+                        "project_identifiers"
+                    };
+                    self.insert(table, &child)?;
+                }
+                self.stats.projects_created += 1;
+                self.projects.insert(id.to_string(), id.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn session(&mut self, record: &Value, mut row: Map<String, Value>) -> Result<()> {
+        let archived_id = row
+            .get("id")
+            .and_then(Value::as_str)
+            .context("Session record without an ID")?
+            .to_string();
+
+        let exists = self.store.get_session_source_path(&archived_id)?.is_some();
+        let mut id = archived_id.clone();
+        if exists {
+            match self.policy {
+                ConflictPolicy::Skip => {
+                    self.stats.skipped += 1;
+                    return Ok(());
+                }
+                ConflictPolicy::Replace => {
+                    self.store.delete_session_rows(&archived_id)?;
+                    self.stats.replaced += 1;
+                }
+                ConflictPolicy::KeepBoth => {
+                    let mut n = 1;
+                    while self
+                        .store
+                        .get_session_source_path(&format!("{}~{}", archived_id, n))?
+                        .is_some()
+                    {
+                        n += 1;
+                    }
+                    id = format!("{}~{}", archived_id, n);
+                    self.renamed.insert(archived_id.clone(), id.clone());
+                    self.stats.renamed += 1;
+                }
+            }
+        }
+
+        // Short hashes are per-store; take a fresh one if this one is in use
+        let short_hash = row
+            .get("short_hash")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let taken: bool = self.store.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sessions WHERE short_hash = ?1)",
+            params![short_hash],
+            |r| r.get(0),
+        )?;
+        if taken || short_hash.is_empty() {
+            let external_id = row
+                .get("external_id")
+                .and_then(Value::as_str)
+                .unwrap_or(&archived_id)
+                .to_string();
+            let fresh = self.store.compute_short_hash(&external_id)?;
+            row.insert("short_hash".into(), json!(fresh));
+        }
+
+        let project = row
+            .get("project_id")
+            .and_then(Value::as_str)
+            .and_then(|p| self.projects.get(p))
+            .map_or(Value::Null, |p| json!(p));
+        row.insert("project_id".into(), project);
+        row.insert("id".into(), json!(id));
+        // Parents are archived first, so a renamed parent is already known here
+        for column in ["parent_session_id", "merged_into"] {
+            let renamed = row
+                .get(column)
+                .and_then(Value::as_str)
+                .and_then(|s| self.renamed.get(s));
+            if let Some(renamed) = renamed {
+                row.insert(column.into(), json!(renamed));
+            }
+        }
+        self.insert("sessions", &row)?;
+
+        for message in rows_of(&record["messages"]) {
+            let mut message = message;
+            let children: Vec<(&str, Vec<Map<String, Value>>)> = MESSAGE_TABLES
+                .iter()
+                .map(|table| (*table, rows_of(&message.remove(*table).unwrap_or_default())))
+                .collect();
+            message.insert("session_id".into(), json!(id));
+            self.insert("messages", &message)?;
+            let message_id = self.store.conn.last_insert_rowid();
+            for (table, rows) in children {
+                for mut child in rows {
+                    child.insert("message_id".into(), json!(message_id));
+                    self.insert(table, &child)?;
+                }
+            }
+            self.stats.messages += 1;
+        }
+
+        for table in SESSION_TABLES {
+            for mut child in rows_of(&record[*table]) {
+                child.insert("session_id".into(), json!(id));
+                self.insert(table, &child)?;
+            }
+        }
+        self.stats.sessions += 1;
+        Ok(())
+    }
+
+    /// Insert the columns of `row` this database knows; existing rows are left alone
+    fn insert(&mut self, table: &str, row: &Map<String, Value>) -> Result<usize> {
+        if !self.columns.contains_key(table) {
+            let mut stmt = self
+                .store
+                .conn
+                .prepare("SELECT name FROM pragma_table_info(?1)")?;
+            let names = stmt
+                .query_map(params![table], |r| r.get::<_, String>(0))?
+                .collect::<Result<HashSet<_>, _>>()?;
+            self.columns.insert(table.to_string(), names);
+        }
+        let known = &self.columns[table];
+
+        let (names, values): (Vec<&str>, Vec<SqlValue>) = row
+            .iter()
+            .filter(|(name, _)| known.contains(name.as_str()))
+            .map(|(name, value)| (name.as_str(), self.sql_value(name, value)))
+            .unzip();
+        let sql = format!(
+            "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+            table,
+            names.join(", "),
+            vec!["?"; names.len()].join(", ")
+        );
+        Ok(self.store.conn.execute(&sql, params_from_iter(values))?)
+    }
+
+    fn sql_value(&self, column: &str, value: &Value) -> SqlValue {
+        match value {
+            Value::Null => SqlValue::Null,
+            Value::Bool(b) => SqlValue::Integer(*b as i64),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => SqlValue::Integer(i),
+                None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) if PATH_COLUMNS.contains(&column) => SqlValue::Text(self.expand(s)),
+            Value::String(s) => SqlValue::Text(s.clone()),
+            other => SqlValue::Text(other.to_string()),
+        }
+    }
+
+    /// Resolve an archived `~/...` path against this machine's home directory
+    fn expand(&self, path: &str) -> String {
+        match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", self.home, rest)
+            }
+            _ => path.to_string(),
+        }
+    }
+}
+
+/// Home directory without a trailing slash; a NUL placeholder when unknown, so no
+/// path matches it
+fn home_prefix() -> String {
+    dirs::home_dir()
+        .map(|h| h.to_string_lossy().trim_end_matches('/').to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "\u{0}".to_string())
+}
+
+fn rows_of(value: &Value) -> Vec<Map<String, Value>> {
+    value
+        .as_array()
+        .map(|rows| rows.iter().filter_map(|r| r.as_object().cloned()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{
+        ContentRef, MessageMetadata, RequestParams, SessionMetadata, SessionRef, SkipCounts,
+        SourceType,
+    };
+    use crate::store::Visibility;
+    use std::path::PathBuf;
+
+    fn store_with_session(path: &std::path::Path) -> MetadataStore {
+        let store = MetadataStore::open(path).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let messages: Vec<MessageMetadata> = (0..3)
+            .map(|line| MessageMetadata {
+                uuid: Some(format!("m{}", line)),
+                role: "user".to_string(),
+                provider_id: None,
+                model: None,
+                timestamp: None,
+                content_ref: ContentRef::jsonl(PathBuf::from("/tmp/s.jsonl"), 0, line),
+                has_tool_use: false,
+                has_thinking: false,
+                tool_uses: vec![],
+                token_usage: None,
+                request_params: Some(RequestParams {
+                    system_prompt: Some("Be brief.".to_string()),
+                    ..Default::default()
+                }),
+                subtype: None,
+            })
+            .collect();
+        let meta = SessionMetadata {
+            external_id: "abcdef0123".to_string(),
+            title: Some("Archived".to_string()),
+            project_path: None,
+            git_remote: None,
+            source_group: None,
+            primary_provider: None,
+            primary_model: None,
+            first_timestamp: None,
+            last_timestamp: None,
+            messages,
+            references: vec![],
+            language: None,
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        };
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        store.insert_messages(&id, &meta.messages).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_archive_round_trip_and_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = store_with_session(&dir.path().join("a.db"));
+        let mut archive = vec![];
+        let exported = source.export_archive(&mut archive).unwrap();
+        assert_eq!((exported.sessions, exported.messages), (1, 3));
+
+        let target = MetadataStore::open(&dir.path().join("b.db")).unwrap();
+        let imported = target
+            .import_archive(archive.as_slice(), ConflictPolicy::Skip)
+            .unwrap();
+        assert_eq!((imported.sessions, imported.messages), (1, 3));
+        let params = target.session_request_params("t:Test:abcdef0123").unwrap();
+        assert_eq!(params[0].requests, 3);
+
+        let again = target
+            .import_archive(archive.as_slice(), ConflictPolicy::Skip)
+            .unwrap();
+        assert_eq!((again.sessions, again.skipped), (0, 1));
+
+        let both = target
+            .import_archive(archive.as_slice(), ConflictPolicy::KeepBoth)
+            .unwrap();
+        assert_eq!(both.renamed, 1);
+        let copy = target.get_session("t:Test:abcdef0123~1").unwrap().unwrap();
+        assert_ne!(copy.short_hash, "abcdef01");
+        assert_eq!(target.get_messages(&copy.id).unwrap().len(), 3);
+
+        // Replacing leaves no rows of the old copy behind
+        let replaced = target
+            .import_archive(archive.as_slice(), ConflictPolicy::Replace)
+            .unwrap();
+        assert_eq!(replaced.replaced, 1);
+        assert_eq!(target.get_messages("t:Test:abcdef0123").unwrap().len(), 3);
+        let params = target.session_request_params("t:Test:abcdef0123").unwrap();
+        assert_eq!(params[0].requests, 3);
+    }
+
+    #[test]
+    fn test_private_projects_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = store_with_session(&dir.path().join("a.db"));
+        source
+            .create_project("p1", "job-search", "general", Some("/home/me/jobs"), None)
+            .unwrap();
+        source
+            .set_project_visibility("p1", Visibility::Private)
+            .unwrap();
+        source
+            .assign_session_to_project("t:Test:abcdef0123", Some("p1"))
+            .unwrap();
+
+        let mut archive = vec![];
+        let exported = source.export_archive(&mut archive).unwrap();
+        assert_eq!((exported.sessions, exported.redacted), (1, 1));
+        let text = String::from_utf8(archive.clone()).unwrap();
+        for secret in [
+            "job-search",
+            "/home/me/jobs",
+            "Archived",
+            "Be brief.",
+            "/tmp/s.jsonl",
+        ] {
+            assert!(!text.contains(secret), "archive leaks {}", secret);
+        }
+
+        // Usage survives, and the project stays private on the other side
+        let target = MetadataStore::open(&dir.path().join("b.db")).unwrap();
+        let imported = target
+            .import_archive(archive.as_slice(), ConflictPolicy::Skip)
+            .unwrap();
+        assert_eq!((imported.sessions, imported.messages), (1, 3));
+        let project = target.find_project("p1").unwrap().unwrap();
+        assert_eq!(project.visibility, Visibility::Private);
+    }
+}
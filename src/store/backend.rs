@@ -0,0 +1,376 @@
+//! Storage backend abstraction
+//!
+//! `StorageBackend` covers the ingest path (everything `extract` writes or reads back while
+//! indexing), so the extraction engine doesn't depend on SQLite specifics. `MetadataStore`
+//! (SQLite, file at `database.path`) is the default implementation; `PostgresStore`
+//! (`database.backend: postgres`, built with `--features postgres`) is an experimental
+//! one for team servers with several concurrent ingest clients. Query commands read
+//! `MetadataStore` directly, so they need `sqlite`.
+
+use anyhow::Result;
+
+use super::{MetadataStore, ProjectRow, UsageRow};
+use crate::analysis::{DailyUsage, IssueReference, ToolLoop};
+use crate::config::{Config, DatabaseConfig};
+use crate::probe::{
+    CommitRef, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef, SkipCounts,
+    SourceFingerprint, SourceType,
+};
+
+/// Storage operations used during ingestion
+pub trait StorageBackend {
+    /// Run `f` as one transaction: all of its writes commit together or not at all
+    fn transaction(&self, f: &mut dyn FnMut() -> Result<()>) -> Result<()>;
+
+    fn ensure_provider(&self, id: &str, name: &str, description: Option<&str>) -> Result<()>;
+
+    fn ensure_probe_source(
+        &self,
+        id: &str,
+        provider_id: Option<&str>,
+        source_name: &str,
+        source_type: SourceType,
+        base_path: Option<&str>,
+        status: &str,
+    ) -> Result<()>;
+
+    fn update_probe_indexed(&self, probe_id: &str) -> Result<()>;
+
+    fn set_probe_capabilities(
+        &self,
+        probe_id: &str,
+        capabilities: &ProbeCapabilities,
+    ) -> Result<()>;
+
+    /// Insert or update a session; returns the stored session ID
+    fn upsert_session(
+        &self,
+        probe_source_id: &str,
+        session: &SessionRef,
+        metadata: &SessionMetadata,
+    ) -> Result<String>;
+
+    /// Whether a session with this stored ID has been extracted
+    fn has_session(&self, session_id: &str) -> Result<bool>;
+
+    /// Probe and source session ID of the session a short hash, ID or prefix names, so
+    /// it can be re-extracted
+    fn session_origin(&self, query: &str) -> Result<Option<(String, String)>>;
+
+    /// Source fingerprint recorded at the session's last extraction
+    fn get_session_fingerprint(&self, session_id: &str) -> Result<Option<SourceFingerprint>>;
+
+    fn set_session_fingerprint(
+        &self,
+        session_id: &str,
+        fingerprint: &SourceFingerprint,
+    ) -> Result<()>;
+
+    /// Replace all messages (with tool uses and token usage) of a session; returns the
+    /// message ids in the order given
+    fn insert_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<Vec<i64>>;
+
+    /// Store copies of raw message content by message id
+    fn cache_content(&self, contents: &[(i64, String)]) -> Result<()>;
+
+    fn replace_session_references(
+        &self,
+        session_id: &str,
+        references: &[IssueReference],
+    ) -> Result<()>;
+
+    fn replace_session_commits(&self, session_id: &str, commits: &[CommitRef]) -> Result<()>;
+
+    fn replace_tool_loops(&self, session_id: &str, loops: &[ToolLoop]) -> Result<()>;
+
+    fn replace_session_skips(&self, session_id: &str, skipped: &SkipCounts) -> Result<()>;
+
+    /// Store every unparsed record of a session with its location (strict mode)
+    fn replace_parse_errors(&self, session_id: &str, skipped: &SkipCounts) -> Result<()>;
+
+    fn create_project(
+        &self,
+        id: &str,
+        name: &str,
+        project_type: &str,
+        primary_path: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<()>;
+
+    fn find_project(&self, query: &str) -> Result<Option<ProjectRow>>;
+
+    /// Project registered for a working directory (or, normalizing, an ancestor of it)
+    fn project_for_path(&self, path: &str) -> Result<Option<String>>;
+
+    fn find_project_by_git_remote(&self, remote: &str) -> Result<Option<String>>;
+
+    fn add_project_identifier(
+        &self,
+        project_id: &str,
+        identifier_type: &str,
+        identifier_value: &str,
+    ) -> Result<()>;
+
+    fn list_projects(&self) -> Result<Vec<ProjectRow>>;
+
+    /// Paths of a project, primary path first
+    fn get_project_paths(&self, project_id: &str) -> Result<Vec<String>>;
+
+    /// Link a session to a project unless the user has assigned it explicitly
+    fn link_session_auto(&self, session_id: &str, project_id: &str) -> Result<bool>;
+
+    fn merge_project_metadata(
+        &self,
+        project_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<()>;
+
+    /// Recompute a session's activity metrics once its messages are stored
+    fn refresh_session_metrics(&self, session_id: &str) -> Result<()>;
+
+    /// Total tokens per local day with usage, from a local `YYYY-MM-DD` day onward
+    fn daily_token_usage(&self, since_day: &str) -> Result<Vec<DailyUsage>>;
+
+    /// Token totals grouped by session, model and day, optionally from an RFC 3339 start time
+    fn token_usage(&self, since: Option<&str>) -> Result<Vec<UsageRow>>;
+
+    /// Recompute daily usage rollups from a local `YYYY-MM-DD` day onward (None: all)
+    fn refresh_usage_rollups(&self, since_day: Option<&str>) -> Result<()>;
+
+    /// Record a fired alert; returns false if it was already recorded
+    fn record_alert(
+        &self,
+        kind: &str,
+        day: &str,
+        value: f64,
+        baseline: Option<f64>,
+    ) -> Result<bool>;
+}
+
+/// Reject a `database.backend` that can't answer queries (everything but ingestion)
+pub fn check_backend(database: &DatabaseConfig) -> Result<()> {
+    match database.backend.as_str() {
+        "sqlite" => Ok(()),
+        "postgres" => anyhow::bail!(
+            "The postgres backend only takes extract and watch; other commands read a SQLite \
+             database (--db, or database.backend: sqlite)"
+        ),
+        other => anyhow::bail!(
+            "Unknown database backend: {} (expected sqlite or postgres)",
+            other
+        ),
+    }
+}
+
+/// Open the ingest backend selected in the configuration
+pub fn open_backend(config: &Config) -> Result<Box<dyn StorageBackend>> {
+    match config.database.backend.as_str() {
+        "sqlite" => Ok(Box::new(
+            MetadataStore::open_with(&config.database_path(), &config.database)?
+                .with_path_normalization(config.linking.normalize_paths),
+        )),
+        #[cfg(feature = "postgres")]
+        "postgres" => {
+            let Some(ref url) = config.database.url else {
+                anyhow::bail!("database.backend: postgres needs database.url");
+            };
+            Ok(Box::new(
+                super::PostgresStore::connect(url)?
+                    .with_path_normalization(config.linking.normalize_paths),
+            ))
+        }
+        #[cfg(not(feature = "postgres"))]
+        "postgres" => {
+            anyhow::bail!("database.backend: postgres needs a build with `--features postgres`")
+        }
+        other => anyhow::bail!(
+            "Unknown database backend: {} (expected sqlite or postgres)",
+            other
+        ),
+    }
+}
+
+impl StorageBackend for MetadataStore {
+    fn transaction(&self, f: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        MetadataStore::transaction(self, f)
+    }
+
+    fn ensure_provider(&self, id: &str, name: &str, description: Option<&str>) -> Result<()> {
+        MetadataStore::ensure_provider(self, id, name, description)
+    }
+
+    fn ensure_probe_source(
+        &self,
+        id: &str,
+        provider_id: Option<&str>,
+        source_name: &str,
+        source_type: SourceType,
+        base_path: Option<&str>,
+        status: &str,
+    ) -> Result<()> {
+        MetadataStore::ensure_probe_source(
+            self,
+            id,
+            provider_id,
+            source_name,
+            source_type,
+            base_path,
+            status,
+        )
+    }
+
+    fn update_probe_indexed(&self, probe_id: &str) -> Result<()> {
+        MetadataStore::update_probe_indexed(self, probe_id)
+    }
+
+    fn set_probe_capabilities(
+        &self,
+        probe_id: &str,
+        capabilities: &ProbeCapabilities,
+    ) -> Result<()> {
+        MetadataStore::set_probe_capabilities(self, probe_id, capabilities)
+    }
+
+    fn upsert_session(
+        &self,
+        probe_source_id: &str,
+        session: &SessionRef,
+        metadata: &SessionMetadata,
+    ) -> Result<String> {
+        MetadataStore::upsert_session(self, probe_source_id, session, metadata)
+    }
+
+    fn has_session(&self, session_id: &str) -> Result<bool> {
+        MetadataStore::has_session(self, session_id)
+    }
+
+    fn session_origin(&self, query: &str) -> Result<Option<(String, String)>> {
+        Ok(self
+            .get_session(query)?
+            .map(|s| (s.probe_source_id, s.external_id)))
+    }
+
+    fn get_session_fingerprint(&self, session_id: &str) -> Result<Option<SourceFingerprint>> {
+        MetadataStore::get_session_fingerprint(self, session_id)
+    }
+
+    fn set_session_fingerprint(
+        &self,
+        session_id: &str,
+        fingerprint: &SourceFingerprint,
+    ) -> Result<()> {
+        MetadataStore::set_session_fingerprint(self, session_id, fingerprint)
+    }
+
+    fn insert_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<Vec<i64>> {
+        MetadataStore::insert_messages(self, session_id, messages)
+    }
+
+    fn cache_content(&self, contents: &[(i64, String)]) -> Result<()> {
+        MetadataStore::cache_content(self, contents)
+    }
+
+    fn replace_session_references(
+        &self,
+        session_id: &str,
+        references: &[IssueReference],
+    ) -> Result<()> {
+        MetadataStore::replace_session_references(self, session_id, references)
+    }
+
+    fn replace_session_commits(&self, session_id: &str, commits: &[CommitRef]) -> Result<()> {
+        MetadataStore::replace_session_commits(self, session_id, commits)
+    }
+
+    fn replace_tool_loops(&self, session_id: &str, loops: &[ToolLoop]) -> Result<()> {
+        MetadataStore::replace_tool_loops(self, session_id, loops)
+    }
+
+    fn replace_session_skips(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        MetadataStore::replace_session_skips(self, session_id, skipped)
+    }
+
+    fn replace_parse_errors(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        MetadataStore::replace_parse_errors(self, session_id, skipped)
+    }
+
+    fn create_project(
+        &self,
+        id: &str,
+        name: &str,
+        project_type: &str,
+        primary_path: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<()> {
+        MetadataStore::create_project(self, id, name, project_type, primary_path, metadata)
+    }
+
+    fn find_project(&self, query: &str) -> Result<Option<ProjectRow>> {
+        MetadataStore::find_project(self, query)
+    }
+
+    fn project_for_path(&self, path: &str) -> Result<Option<String>> {
+        MetadataStore::project_for_path(self, path)
+    }
+
+    fn find_project_by_git_remote(&self, remote: &str) -> Result<Option<String>> {
+        MetadataStore::find_project_by_git_remote(self, remote)
+    }
+
+    fn add_project_identifier(
+        &self,
+        project_id: &str,
+        identifier_type: &str,
+        identifier_value: &str,
+    ) -> Result<()> {
+        MetadataStore::add_project_identifier(self, project_id, identifier_type, identifier_value)
+    }
+
+    fn list_projects(&self) -> Result<Vec<ProjectRow>> {
+        MetadataStore::list_projects(self)
+    }
+
+    fn get_project_paths(&self, project_id: &str) -> Result<Vec<String>> {
+        MetadataStore::get_project_paths(self, project_id)
+    }
+
+    fn link_session_auto(&self, session_id: &str, project_id: &str) -> Result<bool> {
+        MetadataStore::link_session_auto(self, session_id, project_id)
+    }
+
+    fn merge_project_metadata(
+        &self,
+        project_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<()> {
+        MetadataStore::merge_project_metadata(self, project_id, key, value)
+    }
+
+    fn refresh_session_metrics(&self, session_id: &str) -> Result<()> {
+        MetadataStore::refresh_session_metrics(self, session_id)
+    }
+
+    fn daily_token_usage(&self, since_day: &str) -> Result<Vec<DailyUsage>> {
+        MetadataStore::daily_token_usage(self, since_day)
+    }
+
+    fn token_usage(&self, since: Option<&str>) -> Result<Vec<UsageRow>> {
+        MetadataStore::token_usage(self, since)
+    }
+
+    fn refresh_usage_rollups(&self, since_day: Option<&str>) -> Result<()> {
+        MetadataStore::refresh_usage_rollups(self, since_day)
+    }
+
+    fn record_alert(
+        &self,
+        kind: &str,
+        day: &str,
+        value: f64,
+        baseline: Option<f64>,
+    ) -> Result<bool> {
+        MetadataStore::record_alert(self, kind, day, value, baseline)
+    }
+}
@@ -0,0 +1,90 @@
+//! Store fixtures for command tests: sessions built in code rather than by a probe
+
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+use super::MetadataStore;
+use crate::probe::{
+    ContentRef, MessageMetadata, SessionMetadata, SessionRef, SkipCounts, SourceType,
+};
+
+/// A message with a stable uuid; tests set the remaining fields they care about
+pub fn message(uuid: &str, role: &str, timestamp: Option<DateTime<Utc>>) -> MessageMetadata {
+    MessageMetadata {
+        uuid: Some(uuid.to_string()),
+        role: role.to_string(),
+        provider_id: None,
+        model: None,
+        timestamp,
+        content_ref: ContentRef::jsonl(PathBuf::from(format!("/tmp/{}.jsonl", uuid)), 0, 0),
+        has_tool_use: false,
+        has_thinking: false,
+        tool_uses: vec![],
+        token_usage: None,
+        request_params: None,
+        subtype: None,
+    }
+}
+
+/// Session metadata spanning its messages' timestamps
+pub fn session(
+    external_id: &str,
+    project_path: Option<&str>,
+    messages: Vec<MessageMetadata>,
+) -> SessionMetadata {
+    let timestamps = messages.iter().filter_map(|m| m.timestamp);
+    SessionMetadata {
+        external_id: external_id.to_string(),
+        title: None,
+        project_path: project_path.map(String::from),
+        git_remote: None,
+        source_group: None,
+        primary_provider: None,
+        primary_model: None,
+        first_timestamp: timestamps.clone().min(),
+        last_timestamp: timestamps.max(),
+        messages,
+        references: vec![],
+        language: None,
+        commits: vec![],
+        compactions: 0,
+        skipped: SkipCounts::default(),
+    }
+}
+
+/// Store a session and its messages under `probe_id` (`provider:Source`); returns the
+/// session id
+pub fn seed(store: &MetadataStore, probe_id: &str, metadata: &SessionMetadata) -> String {
+    let source = PathBuf::from(format!("/tmp/{}.jsonl", metadata.external_id));
+    seed_from(store, probe_id, metadata, &source)
+}
+
+/// `seed`, extracted from the source file at `source`
+pub fn seed_from(
+    store: &MetadataStore,
+    probe_id: &str,
+    metadata: &SessionMetadata,
+    source: &Path,
+) -> String {
+    let source_name = probe_id.split_once(':').map_or(probe_id, |(_, s)| s);
+    store
+        .ensure_probe_source(
+            probe_id,
+            None,
+            source_name,
+            SourceType::Multi,
+            None,
+            "active",
+        )
+        .unwrap();
+    let session = SessionRef {
+        id: metadata.external_id.clone(),
+        source_path: source.to_path_buf(),
+    };
+    let session_id = store.upsert_session(probe_id, &session, metadata).unwrap();
+    store
+        .insert_messages(&session_id, &metadata.messages)
+        .unwrap();
+    store.refresh_session_metrics(&session_id).unwrap();
+    session_id
+}
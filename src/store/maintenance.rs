@@ -0,0 +1,169 @@
+//! Database upkeep: compaction, integrity checks and size accounting
+//!
+//! Re-extraction rewrites messages in place, so deleted pages pile up on the freelist
+//! and FTS segments multiply; `vacuum` rebuilds the search index and the file, `check`
+//! reports what SQLite and the search index think of their own state.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{schema, MetadataStore};
+
+/// Space used by a table or index
+#[derive(Debug, Serialize)]
+pub struct TableSize {
+    pub name: String,
+    /// Table this index (or FTS shadow table) belongs to; the name itself for tables
+    pub table: String,
+    pub bytes: u64,
+    /// Rows, for tables only
+    pub rows: Option<u64>,
+}
+
+/// Size of the database file and how much of it is free pages
+#[derive(Debug, Default, Serialize)]
+pub struct FileSize {
+    pub bytes: u64,
+    pub free_bytes: u64,
+}
+
+/// Outcome of `check`
+#[derive(Debug, Serialize)]
+pub struct IntegrityReport {
+    /// Problems `PRAGMA integrity_check` found (FTS5 tables included); empty when the
+    /// database is sound
+    pub problems: Vec<String>,
+    /// Sessions missing from the search index, or indexed but gone
+    pub search_index_drift: u64,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty() && self.search_index_drift == 0
+    }
+}
+
+impl MetadataStore {
+    pub fn file_size(&self) -> Result<FileSize> {
+        let pragma = |name: &str| -> Result<u64> {
+            let value: i64 = self
+                .conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))?;
+            Ok(value as u64)
+        };
+        let page_size = pragma("page_size")?;
+        Ok(FileSize {
+            bytes: pragma("page_count")? * page_size,
+            free_bytes: pragma("freelist_count")? * page_size,
+        })
+    }
+
+    /// Bytes used per table and index, largest first
+    pub fn table_sizes(&self) -> Result<Vec<TableSize>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT d.name, COALESCE(m.tbl_name, d.name), SUM(d.pgsize), m.type
+               FROM dbstat d LEFT JOIN sqlite_master m ON m.name = d.name
+               GROUP BY d.name
+               ORDER BY SUM(d.pgsize) DESC"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sizes = vec![];
+        for (name, table, bytes, kind) in rows {
+            let rows = match kind.as_deref() {
+                Some("table") => Some(self.conn.query_row(
+                    &format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")),
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )? as u64),
+                _ => None,
+            };
+            sizes.push(TableSize {
+                name,
+                table,
+                bytes: bytes as u64,
+                rows,
+            });
+        }
+        Ok(sizes)
+    }
+
+    /// Run SQLite's integrity check and verify the search index against the sessions
+    pub fn integrity_check(&self) -> Result<IntegrityReport> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let problems = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|line| line != "ok")
+            .collect();
+        let search_index_drift = self.conn.query_row(
+            r#"SELECT (SELECT COUNT(*) FROM sessions s
+                       WHERE NOT EXISTS (SELECT 1 FROM session_search f WHERE f.session_id = s.id))
+                    + (SELECT COUNT(*) FROM session_search f
+                       WHERE NOT EXISTS (SELECT 1 FROM sessions s WHERE s.id = f.session_id))"#,
+            [],
+            |row| row.get::<_, i64>(0),
+        )? as u64;
+        Ok(IntegrityReport {
+            problems,
+            search_index_drift,
+        })
+    }
+
+    /// Rebuild the search index from the sessions table and merge its segments
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        self.transaction(|| {
+            self.conn.execute_batch(schema::REINDEX_SEARCH)?;
+            self.conn.execute(
+                "INSERT INTO session_search (session_search) VALUES ('optimize')",
+                [],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Rewrite the database file without free pages
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_and_vacuum() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        assert!(store.integrity_check().unwrap().is_ok());
+
+        store
+            .conn
+            .execute(
+                "INSERT INTO session_search (session_id, title) VALUES ('gone', 'stale')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(store.integrity_check().unwrap().search_index_drift, 1);
+        store.rebuild_search_index().unwrap();
+        assert!(store.integrity_check().unwrap().is_ok());
+
+        let sizes = store.table_sizes().unwrap();
+        let sessions = sizes.iter().find(|s| s.name == "sessions").unwrap();
+        assert_eq!(sessions.rows, Some(0));
+        store.vacuum().unwrap();
+        assert_eq!(store.file_size().unwrap().free_bytes, 0);
+    }
+}
@@ -6,38 +6,199 @@
 //! - Updated messages with provider_id and content_ref
 //! - Removed artifact storage (Antigravity-specific)
 
+mod archive;
+mod backend;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod maintenance;
+mod paths;
+mod pool;
+#[cfg(feature = "postgres")]
+mod postgres;
 mod schema;
+mod search;
 
 use anyhow::Result;
-use rusqlite::{params, Connection};
-use std::path::Path;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
-use crate::probe::{MessageMetadata, SessionMetadata, SessionRef, SourceType};
+use crate::analysis::dedupe::{DuplicateMatch, SessionSignature};
+use crate::analysis::snapshot::{self, ProjectSnapshot};
+use crate::analysis::{tools, DailyUsage, IssueReference, TokenCounts, ToolLoop};
+use crate::config::DatabaseConfig;
+use crate::content::ContentArchive;
+use crate::error::Error;
+use crate::probe::{
+    CommitRef, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef, SkipCounts,
+    SourceFingerprint, SourceType,
+};
 
-pub use schema::SCHEMA;
+pub use archive::{ConflictPolicy, ExportStats, ImportStats};
+pub use backend::{check_backend, open_backend, StorageBackend};
+pub use maintenance::{FileSize, IntegrityReport, TableSize};
+pub use pool::{PooledReader, ReadPool};
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+pub use schema::{COUNTER_TRIGGERS, SCHEMA, SEARCH_TRIGGERS};
+pub use search::{relevance, SearchRank};
 
 pub struct MetadataStore {
     conn: Connection,
+    path: PathBuf,
+    /// Match project paths after normalization and by ancestor (`linking.normalize_paths`)
+    normalize_paths: bool,
+    /// Upgraded temporary copy behind a read-only store (declared after `conn`, so it
+    /// is removed once the connection has closed)
+    scratch: Option<ScratchFile>,
+}
+
+/// Temporary database file, removed on drop
+struct ScratchFile(PathBuf);
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
 
 impl MetadataStore {
+    /// Open (creating and migrating) the database with the default connection settings:
+    /// WAL, `synchronous = NORMAL` and a 5 s busy timeout
     pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with(path, &DatabaseConfig::default())
+    }
+
+    /// Open the database with the journal, sync and lock settings of `database`
+    pub fn open_with(path: &Path, database: &DatabaseConfig) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         let conn = Connection::open(path)?;
-        let store = Self { conn };
+        configure_connection(&conn, database)?;
+        let store = Self {
+            conn,
+            path: path.to_path_buf(),
+            normalize_paths: false,
+            scratch: None,
+        };
         store.init_schema()?;
         Ok(store)
     }
 
+    /// Open a database for reading only, e.g. one copied from another machine: nothing
+    /// is ever written to it. A database at the current schema version is read in
+    /// place; an older one is snapshotted to a temporary file and upgraded there.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(Error::DatabaseNotFound(path.to_path_buf()).into());
+        }
+        let conn = Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(std::time::Duration::from_millis(
+            DatabaseConfig::default().busy_timeout_ms,
+        ))?;
+        let version = schema::recorded_version(&conn)?;
+        let latest = schema::latest_version();
+        if version > latest {
+            return Err(Error::SchemaMismatch {
+                path: path.to_path_buf(),
+                found: version,
+                supported: latest,
+            }
+            .into());
+        }
+        if version == latest {
+            conn.execute_batch("PRAGMA query_only = ON")?;
+            return Ok(Self {
+                conn,
+                path: path.to_path_buf(),
+                normalize_paths: false,
+                scratch: None,
+            });
+        }
+
+        let scratch =
+            std::env::temp_dir().join(format!("chronicle-read-only-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&scratch);
+        conn.execute("VACUUM INTO ?", params![scratch.to_string_lossy()])?;
+        drop(conn);
+        let guard = ScratchFile(scratch.clone());
+        // A rollback journal leaves no -wal/-shm files behind next to the scratch copy
+        let rollback = DatabaseConfig {
+            journal_mode: "delete".to_string(),
+            ..Default::default()
+        };
+        let mut store = Self::open_with(&scratch, &rollback)?;
+        store.scratch = Some(guard);
+        store.conn.execute_batch("PRAGMA query_only = ON")?;
+        Ok(store)
+    }
+
+    /// Whether writes are refused (see [`MetadataStore::open_read_only`])
+    pub fn is_read_only(&self) -> bool {
+        self.conn
+            .query_row("PRAGMA query_only", [], |row| row.get(0))
+            .unwrap_or(false)
+    }
+
+    /// Compare project paths normalized (symlinks, trailing slashes, case) and link
+    /// sessions in subdirectories of a project path, per `linking.normalize_paths`
+    pub fn with_path_normalization(mut self, enabled: bool) -> Self {
+        self.normalize_paths = enabled;
+        self
+    }
+
+    /// Pool of read-only connections to this database, for readers running alongside
+    /// this (writing) store. The journal mode stays the configured one: under WAL
+    /// readers and the writer don't block each other, otherwise readers wait out writes
+    /// up to the busy timeout.
+    pub fn read_pool(&self, max_idle: usize) -> Result<ReadPool> {
+        let busy_timeout: u64 = self
+            .conn
+            .query_row("PRAGMA busy_timeout", [], |row| row.get(0))?;
+        let mut pool = ReadPool::new(&self.path, max_idle);
+        pool.normalize_paths = self.normalize_paths;
+        pool.busy_timeout = std::time::Duration::from_millis(busy_timeout);
+        Ok(pool)
+    }
+
     fn init_schema(&self) -> Result<()> {
+        schema::migrate(&self.conn)?;
+        // New tables and indexes are created idempotently on every open
         self.conn.execute_batch(SCHEMA)?;
+        self.conn.execute_batch(COUNTER_TRIGGERS)?;
+        self.conn.execute_batch(SEARCH_TRIGGERS)?;
         Ok(())
     }
 
+    /// Schema version of the open database
+    pub fn schema_version(&self) -> Result<u32> {
+        schema::current_version(&self.conn)
+    }
+
+    /// Run `f` inside a transaction, committing on success and rolling back on error.
+    /// Uses a savepoint, so calls nest inside an outer transaction.
+    pub fn transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT chronicle_tx")?;
+        match f() {
+            Ok(value) => {
+                self.conn.execute_batch("RELEASE chronicle_tx")?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.conn
+                    .execute_batch("ROLLBACK TO chronicle_tx; RELEASE chronicle_tx")?;
+                Err(e)
+            }
+        }
+    }
+
     // ============================================
     // PROVIDERS & SOURCES
     // ============================================
@@ -75,6 +236,19 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Record what a probe's source supports, for output that qualifies its numbers
+    pub fn set_probe_capabilities(
+        &self,
+        probe_id: &str,
+        capabilities: &ProbeCapabilities,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE probe_sources SET capabilities = ? WHERE id = ?",
+            params![serde_json::to_string(capabilities)?, probe_id],
+        )?;
+        Ok(())
+    }
+
     // ============================================
     // PROJECTS
     // ============================================
@@ -127,19 +301,170 @@ impl MetadataStore {
         Ok(())
     }
 
-    /// Find project by path
-    pub fn find_project_by_path(&self, path: &str) -> Result<Option<String>> {
-        let result = self.conn.query_row(
-            "SELECT project_id FROM project_paths WHERE path = ?",
-            params![path],
+    /// Find project by ID prefix or exact name
+    pub fn find_project(&self, query: &str) -> Result<Option<ProjectRow>> {
+        Ok(self
+            .list_projects()?
+            .into_iter()
+            .find(|p| p.id.starts_with(query) || p.name == query))
+    }
+
+    /// Get all paths registered for a project (primary first)
+    pub fn get_project_paths(&self, project_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT path FROM project_paths WHERE project_id = ? ORDER BY is_primary DESC, path",
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| row.get(0))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Get identifiers (type, value) registered for a project
+    pub fn get_project_identifiers(&self, project_id: &str) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT identifier_type, identifier_value FROM project_identifiers
+             WHERE project_id = ? ORDER BY identifier_type, identifier_value",
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Set one top-level key in a project's JSON metadata, keeping other keys
+    pub fn merge_project_metadata(
+        &self,
+        project_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<()> {
+        let existing: Option<String> = self.conn.query_row(
+            "SELECT metadata FROM projects WHERE id = ?",
+            params![project_id],
             |row| row.get(0),
-        );
+        )?;
 
-        match result {
-            Ok(id) => Ok(Some(id)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+        let mut metadata = existing
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+            .filter(|m| m.is_object())
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata[key] = value;
+
+        self.conn.execute(
+            "UPDATE projects SET metadata = ? WHERE id = ?",
+            params![metadata.to_string(), project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Remove one top-level key from a project's JSON metadata
+    pub fn remove_project_metadata(&self, project_id: &str, key: &str) -> Result<bool> {
+        let existing: Option<String> = self.conn.query_row(
+            "SELECT metadata FROM projects WHERE id = ?",
+            params![project_id],
+            |row| row.get(0),
+        )?;
+        let Some(mut metadata) =
+            existing.and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+        else {
+            return Ok(false);
+        };
+        let removed = metadata
+            .as_object_mut()
+            .is_some_and(|m| m.remove(key).is_some());
+        if removed {
+            self.conn.execute(
+                "UPDATE projects SET metadata = ? WHERE id = ?",
+                params![metadata.to_string(), project_id],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    pub fn rename_project(&self, project_id: &str, name: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects SET name = ? WHERE id = ?",
+            params![name, project_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_project_type(&self, project_id: &str, project_type: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects SET type = ? WHERE id = ?",
+            params![project_type, project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Make `path` the project's primary path; the previous primary stays registered
+    pub fn set_project_primary_path(&self, project_id: &str, path: &str) -> Result<()> {
+        if let Some(owner) = self.find_project_by_path(path)? {
+            if owner != project_id {
+                anyhow::bail!("Path {} already belongs to project {}", path, owner);
+            }
+        }
+        self.transaction(|| {
+            self.conn.execute(
+                "UPDATE project_paths SET is_primary = (path = ?2) WHERE project_id = ?1",
+                params![project_id, path],
+            )?;
+            self.add_project_path(project_id, path, true)?;
+            self.conn.execute(
+                "UPDATE projects SET primary_path = ? WHERE id = ?",
+                params![path, project_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Find the project a path is registered to
+    pub fn find_project_by_path(&self, path: &str) -> Result<Option<String>> {
+        let exact = self
+            .conn
+            .query_row(
+                "SELECT project_id FROM project_paths WHERE path = ?",
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if exact.is_some() || !self.normalize_paths {
+            return Ok(exact);
+        }
+        let normalized = paths::normalize_path(path);
+        let (registered, owners) = self.normalized_project_paths()?;
+        Ok(registered
+            .iter()
+            .position(|p| *p == normalized)
+            .map(|i| owners[i].clone()))
+    }
+
+    /// Project a session working in `path` belongs to: the one registered for the path
+    /// or, with path normalization, for its nearest ancestor
+    pub fn project_for_path(&self, path: &str) -> Result<Option<String>> {
+        if !self.normalize_paths {
+            return self.find_project_by_path(path);
+        }
+        let (registered, owners) = self.normalized_project_paths()?;
+        Ok(
+            paths::nearest_registered(&paths::normalize_path(path), &registered)
+                .map(|i| owners[i].clone()),
+        )
+    }
+
+    /// Registered project paths (normalized) and their projects
+    fn normalized_project_paths(&self) -> Result<(Vec<String>, Vec<String>)> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, project_id FROM project_paths ORDER BY is_primary DESC")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut registered = vec![];
+        let mut owners = vec![];
+        for row in rows {
+            let (path, project_id) = row?;
+            registered.push(paths::normalize_path(&path));
+            owners.push(project_id);
         }
+        Ok((registered, owners))
     }
 
     /// Find project by git remote
@@ -158,6 +483,24 @@ impl MetadataStore {
         }
     }
 
+    pub fn set_project_visibility(&self, project_id: &str, visibility: Visibility) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects SET visibility = ? WHERE id = ?",
+            params![visibility.as_str(), project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Projects whose history may not be sent to `audience`, by ID
+    pub fn withheld_projects(&self, audience: Audience) -> Result<HashMap<String, Visibility>> {
+        Ok(self
+            .list_projects()?
+            .into_iter()
+            .filter(|p| !p.visibility.allows(audience))
+            .map(|p| (p.id, p.visibility))
+            .collect())
+    }
+
     /// Update project last_activity timestamp
     pub fn touch_project(&self, project_id: &str) -> Result<()> {
         self.conn.execute(
@@ -167,6 +510,82 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Delete a project. Its sessions move to `reassign_to` (keeping how they were
+    /// assigned) or, without one, become unassigned and free to auto-link again.
+    /// Returns the number of sessions moved or unassigned.
+    pub fn delete_project(&self, project_id: &str, reassign_to: Option<&str>) -> Result<usize> {
+        self.transaction(|| {
+            let sessions = match reassign_to {
+                Some(target) => self.conn.execute(
+                    "UPDATE sessions SET project_id = ?2 WHERE project_id = ?1",
+                    params![project_id, target],
+                )?,
+                None => self.conn.execute(
+                    "UPDATE sessions SET project_id = NULL, project_assignment = 'auto'
+                     WHERE project_id = ?1",
+                    params![project_id],
+                )?,
+            };
+            // Foreign keys aren't enforced, so dependent rows go by hand
+            for table in ["project_paths", "project_identifiers", "project_snapshots"] {
+                self.conn.execute(
+                    &format!("DELETE FROM {} WHERE project_id = ?", table),
+                    params![project_id],
+                )?;
+            }
+            self.conn
+                .execute("DELETE FROM projects WHERE id = ?", params![project_id])?;
+            if let Some(target) = reassign_to {
+                self.touch_project(target)?;
+            }
+            Ok(sessions)
+        })
+    }
+
+    /// Fold `source` into `target`: sessions, paths and identifiers move over,
+    /// metadata keys `target` lacks are copied, and the stricter visibility wins.
+    /// `source` is then deleted along with its snapshots.
+    pub fn merge_projects(&self, source: &ProjectRow, target: &ProjectRow) -> Result<MergeCounts> {
+        self.transaction(|| {
+            let paths = self.conn.execute(
+                "UPDATE OR IGNORE project_paths SET project_id = ?2, is_primary = FALSE
+                 WHERE project_id = ?1",
+                params![source.id, target.id],
+            )?;
+            let identifiers = self.conn.execute(
+                "UPDATE OR IGNORE project_identifiers SET project_id = ?2 WHERE project_id = ?1",
+                params![source.id, target.id],
+            )?;
+            let source_metadata = source
+                .metadata
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok());
+            if let Some(serde_json::Value::Object(keys)) = source_metadata {
+                let target_metadata = target
+                    .metadata
+                    .as_deref()
+                    .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok());
+                for (key, value) in keys {
+                    if target_metadata
+                        .as_ref()
+                        .is_none_or(|m| m.get(&key).is_none())
+                    {
+                        self.merge_project_metadata(&target.id, &key, value)?;
+                    }
+                }
+            }
+            if source.visibility < target.visibility {
+                self.set_project_visibility(&target.id, source.visibility)?;
+            }
+            let sessions = self.delete_project(&source.id, Some(&target.id))?;
+            Ok(MergeCounts {
+                sessions,
+                paths,
+                identifiers,
+            })
+        })
+    }
+
     // ============================================
     // SESSIONS
     // ============================================
@@ -261,14 +680,19 @@ impl MetadataStore {
             r#"INSERT INTO sessions 
                (id, probe_source_id, project_id, project_assignment, external_id, short_hash, 
                 title, primary_provider, primary_model, message_count, first_timestamp, 
-                last_timestamp, source_path, raw_project_path, raw_git_remote, indexed_at)
-               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
+                last_timestamp, source_path, raw_project_path, raw_git_remote, source_group,
+                language, compactions, indexed_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
                ON CONFLICT(id) DO UPDATE SET
                    title = excluded.title,
+                   source_group = excluded.source_group,
+                   language = excluded.language,
+                   compactions = excluded.compactions,
                    primary_provider = excluded.primary_provider,
                    primary_model = excluded.primary_model,
                    message_count = excluded.message_count,
                    last_timestamp = excluded.last_timestamp,
+                   orphaned_at = NULL,
                    indexed_at = datetime('now')"#,
             params![
                 session_id,
@@ -286,6 +710,9 @@ impl MetadataStore {
                 session.source_path.to_string_lossy().to_string(),
                 metadata.project_path,
                 metadata.git_remote,
+                metadata.source_group,
+                metadata.language,
+                metadata.compactions as i64,
             ],
         )?;
 
@@ -297,17 +724,69 @@ impl MetadataStore {
         Ok(session_id)
     }
 
+    /// Whether a session with this stored ID has been extracted
+    pub fn has_session(&self, session_id: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?)",
+            params![session_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Source fingerprint recorded at the session's last extraction
+    pub fn get_session_fingerprint(&self, session_id: &str) -> Result<Option<SourceFingerprint>> {
+        let fingerprint = self
+            .conn
+            .query_row(
+                "SELECT source_mtime, source_size FROM sessions WHERE id = ?",
+                params![session_id],
+                |row| {
+                    Ok(
+                        match (row.get::<_, Option<i64>>(0)?, row.get::<_, Option<i64>>(1)?) {
+                            (Some(mtime), Some(size)) => Some(SourceFingerprint {
+                                mtime,
+                                size: size as u64,
+                            }),
+                            _ => None,
+                        },
+                    )
+                },
+            )
+            .optional()?;
+        Ok(fingerprint.flatten())
+    }
+
+    /// Record the source fingerprint after a successful extraction
+    pub fn set_session_fingerprint(
+        &self,
+        session_id: &str,
+        fingerprint: &SourceFingerprint,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET source_mtime = ?, source_size = ? WHERE id = ?",
+            params![fingerprint.mtime, fingerprint.size as i64, session_id],
+        )?;
+        Ok(())
+    }
+
     /// Try to auto-link a session to an existing project
     fn auto_link_project(&self, metadata: &SessionMetadata) -> Result<Option<String>> {
+        self.auto_link_target(
+            metadata.project_path.as_deref(),
+            metadata.git_remote.as_deref(),
+        )
+    }
+
+    fn auto_link_target(&self, path: Option<&str>, remote: Option<&str>) -> Result<Option<String>> {
         // Try path matching first
-        if let Some(ref path) = metadata.project_path {
-            if let Some(project_id) = self.find_project_by_path(path)? {
+        if let Some(path) = path {
+            if let Some(project_id) = self.project_for_path(path)? {
                 return Ok(Some(project_id));
             }
         }
 
         // Try git remote matching
-        if let Some(ref remote) = metadata.git_remote {
+        if let Some(remote) = remote {
             if let Some(project_id) = self.find_project_by_git_remote(remote)? {
                 return Ok(Some(project_id));
             }
@@ -316,6 +795,46 @@ impl MetadataStore {
         Ok(None)
     }
 
+    /// Link a session to a project unless the user has assigned it explicitly
+    pub fn link_session_auto(&self, session_id: &str, project_id: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE sessions SET project_id = ?1
+             WHERE id = ?2 AND project_assignment = 'auto'
+               AND (project_id IS NULL OR project_id != ?1)",
+            params![project_id, session_id],
+        )?;
+        if changed > 0 {
+            self.touch_project(project_id)?;
+        }
+        Ok(changed > 0)
+    }
+
+    /// Re-run path and git remote auto-linking for sessions left without a project (and
+    /// not unassigned by the user), e.g. after a project gained a path; returns how
+    /// many were linked
+    pub fn relink_sessions(&self) -> Result<usize> {
+        let candidates: Vec<(String, Option<String>, Option<String>)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, raw_project_path, raw_git_remote FROM sessions
+                 WHERE project_id IS NULL AND project_assignment = 'auto'
+                   AND (raw_project_path IS NOT NULL OR raw_git_remote IS NOT NULL)",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        self.transaction(|| {
+            let mut linked = 0;
+            for (session_id, path, remote) in &candidates {
+                if let Some(project_id) =
+                    self.auto_link_target(path.as_deref(), remote.as_deref())?
+                {
+                    linked += self.link_session_auto(session_id, &project_id)? as usize;
+                }
+            }
+            Ok(linked)
+        })
+    }
+
     /// Assign a session to a project (user action)
     pub fn assign_session_to_project(
         &self,
@@ -335,6 +854,16 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Archive or unarchive a session; returns whether it changed
+    pub fn set_session_archived(&self, session_id: &str, archived: bool) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE sessions SET archived = ?2
+             WHERE id = ?1 AND COALESCE(archived, FALSE) != ?2",
+            params![session_id, archived],
+        )?;
+        Ok(changed > 0)
+    }
+
     /// Mark a session as explicitly unassigned
     pub fn unassign_session(&self, session_id: &str) -> Result<()> {
         self.conn.execute(
@@ -348,14 +877,48 @@ impl MetadataStore {
     // MESSAGES
     // ============================================
 
-    pub fn insert_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<()> {
-        // Delete existing messages for this session
-        self.conn.execute(
-            "DELETE FROM messages WHERE session_id = ?",
-            params![session_id],
+    /// Upsert a session's messages by stable identity, so message ids (and rows that
+    /// reference them) survive re-indexing. Messages no longer in the source are removed.
+    /// Returns the message ids in the order given.
+    pub fn insert_messages(
+        &self,
+        session_id: &str,
+        messages: &[MessageMetadata],
+    ) -> Result<Vec<i64>> {
+        self.transaction(|| self.write_messages(session_id, messages))
+    }
+
+    fn write_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<Vec<i64>> {
+        // Existing messages of this session and any sessions split from it
+        let mut stmt = self.conn.prepare(
+            "SELECT message_key, id FROM messages WHERE session_id = ?1
+                OR session_id IN (SELECT id FROM sessions WHERE parent_session_id = ?1)",
         )?;
+        let existing: HashMap<String, i64> = stmt
+            .query_map(params![session_id], |row| {
+                Ok((row.get::<_, Option<String>>(0)?, row.get(1)?))
+            })?
+            .filter_map(|row| match row {
+                Ok((Some(key), id)) => Some(Ok((key, id))),
+                Ok((None, _)) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut seen_keys: HashMap<String, usize> = HashMap::new();
+        let mut kept_ids = vec![];
+
+        for (idx, msg) in messages.iter().enumerate() {
+            // Disambiguate repeated keys by occurrence
+            let base_key = message_key(msg, idx);
+            let occurrence = seen_keys.entry(base_key.clone()).or_insert(0);
+            let key = if *occurrence == 0 {
+                base_key
+            } else {
+                format!("{}#{}", base_key, occurrence)
+            };
+            *occurrence += 1;
 
-        for msg in messages {
             // Determine content_ref string (path for JSON files, empty for JSONL)
             let content_ref = msg
                 .content_ref
@@ -363,286 +926,3467 @@ impl MetadataStore {
                 .as_ref()
                 .map(|p| p.to_string_lossy().to_string());
 
-            let msg_id: i64 = self.conn.query_row(
-                r#"INSERT INTO messages 
-                   (session_id, uuid, role, provider_id, model, timestamp, source_path, 
-                    byte_offset, line_number, content_ref, has_tool_use, has_thinking)
-                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                   RETURNING id"#,
-                params![
-                    session_id,
-                    msg.uuid,
-                    msg.role,
-                    msg.provider_id,
-                    msg.model,
-                    msg.timestamp.map(|t| t.to_rfc3339()),
-                    msg.content_ref.source_path.to_string_lossy().to_string(),
-                    msg.content_ref.byte_offset.map(|o| o as i64),
-                    msg.content_ref.line_number.map(|n| n as i64),
-                    content_ref,
-                    msg.has_tool_use,
-                    msg.has_thinking,
-                ],
-                |row| row.get(0),
-            )?;
+            let values = params![
+                session_id,
+                msg.uuid,
+                key,
+                msg.role,
+                msg.provider_id,
+                msg.model,
+                msg.timestamp.map(|t| t.to_rfc3339()),
+                msg.content_ref.source_path.to_string_lossy().to_string(),
+                msg.content_ref.byte_offset.map(|o| o as i64),
+                msg.content_ref.line_number.map(|n| n as i64),
+                content_ref,
+                msg.has_tool_use,
+                msg.has_thinking,
+                msg.subtype,
+            ];
+
+            let msg_id: i64 = match existing.get(&key) {
+                Some(&id) => {
+                    self.conn
+                        .prepare_cached(
+                            r#"UPDATE messages SET
+                           session_id = ?1, uuid = ?2, message_key = ?3, role = ?4,
+                           provider_id = ?5, model = ?6, timestamp = ?7, source_path = ?8,
+                           byte_offset = ?9, line_number = ?10, content_ref = ?11,
+                           has_tool_use = ?12, has_thinking = ?13, subtype = ?14
+                           WHERE id = ?15"#,
+                        )?
+                        .execute(rusqlite::params_from_iter(
+                            values.iter().copied().chain([&id as &dyn rusqlite::ToSql]),
+                        ))?;
+                    self.conn
+                        .prepare_cached("DELETE FROM tool_uses WHERE message_id = ?")?
+                        .execute(params![id])?;
+                    self.conn
+                        .prepare_cached("DELETE FROM token_usage WHERE message_id = ?")?
+                        .execute(params![id])?;
+                    self.conn
+                        .prepare_cached("DELETE FROM request_params WHERE message_id = ?")?
+                        .execute(params![id])?;
+                    id
+                }
+                None => self
+                    .conn
+                    .prepare_cached(
+                        r#"INSERT INTO messages
+                       (session_id, uuid, message_key, role, provider_id, model, timestamp,
+                        source_path, byte_offset, line_number, content_ref, has_tool_use,
+                        has_thinking, subtype)
+                       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                       RETURNING id"#,
+                    )?
+                    .query_row(values, |row| row.get(0))?,
+            };
+            kept_ids.push(msg_id);
 
             // Insert tool uses
             for tool in &msg.tool_uses {
-                self.conn.execute(
-                    "INSERT INTO tool_uses (message_id, tool_id, tool_name, has_result)
-                     VALUES (?, ?, ?, ?)",
-                    params![msg_id, tool.tool_id, tool.tool_name, tool.has_result],
-                )?;
+                self.conn
+                    .prepare_cached(
+                        "INSERT INTO tool_uses
+                         (message_id, tool_id, tool_name, origin, mcp_server, has_result,
+                          is_error, permission, input_hash, file_path)
+                         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    )?
+                    .execute(params![
+                        msg_id,
+                        tool.tool_id,
+                        tool.tool_name,
+                        tools::origin(&tool.tool_name),
+                        tools::mcp_server(&tool.tool_name),
+                        tool.has_result,
+                        tool.is_error,
+                        tool.permission.map(|p| p.as_str()),
+                        tool.input_hash,
+                        tool.file_path
+                    ])?;
             }
 
             // Insert token usage
             if let Some(usage) = &msg.token_usage {
-                self.conn.execute(
-                    "INSERT OR REPLACE INTO token_usage 
-                     (message_id, input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens)
-                     VALUES (?, ?, ?, ?, ?)",
-                    params![
+                self.conn
+                    .prepare_cached(
+                        "INSERT OR REPLACE INTO token_usage
+                         (message_id, input_tokens, output_tokens, cache_read_tokens,
+                          cache_creation_tokens, reasoning_tokens)
+                         VALUES (?, ?, ?, ?, ?, ?)",
+                    )?
+                    .execute(params![
                         msg_id,
                         usage.input_tokens,
                         usage.output_tokens,
                         usage.cache_read_tokens,
                         usage.cache_creation_tokens,
-                    ],
-                )?;
+                        usage.reasoning_tokens,
+                    ])?;
+            }
+
+            if let Some(request) = &msg.request_params {
+                let prompt_hash = request.system_prompt_hash();
+                if let (Some(hash), Some(prompt)) = (&prompt_hash, &request.system_prompt) {
+                    self.conn
+                        .prepare_cached(
+                            "INSERT OR IGNORE INTO system_prompts (hash, content, chars)
+                             VALUES (?, ?, ?)",
+                        )?
+                        .execute(params![hash, prompt, prompt.chars().count() as i64])?;
+                }
+                self.conn
+                    .prepare_cached(
+                        "INSERT OR REPLACE INTO request_params
+                         (message_id, temperature, max_tokens, system_prompt_hash)
+                         VALUES (?, ?, ?, ?)",
+                    )?
+                    .execute(params![
+                        msg_id,
+                        request.temperature,
+                        request.max_tokens,
+                        prompt_hash
+                    ])?;
             }
         }
 
-        Ok(())
+        // Remove messages that disappeared from the source (and legacy rows without a key)
+        let kept: std::collections::HashSet<i64> = kept_ids.iter().copied().collect();
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM messages WHERE session_id = ?1
+                OR session_id IN (SELECT id FROM sessions WHERE parent_session_id = ?1)",
+        )?;
+        let stale: Vec<i64> = stmt
+            .query_map(params![session_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?
+            .into_iter()
+            .filter(|id| !kept.contains(id))
+            .collect();
+        for id in stale {
+            self.conn
+                .execute("DELETE FROM tool_uses WHERE message_id = ?", params![id])?;
+            self.conn
+                .execute("DELETE FROM token_usage WHERE message_id = ?", params![id])?;
+            self.conn.execute(
+                "DELETE FROM request_params WHERE message_id = ?",
+                params![id],
+            )?;
+            self.conn.execute(
+                "DELETE FROM message_content WHERE message_id = ?",
+                params![id],
+            )?;
+            self.conn
+                .execute("DELETE FROM messages WHERE id = ?", params![id])?;
+        }
+
+        // Re-apply metadata-level splits so derived sessions keep their tail messages
+        if self.has_splits(session_id)? {
+            self.route_split_messages(session_id)?;
+        }
+
+        Ok(kept_ids)
     }
 
     // ============================================
-    // QUERIES
+    // CONTENT CACHE
     // ============================================
 
-    pub fn list_sessions(
-        &self,
-        provider: Option<&str>,
-        source: Option<&str>,
-    ) -> Result<Vec<SessionRow>> {
-        let base_query = r#"SELECT s.id, s.probe_source_id, s.external_id, s.short_hash,
-                      s.project_id, s.project_assignment, s.title, s.primary_provider,
-                      s.primary_model, s.message_count, s.first_timestamp, 
-                      s.last_timestamp, s.raw_project_path, ps.source_name,
-                      COALESCE(p.name, ps.provider_id, 'multi') as provider_name,
-                      proj.name as project_name
-               FROM sessions s
-               JOIN probe_sources ps ON s.probe_source_id = ps.id
-               LEFT JOIN providers p ON ps.provider_id = p.id
-               LEFT JOIN projects proj ON s.project_id = proj.id"#;
-
-        let query = match (provider, source) {
-            (Some(_), Some(_)) => format!(
-                "{} WHERE (p.id = ?1 OR ps.provider_id = ?1) AND ps.source_name = ?2 ORDER BY s.last_timestamp DESC",
-                base_query
-            ),
-            (Some(_), None) => format!(
-                "{} WHERE p.id = ?1 OR ps.provider_id = ?1 ORDER BY s.last_timestamp DESC",
-                base_query
-            ),
-            (None, Some(_)) => format!(
-                "{} WHERE ps.source_name = ?1 ORDER BY s.last_timestamp DESC",
-                base_query
-            ),
-            (None, None) => format!("{} ORDER BY s.last_timestamp DESC", base_query),
-        };
+    /// Store compressed copies of raw message content, replacing older copies
+    pub fn cache_content(&self, contents: &[(i64, String)]) -> Result<()> {
+        for (message_id, raw) in contents {
+            let data = zstd::encode_all(raw.as_bytes(), 3)?;
+            self.conn
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO message_content (message_id, data, size)
+                     VALUES (?, ?, ?)",
+                )?
+                .execute(params![message_id, data, raw.len() as i64])?;
+        }
+        Ok(())
+    }
 
-        let mut stmt = self.conn.prepare(&query)?;
+    // ============================================
+    // SPLITS
+    // ============================================
 
-        let map_row = |row: &rusqlite::Row| -> rusqlite::Result<SessionRow> {
-            Ok(SessionRow {
-                id: row.get(0)?,
-                probe_source_id: row.get(1)?,
-                external_id: row.get(2)?,
-                short_hash: row.get(3)?,
-                project_id: row.get(4)?,
-                project_assignment: row.get(5)?,
-                title: row.get(6)?,
-                primary_provider: row.get(7)?,
-                primary_model: row.get(8)?,
-                message_count: row.get(9)?,
-                first_timestamp: row.get(10)?,
-                last_timestamp: row.get(11)?,
-                project_path: row.get(12)?,
-                source_name: row.get(13)?,
-                provider_name: row.get(14)?,
-                project_name: row.get(15)?,
-            })
-        };
-
-        let rows: Vec<SessionRow> = match (provider, source) {
-            (Some(p), Some(s)) => stmt
-                .query_map(params![p, s], map_row)?
-                .collect::<Result<Vec<_>, _>>()?,
-            (Some(p), None) => stmt
-                .query_map(params![p], map_row)?
-                .collect::<Result<Vec<_>, _>>()?,
-            (None, Some(s)) => stmt
-                .query_map(params![s], map_row)?
-                .collect::<Result<Vec<_>, _>>()?,
-            (None, None) => stmt
-                .query_map([], map_row)?
-                .collect::<Result<Vec<_>, _>>()?,
-        };
-
-        Ok(rows)
+    fn has_splits(&self, root_id: &str) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sessions WHERE parent_session_id = ?",
+            params![root_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
     }
 
-    /// Get session by short_hash (primary search) or fallback to id/external_id
-    pub fn get_session(&self, query: &str) -> Result<Option<SessionRow>> {
-        let row = self.conn.query_row(
-            r#"SELECT s.id, s.probe_source_id, s.external_id, s.short_hash,
-                      s.project_id, s.project_assignment, s.title, s.primary_provider,
-                      s.primary_model, s.message_count, s.first_timestamp, 
-                      s.last_timestamp, s.raw_project_path, ps.source_name,
-                      COALESCE(p.name, ps.provider_id, 'multi') as provider_name,
-                      proj.name as project_name
-               FROM sessions s
-               JOIN probe_sources ps ON s.probe_source_id = ps.id
-               LEFT JOIN providers p ON ps.provider_id = p.id
-               LEFT JOIN projects proj ON s.project_id = proj.id
-               WHERE s.short_hash = ?1 OR s.short_hash LIKE ?2
-                  OR s.id LIKE ?2 OR s.external_id LIKE ?2
-               ORDER BY 
-                   CASE WHEN s.short_hash = ?1 THEN 0 ELSE 1 END
-               LIMIT 1"#,
-            params![query, format!("{}%", query)],
-            |row| {
-                Ok(SessionRow {
-                    id: row.get(0)?,
-                    probe_source_id: row.get(1)?,
-                    external_id: row.get(2)?,
-                    short_hash: row.get(3)?,
-                    project_id: row.get(4)?,
-                    project_assignment: row.get(5)?,
-                    title: row.get(6)?,
-                    primary_provider: row.get(7)?,
-                    primary_model: row.get(8)?,
-                    message_count: row.get(9)?,
-                    first_timestamp: row.get(10)?,
-                    last_timestamp: row.get(11)?,
-                    project_path: row.get(12)?,
-                    source_name: row.get(13)?,
-                    provider_name: row.get(14)?,
-                    project_name: row.get(15)?,
-                })
-            },
-        );
-
-        match row {
-            Ok(r) => Ok(Some(r)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+    /// Split a session at a 1-based message number, creating a derived session
+    /// that owns that message and everything after it (up to any later split).
+    /// Source files are untouched; returns the derived session's short hash.
+    pub fn split_session(&self, session: &SessionRow, at_message: usize) -> Result<String> {
+        let message_count = self.get_messages(&session.id)?.len();
+        if at_message < 2 || at_message > message_count {
+            anyhow::bail!(
+                "Split point must be between 2 and {} for session '{}'",
+                message_count,
+                session.short_hash
+            );
         }
-    }
 
-    pub fn get_messages(&self, session_id: &str) -> Result<Vec<MessageRow>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, uuid, role, provider_id, model, timestamp, source_path, 
-                      byte_offset, line_number, content_ref, has_tool_use, has_thinking
-               FROM messages
-               WHERE session_id = ?
-               ORDER BY COALESCE(line_number, id)"#,
+        // Splits are positioned against the root session's full message order
+        let (root_id, offset): (String, i64) = self.conn.query_row(
+            "SELECT COALESCE(parent_session_id, id), COALESCE(split_index, 0)
+             FROM sessions WHERE id = ?",
+            params![session.id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
+        let split_index = offset + at_message as i64 - 1;
 
-        let rows = stmt.query_map(params![session_id], |row| {
-            Ok(MessageRow {
-                id: row.get(0)?,
-                uuid: row.get(1)?,
-                role: row.get(2)?,
-                provider_id: row.get(3)?,
-                model: row.get(4)?,
-                timestamp: row.get(5)?,
-                source_path: row.get(6)?,
-                byte_offset: row.get(7)?,
-                line_number: row.get(8)?,
-                content_ref: row.get(9)?,
-                has_tool_use: row.get(10)?,
-                has_thinking: row.get(11)?,
+        let root_short_hash: String = self.conn.query_row(
+            "SELECT short_hash FROM sessions WHERE id = ?",
+            params![root_id],
+            |row| row.get(0),
+        )?;
+        let derived_id = format!("{}@{}", root_id, split_index + 1);
+        let derived_hash = format!("{}@{}", root_short_hash, split_index + 1);
+        if self.has_session(&derived_id)? {
+            anyhow::bail!(
+                "Session '{}' is already split at message {} (session '{}')",
+                session.short_hash,
+                at_message,
+                derived_hash
+            );
+        }
+
+        self.conn.execute(
+            r#"INSERT INTO sessions
+               (id, probe_source_id, project_id, project_assignment, external_id, short_hash,
+                title, primary_provider, primary_model, message_count, first_timestamp,
+                last_timestamp, source_path, raw_project_path, raw_git_remote, source_group,
+                language, parent_session_id, split_index, indexed_at)
+               SELECT ?1, probe_source_id, project_id, project_assignment, external_id, ?2,
+                      COALESCE(title, '') || ' (from message ' || ?4 || ')',
+                      primary_provider, primary_model, 0, NULL, NULL, source_path,
+                      raw_project_path, raw_git_remote, source_group, language, ?3, ?5,
+                      datetime('now')
+               FROM sessions WHERE id = ?3"#,
+            params![
+                derived_id,
+                derived_hash,
+                root_id,
+                split_index + 1,
+                split_index
+            ],
+        )?;
+
+        self.route_split_messages(&root_id)?;
+        Ok(derived_hash)
+    }
+
+    /// Assign each message of a root session's family to the split covering its position
+    fn route_split_messages(&self, root_id: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, split_index FROM sessions WHERE parent_session_id = ? ORDER BY split_index",
+        )?;
+        let splits: Vec<(String, i64)> = stmt
+            .query_map(params![root_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut family: Vec<String> = vec![root_id.to_string()];
+        family.extend(splits.iter().map(|(id, _)| id.clone()));
+
+        // Message rows of the whole family in source order
+        let placeholders = vec!["?"; family.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id FROM messages WHERE session_id IN ({}) ORDER BY COALESCE(line_number, id), id",
+            placeholders
+        ))?;
+        let message_ids: Vec<i64> = stmt
+            .query_map(rusqlite::params_from_iter(family.iter()), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (position, message_id) in message_ids.iter().enumerate() {
+            let owner = splits
+                .iter()
+                .rev()
+                .find(|(_, start)| *start <= position as i64)
+                .map(|(id, _)| id.as_str())
+                .unwrap_or(root_id);
+            self.conn.execute(
+                "UPDATE messages SET session_id = ? WHERE id = ?",
+                params![owner, message_id],
+            )?;
+        }
+
+        // Refresh counts and time bounds from the routed messages
+        for session_id in &family {
+            self.conn.execute(
+                r#"UPDATE sessions SET
+                       message_count = (SELECT COUNT(*) FROM messages WHERE session_id = ?1),
+                       first_timestamp = CASE WHEN parent_session_id IS NULL THEN first_timestamp
+                           ELSE (SELECT MIN(timestamp) FROM messages WHERE session_id = ?1) END,
+                       last_timestamp = COALESCE(
+                           (SELECT MAX(timestamp) FROM messages WHERE session_id = ?1),
+                           last_timestamp)
+                   WHERE id = ?1"#,
+                params![session_id],
+            )?;
+            self.refresh_session_metrics(session_id)?;
+        }
+
+        Ok(())
+    }
+
+    // ============================================
+    // REFERENCES
+    // ============================================
+
+    /// Replace the issue/PR references recorded for a session
+    pub fn replace_session_references(
+        &self,
+        session_id: &str,
+        references: &[IssueReference],
+    ) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM session_references WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for reference in references {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO session_references (session_id, ref_type, ref_key, url)
+                 VALUES (?, ?, ?, ?)",
+                params![
+                    session_id,
+                    reference.kind.as_str(),
+                    reference.key,
+                    reference.url
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Replace the git commits recorded for a session
+    pub fn replace_session_commits(&self, session_id: &str, commits: &[CommitRef]) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM session_commits WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for commit in commits {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO session_commits (session_id, commit_hash, message)
+                 VALUES (?, ?, ?)",
+                params![session_id, commit.hash, commit.message],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Find sessions referencing an issue key (exact, or `#N` matching any `owner/repo#N`)
+    pub fn find_sessions_by_reference(&self, key: &str) -> Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"{}
+               WHERE s.id IN (
+                   SELECT session_id FROM session_references
+                   WHERE ref_key = ?1 COLLATE NOCASE
+                      OR (?1 LIKE '#%' AND ref_key LIKE '%' || ?1)
+               )
+               ORDER BY s.last_timestamp DESC"#,
+            SESSION_SELECT
+        ))?;
+
+        let rows = stmt.query_map(params![key], session_from_row)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    // ============================================
+    // ANOMALIES
+    // ============================================
+
+    /// Replace the tool-loop anomalies recorded for a session
+    pub fn replace_tool_loops(&self, session_id: &str, loops: &[ToolLoop]) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM anomalies WHERE session_id = ? AND kind = 'tool_loop'",
+            params![session_id],
+        )?;
+
+        for tool_loop in loops {
+            self.conn.execute(
+                "INSERT INTO anomalies (session_id, kind, tool_name, repeat_count, message_index)
+                 VALUES (?, 'tool_loop', ?, ?, ?)",
+                params![
+                    session_id,
+                    tool_loop.tool_name,
+                    tool_loop.repeats as i64,
+                    tool_loop.first_message as i64
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// All recorded anomalies with their session's display info, worst first
+    pub fn list_anomalies(&self) -> Result<Vec<AnomalyRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.short_hash, s.title, s.last_timestamp, a.kind, a.tool_name,
+                      a.repeat_count, a.message_index
+               FROM anomalies a
+               JOIN sessions s ON s.id = a.session_id
+               ORDER BY a.repeat_count DESC, s.last_timestamp DESC"#,
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(AnomalyRow {
+                short_hash: row.get(0)?,
+                title: row.get(1)?,
+                last_timestamp: row.get(2)?,
+                kind: row.get(3)?,
+                tool_name: row.get(4)?,
+                repeat_count: row.get(5)?,
+                message_index: row.get(6)?,
             })
         })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    // ============================================
+    // PARSE STATS
+    // ============================================
+
+    /// Replace the parse skip counts recorded for a session
+    pub fn replace_session_skips(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM session_skips WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for (reason, count) in skipped.iter() {
+            self.conn.execute(
+                "INSERT INTO session_skips (session_id, reason, count) VALUES (?, ?, ?)",
+                params![session_id, reason, count as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Replace the individual unparsed records stored for a session
+    pub fn replace_parse_errors(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM parse_errors WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for record in skipped.records() {
+            self.conn
+                .prepare_cached(
+                    "INSERT INTO parse_errors (session_id, reason, location) VALUES (?, ?, ?)",
+                )?
+                .execute(params![session_id, record.reason, record.location])?;
+        }
+        Ok(())
+    }
 
+    /// Indexed sessions and messages per probe source
+    pub fn probe_stats(&self) -> Result<Vec<ProbeStatsRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT ps.id, ps.last_indexed,
+                      (SELECT COUNT(*) FROM sessions s WHERE s.probe_source_id = ps.id),
+                      (SELECT COUNT(*) FROM messages m
+                       JOIN sessions s ON s.id = m.session_id
+                       WHERE s.probe_source_id = ps.id),
+                      ps.capabilities
+               FROM probe_sources ps
+               ORDER BY ps.id"#,
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let capabilities: Option<String> = row.get(4)?;
+            Ok(ProbeStatsRow {
+                probe_source_id: row.get(0)?,
+                last_indexed: row.get(1)?,
+                sessions: row.get(2)?,
+                messages: row.get(3)?,
+                // Not recorded before the first extraction with this build
+                capabilities: capabilities
+                    .and_then(|c| serde_json::from_str(&c).ok())
+                    .unwrap_or_default(),
+            })
+        })?;
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
-    pub fn list_projects(&self) -> Result<Vec<ProjectRow>> {
+    /// Session activity metrics per probe source: average times and message totals
+    pub fn activity_stats(&self) -> Result<Vec<ActivityStatsRow>> {
         let mut stmt = self.conn.prepare(
-            r#"SELECT p.id, p.name, p.type, p.primary_path, p.metadata, 
-                      p.created_at, p.last_activity,
-                      (SELECT COUNT(*) FROM sessions s WHERE s.project_id = p.id) as session_count
-               FROM projects p
-               ORDER BY p.last_activity DESC"#,
+            r#"SELECT probe_source_id, AVG(duration_secs), AVG(active_secs),
+                      SUM(COALESCE(user_messages, 0)), SUM(COALESCE(assistant_messages, 0)),
+                      SUM(COALESCE(tool_calls, 0))
+               FROM sessions
+               WHERE merged_into IS NULL
+               GROUP BY probe_source_id
+               ORDER BY probe_source_id"#,
         )?;
 
         let rows = stmt.query_map([], |row| {
-            Ok(ProjectRow {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                project_type: row.get(2)?,
-                primary_path: row.get(3)?,
-                metadata: row.get(4)?,
-                created_at: row.get(5)?,
-                last_activity: row.get(6)?,
-                session_count: row.get(7)?,
+            let average = |index| -> rusqlite::Result<Option<i64>> {
+                Ok(row.get::<_, Option<f64>>(index)?.map(|v| v.round() as i64))
+            };
+            Ok(ActivityStatsRow {
+                probe_source_id: row.get(0)?,
+                totals: SessionMetrics {
+                    duration_secs: average(1)?,
+                    active_secs: average(2)?,
+                    user_messages: row.get(3)?,
+                    assistant_messages: row.get(4)?,
+                    tool_calls: row.get(5)?,
+                },
             })
         })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Skipped source entries per probe source and reason, most frequent first
+    pub fn skip_stats(&self) -> Result<Vec<SkipStatsRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.probe_source_id, k.reason, COUNT(DISTINCT k.session_id), SUM(k.count)
+               FROM session_skips k
+               JOIN sessions s ON s.id = k.session_id
+               GROUP BY s.probe_source_id, k.reason
+               ORDER BY s.probe_source_id, SUM(k.count) DESC"#,
+        )?;
 
+        let rows = stmt.query_map([], |row| {
+            Ok(SkipStatsRow {
+                probe_source_id: row.get(0)?,
+                reason: row.get(1)?,
+                sessions: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })?;
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
-}
 
-// ============================================
-// ROW TYPES
-// ============================================
+    // ============================================
+    // USAGE & ALERTS
+    // ============================================
 
-#[derive(Debug)]
-pub struct SessionRow {
-    pub id: String,
-    pub probe_source_id: String,
-    pub external_id: String,
-    pub short_hash: String,
-    pub project_id: Option<String>,
-    pub project_assignment: String,
-    pub title: Option<String>,
-    pub primary_provider: Option<String>,
-    pub primary_model: Option<String>,
-    pub message_count: i64,
-    pub first_timestamp: Option<String>,
-    pub last_timestamp: Option<String>,
-    pub project_path: Option<String>,
-    pub source_name: String,
-    pub provider_name: String,
-    pub project_name: Option<String>,
-}
+    /// Total tokens (input + output + cache) per local day with usage, from a local
+    /// `YYYY-MM-DD` day onward, ascending
+    pub fn daily_token_usage(&self, since_day: &str) -> Result<Vec<DailyUsage>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT date(m.timestamp, 'localtime') as day,
+                      SUM(COALESCE(t.input_tokens, 0) + COALESCE(t.output_tokens, 0)
+                          + COALESCE(t.cache_read_tokens, 0)
+                          + COALESCE(t.cache_creation_tokens, 0))
+               FROM token_usage t
+               JOIN messages m ON m.id = t.message_id
+               WHERE m.timestamp IS NOT NULL
+                 -- A day wide to cover any UTC offset; the local day decides
+                 AND m.timestamp >= date(?1, '-1 day')
+                 AND date(m.timestamp, 'localtime') >= ?1
+               GROUP BY day
+               ORDER BY day"#,
+        )?;
 
-#[derive(Debug)]
-pub struct MessageRow {
-    pub id: i64,
-    pub uuid: Option<String>,
-    pub role: String,
-    pub provider_id: Option<String>,
-    pub model: Option<String>,
-    pub timestamp: Option<String>,
-    pub source_path: String,
-    pub byte_offset: Option<i64>,
-    pub line_number: Option<i64>,
-    pub content_ref: Option<String>,
-    pub has_tool_use: bool,
-    pub has_thinking: bool,
-}
+        let rows = stmt.query_map(params![since_day], |row| {
+            Ok(DailyUsage {
+                day: row.get(0)?,
+                tokens: row.get(1)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
 
-#[derive(Debug)]
-pub struct ProjectRow {
-    pub id: String,
-    pub name: String,
-    pub project_type: String,
-    pub primary_path: Option<String>,
-    pub metadata: Option<String>,
-    pub created_at: Option<String>,
-    pub last_activity: Option<String>,
-    pub session_count: i64,
+    /// Token totals grouped by session, model and day, optionally from an RFC 3339 start time
+    pub fn token_usage(&self, since: Option<&str>) -> Result<Vec<UsageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.short_hash, s.probe_source_id, p.name, m.model,
+                      date(m.timestamp) as day,
+                      SUM(COALESCE(t.input_tokens, 0)), SUM(COALESCE(t.output_tokens, 0)),
+                      SUM(COALESCE(t.cache_read_tokens, 0)),
+                      SUM(COALESCE(t.cache_creation_tokens, 0))
+               FROM token_usage t
+               JOIN messages m ON m.id = t.message_id
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE ?1 IS NULL OR m.timestamp >= ?1
+               GROUP BY s.id, m.model, day
+               ORDER BY day"#,
+        )?;
+
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(UsageRow {
+                session_id: row.get(0)?,
+                short_hash: row.get(1)?,
+                probe_source_id: row.get(2)?,
+                project_name: row.get(3)?,
+                model: row.get(4)?,
+                day: row.get(5)?,
+                tokens: token_counts(row, 6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Messages per session and model from an RFC 3339 start time, for `chronicle report`
+    pub fn message_activity(&self, since: &str) -> Result<Vec<ActivityRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, p.name, m.model, COUNT(*)
+               FROM messages m
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE s.merged_into IS NULL AND m.timestamp >= ?1
+               GROUP BY s.id, m.model"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(ActivityRow {
+                session_id: row.get(0)?,
+                project_name: row.get(1)?,
+                model: row.get(2)?,
+                messages: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Token usage of each message in a session that reported it, in order
+    pub fn message_token_usage(&self, session_id: &str) -> Result<Vec<(MessageRow, TokenCounts)>> {
+        let messages = self.get_messages(session_id)?;
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.message_id, COALESCE(t.input_tokens, 0), COALESCE(t.output_tokens, 0),
+                      COALESCE(t.cache_read_tokens, 0), COALESCE(t.cache_creation_tokens, 0)
+               FROM token_usage t
+               JOIN messages m ON m.id = t.message_id
+               WHERE m.session_id = ?"#,
+        )?;
+        let mut usage: HashMap<i64, TokenCounts> = stmt
+            .query_map(params![session_id], |row| {
+                Ok((row.get(0)?, token_counts(row, 1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(messages
+            .into_iter()
+            .filter_map(|m| usage.remove(&m.id).map(|tokens| (m, tokens)))
+            .collect())
+    }
+
+    /// Prompt sizes of each session's model calls in order, for sessions active since
+    /// `since`; most recently active session first
+    pub fn context_usage(&self, since: Option<&str>) -> Result<Vec<ContextUsageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.short_hash, s.probe_source_id, p.name, s.title, s.last_timestamp,
+                      COALESCE(s.compactions, 0), m.model,
+                      COALESCE(t.input_tokens, 0) + COALESCE(t.cache_read_tokens, 0)
+                          + COALESCE(t.cache_creation_tokens, 0)
+               FROM token_usage t
+               JOIN messages m ON m.id = t.message_id
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE s.merged_into IS NULL AND (?1 IS NULL OR s.last_timestamp >= ?1)
+               ORDER BY s.last_timestamp DESC, s.id, m.id"#,
+        )?;
+        let mut rows = stmt.query(params![since])?;
+        let mut sessions: Vec<ContextUsageRow> = vec![];
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            if sessions.last().is_none_or(|s| s.session_id != session_id) {
+                sessions.push(ContextUsageRow {
+                    session_id,
+                    short_hash: row.get(1)?,
+                    probe_source_id: row.get(2)?,
+                    project_name: row.get(3)?,
+                    title: row.get(4)?,
+                    last_timestamp: row.get(5)?,
+                    compactions: row.get(6)?,
+                    calls: vec![],
+                });
+            }
+            if let Some(session) = sessions.last_mut() {
+                session.calls.push((row.get(7)?, row.get(8)?));
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Length and compaction events of each session active since `since`
+    pub fn compaction_stats(&self, since: Option<&str>) -> Result<Vec<CompactionRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT p.name, s.primary_model, s.message_count,
+                      (SELECT COUNT(*) FROM messages m
+                       WHERE m.session_id = s.id AND m.subtype = 'compact')
+               FROM sessions s
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE s.merged_into IS NULL AND (?1 IS NULL OR s.last_timestamp >= ?1)"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(CompactionRow {
+                project_name: row.get(0)?,
+                model: row.get(1)?,
+                message_count: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                compactions: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Distinct request parameters used in a session, with how many requests used each
+    pub fn session_request_params(&self, session_id: &str) -> Result<Vec<RequestParamsRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT r.temperature, r.max_tokens, r.system_prompt_hash, sp.chars, COUNT(*)
+               FROM request_params r
+               JOIN messages m ON m.id = r.message_id
+               LEFT JOIN system_prompts sp ON sp.hash = r.system_prompt_hash
+               WHERE m.session_id = ?
+               GROUP BY r.temperature, r.max_tokens, r.system_prompt_hash
+               ORDER BY MIN(m.id)"#,
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(RequestParamsRow {
+                temperature: row.get(0)?,
+                max_tokens: row.get(1)?,
+                system_prompt_hash: row.get(2)?,
+                system_prompt_chars: row.get(3)?,
+                requests: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Distinct system prompts seen per probe source, with when they were in use and
+    /// how the sessions that sent them went
+    pub fn system_prompt_registry(&self, source: Option<&str>) -> Result<Vec<SystemPromptRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"WITH used AS (
+                   SELECT s.probe_source_id AS source, r.system_prompt_hash AS hash,
+                          m.session_id, COUNT(*) AS requests,
+                          MIN(COALESCE(m.timestamp, s.first_timestamp)) AS first_seen,
+                          MAX(COALESCE(m.timestamp, s.last_timestamp)) AS last_seen
+                   FROM request_params r
+                   JOIN messages m ON m.id = r.message_id
+                   JOIN sessions s ON s.id = m.session_id
+                   JOIN probe_sources ps ON ps.id = s.probe_source_id
+                   WHERE r.system_prompt_hash IS NOT NULL
+                     AND (?1 IS NULL OR ps.source_name = ?1)
+                   GROUP BY s.probe_source_id, r.system_prompt_hash, m.session_id
+               )
+               SELECT u.hash, u.source, sp.chars, COUNT(*), SUM(u.requests),
+                      MIN(u.first_seen), MAX(u.last_seen), AVG(s.message_count),
+                      AVG((SELECT COUNT(*) FROM tool_uses t
+                           JOIN messages m ON m.id = t.message_id
+                           WHERE m.session_id = u.session_id))
+               FROM used u
+               JOIN sessions s ON s.id = u.session_id
+               JOIN system_prompts sp ON sp.hash = u.hash
+               GROUP BY u.source, u.hash
+               ORDER BY u.source, MIN(u.first_seen)"#,
+        )?;
+        let rows = stmt.query_map(params![source], |row| {
+            Ok(SystemPromptRow {
+                hash: row.get(0)?,
+                probe_source_id: row.get(1)?,
+                chars: row.get(2)?,
+                sessions: row.get(3)?,
+                requests: row.get(4)?,
+                first_seen: row.get(5)?,
+                last_seen: row.get(6)?,
+                avg_messages: row.get::<_, Option<f64>>(7)?.unwrap_or_default(),
+                avg_tool_uses: row.get::<_, Option<f64>>(8)?.unwrap_or_default(),
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// A stored system prompt by hash or unique hash prefix: (hash, content)
+    pub fn get_system_prompt(&self, hash: &str) -> Result<Option<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT hash, content FROM system_prompts WHERE hash LIKE ?1 || '%' LIMIT 2",
+        )?;
+        let matches = stmt
+            .query_map(params![hash], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(matches.into_iter().next()),
+            _ => anyhow::bail!("System prompt hash prefix '{}' is ambiguous", hash),
+        }
+    }
+
+    /// Recompute the daily usage rollups from `since_day` (`YYYY-MM-DD`, local) onward,
+    /// or every day with None
+    pub fn refresh_usage_rollups(&self, since_day: Option<&str>) -> Result<()> {
+        self.transaction(|| schema::refresh_rollups(&self.conn, since_day))
+    }
+
+    /// Rolled-up usage of one local day by source and model, with when it was last
+    /// refreshed
+    pub fn usage_rollups(&self, day: &str) -> Result<Vec<RollupRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT probe_source_id, NULLIF(model, ''), messages, input_tokens, output_tokens,
+                    cache_read_tokens, cache_creation_tokens, refreshed_at
+             FROM usage_rollups WHERE day = ? ORDER BY probe_source_id, model",
+        )?;
+        let rows = stmt.query_map(params![day], |row| {
+            Ok(RollupRow {
+                probe_source_id: row.get(0)?,
+                model: row.get(1)?,
+                messages: row.get(2)?,
+                tokens: token_counts(row, 3)?,
+                refreshed_at: row.get(7)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Whether rollups were ever built while token usage exists (databases upgraded
+    /// before the rollups landed are built on first use)
+    pub fn rollups_missing(&self) -> Result<bool> {
+        let missing = self.conn.query_row(
+            "SELECT NOT EXISTS (SELECT 1 FROM usage_rollups)
+                AND EXISTS (SELECT 1 FROM token_usage)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(missing)
+    }
+
+    /// Sessions with activity at or after an RFC 3339 time
+    pub fn count_sessions_active_since(&self, since: &str) -> Result<i64> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM sessions WHERE last_timestamp >= ? AND merged_into IS NULL",
+            params![since],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Record an alert; returns false if one of this kind already fired for the day
+    pub fn record_alert(
+        &self,
+        kind: &str,
+        day: &str,
+        value: f64,
+        baseline: Option<f64>,
+    ) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO usage_alerts (kind, day, value, baseline) VALUES (?, ?, ?, ?)",
+            params![kind, day, value, baseline],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Most recent alerts, newest first
+    pub fn list_alerts(&self, limit: usize) -> Result<Vec<AlertRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, day, value, baseline, created_at FROM usage_alerts
+             ORDER BY day DESC, id DESC LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(AlertRow {
+                kind: row.get(0)?,
+                day: row.get(1)?,
+                value: row.get(2)?,
+                baseline: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    // ============================================
+    // TOOL USES
+    // ============================================
+
+    /// MCP tool calls per server and tool, most-called first within each server
+    pub fn mcp_usage(&self, since: Option<&str>) -> Result<Vec<McpUsageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.mcp_server, t.tool_name, COUNT(*), SUM(t.is_error),
+                      SUM(NOT t.has_result), COUNT(DISTINCT m.session_id), MAX(m.timestamp)
+               FROM tool_uses t
+               JOIN messages m ON m.id = t.message_id
+               WHERE t.origin = 'mcp' AND (?1 IS NULL OR m.timestamp >= ?1)
+               GROUP BY t.mcp_server, t.tool_name
+               ORDER BY t.mcp_server, COUNT(*) DESC"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(McpUsageRow {
+                server: row.get(0)?,
+                tool: row.get(1)?,
+                calls: row.get(2)?,
+                errors: row.get(3)?,
+                unanswered: row.get(4)?,
+                sessions: row.get(5)?,
+                last_used: row.get(6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Answering model of each assistant message with a recorded model, numbered as
+    /// `read` numbers messages, per session in order; for model switch reports
+    pub fn answer_models(
+        &self,
+        session_id: Option<&str>,
+        since: Option<&str>,
+    ) -> Result<Vec<AnswerModelRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT m.session_id, s.short_hash, s.title, s.first_timestamp,
+                      m.number, m.model, m.timestamp
+               FROM (SELECT session_id, role, model, timestamp, line_number, id,
+                            ROW_NUMBER() OVER (PARTITION BY session_id
+                                               ORDER BY COALESCE(line_number, id)) AS number
+                     FROM messages
+                     WHERE ?1 IS NULL OR session_id = ?1) m
+               JOIN sessions s ON s.id = m.session_id
+               WHERE m.role = 'assistant' AND m.model IS NOT NULL AND m.model != ''
+                 AND s.merged_into IS NULL
+                 AND (?2 IS NULL OR s.last_timestamp >= ?2)
+               ORDER BY s.last_timestamp DESC, m.session_id, m.number"#,
+        )?;
+        let rows = stmt.query_map(params![session_id, since], |row| {
+            Ok(AnswerModelRow {
+                session_id: row.get(0)?,
+                short_hash: row.get(1)?,
+                title: row.get(2)?,
+                session_start: row.get(3)?,
+                message: row.get::<_, i64>(4)? as usize,
+                model: row.get(5)?,
+                timestamp: row.get(6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Tool calls that named a file, with their session's working directory and
+    /// project; for `chronicle files`
+    pub fn file_touches(&self, project_id: Option<&str>) -> Result<Vec<FileTouchRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.project_id, p.name, s.raw_project_path, t.tool_name, t.file_path,
+                      m.timestamp
+               FROM tool_uses t
+               JOIN messages m ON m.id = t.message_id
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE t.file_path IS NOT NULL AND s.merged_into IS NULL
+                 AND (?1 IS NULL OR s.project_id = ?1)"#,
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(FileTouchRow {
+                session_id: row.get(0)?,
+                project_id: row.get(1)?,
+                project_name: row.get(2)?,
+                cwd: row.get(3)?,
+                tool_name: row.get(4)?,
+                file_path: row.get(5)?,
+                timestamp: row.get(6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Tool calls per tool, project, provider and day, for `chronicle tools`
+    pub fn tool_usage(&self, since: Option<&str>) -> Result<Vec<ToolUsageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.tool_name, p.name, m.provider_id, date(m.timestamp) AS day,
+                      COUNT(*), SUM(NOT t.has_result), SUM(t.is_error), MAX(m.timestamp)
+               FROM tool_uses t
+               JOIN messages m ON m.id = t.message_id
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN projects p ON p.id = s.project_id
+               WHERE s.merged_into IS NULL AND (?1 IS NULL OR m.timestamp >= ?1)
+               GROUP BY t.tool_name, p.name, m.provider_id, day
+               ORDER BY day"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(ToolUsageRow {
+                tool_name: row.get(0)?,
+                project_name: row.get(1)?,
+                provider: row.get(2)?,
+                day: row.get(3)?,
+                calls: row.get(4)?,
+                unanswered: row.get(5)?,
+                errors: row.get(6)?,
+                last_used: row.get(7)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Permission decisions per tool, for calls from sources that record them
+    pub fn permission_stats(&self, since: Option<&str>) -> Result<Vec<PermissionRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.tool_name,
+                      SUM(t.permission = 'auto'),
+                      SUM(t.permission = 'approved'),
+                      SUM(t.permission = 'denied')
+               FROM tool_uses t
+               JOIN messages m ON m.id = t.message_id
+               WHERE t.permission IS NOT NULL AND (?1 IS NULL OR m.timestamp >= ?1)
+               GROUP BY t.tool_name
+               ORDER BY COUNT(*) DESC, t.tool_name"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(PermissionRow {
+                tool_name: row.get(0)?,
+                auto: row.get(1)?,
+                approved: row.get(2)?,
+                denied: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    // ============================================
+    // DEDUPLICATION
+    // ============================================
+
+    /// Timestamps and tool-call IDs of every session not already merged away
+    pub fn session_signatures(&self) -> Result<Vec<SessionSignature>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, probe_source_id, first_timestamp, last_timestamp
+             FROM sessions WHERE merged_into IS NULL",
+        )?;
+        let mut signatures: Vec<SessionSignature> = stmt
+            .query_map([], |row| {
+                Ok(SessionSignature {
+                    session_id: row.get(0)?,
+                    probe_source_id: row.get(1)?,
+                    first: unix_seconds(row.get::<_, Option<String>>(2)?.as_deref()),
+                    last: unix_seconds(row.get::<_, Option<String>>(3)?.as_deref()),
+                    ..Default::default()
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        let index: HashMap<String, usize> = signatures
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.session_id.clone(), i))
+            .collect();
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT session_id, timestamp FROM messages WHERE timestamp IS NOT NULL")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            let timestamp: String = row.get(1)?;
+            if let (Some(&i), Some(ts)) = (index.get(&session_id), unix_seconds(Some(&timestamp))) {
+                signatures[i].timestamps.insert(ts);
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT m.session_id, t.tool_id FROM tool_uses t
+             JOIN messages m ON m.id = t.message_id
+             WHERE t.tool_id IS NOT NULL",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            if let Some(&i) = index.get(&session_id) {
+                signatures[i].tool_ids.insert(row.get(1)?);
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    /// Record a detected duplicate pair; returns true if the pair is new. Confidence of
+    /// an unresolved pair is refreshed, resolved pairs are left alone.
+    pub fn record_duplicate(&self, duplicate: &DuplicateMatch) -> Result<bool> {
+        let known: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM session_duplicates WHERE session_a = ?1 AND session_b = ?2",
+            params![duplicate.session_a, duplicate.session_b],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            r#"INSERT INTO session_duplicates (session_a, session_b, confidence, detection_method)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT(session_a, session_b) DO UPDATE SET
+                   confidence = excluded.confidence,
+                   detection_method = excluded.detection_method,
+                   detected_at = CURRENT_TIMESTAMP
+               WHERE resolved = FALSE"#,
+            params![
+                duplicate.session_a,
+                duplicate.session_b,
+                duplicate.confidence,
+                duplicate.method
+            ],
+        )?;
+        Ok(!known)
+    }
+
+    /// Recorded duplicate pairs with both sessions, highest confidence first
+    pub fn list_duplicates(&self, include_resolved: bool) -> Result<Vec<DuplicateRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT d.id, d.confidence, d.detection_method, d.resolution,
+                      a.id, a.short_hash, psa.source_name, a.title, a.message_count, a.merged_into,
+                      b.id, b.short_hash, psb.source_name, b.title, b.message_count, b.merged_into
+               FROM session_duplicates d
+               JOIN sessions a ON a.id = d.session_a
+               JOIN sessions b ON b.id = d.session_b
+               JOIN probe_sources psa ON psa.id = a.probe_source_id
+               JOIN probe_sources psb ON psb.id = b.probe_source_id
+               WHERE ?1 OR d.resolved = FALSE
+               ORDER BY d.resolved, d.confidence DESC, d.id"#,
+        )?;
+        let side = |row: &rusqlite::Row, start: usize| -> rusqlite::Result<DuplicateSide> {
+            Ok(DuplicateSide {
+                session_id: row.get(start)?,
+                short_hash: row.get(start + 1)?,
+                source_name: row.get(start + 2)?,
+                title: row.get(start + 3)?,
+                message_count: row.get(start + 4)?,
+                merged_into: row.get(start + 5)?,
+            })
+        };
+        let rows = stmt.query_map(params![include_resolved], |row| {
+            Ok(DuplicateRow {
+                id: row.get(0)?,
+                confidence: row.get(1)?,
+                method: row.get(2)?,
+                resolution: row.get(3)?,
+                a: side(row, 4)?,
+                b: side(row, 10)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Mark a duplicate pair resolved ('merged', 'kept_both' or 'false_positive')
+    pub fn resolve_duplicate(&self, id: i64, resolution: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE session_duplicates SET resolved = TRUE, resolution = ?2,
+                    resolved_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![id, resolution],
+        )?;
+        Ok(())
+    }
+
+    /// Fold a duplicate session into the one kept: the duplicate is hidden from listings
+    /// and leaves its project, whose assignment the kept session inherits if it has none
+    pub fn merge_session(&self, keep_id: &str, drop_id: &str) -> Result<()> {
+        self.transaction(|| {
+            self.conn.execute(
+                r#"UPDATE sessions SET
+                       project_id = (SELECT project_id FROM sessions WHERE id = ?2),
+                       project_assignment = (SELECT project_assignment FROM sessions WHERE id = ?2)
+                   WHERE id = ?1 AND project_id IS NULL
+                     AND (SELECT project_id FROM sessions WHERE id = ?2) IS NOT NULL"#,
+                params![keep_id, drop_id],
+            )?;
+            self.conn.execute(
+                "UPDATE sessions SET merged_into = ?1, project_id = NULL,
+                        project_assignment = 'merged'
+                 WHERE id = ?2",
+                params![keep_id, drop_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    // ============================================
+    // PROJECT SNAPSHOTS
+    // ============================================
+
+    /// Current aggregates of a project; open questions are left for the caller, which
+    /// needs message content to find them
+    pub fn project_snapshot(&self, project_id: &str) -> Result<ProjectSnapshot> {
+        let mut snapshot = ProjectSnapshot::default();
+        let live = "s.project_id = ?1 AND s.merged_into IS NULL";
+
+        (snapshot.session_count, snapshot.message_count) = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(s.message_count), 0) FROM sessions s WHERE {}",
+                live
+            ),
+            params![project_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        snapshot.total_tokens = self.conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(COALESCE(t.input_tokens, 0) + COALESCE(t.output_tokens, 0)
+                        + COALESCE(t.cache_read_tokens, 0) + COALESCE(t.cache_creation_tokens, 0)), 0)
+                 FROM token_usage t
+                 JOIN messages m ON m.id = t.message_id
+                 JOIN sessions s ON s.id = m.session_id
+                 WHERE {}",
+                live
+            ),
+            params![project_id],
+            |row| row.get(0),
+        )?;
+
+        let counts = |sql: &str| -> Result<BTreeMap<String, i64>> {
+            let mut stmt = self.conn.prepare(sql)?;
+            let rows = stmt.query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>().map_err(Into::into)
+        };
+        snapshot.sources = counts(&format!(
+            "SELECT ps.source_name, COUNT(*) FROM sessions s
+             JOIN probe_sources ps ON ps.id = s.probe_source_id
+             WHERE {} GROUP BY ps.source_name",
+            live
+        ))?;
+        snapshot.models = counts(&format!(
+            "SELECT COALESCE(s.primary_model, '(unknown)'), COUNT(*) FROM sessions s
+             WHERE {} GROUP BY 1",
+            live
+        ))?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT DISTINCT r.ref_key FROM session_references r
+             JOIN sessions s ON s.id = r.session_id WHERE {}",
+            live
+        ))?;
+        snapshot.references = stmt
+            .query_map(params![project_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.short_hash, COALESCE(s.title, '') FROM sessions s
+             WHERE {} ORDER BY s.last_timestamp DESC LIMIT ?2",
+            live
+        ))?;
+        snapshot.recent_sessions = stmt
+            .query_map(
+                params![project_id, snapshot::RECENT_SESSIONS as i64],
+                |row| {
+                    let (hash, title): (String, String) = (row.get(0)?, row.get(1)?);
+                    let title = title.lines().next().unwrap_or("").trim().to_string();
+                    Ok(format!("{}: {}", hash, title))
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+
+        Ok(snapshot)
+    }
+
+    /// Store a snapshot; returns its ID
+    pub fn save_project_snapshot(
+        &self,
+        project_id: &str,
+        label: Option<&str>,
+        snapshot: &ProjectSnapshot,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO project_snapshots (project_id, label, data) VALUES (?, ?, ?)",
+            params![project_id, label, serde_json::to_string(snapshot)?],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// A project's stored snapshots, oldest first
+    pub fn list_project_snapshots(&self, project_id: &str) -> Result<Vec<SnapshotRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, label, taken_at, data FROM project_snapshots
+             WHERE project_id = ? ORDER BY taken_at, id",
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+        let mut snapshots = vec![];
+        for row in rows {
+            let (id, label, taken_at, data) = row?;
+            snapshots.push(SnapshotRow {
+                id,
+                label,
+                taken_at,
+                snapshot: serde_json::from_str(&data)?,
+            });
+        }
+        Ok(snapshots)
+    }
+
+    // ============================================
+    // ENRICHMENT
+    // ============================================
+
+    /// Queue a `kind` job for every session without one; returns how many were added
+    pub fn enqueue_enrichment(&self, kind: &str) -> Result<usize> {
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO enrichment_jobs (session_id, kind)
+             SELECT id, ?1 FROM sessions WHERE merged_into IS NULL",
+            params![kind],
+        )?;
+        Ok(added)
+    }
+
+    /// Return jobs left running by an interrupted run to the queue
+    pub fn requeue_running_enrichment(&self) -> Result<usize> {
+        let requeued = self.conn.execute(
+            "UPDATE enrichment_jobs SET status = 'pending' WHERE status = 'running'",
+            [],
+        )?;
+        Ok(requeued)
+    }
+
+    /// Next jobs of a kind to work, most recent sessions first: pending ones and failed
+    /// ones with attempts to spare
+    pub fn next_enrichment_jobs(
+        &self,
+        kind: &str,
+        max_attempts: u32,
+        limit: Option<usize>,
+    ) -> Result<Vec<EnrichmentJob>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT j.id, j.session_id, j.kind, j.attempts FROM enrichment_jobs j
+             JOIN sessions s ON s.id = j.session_id
+             WHERE j.kind = ?1 AND s.merged_into IS NULL
+               AND (j.status = 'pending' OR (j.status = 'failed' AND j.attempts < ?2))
+             ORDER BY s.last_timestamp DESC LIMIT ?3",
+        )?;
+        // SQLite treats a negative LIMIT as no limit
+        let limit = limit.map_or(-1, |n| n as i64);
+        let rows = stmt.query_map(params![kind, max_attempts, limit], |row| {
+            Ok(EnrichmentJob {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                kind: row.get(2)?,
+                attempts: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Mark a job as being worked, so an interrupted run can requeue it
+    pub fn start_enrichment(&self, job_id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE enrichment_jobs SET status = 'running', updated_at = datetime('now')
+             WHERE id = ?",
+            params![job_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record a job's outcome: its result, or the error that failed it
+    pub fn finish_enrichment(
+        &self,
+        job_id: i64,
+        outcome: std::result::Result<&str, &str>,
+        cost: Option<f64>,
+    ) -> Result<()> {
+        let (status, result, error) = match outcome {
+            Ok(result) => ("done", Some(result), None),
+            Err(error) => ("failed", None, Some(error)),
+        };
+        self.conn.execute(
+            "UPDATE enrichment_jobs SET status = ?1, result = ?2, error = ?3, cost = ?4,
+                    attempts = attempts + 1, updated_at = datetime('now')
+             WHERE id = ?5",
+            params![status, result, error, cost, job_id],
+        )?;
+        Ok(())
+    }
+
+    /// Job counts as (kind, status, count)
+    pub fn enrichment_status(&self) -> Result<Vec<(String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, status, COUNT(*) FROM enrichment_jobs GROUP BY kind, status
+             ORDER BY kind, status",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Generated metadata of a session as (kind, result)
+    pub fn session_enrichments(&self, session_id: &str) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, result FROM enrichment_jobs
+             WHERE session_id = ? AND status = 'done' ORDER BY kind",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    // ============================================
+    // QUERIES
+    // ============================================
+
+    pub fn list_sessions(
+        &self,
+        provider: Option<&str>,
+        source: Option<&str>,
+    ) -> Result<Vec<SessionRow>> {
+        self.query_sessions(&SessionQuery {
+            provider: provider.map(str::to_string),
+            source: source.map(str::to_string),
+            archived: true,
+            ..Default::default()
+        })
+    }
+
+    /// Sessions matching `query`, most recent first; merged duplicates are left out
+    pub fn query_sessions(&self, query: &SessionQuery) -> Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE {} ORDER BY {}",
+            SESSION_SELECT,
+            SESSION_FILTER,
+            query.order.sql()
+        ))?;
+        let rows = stmt.query_map(query.params(), session_from_row)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Assign every session matching `query` to a project in one statement (as a user
+    /// assignment); returns the number of sessions that changed project
+    pub fn assign_sessions(&self, query: &SessionQuery, project_id: &str) -> Result<usize> {
+        let changed = self.conn.execute(
+            &format!(
+                "UPDATE sessions SET project_id = ?10, project_assignment = 'user'
+                 WHERE project_id IS NOT ?10 AND id IN (
+                     SELECT s.id FROM sessions s
+                     JOIN probe_sources ps ON s.probe_source_id = ps.id
+                     LEFT JOIN providers p ON ps.provider_id = p.id
+                     WHERE {})",
+                SESSION_FILTER
+            ),
+            (
+                &query.provider,
+                &query.source,
+                &query.since,
+                &query.until,
+                &query.project_id,
+                query.unassigned,
+                query.archived,
+                &query.path_prefix,
+                &query.path_glob,
+                project_id,
+            ),
+        )?;
+        if changed > 0 {
+            self.touch_project(project_id)?;
+        }
+        Ok(changed)
+    }
+
+    /// Sessions active since an RFC 3339 timestamp, oldest first, optionally for one project
+    pub fn sessions_since(&self, project_id: Option<&str>, since: &str) -> Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE COALESCE(s.last_timestamp, s.first_timestamp) >= ?1
+                 AND (?2 IS NULL OR s.project_id = ?2)
+             ORDER BY s.first_timestamp",
+            SESSION_SELECT
+        ))?;
+        let rows = stmt.query_map(params![since, project_id], session_from_row)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Sessions whose title, generated summary, project or working directory match every
+    /// word of `text` (as a prefix), ordered by `rank`. Relevance ranking boosts sessions
+    /// of `project_id`, the project being worked in.
+    pub fn search_sessions(
+        &self,
+        text: &str,
+        rank: SearchRank,
+        project_id: Option<&str>,
+    ) -> Result<Vec<SessionRow>> {
+        let Some(query) = search::fts_query(text) else {
+            return Ok(vec![]);
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT session_id, bm25(session_search, {}) FROM session_search
+             WHERE session_search MATCH ?1",
+            search::BM25_WEIGHTS
+        ))?;
+        let scores: HashMap<String, f64> = stmt
+            .query_map(params![query], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE s.merged_into IS NULL
+                 AND s.id IN (SELECT session_id FROM session_search WHERE session_search MATCH ?1)
+             ORDER BY s.last_timestamp DESC",
+            SESSION_SELECT
+        ))?;
+        let mut sessions = stmt
+            .query_map(params![query], session_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if rank == SearchRank::Relevance {
+            let now = chrono::Utc::now();
+            let score = |s: &SessionRow| {
+                let last_active = s
+                    .last_timestamp
+                    .as_deref()
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc));
+                let project_match = project_id.is_some() && s.project_id.as_deref() == project_id;
+                relevance(scores[&s.id], last_active, now, project_match)
+            };
+            let mut scored: Vec<(f64, SessionRow)> =
+                sessions.into_iter().map(|s| (score(&s), s)).collect();
+            // Stable: equal scores stay most recent first
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            sessions = scored.into_iter().map(|(_, s)| s).collect();
+        }
+        Ok(sessions)
+    }
+
+    /// Git commits recorded for a session
+    pub fn get_session_commits(&self, session_id: &str) -> Result<Vec<CommitRef>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT commit_hash, message FROM session_commits WHERE session_id = ? ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(CommitRef {
+                hash: row.get(0)?,
+                message: row.get(1)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Issue/PR reference keys detected in a session
+    pub fn get_session_references(&self, session_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ref_key FROM session_references WHERE session_id = ? ORDER BY ref_key",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| row.get(0))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Get session by short_hash (primary search) or fallback to id/external_id
+    /// Find a session by short hash, ID or external ID (prefixes allowed), or by the
+    /// path of its source file when the query looks like a path
+    pub fn get_session(&self, query: &str) -> Result<Option<SessionRow>> {
+        let row = self.conn.query_row(
+            &format!(
+                r#"{}
+               WHERE s.short_hash = ?1 OR s.short_hash LIKE ?2
+                  OR s.id LIKE ?2 OR s.external_id LIKE ?2
+               ORDER BY 
+                   CASE WHEN s.short_hash = ?1 THEN 0
+                        WHEN s.external_id = ?1 THEN 1
+                        ELSE 2 END
+               LIMIT 1"#,
+                SESSION_SELECT
+            ),
+            params![query, format!("{}%", query)],
+            session_from_row,
+        );
+
+        match row {
+            Ok(r) => Ok(Some(r)),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+                if query.contains(std::path::MAIN_SEPARATOR) || query.starts_with('~') =>
+            {
+                self.get_session_by_path(query)
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Path of the file or directory a session was extracted from
+    pub fn get_session_source_path(&self, session_id: &str) -> Result<Option<String>> {
+        let path = self
+            .conn
+            .query_row(
+                "SELECT source_path FROM sessions WHERE id = ?",
+                params![session_id],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(path)
+    }
+
+    /// Indexed sessions with the source they were extracted from, for re-deriving data
+    /// (split parts are skipped: they are cut from their original session)
+    pub fn session_sources(&self, probe_source_id: Option<&str>) -> Result<Vec<SessionSourceRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, probe_source_id, source_path, orphaned_at IS NOT NULL FROM sessions
+               WHERE parent_session_id IS NULL AND (?1 IS NULL OR probe_source_id = ?1)
+               ORDER BY probe_source_id, id"#,
+        )?;
+        let rows = stmt.query_map(params![probe_source_id], |row| {
+            Ok(SessionSourceRow {
+                session_id: row.get(0)?,
+                probe_source_id: row.get(1)?,
+                source_path: row.get(2)?,
+                orphaned: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Mark sessions (with their split parts) as having lost their source, or clear the
+    /// mark when it's back; returns how many sessions changed
+    pub fn set_sessions_orphaned(&self, session_ids: &[String], orphaned: bool) -> Result<usize> {
+        self.transaction(|| {
+            let mut stmt = self.conn.prepare_cached(
+                "UPDATE sessions SET orphaned_at = CASE WHEN ?2 THEN datetime('now') END
+                 WHERE (id = ?1 OR parent_session_id = ?1) AND (orphaned_at IS NOT NULL) != ?2",
+            )?;
+            let mut changed = 0;
+            for id in session_ids {
+                changed += stmt.execute(params![id, orphaned])?;
+            }
+            Ok(changed)
+        })
+    }
+
+    /// Sessions with message content in the cache, which stay readable without a source
+    pub fn sessions_with_cached_content(&self) -> Result<std::collections::HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT m.session_id FROM message_content c JOIN messages m ON m.id = c.message_id",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>().map_err(Into::into)
+    }
+
+    /// Delete sessions (with their split parts) and everything derived from them, then
+    /// bring project counters and usage rollups back in line; returns the sessions deleted
+    pub fn delete_sessions(&self, session_ids: &[String]) -> Result<usize> {
+        let deleted = self.transaction(|| {
+            let mut deleted = 0;
+            for id in session_ids {
+                let mut stmt = self.conn.prepare_cached(
+                    "SELECT id FROM sessions WHERE id = ?1 OR parent_session_id = ?1",
+                )?;
+                let ids: Vec<String> = stmt
+                    .query_map(params![id], |row| row.get(0))?
+                    .collect::<Result<_, _>>()?;
+                for session_id in ids {
+                    deleted += self.delete_session_rows(&session_id)?;
+                }
+            }
+            Ok(deleted)
+        })?;
+        if deleted > 0 {
+            self.recount_projects()?;
+            self.refresh_usage_rollups(None)?;
+        }
+        Ok(deleted)
+    }
+
+    /// Remove one session and every row that hangs off it; sessions merged into it become
+    /// independent again. Foreign keys aren't enforced, so dependent rows go by hand.
+    fn delete_session_rows(&self, session_id: &str) -> Result<usize> {
+        for table in [
+            "tool_uses",
+            "token_usage",
+            "request_params",
+            "message_content",
+        ] {
+            self.conn.execute(
+                &format!(
+                    "DELETE FROM {} WHERE message_id IN
+                         (SELECT id FROM messages WHERE session_id = ?)",
+                    table
+                ),
+                params![session_id],
+            )?;
+        }
+        for table in [
+            "messages",
+            "session_models",
+            "session_references",
+            "session_commits",
+            "anomalies",
+            "session_skips",
+            "parse_errors",
+            "enrichment_jobs",
+        ] {
+            self.conn.execute(
+                &format!("DELETE FROM {} WHERE session_id = ?", table),
+                params![session_id],
+            )?;
+        }
+        self.conn.execute(
+            "DELETE FROM session_duplicates WHERE session_a = ?1 OR session_b = ?1",
+            params![session_id],
+        )?;
+        self.conn.execute(
+            "UPDATE sessions SET merged_into = NULL, project_assignment = 'auto'
+             WHERE merged_into = ?",
+            params![session_id],
+        )?;
+        Ok(self
+            .conn
+            .execute("DELETE FROM sessions WHERE id = ?", params![session_id])?)
+    }
+
+    pub fn set_session_language(&self, session_id: &str, language: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET language = ? WHERE id = ?",
+            params![language, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Recompute a session's activity metrics and model breakdown from its indexed
+    /// messages
+    pub fn refresh_session_metrics(&self, session_id: &str) -> Result<()> {
+        self.conn
+            .execute(schema::SESSION_METRICS, params![session_id])?;
+        schema::refresh_session_models(&self.conn, Some(session_id))
+    }
+
+    /// Messages and tokens per model of a session, busiest model first
+    pub fn session_models(&self, session_id: &str) -> Result<Vec<SessionModelRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT model, messages, input_tokens, output_tokens, cache_read_tokens,
+                      cache_creation_tokens
+               FROM session_models
+               WHERE session_id = ?
+               ORDER BY messages DESC, model"#,
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(SessionModelRow {
+                model: row.get(0)?,
+                sessions: 1,
+                messages: row.get(1)?,
+                tokens: token_counts(row, 2)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Sessions, messages and tokens per model across all sessions, busiest first
+    pub fn model_breakdown(&self) -> Result<Vec<SessionModelRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT sm.model, COUNT(*), SUM(sm.messages), SUM(sm.input_tokens),
+                      SUM(sm.output_tokens), SUM(sm.cache_read_tokens),
+                      SUM(sm.cache_creation_tokens)
+               FROM session_models sm
+               JOIN sessions s ON s.id = sm.session_id
+               WHERE s.merged_into IS NULL
+               GROUP BY sm.model
+               ORDER BY SUM(sm.messages) DESC, sm.model"#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SessionModelRow {
+                model: row.get(0)?,
+                sessions: row.get(1)?,
+                messages: row.get(2)?,
+                tokens: token_counts(row, 3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Messages, tokens and tool calls per model from an RFC 3339 start time, busiest
+    /// model first
+    pub fn model_usage(&self, since: Option<&str>) -> Result<Vec<ModelUsageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT m.model, GROUP_CONCAT(DISTINCT ps.source_name),
+                      COUNT(DISTINCT m.session_id), COUNT(*),
+                      SUM(COALESCE(tu.calls, 0)), SUM(tu.calls IS NOT NULL),
+                      SUM(COALESCE(t.input_tokens, 0)), SUM(COALESCE(t.output_tokens, 0)),
+                      SUM(COALESCE(t.cache_read_tokens, 0)),
+                      SUM(COALESCE(t.cache_creation_tokens, 0))
+               FROM messages m
+               JOIN sessions s ON s.id = m.session_id
+               JOIN probe_sources ps ON ps.id = s.probe_source_id
+               LEFT JOIN token_usage t ON t.message_id = m.id
+               LEFT JOIN (SELECT message_id, COUNT(*) AS calls FROM tool_uses
+                          GROUP BY message_id) tu ON tu.message_id = m.id
+               WHERE m.model IS NOT NULL AND m.model != '' AND s.merged_into IS NULL
+                 AND (?1 IS NULL OR m.timestamp >= ?1)
+               GROUP BY m.model
+               ORDER BY COUNT(*) DESC, m.model"#,
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            let sources: Option<String> = row.get(1)?;
+            let mut sources: Vec<String> = sources
+                .unwrap_or_default()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            sources.sort();
+            Ok(ModelUsageRow {
+                model: row.get(0)?,
+                sources,
+                sessions: row.get(2)?,
+                messages: row.get(3)?,
+                tool_calls: row.get(4)?,
+                tool_messages: row.get(5)?,
+                tokens: token_counts(row, 6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Assistant output and the reasoning within it per source and model, for sources
+    /// and models that did any thinking; most reasoning first
+    pub fn thinking_usage(&self) -> Result<Vec<ThinkingRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.probe_source_id, m.model, COUNT(*), SUM(m.has_thinking),
+                      SUM(t.reasoning_tokens IS NOT NULL),
+                      SUM(COALESCE(t.output_tokens, 0)), SUM(COALESCE(t.reasoning_tokens, 0))
+               FROM messages m
+               JOIN sessions s ON s.id = m.session_id
+               LEFT JOIN token_usage t ON t.message_id = m.id
+               WHERE m.role = 'assistant' AND s.merged_into IS NULL
+               GROUP BY s.probe_source_id, m.model
+               HAVING SUM(m.has_thinking) > 0 OR SUM(t.reasoning_tokens) > 0
+               ORDER BY SUM(COALESCE(t.reasoning_tokens, 0)) DESC, s.probe_source_id, m.model"#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ThinkingRow {
+                probe_source_id: row.get(0)?,
+                model: row.get(1)?,
+                messages: row.get(2)?,
+                thinking_messages: row.get(3)?,
+                counted_messages: row.get(4)?,
+                output_tokens: row.get(5)?,
+                reasoning_tokens: row.get(6)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Sessions whose messages came from more than one model
+    pub fn count_multi_model_sessions(&self) -> Result<i64> {
+        let count = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM (
+                   SELECT sm.session_id FROM session_models sm
+                   JOIN sessions s ON s.id = sm.session_id
+                   WHERE s.merged_into IS NULL
+                   GROUP BY sm.session_id HAVING COUNT(*) > 1)"#,
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Find a session by its exact external (source-native) ID
+    pub fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<SessionRow>> {
+        let row = self.conn.query_row(
+            &format!(
+                r#"{}
+               WHERE s.external_id = ?1
+               ORDER BY s.parent_session_id IS NOT NULL, s.split_index
+               LIMIT 1"#,
+                SESSION_SELECT
+            ),
+            params![external_id],
+            session_from_row,
+        );
+
+        match row {
+            Ok(r) => Ok(Some(r)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Find the session stored from a source file. Sources holding several sessions
+    /// (e.g. a Cursor database) resolve to the most recently active one.
+    pub fn get_session_by_path(&self, path: &str) -> Result<Option<SessionRow>> {
+        let expanded = shellexpand::tilde(path).to_string();
+        let canonical = std::fs::canonicalize(&expanded)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| expanded.clone());
+
+        let row = self.conn.query_row(
+            &format!(
+                r#"{}
+               WHERE s.source_path IN (?1, ?2)
+               ORDER BY s.parent_session_id IS NOT NULL, s.last_timestamp DESC
+               LIMIT 1"#,
+                SESSION_SELECT
+            ),
+            params![expanded, canonical],
+            session_from_row,
+        );
+
+        match row {
+            Ok(r) => Ok(Some(r)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get_messages(&self, session_id: &str) -> Result<Vec<MessageRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, uuid, role, provider_id, model, timestamp, source_path, 
+                      byte_offset, line_number, content_ref, has_tool_use, has_thinking, subtype
+               FROM messages
+               WHERE session_id = ?
+               ORDER BY COALESCE(line_number, id)"#,
+        )?;
+
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(MessageRow {
+                id: row.get(0)?,
+                uuid: row.get(1)?,
+                role: row.get(2)?,
+                provider_id: row.get(3)?,
+                model: row.get(4)?,
+                timestamp: row.get(5)?,
+                source_path: row.get(6)?,
+                byte_offset: row.get(7)?,
+                line_number: row.get(8)?,
+                content_ref: row.get(9)?,
+                has_tool_use: row.get(10)?,
+                has_thinking: row.get(11)?,
+                subtype: row.get(12)?,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    pub fn list_projects(&self) -> Result<Vec<ProjectRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT p.id, p.name, p.type, p.primary_path, p.metadata,
+                      p.created_at, p.last_activity,
+                      COALESCE(p.session_count, 0), COALESCE(p.message_count, 0), p.last_session_at,
+                      p.visibility
+               FROM projects p
+               ORDER BY p.last_activity DESC"#,
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(ProjectRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                project_type: row.get(2)?,
+                primary_path: row.get(3)?,
+                metadata: row.get(4)?,
+                created_at: row.get(5)?,
+                last_activity: row.get(6)?,
+                session_count: row.get(7)?,
+                message_count: row.get(8)?,
+                last_session_at: row.get(9)?,
+                // Unknown levels are treated as the most restrictive
+                visibility: row
+                    .get::<_, Option<String>>(10)?
+                    .map_or(Visibility::Shareable, |v| {
+                        Visibility::parse(&v).unwrap_or(Visibility::Private)
+                    }),
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Rebuild the cached project counters from the sessions table; returns how many
+    /// projects had drifted
+    pub fn recount_projects(&self) -> Result<usize> {
+        let drifted = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM projects p
+               WHERE COALESCE(p.session_count, 0) != (SELECT COUNT(*) FROM sessions s WHERE s.project_id = p.id)
+                  OR COALESCE(p.message_count, 0) != (SELECT COALESCE(SUM(s.message_count), 0) FROM sessions s WHERE s.project_id = p.id)
+                  OR p.last_session_at IS NOT (SELECT MAX(s.last_timestamp) FROM sessions s WHERE s.project_id = p.id)"#,
+            [],
+            |row| row.get::<_, i64>(0),
+        )?;
+        self.conn.execute_batch(schema::RECOUNT_PROJECTS)?;
+        Ok(drifted as usize)
+    }
+}
+
+/// Apply the journal mode, sync level and busy timeout of `database` to a writable
+/// connection
+fn configure_connection(conn: &Connection, database: &DatabaseConfig) -> Result<()> {
+    let journal_mode = database.journal_mode.to_lowercase();
+    if !["wal", "delete", "truncate", "persist", "memory"].contains(&journal_mode.as_str()) {
+        anyhow::bail!(
+            "Unknown database.journal_mode: {} (expected wal or delete)",
+            database.journal_mode
+        );
+    }
+    let synchronous = database.synchronous.to_lowercase();
+    if !["off", "normal", "full", "extra"].contains(&synchronous.as_str()) {
+        anyhow::bail!(
+            "Unknown database.synchronous: {} (expected off, normal, full or extra)",
+            database.synchronous
+        );
+    }
+    conn.busy_timeout(std::time::Duration::from_millis(database.busy_timeout_ms))?;
+    // Returns the mode in effect, which stays the old one if another connection holds
+    // the database open in a mode that can't be left
+    conn.query_row(
+        &format!("PRAGMA journal_mode = {}", journal_mode),
+        [],
+        |row| row.get::<_, String>(0),
+    )?;
+    conn.execute_batch(&format!("PRAGMA synchronous = {}", synchronous))?;
+    Ok(())
+}
+
+/// Parse a stored RFC 3339 timestamp into unix seconds
+fn unix_seconds(timestamp: Option<&str>) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp?)
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+/// Read four consecutive token columns (input, output, cache read, cache creation)
+fn token_counts(row: &rusqlite::Row, start: usize) -> rusqlite::Result<TokenCounts> {
+    Ok(TokenCounts {
+        input: row.get(start)?,
+        output: row.get(start + 1)?,
+        cache_read: row.get(start + 2)?,
+        cache_creation: row.get(start + 3)?,
+    })
+}
+
+/// Stable identity of a message within its session: source uuid, else its position
+fn message_key(msg: &MessageMetadata, index: usize) -> String {
+    if let Some(ref uuid) = msg.uuid {
+        return format!("uuid:{}", uuid);
+    }
+    if let Some(line) = msg.content_ref.line_number {
+        return format!("line:{}", line);
+    }
+    if let Some(ref path) = msg.content_ref.content_path {
+        return format!("ref:{}", path.to_string_lossy());
+    }
+    format!("idx:{}", index)
+}
+
+/// Shared SELECT for session rows, joined with source, provider and project names
+const SESSION_SELECT: &str = r#"SELECT s.id, s.probe_source_id, s.external_id, s.short_hash,
+                      s.project_id, s.project_assignment, s.title, s.primary_provider,
+                      s.primary_model, s.message_count, s.first_timestamp, 
+                      s.last_timestamp, s.raw_project_path, ps.source_name,
+                      COALESCE(p.name, ps.provider_id, 'multi') as provider_name,
+                      proj.name as project_name, s.source_group, proj.type as project_type,
+                      s.language, COALESCE(s.archived, FALSE), s.duration_secs, s.active_secs,
+                      COALESCE(s.user_messages, 0), COALESCE(s.assistant_messages, 0),
+                      COALESCE(s.tool_calls, 0)
+               FROM sessions s
+               JOIN probe_sources ps ON s.probe_source_id = ps.id
+               LEFT JOIN providers p ON ps.provider_id = p.id
+               LEFT JOIN projects proj ON s.project_id = proj.id"#;
+
+/// WHERE clause over `SESSION_SELECT` tables for a [`SessionQuery`]
+const SESSION_FILTER: &str = r#"s.merged_into IS NULL
+                 AND (?1 IS NULL OR p.id = ?1 OR ps.provider_id = ?1)
+                 AND (?2 IS NULL OR ps.source_name = ?2)
+                 AND (?3 IS NULL OR s.last_timestamp >= ?3)
+                 AND (?4 IS NULL OR s.last_timestamp < ?4)
+                 AND (?5 IS NULL OR s.project_id = ?5)
+                 AND (NOT ?6 OR s.project_id IS NULL)
+                 AND (?7 OR NOT COALESCE(s.archived, FALSE))
+                 AND (?8 IS NULL OR s.raw_project_path = ?8
+                      OR substr(s.raw_project_path, 1, length(?8) + 1) = ?8 || '/')
+                 AND (?9 IS NULL OR s.raw_project_path GLOB ?9)"#;
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionRow> {
+    Ok(SessionRow {
+        id: row.get(0)?,
+        probe_source_id: row.get(1)?,
+        external_id: row.get(2)?,
+        short_hash: row.get(3)?,
+        project_id: row.get(4)?,
+        project_assignment: row.get(5)?,
+        title: row.get(6)?,
+        primary_provider: row.get(7)?,
+        primary_model: row.get(8)?,
+        message_count: row.get(9)?,
+        first_timestamp: row.get(10)?,
+        last_timestamp: row.get(11)?,
+        project_path: row.get(12)?,
+        source_name: row.get(13)?,
+        provider_name: row.get(14)?,
+        project_name: row.get(15)?,
+        source_group: row.get(16)?,
+        project_type: row.get(17)?,
+        language: row.get(18)?,
+        archived: row.get(19)?,
+        metrics: SessionMetrics {
+            duration_secs: row.get(20)?,
+            active_secs: row.get(21)?,
+            user_messages: row.get(22)?,
+            assistant_messages: row.get(23)?,
+            tool_calls: row.get(24)?,
+        },
+    })
+}
+
+// ============================================
+// ROW TYPES
+// ============================================
+
+#[derive(Debug, Default, Serialize)]
+pub struct SessionRow {
+    pub id: String,
+    pub probe_source_id: String,
+    pub external_id: String,
+    pub short_hash: String,
+    pub project_id: Option<String>,
+    pub project_assignment: String,
+    pub title: Option<String>,
+    pub primary_provider: Option<String>,
+    pub primary_model: Option<String>,
+    pub message_count: i64,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub project_path: Option<String>,
+    pub source_name: String,
+    pub provider_name: String,
+    pub project_name: Option<String>,
+    pub source_group: Option<String>,
+    pub project_type: Option<String>,
+    /// Language of the user's messages (ISO 639-1)
+    pub language: Option<String>,
+    /// Hidden from `list` unless asked for
+    pub archived: bool,
+    #[serde(flatten)]
+    pub metrics: SessionMetrics,
+}
+
+/// Activity derived from a session's indexed messages
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SessionMetrics {
+    /// Wall-clock time from first to last message
+    pub duration_secs: Option<i64>,
+    /// Time between consecutive messages, leaving out gaps over 5 minutes
+    pub active_secs: Option<i64>,
+    pub user_messages: i64,
+    pub assistant_messages: i64,
+    pub tool_calls: i64,
+}
+
+impl SessionMetrics {
+    /// User messages per assistant message
+    pub fn user_assistant_ratio(&self) -> Option<f64> {
+        (self.assistant_messages > 0)
+            .then(|| self.user_messages as f64 / self.assistant_messages as f64)
+    }
+
+    /// Tool calls per assistant message
+    pub fn tool_calls_per_message(&self) -> Option<f64> {
+        (self.assistant_messages > 0)
+            .then(|| self.tool_calls as f64 / self.assistant_messages as f64)
+    }
+}
+
+impl SessionRow {
+    /// General chat rather than coding: in a `general` project, or unassigned with no
+    /// working directory
+    pub fn is_general(&self) -> bool {
+        match self.project_type.as_deref() {
+            Some(project_type) => project_type == "general",
+            None => self.project_id.is_none() && self.project_path.is_none(),
+        }
+    }
+
+    /// Time of the session's first message
+    pub fn first_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.first_timestamp.as_deref().and_then(parse_stored_time)
+    }
+
+    /// Time of the session's last message
+    pub fn last_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.last_timestamp.as_deref().and_then(parse_stored_time)
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionSourceRow {
+    pub session_id: String,
+    pub probe_source_id: String,
+    pub source_path: String,
+    /// `prune` found the source gone
+    pub orphaned: bool,
+}
+
+/// A stored timestamp: RFC 3339 as written by extraction, or SQLite's
+/// `YYYY-MM-DD HH:MM:SS` (UTC) from `datetime('now')` defaults
+pub fn parse_stored_time(timestamp: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MessageRow {
+    pub id: i64,
+    pub uuid: Option<String>,
+    pub role: String,
+    pub provider_id: Option<String>,
+    pub model: Option<String>,
+    pub timestamp: Option<String>,
+    pub source_path: String,
+    pub byte_offset: Option<i64>,
+    pub line_number: Option<i64>,
+    pub content_ref: Option<String>,
+    pub has_tool_use: bool,
+    pub has_thinking: bool,
+    /// Kind of event within the role, e.g. `compact`
+    pub subtype: Option<String>,
+}
+
+impl MessageRow {
+    /// Time the message was sent
+    pub fn at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp.as_deref().and_then(parse_stored_time)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectRow {
+    pub id: String,
+    pub name: String,
+    pub project_type: String,
+    pub primary_path: Option<String>,
+    pub metadata: Option<String>,
+    pub created_at: Option<String>,
+    pub last_activity: Option<String>,
+    pub session_count: i64,
+    pub message_count: i64,
+    pub last_session_at: Option<String>,
+    pub visibility: Visibility,
+}
+
+/// What `merge_projects` moved to the kept project
+#[derive(Debug, Default)]
+pub struct MergeCounts {
+    pub sessions: usize,
+    pub paths: usize,
+    pub identifiers: usize,
+}
+
+/// Who a project's history may be shown to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Stays on this machine: never exported, redacted from archives
+    Private,
+    /// May reach teammates (archives, the API) but not published documents
+    Internal,
+    /// May be exported anywhere
+    #[default]
+    Shareable,
+}
+
+/// Where an outward-facing command sends history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience {
+    /// Team channels: `db export` archives and the `serve` API
+    Team,
+    /// Documents that can be published anywhere: `export`, `changelog`
+    Public,
+}
+
+impl Visibility {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "private" => Ok(Self::Private),
+            "internal" => Ok(Self::Internal),
+            "shareable" => Ok(Self::Shareable),
+            other => anyhow::bail!(
+                "Unknown visibility '{}' (use private, internal or shareable)",
+                other
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::Internal => "internal",
+            Self::Shareable => "shareable",
+        }
+    }
+
+    /// Whether history at this level may be sent to `audience`
+    pub fn allows(&self, audience: Audience) -> bool {
+        match audience {
+            Audience::Team => *self >= Self::Internal,
+            Audience::Public => *self == Self::Shareable,
+        }
+    }
+}
+
+/// A queued enrichment job
+#[derive(Debug)]
+pub struct EnrichmentJob {
+    pub id: i64,
+    pub session_id: String,
+    pub kind: String,
+    pub attempts: u32,
+}
+
+/// A stored project snapshot
+#[derive(Debug)]
+pub struct SnapshotRow {
+    pub id: i64,
+    pub label: Option<String>,
+    pub taken_at: String,
+    pub snapshot: ProjectSnapshot,
+}
+
+/// A recorded duplicate pair with both sessions
+#[derive(Debug)]
+pub struct DuplicateRow {
+    pub id: i64,
+    pub confidence: f64,
+    pub method: String,
+    pub resolution: Option<String>,
+    pub a: DuplicateSide,
+    pub b: DuplicateSide,
+}
+
+/// One session of a duplicate pair
+#[derive(Debug)]
+pub struct DuplicateSide {
+    pub session_id: String,
+    pub short_hash: String,
+    pub source_name: String,
+    pub title: Option<String>,
+    pub message_count: i64,
+    pub merged_into: Option<String>,
+}
+
+/// One combination of request parameters used in a session
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestParamsRow {
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<i64>,
+    pub system_prompt_hash: Option<String>,
+    pub system_prompt_chars: Option<i64>,
+    /// Requests sent with these parameters
+    pub requests: i64,
+}
+
+/// A session's model calls as (model, prompt tokens), in order
+#[derive(Debug, Clone)]
+pub struct ContextUsageRow {
+    pub session_id: String,
+    pub short_hash: String,
+    pub probe_source_id: String,
+    pub project_name: Option<String>,
+    pub title: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// Compactions the source recorded
+    pub compactions: i64,
+    pub calls: Vec<(Option<String>, i64)>,
+}
+
+/// A session's length and how often its context was compacted
+#[derive(Debug, Clone)]
+pub struct CompactionRow {
+    pub project_name: Option<String>,
+    /// Primary model of the session
+    pub model: Option<String>,
+    pub message_count: i64,
+    /// Compaction events (`system` / `compact` message rows)
+    pub compactions: i64,
+}
+
+/// A tool call that named a file
+#[derive(Debug, Clone)]
+pub struct FileTouchRow {
+    pub session_id: String,
+    pub project_id: Option<String>,
+    pub project_name: Option<String>,
+    /// Session working directory
+    pub cwd: Option<String>,
+    pub tool_name: String,
+    /// As given in the tool input
+    pub file_path: String,
+    pub timestamp: Option<String>,
+}
+
+/// Model of one assistant message
+#[derive(Debug, Clone)]
+pub struct AnswerModelRow {
+    pub session_id: String,
+    pub short_hash: String,
+    pub title: Option<String>,
+    pub session_start: Option<String>,
+    /// 1-based position among all of the session's messages
+    pub message: usize,
+    pub model: String,
+    pub timestamp: Option<String>,
+}
+
+/// Calls of one MCP tool
+#[derive(Debug, Clone, Serialize)]
+pub struct McpUsageRow {
+    pub server: String,
+    pub tool: String,
+    pub calls: i64,
+    /// Calls that came back with an error result
+    pub errors: i64,
+    /// Calls without any recorded result
+    pub unanswered: i64,
+    pub sessions: i64,
+    pub last_used: Option<String>,
+}
+
+/// Calls of one tool within a project, provider and day
+#[derive(Debug, Clone)]
+pub struct ToolUsageRow {
+    pub tool_name: String,
+    pub project_name: Option<String>,
+    pub provider: Option<String>,
+    pub day: Option<String>,
+    pub calls: i64,
+    /// Calls without any recorded result
+    pub unanswered: i64,
+    /// Calls that came back with an error result
+    pub errors: i64,
+    pub last_used: Option<String>,
+}
+
+/// Permission decisions of one tool's calls
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionRow {
+    pub tool_name: String,
+    /// Ran without asking (read-only tool, allow rule or permissive mode)
+    pub auto: i64,
+    /// Ran after the user approved it
+    pub approved: i64,
+    /// Rejected by the user
+    pub denied: i64,
+}
+
+/// Session filters applied in SQL by [`MetadataStore::query_sessions`]
+#[derive(Debug, Clone, Default)]
+pub struct SessionQuery {
+    pub provider: Option<String>,
+    /// Probe source name (e.g. `OpenCode`)
+    pub source: Option<String>,
+    /// RFC 3339; sessions last active at or after this time
+    pub since: Option<String>,
+    /// RFC 3339; sessions last active before this time
+    pub until: Option<String>,
+    pub project_id: Option<String>,
+    /// Only sessions not assigned to any project
+    pub unassigned: bool,
+    /// Include archived sessions
+    pub archived: bool,
+    /// Working directory is this path or below it
+    pub path_prefix: Option<String>,
+    /// Working directory matches this glob (`*`, `?`, `[...]`)
+    pub path_glob: Option<String>,
+    pub order: SessionOrder,
+}
+
+/// Result order of [`MetadataStore::query_sessions`], largest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionOrder {
+    /// Last activity
+    #[default]
+    Recent,
+    /// Wall-clock duration
+    Duration,
+    /// Active time
+    Active,
+    Messages,
+}
+
+impl SessionOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            SessionOrder::Recent => "s.last_timestamp DESC",
+            SessionOrder::Duration => "s.duration_secs DESC NULLS LAST, s.last_timestamp DESC",
+            SessionOrder::Active => "s.active_secs DESC NULLS LAST, s.last_timestamp DESC",
+            SessionOrder::Messages => "s.message_count DESC, s.last_timestamp DESC",
+        }
+    }
+}
+
+impl std::str::FromStr for SessionOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "recent" => SessionOrder::Recent,
+            "duration" => SessionOrder::Duration,
+            "active" => SessionOrder::Active,
+            "messages" => SessionOrder::Messages,
+            other => anyhow::bail!(
+                "Unknown sort order: {} (expected recent, duration, active or messages)",
+                other
+            ),
+        })
+    }
+}
+
+impl SessionQuery {
+    /// Parameters ?1-?9 of `SESSION_FILTER`
+    fn params(&self) -> impl rusqlite::Params + '_ {
+        (
+            &self.provider,
+            &self.source,
+            &self.since,
+            &self.until,
+            &self.project_id,
+            self.unassigned,
+            self.archived,
+            &self.path_prefix,
+            &self.path_glob,
+        )
+    }
+}
+
+/// A distinct system prompt as sent by one probe source
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemPromptRow {
+    pub hash: String,
+    pub probe_source_id: String,
+    pub chars: i64,
+    /// Sessions that sent this prompt at least once
+    pub sessions: i64,
+    pub requests: i64,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    /// Mean message count of those sessions
+    pub avg_messages: f64,
+    /// Mean tool uses per session
+    pub avg_tool_uses: f64,
+}
+
+/// Messages one session sent with one model
+#[derive(Debug, Clone)]
+pub struct ActivityRow {
+    pub session_id: String,
+    pub project_name: Option<String>,
+    pub model: Option<String>,
+    pub messages: i64,
+}
+
+/// Token totals for one session, model and day
+#[derive(Debug, Clone)]
+pub struct UsageRow {
+    pub session_id: String,
+    pub short_hash: String,
+    pub probe_source_id: String,
+    pub project_name: Option<String>,
+    pub model: Option<String>,
+    pub day: Option<String>,
+    pub tokens: TokenCounts,
+}
+
+/// Rolled-up token totals for one day, source and model
+#[derive(Debug, Clone)]
+pub struct RollupRow {
+    pub probe_source_id: String,
+    pub model: Option<String>,
+    pub messages: i64,
+    pub tokens: TokenCounts,
+    pub refreshed_at: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct AlertRow {
+    pub kind: String,
+    pub day: String,
+    pub value: f64,
+    pub baseline: Option<f64>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnomalyRow {
+    pub short_hash: String,
+    pub title: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub kind: String,
+    pub tool_name: Option<String>,
+    pub repeat_count: Option<i64>,
+    pub message_index: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProbeStatsRow {
+    pub probe_source_id: String,
+    pub last_indexed: Option<String>,
+    pub sessions: i64,
+    pub messages: i64,
+    pub capabilities: ProbeCapabilities,
+}
+
+/// Messages and tokens of one model, in a session or summed over sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionModelRow {
+    pub model: String,
+    /// Sessions that used the model (1 for a single session's breakdown)
+    pub sessions: i64,
+    pub messages: i64,
+    pub tokens: TokenCounts,
+}
+
+/// Usage of one model across sessions and sources
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUsageRow {
+    pub model: String,
+    /// Source names the model's messages came from
+    pub sources: Vec<String>,
+    pub sessions: i64,
+    pub messages: i64,
+    pub tool_calls: i64,
+    /// Messages with at least one tool call
+    pub tool_messages: i64,
+    pub tokens: TokenCounts,
+}
+
+impl ModelUsageRow {
+    /// Share of prompt tokens served from the cache
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let prompt = self.tokens.input + self.tokens.cache_read + self.tokens.cache_creation;
+        (prompt > 0).then(|| self.tokens.cache_read as f64 / prompt as f64)
+    }
+
+    /// Tool calls per message
+    pub fn tool_calls_per_message(&self) -> Option<f64> {
+        (self.messages > 0).then(|| self.tool_calls as f64 / self.messages as f64)
+    }
+}
+
+/// Reasoning of one model in one source (`MetadataStore::thinking_usage`)
+#[derive(Debug, Serialize)]
+pub struct ThinkingRow {
+    pub probe_source_id: String,
+    pub model: Option<String>,
+    /// Assistant messages
+    pub messages: i64,
+    /// Assistant messages with a thinking block
+    pub thinking_messages: i64,
+    /// Messages whose reasoning tokens were recorded (reported or estimated)
+    pub counted_messages: i64,
+    pub output_tokens: i64,
+    /// Counted within `output_tokens`
+    pub reasoning_tokens: i64,
+}
+
+impl ThinkingRow {
+    /// Share of the output spent reasoning
+    pub fn reasoning_share(&self) -> Option<f64> {
+        (self.output_tokens > 0).then(|| self.reasoning_tokens as f64 / self.output_tokens as f64)
+    }
+}
+
+/// Activity of a probe source's sessions
+#[derive(Debug, Serialize)]
+pub struct ActivityStatsRow {
+    pub probe_source_id: String,
+    /// Average duration and active time per session; summed message and tool call counts
+    #[serde(flatten)]
+    pub totals: SessionMetrics,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkipStatsRow {
+    pub probe_source_id: String,
+    pub reason: String,
+    pub sessions: i64,
+    pub count: i64,
+}
+
+/// Cached content (`extraction.cache_content`) backs `read` and exports once a source
+/// file is gone
+impl ContentArchive for MetadataStore {
+    fn get(&self, message: &MessageRow) -> Result<Option<String>> {
+        let data: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT data FROM message_content WHERE message_id = ?",
+                params![message.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        data.map(|data| Ok(String::from_utf8(zstd::decode_all(data.as_slice())?)?))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{ContentRef, RequestParams};
+    use std::path::PathBuf;
+
+    fn message(uuid: &str, line: u32) -> MessageMetadata {
+        MessageMetadata {
+            uuid: Some(uuid.to_string()),
+            role: "user".to_string(),
+            provider_id: None,
+            model: None,
+            timestamp: None,
+            content_ref: ContentRef::jsonl(PathBuf::from("/tmp/s.jsonl"), 0, line),
+            has_tool_use: false,
+            has_thinking: false,
+            tool_uses: vec![],
+            token_usage: None,
+            request_params: None,
+            subtype: None,
+        }
+    }
+
+    fn metadata(messages: Vec<MessageMetadata>) -> SessionMetadata {
+        SessionMetadata {
+            external_id: "abcdef0123".to_string(),
+            title: None,
+            project_path: None,
+            git_remote: None,
+            source_group: None,
+            primary_provider: None,
+            primary_model: None,
+            first_timestamp: None,
+            last_timestamp: None,
+            messages,
+            references: vec![],
+            language: None,
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        }
+    }
+
+    #[test]
+    fn test_reindex_keeps_message_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+
+        let first = metadata(vec![message("a", 0), message("b", 1), message("c", 2)]);
+        let session_id = store.upsert_session("t:Test", &session, &first).unwrap();
+        store.insert_messages(&session_id, &first.messages).unwrap();
+        let before = store.get_messages(&session_id).unwrap();
+
+        // "b" disappears, "d" is appended
+        let second = metadata(vec![message("a", 0), message("c", 2), message("d", 3)]);
+        store
+            .insert_messages(&session_id, &second.messages)
+            .unwrap();
+        let after = store.get_messages(&session_id).unwrap();
+
+        let uuids: Vec<_> = after.iter().filter_map(|m| m.uuid.as_deref()).collect();
+        assert_eq!(uuids, vec!["a", "c", "d"]);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[1].id, before[2].id);
+    }
+
+    #[test]
+    fn test_split_session_and_reindex() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let uuids = |id: &str| -> Vec<String> {
+            store
+                .get_messages(id)
+                .unwrap()
+                .into_iter()
+                .filter_map(|m| m.uuid)
+                .collect()
+        };
+
+        let first = metadata(
+            ["a", "b", "c", "d", "e"]
+                .iter()
+                .zip(0..)
+                .map(|(u, i)| message(u, i))
+                .collect(),
+        );
+        let root_id = store.upsert_session("t:Test", &session, &first).unwrap();
+        store.insert_messages(&root_id, &first.messages).unwrap();
+        let root = store.get_session(&root_id).unwrap().unwrap();
+
+        let tail = store.split_session(&root, 3).unwrap();
+        assert_eq!(tail, format!("{}@3", root.short_hash));
+        assert_eq!(uuids(&root_id), vec!["a", "b"]);
+        let tail_id = format!("{}@3", root_id);
+        assert_eq!(uuids(&tail_id), vec!["c", "d", "e"]);
+
+        // Out-of-range points are refused
+        assert!(store.split_session(&root, 1).is_err());
+        assert!(store.split_session(&root, 3).is_err());
+
+        // Splitting a derived session positions against the root's messages
+        let tail_row = store.get_session(&tail_id).unwrap().unwrap();
+        assert_eq!(tail_row.message_count, 3);
+        store.split_session(&tail_row, 2).unwrap();
+        let last_id = format!("{}@4", root_id);
+        assert_eq!(uuids(&tail_id), vec!["c"]);
+        assert_eq!(uuids(&last_id), vec!["d", "e"]);
+
+        // Re-extraction keeps the splits: appended messages land in the last one
+        let second = metadata(
+            ["a", "b", "c", "d", "e", "f"]
+                .iter()
+                .zip(0..)
+                .map(|(u, i)| message(u, i))
+                .collect(),
+        );
+        store.upsert_session("t:Test", &session, &second).unwrap();
+        store.insert_messages(&root_id, &second.messages).unwrap();
+        assert_eq!(uuids(&root_id), vec!["a", "b"]);
+        assert_eq!(uuids(&tail_id), vec!["c"]);
+        assert_eq!(uuids(&last_id), vec!["d", "e", "f"]);
+        assert_eq!(
+            store.get_session(&last_id).unwrap().unwrap().message_count,
+            3
+        );
+
+        // With messages back in the root (e.g. rows restored from an archive), the same
+        // point again names the existing split instead of failing on its ID
+        store
+            .conn
+            .execute(
+                "UPDATE messages SET session_id = ?1 WHERE session_id LIKE ?1 || '@%'",
+                params![root_id],
+            )
+            .unwrap();
+        let error = store.split_session(&root, 3).unwrap_err().to_string();
+        assert!(error.contains("already split at message 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_project_counters_follow_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project("p1", "One", "code", None, None)
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let mut meta = metadata(vec![message("a", 0), message("b", 1)]);
+        meta.last_timestamp = Some(chrono::Utc::now());
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        let counts = |store: &MetadataStore| {
+            let p = store.find_project("p1").unwrap().unwrap();
+            (p.session_count, p.message_count, p.last_session_at)
+        };
+
+        store
+            .assign_session_to_project(&session_id, Some("p1"))
+            .unwrap();
+        let (sessions, messages, last) = counts(&store);
+        assert_eq!((sessions, messages), (1, 2));
+        assert!(last.is_some());
+
+        // Re-extraction with more messages updates the cached total
+        meta.messages.push(message("c", 2));
+        store.upsert_session("t:Test", &session, &meta).unwrap();
+        assert_eq!(counts(&store).1, 3);
+
+        store.unassign_session(&session_id).unwrap();
+        assert_eq!(counts(&store), (0, 0, None));
+        assert_eq!(store.recount_projects().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_merged_project_moves_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project(
+                "p1",
+                "Typo",
+                "code",
+                Some("/src/a"),
+                Some(r#"{"team":"x"}"#),
+            )
+            .unwrap();
+        store
+            .create_project("p2", "Real", "code", Some("/src/b"), None)
+            .unwrap();
+        store
+            .add_project_identifier("p1", "git_remote", "git@host:a.git")
+            .unwrap();
+        store
+            .set_project_visibility("p1", Visibility::Private)
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let mut meta = metadata(vec![message("a", 0)]);
+        meta.project_path = Some("/src/a".to_string());
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+
+        let (source, target) = (
+            store.find_project("p1").unwrap().unwrap(),
+            store.find_project("p2").unwrap().unwrap(),
+        );
+        let moved = store.merge_projects(&source, &target).unwrap();
+        assert_eq!((moved.sessions, moved.paths, moved.identifiers), (1, 1, 1));
+        assert!(store.find_project("p1").unwrap().is_none());
+        let kept = store.find_project("p2").unwrap().unwrap();
+        assert_eq!(kept.session_count, 1);
+        assert_eq!(kept.visibility, Visibility::Private);
+        assert_eq!(kept.metadata.as_deref(), Some(r#"{"team":"x"}"#));
+        assert_eq!(store.get_project_paths("p2").unwrap(), ["/src/b", "/src/a"]);
+
+        // Deleting without a target leaves the session free to auto-link again
+        assert_eq!(store.delete_project("p2", None).unwrap(), 1);
+        let session = store.get_session(&session_id).unwrap().unwrap();
+        assert_eq!(session.project_id, None);
+        assert!(store.get_project_paths("p2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_project_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .create_project("p1", "One", "code", Some("/src/a"), Some(r#"{"team":"x"}"#))
+            .unwrap();
+        store
+            .create_project("p2", "Two", "code", Some("/src/b"), None)
+            .unwrap();
+
+        store.rename_project("p1", "Uno").unwrap();
+        store.set_project_type("p1", "research").unwrap();
+        store.set_project_primary_path("p1", "/src/c").unwrap();
+        assert!(store.set_project_primary_path("p1", "/src/b").is_err());
+        assert!(store.remove_project_metadata("p1", "team").unwrap());
+        assert!(!store.remove_project_metadata("p1", "team").unwrap());
+
+        let project = store.find_project("Uno").unwrap().unwrap();
+        assert_eq!(project.project_type, "research");
+        assert_eq!(project.primary_path.as_deref(), Some("/src/c"));
+        assert_eq!(project.metadata.as_deref(), Some("{}"));
+        assert_eq!(store.get_project_paths("p1").unwrap(), ["/src/c", "/src/a"]);
+    }
+
+    #[test]
+    fn test_usage_rollups_refresh_by_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let now = chrono::Utc::now();
+        let mut messages = vec![message("a", 0), message("b", 1), message("c", 2)];
+        for (i, m) in messages.iter_mut().enumerate() {
+            m.model = Some("sonnet".to_string());
+            m.token_usage = Some(crate::probe::TokenUsage {
+                input_tokens: Some(100),
+                output_tokens: Some(10),
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            });
+            // The first message was three days ago
+            m.timestamp = Some(now - chrono::Duration::days(if i == 0 { 3 } else { 0 }));
+        }
+        let meta = metadata(messages);
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        store.insert_messages(&session_id, &meta.messages).unwrap();
+
+        let local = |t: chrono::DateTime<chrono::Utc>| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        };
+        let today = local(now);
+        assert!(store.rollups_missing().unwrap());
+        store.refresh_usage_rollups(None).unwrap();
+        // Refreshing recent days neither duplicates nor drops older ones
+        store.refresh_usage_rollups(Some(&today)).unwrap();
+        let rows = store.usage_rollups(&today).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].messages, rows[0].tokens.total()), (2, 220));
+        assert_eq!(rows[0].model.as_deref(), Some("sonnet"));
+        let earlier = store
+            .usage_rollups(&local(now - chrono::Duration::days(3)))
+            .unwrap();
+        assert_eq!(earlier[0].messages, 1);
+        assert!(!store.rollups_missing().unwrap());
+    }
+
+    #[test]
+    fn test_archived_sessions_hidden_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let session_id = store
+            .upsert_session("t:Test", &session, &metadata(vec![message("a", 0)]))
+            .unwrap();
+        let listed = |archived| {
+            store
+                .query_sessions(&SessionQuery {
+                    archived,
+                    ..Default::default()
+                })
+                .unwrap()
+                .len()
+        };
+
+        assert!(store.set_session_archived(&session_id, true).unwrap());
+        assert!(!store.set_session_archived(&session_id, true).unwrap());
+        assert_eq!((listed(false), listed(true)), (0, 1));
+        // Other readers still see it; re-extraction keeps the flag
+        assert!(store.list_sessions(None, None).unwrap()[0].archived);
+        store
+            .upsert_session("t:Test", &session, &metadata(vec![message("a", 0)]))
+            .unwrap();
+        assert_eq!(listed(false), 0);
+        assert!(store.set_session_archived(&session_id, false).unwrap());
+        assert_eq!(listed(false), 1);
+    }
+
+    #[test]
+    fn test_session_metrics_and_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T10:00:00Z")
+            .unwrap()
+            .to_utc();
+        let store_session = |id: &str, minutes: &[i64]| {
+            let mut messages: Vec<MessageMetadata> = minutes
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    let mut message = message(&format!("{}-{}", id, i), i as u32);
+                    message.timestamp = Some(start + chrono::Duration::minutes(*m));
+                    if i % 2 == 1 {
+                        message.role = "assistant".to_string();
+                    }
+                    message
+                })
+                .collect();
+            messages[1].tool_uses = vec![crate::probe::ToolUseMetadata {
+                tool_id: None,
+                tool_name: "Bash".to_string(),
+                has_result: true,
+                is_error: false,
+                permission: None,
+                input_hash: None,
+                file_path: None,
+            }];
+            let mut meta = metadata(messages);
+            meta.external_id = id.to_string();
+            meta.first_timestamp = meta.messages.first().and_then(|m| m.timestamp);
+            meta.last_timestamp = meta.messages.last().and_then(|m| m.timestamp);
+            let session = SessionRef {
+                id: id.to_string(),
+                source_path: PathBuf::from("/tmp/s.jsonl"),
+            };
+            let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+            store.insert_messages(&session_id, &meta.messages).unwrap();
+            store.refresh_session_metrics(&session_id).unwrap();
+        };
+        // An hour's idle gap between 3 and 63 minutes
+        store_session("aaaa1111", &[0, 1, 3, 63, 64]);
+        store_session("bbbb2222", &[0, 10]);
+
+        let sessions = store
+            .query_sessions(&SessionQuery {
+                order: SessionOrder::Duration,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(sessions[0].short_hash, "aaaa1111");
+        let metrics = sessions[0].metrics;
+        assert_eq!(metrics.duration_secs, Some(64 * 60));
+        assert_eq!(metrics.active_secs, Some(4 * 60));
+        assert_eq!((metrics.user_messages, metrics.assistant_messages), (3, 2));
+        assert_eq!(metrics.tool_calls_per_message(), Some(0.5));
+        // The other session's only gap is idle
+        assert_eq!(sessions[1].metrics.active_secs, Some(0));
+
+        let by_active = store
+            .query_sessions(&SessionQuery {
+                order: SessionOrder::Active,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_active[0].short_hash, "aaaa1111");
+        let activity = store.activity_stats().unwrap();
+        assert_eq!(activity[0].totals.tool_calls, 2);
+    }
+
+    #[test]
+    fn test_session_model_breakdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let mut messages = vec![
+            message("a", 0),
+            message("b", 1),
+            message("c", 2),
+            message("d", 3),
+        ];
+        for (m, model) in messages.iter_mut().zip(["", "opus", "haiku", "opus"]) {
+            if !model.is_empty() {
+                m.role = "assistant".to_string();
+                m.model = Some(model.to_string());
+                m.token_usage = Some(crate::probe::TokenUsage {
+                    input_tokens: Some(100),
+                    output_tokens: Some(10),
+                    cache_read_tokens: None,
+                    cache_creation_tokens: None,
+                    reasoning_tokens: Some(5),
+                });
+            }
+        }
+        let meta = metadata(messages);
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        store.insert_messages(&session_id, &meta.messages).unwrap();
+        store.refresh_session_metrics(&session_id).unwrap();
+
+        let models = store.session_models(&session_id).unwrap();
+        let split: Vec<(&str, i64, i64)> = models
+            .iter()
+            .map(|m| (m.model.as_str(), m.messages, m.tokens.total()))
+            .collect();
+        assert_eq!(split, vec![("opus", 2, 220), ("haiku", 1, 110)]);
+        assert_eq!(store.model_breakdown().unwrap()[0].sessions, 1);
+        assert_eq!(store.count_multi_model_sessions().unwrap(), 1);
+
+        let usage = store.model_usage(None).unwrap();
+        assert_eq!(usage[0].model, "opus");
+        assert_eq!((usage[0].messages, usage[0].tokens.total()), (2, 220));
+        assert_eq!(usage[0].cache_hit_ratio(), Some(0.0));
+        assert_eq!(usage[1].tool_calls_per_message(), Some(0.0));
+        assert!(store
+            .model_usage(Some("2999-01-01T00:00:00Z"))
+            .unwrap()
+            .is_empty());
+        let thinking = store.thinking_usage().unwrap();
+        assert_eq!(thinking[0].model.as_deref(), Some("opus"));
+        assert_eq!(
+            (thinking[0].reasoning_tokens, thinking[0].output_tokens),
+            (10, 20)
+        );
+        assert_eq!(thinking[0].reasoning_share(), Some(0.5));
+
+        store
+            .delete_sessions(std::slice::from_ref(&session_id))
+            .unwrap();
+        assert!(store.session_models(&session_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_connection_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let pragma = |store: &MetadataStore, name: &str| -> String {
+            store
+                .conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| {
+                    row.get::<_, rusqlite::types::Value>(0)
+                })
+                .map(|v| match v {
+                    rusqlite::types::Value::Integer(n) => n.to_string(),
+                    rusqlite::types::Value::Text(s) => s,
+                    other => format!("{:?}", other),
+                })
+                .unwrap()
+        };
+
+        let store = MetadataStore::open(&path).unwrap();
+        assert_eq!(pragma(&store, "journal_mode"), "wal");
+        assert_eq!(pragma(&store, "synchronous"), "1");
+        assert_eq!(pragma(&store, "busy_timeout"), "5000");
+        drop(store);
+
+        let config = DatabaseConfig {
+            journal_mode: "DELETE".to_string(),
+            synchronous: "full".to_string(),
+            busy_timeout_ms: 250,
+            ..Default::default()
+        };
+        let store = MetadataStore::open_with(&path, &config).unwrap();
+        assert_eq!(pragma(&store, "journal_mode"), "delete");
+        assert_eq!(pragma(&store, "synchronous"), "2");
+        assert_eq!(pragma(&store, "busy_timeout"), "250");
+        drop(store);
+
+        let bogus = DatabaseConfig {
+            synchronous: "sometimes".to_string(),
+            ..Default::default()
+        };
+        assert!(MetadataStore::open_with(&path, &bogus).is_err());
+    }
+
+    #[test]
+    fn test_orphan_and_delete_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/gone.jsonl"),
+        };
+        let session_id = store
+            .upsert_session("t:Test", &session, &metadata(vec![message("a", 0)]))
+            .unwrap();
+        let message_ids = store
+            .insert_messages(&session_id, &[message("a", 0), message("b", 1)])
+            .unwrap();
+        store
+            .cache_content(&[(message_ids[0], "{}".to_string())])
+            .unwrap();
+        let ids = vec![session_id.clone()];
+        let orphaned = || store.session_sources(None).unwrap()[0].orphaned;
+
+        assert_eq!(store.set_sessions_orphaned(&ids, true).unwrap(), 1);
+        assert_eq!(store.set_sessions_orphaned(&ids, true).unwrap(), 0);
+        assert!(orphaned());
+        // Re-extraction means the source is back
+        store
+            .upsert_session("t:Test", &session, &metadata(vec![message("a", 0)]))
+            .unwrap();
+        assert!(!orphaned());
+        assert!(store
+            .sessions_with_cached_content()
+            .unwrap()
+            .contains(&session_id));
+
+        assert_eq!(store.delete_sessions(&ids).unwrap(), 1);
+        assert!(store.session_sources(None).unwrap().is_empty());
+        let messages: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(messages, 0);
+        assert!(store.sessions_with_cached_content().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bulk_assign_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project("p1", "foo", "code", None, None)
+            .unwrap();
+        for (id, path) in [
+            ("aaaa1111", "/work/foo"),
+            ("bbbb2222", "/work/foo/api"),
+            ("cccc3333", "/work/foobar"),
+        ] {
+            let session = SessionRef {
+                id: id.to_string(),
+                source_path: PathBuf::from(format!("/tmp/{}.jsonl", id)),
+            };
+            let mut metadata = metadata(vec![]);
+            metadata.external_id = id.to_string();
+            metadata.project_path = Some(path.to_string());
+            store.upsert_session("t:Test", &session, &metadata).unwrap();
+        }
+        let query = SessionQuery {
+            path_prefix: Some("/work/foo".to_string()),
+            archived: true,
+            ..Default::default()
+        };
+
+        assert_eq!(store.assign_sessions(&query, "p1").unwrap(), 2);
+        // Already in the project: nothing changes
+        assert_eq!(store.assign_sessions(&query, "p1").unwrap(), 0);
+        let glob = SessionQuery {
+            path_glob: Some("/work/foo*".to_string()),
+            archived: true,
+            ..Default::default()
+        };
+        assert_eq!(store.assign_sessions(&glob, "p1").unwrap(), 1);
+        let sessions = store.list_sessions(None, None).unwrap();
+        assert!(sessions
+            .iter()
+            .all(|s| s.project_id.as_deref() == Some("p1")));
+    }
+
+    #[test]
+    fn test_relink_after_project_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db"))
+            .unwrap()
+            .with_path_normalization(true);
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let mut ids = vec![];
+        for (id, path) in [
+            ("aaaa1111", "/work/foo"),
+            ("bbbb2222", "/work/foo"),
+            ("cccc3333", "/work/foo/api/"),
+        ] {
+            let session = SessionRef {
+                id: id.to_string(),
+                source_path: PathBuf::from(format!("/tmp/{}.jsonl", id)),
+            };
+            let mut metadata = metadata(vec![]);
+            metadata.external_id = id.to_string();
+            metadata.project_path = Some(path.to_string());
+            ids.push(store.upsert_session("t:Test", &session, &metadata).unwrap());
+        }
+        // The user said this one belongs nowhere
+        store.unassign_session(&ids[1]).unwrap();
+        store
+            .create_project("p1", "foo", "code", Some("/work/foo/"), None)
+            .unwrap();
+
+        // Trailing slashes and subdirectories still match
+        assert_eq!(store.relink_sessions().unwrap(), 2);
+        assert_eq!(store.relink_sessions().unwrap(), 0);
+        let project = |id: &str| store.get_session(id).unwrap().unwrap().project_id;
+        assert_eq!(project(&ids[0]).as_deref(), Some("p1"));
+        assert_eq!(project(&ids[1]), None);
+        assert_eq!(project(&ids[2]).as_deref(), Some("p1"));
+    }
+
+    #[test]
+    fn test_read_only_open_never_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("other.db");
+        {
+            let store = MetadataStore::open(&path).unwrap();
+            store
+                .create_project("p1", "foo", "code", Some("/work/foo"), None)
+                .unwrap();
+            // Pretend the database comes from an older chronicle
+            store
+                .conn
+                .execute(
+                    "DELETE FROM schema_version WHERE version = ?",
+                    [schema::latest_version()],
+                )
+                .unwrap();
+        }
+        let before = std::fs::read(&path).unwrap();
+
+        let store = MetadataStore::open_read_only(&path).unwrap();
+        assert!(store.is_read_only());
+        assert_eq!(store.schema_version().unwrap(), schema::latest_version());
+        assert_eq!(store.list_projects().unwrap().len(), 1);
+        assert!(store.rename_project("p1", "bar").is_err());
+        drop(store);
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        let scratch =
+            std::env::temp_dir().join(format!("chronicle-read-only-{}.db", std::process::id()));
+        assert!(!scratch.exists());
+    }
+
+    #[test]
+    fn test_system_prompts_stored_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let params = RequestParams {
+            temperature: Some(0.2),
+            system_prompt: Some("You are a careful engineer.".to_string()),
+            ..Default::default()
+        };
+        let mut messages = vec![message("a", 0), message("b", 1), message("c", 2)];
+        messages[0].request_params = Some(params.clone());
+        messages[2].request_params = Some(params);
+        let meta = metadata(messages);
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        store.insert_messages(&session_id, &meta.messages).unwrap();
+
+        let used = store.session_request_params(&session_id).unwrap();
+        assert_eq!(used.len(), 1);
+        assert_eq!(
+            (used[0].requests, used[0].system_prompt_chars),
+            (2, Some(27))
+        );
+        let prompts: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM system_prompts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(prompts, 1);
+    }
+
+    #[test]
+    fn test_cached_content_outlives_message_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef0123".to_string(),
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+        };
+        let meta = metadata(vec![message("a", 0), message("b", 1)]);
+        let session_id = store.upsert_session("t:Test", &session, &meta).unwrap();
+        let ids = store.insert_messages(&session_id, &meta.messages).unwrap();
+        store
+            .cache_content(&[(ids[0], r#"{"content":"first"}"#.to_string())])
+            .unwrap();
+
+        // Re-indexing keeps message ids, so the cached copy still applies
+        let again = store.insert_messages(&session_id, &meta.messages).unwrap();
+        assert_eq!(again, ids);
+        let rows = store.get_messages(&session_id).unwrap();
+        let cached = ContentArchive::get(&store, &rows[0]).unwrap();
+        assert_eq!(cached.as_deref(), Some(r#"{"content":"first"}"#));
+        assert_eq!(ContentArchive::get(&store, &rows[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_probe_capabilities_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        for id in ["a:Full", "b:NoTokens"] {
+            store
+                .ensure_probe_source(id, None, "Test", SourceType::Multi, None, "active")
+                .unwrap();
+        }
+        let no_tokens = ProbeCapabilities {
+            supports_token_usage: false,
+            ..ProbeCapabilities::FULL
+        };
+        store
+            .set_probe_capabilities("b:NoTokens", &no_tokens)
+            .unwrap();
+
+        // Unrecorded capabilities count as full
+        let stats = store.probe_stats().unwrap();
+        assert_eq!(stats[0].capabilities, ProbeCapabilities::FULL);
+        assert_eq!(stats[1].capabilities, no_tokens);
+    }
 }
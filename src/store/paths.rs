@@ -0,0 +1,65 @@
+//! Path normalization for project matching
+//!
+//! Tools record working directories as they saw them: through symlinks, with
+//! trailing slashes, or in a different case than the project was registered with on
+//! case-insensitive filesystems. With `linking.normalize_paths` both sides of a
+//! comparison are normalized first, and a session also matches a project registered
+//! for one of its working directory's ancestors (the nearest one wins).
+
+use std::path::Path;
+
+/// Comparable form of a path: symlinks resolved when it exists, no trailing slash, and
+/// lowercased where the filesystem ignores case
+pub fn normalize_path(path: &str) -> String {
+    let trimmed = match path.trim_end_matches(['/', '\\']) {
+        "" if !path.is_empty() => &path[..1],
+        trimmed => trimmed,
+    };
+    let resolved = std::fs::canonicalize(trimmed)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| trimmed.to_string());
+    // Windows canonical paths carry the verbatim prefix
+    let resolved = resolved
+        .strip_prefix(r"\\?\")
+        .map(str::to_string)
+        .unwrap_or(resolved);
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        resolved.to_lowercase()
+    } else {
+        resolved
+    }
+}
+
+/// Index of the entry in `registered` (normalized paths) that is `path` (normalized) or
+/// its nearest ancestor
+pub fn nearest_registered(path: &str, registered: &[String]) -> Option<usize> {
+    Path::new(path).ancestors().find_map(|ancestor| {
+        let ancestor = ancestor.to_string_lossy();
+        registered.iter().position(|p| *p == ancestor)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_normalized_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real");
+        std::fs::create_dir_all(real.join("api/src")).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let real = normalize_path(&real.to_string_lossy());
+
+        assert_eq!(normalize_path(&format!("{}/", link.display())), real);
+        assert_eq!(normalize_path("/nonexistent/dir//"), "/nonexistent/dir");
+        assert_eq!(normalize_path("/"), "/");
+
+        let registered = vec!["/elsewhere".to_string(), real.clone()];
+        let cwd = normalize_path(&link.join("api/src").to_string_lossy());
+        assert_eq!(nearest_registered(&cwd, &registered), Some(1));
+        assert_eq!(nearest_registered("/tmp", &registered), None);
+    }
+}
@@ -0,0 +1,166 @@
+//! Read-only connection pool
+//!
+//! `MetadataStore` wraps one connection, so it can't be shared between threads. A
+//! `ReadPool` is: each `get()` hands out a read-only store on its own connection,
+//! returned to the pool when dropped. With the database in WAL mode
+//! (`database.journal_mode: wal`, the default), readers see the last committed state
+//! and never wait on (or block) the writing store.
+
+use anyhow::Result;
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::MetadataStore;
+
+/// How long a reader waits on a lock before failing (checkpoints, schema changes),
+/// unless the store sets its own
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct ReadPool {
+    path: PathBuf,
+    max_idle: usize,
+    idle: Mutex<Vec<Connection>>,
+    /// Path normalization of the store the pool was made from
+    pub(super) normalize_paths: bool,
+    /// Busy timeout of the store the pool was made from
+    pub(super) busy_timeout: Duration,
+}
+
+/// A read-only store borrowed from a [`ReadPool`]
+pub struct PooledReader<'a> {
+    pool: &'a ReadPool,
+    store: Option<MetadataStore>,
+}
+
+impl ReadPool {
+    /// Pool for the database at `path`; at most `max_idle` connections are kept open
+    /// between uses. Prefer [`MetadataStore::read_pool`], which carries over the store's
+    /// settings.
+    pub fn new(path: &Path, max_idle: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_idle,
+            idle: Mutex::new(vec![]),
+            normalize_paths: false,
+            busy_timeout: BUSY_TIMEOUT,
+        }
+    }
+
+    /// A read-only store, reusing an idle connection when there is one
+    pub fn get(&self) -> Result<PooledReader<'_>> {
+        let idle = self.idle.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self.connect()?,
+        };
+        Ok(PooledReader {
+            pool: self,
+            store: Some(MetadataStore {
+                conn,
+                path: self.path.clone(),
+                normalize_paths: self.normalize_paths,
+                scratch: None,
+            }),
+        })
+    }
+
+    /// Connections currently idle in the pool
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        // The writer owns the schema: readers never migrate
+        let conn = Connection::open_with_flags(
+            &self.path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(self.busy_timeout)?;
+        Ok(conn)
+    }
+}
+
+impl Deref for PooledReader<'_> {
+    type Target = MetadataStore;
+
+    fn deref(&self) -> &MetadataStore {
+        self.store.as_ref().expect("reader is only taken on drop")
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        let Some(store) = self.store.take() else {
+            return;
+        };
+        let mut idle = self.pool.idle.lock().unwrap();
+        // A reader left inside a transaction would pin an old snapshot
+        if idle.len() < self.pool.max_idle && store.conn.is_autocommit() {
+            idle.push(store.conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::SourceType;
+    use std::thread;
+
+    #[test]
+    fn test_readers_run_beside_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        writer
+            .ensure_probe_source("a:One", None, "One", SourceType::Multi, None, "active")
+            .unwrap();
+        let pool = writer.read_pool(2).unwrap();
+        let sources = |pool: &ReadPool| pool.get().unwrap().probe_stats().unwrap().len();
+
+        // An open write transaction neither blocks readers nor shows them its rows
+        writer
+            .transaction(|| {
+                writer.ensure_probe_source(
+                    "b:Two",
+                    None,
+                    "Two",
+                    SourceType::Multi,
+                    None,
+                    "active",
+                )?;
+                thread::scope(|scope| {
+                    let readers: Vec<_> = (0..3).map(|_| scope.spawn(|| sources(&pool))).collect();
+                    for reader in readers {
+                        assert_eq!(reader.join().unwrap(), 1);
+                    }
+                });
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(sources(&pool), 2);
+        assert!(pool.idle() <= 2);
+
+        let reader = pool.get().unwrap();
+        assert!(reader.update_probe_indexed("a:One").is_err());
+    }
+
+    #[test]
+    fn test_pool_keeps_journal_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = crate::config::DatabaseConfig {
+            journal_mode: "delete".to_string(),
+            ..Default::default()
+        };
+        let writer = MetadataStore::open_with(&dir.path().join("test.db"), &database).unwrap();
+        let pool = writer.read_pool(1).unwrap();
+        assert!(pool.get().unwrap().probe_stats().unwrap().is_empty());
+        let mode: String = writer
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "delete");
+    }
+}
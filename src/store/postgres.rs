@@ -0,0 +1,1321 @@
+//! Postgres storage backend (experimental, `--features postgres`)
+//!
+//! For a team server that several machines run `extract` or `watch` against at once:
+//! Postgres takes concurrent writers where SQLite allows one. It implements only the
+//! ingest path ([`StorageBackend`]); reports and the other query commands still read a
+//! SQLite database.
+//!
+//! The schema mirrors the SQLite tables ingestion writes, with native timestamps. It is
+//! created on connect and has no migrations yet, so an upgraded Chronicle may need a
+//! fresh database. Local days (usage rollups, alerts) use the client's UTC offset at
+//! connect time.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use postgres::{Client, NoTls};
+use std::cell::{Cell, RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+
+use super::{message_key, paths, ProjectRow, StorageBackend, UsageRow, Visibility};
+use crate::analysis::{tools, DailyUsage, IssueReference, TokenCounts, ToolLoop};
+use crate::probe::{
+    CommitRef, MessageMetadata, ProbeCapabilities, SessionMetadata, SessionRef, SkipCounts,
+    SourceFingerprint, SourceType,
+};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS providers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS probe_sources (
+    id TEXT PRIMARY KEY,
+    provider_id TEXT REFERENCES providers(id),
+    source_name TEXT NOT NULL,
+    source_type TEXT DEFAULT 'single',
+    base_path TEXT,
+    status TEXT DEFAULT 'active',
+    last_indexed TIMESTAMPTZ,
+    capabilities TEXT
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    type TEXT DEFAULT 'code',
+    primary_path TEXT,
+    metadata TEXT,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    last_activity TIMESTAMPTZ,
+    visibility TEXT DEFAULT 'shareable'
+);
+
+CREATE TABLE IF NOT EXISTS project_paths (
+    id BIGSERIAL PRIMARY KEY,
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    path TEXT NOT NULL UNIQUE,
+    is_primary BOOLEAN DEFAULT FALSE,
+    added_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS project_identifiers (
+    id BIGSERIAL PRIMARY KEY,
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    identifier_type TEXT NOT NULL,
+    identifier_value TEXT NOT NULL,
+    UNIQUE(identifier_type, identifier_value)
+);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    probe_source_id TEXT NOT NULL REFERENCES probe_sources(id),
+    project_id TEXT REFERENCES projects(id) ON DELETE SET NULL,
+    project_assignment TEXT DEFAULT 'auto',
+    external_id TEXT,
+    short_hash TEXT NOT NULL,
+    title TEXT,
+    primary_provider TEXT,
+    primary_model TEXT,
+    message_count BIGINT DEFAULT 0,
+    first_timestamp TIMESTAMPTZ,
+    last_timestamp TIMESTAMPTZ,
+    source_path TEXT NOT NULL,
+    raw_project_path TEXT,
+    raw_git_remote TEXT,
+    source_group TEXT,
+    language TEXT,
+    compactions BIGINT DEFAULT 0,
+    source_mtime BIGINT,
+    source_size BIGINT,
+    orphaned_at TIMESTAMPTZ,
+    duration_secs BIGINT,
+    active_secs BIGINT,
+    user_messages BIGINT DEFAULT 0,
+    assistant_messages BIGINT DEFAULT 0,
+    tool_calls BIGINT DEFAULT 0,
+    indexed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    uuid TEXT,
+    message_key TEXT,
+    role TEXT NOT NULL,
+    provider_id TEXT,
+    model TEXT,
+    timestamp TIMESTAMPTZ,
+    source_path TEXT NOT NULL,
+    byte_offset BIGINT,
+    line_number BIGINT,
+    content_ref TEXT,
+    has_tool_use BOOLEAN DEFAULT FALSE,
+    has_thinking BOOLEAN DEFAULT FALSE,
+    subtype TEXT
+);
+
+CREATE TABLE IF NOT EXISTS tool_uses (
+    id BIGSERIAL PRIMARY KEY,
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    tool_id TEXT,
+    tool_name TEXT NOT NULL,
+    origin TEXT DEFAULT 'builtin',
+    mcp_server TEXT,
+    has_result BOOLEAN DEFAULT FALSE,
+    is_error BOOLEAN DEFAULT FALSE,
+    permission TEXT,
+    input_hash TEXT,
+    file_path TEXT
+);
+
+CREATE TABLE IF NOT EXISTS token_usage (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    input_tokens BIGINT,
+    output_tokens BIGINT,
+    cache_read_tokens BIGINT,
+    cache_creation_tokens BIGINT,
+    reasoning_tokens BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS request_params (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    temperature DOUBLE PRECISION,
+    max_tokens BIGINT,
+    system_prompt_hash TEXT
+);
+
+CREATE TABLE IF NOT EXISTS message_content (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    data BYTEA NOT NULL,
+    size BIGINT NOT NULL,
+    cached_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS system_prompts (
+    hash TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    chars BIGINT NOT NULL,
+    first_seen TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS session_references (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    ref_type TEXT NOT NULL,
+    ref_key TEXT NOT NULL,
+    url TEXT,
+    UNIQUE(session_id, ref_type, ref_key)
+);
+
+CREATE TABLE IF NOT EXISTS session_commits (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    commit_hash TEXT NOT NULL,
+    message TEXT,
+    UNIQUE(session_id, commit_hash)
+);
+
+CREATE TABLE IF NOT EXISTS anomalies (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    tool_name TEXT,
+    repeat_count BIGINT,
+    message_index BIGINT,
+    detected_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS session_skips (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    count BIGINT NOT NULL,
+    UNIQUE(session_id, reason)
+);
+
+CREATE TABLE IF NOT EXISTS parse_errors (
+    id BIGSERIAL PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    location TEXT NOT NULL,
+    recorded_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS usage_alerts (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    day TEXT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    baseline DOUBLE PRECISION,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    UNIQUE(kind, day)
+);
+
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    day TEXT NOT NULL,
+    probe_source_id TEXT NOT NULL,
+    model TEXT NOT NULL DEFAULT '',
+    messages BIGINT NOT NULL DEFAULT 0,
+    input_tokens BIGINT NOT NULL DEFAULT 0,
+    output_tokens BIGINT NOT NULL DEFAULT 0,
+    cache_read_tokens BIGINT NOT NULL DEFAULT 0,
+    cache_creation_tokens BIGINT NOT NULL DEFAULT 0,
+    refreshed_at TIMESTAMPTZ DEFAULT now(),
+    PRIMARY KEY(day, probe_source_id, model)
+);
+
+CREATE TABLE IF NOT EXISTS session_models (
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    messages BIGINT NOT NULL DEFAULT 0,
+    input_tokens BIGINT NOT NULL DEFAULT 0,
+    output_tokens BIGINT NOT NULL DEFAULT 0,
+    cache_read_tokens BIGINT NOT NULL DEFAULT 0,
+    cache_creation_tokens BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY(session_id, model)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_project ON sessions(project_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_short_hash ON sessions(short_hash);
+CREATE INDEX IF NOT EXISTS idx_messages_session ON messages(session_id);
+CREATE INDEX IF NOT EXISTS idx_messages_timestamp ON messages(timestamp);
+CREATE INDEX IF NOT EXISTS idx_tool_uses_message ON tool_uses(message_id);
+CREATE INDEX IF NOT EXISTS idx_project_ids_value ON project_identifiers(identifier_value);
+"#;
+
+/// Advisory lock keys, so concurrent clients don't race on schema setup or short hashes
+const SCHEMA_LOCK: i64 = 0x6368_726f_6e00;
+const SHORT_HASH_LOCK: i64 = 0x6368_726f_6e01;
+
+pub struct PostgresStore {
+    client: RefCell<Client>,
+    /// Open transactions: the outermost is BEGIN/COMMIT, nested ones savepoints
+    depth: Cell<u32>,
+    /// Match project paths after normalization and by ancestor (`linking.normalize_paths`)
+    normalize_paths: bool,
+}
+
+impl PostgresStore {
+    /// Connect to `url` (`postgres://user@host/db`), creating the schema if needed
+    pub fn connect(url: &str) -> Result<Self> {
+        let mut client = Client::connect(url, NoTls)
+            .with_context(|| format!("Failed to connect to {}", redact(url)))?;
+        // Local days are bucketed by the session time zone
+        let offset = Local::now().offset().to_string();
+        client.batch_execute(&format!(
+            "SET TIME ZONE INTERVAL '{}' HOUR TO MINUTE",
+            offset
+        ))?;
+        let mut tx = client.transaction()?;
+        tx.execute("SELECT pg_advisory_xact_lock($1)", &[&SCHEMA_LOCK])?;
+        tx.batch_execute(SCHEMA)?;
+        tx.commit()?;
+        Ok(Self {
+            client: RefCell::new(client),
+            depth: Cell::new(0),
+            normalize_paths: false,
+        })
+    }
+
+    /// Compare project paths normalized and link sessions in subdirectories of a project
+    /// path, per `linking.normalize_paths`
+    pub fn with_path_normalization(mut self, enabled: bool) -> Self {
+        self.normalize_paths = enabled;
+        self
+    }
+
+    fn client(&self) -> RefMut<'_, Client> {
+        self.client.borrow_mut()
+    }
+
+    fn compute_short_hash(&self, external_id: &str) -> Result<String> {
+        let base = external_id
+            .strip_prefix("agent-")
+            .or_else(|| external_id.strip_prefix("ses_"))
+            .unwrap_or(external_id);
+        let base_hash = if base.len() >= 8 { &base[..8] } else { base };
+
+        let taken: Vec<String> = self
+            .client()
+            .query(
+                "SELECT short_hash FROM sessions WHERE short_hash = $1 OR short_hash LIKE $2",
+                &[&base_hash, &format!("{}-%", base_hash)],
+            )?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        let suffixes: Vec<i64> = taken
+            .iter()
+            .filter_map(|hash| {
+                hash.strip_prefix(base_hash)?
+                    .strip_prefix('-')?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+        let unsuffixed = taken.iter().any(|hash| hash == base_hash);
+        if !unsuffixed && suffixes.is_empty() {
+            return Ok(base_hash.to_string());
+        }
+
+        // The first duplicate renames the original to `-1`
+        if unsuffixed && suffixes.is_empty() {
+            self.client().execute(
+                "UPDATE sessions SET short_hash = $1 WHERE short_hash = $2",
+                &[&format!("{}-1", base_hash), &base_hash],
+            )?;
+        }
+        let next = suffixes.into_iter().max().unwrap_or(1) + 1;
+        Ok(format!("{}-{}", base_hash, next))
+    }
+
+    fn find_project_by_path(&self, path: &str) -> Result<Option<String>> {
+        let exact = self
+            .client()
+            .query_opt(
+                "SELECT project_id FROM project_paths WHERE path = $1",
+                &[&path],
+            )?
+            .map(|row| row.get(0));
+        if exact.is_some() || !self.normalize_paths {
+            return Ok(exact);
+        }
+        let normalized = paths::normalize_path(path);
+        let (registered, owners) = self.normalized_project_paths()?;
+        Ok(registered
+            .iter()
+            .position(|p| *p == normalized)
+            .map(|i| owners[i].clone()))
+    }
+
+    fn normalized_project_paths(&self) -> Result<(Vec<String>, Vec<String>)> {
+        let rows = self.client().query(
+            "SELECT path, project_id FROM project_paths ORDER BY is_primary DESC",
+            &[],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                (
+                    paths::normalize_path(row.get::<_, &str>(0)),
+                    row.get::<_, String>(1),
+                )
+            })
+            .unzip())
+    }
+
+    fn touch_project(&self, project_id: &str) -> Result<()> {
+        self.client().execute(
+            "UPDATE projects SET last_activity = now() WHERE id = $1",
+            &[&project_id],
+        )?;
+        Ok(())
+    }
+
+    fn write_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<Vec<i64>> {
+        let existing: HashMap<String, i64> = self
+            .client()
+            .query(
+                "SELECT message_key, id FROM messages
+                 WHERE session_id = $1 AND message_key IS NOT NULL",
+                &[&session_id],
+            )?
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+
+        let mut client = self.client();
+        let mut seen_keys: HashMap<String, usize> = HashMap::new();
+        let mut kept_ids = vec![];
+        for (idx, msg) in messages.iter().enumerate() {
+            // Disambiguate repeated keys by occurrence
+            let base_key = message_key(msg, idx);
+            let occurrence = seen_keys.entry(base_key.clone()).or_insert(0);
+            let key = if *occurrence == 0 {
+                base_key
+            } else {
+                format!("{}#{}", base_key, occurrence)
+            };
+            *occurrence += 1;
+
+            let content_ref = msg
+                .content_ref
+                .content_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string());
+            let source_path = msg.content_ref.source_path.to_string_lossy().to_string();
+            let byte_offset = msg.content_ref.byte_offset.map(|o| o as i64);
+            let line_number = msg.content_ref.line_number.map(i64::from);
+            let values: [&(dyn postgres::types::ToSql + Sync); 14] = [
+                &session_id,
+                &msg.uuid,
+                &key,
+                &msg.role,
+                &msg.provider_id,
+                &msg.model,
+                &msg.timestamp,
+                &source_path,
+                &byte_offset,
+                &line_number,
+                &content_ref,
+                &msg.has_tool_use,
+                &msg.has_thinking,
+                &msg.subtype,
+            ];
+
+            let msg_id: i64 = match existing.get(&key) {
+                Some(&id) => {
+                    let mut params = values.to_vec();
+                    params.push(&id);
+                    client.execute(
+                        "UPDATE messages SET
+                         session_id = $1, uuid = $2, message_key = $3, role = $4,
+                         provider_id = $5, model = $6, timestamp = $7, source_path = $8,
+                         byte_offset = $9, line_number = $10, content_ref = $11,
+                         has_tool_use = $12, has_thinking = $13, subtype = $14
+                         WHERE id = $15",
+                        &params,
+                    )?;
+                    for table in ["tool_uses", "token_usage", "request_params"] {
+                        client.execute(
+                            &format!("DELETE FROM {} WHERE message_id = $1", table),
+                            &[&id],
+                        )?;
+                    }
+                    id
+                }
+                None => client
+                    .query_one(
+                        "INSERT INTO messages
+                         (session_id, uuid, message_key, role, provider_id, model, timestamp,
+                          source_path, byte_offset, line_number, content_ref, has_tool_use,
+                          has_thinking, subtype)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                         RETURNING id",
+                        &values,
+                    )?
+                    .get(0),
+            };
+            kept_ids.push(msg_id);
+
+            for tool in &msg.tool_uses {
+                client.execute(
+                    "INSERT INTO tool_uses
+                     (message_id, tool_id, tool_name, origin, mcp_server, has_result,
+                      is_error, permission, input_hash, file_path)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                    &[
+                        &msg_id,
+                        &tool.tool_id,
+                        &tool.tool_name,
+                        &tools::origin(&tool.tool_name),
+                        &tools::mcp_server(&tool.tool_name),
+                        &tool.has_result,
+                        &tool.is_error,
+                        &tool.permission.map(|p| p.as_str()),
+                        &tool.input_hash,
+                        &tool.file_path,
+                    ],
+                )?;
+            }
+
+            if let Some(usage) = &msg.token_usage {
+                client.execute(
+                    "INSERT INTO token_usage
+                     (message_id, input_tokens, output_tokens, cache_read_tokens,
+                      cache_creation_tokens, reasoning_tokens)
+                     VALUES ($1, $2, $3, $4, $5, $6)",
+                    &[
+                        &msg_id,
+                        &usage.input_tokens,
+                        &usage.output_tokens,
+                        &usage.cache_read_tokens,
+                        &usage.cache_creation_tokens,
+                        &usage.reasoning_tokens,
+                    ],
+                )?;
+            }
+
+            if let Some(request) = &msg.request_params {
+                let prompt_hash = request.system_prompt_hash();
+                if let (Some(hash), Some(prompt)) = (&prompt_hash, &request.system_prompt) {
+                    client.execute(
+                        "INSERT INTO system_prompts (hash, content, chars) VALUES ($1, $2, $3)
+                         ON CONFLICT DO NOTHING",
+                        &[hash, prompt, &(prompt.chars().count() as i64)],
+                    )?;
+                }
+                client.execute(
+                    "INSERT INTO request_params
+                     (message_id, temperature, max_tokens, system_prompt_hash)
+                     VALUES ($1, $2, $3, $4)",
+                    &[
+                        &msg_id,
+                        &request.temperature,
+                        &request.max_tokens,
+                        &prompt_hash,
+                    ],
+                )?;
+            }
+        }
+
+        // Remove messages that disappeared from the source (dependent rows cascade)
+        let kept: HashSet<i64> = kept_ids.iter().copied().collect();
+        let stale: Vec<i64> = client
+            .query(
+                "SELECT id FROM messages WHERE session_id = $1",
+                &[&session_id],
+            )?
+            .iter()
+            .map(|row| row.get(0))
+            .filter(|id| !kept.contains(id))
+            .collect();
+        client.execute("DELETE FROM messages WHERE id = ANY($1)", &[&stale])?;
+        Ok(kept_ids)
+    }
+}
+
+impl StorageBackend for PostgresStore {
+    fn transaction(&self, f: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        let depth = self.depth.get();
+        let (begin, commit, rollback) = match depth {
+            0 => ("BEGIN", "COMMIT", "ROLLBACK"),
+            _ => (
+                "SAVEPOINT chronicle_tx",
+                "RELEASE chronicle_tx",
+                "ROLLBACK TO chronicle_tx; RELEASE chronicle_tx",
+            ),
+        };
+        self.client().batch_execute(begin)?;
+        self.depth.set(depth + 1);
+        let result = f();
+        self.depth.set(depth);
+        match result {
+            Ok(()) => Ok(self.client().batch_execute(commit)?),
+            Err(e) => {
+                // The original error matters more than a failed rollback
+                let _ = self.client().batch_execute(rollback);
+                Err(e)
+            }
+        }
+    }
+
+    fn ensure_provider(&self, id: &str, name: &str, description: Option<&str>) -> Result<()> {
+        self.client().execute(
+            "INSERT INTO providers (id, name, description) VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING",
+            &[&id, &name, &description],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_probe_source(
+        &self,
+        id: &str,
+        provider_id: Option<&str>,
+        source_name: &str,
+        source_type: SourceType,
+        base_path: Option<&str>,
+        status: &str,
+    ) -> Result<()> {
+        self.client().execute(
+            "INSERT INTO probe_sources (id, provider_id, source_name, source_type, base_path, status)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT DO NOTHING",
+            &[
+                &id,
+                &provider_id,
+                &source_name,
+                &source_type.as_str(),
+                &base_path,
+                &status,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn update_probe_indexed(&self, probe_id: &str) -> Result<()> {
+        self.client().execute(
+            "UPDATE probe_sources SET last_indexed = now() WHERE id = $1",
+            &[&probe_id],
+        )?;
+        Ok(())
+    }
+
+    fn set_probe_capabilities(
+        &self,
+        probe_id: &str,
+        capabilities: &ProbeCapabilities,
+    ) -> Result<()> {
+        self.client().execute(
+            "UPDATE probe_sources SET capabilities = $1 WHERE id = $2",
+            &[&serde_json::to_string(capabilities)?, &probe_id],
+        )?;
+        Ok(())
+    }
+
+    fn upsert_session(
+        &self,
+        probe_source_id: &str,
+        session: &SessionRef,
+        metadata: &SessionMetadata,
+    ) -> Result<String> {
+        let session_id = format!("{}:{}", probe_source_id, session.id);
+
+        let existing: Option<String> = self
+            .client()
+            .query_opt(
+                "SELECT short_hash FROM sessions WHERE id = $1",
+                &[&session_id],
+            )?
+            .map(|row| row.get(0));
+        let short_hash = match existing {
+            Some(hash) => hash,
+            None => {
+                // Held until the session's transaction ends, so two clients can't pick
+                // the same hash
+                self.client()
+                    .execute("SELECT pg_advisory_xact_lock($1)", &[&SHORT_HASH_LOCK])?;
+                self.compute_short_hash(&metadata.external_id)?
+            }
+        };
+
+        let mut project_id = None;
+        if let Some(ref path) = metadata.project_path {
+            project_id = self.project_for_path(path)?;
+        }
+        if project_id.is_none() {
+            if let Some(ref remote) = metadata.git_remote {
+                project_id = self.find_project_by_git_remote(remote)?;
+            }
+        }
+
+        self.client().execute(
+            "INSERT INTO sessions
+             (id, probe_source_id, project_id, project_assignment, external_id, short_hash,
+              title, primary_provider, primary_model, message_count, first_timestamp,
+              last_timestamp, source_path, raw_project_path, raw_git_remote, source_group,
+              language, compactions, indexed_at)
+             VALUES ($1, $2, $3, 'auto', $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15,
+                     $16, $17, now())
+             ON CONFLICT (id) DO UPDATE SET
+                 title = excluded.title,
+                 source_group = excluded.source_group,
+                 language = excluded.language,
+                 compactions = excluded.compactions,
+                 primary_provider = excluded.primary_provider,
+                 primary_model = excluded.primary_model,
+                 message_count = excluded.message_count,
+                 last_timestamp = excluded.last_timestamp,
+                 orphaned_at = NULL,
+                 indexed_at = now()",
+            &[
+                &session_id,
+                &probe_source_id,
+                &project_id,
+                &metadata.external_id,
+                &short_hash,
+                &metadata.title,
+                &metadata.primary_provider,
+                &metadata.primary_model,
+                &(metadata.messages.len() as i64),
+                &metadata.first_timestamp,
+                &metadata.last_timestamp,
+                &session.source_path.to_string_lossy().to_string(),
+                &metadata.project_path,
+                &metadata.git_remote,
+                &metadata.source_group,
+                &metadata.language,
+                &(metadata.compactions as i64),
+            ],
+        )?;
+
+        if let Some(ref pid) = project_id {
+            self.touch_project(pid)?;
+        }
+        Ok(session_id)
+    }
+
+    fn has_session(&self, session_id: &str) -> Result<bool> {
+        Ok(self
+            .client()
+            .query_one(
+                "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = $1)",
+                &[&session_id],
+            )?
+            .get(0))
+    }
+
+    fn session_origin(&self, query: &str) -> Result<Option<(String, String)>> {
+        let row = self.client().query_opt(
+            "SELECT probe_source_id, external_id FROM sessions
+             WHERE short_hash = $1 OR short_hash LIKE $2 OR id LIKE $2 OR external_id LIKE $2
+             ORDER BY CASE WHEN short_hash = $1 THEN 0
+                           WHEN external_id = $1 THEN 1
+                           ELSE 2 END
+             LIMIT 1",
+            &[&query, &format!("{}%", query)],
+        )?;
+        Ok(row.map(|row| (row.get(0), row.get(1))))
+    }
+
+    fn get_session_fingerprint(&self, session_id: &str) -> Result<Option<SourceFingerprint>> {
+        let row = self.client().query_opt(
+            "SELECT source_mtime, source_size FROM sessions WHERE id = $1",
+            &[&session_id],
+        )?;
+        Ok(row.and_then(
+            |row| match (row.get::<_, Option<i64>>(0), row.get::<_, Option<i64>>(1)) {
+                (Some(mtime), Some(size)) => Some(SourceFingerprint {
+                    mtime,
+                    size: size as u64,
+                }),
+                _ => None,
+            },
+        ))
+    }
+
+    fn set_session_fingerprint(
+        &self,
+        session_id: &str,
+        fingerprint: &SourceFingerprint,
+    ) -> Result<()> {
+        self.client().execute(
+            "UPDATE sessions SET source_mtime = $1, source_size = $2 WHERE id = $3",
+            &[&fingerprint.mtime, &(fingerprint.size as i64), &session_id],
+        )?;
+        Ok(())
+    }
+
+    fn insert_messages(&self, session_id: &str, messages: &[MessageMetadata]) -> Result<Vec<i64>> {
+        let mut ids = vec![];
+        self.transaction(&mut || {
+            ids = self.write_messages(session_id, messages)?;
+            Ok(())
+        })?;
+        Ok(ids)
+    }
+
+    fn cache_content(&self, contents: &[(i64, String)]) -> Result<()> {
+        for (message_id, raw) in contents {
+            let data = zstd::encode_all(raw.as_bytes(), 3)?;
+            self.client().execute(
+                "INSERT INTO message_content (message_id, data, size) VALUES ($1, $2, $3)
+                 ON CONFLICT (message_id) DO UPDATE SET
+                     data = excluded.data, size = excluded.size, cached_at = now()",
+                &[message_id, &data, &(raw.len() as i64)],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_session_references(
+        &self,
+        session_id: &str,
+        references: &[IssueReference],
+    ) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "DELETE FROM session_references WHERE session_id = $1",
+            &[&session_id],
+        )?;
+        for reference in references {
+            client.execute(
+                "INSERT INTO session_references (session_id, ref_type, ref_key, url)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT DO NOTHING",
+                &[
+                    &session_id,
+                    &reference.kind.as_str(),
+                    &reference.key,
+                    &reference.url,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_session_commits(&self, session_id: &str, commits: &[CommitRef]) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "DELETE FROM session_commits WHERE session_id = $1",
+            &[&session_id],
+        )?;
+        for commit in commits {
+            client.execute(
+                "INSERT INTO session_commits (session_id, commit_hash, message)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT DO NOTHING",
+                &[&session_id, &commit.hash, &commit.message],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_tool_loops(&self, session_id: &str, loops: &[ToolLoop]) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "DELETE FROM anomalies WHERE session_id = $1 AND kind = 'tool_loop'",
+            &[&session_id],
+        )?;
+        for tool_loop in loops {
+            client.execute(
+                "INSERT INTO anomalies (session_id, kind, tool_name, repeat_count, message_index)
+                 VALUES ($1, 'tool_loop', $2, $3, $4)",
+                &[
+                    &session_id,
+                    &tool_loop.tool_name,
+                    &(tool_loop.repeats as i64),
+                    &(tool_loop.first_message as i64),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_session_skips(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "DELETE FROM session_skips WHERE session_id = $1",
+            &[&session_id],
+        )?;
+        for (reason, count) in skipped.iter() {
+            client.execute(
+                "INSERT INTO session_skips (session_id, reason, count) VALUES ($1, $2, $3)",
+                &[&session_id, &reason, &(count as i64)],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_parse_errors(&self, session_id: &str, skipped: &SkipCounts) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "DELETE FROM parse_errors WHERE session_id = $1",
+            &[&session_id],
+        )?;
+        for record in skipped.records() {
+            client.execute(
+                "INSERT INTO parse_errors (session_id, reason, location) VALUES ($1, $2, $3)",
+                &[&session_id, &record.reason, &record.location],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn create_project(
+        &self,
+        id: &str,
+        name: &str,
+        project_type: &str,
+        primary_path: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<()> {
+        let mut client = self.client();
+        client.execute(
+            "INSERT INTO projects (id, name, type, primary_path, metadata, last_activity)
+             VALUES ($1, $2, $3, $4, $5, now())",
+            &[&id, &name, &project_type, &primary_path, &metadata],
+        )?;
+        if let Some(path) = primary_path {
+            client.execute(
+                "INSERT INTO project_paths (project_id, path, is_primary) VALUES ($1, $2, TRUE)
+                 ON CONFLICT DO NOTHING",
+                &[&id, &path],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn find_project(&self, query: &str) -> Result<Option<ProjectRow>> {
+        Ok(self
+            .list_projects()?
+            .into_iter()
+            .find(|p| p.id.starts_with(query) || p.name == query))
+    }
+
+    fn project_for_path(&self, path: &str) -> Result<Option<String>> {
+        if !self.normalize_paths {
+            return self.find_project_by_path(path);
+        }
+        let (registered, owners) = self.normalized_project_paths()?;
+        Ok(
+            paths::nearest_registered(&paths::normalize_path(path), &registered)
+                .map(|i| owners[i].clone()),
+        )
+    }
+
+    fn find_project_by_git_remote(&self, remote: &str) -> Result<Option<String>> {
+        let row = self.client().query_opt(
+            "SELECT project_id FROM project_identifiers
+             WHERE identifier_type = 'git_remote' AND identifier_value = $1",
+            &[&remote],
+        )?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    fn add_project_identifier(
+        &self,
+        project_id: &str,
+        identifier_type: &str,
+        identifier_value: &str,
+    ) -> Result<()> {
+        self.client().execute(
+            "INSERT INTO project_identifiers (project_id, identifier_type, identifier_value)
+             VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING",
+            &[&project_id, &identifier_type, &identifier_value],
+        )?;
+        Ok(())
+    }
+
+    fn list_projects(&self) -> Result<Vec<ProjectRow>> {
+        // Counters are computed here; there are no triggers caching them
+        let rows = self.client().query(
+            "SELECT p.id, p.name, p.type, p.primary_path, p.metadata,
+                    to_char(p.created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+                    to_char(p.last_activity AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+                    (SELECT COUNT(*) FROM sessions s WHERE s.project_id = p.id),
+                    (SELECT COALESCE(SUM(s.message_count), 0)::BIGINT FROM sessions s
+                     WHERE s.project_id = p.id),
+                    (SELECT MAX(s.last_timestamp) FROM sessions s WHERE s.project_id = p.id),
+                    p.visibility
+             FROM projects p
+             ORDER BY p.last_activity DESC NULLS LAST",
+            &[],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| ProjectRow {
+                id: row.get(0),
+                name: row.get(1),
+                project_type: row.get::<_, Option<String>>(2).unwrap_or_default(),
+                primary_path: row.get(3),
+                metadata: row.get(4),
+                created_at: row.get(5),
+                last_activity: row.get(6),
+                session_count: row.get(7),
+                message_count: row.get(8),
+                last_session_at: row
+                    .get::<_, Option<DateTime<Utc>>>(9)
+                    .map(|t| t.to_rfc3339()),
+                // Unknown levels are treated as the most restrictive
+                visibility: row
+                    .get::<_, Option<String>>(10)
+                    .map_or(Visibility::Shareable, |v| {
+                        Visibility::parse(&v).unwrap_or(Visibility::Private)
+                    }),
+            })
+            .collect())
+    }
+
+    fn get_project_paths(&self, project_id: &str) -> Result<Vec<String>> {
+        let rows = self.client().query(
+            "SELECT path FROM project_paths WHERE project_id = $1 ORDER BY is_primary DESC, path",
+            &[&project_id],
+        )?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    fn link_session_auto(&self, session_id: &str, project_id: &str) -> Result<bool> {
+        let changed = self.client().execute(
+            "UPDATE sessions SET project_id = $1
+             WHERE id = $2 AND project_assignment = 'auto'
+               AND project_id IS DISTINCT FROM $1",
+            &[&project_id, &session_id],
+        )?;
+        if changed > 0 {
+            self.touch_project(project_id)?;
+        }
+        Ok(changed > 0)
+    }
+
+    fn merge_project_metadata(
+        &self,
+        project_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<()> {
+        let existing: Option<String> = self
+            .client()
+            .query_one(
+                "SELECT metadata FROM projects WHERE id = $1",
+                &[&project_id],
+            )?
+            .get(0);
+        let mut metadata = existing
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+            .filter(|m| m.is_object())
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata[key] = value;
+        self.client().execute(
+            "UPDATE projects SET metadata = $1 WHERE id = $2",
+            &[&metadata.to_string(), &project_id],
+        )?;
+        Ok(())
+    }
+
+    fn refresh_session_metrics(&self, session_id: &str) -> Result<()> {
+        let mut client = self.client();
+        // A gap of more than 5 minutes between consecutive messages counts as idle
+        client.execute(
+            "WITH gaps AS (
+                 SELECT EXTRACT(EPOCH FROM timestamp - LAG(timestamp)
+                                OVER (ORDER BY timestamp, id)) AS gap
+                 FROM messages
+                 WHERE session_id = $1 AND timestamp IS NOT NULL
+             )
+             UPDATE sessions SET
+                 duration_secs = ROUND(EXTRACT(EPOCH FROM last_timestamp - first_timestamp))::BIGINT,
+                 active_secs = CASE WHEN first_timestamp IS NOT NULL THEN
+                     (SELECT ROUND(COALESCE(SUM(gap), 0))::BIGINT FROM gaps WHERE gap <= 300) END,
+                 user_messages = (SELECT COUNT(*) FROM messages m
+                                  WHERE m.session_id = sessions.id AND m.role = 'user'),
+                 assistant_messages = (SELECT COUNT(*) FROM messages m
+                                       WHERE m.session_id = sessions.id AND m.role = 'assistant'),
+                 tool_calls = (SELECT COUNT(*) FROM tool_uses t
+                               JOIN messages m ON m.id = t.message_id
+                               WHERE m.session_id = sessions.id)
+             WHERE id = $1",
+            &[&session_id],
+        )?;
+        client.execute(
+            "DELETE FROM session_models WHERE session_id = $1",
+            &[&session_id],
+        )?;
+        client.execute(
+            "INSERT INTO session_models
+             (session_id, model, messages, input_tokens, output_tokens, cache_read_tokens,
+              cache_creation_tokens)
+             SELECT m.session_id, m.model, COUNT(*), SUM(COALESCE(t.input_tokens, 0)),
+                    SUM(COALESCE(t.output_tokens, 0)), SUM(COALESCE(t.cache_read_tokens, 0)),
+                    SUM(COALESCE(t.cache_creation_tokens, 0))
+             FROM messages m
+             LEFT JOIN token_usage t ON t.message_id = m.id
+             WHERE m.session_id = $1 AND m.model IS NOT NULL AND m.model != ''
+             GROUP BY m.session_id, m.model",
+            &[&session_id],
+        )?;
+        Ok(())
+    }
+
+    fn daily_token_usage(&self, since_day: &str) -> Result<Vec<DailyUsage>> {
+        let rows = self.client().query(
+            "SELECT to_char(m.timestamp, 'YYYY-MM-DD') AS day,
+                    SUM(COALESCE(t.input_tokens, 0) + COALESCE(t.output_tokens, 0)
+                        + COALESCE(t.cache_read_tokens, 0)
+                        + COALESCE(t.cache_creation_tokens, 0))::BIGINT
+             FROM token_usage t
+             JOIN messages m ON m.id = t.message_id
+             WHERE m.timestamp >= $1::TEXT::DATE
+             GROUP BY day
+             ORDER BY day",
+            &[&since_day],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| DailyUsage {
+                day: row.get(0),
+                tokens: row.get(1),
+            })
+            .collect())
+    }
+
+    fn token_usage(&self, since: Option<&str>) -> Result<Vec<UsageRow>> {
+        let rows = self.client().query(
+            "SELECT s.id, s.short_hash, s.probe_source_id, p.name, m.model,
+                    to_char(m.timestamp AT TIME ZONE 'UTC', 'YYYY-MM-DD') AS day,
+                    SUM(COALESCE(t.input_tokens, 0))::BIGINT,
+                    SUM(COALESCE(t.output_tokens, 0))::BIGINT,
+                    SUM(COALESCE(t.cache_read_tokens, 0))::BIGINT,
+                    SUM(COALESCE(t.cache_creation_tokens, 0))::BIGINT
+             FROM token_usage t
+             JOIN messages m ON m.id = t.message_id
+             JOIN sessions s ON s.id = m.session_id
+             LEFT JOIN projects p ON p.id = s.project_id
+             WHERE $1::TEXT IS NULL OR m.timestamp >= $1::TEXT::TIMESTAMPTZ
+             GROUP BY s.id, p.name, m.model, day
+             ORDER BY day",
+            &[&since],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| UsageRow {
+                session_id: row.get(0),
+                short_hash: row.get(1),
+                probe_source_id: row.get(2),
+                project_name: row.get(3),
+                model: row.get(4),
+                day: row.get(5),
+                tokens: TokenCounts {
+                    input: row.get(6),
+                    output: row.get(7),
+                    cache_read: row.get(8),
+                    cache_creation: row.get(9),
+                },
+            })
+            .collect())
+    }
+
+    fn refresh_usage_rollups(&self, since_day: Option<&str>) -> Result<()> {
+        self.transaction(&mut || {
+            let mut client = self.client();
+            client.execute(
+                "DELETE FROM usage_rollups WHERE $1::TEXT IS NULL OR day >= $1",
+                &[&since_day],
+            )?;
+            client.execute(
+                "INSERT INTO usage_rollups
+                 (day, probe_source_id, model, messages, input_tokens, output_tokens,
+                  cache_read_tokens, cache_creation_tokens)
+                 SELECT to_char(m.timestamp, 'YYYY-MM-DD'), s.probe_source_id,
+                        COALESCE(m.model, ''), COUNT(*), SUM(COALESCE(t.input_tokens, 0)),
+                        SUM(COALESCE(t.output_tokens, 0)), SUM(COALESCE(t.cache_read_tokens, 0)),
+                        SUM(COALESCE(t.cache_creation_tokens, 0))
+                 FROM messages m
+                 JOIN sessions s ON s.id = m.session_id
+                 LEFT JOIN token_usage t ON t.message_id = m.id
+                 WHERE m.timestamp IS NOT NULL
+                   AND ($1::TEXT IS NULL OR m.timestamp >= $1::TEXT::DATE)
+                 GROUP BY 1, 2, 3",
+                &[&since_day],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn record_alert(
+        &self,
+        kind: &str,
+        day: &str,
+        value: f64,
+        baseline: Option<f64>,
+    ) -> Result<bool> {
+        let inserted = self.client().execute(
+            "INSERT INTO usage_alerts (kind, day, value, baseline) VALUES ($1, $2, $3, $4)
+             ON CONFLICT DO NOTHING",
+            &[&kind, &day, &value, &baseline],
+        )?;
+        Ok(inserted > 0)
+    }
+}
+
+/// Connection URL without its password, for error messages
+fn redact(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme), Some(at)) if at > scheme => match url[scheme + 3..at].split_once(':') {
+            Some((user, _)) => format!("{}{}:***{}", &url[..scheme + 3], user, &url[at..]),
+            None => url.to_string(),
+        },
+        _ => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{ContentRef, TokenUsage};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("postgres://chronicle:hunter2@db/chronicle"),
+            "postgres://chronicle:***@db/chronicle"
+        );
+        assert_eq!(redact("postgres://db/chronicle"), "postgres://db/chronicle");
+    }
+
+    /// Runs against `CHRONICLE_TEST_POSTGRES_URL` (a scratch database it may wipe)
+    #[test]
+    fn test_ingest_round_trip() {
+        let Ok(url) = std::env::var("CHRONICLE_TEST_POSTGRES_URL") else {
+            eprintln!("CHRONICLE_TEST_POSTGRES_URL not set, skipping");
+            return;
+        };
+        let mut client = Client::connect(&url, NoTls).unwrap();
+        client
+            .batch_execute("DROP SCHEMA public CASCADE; CREATE SCHEMA public;")
+            .unwrap();
+        let store = PostgresStore::connect(&url).unwrap();
+
+        store
+            .ensure_probe_source("acme:Acme", None, "Acme", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project("p1", "widgets", "code", Some("/work/widgets"), None)
+            .unwrap();
+        let session = SessionRef {
+            id: "abcdef123".to_string(),
+            source_path: PathBuf::from("/tmp/a.jsonl"),
+        };
+        let at = |secs: i64| DateTime::from_timestamp(1_750_000_000 + secs, 0);
+        let message = |uuid: &str, secs: i64| MessageMetadata {
+            uuid: Some(uuid.to_string()),
+            role: "assistant".to_string(),
+            provider_id: Some("anthropic".to_string()),
+            model: Some("claude-sonnet-4".to_string()),
+            timestamp: at(secs),
+            content_ref: ContentRef::jsonl(session.source_path.clone(), 0, secs as u32),
+            has_tool_use: false,
+            has_thinking: false,
+            tool_uses: vec![],
+            token_usage: Some(TokenUsage {
+                input_tokens: Some(10),
+                output_tokens: Some(5),
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            }),
+            request_params: None,
+            subtype: None,
+        };
+        let mut metadata = SessionMetadata {
+            external_id: "abcdef123".to_string(),
+            title: None,
+            project_path: Some("/work/widgets".to_string()),
+            git_remote: None,
+            source_group: None,
+            primary_provider: None,
+            primary_model: None,
+            first_timestamp: at(0),
+            last_timestamp: at(60),
+            messages: vec![message("a", 0), message("b", 60)],
+            references: vec![],
+            language: None,
+            commits: vec![],
+            compactions: 0,
+            skipped: SkipCounts::default(),
+        };
+
+        let mut ids = vec![];
+        store
+            .transaction(&mut || {
+                let session_id = store.upsert_session("acme:Acme", &session, &metadata)?;
+                ids = store.insert_messages(&session_id, &metadata.messages)?;
+                store.refresh_session_metrics(&session_id)
+            })
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+        assert!(store.has_session("acme:Acme:abcdef123").unwrap());
+        assert_eq!(
+            store.session_origin("abcdef12").unwrap(),
+            Some(("acme:Acme".to_string(), "abcdef123".to_string()))
+        );
+        let projects = store.list_projects().unwrap();
+        assert_eq!(
+            (projects[0].session_count, projects[0].message_count),
+            (1, 2)
+        );
+
+        // Re-extraction keeps the ids of messages still in the source
+        metadata.messages.remove(0);
+        let kept = store
+            .insert_messages("acme:Acme:abcdef123", &metadata.messages)
+            .unwrap();
+        assert_eq!(kept, vec![ids[1]]);
+        let usage = store.token_usage(None).unwrap();
+        assert_eq!((usage.len(), usage[0].tokens.input), (1, 10));
+
+        // A failed transaction leaves nothing behind
+        let failed = store.transaction(&mut || {
+            store.ensure_provider("openai", "openai", None)?;
+            anyhow::bail!("boom")
+        });
+        assert!(failed.is_err());
+        let providers: i64 = store
+            .client()
+            .query_one("SELECT COUNT(*) FROM providers", &[])
+            .unwrap()
+            .get(0);
+        assert_eq!(providers, 0);
+
+        // A second session with the same hash prefix gets suffixes
+        let other = SessionRef {
+            id: "abcdef12-other".to_string(),
+            ..session.clone()
+        };
+        metadata.external_id = "abcdef12-other".to_string();
+        let other_id = store
+            .upsert_session("acme:Acme", &other, &metadata)
+            .unwrap();
+        let hashes: Vec<String> = store
+            .client()
+            .query("SELECT short_hash FROM sessions ORDER BY short_hash", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(hashes, vec!["abcdef12-1", "abcdef12-2"]);
+        assert!(store.has_session(&other_id).unwrap());
+
+        assert!(store
+            .record_alert("token_spike", "2025-06-15", 1.0, None)
+            .unwrap());
+        assert!(!store
+            .record_alert("token_spike", "2025-06-15", 2.0, None)
+            .unwrap());
+    }
+}
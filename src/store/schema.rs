@@ -7,6 +7,16 @@
 //! - Updated messages with provider_id and content_ref
 //! - Updated probe_sources with source_type and status
 //! - Removed artifacts table (Antigravity-specific, now frozen)
+//!
+//! `SCHEMA` is the current layout and creates fresh databases. Existing databases
+//! are upgraded by the ordered [`MIGRATIONS`], tracked in the `schema_version`
+//! table: a schema change that alters existing tables needs a new migration, while
+//! new tables and indexes can go straight into `SCHEMA`.
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+use crate::error::Error;
 
 pub const SCHEMA: &str = r#"
 -- ============================================
@@ -29,6 +39,7 @@ CREATE TABLE IF NOT EXISTS probe_sources (
     base_path TEXT,
     status TEXT DEFAULT 'active',          -- 'active', 'frozen', 'deprecated'
     last_indexed DATETIME,
+    capabilities TEXT,                     -- JSON ProbeCapabilities, written on extraction
     FOREIGN KEY(provider_id) REFERENCES providers(id)
 );
 
@@ -45,7 +56,12 @@ CREATE TABLE IF NOT EXISTS projects (
     primary_path TEXT,                     -- Main directory (nullable for virtual projects)
     metadata TEXT,                         -- JSON: type-specific fields
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    last_activity DATETIME
+    last_activity DATETIME,
+    -- Cached counters, maintained by the session triggers in COUNTER_TRIGGERS
+    session_count INTEGER DEFAULT 0,
+    message_count INTEGER DEFAULT 0,
+    last_session_at DATETIME,               -- Latest session activity
+    visibility TEXT DEFAULT 'shareable'     -- 'private', 'internal', 'shareable'
 );
 
 -- Multiple paths can map to the same project
@@ -78,7 +94,7 @@ CREATE TABLE IF NOT EXISTS sessions (
     id TEXT PRIMARY KEY,
     probe_source_id TEXT NOT NULL,
     project_id TEXT,                       -- NULL = unassigned/pending
-    project_assignment TEXT DEFAULT 'auto', -- 'auto', 'user', 'unassigned'
+    project_assignment TEXT DEFAULT 'auto', -- 'auto', 'user', 'unassigned', 'merged'
     external_id TEXT,                      -- Original ID from source
     short_hash TEXT NOT NULL,              -- 8-char display hash with optional -N suffix
     title TEXT,                            -- Session title/summary
@@ -90,6 +106,21 @@ CREATE TABLE IF NOT EXISTS sessions (
     source_path TEXT NOT NULL,             -- Path to source file/dir
     raw_project_path TEXT,                 -- Original path from source (for linking)
     raw_git_remote TEXT,                   -- Git remote if available
+    source_group TEXT,                     -- Source-native grouping (OpenCode project hash)
+    language TEXT,                         -- ISO 639-1 language of the user's messages
+    compactions INTEGER DEFAULT 0,         -- Context compactions the source recorded
+    parent_session_id TEXT,                -- Set on sessions derived by `session split`
+    split_index INTEGER,                   -- First message position (0-based) of a split
+    merged_into TEXT,                      -- Kept session when resolved as a duplicate
+    archived BOOLEAN DEFAULT FALSE,        -- Hidden from `list` unless --all
+    source_mtime INTEGER,                  -- Source modification time (ms) at last extraction
+    source_size INTEGER,                   -- Source size (bytes) at last extraction
+    orphaned_at DATETIME,                  -- When `prune` found the source gone
+    duration_secs INTEGER,                 -- Wall-clock time from first to last message
+    active_secs INTEGER,                   -- Time between messages, idle gaps left out
+    user_messages INTEGER DEFAULT 0,
+    assistant_messages INTEGER DEFAULT 0,
+    tool_calls INTEGER DEFAULT 0,
     indexed_at DATETIME,
     FOREIGN KEY(probe_source_id) REFERENCES probe_sources(id),
     FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE SET NULL
@@ -104,6 +135,7 @@ CREATE TABLE IF NOT EXISTS messages (
     id INTEGER PRIMARY KEY,
     session_id TEXT NOT NULL,
     uuid TEXT,
+    message_key TEXT,                      -- Stable identity within the session (uuid or position)
     role TEXT NOT NULL,                    -- 'user', 'assistant', 'system', 'tool'
     provider_id TEXT,                      -- 'anthropic', 'openai', 'google', etc.
     model TEXT,                            -- 'claude-opus-4-5', 'gpt-4', etc.
@@ -114,6 +146,7 @@ CREATE TABLE IF NOT EXISTS messages (
     content_ref TEXT,                      -- For JSON file sources (OpenCode part path)
     has_tool_use BOOLEAN DEFAULT FALSE,
     has_thinking BOOLEAN DEFAULT FALSE,
+    subtype TEXT,                          -- Event kind within the role ('compact')
     FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
 );
 
@@ -126,7 +159,13 @@ CREATE TABLE IF NOT EXISTS tool_uses (
     message_id INTEGER NOT NULL,
     tool_id TEXT,
     tool_name TEXT NOT NULL,
+    origin TEXT DEFAULT 'builtin',         -- 'builtin' | 'mcp'
+    mcp_server TEXT,                       -- Server of an MCP tool ('github')
     has_result BOOLEAN DEFAULT FALSE,
+    is_error BOOLEAN DEFAULT FALSE,        -- The call came back with an error result
+    permission TEXT,                       -- 'auto' | 'approved' | 'denied' (NULL = not recorded)
+    input_hash TEXT,                       -- Fingerprint of normalized tool input
+    file_path TEXT,                        -- File the call read or edited, as the tool gave it
     FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
 );
 
@@ -140,9 +179,37 @@ CREATE TABLE IF NOT EXISTS token_usage (
     output_tokens INTEGER,
     cache_read_tokens INTEGER,
     cache_creation_tokens INTEGER,
+    reasoning_tokens INTEGER,              -- thinking, counted within output_tokens
+    FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- Request parameters sources recorded for model calls
+CREATE TABLE IF NOT EXISTS request_params (
+    message_id INTEGER PRIMARY KEY,
+    temperature REAL,
+    max_tokens INTEGER,
+    system_prompt_hash TEXT,               -- system_prompts.hash
+    FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- Compressed copies of raw message content (`extraction.cache_content`), read when
+-- the source file is gone
+CREATE TABLE IF NOT EXISTS message_content (
+    message_id INTEGER PRIMARY KEY,
+    data BLOB NOT NULL,                    -- zstd-compressed raw content
+    size INTEGER NOT NULL,                 -- Uncompressed bytes
+    cached_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
 );
 
+-- Distinct system prompts, stored once however many requests sent them
+CREATE TABLE IF NOT EXISTS system_prompts (
+    hash TEXT PRIMARY KEY,                 -- SHA-256 of the prompt (hex)
+    content TEXT NOT NULL,
+    chars INTEGER NOT NULL,
+    first_seen DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
 -- ============================================
 -- DEDUPLICATION (New in v2)
 -- ============================================
@@ -163,6 +230,167 @@ CREATE TABLE IF NOT EXISTS session_duplicates (
     FOREIGN KEY(session_b) REFERENCES sessions(id) ON DELETE CASCADE
 );
 
+-- ============================================
+-- SESSION REFERENCES
+-- ============================================
+
+-- Issue tracker / PR references detected in session titles and content
+CREATE TABLE IF NOT EXISTS session_references (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    ref_type TEXT NOT NULL,                -- 'github_issue', 'github_pr', 'issue', 'jira'
+    ref_key TEXT NOT NULL,                 -- Normalized: 'owner/repo#12', '#12', 'PROJ-42'
+    url TEXT,
+    UNIQUE(session_id, ref_type, ref_key),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- SESSION COMMITS
+-- ============================================
+
+-- Git commits recorded by sources (e.g. Aider auto-commits)
+CREATE TABLE IF NOT EXISTS session_commits (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    commit_hash TEXT NOT NULL,             -- Abbreviated or full hash as recorded
+    message TEXT,
+    UNIQUE(session_id, commit_hash),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- ANOMALIES
+-- ============================================
+
+-- Heuristically detected problem patterns in sessions
+CREATE TABLE IF NOT EXISTS anomalies (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    kind TEXT NOT NULL,                    -- 'tool_loop'
+    tool_name TEXT,
+    repeat_count INTEGER,
+    message_index INTEGER,                 -- Position of the first affected message
+    detected_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- PARSE STATS
+-- ============================================
+
+-- Source entries a probe dropped while parsing a session, by reason
+CREATE TABLE IF NOT EXISTS session_skips (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    reason TEXT NOT NULL,                  -- e.g. 'invalid JSON line'
+    count INTEGER NOT NULL,
+    UNIQUE(session_id, reason),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Individual unparsed records, written by `extract --strict`
+CREATE TABLE IF NOT EXISTS parse_errors (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    location TEXT NOT NULL,                -- File and line/key of the record
+    recorded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- USAGE ALERTS
+-- ============================================
+
+-- Fired usage alerts, one per kind per day so repeated checks stay quiet
+CREATE TABLE IF NOT EXISTS usage_alerts (
+    id INTEGER PRIMARY KEY,
+    kind TEXT NOT NULL,                    -- 'token_spike'
+    day TEXT NOT NULL,                     -- 'YYYY-MM-DD'
+    value REAL NOT NULL,                   -- Observed value for the day
+    baseline REAL,                         -- Baseline the value was compared against
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(kind, day)
+);
+
+-- Token totals per local calendar day, source and model, so today's usage is read
+-- without scanning messages. Refreshed from the days an extraction touched.
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    day TEXT NOT NULL,                     -- 'YYYY-MM-DD', local time
+    probe_source_id TEXT NOT NULL,
+    model TEXT NOT NULL DEFAULT '',        -- '' = unknown
+    messages INTEGER NOT NULL DEFAULT 0,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    refreshed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(day, probe_source_id, model)
+);
+
+-- Messages and tokens per model within a session, for sources that switch models
+-- mid-session. Rebuilt from the session's messages at extraction.
+CREATE TABLE IF NOT EXISTS session_models (
+    session_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    messages INTEGER NOT NULL DEFAULT 0,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY(session_id, model),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- PROJECT SNAPSHOTS
+-- ============================================
+
+-- Point-in-time project aggregates for retrospectives
+CREATE TABLE IF NOT EXISTS project_snapshots (
+    id INTEGER PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    label TEXT,                            -- Optional user label ('sprint-12')
+    taken_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    data TEXT NOT NULL,                    -- JSON: analysis::ProjectSnapshot
+    FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- ENRICHMENT
+-- ============================================
+
+-- LLM-generated session metadata, queued per session and kind and worked by `enrich`
+CREATE TABLE IF NOT EXISTS enrichment_jobs (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    kind TEXT NOT NULL,                    -- 'summary', 'topics'
+    status TEXT NOT NULL DEFAULT 'pending', -- 'pending', 'running', 'done', 'failed'
+    attempts INTEGER NOT NULL DEFAULT 0,
+    result TEXT,
+    error TEXT,
+    cost REAL,                             -- Estimated USD
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME,
+    UNIQUE(session_id, kind),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- ============================================
+-- SEARCH
+-- ============================================
+
+-- Full-text index over what a session is about, maintained by SEARCH_TRIGGERS
+CREATE VIRTUAL TABLE IF NOT EXISTS session_search USING fts5(
+    session_id UNINDEXED,
+    title,
+    summary,                               -- Finished enrichment results
+    project,                               -- Project name
+    path,                                  -- Working directory
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
 -- ============================================
 -- INDEXES
 -- ============================================
@@ -189,6 +417,677 @@ CREATE INDEX IF NOT EXISTS idx_project_paths_path ON project_paths(path);
 CREATE INDEX IF NOT EXISTS idx_project_ids_value ON project_identifiers(identifier_value);
 CREATE INDEX IF NOT EXISTS idx_project_ids_type ON project_identifiers(identifier_type);
 
+-- Reference indexes
+CREATE INDEX IF NOT EXISTS idx_session_refs_key ON session_references(ref_key);
+
+-- Commit indexes
+CREATE INDEX IF NOT EXISTS idx_session_commits_hash ON session_commits(commit_hash);
+
+-- Parse error indexes
+CREATE INDEX IF NOT EXISTS idx_parse_errors_session ON parse_errors(session_id);
+
+-- Anomaly indexes
+CREATE INDEX IF NOT EXISTS idx_anomalies_session ON anomalies(session_id);
+
+-- Snapshot indexes
+CREATE INDEX IF NOT EXISTS idx_project_snapshots_project ON project_snapshots(project_id);
+
+-- Enrichment indexes
+CREATE INDEX IF NOT EXISTS idx_enrichment_status ON enrichment_jobs(kind, status);
+
 -- Deduplication indexes
 CREATE INDEX IF NOT EXISTS idx_duplicates_unresolved ON session_duplicates(resolved) WHERE resolved = FALSE;
 "#;
+
+/// Triggers keeping the cached project counters in step with session inserts, upserts,
+/// reassignments and deletes. Applied after column migrations so older databases have
+/// the counter columns.
+pub const COUNTER_TRIGGERS: &str = r#"
+CREATE TRIGGER IF NOT EXISTS trg_project_counts_insert
+AFTER INSERT ON sessions WHEN NEW.project_id IS NOT NULL
+BEGIN
+    UPDATE projects SET
+        session_count = session_count + 1,
+        message_count = message_count + COALESCE(NEW.message_count, 0),
+        last_session_at = CASE WHEN last_session_at IS NULL OR NEW.last_timestamp > last_session_at
+                               THEN NEW.last_timestamp ELSE last_session_at END
+    WHERE id = NEW.project_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_project_counts_update
+AFTER UPDATE OF project_id, message_count, last_timestamp ON sessions
+BEGIN
+    UPDATE projects SET
+        session_count = session_count - 1,
+        message_count = message_count - COALESCE(OLD.message_count, 0)
+    WHERE id = OLD.project_id;
+    UPDATE projects SET
+        session_count = session_count + 1,
+        message_count = message_count + COALESCE(NEW.message_count, 0),
+        last_session_at = CASE WHEN last_session_at IS NULL OR NEW.last_timestamp > last_session_at
+                               THEN NEW.last_timestamp ELSE last_session_at END
+    WHERE id = NEW.project_id;
+    -- A session leaving a project may have been its latest one
+    UPDATE projects SET
+        last_session_at = (SELECT MAX(last_timestamp) FROM sessions WHERE project_id = OLD.project_id)
+    WHERE id = OLD.project_id AND OLD.project_id IS NOT NEW.project_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_project_counts_delete
+AFTER DELETE ON sessions WHEN OLD.project_id IS NOT NULL
+BEGIN
+    UPDATE projects SET
+        session_count = session_count - 1,
+        message_count = message_count - COALESCE(OLD.message_count, 0),
+        last_session_at = (SELECT MAX(last_timestamp) FROM sessions WHERE project_id = OLD.project_id)
+    WHERE id = OLD.project_id;
+END;
+"#;
+
+/// Triggers keeping `session_search` in step with session titles, paths and project
+/// links, project renames and finished enrichment jobs
+pub const SEARCH_TRIGGERS: &str = r#"
+CREATE TRIGGER IF NOT EXISTS trg_search_insert
+AFTER INSERT ON sessions
+BEGIN
+    INSERT INTO session_search (session_id, title, summary, project, path)
+    SELECT s.id, s.title,
+           (SELECT group_concat(j.result, ' ') FROM enrichment_jobs j
+            WHERE j.session_id = s.id AND j.status = 'done'),
+           p.name, s.raw_project_path
+    FROM sessions s LEFT JOIN projects p ON p.id = s.project_id
+    WHERE s.id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_update
+AFTER UPDATE OF title, raw_project_path, project_id ON sessions
+WHEN OLD.title IS NOT NEW.title OR OLD.raw_project_path IS NOT NEW.raw_project_path
+  OR OLD.project_id IS NOT NEW.project_id
+BEGIN
+    DELETE FROM session_search WHERE session_id = NEW.id;
+    INSERT INTO session_search (session_id, title, summary, project, path)
+    SELECT s.id, s.title,
+           (SELECT group_concat(j.result, ' ') FROM enrichment_jobs j
+            WHERE j.session_id = s.id AND j.status = 'done'),
+           p.name, s.raw_project_path
+    FROM sessions s LEFT JOIN projects p ON p.id = s.project_id
+    WHERE s.id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_delete
+AFTER DELETE ON sessions
+BEGIN
+    DELETE FROM session_search WHERE session_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_enrichment
+AFTER UPDATE OF status, result ON enrichment_jobs
+WHEN NEW.status = 'done' OR OLD.status = 'done'
+BEGIN
+    UPDATE session_search SET summary =
+        (SELECT group_concat(j.result, ' ') FROM enrichment_jobs j
+         WHERE j.session_id = NEW.session_id AND j.status = 'done')
+    WHERE session_id = NEW.session_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_enrichment_insert
+AFTER INSERT ON enrichment_jobs WHEN NEW.status = 'done'
+BEGIN
+    UPDATE session_search SET summary =
+        (SELECT group_concat(j.result, ' ') FROM enrichment_jobs j
+         WHERE j.session_id = NEW.session_id AND j.status = 'done')
+    WHERE session_id = NEW.session_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_project
+AFTER UPDATE OF name ON projects
+BEGIN
+    UPDATE session_search SET project = NEW.name
+    WHERE session_id IN (SELECT id FROM sessions WHERE project_id = NEW.id);
+END;
+"#;
+
+/// Rebuild `session_search` from the sessions table
+pub const REINDEX_SEARCH: &str = r#"
+DELETE FROM session_search;
+INSERT INTO session_search (session_id, title, summary, project, path)
+SELECT s.id, s.title,
+       (SELECT group_concat(j.result, ' ') FROM enrichment_jobs j
+        WHERE j.session_id = s.id AND j.status = 'done'),
+       p.name, s.raw_project_path
+FROM sessions s LEFT JOIN projects p ON p.id = s.project_id;
+"#;
+
+/// Recompute `usage_rollups` for days from ?1 (`YYYY-MM-DD`, local) onward; NULL
+/// rebuilds every day. Run after deleting the same days.
+const ROLLUP_DAYS: &str = r#"
+INSERT INTO usage_rollups
+    (day, probe_source_id, model, messages, input_tokens, output_tokens,
+     cache_read_tokens, cache_creation_tokens)
+SELECT date(m.timestamp, 'localtime') AS day, s.probe_source_id, COALESCE(m.model, ''),
+       COUNT(*), SUM(COALESCE(t.input_tokens, 0)), SUM(COALESCE(t.output_tokens, 0)),
+       SUM(COALESCE(t.cache_read_tokens, 0)), SUM(COALESCE(t.cache_creation_tokens, 0))
+FROM messages m
+JOIN sessions s ON s.id = m.session_id
+LEFT JOIN token_usage t ON t.message_id = m.id
+WHERE m.timestamp IS NOT NULL
+  -- The indexed range check is a day wide to cover any UTC offset; the local day decides
+  AND (?1 IS NULL OR (m.timestamp >= date(?1, '-1 day')
+                      AND date(m.timestamp, 'localtime') >= ?1))
+GROUP BY 1, 2, 3
+"#;
+
+/// Recompute the activity metrics of session ?1 (NULL: every session) from its messages.
+/// A gap of more than 5 minutes between consecutive messages counts as idle.
+pub const SESSION_METRICS: &str = r#"
+WITH gaps AS MATERIALIZED (
+    SELECT session_id,
+           (julianday(timestamp) - julianday(LAG(timestamp) OVER (
+               PARTITION BY session_id ORDER BY timestamp, id))) * 86400 AS gap
+    FROM messages
+    WHERE timestamp IS NOT NULL AND (?1 IS NULL OR session_id = ?1)
+)
+UPDATE sessions SET
+    duration_secs = CAST(ROUND((julianday(last_timestamp) - julianday(first_timestamp)) * 86400)
+                         AS INTEGER),
+    active_secs = CASE WHEN first_timestamp IS NOT NULL THEN
+        (SELECT CAST(ROUND(COALESCE(SUM(gap), 0)) AS INTEGER) FROM gaps
+         WHERE gaps.session_id = sessions.id AND gap <= 300) END,
+    user_messages = (SELECT COUNT(*) FROM messages m
+                     WHERE m.session_id = sessions.id AND m.role = 'user'),
+    assistant_messages = (SELECT COUNT(*) FROM messages m
+                          WHERE m.session_id = sessions.id AND m.role = 'assistant'),
+    tool_calls = (SELECT COUNT(*) FROM tool_uses t JOIN messages m ON m.id = t.message_id
+                  WHERE m.session_id = sessions.id)
+WHERE ?1 IS NULL OR id = ?1
+"#;
+
+/// Recompute the per-model breakdown of session ?1 (NULL: every session); run after
+/// deleting the same rows
+const SESSION_MODELS: &str = r#"
+INSERT INTO session_models
+    (session_id, model, messages, input_tokens, output_tokens, cache_read_tokens,
+     cache_creation_tokens)
+SELECT m.session_id, m.model, COUNT(*), SUM(COALESCE(t.input_tokens, 0)),
+       SUM(COALESCE(t.output_tokens, 0)), SUM(COALESCE(t.cache_read_tokens, 0)),
+       SUM(COALESCE(t.cache_creation_tokens, 0))
+FROM messages m
+LEFT JOIN token_usage t ON t.message_id = m.id
+WHERE m.model IS NOT NULL AND m.model != '' AND (?1 IS NULL OR m.session_id = ?1)
+GROUP BY m.session_id, m.model
+"#;
+
+/// Recompute the cached project counters from the sessions table
+pub const RECOUNT_PROJECTS: &str = r#"
+UPDATE projects SET
+    session_count = (SELECT COUNT(*) FROM sessions s WHERE s.project_id = projects.id),
+    message_count = (SELECT COALESCE(SUM(s.message_count), 0) FROM sessions s WHERE s.project_id = projects.id),
+    last_session_at = (SELECT MAX(s.last_timestamp) FROM sessions s WHERE s.project_id = projects.id)
+"#;
+
+const VERSION_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+/// One schema upgrade step
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// Upgrades in the order they are applied; versions must increase by one
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "v2 project-centric layout",
+        apply: upgrade_v1,
+    },
+    Migration {
+        version: 2,
+        description: "source groups, session splits, fingerprints, merges and message keys",
+        apply: add_session_tracking,
+    },
+    Migration {
+        version: 3,
+        description: "cached project counters",
+        apply: add_project_counters,
+    },
+    Migration {
+        version: 4,
+        description: "session language",
+        apply: add_session_language,
+    },
+    Migration {
+        version: 5,
+        description: "Zed message thread references",
+        apply: add_zed_thread_refs,
+    },
+    Migration {
+        version: 6,
+        description: "tool origin and errors",
+        apply: add_tool_origin,
+    },
+    Migration {
+        version: 7,
+        description: "tool permission decisions",
+        apply: add_tool_permissions,
+    },
+    Migration {
+        version: 8,
+        description: "session compactions",
+        apply: add_session_compactions,
+    },
+    Migration {
+        version: 9,
+        description: "message subtypes",
+        apply: add_message_subtype,
+    },
+    Migration {
+        version: 10,
+        description: "probe capabilities",
+        apply: add_probe_capabilities,
+    },
+    Migration {
+        version: 11,
+        description: "project visibility",
+        apply: add_project_visibility,
+    },
+    Migration {
+        version: 12,
+        description: "session search index",
+        apply: add_session_search,
+    },
+    Migration {
+        version: 13,
+        description: "daily usage rollups",
+        apply: add_usage_rollups,
+    },
+    Migration {
+        version: 14,
+        description: "session archiving",
+        apply: add_session_archived,
+    },
+    Migration {
+        version: 15,
+        description: "tool call file paths",
+        apply: add_tool_file_paths,
+    },
+    Migration {
+        version: 16,
+        description: "orphaned sessions",
+        apply: add_session_orphaned,
+    },
+    Migration {
+        version: 17,
+        description: "session activity metrics",
+        apply: add_session_metrics,
+    },
+    Migration {
+        version: 18,
+        description: "per-session model breakdown",
+        apply: add_session_models,
+    },
+    Migration {
+        version: 19,
+        description: "reasoning token counts",
+        apply: add_reasoning_tokens,
+    },
+];
+
+/// Version of the current `SCHEMA`
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Version recorded in the database; 0 for databases predating versioning
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    conn.execute_batch(VERSION_TABLE)?;
+    let version = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// Version recorded in a database opened read-only (which can't gain the version table)
+pub fn recorded_version(conn: &Connection) -> Result<u32> {
+    let versioned: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !versioned {
+        return Ok(0);
+    }
+    let version = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// Bring the database up to the current schema, creating it if empty; returns the
+/// migrations applied. Each migration commits on its own, so a failure leaves the
+/// database at the last good version.
+pub fn migrate(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        return Err(Error::SchemaMismatch {
+            path: conn.path().unwrap_or_default().into(),
+            found: current,
+            supported: latest_version(),
+        }
+        .into());
+    }
+
+    // Fresh database: create the current layout directly
+    if current == 0 && !table_exists(conn, "sessions")? {
+        conn.execute_batch(SCHEMA)?;
+        for migration in MIGRATIONS {
+            record(conn, migration)?;
+        }
+        return Ok(vec![]);
+    }
+
+    let mut applied = vec![];
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        conn.execute_batch("SAVEPOINT chronicle_migration")?;
+        let result = (migration.apply)(conn).and_then(|_| record(conn, migration));
+        match result {
+            Ok(()) => conn.execute_batch("RELEASE chronicle_migration")?,
+            Err(e) => {
+                conn.execute_batch("ROLLBACK TO chronicle_migration; RELEASE chronicle_migration")?;
+                return Err(e.context(format!(
+                    "Schema migration {} ({}) failed",
+                    migration.version, migration.description
+                )));
+            }
+        }
+        applied.push(migration);
+    }
+    Ok(applied)
+}
+
+fn record(conn: &Connection, migration: &Migration) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO schema_version (version, description) VALUES (?, ?)",
+        params![migration.version, migration.description],
+    )?;
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let exists = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+        params![table],
+        |row| row.get(0),
+    )?;
+    Ok(exists)
+}
+
+/// Add a column to an existing table if an older database lacks it; returns whether it was added
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<bool> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+    }
+    Ok(!exists)
+}
+
+/// v1 tables gain the v2 linking and provider columns, then the v2 tables are created.
+/// The v1 `artifacts` table is left in place.
+fn upgrade_v1(conn: &Connection) -> Result<()> {
+    if table_exists(conn, "sessions")? {
+        ensure_column(conn, "sessions", "project_id", "TEXT")?;
+        ensure_column(
+            conn,
+            "sessions",
+            "project_assignment",
+            "TEXT DEFAULT 'auto'",
+        )?;
+        ensure_column(conn, "sessions", "raw_project_path", "TEXT")?;
+        ensure_column(conn, "sessions", "raw_git_remote", "TEXT")?;
+        if ensure_column(conn, "sessions", "short_hash", "TEXT")? {
+            conn.execute_batch(
+                "UPDATE sessions SET short_hash = substr(COALESCE(external_id, id), 1, 8)",
+            )?;
+        }
+    }
+    if table_exists(conn, "messages")? {
+        ensure_column(conn, "messages", "provider_id", "TEXT")?;
+        ensure_column(conn, "messages", "content_ref", "TEXT")?;
+    }
+    if table_exists(conn, "probe_sources")? {
+        ensure_column(
+            conn,
+            "probe_sources",
+            "source_type",
+            "TEXT DEFAULT 'single'",
+        )?;
+        ensure_column(conn, "probe_sources", "status", "TEXT DEFAULT 'active'")?;
+    }
+    conn.execute_batch(SCHEMA)?;
+    Ok(())
+}
+
+fn add_session_tracking(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "source_group", "TEXT")?;
+    ensure_column(conn, "tool_uses", "input_hash", "TEXT")?;
+    ensure_column(conn, "sessions", "parent_session_id", "TEXT")?;
+    ensure_column(conn, "sessions", "split_index", "INTEGER")?;
+    ensure_column(conn, "messages", "message_key", "TEXT")?;
+    ensure_column(conn, "sessions", "source_mtime", "INTEGER")?;
+    ensure_column(conn, "sessions", "source_size", "INTEGER")?;
+    ensure_column(conn, "sessions", "merged_into", "TEXT")?;
+    Ok(())
+}
+
+fn add_project_counters(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "projects", "session_count", "INTEGER DEFAULT 0")?;
+    ensure_column(conn, "projects", "message_count", "INTEGER DEFAULT 0")?;
+    ensure_column(conn, "projects", "last_session_at", "DATETIME")?;
+    conn.execute_batch(RECOUNT_PROJECTS)?;
+    Ok(())
+}
+
+/// Languages are detected while parsing, so clear the source fingerprints to have
+/// the next extraction re-read every session
+fn add_session_language(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "language", "TEXT")?;
+    conn.execute_batch("UPDATE sessions SET source_mtime = NULL, source_size = NULL")?;
+    Ok(())
+}
+
+/// Zed messages were indexed without the thread they belong to; it is the session's
+/// external ID
+fn add_zed_thread_refs(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "UPDATE messages SET content_ref = (
+             SELECT s.external_id FROM sessions s WHERE s.id = messages.session_id)
+         WHERE content_ref IS NULL
+           AND session_id IN (SELECT id FROM sessions WHERE probe_source_id LIKE 'zed:%')",
+    )?;
+    Ok(())
+}
+
+/// Origins are derived from tool names and backfilled here; error results are only
+/// known to the probes, so sources are re-read on the next extraction
+fn add_tool_origin(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "tool_uses", "origin", "TEXT DEFAULT 'builtin'")?;
+    ensure_column(conn, "tool_uses", "mcp_server", "TEXT")?;
+    ensure_column(conn, "tool_uses", "is_error", "BOOLEAN DEFAULT FALSE")?;
+    conn.execute_batch(
+        r#"UPDATE tool_uses SET origin = 'mcp',
+               mcp_server = SUBSTR(tool_name, 6, INSTR(SUBSTR(tool_name, 6), '__') - 1)
+           WHERE tool_name LIKE 'mcp\_\_%' ESCAPE '\'
+             AND INSTR(SUBSTR(tool_name, 6), '__') > 1;
+           UPDATE sessions SET source_mtime = NULL, source_size = NULL;"#,
+    )?;
+    Ok(())
+}
+
+/// Permission decisions are only known to the probes; sources are re-read on the
+/// next extraction
+fn add_tool_permissions(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "tool_uses", "permission", "TEXT")?;
+    conn.execute_batch("UPDATE sessions SET source_mtime = NULL, source_size = NULL;")?;
+    Ok(())
+}
+
+/// Compactions are counted by the probes; sources are re-read on the next extraction
+fn add_session_compactions(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "compactions", "INTEGER DEFAULT 0")?;
+    conn.execute_batch("UPDATE sessions SET source_mtime = NULL, source_size = NULL;")?;
+    Ok(())
+}
+
+/// Compaction events become their own message rows on the next extraction
+fn add_message_subtype(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "messages", "subtype", "TEXT")?;
+    conn.execute_batch("UPDATE sessions SET source_mtime = NULL, source_size = NULL;")?;
+    Ok(())
+}
+
+/// Capabilities are recorded by the next extraction; until then probes count as full
+fn add_probe_capabilities(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "probe_sources", "capabilities", "TEXT")?;
+    Ok(())
+}
+
+/// Existing projects stay shareable, so nothing changes until a project is restricted
+fn add_project_visibility(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "projects", "visibility", "TEXT DEFAULT 'shareable'")?;
+    Ok(())
+}
+
+/// Creates the search table (via the idempotent `SCHEMA`) and indexes existing sessions
+fn add_session_search(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    conn.execute_batch(REINDEX_SEARCH)?;
+    Ok(())
+}
+
+fn add_usage_rollups(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    refresh_rollups(conn, None)
+}
+
+fn add_session_archived(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "archived", "BOOLEAN DEFAULT FALSE")?;
+    Ok(())
+}
+
+fn add_tool_file_paths(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "tool_uses", "file_path", "TEXT")?;
+    Ok(())
+}
+
+fn add_session_orphaned(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "orphaned_at", "DATETIME")?;
+    Ok(())
+}
+
+fn add_session_metrics(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "sessions", "duration_secs", "INTEGER")?;
+    ensure_column(conn, "sessions", "active_secs", "INTEGER")?;
+    ensure_column(conn, "sessions", "user_messages", "INTEGER DEFAULT 0")?;
+    ensure_column(conn, "sessions", "assistant_messages", "INTEGER DEFAULT 0")?;
+    ensure_column(conn, "sessions", "tool_calls", "INTEGER DEFAULT 0")?;
+    // Indexed messages hold everything the metrics need, so no re-extraction
+    conn.execute(SESSION_METRICS, params![None::<String>])?;
+    Ok(())
+}
+
+fn add_session_models(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    refresh_session_models(conn, None)
+}
+
+// Sources only started recording it now; older rows stay NULL until re-extracted
+fn add_reasoning_tokens(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "token_usage", "reasoning_tokens", "INTEGER")?;
+    Ok(())
+}
+
+/// Rebuild the per-model breakdown of one session (None: every session)
+pub fn refresh_session_models(conn: &Connection, session_id: Option<&str>) -> Result<()> {
+    conn.execute(
+        "DELETE FROM session_models WHERE ?1 IS NULL OR session_id = ?1",
+        params![session_id],
+    )?;
+    conn.execute(SESSION_MODELS, params![session_id])?;
+    Ok(())
+}
+
+/// Rebuild the usage rollups from a local `YYYY-MM-DD` day onward (None: every day)
+pub fn refresh_rollups(conn: &Connection, since_day: Option<&str>) -> Result<()> {
+    conn.execute(
+        "DELETE FROM usage_rollups WHERE ?1 IS NULL OR day >= ?1",
+        params![since_day],
+    )?;
+    conn.execute(ROLLUP_DAYS, params![since_day])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrates_v1_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, probe_source_id TEXT NOT NULL,
+                 external_id TEXT, title TEXT, message_count INTEGER, first_timestamp DATETIME,
+                 last_timestamp DATETIME, source_path TEXT NOT NULL, indexed_at DATETIME);
+             CREATE TABLE messages (id INTEGER PRIMARY KEY, session_id TEXT NOT NULL, uuid TEXT,
+                 role TEXT NOT NULL, model TEXT, timestamp DATETIME, source_path TEXT NOT NULL,
+                 byte_offset INTEGER, line_number INTEGER, has_tool_use BOOLEAN,
+                 has_thinking BOOLEAN);
+             CREATE TABLE artifacts (id INTEGER PRIMARY KEY, path TEXT);
+             INSERT INTO sessions (id, probe_source_id, external_id, source_path)
+                 VALUES ('s1', 'claude:ClaudeCode', 'abcdef123456', '/tmp/s1.jsonl');",
+        )
+        .unwrap();
+
+        let applied: Vec<u32> = migrate(&conn).unwrap().iter().map(|m| m.version).collect();
+        assert_eq!(
+            applied,
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19]
+        );
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        let (hash, assignment): (String, String) = conn
+            .query_row(
+                "SELECT short_hash, project_assignment FROM sessions WHERE id = 's1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((hash.as_str(), assignment.as_str()), ("abcdef12", "auto"));
+        assert!(table_exists(&conn, "projects").unwrap());
+        assert!(migrate(&conn).unwrap().is_empty());
+
+        conn.execute(
+            "INSERT INTO schema_version (version, description) VALUES (99, 'x')",
+            [],
+        )
+        .unwrap();
+        assert!(migrate(&conn).is_err());
+    }
+}
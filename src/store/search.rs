@@ -0,0 +1,149 @@
+//! Session search ranking
+//!
+//! Titles, enrichment summaries, project names and working directories are indexed
+//! in the `session_search` FTS5 table (kept current by `SEARCH_TRIGGERS`). Matches
+//! are ranked by BM25, boosted for recent activity and for the project being worked
+//! in, or simply by last activity with [`SearchRank::Recent`].
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+
+/// BM25 column weights: session_id (unindexed), title, summary, project, path
+pub(super) const BM25_WEIGHTS: &str = "0.0, 10.0, 4.0, 3.0, 1.0";
+
+/// Days after which the recency boost has halved
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Score multiplier for a session active right now (fades toward 1 with age). BM25
+/// saturates, so a title hit scores only about twice a path hit: larger boosts would
+/// let any recent mention outrank the session actually about the words.
+const RECENCY_BOOST: f64 = 1.5;
+
+/// Score multiplier for sessions of the project being worked in
+const PROJECT_BOOST: f64 = 1.5;
+
+/// Result order for `search`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchRank {
+    /// Best match first: BM25 with recency and project boosts
+    #[default]
+    Relevance,
+    /// Most recently active first
+    Recent,
+}
+
+impl SearchRank {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "relevance" => Ok(Self::Relevance),
+            "recent" => Ok(Self::Recent),
+            other => bail!("Unknown rank: {} (expected relevance or recent)", other),
+        }
+    }
+}
+
+/// FTS5 query matching every word of `text` as a prefix; None when it has no words
+pub(super) fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Ranking score (higher is better) from a BM25 value (lower is better, as SQLite
+/// reports it), the session's last activity and whether it is in the boosted project
+pub fn relevance(
+    bm25: f64,
+    last_active: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    project_match: bool,
+) -> f64 {
+    let age_days = last_active.map_or(f64::INFINITY, |t| {
+        (now - t).num_seconds().max(0) as f64 / 86_400.0
+    });
+    let recency = 1.0 + (RECENCY_BOOST - 1.0) * 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+    let project = if project_match { PROJECT_BOOST } else { 1.0 };
+    -bm25 * recency * project
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{SessionMetadata, SessionRef, SkipCounts, SourceType};
+    use crate::store::MetadataStore;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ranked_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(&dir.path().join("test.db")).unwrap();
+        store
+            .ensure_probe_source("t:Test", None, "Test", SourceType::Multi, None, "active")
+            .unwrap();
+        store
+            .create_project("p1", "auth", "code", Some("/src/login-service"), None)
+            .unwrap();
+        let add = |id: &str, title: &str, path: &str, days_ago: i64| {
+            let session = SessionRef {
+                id: id.to_string(),
+                source_path: PathBuf::from(format!("/tmp/{}.jsonl", id)),
+            };
+            let metadata = SessionMetadata {
+                external_id: id.to_string(),
+                title: Some(title.to_string()),
+                project_path: Some(path.to_string()),
+                git_remote: None,
+                source_group: None,
+                primary_provider: None,
+                primary_model: None,
+                first_timestamp: None,
+                last_timestamp: Some(Utc::now() - chrono::Duration::days(days_ago)),
+                messages: vec![],
+                references: vec![],
+                language: None,
+                commits: vec![],
+                compactions: 0,
+                skipped: SkipCounts::default(),
+            };
+            store.upsert_session("t:Test", &session, &metadata).unwrap()
+        };
+        let titled = add("aaaa1111", "Fix the login redirect", "/src/other", 90);
+        let pathed = add("bbbb2222", "Refactor", "/src/login-service", 0);
+        let recent = add("cccc3333", "Login page styles", "/src/web", 1);
+        let ids = |rank, project| -> Vec<String> {
+            store
+                .search_sessions("logi", rank, project)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+
+        // A title match outranks a path match despite being older
+        let relevant = ids(SearchRank::Relevance, None);
+        assert_eq!(relevant.len(), 3);
+        assert_eq!(relevant.last(), Some(&pathed));
+        assert_eq!(
+            ids(SearchRank::Recent, None),
+            [pathed.clone(), recent.clone(), titled]
+        );
+        assert_eq!(ids(SearchRank::Relevance, Some("p1"))[0], recent);
+
+        // The index follows project renames
+        store.rename_project("p1", "storefront").unwrap();
+        assert_eq!(
+            store
+                .search_sessions("storefront", SearchRank::Relevance, None)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(store
+            .search_sessions("\"*", SearchRank::Relevance, None)
+            .unwrap()
+            .is_empty());
+    }
+}